@@ -1,5 +1,12 @@
-use netok_core::{get_default_settings, run_diagnostics, Settings};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::Duration;
 
+use netok_core::{get_default_settings, run_diagnostics};
+
+#[cfg(feature = "server")]
+pub mod server;
 mod types;
 pub use types::{
     ComputerInfo, ConnectionType, InternetInfo, NetworkInfo, NodeId, NodeResult, Overall,
@@ -12,18 +19,172 @@ pub enum BridgeError {
     InvalidJson(String),
 }
 
+// ==================== Errors ====================
+
+// Re-export core error types
+pub use netok_core::{
+    CaptureError, DnsError, ErrorKind, GeoIpError, PresetError, SettingsError, StorageError,
+    VpnError,
+};
+
+/// Error shape for the UI: machine-readable kind, i18n key and English message.
+#[derive(thiserror::Error, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[error("{message}")]
+pub struct ErrorInfo {
+    pub kind: ErrorKind,
+    /// Translation key, see `error_i18n_key`.
+    pub key: String,
+    pub message: String,
+    /// Byte offset into the input for parse errors.
+    pub position: Option<usize>,
+}
+
+/// i18n key for an error kind (`errors.*` in the UI locale files).
+pub fn error_i18n_key(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::UnsupportedPlatform => "errors.unsupported_platform",
+        ErrorKind::PermissionDenied => "errors.permission_denied",
+        ErrorKind::Timeout => "errors.timeout",
+        ErrorKind::Parse => "errors.parse",
+        ErrorKind::InvalidInput => "errors.invalid_input",
+        ErrorKind::NotFound => "errors.not_found",
+        ErrorKind::Network => "errors.network",
+        ErrorKind::CommandFailed => "errors.command_failed",
+        ErrorKind::Io => "errors.io",
+        ErrorKind::Internal => "errors.internal",
+    }
+}
+
+impl ErrorInfo {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            key: error_i18n_key(kind).to_string(),
+            message: message.into(),
+            position: None,
+        }
+    }
+
+    /// Failure with no more specific kind (poisoned lock, runtime error).
+    pub fn internal(e: impl std::fmt::Display) -> Self {
+        Self::new(ErrorKind::Internal, e.to_string())
+    }
+
+    /// A blocking task panicked or was cancelled.
+    fn task(what: &str, e: tokio::task::JoinError) -> Self {
+        Self::new(
+            ErrorKind::Internal,
+            format!("Failed to run {} task: {}", what, e),
+        )
+    }
+}
+
+impl From<DnsError> for ErrorInfo {
+    fn from(e: DnsError) -> Self {
+        Self::new(e.kind(), e.to_string())
+    }
+}
+
+impl From<VpnError> for ErrorInfo {
+    fn from(e: VpnError) -> Self {
+        Self {
+            position: e.position(),
+            ..Self::new(e.kind(), e.to_string())
+        }
+    }
+}
+
+impl From<GeoIpError> for ErrorInfo {
+    fn from(e: GeoIpError) -> Self {
+        Self::new(e.kind(), e.to_string())
+    }
+}
+
+impl From<StorageError> for ErrorInfo {
+    fn from(e: StorageError) -> Self {
+        Self::new(e.kind(), e.to_string())
+    }
+}
+
+impl From<CaptureError> for ErrorInfo {
+    fn from(e: CaptureError) -> Self {
+        Self::new(e.kind(), e.to_string())
+    }
+}
+
+impl From<PresetError> for ErrorInfo {
+    fn from(e: PresetError) -> Self {
+        Self::new(e.kind(), e.to_string())
+    }
+}
+
+impl From<SettingsError> for ErrorInfo {
+    fn from(e: SettingsError) -> Self {
+        Self::new(e.kind(), e.to_string())
+    }
+}
+
+// ==================== Settings ====================
+
+pub use netok_core::{Settings, SettingsStore};
+
+/// Settings every bridge call runs with, replaced by [`apply_settings`].
+static SETTINGS: LazyLock<RwLock<Settings>> = LazyLock::new(|| RwLock::new(get_default_settings()));
+
+/// The settings in effect.
+pub fn current_settings() -> Settings {
+    SETTINGS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Make `settings` the ones every later call runs with.
+pub fn apply_settings(settings: Settings) {
+    *SETTINGS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = settings;
+}
+
+fn parse_settings(json: &str) -> Result<Settings, BridgeError> {
+    serde_json::from_str::<Settings>(json).map_err(|e| BridgeError::InvalidJson(e.to_string()))
+}
+
 pub fn get_settings_json() -> String {
-    serde_json::to_string(&get_default_settings()).unwrap()
+    serde_json::to_string(&current_settings()).unwrap()
 }
 
+/// Parse settings JSON and apply it. Not persisted; see [`save_settings`].
 pub fn set_settings_json(json: &str) -> Result<Settings, BridgeError> {
-    serde_json::from_str::<Settings>(json).map_err(|e| BridgeError::InvalidJson(e.to_string()))
+    let settings = parse_settings(json)?;
+    apply_settings(settings.clone());
+    Ok(settings)
+}
+
+/// Load the settings saved in `store` and apply them.
+pub async fn load_settings(store: Arc<SettingsStore>) -> Result<Settings, ErrorInfo> {
+    let settings = tokio::task::spawn_blocking(move || store.load())
+        .await
+        .map_err(|e| ErrorInfo::task("settings", e))??;
+    apply_settings(settings.clone());
+    Ok(settings)
+}
+
+/// Save `settings` to `store` and apply them.
+pub async fn save_settings(store: Arc<SettingsStore>, settings: Settings) -> Result<(), ErrorInfo> {
+    let saved = settings.clone();
+    tokio::task::spawn_blocking(move || store.save(&saved))
+        .await
+        .map_err(|e| ErrorInfo::task("settings", e))??;
+    apply_settings(settings);
+    Ok(())
 }
 
+/// Run diagnostics with `settings_json`, or the settings in effect.
 pub fn run_diagnostics_json(settings_json: Option<&str>) -> Result<String, BridgeError> {
     let settings = match settings_json {
-        Some(s) => set_settings_json(s)?,
-        None => get_default_settings(),
+        Some(s) => parse_settings(s)?,
+        None => current_settings(),
     };
     let snapshot = run_diagnostics(&settings);
     Ok(serde_json::to_string(&snapshot).unwrap())
@@ -57,16 +218,43 @@ fn convert_node(node: &netok_core::NodeInfo) -> NodeResult {
         status,
         latency_ms: node.latency_ms.map(|ms| ms as u64),
         details: None,
+        hint_key: node.hint_key.clone(),
     }
 }
 
 pub async fn run_diagnostics_struct() -> Result<Snapshot, anyhow::Error> {
-    let core_snapshot = tokio::task::spawn_blocking(|| {
-        let settings = get_default_settings();
-        run_diagnostics(&settings)
-    })
-    .await?;
+    run_diagnostics_struct_with_backend(system_backend()).await
+}
+
+/// Run full diagnostics against `backend` (e.g. a simulation in demo mode).
+pub async fn run_diagnostics_struct_with_backend(
+    backend: Arc<dyn NetworkBackend>,
+) -> Result<Snapshot, anyhow::Error> {
+    let settings = current_settings();
+    let core_snapshot = netok_core::run_diagnostics_async(backend.as_ref(), &settings).await;
 
+    Ok(snapshot_from_core(core_snapshot))
+}
+
+/// Run full diagnostics against `backend`, stopping when `cancel` fires or
+/// `deadline` passes. Nodes not reached are reported as unknown.
+pub async fn run_diagnostics_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<Snapshot> {
+    let settings = current_settings();
+    netok_core::run_diagnostics_cancellable(
+        backend.as_ref(),
+        &settings,
+        &Cancellation::new(cancel, deadline),
+    )
+    .await
+    .map(snapshot_from_core)
+}
+
+/// Convert a core snapshot to the UI shape.
+fn snapshot_from_core(core_snapshot: netok_core::DiagnosticsSnapshot) -> Snapshot {
     let nodes: Vec<NodeResult> = core_snapshot.nodes.iter().map(convert_node).collect();
 
     let overall = if core_snapshot
@@ -85,7 +273,7 @@ pub async fn run_diagnostics_struct() -> Result<Snapshot, anyhow::Error> {
         Overall::Partial
     };
 
-    Ok(Snapshot {
+    Snapshot {
         at_utc: core_snapshot.at_utc,
         overall,
         summary_key: core_snapshot.summary_key,
@@ -95,13 +283,121 @@ pub async fn run_diagnostics_struct() -> Result<Snapshot, anyhow::Error> {
         network: core_snapshot.network,
         router: core_snapshot.router,
         internet: core_snapshot.internet,
-    })
+    }
+}
+
+// ==================== Network Backend ====================
+
+// Re-export backend types
+pub use netok_core::{DiagnosticScenario, NetworkBackend, SimulatedBackend, SystemBackend};
+
+/// The real machine.
+pub fn system_backend() -> Arc<dyn NetworkBackend> {
+    Arc::new(SystemBackend)
+}
+
+/// A simulated network in the state described by `scenario`.
+pub fn simulated_backend(scenario: DiagnosticScenario) -> Arc<dyn NetworkBackend> {
+    Arc::new(SimulatedBackend::scenario(scenario))
+}
+
+// ==================== Cancellation ====================
+
+// Re-export cancellation types
+pub use netok_core::{CancelToken, Cancellation, Completion, Partial};
+
+/// In-flight operations that can be cancelled by id, e.g. when the UI closes
+/// the screen that started them.
+#[derive(Clone, Default)]
+pub struct OperationRegistry {
+    operations: Arc<Mutex<HashMap<String, (u64, CancelToken)>>>,
+    next_seq: Arc<AtomicU64>,
+}
+
+impl OperationRegistry {
+    /// Track a new operation under `id` until the returned guard is dropped.
+    ///
+    /// An operation still running under the same id is cancelled: the caller
+    /// has started over. Without an id the operation can't be cancelled here.
+    pub fn register(&self, id: Option<String>) -> OperationGuard {
+        let token = CancelToken::new();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        if let (Some(id), Ok(mut operations)) = (&id, self.operations.lock()) {
+            if let Some((_, previous)) = operations.insert(id.clone(), (seq, token.clone())) {
+                previous.cancel();
+            }
+        }
+        OperationGuard {
+            registry: self.clone(),
+            id,
+            seq,
+            token,
+        }
+    }
+
+    /// Cancel the operation running under `id`. `false` if there is none.
+    pub fn cancel(&self, id: &str) -> bool {
+        let Ok(operations) = self.operations.lock() else {
+            return false;
+        };
+        match operations.get(id) {
+            Some((_, token)) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ids of the operations currently running, sorted.
+    pub fn in_flight(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self
+            .operations
+            .lock()
+            .map(|operations| operations.keys().cloned().collect())
+            .unwrap_or_default();
+        ids.sort();
+        ids
+    }
+}
+
+/// Registration of one operation; unregisters it when dropped.
+pub struct OperationGuard {
+    registry: OperationRegistry,
+    id: Option<String>,
+    seq: u64,
+    token: CancelToken,
+}
+
+impl OperationGuard {
+    /// Token to hand to the operation.
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let (Some(id), Ok(mut operations)) = (&self.id, self.registry.operations.lock()) else {
+            return;
+        };
+        // A newer operation may have taken over the id
+        if operations.get(id).is_some_and(|(seq, _)| *seq == self.seq) {
+            operations.remove(id);
+        }
+    }
 }
 
 // ==================== Progressive Diagnostics Commands ====================
 
 pub async fn check_computer_node() -> Result<SingleNodeResult, anyhow::Error> {
-    let (node_info, computer) = tokio::task::spawn_blocking(netok_core::check_computer).await?;
+    check_computer_node_with_backend(system_backend()).await
+}
+
+pub async fn check_computer_node_with_backend(
+    backend: Arc<dyn NetworkBackend>,
+) -> Result<SingleNodeResult, anyhow::Error> {
+    let (node_info, computer) = netok_core::check_computer_async(backend.as_ref()).await;
     Ok(SingleNodeResult {
         node: convert_node(&node_info),
         computer: Some(computer),
@@ -113,9 +409,16 @@ pub async fn check_computer_node() -> Result<SingleNodeResult, anyhow::Error> {
 
 pub async fn check_network_node(
     adapter: Option<String>,
+) -> Result<SingleNodeResult, anyhow::Error> {
+    check_network_node_with_backend(system_backend(), adapter).await
+}
+
+pub async fn check_network_node_with_backend(
+    backend: Arc<dyn NetworkBackend>,
+    adapter: Option<String>,
 ) -> Result<SingleNodeResult, anyhow::Error> {
     let (node_info, network) =
-        tokio::task::spawn_blocking(move || netok_core::check_network(adapter.as_deref())).await?;
+        netok_core::check_network_async(backend.as_ref(), adapter.as_deref()).await;
     Ok(SingleNodeResult {
         node: convert_node(&node_info),
         computer: None,
@@ -126,7 +429,13 @@ pub async fn check_network_node(
 }
 
 pub async fn check_router_node() -> Result<SingleNodeResult, anyhow::Error> {
-    let (node_info, router) = tokio::task::spawn_blocking(netok_core::check_router).await?;
+    check_router_node_with_backend(system_backend()).await
+}
+
+pub async fn check_router_node_with_backend(
+    backend: Arc<dyn NetworkBackend>,
+) -> Result<SingleNodeResult, anyhow::Error> {
+    let (node_info, router) = netok_core::check_router_async(backend.as_ref()).await;
     Ok(SingleNodeResult {
         node: convert_node(&node_info),
         computer: None,
@@ -137,7 +446,14 @@ pub async fn check_router_node() -> Result<SingleNodeResult, anyhow::Error> {
 }
 
 pub async fn check_internet_node() -> Result<SingleNodeResult, anyhow::Error> {
-    let (node_info, internet) = tokio::task::spawn_blocking(netok_core::check_internet).await?;
+    check_internet_node_with_backend(system_backend()).await
+}
+
+pub async fn check_internet_node_with_backend(
+    backend: Arc<dyn NetworkBackend>,
+) -> Result<SingleNodeResult, anyhow::Error> {
+    let (node_info, internet) =
+        netok_core::check_internet_async(backend.as_ref(), &current_settings().probe_targets).await;
     Ok(SingleNodeResult {
         node: convert_node(&node_info),
         computer: None,
@@ -148,266 +464,445 @@ pub async fn check_internet_node() -> Result<SingleNodeResult, anyhow::Error> {
 }
 
 // Re-export IpInfoResponse for Tauri commands
-pub use netok_core::IpInfoResponse;
+pub use netok_core::{IpInfoResponse, ProbeTargets};
 
-// IP geolocation lookup (async wrapper)
-pub async fn lookup_ip_location(ip: String) -> Result<IpInfoResponse, String> {
-    tokio::task::spawn_blocking(move || netok_core::lookup_ip_location(&ip))
-        .await
-        .map_err(|e| format!("Failed to run IP location lookup task: {}", e))?
+// IP geolocation lookup
+pub async fn lookup_ip_location(ip: String) -> Result<IpInfoResponse, ErrorInfo> {
+    Ok(netok_core::lookup_ip_location_async(&ip, &current_settings().probe_targets).await?)
 }
 
-// DNS Provider types for Tauri
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-#[serde(tag = "type")]
+// Re-export DNS catalog types
+pub use netok_core::{CatalogProvider, DnsCatalog, DnsFilter, ProviderCategory};
+
+/// DNS provider as the UI names it.
+///
+/// Serialized as `{"type": "Auto"}`, `{"type": "Custom", ...}` or, for a
+/// catalog provider, `{"type": family, "variant": variant}` (no `variant` for
+/// single-entry families like Google). Families and variants come from the
+/// catalog, so unknown ones fail to deserialize.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "ProviderRepr", into = "ProviderRepr")]
 pub enum DnsProviderType {
     Auto,
-    Cloudflare {
-        variant: CloudflareVariant,
-    },
-    Google,
-    AdGuard {
-        variant: AdGuardVariant,
-    },
-    Dns4Eu {
-        variant: Dns4EuVariant,
+    /// Catalog provider by family and variant, e.g. `Quad9`/`Recommended`.
+    Catalog {
+        family: String,
+        variant: Option<String>,
     },
-    Quad9 {
-        variant: Quad9Variant,
-    },
-    OpenDns {
-        variant: OpenDnsVariant,
-    },
-    #[serde(rename_all = "camelCase")]
     Custom {
         primary: String,
         secondary: String,
         primary_ipv6: Option<String>,
         secondary_ipv6: Option<String>,
+        /// DoH URL template (RFC 8484)
+        doh_template: Option<String>,
+        /// DoT hostname (RFC 7858)
+        dot_hostname: Option<String>,
     },
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub enum CloudflareVariant {
-    Standard, // 1.1.1.1
-    Malware,  // 1.1.1.2
-    Family,   // 1.1.1.3
+impl DnsProviderType {
+    /// Catalog provider `family`/`variant`, `None` if the catalog has no such entry.
+    pub fn catalog(family: &str, variant: Option<&str>) -> Option<Self> {
+        let entry = DnsCatalog::built_in().find(family, variant)?;
+        Some(DnsProviderType::Catalog {
+            family: entry.family.clone(),
+            variant: entry.variant.clone(),
+        })
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub enum AdGuardVariant {
-    Standard,     // 94.140.14.14
-    NonFiltering, // 94.140.14.140
-    Family,       // 94.140.14.15
+/// Flat JSON shape of [`DnsProviderType`].
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProviderRepr {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variant: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secondary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary_ipv6: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secondary_ipv6: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    doh_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dot_hostname: Option<String>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub enum Dns4EuVariant {
-    Protective,        // 86.54.11.1
-    ProtectiveChild,   // 86.54.11.12
-    ProtectiveAd,      // 86.54.11.13
-    ProtectiveChildAd, // 86.54.11.11
-    Unfiltered,        // 86.54.11.100
+impl ProviderRepr {
+    fn kind(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            variant: None,
+            primary: None,
+            secondary: None,
+            primary_ipv6: None,
+            secondary_ipv6: None,
+            doh_template: None,
+            dot_hostname: None,
+        }
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub enum Quad9Variant {
-    Recommended, // 9.9.9.9
-    SecuredEcs,  // 9.9.9.11
-    Unsecured,   // 9.9.9.10
+impl TryFrom<ProviderRepr> for DnsProviderType {
+    type Error = String;
+
+    fn try_from(repr: ProviderRepr) -> Result<Self, Self::Error> {
+        match repr.kind.as_str() {
+            "Auto" => Ok(DnsProviderType::Auto),
+            "Custom" => Ok(DnsProviderType::Custom {
+                primary: repr.primary.ok_or("missing field `primary`")?,
+                secondary: repr.secondary.unwrap_or_default(),
+                primary_ipv6: repr.primary_ipv6,
+                secondary_ipv6: repr.secondary_ipv6,
+                doh_template: repr.doh_template,
+                dot_hostname: repr.dot_hostname,
+            }),
+            family => {
+                DnsProviderType::catalog(family, repr.variant.as_deref()).ok_or_else(|| match repr
+                    .variant
+                {
+                    Some(variant) => format!("unknown DNS provider {} {}", family, variant),
+                    None => format!("unknown DNS provider {}", family),
+                })
+            }
+        }
+    }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
-pub enum OpenDnsVariant {
-    FamilyShield, // 208.67.222.123
-    Home,         // 208.67.222.222
+impl From<DnsProviderType> for ProviderRepr {
+    fn from(provider: DnsProviderType) -> Self {
+        match provider {
+            DnsProviderType::Auto => ProviderRepr::kind("Auto"),
+            DnsProviderType::Catalog { family, variant } => ProviderRepr {
+                variant,
+                ..ProviderRepr::kind(&family)
+            },
+            DnsProviderType::Custom {
+                primary,
+                secondary,
+                primary_ipv6,
+                secondary_ipv6,
+                doh_template,
+                dot_hostname,
+            } => ProviderRepr {
+                primary: Some(primary),
+                secondary: Some(secondary),
+                primary_ipv6,
+                secondary_ipv6,
+                doh_template,
+                dot_hostname,
+                ..ProviderRepr::kind("Custom")
+            },
+        }
+    }
 }
 
 // Convert bridge type to core type
-fn dns_provider_to_core(provider: DnsProviderType) -> netok_core::DnsProvider {
-    match provider {
+fn dns_provider_to_core(provider: DnsProviderType) -> Result<netok_core::DnsProvider, ErrorInfo> {
+    Ok(match provider {
         DnsProviderType::Auto => netok_core::DnsProvider::Auto,
-        DnsProviderType::Cloudflare { variant } => match variant {
-            CloudflareVariant::Standard => netok_core::DnsProvider::Cloudflare,
-            CloudflareVariant::Malware => netok_core::DnsProvider::CloudflareMalware,
-            CloudflareVariant::Family => netok_core::DnsProvider::CloudflareFamily,
-        },
-        DnsProviderType::Google => netok_core::DnsProvider::Google,
-        DnsProviderType::AdGuard { variant } => match variant {
-            AdGuardVariant::Standard => netok_core::DnsProvider::AdGuard,
-            AdGuardVariant::NonFiltering => netok_core::DnsProvider::AdGuardNonFiltering,
-            AdGuardVariant::Family => netok_core::DnsProvider::AdGuardFamily,
-        },
-        DnsProviderType::Dns4Eu { variant } => match variant {
-            Dns4EuVariant::Protective => netok_core::DnsProvider::Dns4EuProtective,
-            Dns4EuVariant::ProtectiveChild => netok_core::DnsProvider::Dns4EuProtectiveChild,
-            Dns4EuVariant::ProtectiveAd => netok_core::DnsProvider::Dns4EuProtectiveAd,
-            Dns4EuVariant::ProtectiveChildAd => netok_core::DnsProvider::Dns4EuProtectiveChildAd,
-            Dns4EuVariant::Unfiltered => netok_core::DnsProvider::Dns4EuUnfiltered,
-        },
-        DnsProviderType::Quad9 { variant } => match variant {
-            Quad9Variant::Recommended => netok_core::DnsProvider::Quad9Recommended,
-            Quad9Variant::SecuredEcs => netok_core::DnsProvider::Quad9SecuredEcs,
-            Quad9Variant::Unsecured => netok_core::DnsProvider::Quad9Unsecured,
-        },
-        DnsProviderType::OpenDns { variant } => match variant {
-            OpenDnsVariant::FamilyShield => netok_core::DnsProvider::OpenDnsFamilyShield,
-            OpenDnsVariant::Home => netok_core::DnsProvider::OpenDnsHome,
-        },
+        DnsProviderType::Catalog { family, variant } => {
+            let entry = DnsCatalog::built_in()
+                .find(&family, variant.as_deref())
+                .ok_or_else(|| {
+                    ErrorInfo::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Unknown DNS provider: {} {}",
+                            family,
+                            variant.unwrap_or_default()
+                        ),
+                    )
+                })?;
+            netok_core::DnsProvider::Catalog(entry.id.clone())
+        }
         DnsProviderType::Custom {
             primary,
             secondary,
             primary_ipv6,
             secondary_ipv6,
-        } => netok_core::DnsProvider::Custom(primary, secondary, primary_ipv6, secondary_ipv6),
-    }
+            doh_template,
+            dot_hostname,
+        } => netok_core::DnsProvider::Custom(
+            primary,
+            secondary,
+            primary_ipv6,
+            secondary_ipv6,
+            doh_template,
+            dot_hostname,
+        ),
+    })
 }
 
-// Convert core DNS provider to bridge type
-fn dns_provider_from_core(provider: netok_core::DnsProvider) -> DnsProviderType {
-    match provider {
+// Convert core DNS provider to bridge type; `None` for an id this build's
+// catalog doesn't have
+fn dns_provider_from_core(provider: netok_core::DnsProvider) -> Option<DnsProviderType> {
+    Some(match provider {
         netok_core::DnsProvider::Auto => DnsProviderType::Auto,
-        // Cloudflare
-        netok_core::DnsProvider::Cloudflare => DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Standard,
-        },
-        netok_core::DnsProvider::CloudflareMalware => DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Malware,
-        },
-        netok_core::DnsProvider::CloudflareFamily => DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Family,
-        },
-        // Google
-        netok_core::DnsProvider::Google => DnsProviderType::Google,
-        // AdGuard
-        netok_core::DnsProvider::AdGuard => DnsProviderType::AdGuard {
-            variant: AdGuardVariant::Standard,
-        },
-        netok_core::DnsProvider::AdGuardNonFiltering => DnsProviderType::AdGuard {
-            variant: AdGuardVariant::NonFiltering,
-        },
-        netok_core::DnsProvider::AdGuardFamily => DnsProviderType::AdGuard {
-            variant: AdGuardVariant::Family,
-        },
-        // DNS4EU
-        netok_core::DnsProvider::Dns4EuProtective => DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::Protective,
-        },
-        netok_core::DnsProvider::Dns4EuProtectiveChild => DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::ProtectiveChild,
-        },
-        netok_core::DnsProvider::Dns4EuProtectiveAd => DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::ProtectiveAd,
-        },
-        netok_core::DnsProvider::Dns4EuProtectiveChildAd => DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::ProtectiveChildAd,
-        },
-        netok_core::DnsProvider::Dns4EuUnfiltered => DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::Unfiltered,
-        },
-        // Quad9
-        netok_core::DnsProvider::Quad9Recommended => DnsProviderType::Quad9 {
-            variant: Quad9Variant::Recommended,
-        },
-        netok_core::DnsProvider::Quad9SecuredEcs => DnsProviderType::Quad9 {
-            variant: Quad9Variant::SecuredEcs,
-        },
-        netok_core::DnsProvider::Quad9Unsecured => DnsProviderType::Quad9 {
-            variant: Quad9Variant::Unsecured,
-        },
-        // OpenDNS
-        netok_core::DnsProvider::OpenDnsFamilyShield => DnsProviderType::OpenDns {
-            variant: OpenDnsVariant::FamilyShield,
-        },
-        netok_core::DnsProvider::OpenDnsHome => DnsProviderType::OpenDns {
-            variant: OpenDnsVariant::Home,
-        },
-        // Custom
-        netok_core::DnsProvider::Custom(primary, secondary, primary_ipv6, secondary_ipv6) => {
-            DnsProviderType::Custom {
-                primary,
-                secondary,
-                primary_ipv6,
-                secondary_ipv6,
+        netok_core::DnsProvider::Catalog(_) => {
+            let entry = provider.entry()?;
+            DnsProviderType::Catalog {
+                family: entry.family.clone(),
+                variant: entry.variant.clone(),
             }
         }
-    }
+        netok_core::DnsProvider::Custom(
+            primary,
+            secondary,
+            primary_ipv6,
+            secondary_ipv6,
+            doh_template,
+            dot_hostname,
+        ) => DnsProviderType::Custom {
+            primary,
+            secondary,
+            primary_ipv6,
+            secondary_ipv6,
+            doh_template,
+            dot_hostname,
+        },
+    })
+}
+
+/// The built-in DNS providers.
+pub fn get_dns_catalog() -> &'static DnsCatalog {
+    DnsCatalog::built_in()
 }
 
 /// Build the netsh command lines needed to apply a DNS provider.
 ///
 /// Does NOT execute anything — the caller must run them (usually elevated).
-pub async fn build_dns_commands(provider: DnsProviderType) -> Result<Vec<String>, String> {
-    let core_provider = dns_provider_to_core(provider);
-    tokio::task::spawn_blocking(move || netok_core::build_dns_commands(core_provider))
-        .await
-        .map_err(|e| format!("Failed to build DNS commands: {}", e))?
+pub async fn build_dns_commands(provider: DnsProviderType) -> Result<Vec<String>, ErrorInfo> {
+    let core_provider = dns_provider_to_core(provider)?;
+    Ok(
+        tokio::task::spawn_blocking(move || netok_core::build_dns_commands(core_provider))
+            .await
+            .map_err(|e| ErrorInfo::task("DNS command builder", e))??,
+    )
 }
 
 // Set DNS provider (async wrapper — runs netsh directly, needs admin rights)
-pub async fn set_dns_provider(provider: DnsProviderType) -> Result<(), String> {
-    eprintln!("[DNS] Setting provider: {:?}", provider);
-    let core_provider = dns_provider_to_core(provider.clone());
+pub async fn set_dns_provider(provider: DnsProviderType) -> Result<(), ErrorInfo> {
+    tracing::info!(provider = ?provider, "setting DNS provider");
+    let core_provider = dns_provider_to_core(provider.clone())?;
 
     // Run blocking DNS configuration in a separate thread
     let result = tokio::task::spawn_blocking(move || netok_core::set_dns(core_provider))
         .await
-        .map_err(|e| format!("Failed to run DNS configuration task: {}", e))?;
+        .map_err(|e| ErrorInfo::task("DNS configuration", e))?;
 
     match &result {
-        Ok(()) => eprintln!("[DNS] Provider set successfully: {:?}", provider),
-        Err(e) => eprintln!("[DNS] Failed to set provider: {}", e),
+        Ok(()) => tracing::info!(provider = ?provider, "DNS provider set"),
+        Err(e) => {
+            tracing::warn!(provider = ?provider, kind = ?e.kind(), error = %e, "failed to set DNS provider")
+        }
     }
 
-    result
+    Ok(result?)
 }
 
 // Get current DNS provider (async wrapper)
-pub async fn get_dns_provider() -> Result<DnsProviderType, String> {
-    eprintln!("[DNS] Getting current provider...");
-
+pub async fn get_dns_provider() -> Result<DnsProviderType, ErrorInfo> {
     // Run blocking DNS detection in a separate thread
-    let result = tokio::task::spawn_blocking(|| {
+    let result = tokio::task::spawn_blocking(|| -> Result<_, ErrorInfo> {
         let dns_servers = netok_core::get_current_dns()?;
-        eprintln!("[DNS] Detected DNS servers: {:?}", dns_servers);
         let core_provider = netok_core::detect_dns_provider(&dns_servers);
-        eprintln!("[DNS] Detected core provider: {:?}", core_provider);
-        Ok(dns_provider_from_core(core_provider))
+        tracing::debug!(servers = ?dns_servers, provider = ?core_provider, "detected DNS provider");
+        // Detection only yields ids from this build's catalog
+        Ok(dns_provider_from_core(core_provider).unwrap_or(DnsProviderType::Auto))
     })
     .await
-    .map_err(|e| format!("Failed to run DNS detection task: {}", e))?;
+    .map_err(|e| ErrorInfo::task("DNS detection", e))?;
 
-    match &result {
-        Ok(provider) => eprintln!("[DNS] Returning provider: {:?}", provider),
-        Err(e) => eprintln!("[DNS] Failed to get provider: {}", e),
+    if let Err(e) = &result {
+        tracing::warn!(kind = ?e.kind, error = %e, "failed to detect DNS provider");
     }
 
     result
 }
 
 // Get raw DNS server IPs currently configured on the system
-pub async fn get_dns_servers() -> Result<Vec<String>, String> {
-    tokio::task::spawn_blocking(netok_core::get_current_dns)
+pub async fn get_dns_servers() -> Result<Vec<String>, ErrorInfo> {
+    Ok(tokio::task::spawn_blocking(netok_core::get_current_dns)
         .await
-        .map_err(|e| format!("Failed to run DNS servers task: {}", e))?
+        .map_err(|e| ErrorInfo::task("DNS servers", e))??)
 }
 
-// Test if a DNS server is reachable (async wrapper)
-pub async fn test_dns_server_reachable(server_ip: String) -> Result<bool, String> {
-    tokio::task::spawn_blocking(move || {
-        netok_core::test_dns_server(&server_ip, 5) // 5 second timeout
-    })
+// Re-export DNS transport types
+pub use netok_core::{DnsTransport, DnsTransportStatus};
+
+// Test if a DNS server (IP, DoH URL or tls:// hostname) is reachable
+pub async fn test_dns_server_reachable(server_ip: String) -> Result<bool, ErrorInfo> {
+    Ok(netok_core::test_dns_server_async(&server_ip, 5).await?) // 5 second timeout
+}
+
+// Ping a DNS server (IP, DoH URL or tls:// hostname) and measure latency in ms
+pub async fn ping_dns_server(server_ip: String) -> Result<Option<u64>, ErrorInfo> {
+    Ok(netok_core::ping_dns_server_async(&server_ip, 3).await?) // 3 second timeout
+}
+
+/// Check which transports (UDP, DoH, DoT) of `provider` work on this network.
+pub async fn check_dns_transports(
+    provider: DnsProviderType,
+) -> Result<Vec<DnsTransportStatus>, ErrorInfo> {
+    let provider = dns_provider_to_core(provider)?;
+    Ok(netok_core::check_dns_transports_async(&provider, 3).await) // 3 second timeout
+}
+
+// ==================== DNS Presets ====================
+
+// Re-export preset types
+pub use netok_core::{DnsPreset, PresetStore};
+
+/// A saved preset, with the provider as the UI names it.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct DnsPresetInfo {
+    pub name: String,
+    pub provider: DnsProviderType,
+}
+
+/// Saved presets. Presets of providers no longer in the catalog are skipped.
+pub async fn list_dns_presets(
+    store: std::sync::Arc<PresetStore>,
+) -> Result<Vec<DnsPresetInfo>, ErrorInfo> {
+    let presets = tokio::task::spawn_blocking(move || store.list())
+        .await
+        .map_err(|e| ErrorInfo::task("DNS presets", e))??;
+    Ok(presets
+        .into_iter()
+        .filter_map(|preset| {
+            let provider = dns_provider_from_core(preset.provider.clone());
+            if provider.is_none() {
+                tracing::warn!(preset = %preset.name, provider = ?preset.provider, "skipping preset of unknown DNS provider");
+            }
+            Some(DnsPresetInfo {
+                name: preset.name,
+                provider: provider?,
+            })
+        })
+        .collect())
+}
+
+/// Save `provider` under `name`, replacing a preset with the same name.
+pub async fn save_dns_preset(
+    store: std::sync::Arc<PresetStore>,
+    name: String,
+    provider: DnsProviderType,
+) -> Result<(), ErrorInfo> {
+    let provider = dns_provider_to_core(provider)?;
+    Ok(
+        tokio::task::spawn_blocking(move || store.save(DnsPreset { name, provider }))
+            .await
+            .map_err(|e| ErrorInfo::task("DNS presets", e))??,
+    )
+}
+
+/// Delete the preset named `name`. Returns `false` if there was none.
+pub async fn delete_dns_preset(
+    store: std::sync::Arc<PresetStore>,
+    name: String,
+) -> Result<bool, ErrorInfo> {
+    Ok(tokio::task::spawn_blocking(move || store.remove(&name))
+        .await
+        .map_err(|e| ErrorInfo::task("DNS presets", e))??)
+}
+
+// ==================== DNS Benchmark ====================
+
+// Re-export DNS benchmark types
+pub use netok_core::{BenchmarkOptions, DnsBenchmark, ResolverBenchmark};
+
+/// Benchmark the current resolver and every built-in provider against
+/// `backend`, stopping when `cancel` fires or `deadline` passes with the
+/// resolvers ranked on the queries answered so far.
+pub async fn run_dns_benchmark_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    options: BenchmarkOptions,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<DnsBenchmark> {
+    netok_core::run_dns_benchmark_cancellable(
+        backend.as_ref(),
+        &options,
+        &Cancellation::new(cancel, deadline),
+    )
     .await
-    .map_err(|e| format!("Failed to run DNS server test task: {}", e))?
 }
 
-// Ping a DNS server and measure latency in ms (async wrapper)
-pub async fn ping_dns_server(server_ip: String) -> Result<Option<u64>, String> {
-    tokio::task::spawn_blocking(move || {
-        netok_core::ping_dns_server(&server_ip, 3) // 3 second timeout
-    })
+// ==================== DNSSEC ====================
+
+// Re-export DNSSEC types
+pub use netok_core::{DnssecCheck, DnssecReport, DnssecStatus};
+
+/// Check whether the current resolver and every built-in provider validate
+/// DNSSEC, stopping when `cancel` fires or `deadline` passes. Resolvers not
+/// checked by then are reported as unknown.
+pub async fn check_dnssec_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    timeout_secs: u64,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<DnssecReport> {
+    netok_core::check_dnssec_cancellable(
+        backend.as_ref(),
+        timeout_secs,
+        &Cancellation::new(cancel, deadline),
+    )
+    .await
+}
+
+// ==================== DNS Filtering ====================
+
+// Re-export DNS filtering verification types
+pub use netok_core::{CategoryCheck, FilterResult, FilteringReport, FilteringStatus};
+
+/// Check that `provider` (`Auto` for the current one) blocks the categories it
+/// promises to and that lookups on this machine go through it, stopping when
+/// `cancel` fires or `deadline` passes. Categories not checked by then are
+/// reported as unknown.
+pub async fn verify_dns_filtering_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    provider: DnsProviderType,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Result<Partial<FilteringReport>, ErrorInfo> {
+    let core_provider = dns_provider_to_core(provider)?;
+    let settings = current_settings();
+    Ok(netok_core::verify_dns_filtering_cancellable(
+        backend.as_ref(),
+        &settings.probe_targets,
+        &core_provider,
+        &Cancellation::new(cancel, deadline),
+    )
+    .await)
+}
+
+// ==================== Resolver Identity ====================
+
+// Re-export resolver identification types
+pub use netok_core::{IdentityConfidence, IdentityEvidence, IdentityMethod, ResolverIdentity};
+
+/// Identify the provider that answers this machine's DNS queries, even behind
+/// a local forwarder, stopping when `cancel` fires or `deadline` passes with
+/// the evidence gathered so far.
+pub async fn identify_resolver_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<ResolverIdentity> {
+    let settings = current_settings();
+    netok_core::identify_resolver_cancellable(
+        backend.as_ref(),
+        &settings.probe_targets,
+        &Cancellation::new(cancel, deadline),
+    )
     .await
-    .map_err(|e| format!("Failed to run DNS ping task: {}", e))?
 }
 
 // ==================== VPN Validation ====================
@@ -420,66 +915,87 @@ pub struct VpnKeyValidation {
     pub server: String,
     pub port: u16,
     pub error: Option<String>,
+    /// Structured form of `error` (kind, i18n key, position in the URI).
+    #[serde(default)]
+    pub error_info: Option<ErrorInfo>,
 }
 
 /// Validate a VPN URI: parse it and check if the server is reachable via TCP.
-pub async fn validate_vpn_key(raw_uri: String) -> Result<VpnKeyValidation, String> {
-    tokio::task::spawn_blocking(move || {
-        // Step 1: Parse URI
-        let protocol = match netok_core::parse_vpn_uri(&raw_uri) {
-            Ok(p) => p,
-            Err(e) => {
-                return Ok(VpnKeyValidation {
+pub async fn validate_vpn_key(raw_uri: String) -> Result<VpnKeyValidation, ErrorInfo> {
+    Ok(
+        validate_vpn_key_cancellable(raw_uri, &CancelToken::new(), None)
+            .await
+            .value,
+    )
+}
+
+/// [`validate_vpn_key`] that stops when `cancel` fires or `deadline` passes.
+/// A reachability probe cut short reports `reachable: false`.
+pub async fn validate_vpn_key_cancellable(
+    raw_uri: String,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<VpnKeyValidation> {
+    // Step 1: Parse URI
+    let protocol = match netok_core::parse_vpn_uri(&raw_uri) {
+        Ok(p) => p,
+        Err(e) => {
+            let info = ErrorInfo::from(e);
+            return Partial::new(
+                VpnKeyValidation {
                     valid: false,
                     reachable: false,
                     protocol: String::new(),
                     server: String::new(),
                     port: 0,
-                    error: Some(e),
-                });
-            }
-        };
+                    error: Some(info.message.clone()),
+                    error_info: Some(info),
+                },
+                Completion::Complete,
+            );
+        }
+    };
 
-        // Step 2: Extract server + port
-        let (server, port, proto_name) = match &protocol {
-            netok_core::VpnProtocol::Vless(p) => (p.server.clone(), p.port, "VLESS"),
-            netok_core::VpnProtocol::Vmess(p) => (p.server.clone(), p.port, "VMess"),
-            netok_core::VpnProtocol::Shadowsocks(p) => (p.server.clone(), p.port, "Shadowsocks"),
-            netok_core::VpnProtocol::Trojan(p) => (p.server.clone(), p.port, "Trojan"),
-            netok_core::VpnProtocol::WireGuard(p) => (p.server.clone(), p.port, "WireGuard"),
-        };
+    // Step 2: Extract server + port
+    let (server, port, proto_name) = match &protocol {
+        netok_core::VpnProtocol::Vless(p) => (p.server.clone(), p.port, "VLESS"),
+        netok_core::VpnProtocol::Vmess(p) => (p.server.clone(), p.port, "VMess"),
+        netok_core::VpnProtocol::Shadowsocks(p) => (p.server.clone(), p.port, "Shadowsocks"),
+        netok_core::VpnProtocol::Trojan(p) => (p.server.clone(), p.port, "Trojan"),
+        netok_core::VpnProtocol::WireGuard(p) => (p.server.clone(), p.port, "WireGuard"),
+    };
 
-        // Step 3: TCP connect to server:port (3s timeout)
-        let addr = format!("{}:{}", server, port);
-        let reachable = match addr.parse::<std::net::SocketAddr>() {
-            Ok(sock_addr) => {
-                std::net::TcpStream::connect_timeout(&sock_addr, std::time::Duration::from_secs(3))
-                    .is_ok()
-            }
-            Err(_) => {
-                // Hostname — try resolving + connecting
-                use std::net::ToSocketAddrs;
-                match addr.to_socket_addrs() {
-                    Ok(mut addrs) => addrs.any(|a| {
-                        std::net::TcpStream::connect_timeout(&a, std::time::Duration::from_secs(3))
-                            .is_ok()
-                    }),
-                    Err(_) => false,
-                }
-            }
+    // Step 3: resolve + TCP connect to server:port (3s timeout per address)
+    let addr = format!("{}:{}", server, port);
+    let probe = async {
+        let Ok(addrs) = tokio::net::lookup_host(&addr).await else {
+            return false;
         };
+        for sock_addr in addrs {
+            let connect = tokio::net::TcpStream::connect(sock_addr);
+            if let Ok(Ok(_)) = tokio::time::timeout(Duration::from_secs(3), connect).await {
+                return true;
+            }
+        }
+        false
+    };
+    let (reachable, completion) = match Cancellation::new(cancel, deadline).run(probe).await {
+        Ok(reachable) => (reachable, Completion::Complete),
+        Err(reason) => (false, reason),
+    };
 
-        Ok(VpnKeyValidation {
+    Partial::new(
+        VpnKeyValidation {
             valid: true,
             reachable,
             protocol: proto_name.to_string(),
             server,
             port,
             error: None,
-        })
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
+            error_info: None,
+        },
+        completion,
+    )
 }
 
 // ==================== VPN Types ====================
@@ -510,7 +1026,7 @@ pub struct VpnStatus {
 
 /// Parse and validate a VPN URI, generate sing-box config JSON.
 /// Pure logic — no process management.
-pub fn generate_vpn_config(raw_uri: &str) -> Result<String, String> {
+pub fn generate_vpn_config(raw_uri: &str) -> Result<String, ErrorInfo> {
     generate_vpn_config_with_log(raw_uri, None)
 }
 
@@ -518,50 +1034,59 @@ pub fn generate_vpn_config(raw_uri: &str) -> Result<String, String> {
 pub fn generate_vpn_config_with_log(
     raw_uri: &str,
     log_path: Option<&str>,
-) -> Result<String, String> {
+) -> Result<String, ErrorInfo> {
     let protocol = netok_core::parse_vpn_uri(raw_uri)?;
     let config = netok_core::generate_singbox_config_with_log(&protocol, log_path)?;
-    serde_json::to_string_pretty(&config).map_err(|e| format!("Failed to serialize config: {}", e))
+    serde_json::to_string_pretty(&config).map_err(|e| {
+        ErrorInfo::new(
+            ErrorKind::Internal,
+            format!("Failed to serialize config: {}", e),
+        )
+    })
 }
 
-/// Verify VPN connection by checking current public IP.
-pub async fn verify_vpn_ip() -> Result<Option<String>, String> {
-    tokio::task::spawn_blocking(|| {
-        let client = reqwest::blocking::Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .map_err(|e| format!("HTTP client error: {}", e))?;
-
-        let resp = client
-            .get("https://ipinfo.io/json")
-            .send()
-            .map_err(|e| format!("IP check failed: {}", e))?;
-
-        let info: netok_core::IpInfoResponse = resp
-            .json()
-            .map_err(|e| format!("Failed to parse IP response: {}", e))?;
-
-        Ok(info.ip)
-    })
+// Re-export DNS leak test types
+pub use netok_core::{DnsLeakReport, LeakResolver, LeakStatus, LeakTestOptions, ResolverOwner};
+
+/// Test which resolvers handle DNS queries while the VPN is up and whether any
+/// belongs to the ISP, stopping when `cancel` fires or `deadline` passes with
+/// the resolvers seen so far.
+pub async fn run_dns_leak_test_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    options: LeakTestOptions,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<DnsLeakReport> {
+    let settings = current_settings();
+    netok_core::run_dns_leak_test_cancellable(
+        backend.as_ref(),
+        &settings.probe_targets,
+        &options,
+        &Cancellation::new(cancel, deadline),
+    )
     .await
-    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Verify VPN connection by checking current public IP.
+pub async fn verify_vpn_ip() -> Result<Option<String>, ErrorInfo> {
+    // An empty IP asks the geolocation API about the caller
+    let info = netok_core::lookup_ip_location_async("", &current_settings().probe_targets).await?;
+    Ok(info.ip)
 }
 
 // Flush DNS cache (async wrapper)
-pub async fn flush_dns() -> Result<(), String> {
-    tokio::task::spawn_blocking(netok_core::flush_dns)
+pub async fn flush_dns() -> Result<(), ErrorInfo> {
+    Ok(tokio::task::spawn_blocking(netok_core::flush_dns)
         .await
-        .map_err(|e| format!("Failed to run DNS flush task: {}", e))?
+        .map_err(|e| ErrorInfo::task("DNS flush", e))??)
 }
 
 // Re-export device scan types
 pub use netok_core::{DeviceType, NetworkDevice};
 
 /// Scan the local network for devices using the ARP table.
-pub async fn scan_network_devices() -> Result<Vec<NetworkDevice>, String> {
-    tokio::task::spawn_blocking(netok_core::scan_network_devices)
-        .await
-        .map_err(|e| format!("Failed to run network scan task: {}", e))
+pub async fn scan_network_devices() -> Result<Vec<NetworkDevice>, ErrorInfo> {
+    Ok(netok_core::scan_network_devices_async(&SystemBackend, None).await)
 }
 
 /// Scan the local network with progress reporting via callback.
@@ -569,15 +1094,41 @@ pub async fn scan_network_devices() -> Result<Vec<NetworkDevice>, String> {
 /// The callback receives stage names: "scanning" (ping sweep), "identifying" (ARP + OUI + DNS).
 pub async fn scan_network_devices_with_progress<F>(
     on_progress: F,
-) -> Result<Vec<NetworkDevice>, String>
+) -> Result<Vec<NetworkDevice>, ErrorInfo>
 where
     F: Fn(&str) + Send + Sync + 'static,
 {
-    tokio::task::spawn_blocking(move || {
-        netok_core::scan_network_devices_with_progress(Some(Box::new(on_progress)))
-    })
+    scan_network_devices_with_backend(system_backend(), on_progress).await
+}
+
+/// Scan the network seen through `backend`, with progress reporting.
+pub async fn scan_network_devices_with_backend<F>(
+    backend: Arc<dyn NetworkBackend>,
+    on_progress: F,
+) -> Result<Vec<NetworkDevice>, ErrorInfo>
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    Ok(netok_core::scan_network_devices_async(backend.as_ref(), Some(Box::new(on_progress))).await)
+}
+
+/// Scan the network seen through `backend`, stopping when `cancel` fires or
+/// `deadline` passes, with the devices identified so far.
+pub async fn scan_network_devices_cancellable<F>(
+    backend: Arc<dyn NetworkBackend>,
+    on_progress: F,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<Vec<NetworkDevice>>
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    netok_core::scan_network_devices_cancellable(
+        backend.as_ref(),
+        Some(Box::new(on_progress)),
+        &Cancellation::new(cancel, deadline),
+    )
     .await
-    .map_err(|e| format!("Failed to run network scan task: {}", e))
 }
 
 // ==================== WiFi Security ====================
@@ -586,8 +1137,308 @@ where
 pub use netok_core::{SecurityCheck, SecurityCheckType, SecurityStatus, WiFiSecurityReport};
 
 /// Run all WiFi security checks (encryption, evil twin, ARP spoofing, DNS hijacking).
-pub async fn check_wifi_security() -> Result<WiFiSecurityReport, String> {
-    tokio::task::spawn_blocking(netok_core::check_wifi_security)
+pub async fn check_wifi_security() -> Result<WiFiSecurityReport, ErrorInfo> {
+    check_wifi_security_with_backend(system_backend()).await
+}
+
+/// Run all WiFi security checks against `backend`.
+pub async fn check_wifi_security_with_backend(
+    backend: Arc<dyn NetworkBackend>,
+) -> Result<WiFiSecurityReport, ErrorInfo> {
+    Ok(
+        netok_core::check_wifi_security_async(backend.as_ref(), &current_settings().probe_targets)
+            .await,
+    )
+}
+
+/// Run the WiFi security checks against `backend`, stopping when `cancel`
+/// fires or `deadline` passes, with the checks finished so far.
+pub async fn check_wifi_security_cancellable(
+    backend: Arc<dyn NetworkBackend>,
+    cancel: &CancelToken,
+    deadline: Option<Duration>,
+) -> Partial<WiFiSecurityReport> {
+    netok_core::check_wifi_security_cancellable(
+        backend.as_ref(),
+        &current_settings().probe_targets,
+        &Cancellation::new(cancel, deadline),
+    )
+    .await
+}
+
+// ==================== Probe Capture ====================
+
+// Re-export capture types
+pub use netok_core::{
+    Capture, ProbeCall, RawOutput, RecordingBackend, ReplayBackend, CAPTURE_FORMAT_VERSION,
+};
+
+/// Results of one diagnostics run plus security checks, recorded or replayed.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CaptureRun {
+    pub snapshot: Snapshot,
+    pub security: WiFiSecurityReport,
+}
+
+/// Run diagnostics and the Wi-Fi security checks against `backend`,
+/// recording every probe response and raw platform output into `path`.
+pub async fn record_capture(
+    backend: Arc<dyn NetworkBackend>,
+    path: std::path::PathBuf,
+) -> Result<CaptureRun, ErrorInfo> {
+    let settings = current_settings();
+    let recorder = RecordingBackend::new(backend);
+    let snapshot = netok_core::run_diagnostics_async(&recorder, &settings).await;
+    let security = netok_core::check_wifi_security_async(&recorder, &settings.probe_targets).await;
+    netok_core::save_capture_async(&recorder.finish(), &path).await?;
+    Ok(CaptureRun {
+        snapshot: snapshot_from_core(snapshot),
+        security,
+    })
+}
+
+/// Replay a capture file through diagnostics and the Wi-Fi security checks.
+pub async fn replay_capture(path: std::path::PathBuf) -> Result<CaptureRun, ErrorInfo> {
+    let replay = ReplayBackend::new(&netok_core::load_capture_async(&path).await?);
+    let settings = current_settings();
+    let snapshot = netok_core::run_diagnostics_async(&replay, &settings).await;
+    let security = netok_core::check_wifi_security_async(&replay, &settings.probe_targets).await;
+    Ok(CaptureRun {
+        snapshot: snapshot_from_core(snapshot),
+        security,
+    })
+}
+
+// ==================== Metrics ====================
+
+// Re-export metrics types
+pub use netok_core::{
+    render_openmetrics, MetricsSample, ResolverLatency, OPENMETRICS_CONTENT_TYPE,
+};
+
+/// Run every check once against `backend` for the metrics exporter.
+pub async fn collect_metrics(backend: Arc<dyn NetworkBackend>) -> Result<MetricsSample, ErrorInfo> {
+    Ok(netok_core::collect_metrics_async(backend.as_ref(), &current_settings()).await)
+}
+
+// ==================== Connectivity Monitor ====================
+
+// Re-export monitor types
+pub use netok_core::{
+    ConnectivityLayer, MonitorConfig, MonitorEvent, MonitorHandle, Outage, ProbeSample,
+};
+
+/// Monitor status for the UI.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct MonitorStatus {
+    pub running: bool,
+    /// Confirmed failing layer, `None` when online or not running.
+    pub failing_layer: Option<ConnectivityLayer>,
+}
+
+/// Start the background connectivity monitor.
+///
+/// `on_event` is called from the monitor thread for every probe sample and
+/// outage transition. Drop or `stop()` the returned handle to end monitoring.
+pub fn start_connectivity_monitor<F>(config: MonitorConfig, on_event: F) -> MonitorHandle
+where
+    F: Fn(&MonitorEvent) + Send + Sync + 'static,
+{
+    netok_core::start_monitor(config, Some(Box::new(on_event)))
+}
+
+// ==================== History ====================
+
+// Re-export history types
+pub use netok_core::{
+    HistoryEntry, HistoryKind, HistoryQuery, HistoryRecord, HistoryStore, NetworkKey,
+    RetentionPolicy,
+};
+
+/// Convert a bridge Snapshot back to the core form stored in history.
+///
+/// Inverse of `convert_node`; `Partial` maps back to `Warn`.
+pub fn snapshot_to_core(snapshot: &Snapshot) -> netok_core::DiagnosticsSnapshot {
+    let nodes = snapshot
+        .nodes
+        .iter()
+        .map(|node| {
+            let (id, name_key) = match node.id {
+                NodeId::Computer => (netok_core::NodeId::Computer, "nodes.computer.name"),
+                NodeId::Network => (netok_core::NodeId::Wifi, "nodes.wifi.name"),
+                NodeId::Dns => (netok_core::NodeId::RouterUpnp, "nodes.router.name"),
+                NodeId::Internet => (netok_core::NodeId::Internet, "nodes.internet.name"),
+            };
+            let status = match node.status {
+                Overall::Ok => netok_core::Status::Ok,
+                Overall::Partial => netok_core::Status::Warn,
+                Overall::Down => netok_core::Status::Fail,
+            };
+            netok_core::NodeInfo {
+                id,
+                name_key: name_key.to_string(),
+                status,
+                latency_ms: node.latency_ms.map(|ms| ms as u32),
+                hint_key: node.hint_key.clone(),
+            }
+        })
+        .collect();
+
+    netok_core::DiagnosticsSnapshot {
+        at_utc: snapshot.at_utc.clone(),
+        nodes,
+        summary_key: snapshot.summary_key.clone(),
+        computer: snapshot.computer.clone(),
+        network: snapshot.network.clone(),
+        router: snapshot.router.clone(),
+        internet: snapshot.internet.clone(),
+    }
+}
+
+/// Store a diagnostics snapshot in history.
+pub async fn record_diagnostics(
+    store: std::sync::Arc<HistoryStore>,
+    snapshot: Snapshot,
+) -> Result<HistoryRecord, ErrorInfo> {
+    tokio::task::spawn_blocking(move || {
+        let core = snapshot_to_core(&snapshot);
+        let network = NetworkKey::from_snapshot(&core);
+        store.append(
+            HistoryEntry::Diagnostics {
+                snapshot: Box::new(core),
+            },
+            network,
+        )
+    })
+    .await
+    .map_err(|e| ErrorInfo::task("history", e))?
+    .map_err(ErrorInfo::from)
+}
+
+/// Store a Wi-Fi security report in history, tagged with the current network.
+pub async fn record_security_report(
+    store: std::sync::Arc<HistoryStore>,
+    report: WiFiSecurityReport,
+) -> Result<HistoryRecord, ErrorInfo> {
+    tokio::task::spawn_blocking(move || {
+        let mut network = NetworkKey::current();
+        if report.network_ssid.is_some() {
+            network.ssid = report.network_ssid.clone();
+        }
+        store.append(HistoryEntry::Security { report }, network)
+    })
+    .await
+    .map_err(|e| ErrorInfo::task("history", e))?
+    .map_err(ErrorInfo::from)
+}
+
+/// Store device scan results in history, tagged with the current network.
+pub async fn record_device_scan(
+    store: std::sync::Arc<HistoryStore>,
+    devices: Vec<NetworkDevice>,
+) -> Result<HistoryRecord, ErrorInfo> {
+    tokio::task::spawn_blocking(move || {
+        store.append(HistoryEntry::DeviceScan { devices }, NetworkKey::current())
+    })
+    .await
+    .map_err(|e| ErrorInfo::task("history", e))?
+    .map_err(ErrorInfo::from)
+}
+
+/// Query stored history, oldest first.
+pub async fn query_history(
+    store: std::sync::Arc<HistoryStore>,
+    query: HistoryQuery,
+) -> Result<Vec<HistoryRecord>, ErrorInfo> {
+    Ok(tokio::task::spawn_blocking(move || store.query(&query))
         .await
-        .map_err(|e| format!("Failed to run WiFi security check task: {}", e))
+        .map_err(|e| ErrorInfo::task("history", e))??)
+}
+
+// ==================== Snapshot Diff ====================
+
+// Re-export diff types
+pub use netok_core::{DiffOptions, SnapshotChange};
+
+/// Compare two UI snapshots (before, after).
+pub fn diff_snapshots(before: &Snapshot, after: &Snapshot) -> Vec<SnapshotChange> {
+    netok_core::diff_snapshots(&snapshot_to_core(before), &snapshot_to_core(after))
+}
+
+/// Compare two history records of the same kind (before, after).
+pub fn diff_history_records(
+    before: &HistoryRecord,
+    after: &HistoryRecord,
+) -> Result<Vec<SnapshotChange>, ErrorInfo> {
+    match (&before.entry, &after.entry) {
+        (HistoryEntry::Diagnostics { snapshot: a }, HistoryEntry::Diagnostics { snapshot: b }) => {
+            Ok(netok_core::diff_snapshots(a, b))
+        }
+        (HistoryEntry::Security { report: a }, HistoryEntry::Security { report: b }) => {
+            Ok(netok_core::diff_security_reports(a, b))
+        }
+        (HistoryEntry::DeviceScan { devices: a }, HistoryEntry::DeviceScan { devices: b }) => {
+            Ok(netok_core::diff_devices(a, b))
+        }
+        _ => Err(ErrorInfo::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Cannot compare {:?} record with {:?} record",
+                before.entry.kind(),
+                after.entry.kind()
+            ),
+        )),
+    }
+}
+
+// ==================== Report Export ====================
+
+// Re-export report and redaction types
+pub use netok_core::{I18n, RedactionLevel, Redactor, Report, ReportFormat, REPORT_SCHEMA_VERSION};
+
+/// Build a report from UI data. Any part may be omitted.
+///
+/// Identifying data is stripped according to `redaction`; pseudonyms are
+/// consistent within one report. Text formats are rendered in `language`
+/// (the UI's language code), falling back to English.
+pub fn build_report(
+    snapshot: Option<&Snapshot>,
+    security: Option<WiFiSecurityReport>,
+    devices: Option<Vec<NetworkDevice>>,
+    history: Vec<HistoryRecord>,
+    redaction: RedactionLevel,
+    language: &str,
+) -> Report {
+    let report = Report {
+        language: language.to_string(),
+        ..Report::new(snapshot.map(snapshot_to_core), security, devices, history)
+    };
+    Redactor::new(redaction).redact_report(report)
+}
+
+/// Render a report and write it to `path`.
+///
+/// When `format` is `None` it is inferred from the file extension.
+pub async fn save_report(
+    path: std::path::PathBuf,
+    format: Option<ReportFormat>,
+    report: Report,
+) -> Result<(), ErrorInfo> {
+    let format = match format {
+        Some(format) => format,
+        None => ReportFormat::from_path(&path).ok_or_else(|| {
+            ErrorInfo::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Cannot infer report format from {}; use .md, .html or .json",
+                    path.display()
+                ),
+            )
+        })?,
+    };
+    Ok(
+        tokio::task::spawn_blocking(move || netok_core::save_report(&report, format, &path))
+            .await
+            .map_err(|e| ErrorInfo::task("report", e))??,
+    )
 }
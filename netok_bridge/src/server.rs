@@ -0,0 +1,495 @@
+//! Local HTTP API (`server` feature).
+//!
+//! Serves diagnostics, device scans, security reports, history and DNS tests
+//! as JSON under `/api/v1`, with server-sent events for progress, and
+//! OpenMetrics text at `/api/v1/metrics` from periodic runs of every check. Every route
+//! except `/api/v1/health` needs the token, either as
+//! `Authorization: Bearer <token>` or as `?token=` (browsers' `EventSource`
+//! cannot set headers).
+
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::extract::{Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::{ErrorInfo, ErrorKind, HistoryKind, HistoryQuery, HistoryStore, NetworkBackend};
+
+/// Port used when none is given.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Time between metrics collections when none is given.
+pub const DEFAULT_METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+// ==================== Configuration ====================
+
+/// What the server exposes and to whom.
+pub struct ServerConfig {
+    /// Defaults to `127.0.0.1:7878`; other devices need an explicit address.
+    pub bind: SocketAddr,
+    /// Shared secret every request (except health) must present.
+    pub token: String,
+    pub backend: Arc<dyn NetworkBackend>,
+    /// Runs are recorded here and `/history` reads from it; `None` disables both.
+    pub history: Option<Arc<HistoryStore>>,
+    /// How often to run every check for `/metrics`; `None` disables the exporter.
+    pub metrics_interval: Option<Duration>,
+}
+
+impl ServerConfig {
+    /// Localhost-only server on the real machine, without history.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            bind: SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT)),
+            token: token.into(),
+            backend: crate::system_backend(),
+            history: None,
+            metrics_interval: Some(DEFAULT_METRICS_INTERVAL),
+        }
+    }
+}
+
+/// Random 256-bit token, hex-encoded.
+pub fn generate_token() -> Result<String, ErrorInfo> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| {
+        ErrorInfo::new(
+            ErrorKind::Internal,
+            format!("Failed to generate API token: {}", e),
+        )
+    })?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// ==================== Server ====================
+
+#[derive(Clone)]
+struct AppState {
+    token: Arc<str>,
+    backend: Arc<dyn NetworkBackend>,
+    history: Option<Arc<HistoryStore>>,
+    /// Last OpenMetrics rendering; `None` when the exporter is disabled.
+    metrics: Option<Arc<RwLock<Option<String>>>>,
+}
+
+/// A bound, not yet running API server.
+pub struct ApiServer {
+    listener: tokio::net::TcpListener,
+    router: Router,
+    collector: Option<(Duration, AppState)>,
+}
+
+impl ApiServer {
+    /// Bind the listening socket. Fails on an empty token or a taken port.
+    pub async fn bind(config: ServerConfig) -> Result<Self, ErrorInfo> {
+        if config.token.is_empty() {
+            return Err(ErrorInfo::new(
+                ErrorKind::InvalidInput,
+                "API token must not be empty",
+            ));
+        }
+        if !config.bind.ip().is_loopback() {
+            tracing::warn!(bind = %config.bind, "API server reachable from other devices");
+        }
+
+        let listener = tokio::net::TcpListener::bind(config.bind)
+            .await
+            .map_err(|e| {
+                let kind = match e.kind() {
+                    std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+                    _ => ErrorKind::Io,
+                };
+                ErrorInfo::new(kind, format!("Failed to bind {}: {}", config.bind, e))
+            })?;
+
+        let state = AppState {
+            token: config.token.into(),
+            backend: config.backend,
+            history: config.history,
+            metrics: config.metrics_interval.map(|_| Arc::new(RwLock::new(None))),
+        };
+        Ok(Self {
+            listener,
+            router: router(state.clone()),
+            collector: config.metrics_interval.map(|interval| (interval, state)),
+        })
+    }
+
+    /// Address actually bound (useful with port 0).
+    pub fn local_addr(&self) -> Result<SocketAddr, ErrorInfo> {
+        self.listener
+            .local_addr()
+            .map_err(|e| ErrorInfo::new(ErrorKind::Io, e.to_string()))
+    }
+
+    /// Serve until `shutdown` completes; open SSE streams are dropped.
+    pub async fn run(
+        self,
+        shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    ) -> Result<(), ErrorInfo> {
+        tracing::info!(addr = ?self.listener.local_addr().ok(), "API server listening");
+        let collector = self
+            .collector
+            .map(|(interval, state)| tokio::spawn(collect_metrics_loop(interval, state)));
+        let result = axum::serve(self.listener, self.router)
+            .with_graceful_shutdown(shutdown)
+            .await
+            .map_err(|e| ErrorInfo::new(ErrorKind::Io, format!("API server failed: {}", e)));
+        if let Some(collector) = collector {
+            collector.abort();
+        }
+        result
+    }
+}
+
+/// Collect right away, then every `interval`, replacing the cached rendering.
+async fn collect_metrics_loop(interval: Duration, state: AppState) {
+    let Some(cache) = state.metrics else {
+        return;
+    };
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        match crate::collect_metrics(state.backend.clone()).await {
+            Ok(sample) => {
+                let text = crate::render_openmetrics(&sample);
+                if let Ok(mut cached) = cache.write() {
+                    *cached = Some(text);
+                }
+                tracing::debug!(duration_ms = sample.duration_ms, "metrics collected");
+            }
+            Err(e) => tracing::warn!(error = %e, "metrics collection failed"),
+        }
+    }
+}
+
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/api/v1/diagnostics", get(diagnostics))
+        .route("/api/v1/diagnostics/stream", get(diagnostics_stream))
+        .route("/api/v1/scan", get(scan))
+        .route("/api/v1/scan/stream", get(scan_stream))
+        .route("/api/v1/security", get(security))
+        .route("/api/v1/history", get(history))
+        .route("/api/v1/dns/test", get(dns_test))
+        .route("/api/v1/metrics", get(metrics))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .route("/api/v1/health", get(health))
+        .fallback(|| async {
+            ApiError::from(ErrorInfo::new(ErrorKind::NotFound, "No such endpoint"))
+        })
+        .with_state(state)
+}
+
+// ==================== Errors and Auth ====================
+
+enum ApiError {
+    Unauthorized,
+    /// Temporarily unable to answer; the client should retry.
+    Unavailable(&'static str),
+    Info(ErrorInfo),
+}
+
+impl From<ErrorInfo> for ApiError {
+    fn from(e: ErrorInfo) -> Self {
+        ApiError::Info(e)
+    }
+}
+
+fn status_for(kind: ErrorKind) -> StatusCode {
+    match kind {
+        ErrorKind::InvalidInput | ErrorKind::Parse => StatusCode::BAD_REQUEST,
+        ErrorKind::NotFound => StatusCode::NOT_FOUND,
+        ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+        ErrorKind::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        ErrorKind::UnsupportedPlatform => StatusCode::NOT_IMPLEMENTED,
+        ErrorKind::Network | ErrorKind::CommandFailed | ErrorKind::Io | ErrorKind::Internal => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::Unauthorized => {
+                let info =
+                    ErrorInfo::new(ErrorKind::PermissionDenied, "Missing or invalid API token");
+                (
+                    StatusCode::UNAUTHORIZED,
+                    [(header::WWW_AUTHENTICATE, "Bearer")],
+                    Json(serde_json::json!({ "error": info })),
+                )
+                    .into_response()
+            }
+            ApiError::Unavailable(message) => {
+                let info = ErrorInfo::new(ErrorKind::Internal, message);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(header::RETRY_AFTER, "10")],
+                    Json(serde_json::json!({ "error": info })),
+                )
+                    .into_response()
+            }
+            ApiError::Info(info) => (
+                status_for(info.kind),
+                Json(serde_json::json!({ "error": info })),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Compare without an early exit, so timing does not reveal the matching prefix.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn request_token(request: &Request) -> Option<&str> {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    bearer.or_else(|| {
+        request
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+    })
+}
+
+async fn require_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    match request_token(&request) {
+        Some(token) if token_matches(token, &state.token) => Ok(next.run(request).await),
+        _ => Err(ApiError::Unauthorized),
+    }
+}
+
+// ==================== Handlers ====================
+
+/// SSE event with a JSON payload.
+fn event(name: &str, data: &impl Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|_| Event::default().event(name))
+}
+
+fn event_stream(
+    rx: mpsc::UnboundedReceiver<Event>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    Sse::new(UnboundedReceiverStream::new(rx).map(Ok)).keep_alive(KeepAlive::default())
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+    }))
+}
+
+async fn diagnostics(State(state): State<AppState>) -> Result<Json<crate::Snapshot>, ApiError> {
+    let snapshot = crate::run_diagnostics_struct_with_backend(state.backend)
+        .await
+        .map_err(ErrorInfo::internal)?;
+    if let Some(store) = state.history {
+        if let Err(e) = crate::record_diagnostics(store, snapshot.clone()).await {
+            tracing::warn!(error = %e, "failed to record diagnostics");
+        }
+    }
+    Ok(Json(snapshot))
+}
+
+/// One `node` event per node as it finishes, then `done` (or `error`).
+async fn diagnostics_stream(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let backend = state.backend;
+        let result: Result<(), anyhow::Error> = async {
+            let computer = crate::check_computer_node_with_backend(backend.clone()).await?;
+            let adapter = computer.computer.as_ref().and_then(|c| c.adapter.clone());
+            let _ = tx.send(event("node", &computer));
+            let network = crate::check_network_node_with_backend(backend.clone(), adapter).await?;
+            let _ = tx.send(event("node", &network));
+            let router = crate::check_router_node_with_backend(backend.clone()).await?;
+            let _ = tx.send(event("node", &router));
+            let internet = crate::check_internet_node_with_backend(backend).await?;
+            let _ = tx.send(event("node", &internet));
+            Ok(())
+        }
+        .await;
+        let _ = match result {
+            Ok(()) => tx.send(event("done", &serde_json::json!({}))),
+            Err(e) => tx.send(event("error", &ErrorInfo::internal(e))),
+        };
+    });
+    event_stream(rx)
+}
+
+async fn scan(State(state): State<AppState>) -> Result<Json<Vec<crate::NetworkDevice>>, ApiError> {
+    let devices = crate::scan_network_devices_with_backend(state.backend, |_| {}).await?;
+    if let Some(store) = state.history {
+        if let Err(e) = crate::record_device_scan(store, devices.clone()).await {
+            tracing::warn!(error = %e, "failed to record device scan");
+        }
+    }
+    Ok(Json(devices))
+}
+
+/// `progress` events with the scan stage, then `done` with the devices.
+async fn scan_stream(
+    State(state): State<AppState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let progress = tx.clone();
+        let result = crate::scan_network_devices_with_backend(state.backend, move |stage| {
+            let _ = progress.send(event("progress", &serde_json::json!({ "stage": stage })));
+        })
+        .await;
+        match result {
+            Ok(devices) => {
+                if let Some(store) = state.history {
+                    if let Err(e) = crate::record_device_scan(store, devices.clone()).await {
+                        tracing::warn!(error = %e, "failed to record device scan");
+                    }
+                }
+                let _ = tx.send(event("done", &devices));
+            }
+            Err(e) => {
+                let _ = tx.send(event("error", &e));
+            }
+        }
+    });
+    event_stream(rx)
+}
+
+async fn security(
+    State(state): State<AppState>,
+) -> Result<Json<crate::WiFiSecurityReport>, ApiError> {
+    let report = crate::check_wifi_security_with_backend(state.backend).await?;
+    if let Some(store) = state.history {
+        if let Err(e) = crate::record_security_report(store, report.clone()).await {
+            tracing::warn!(error = %e, "failed to record security report");
+        }
+    }
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+struct HistoryParams {
+    kind: Option<HistoryKind>,
+    from: Option<u64>,
+    to: Option<u64>,
+    ssid: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn history(
+    State(state): State<AppState>,
+    Query(params): Query<HistoryParams>,
+) -> Result<Json<Vec<crate::HistoryRecord>>, ApiError> {
+    let store = state
+        .history
+        .ok_or_else(|| ErrorInfo::new(ErrorKind::NotFound, "History is disabled"))?;
+    let query = HistoryQuery {
+        from: params.from,
+        to: params.to,
+        ssid: params.ssid,
+        kind: params.kind,
+        limit: params.limit,
+        ..Default::default()
+    };
+    Ok(Json(crate::query_history(store, query).await?))
+}
+
+async fn metrics(State(state): State<AppState>) -> Result<Response, ApiError> {
+    let cache = state
+        .metrics
+        .ok_or_else(|| ErrorInfo::new(ErrorKind::NotFound, "Metrics are disabled"))?;
+    let text = cache.read().ok().and_then(|cached| cached.clone());
+    let text = text.ok_or(ApiError::Unavailable("Metrics are still being collected"))?;
+    Ok((
+        [(header::CONTENT_TYPE, crate::OPENMETRICS_CONTENT_TYPE)],
+        text,
+    )
+        .into_response())
+}
+
+#[derive(Deserialize)]
+struct DnsTestParams {
+    server: String,
+}
+
+#[derive(Serialize)]
+struct DnsTestResult {
+    server: String,
+    reachable: bool,
+    latency_ms: Option<u64>,
+}
+
+async fn dns_test(Query(params): Query<DnsTestParams>) -> Result<Json<DnsTestResult>, ApiError> {
+    // An invalid server is rejected before anything is sent
+    let latency_ms = crate::ping_dns_server(params.server.clone()).await?;
+    Ok(Json(DnsTestResult {
+        server: params.server,
+        reachable: latency_ms.is_some(),
+        latency_ms,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc124", "abc123"));
+        assert!(!token_matches("abc", "abc123"));
+        assert!(!token_matches("", "abc123"));
+    }
+
+    #[test]
+    fn test_generated_tokens_differ() {
+        let a = generate_token().unwrap();
+        let b = generate_token().unwrap();
+        assert_eq!(a.len(), 64);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_error_status_mapping() {
+        assert_eq!(status_for(ErrorKind::InvalidInput), StatusCode::BAD_REQUEST);
+        assert_eq!(status_for(ErrorKind::NotFound), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status_for(ErrorKind::Internal),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
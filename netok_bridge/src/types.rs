@@ -66,16 +66,19 @@ impl std::fmt::Display for NodeId {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NodeResult {
     pub id: NodeId,
     pub label: String,
     pub status: Overall,
     pub latency_ms: Option<u64>,
     pub details: Option<String>,
+    /// i18n key explaining the status, e.g. `nodes.network.hint_dhcp_failure`.
+    #[serde(default)]
+    pub hint_key: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Speed {
     pub down_mbps: Option<f64>,
     pub up_mbps: Option<f64>,
@@ -91,7 +94,7 @@ pub struct SingleNodeResult {
     pub internet: Option<InternetInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Snapshot {
     pub at_utc: String,
     pub overall: Overall,
@@ -7,6 +7,13 @@ use netok_bridge::{
 // Settings Tests
 // ============================================================================
 
+/// Serializes tests that read back the process-wide settings.
+static SETTINGS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn settings_lock() -> std::sync::MutexGuard<'static, ()> {
+    SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 #[test]
 fn test_get_settings_returns_valid_json() {
     let json = get_settings_json();
@@ -23,6 +30,7 @@ fn test_get_settings_returns_valid_json() {
 
 #[test]
 fn test_set_settings_with_valid_json() {
+    let _lock = settings_lock();
     let settings_json = r#"{
         "language": "ru",
         "test_timeout_ms": 3000,
@@ -49,6 +57,7 @@ fn test_set_settings_with_empty_string() {
 
 #[test]
 fn test_set_settings_with_incomplete_structure() {
+    let _lock = settings_lock();
     let incomplete_json = r#"{"language": "en"}"#;
 
     let result = set_settings_json(incomplete_json);
@@ -57,6 +66,41 @@ fn test_set_settings_with_incomplete_structure() {
     let _ = result;
 }
 
+#[test]
+fn test_saved_settings_are_loaded_and_applied() {
+    use netok_bridge::{current_settings, load_settings, save_settings, SettingsStore};
+    use std::sync::Arc;
+
+    let path =
+        std::env::temp_dir().join(format!("netok_bridge_settings_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let store = Arc::new(SettingsStore::open(&path).unwrap());
+    // Not a tokio test: the lock is held throughout
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _lock = settings_lock();
+    let original = current_settings();
+
+    let mut settings = original.clone();
+    settings.language = "de".into();
+    settings.probe_targets.dns_leak_zone = "leak.example.net".into();
+    runtime
+        .block_on(save_settings(store.clone(), settings.clone()))
+        .expect("Saving should succeed");
+    assert_eq!(current_settings(), settings);
+
+    netok_bridge::apply_settings(original.clone());
+    let loaded = runtime
+        .block_on(load_settings(store))
+        .expect("Loading should succeed");
+    assert_eq!(loaded, settings);
+    assert_eq!(current_settings(), settings);
+    let json: serde_json::Value = serde_json::from_str(&get_settings_json()).unwrap();
+    assert_eq!(json["probe_targets"]["dns_leak_zone"], "leak.example.net");
+
+    netok_bridge::apply_settings(original);
+    let _ = std::fs::remove_file(&path);
+}
+
 // ============================================================================
 // Diagnostics Tests
 // ============================================================================
@@ -179,11 +223,7 @@ async fn test_set_dns_auto_provider() {
 
 #[tokio::test]
 async fn test_set_dns_cloudflare_standard() {
-    use netok_bridge::CloudflareVariant;
-
-    let provider = DnsProviderType::Cloudflare {
-        variant: CloudflareVariant::Standard,
-    };
+    let provider = DnsProviderType::catalog("Cloudflare", Some("Standard")).unwrap();
 
     let result = set_dns_provider(provider).await;
 
@@ -194,7 +234,7 @@ async fn test_set_dns_cloudflare_standard() {
 
 #[tokio::test]
 async fn test_set_dns_google() {
-    let provider = DnsProviderType::Google;
+    let provider = DnsProviderType::catalog("Google", None).unwrap();
 
     let result = set_dns_provider(provider).await;
 
@@ -210,6 +250,8 @@ async fn test_set_dns_custom_provider() {
         secondary: "8.8.8.8".to_string(),
         primary_ipv6: None,
         secondary_ipv6: None,
+        doh_template: None,
+        dot_hostname: None,
     };
 
     let result = set_dns_provider(provider).await;
@@ -225,45 +267,25 @@ async fn test_set_dns_custom_provider() {
 
 #[test]
 fn test_dns_provider_all_variants_serialize() {
-    use netok_bridge::{
-        AdGuardVariant, CloudflareVariant, Dns4EuVariant, OpenDnsVariant, Quad9Variant,
-    };
-
     let variants = vec![
         DnsProviderType::Auto,
-        DnsProviderType::Google,
-        DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Standard,
-        },
-        DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Malware,
-        },
-        DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Family,
-        },
-        DnsProviderType::AdGuard {
-            variant: AdGuardVariant::Standard,
-        },
-        DnsProviderType::AdGuard {
-            variant: AdGuardVariant::NonFiltering,
-        },
-        DnsProviderType::AdGuard {
-            variant: AdGuardVariant::Family,
-        },
-        DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::Protective,
-        },
-        DnsProviderType::Quad9 {
-            variant: Quad9Variant::Recommended,
-        },
-        DnsProviderType::OpenDns {
-            variant: OpenDnsVariant::FamilyShield,
-        },
+        DnsProviderType::catalog("Google", None).unwrap(),
+        DnsProviderType::catalog("Cloudflare", Some("Standard")).unwrap(),
+        DnsProviderType::catalog("Cloudflare", Some("Malware")).unwrap(),
+        DnsProviderType::catalog("Cloudflare", Some("Family")).unwrap(),
+        DnsProviderType::catalog("AdGuard", Some("Standard")).unwrap(),
+        DnsProviderType::catalog("AdGuard", Some("NonFiltering")).unwrap(),
+        DnsProviderType::catalog("AdGuard", Some("Family")).unwrap(),
+        DnsProviderType::catalog("Dns4Eu", Some("Protective")).unwrap(),
+        DnsProviderType::catalog("Quad9", Some("Recommended")).unwrap(),
+        DnsProviderType::catalog("OpenDns", Some("FamilyShield")).unwrap(),
         DnsProviderType::Custom {
             primary: "1.2.3.4".to_string(),
             secondary: "5.6.7.8".to_string(),
             primary_ipv6: Some("2001:db8::1".to_string()),
             secondary_ipv6: Some("2001:db8::2".to_string()),
+            doh_template: Some("https://dns.example/dns-query{?dns}".to_string()),
+            dot_hostname: Some("dns.example".to_string()),
         },
     ];
 
@@ -293,6 +315,37 @@ fn test_dns_provider_deserialization() {
     assert!(provider.is_ok(), "Cloudflare provider should deserialize");
 }
 
+#[test]
+fn test_dns_provider_json_shape_follows_catalog() {
+    let quad9 = DnsProviderType::catalog("Quad9", Some("Recommended")).unwrap();
+    assert_eq!(
+        serde_json::to_value(&quad9).unwrap(),
+        serde_json::json!({"type": "Quad9", "variant": "Recommended"})
+    );
+    let google = DnsProviderType::catalog("Google", None).unwrap();
+    assert_eq!(
+        serde_json::to_value(&google).unwrap(),
+        serde_json::json!({"type": "Google"})
+    );
+    let custom: DnsProviderType =
+        serde_json::from_str(r#"{"type":"Custom","primary":"1.2.3.4","secondary":"","primaryIpv6":null,"secondaryIpv6":null}"#)
+            .unwrap();
+    assert!(matches!(custom, DnsProviderType::Custom { ref primary, .. } if primary == "1.2.3.4"));
+
+    // Every catalog entry can be named, and nothing else
+    for entry in &netok_bridge::get_dns_catalog().providers {
+        let provider = DnsProviderType::catalog(&entry.family, entry.variant.as_deref());
+        let json = serde_json::to_string(&provider.unwrap()).unwrap();
+        assert!(
+            serde_json::from_str::<DnsProviderType>(&json).is_ok(),
+            "{}",
+            json
+        );
+    }
+    assert!(DnsProviderType::catalog("Quad9", Some("Nope")).is_none());
+    assert!(serde_json::from_str::<DnsProviderType>(r#"{"type":"Nope"}"#).is_err());
+}
+
 // ============================================================================
 // Error Handling Tests
 // ============================================================================
@@ -311,6 +364,7 @@ async fn test_diagnostics_completes_even_with_network_issues() {
 
 #[test]
 fn test_settings_json_roundtrip() {
+    let _lock = settings_lock();
     // Get current settings
     let original_json = get_settings_json();
 
@@ -373,6 +427,8 @@ fn test_dns_provider_custom_validates_ips() {
         secondary: "8.8.8.8".to_string(),
         primary_ipv6: None,
         secondary_ipv6: None,
+        doh_template: None,
+        dot_hostname: None,
     };
 
     let json = serde_json::to_string(&provider1);
@@ -383,8 +439,944 @@ fn test_dns_provider_custom_validates_ips() {
         secondary: "192.168.1.2".to_string(),
         primary_ipv6: Some("2001:db8::1".to_string()),
         secondary_ipv6: None,
+        doh_template: None,
+        dot_hostname: None,
     };
 
     let json = serde_json::to_string(&provider2);
     assert!(json.is_ok());
 }
+
+// ============================================================================
+// History Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_history_records_and_queries_snapshots() {
+    use netok_bridge::{
+        query_history, record_diagnostics, HistoryQuery, HistoryStore, RetentionPolicy,
+    };
+    use std::sync::Arc;
+
+    let path =
+        std::env::temp_dir().join(format!("netok_bridge_history_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let store = Arc::new(HistoryStore::open(&path, RetentionPolicy::default()).unwrap());
+
+    let snapshot = run_diagnostics_struct()
+        .await
+        .expect("Diagnostics should complete");
+    let node_count = snapshot.nodes.len();
+    record_diagnostics(store.clone(), snapshot)
+        .await
+        .expect("Recording should succeed");
+
+    let records = query_history(store.clone(), HistoryQuery::default())
+        .await
+        .expect("Query should succeed");
+    assert_eq!(records.len(), 1);
+    match &records[0].entry {
+        netok_bridge::HistoryEntry::Diagnostics { snapshot } => {
+            assert_eq!(snapshot.nodes.len(), node_count);
+        }
+        other => panic!("Unexpected entry: {:?}", other),
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
+
+// ============================================================================
+// Report Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_save_report_infers_format_from_extension() {
+    use netok_bridge::{build_report, save_report, RedactionLevel};
+
+    let snapshot = run_diagnostics_struct()
+        .await
+        .expect("Diagnostics should complete");
+    let report = build_report(
+        Some(&snapshot),
+        None,
+        None,
+        vec![],
+        RedactionLevel::None,
+        "de",
+    );
+
+    let path =
+        std::env::temp_dir().join(format!("netok_bridge_report_{}.html", std::process::id()));
+    save_report(path.clone(), None, report.clone())
+        .await
+        .expect("Saving report should succeed");
+    let html = std::fs::read_to_string(&path).expect("Report should be written");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<html lang=\"de\">"));
+    assert!(html.contains("<h2>Diagnose</h2>"));
+    let _ = std::fs::remove_file(&path);
+
+    let bad = std::env::temp_dir().join("netok_bridge_report.txt");
+    assert!(save_report(bad, None, report).await.is_err());
+}
+
+#[tokio::test]
+async fn test_build_report_applies_redaction() {
+    use netok_bridge::{build_report, RedactionLevel};
+
+    let mut snapshot = run_diagnostics_struct()
+        .await
+        .expect("Diagnostics should complete");
+    snapshot.computer.hostname = Some("alices-laptop".to_string());
+    snapshot.internet.public_ip = Some("203.0.113.7".to_string());
+
+    let report = build_report(
+        Some(&snapshot),
+        None,
+        None,
+        vec![],
+        RedactionLevel::Public,
+        "en",
+    );
+    assert_eq!(report.redaction, RedactionLevel::Public);
+    let json = serde_json::to_string(&report).unwrap();
+    assert!(!json.contains("alices-laptop"));
+    assert!(!json.contains("203.0.113.7"));
+}
+
+// ============================================================================
+// Error Mapping Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_vpn_validation_reports_structured_error() {
+    use netok_bridge::{validate_vpn_key, ErrorKind};
+
+    let uri = "trojan://secret@example.com:notaport".to_string();
+    let result = validate_vpn_key(uri.clone())
+        .await
+        .expect("Validation should complete");
+    assert!(!result.valid);
+
+    let info = result.error_info.expect("Structured error should be set");
+    assert_eq!(info.kind, ErrorKind::Parse);
+    assert_eq!(info.key, "errors.parse");
+    assert_eq!(info.position, uri.find("notaport"));
+    assert_eq!(result.error.as_deref(), Some(info.message.as_str()));
+}
+
+#[cfg(not(target_os = "windows"))]
+#[tokio::test]
+async fn test_unsupported_platform_maps_to_i18n_key() {
+    use netok_bridge::{flush_dns, ErrorKind};
+
+    let err = flush_dns().await.expect_err("Flush is Windows-only");
+    assert_eq!(err.kind, ErrorKind::UnsupportedPlatform);
+    assert_eq!(err.key, "errors.unsupported_platform");
+    assert!(err.message.contains("only supported on Windows"));
+}
+
+// ============================================================================
+// Simulated Backend Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_simulated_backend_drives_diagnostics() {
+    use netok_bridge::{
+        check_network_node_with_backend, run_diagnostics_struct_with_backend, simulated_backend,
+        ConnectionType, DiagnosticScenario,
+    };
+
+    let snapshot =
+        run_diagnostics_struct_with_backend(simulated_backend(DiagnosticScenario::AllGood))
+            .await
+            .expect("Simulated diagnostics should run");
+    assert_eq!(snapshot.overall, Overall::Ok);
+    assert_eq!(snapshot.computer.hostname.as_deref(), Some("DEMO-LAPTOP"));
+
+    let backend = simulated_backend(DiagnosticScenario::WifiDisabled);
+    let snapshot = run_diagnostics_struct_with_backend(backend.clone())
+        .await
+        .expect("Simulated diagnostics should run");
+    assert_eq!(snapshot.overall, Overall::Down);
+    assert_eq!(snapshot.network.connection_type, ConnectionType::Disabled);
+
+    let result = check_network_node_with_backend(backend, None)
+        .await
+        .expect("Network check should run");
+    assert_eq!(result.node.id, NodeId::Network);
+    assert_eq!(result.node.status, Overall::Down);
+}
+
+#[tokio::test]
+async fn test_node_hints_reach_the_bridge() {
+    use netok_bridge::{
+        run_diagnostics_struct_with_backend, simulated_backend, DiagnosticScenario,
+    };
+
+    let snapshot =
+        run_diagnostics_struct_with_backend(simulated_backend(DiagnosticScenario::DhcpFailure))
+            .await
+            .expect("Simulated diagnostics should run");
+    let computer = snapshot
+        .nodes
+        .iter()
+        .find(|n| n.id == NodeId::Computer)
+        .expect("Computer node");
+    assert_eq!(
+        computer.hint_key.as_deref(),
+        Some("nodes.computer.hint_dhcp_failure")
+    );
+    assert!(snapshot
+        .network
+        .dhcp
+        .as_ref()
+        .is_some_and(|d| d.link_local_only));
+
+    // History stores the core form; the hint must survive the trip
+    let core = netok_bridge::snapshot_to_core(&snapshot);
+    assert!(core
+        .nodes
+        .iter()
+        .any(|n| n.hint_key.as_deref() == Some("nodes.computer.hint_dhcp_failure")));
+}
+
+#[tokio::test]
+async fn test_broken_dnssec_reaches_the_internet_node() {
+    use netok_bridge::{
+        check_internet_node_with_backend, DiagnosticScenario, DnssecStatus, SimulatedBackend,
+    };
+    use std::sync::Arc;
+
+    let mut backend = SimulatedBackend::scenario(DiagnosticScenario::AllGood);
+    backend.dnssec = DnssecStatus::Broken;
+    let result = check_internet_node_with_backend(Arc::new(backend))
+        .await
+        .expect("Internet check should run");
+    assert_eq!(result.node.status, Overall::Partial);
+    assert_eq!(
+        result.node.hint_key.as_deref(),
+        Some("nodes.internet.hint_dnssec_broken")
+    );
+    assert_eq!(
+        result.internet.map(|i| i.dnssec),
+        Some(DnssecStatus::Broken)
+    );
+}
+
+// ============================================================================
+// Cancellation Tests
+// ============================================================================
+
+#[test]
+fn test_operation_registry_cancels_by_id() {
+    use netok_bridge::OperationRegistry;
+
+    let registry = OperationRegistry::default();
+    let guard = registry.register(Some("scan".to_string()));
+    assert_eq!(registry.in_flight(), vec!["scan".to_string()]);
+    assert!(registry.cancel("scan"));
+    assert!(guard.token().is_cancelled());
+    assert!(!registry.cancel("unknown"));
+
+    drop(guard);
+    assert!(registry.in_flight().is_empty());
+    assert!(!registry.cancel("scan"));
+}
+
+#[test]
+fn test_operation_registry_replaces_same_id() {
+    use netok_bridge::OperationRegistry;
+
+    let registry = OperationRegistry::default();
+    let first = registry.register(Some("scan".to_string()));
+    let second = registry.register(Some("scan".to_string()));
+    assert!(first.token().is_cancelled());
+    assert!(!second.token().is_cancelled());
+
+    // The stale guard must not unregister its replacement
+    drop(first);
+    assert_eq!(registry.in_flight(), vec!["scan".to_string()]);
+    drop(second);
+    assert!(registry.in_flight().is_empty());
+}
+
+#[tokio::test]
+async fn test_cancelled_scan_is_marked() {
+    use netok_bridge::{
+        run_diagnostics_cancellable, scan_network_devices_cancellable, simulated_backend,
+        CancelToken, Completion, DiagnosticScenario,
+    };
+
+    let token = CancelToken::new();
+    token.cancel();
+    let backend = simulated_backend(DiagnosticScenario::AllGood);
+    let scan = scan_network_devices_cancellable(backend.clone(), |_| {}, &token, None).await;
+    assert_eq!(scan.completion, Completion::Cancelled);
+    assert!(scan.value.is_empty());
+
+    let diagnostics = run_diagnostics_cancellable(backend, &CancelToken::new(), None).await;
+    assert!(diagnostics.is_complete());
+    assert_eq!(diagnostics.value.overall, Overall::Ok);
+}
+
+#[tokio::test]
+async fn test_vpn_validation_past_deadline_times_out() {
+    use netok_bridge::{validate_vpn_key_cancellable, CancelToken, Completion};
+
+    let result = validate_vpn_key_cancellable(
+        "trojan://secret@127.0.0.1:443#Test".to_string(),
+        &CancelToken::new(),
+        Some(std::time::Duration::ZERO),
+    )
+    .await;
+    assert_eq!(result.completion, Completion::TimedOut);
+    assert!(result.value.valid);
+    assert!(!result.value.reachable);
+}
+
+#[tokio::test]
+async fn test_dns_checks_past_deadline_time_out() {
+    use netok_bridge::{
+        check_dnssec_cancellable, identify_resolver_cancellable, run_dns_benchmark_cancellable,
+        run_dns_leak_test_cancellable, simulated_backend, verify_dns_filtering_cancellable,
+        BenchmarkOptions, CancelToken, Completion, DiagnosticScenario, DnsProviderType,
+        DnssecStatus, FilteringStatus, IdentityMethod, LeakStatus, LeakTestOptions,
+    };
+
+    let backend = simulated_backend(DiagnosticScenario::AllGood);
+    let past = Some(std::time::Duration::ZERO);
+    let token = CancelToken::new();
+
+    let benchmark =
+        run_dns_benchmark_cancellable(backend.clone(), BenchmarkOptions::default(), &token, past)
+            .await;
+    assert_eq!(benchmark.completion, Completion::TimedOut);
+    assert!(!benchmark.value.resolvers.is_empty());
+    assert_eq!(benchmark.value.recommended, None);
+
+    let dnssec = check_dnssec_cancellable(backend.clone(), 2, &token, past).await;
+    assert_eq!(dnssec.completion, Completion::TimedOut);
+    assert!(dnssec
+        .value
+        .resolvers
+        .iter()
+        .all(|r| r.status == DnssecStatus::Unknown));
+
+    let leak =
+        run_dns_leak_test_cancellable(backend.clone(), LeakTestOptions::default(), &token, past)
+            .await;
+    assert_eq!(leak.completion, Completion::TimedOut);
+    assert_eq!(leak.value.status, LeakStatus::Inconclusive);
+    assert!(leak.value.resolvers.is_empty());
+
+    let family = DnsProviderType::Catalog {
+        family: "Cloudflare".into(),
+        variant: Some("Family".into()),
+    };
+    let filtering = verify_dns_filtering_cancellable(backend.clone(), family, &token, past)
+        .await
+        .unwrap();
+    assert_eq!(filtering.completion, Completion::TimedOut);
+    assert_eq!(filtering.value.status, FilteringStatus::Unknown);
+    assert!(filtering.value.categories.iter().any(|c| c.expected));
+
+    let identity = identify_resolver_cancellable(backend, &token, past).await;
+    assert_eq!(identity.completion, Completion::TimedOut);
+    // The simulated router is the configured server
+    assert!(identity.value.forwarder);
+    assert!(identity
+        .value
+        .evidence
+        .iter()
+        .all(|e| e.method == IdentityMethod::ServerAddress));
+}
+
+#[tokio::test]
+async fn test_dns_benchmark_recommends_fastest_reliable_resolver() {
+    use netok_bridge::{
+        run_dns_benchmark_cancellable, BenchmarkOptions, CancelToken, Completion, DnssecStatus,
+        NetworkBackend, SimulatedBackend,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // Google is fastest but its validator is broken; the rest of the catalog
+    // never answers
+    let backend: Arc<dyn NetworkBackend> = Arc::new(SimulatedBackend {
+        dns_servers: vec!["192.168.1.1".into()],
+        dns_latencies: HashMap::from([
+            ("192.168.1.1".into(), 40.0),
+            ("1.1.1.1".into(), 10.0),
+            ("8.8.8.8".into(), 5.0),
+        ]),
+        resolver_dnssec: HashMap::from([
+            ("1.1.1.1".into(), DnssecStatus::Validating),
+            ("8.8.8.8".into(), DnssecStatus::Broken),
+        ]),
+        ..Default::default()
+    });
+    let options = BenchmarkOptions {
+        uncached_queries: 1,
+        cached_queries: 1,
+        timeout_ms: 500,
+        ..Default::default()
+    };
+    let benchmark =
+        run_dns_benchmark_cancellable(backend, options, &CancelToken::new(), None).await;
+    assert_eq!(benchmark.completion, Completion::Complete);
+
+    let benchmark = benchmark.value;
+    assert_eq!(
+        serde_json::to_value(&benchmark.recommended).unwrap(),
+        "Cloudflare"
+    );
+    assert!(benchmark.resolvers.iter().all(|r| r.queries == 2));
+    let order: Vec<_> = benchmark.resolvers[..3]
+        .iter()
+        .map(|r| (r.server.as_str(), r.median_ms))
+        .collect();
+    assert_eq!(
+        order,
+        [
+            ("1.1.1.1", Some(10.0)),
+            ("192.168.1.1", Some(40.0)),
+            ("8.8.8.8", Some(5.0)),
+        ]
+    );
+    assert_eq!(
+        serde_json::to_value(&benchmark.resolvers[1].provider).unwrap(),
+        "Auto"
+    );
+    assert!(benchmark.resolvers[3..].iter().all(|r| !r.reliable));
+}
+
+#[tokio::test]
+async fn test_dnssec_check_reports_current_resolver_then_catalog() {
+    use netok_bridge::{
+        check_dnssec_cancellable, CancelToken, Completion, DnsCatalog, DnssecStatus,
+        NetworkBackend, SimulatedBackend,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // The router doesn't validate; of the catalog only Cloudflare answers
+    let backend: Arc<dyn NetworkBackend> = Arc::new(SimulatedBackend {
+        dns_servers: vec!["192.168.1.1".into()],
+        dnssec: DnssecStatus::NonValidating,
+        resolver_dnssec: HashMap::from([("1.1.1.1".into(), DnssecStatus::Validating)]),
+        ..Default::default()
+    });
+    let report = check_dnssec_cancellable(backend, 1, &CancelToken::new(), None).await;
+    assert_eq!(report.completion, Completion::Complete);
+
+    let (current, built_in) = report.value.resolvers.split_first().unwrap();
+    assert_eq!(serde_json::to_value(&current.provider).unwrap(), "Auto");
+    assert_eq!(current.server, "192.168.1.1");
+    assert_eq!(current.status, DnssecStatus::NonValidating);
+    assert!(!built_in.is_empty());
+    for check in built_in {
+        let id = serde_json::to_value(&check.provider).unwrap();
+        let entry = DnsCatalog::built_in()
+            .get(id.as_str().unwrap())
+            .expect("built-in resolvers are catalog entries");
+        assert_eq!(check.server, entry.ipv4[0]);
+        let expected = if check.server == "1.1.1.1" {
+            DnssecStatus::Validating
+        } else {
+            DnssecStatus::Unknown
+        };
+        assert_eq!(check.status, expected);
+    }
+    assert!(built_in.iter().any(|c| c.server == "1.1.1.1"));
+}
+
+#[tokio::test]
+async fn test_dns_filtering_reports_blocked_categories() {
+    use netok_bridge::{
+        verify_dns_filtering_cancellable, CancelToken, Completion, DnsFilter, DnsProviderType,
+        FilterResult, FilteringStatus, NetworkBackend, SimulatedBackend,
+    };
+    use std::sync::Arc;
+
+    // The provider and the system resolver sinkhole malware and adult canaries
+    let mut sim = SimulatedBackend::default();
+    for (domain, ip) in [
+        ("malware.testcategory.com", "0.0.0.0"),
+        ("internetbadguys.com", "0.0.0.0"),
+        ("nudity.testcategory.com", "146.112.61.106"),
+        ("exampleadultsite.com", "146.112.61.106"),
+        ("pagead2.googlesyndication.com", "93.184.215.14"),
+        ("ad.doubleclick.net", "93.184.215.14"),
+        ("example.com", "93.184.215.14"),
+    ] {
+        for resolutions in [&mut sim.system_resolutions, &mut sim.direct_resolutions] {
+            resolutions.insert(domain.into(), vec![ip.into()]);
+        }
+    }
+    let backend: Arc<dyn NetworkBackend> = Arc::new(sim);
+
+    let family = DnsProviderType::Catalog {
+        family: "Cloudflare".into(),
+        variant: Some("Family".into()),
+    };
+    let report =
+        verify_dns_filtering_cancellable(backend.clone(), family, &CancelToken::new(), None)
+            .await
+            .unwrap();
+    assert_eq!(report.completion, Completion::Complete);
+
+    let report = report.value;
+    assert_eq!(report.server.as_deref(), Some("1.1.1.3:53"));
+    assert_eq!(report.status, FilteringStatus::Working);
+    assert!(!report.os_bypass);
+    let categories: Vec<_> = report
+        .categories
+        .iter()
+        .map(|c| (c.category, c.expected, c.provider, c.system))
+        .collect();
+    assert_eq!(
+        categories,
+        [
+            (
+                DnsFilter::Malware,
+                true,
+                FilterResult::Blocked,
+                FilterResult::Blocked
+            ),
+            (
+                DnsFilter::Adult,
+                true,
+                FilterResult::Blocked,
+                FilterResult::Blocked
+            ),
+            (
+                DnsFilter::Ads,
+                false,
+                FilterResult::Resolved,
+                FilterResult::Resolved
+            ),
+        ]
+    );
+
+    let unknown = DnsProviderType::Catalog {
+        family: "Nonexistent".into(),
+        variant: None,
+    };
+    assert!(
+        verify_dns_filtering_cancellable(backend, unknown, &CancelToken::new(), None)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_resolver_identity_names_configured_provider() {
+    use netok_bridge::{
+        identify_resolver_cancellable, CancelToken, Completion, IdentityConfidence, IdentityMethod,
+        IpInfoResponse, NetworkBackend, SimulatedBackend,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // Cloudflare's egress resolver shows up in Google's TXT; its CHAOS id
+    // names a data centre, not a provider
+    let backend: Arc<dyn NetworkBackend> = Arc::new(SimulatedBackend {
+        dns_servers: vec!["1.1.1.1".into(), "1.0.0.1".into()],
+        txt_records: HashMap::from([
+            ("id.server".into(), vec!["FRA".into()]),
+            ("o-o.myaddr.l.google.com".into(), vec!["172.70.1.1".into()]),
+        ]),
+        ip_locations: HashMap::from([(
+            "172.70.1.1".into(),
+            IpInfoResponse {
+                ip: Some("172.70.1.1".into()),
+                city: None,
+                country: None,
+                org: Some("AS13335 Cloudflare, Inc.".into()),
+            },
+        )]),
+        ..Default::default()
+    });
+    let identity = identify_resolver_cancellable(backend, &CancelToken::new(), None).await;
+    assert_eq!(identity.completion, Completion::Complete);
+
+    let identity = identity.value;
+    assert_eq!(identity.family.as_deref(), Some("Cloudflare"));
+    assert_eq!(
+        serde_json::to_value(&identity.provider).unwrap(),
+        "Cloudflare"
+    );
+    assert_eq!(identity.confidence, Some(IdentityConfidence::High));
+    assert!(!identity.forwarder);
+    assert_eq!(identity.egress_ips, ["172.70.1.1"]);
+    let evidence: Vec<_> = identity
+        .evidence
+        .iter()
+        .map(|e| {
+            (
+                e.method,
+                e.detail.as_str(),
+                e.family.as_deref(),
+                e.confidence,
+            )
+        })
+        .collect();
+    assert_eq!(
+        evidence,
+        [
+            (
+                IdentityMethod::ServerAddress,
+                "1.1.1.1",
+                Some("Cloudflare"),
+                IdentityConfidence::High
+            ),
+            (
+                IdentityMethod::Chaos,
+                "id.server FRA",
+                None,
+                IdentityConfidence::Low
+            ),
+            (
+                IdentityMethod::EgressResolver,
+                "172.70.1.1 (AS13335 Cloudflare, Inc.)",
+                Some("Cloudflare"),
+                IdentityConfidence::Medium
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_dns_leak_test_flags_isp_resolver() {
+    use netok_bridge::{
+        run_dns_leak_test_cancellable, CancelToken, Completion, IpInfoResponse, LeakStatus,
+        LeakTestOptions, NetworkBackend, ResolverOwner, SimulatedBackend,
+    };
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // Every name under the zone reaches the ISP's resolver
+    let resolvers = vec!["192.0.2.53".to_string()];
+    let backend: Arc<dyn NetworkBackend> = Arc::new(SimulatedBackend {
+        system_resolutions: HashMap::from([("*.whoami.test".into(), resolvers.clone())]),
+        direct_resolutions: HashMap::from([("*.whoami.test".into(), resolvers)]),
+        ip_locations: HashMap::from([(
+            "192.0.2.53".into(),
+            IpInfoResponse {
+                ip: Some("192.0.2.53".into()),
+                city: None,
+                country: Some("DE".into()),
+                org: Some("AS64500 Example ISP".into()),
+            },
+        )]),
+        ..Default::default()
+    });
+    let options = LeakTestOptions {
+        queries: 3,
+        isp_ip: Some("192.0.2.53".into()),
+        zone: Some("whoami.test".into()),
+        server: None,
+    };
+    let report = run_dns_leak_test_cancellable(backend, options, &CancelToken::new(), None).await;
+    assert_eq!(report.completion, Completion::Complete);
+
+    let report = report.value;
+    assert_eq!(report.status, LeakStatus::Leak);
+    assert_eq!((report.queries, report.answered), (3, 3));
+    assert_eq!(report.resolvers.len(), 1);
+    assert_eq!(report.resolvers[0].ip, "192.0.2.53");
+    assert_eq!(report.resolvers[0].owner, ResolverOwner::Isp);
+    assert_eq!(report.resolvers[0].queries, 3);
+    assert_eq!(report.resolvers[0].asn, Some(64500));
+    assert_eq!(report.resolvers[0].country.as_deref(), Some("DE"));
+}
+
+// ============================================================================
+// DNS Preset Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_dns_presets_round_trip() {
+    use netok_bridge::{
+        delete_dns_preset, list_dns_presets, save_dns_preset, ErrorKind, PresetStore,
+    };
+    use std::sync::Arc;
+
+    let path = std::env::temp_dir().join(format!(
+        "netok_bridge_presets_test_{}.json",
+        std::process::id()
+    ));
+    let store = Arc::new(PresetStore::open(&path).unwrap());
+
+    let family = DnsProviderType::catalog("AdGuard", Some("Family")).unwrap();
+    save_dns_preset(store.clone(), "Kids".into(), family.clone())
+        .await
+        .unwrap();
+    let presets = list_dns_presets(store.clone()).await.unwrap();
+    assert_eq!(presets.len(), 1);
+    assert_eq!(presets[0].name, "Kids");
+    assert_eq!(presets[0].provider, family);
+
+    let err = save_dns_preset(store.clone(), "".into(), DnsProviderType::Auto)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::InvalidInput);
+
+    assert!(delete_dns_preset(store.clone(), "kids".into())
+        .await
+        .unwrap());
+    assert!(list_dns_presets(store).await.unwrap().is_empty());
+    let _ = std::fs::remove_file(&path);
+}
+
+// ============================================================================
+// Probe Capture Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_capture_round_trip() {
+    use netok_bridge::{record_capture, replay_capture, simulated_backend, DiagnosticScenario};
+
+    let path = std::env::temp_dir().join(format!(
+        "netok_bridge_capture_test_{}.json",
+        std::process::id()
+    ));
+    let recorded = record_capture(
+        simulated_backend(DiagnosticScenario::DnsFailure),
+        path.clone(),
+    )
+    .await
+    .expect("Recording should succeed");
+    let replayed = replay_capture(path.clone())
+        .await
+        .expect("Replay should succeed");
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(replayed.snapshot.overall, recorded.snapshot.overall);
+    assert_eq!(replayed.snapshot.summary_key, recorded.snapshot.summary_key);
+    assert_eq!(
+        replayed.snapshot.network.dns_servers,
+        recorded.snapshot.network.dns_servers
+    );
+    assert_eq!(
+        replayed.security.overall_status,
+        recorded.security.overall_status
+    );
+}
+
+#[tokio::test]
+async fn test_replay_missing_capture_is_not_found() {
+    use netok_bridge::{replay_capture, ErrorKind};
+
+    let err = replay_capture(std::env::temp_dir().join("netok_no_such_capture.json"))
+        .await
+        .expect_err("Missing file should fail");
+    assert_eq!(err.kind, ErrorKind::NotFound);
+}
+
+// ============================================================================
+// API Server Tests
+// ============================================================================
+
+#[cfg(feature = "server")]
+mod api_server {
+    use std::sync::Arc;
+
+    use netok_bridge::server::{ApiServer, ServerConfig};
+    use netok_bridge::{simulated_backend, DiagnosticScenario, HistoryStore, RetentionPolicy};
+
+    const TOKEN: &str = "test-token";
+
+    /// Start a simulated server on a free port; returns its base URL.
+    async fn start(history: Option<Arc<HistoryStore>>) -> String {
+        let config = ServerConfig {
+            bind: "127.0.0.1:0".parse().unwrap(),
+            backend: simulated_backend(DiagnosticScenario::AllGood),
+            history,
+            ..ServerConfig::new(TOKEN)
+        };
+        let server = ApiServer::bind(config).await.expect("Server should bind");
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(server.run(std::future::pending()));
+        format!("http://{}/api/v1", addr)
+    }
+
+    #[tokio::test]
+    async fn test_health_needs_no_token() {
+        let base = start(None).await;
+        let response = reqwest::get(format!("{}/health", base)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_endpoints_require_token() {
+        let base = start(None).await;
+        let client = reqwest::Client::new();
+
+        let missing = client
+            .get(format!("{}/diagnostics", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 401);
+        let body: serde_json::Value = missing.json().await.unwrap();
+        assert_eq!(body["error"]["kind"], "permission_denied");
+
+        let wrong = client
+            .get(format!("{}/diagnostics", base))
+            .bearer_auth("nope")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), 401);
+
+        let ok = client
+            .get(format!("{}/diagnostics", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(ok.status(), 200);
+        let snapshot: netok_bridge::Snapshot = ok.json().await.unwrap();
+        assert_eq!(snapshot.computer.hostname.as_deref(), Some("DEMO-LAPTOP"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_sends_progress_then_devices() {
+        let base = start(None).await;
+        let body = reqwest::get(format!("{}/scan/stream?token={}", base, TOKEN))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let progress = body.find("event: progress").expect("progress event");
+        let done = body.find("event: done").expect("done event");
+        assert!(progress < done);
+        assert!(body.contains("192.168.1.1"));
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_stream_sends_each_node() {
+        let base = start(None).await;
+        let body = reqwest::get(format!("{}/diagnostics/stream?token={}", base, TOKEN))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body.matches("event: node").count(), 4);
+        assert!(body.trim_end().ends_with("data: {}"));
+    }
+
+    #[tokio::test]
+    async fn test_dns_test_rejects_invalid_address() {
+        let base = start(None).await;
+        let response = reqwest::Client::new()
+            .get(format!("{}/dns/test?server=not-an-ip", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["key"], "errors.invalid_input");
+    }
+
+    #[tokio::test]
+    async fn test_history_records_runs() {
+        let client = reqwest::Client::new();
+
+        let base = start(None).await;
+        let disabled = client
+            .get(format!("{}/history", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(disabled.status(), 404);
+
+        let path = std::env::temp_dir().join(format!(
+            "netok_bridge_api_history_{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let store = Arc::new(HistoryStore::open(&path, RetentionPolicy::default()).unwrap());
+        let base = start(Some(store)).await;
+        for endpoint in ["diagnostics", "security"] {
+            client
+                .get(format!("{}/{}", base, endpoint))
+                .bearer_auth(TOKEN)
+                .send()
+                .await
+                .unwrap();
+        }
+        let records: Vec<serde_json::Value> = client
+            .get(format!("{}/history?kind=security", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["kind"], "security");
+    }
+
+    #[tokio::test]
+    async fn test_metrics_served_after_first_collection() {
+        let base = start(None).await;
+        let client = reqwest::Client::new();
+
+        let mut response = None;
+        for _ in 0..50 {
+            let r = client
+                .get(format!("{}/metrics", base))
+                .bearer_auth(TOKEN)
+                .send()
+                .await
+                .unwrap();
+            if r.status() != 503 {
+                response = Some(r);
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        let response = response.expect("Metrics should be collected within 5 s");
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            response.headers()["content-type"],
+            netok_bridge::OPENMETRICS_CONTENT_TYPE
+        );
+        let text = response.text().await.unwrap();
+        assert!(text.contains("netok_node_status{node=\"computer\"} 0"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_can_be_disabled() {
+        let config = ServerConfig {
+            bind: "127.0.0.1:0".parse().unwrap(),
+            backend: simulated_backend(DiagnosticScenario::AllGood),
+            metrics_interval: None,
+            ..ServerConfig::new(TOKEN)
+        };
+        let server = ApiServer::bind(config).await.unwrap();
+        let base = format!("http://{}/api/v1", server.local_addr().unwrap());
+        tokio::spawn(server.run(std::future::pending()));
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/metrics", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+}
@@ -0,0 +1,26 @@
+//! Text output in the UI's languages, see [`I18n`].
+
+pub use netok_core::I18n;
+
+/// Language from `--lang`, then `LC_ALL`/`LC_MESSAGES`/`LANG`, then English.
+pub fn detect_language(explicit: Option<&str>) -> String {
+    explicit
+        .map(str::to_string)
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|v| !v.is_empty() && v != "C" && v != "POSIX")
+        })
+        .unwrap_or_else(|| "en".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_language_wins() {
+        assert_eq!(detect_language(Some("ru")), "ru");
+    }
+}
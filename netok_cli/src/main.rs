@@ -0,0 +1,937 @@
+//! `netok` — the diagnostics without the desktop app.
+//!
+//! Runs the same checks as the app (through `netok_core`), for scripts, SSH
+//! sessions and servers. Text output uses the app's translations; `--json`
+//! prints the core's serde types unchanged.
+//!
+//! Exit codes follow the monitoring-plugin convention:
+//! 0 OK, 1 warning, 2 failure, 3 unknown or error, 64 bad usage.
+
+mod i18n;
+mod render;
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use netok_bridge::{server, ErrorInfo};
+use netok_core::{
+    BenchmarkOptions, DiagnosticScenario, DiagnosticSeverity, DnsCatalog, DnsEndpoint, DnsProvider,
+    DnsTransport, DnssecStatus, ErrorKind, FilteringStatus, LeakStatus, LeakTestOptions,
+    NetworkBackend, ReplayBackend, SecurityStatus, Settings, SimulatedBackend, Status,
+    SystemBackend,
+};
+use serde_json::{json, Value};
+
+use crate::i18n::I18n;
+
+// ==================== Arguments ====================
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "netok",
+    version,
+    about = "Network diagnostics from the command line"
+)]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args, Debug)]
+struct GlobalArgs {
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    /// Language for text output (en, de, ru, ...); defaults to $LANG
+    #[arg(long, global = true, value_name = "CODE")]
+    lang: Option<String>,
+    /// Simulate a network scenario (e.g. dns_failure) instead of probing
+    #[arg(long, global = true, value_name = "SCENARIO", value_parser = parse_scenario)]
+    demo: Option<DiagnosticScenario>,
+    /// Answer probes from a capture file recorded by the desktop app
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "demo")]
+    replay: Option<PathBuf>,
+    /// Settings JSON file (probe targets, DNS servers, ...); fields left out
+    /// keep their defaults
+    #[arg(long, global = true, value_name = "FILE", env = "NETOK_SETTINGS")]
+    settings: Option<PathBuf>,
+    /// More log output on stderr (-v debug, -vv trace); RUST_LOG overrides
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check computer, network, router and internet in order
+    Diagnose,
+    /// List devices on the local network
+    Scan,
+    /// Show, change and test DNS servers
+    #[command(subcommand)]
+    Dns(DnsCommand),
+    /// Run the Wi-Fi security checks
+    Security,
+    /// Validate VPN keys and generate sing-box configs
+    #[command(subcommand)]
+    Vpn(VpnCommand),
+    /// Look up the location of an IP address (your public IP by default)
+    Geo { ip: Option<String> },
+    /// Run every check once and print OpenMetrics text
+    Metrics,
+    /// Serve the checks as a JSON API until Ctrl+C
+    Serve(ServeArgs),
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
+    /// Address to listen on; use 0.0.0.0 to allow other devices
+    #[arg(long, default_value_t = SocketAddr::from((Ipv4Addr::LOCALHOST, server::DEFAULT_PORT)))]
+    bind: SocketAddr,
+    /// API token; a random one is generated and printed when not given
+    #[arg(long, env = "NETOK_API_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Record runs to this history file and serve it at /api/v1/history
+    #[arg(long, value_name = "FILE")]
+    history: Option<PathBuf>,
+    /// Seconds between metrics collections for /api/v1/metrics; 0 disables
+    #[arg(long, value_name = "SECS", default_value_t = server::DEFAULT_METRICS_INTERVAL.as_secs())]
+    metrics_interval: u64,
+}
+
+#[derive(Subcommand, Debug)]
+enum DnsCommand {
+    /// Show the configured DNS servers and their provider
+    Get,
+    /// List the built-in providers
+    Providers,
+    /// Switch the DNS provider (Windows, needs administrator rights)
+    Set {
+        /// Provider name (auto, cloudflare, google, quad9-recommended, ...) or "custom"
+        provider: String,
+        /// Servers for "custom": IPv4 and/or IPv6 addresses, primary first, plus
+        /// optionally a DoH URL (https://...) and a DoT hostname (tls://...)
+        servers: Vec<String>,
+    },
+    /// Check that a DNS server answers and measure its latency
+    Test {
+        /// IP address, DoH URL (https://...) or DoT hostname (tls://...)
+        server: String,
+        /// Seconds to wait for an answer
+        #[arg(long, default_value_t = 2)]
+        timeout: u64,
+    },
+    /// Check which transports (UDP, DoH, DoT) of a provider work on this network
+    Transports {
+        /// Provider name, or "custom" followed by its servers as for `set`
+        provider: String,
+        servers: Vec<String>,
+        /// Seconds to wait for each answer
+        #[arg(long, default_value_t = 2)]
+        timeout: u64,
+    },
+    /// Benchmark the current resolver and every built-in provider, and
+    /// recommend the fastest reliable one
+    Bench {
+        /// Queries per resolver, half for random (uncached) names and half
+        /// for popular (cached) ones
+        #[arg(long, default_value_t = 20)]
+        queries: usize,
+        /// Seconds to wait for each answer
+        #[arg(long, default_value_t = 2)]
+        timeout: u64,
+        /// Transport to reach the resolvers over: udp, doh or dot
+        #[arg(long, default_value = "udp", value_parser = parse_transport)]
+        transport: DnsTransport,
+        /// Only recommend a resolver that validates DNSSEC
+        #[arg(long)]
+        require_dnssec: bool,
+    },
+    /// Check whether the current resolver and every built-in provider
+    /// validate DNSSEC
+    Dnssec {
+        /// Seconds to wait for each answer
+        #[arg(long, default_value_t = 2)]
+        timeout: u64,
+    },
+    /// List the resolvers that actually handle DNS queries and flag any on
+    /// the ISP's network (run with the VPN connected)
+    Leak {
+        /// Unique names to resolve
+        #[arg(long, default_value_t = 10)]
+        queries: usize,
+        /// Public IP of this connection without the VPN
+        #[arg(long)]
+        isp_ip: Option<String>,
+        /// Zone whose authoritative server answers with the asking
+        /// resolver's address (default from settings: whoami.akamai.net)
+        #[arg(long)]
+        zone: Option<String>,
+        /// Query this server (ip:port) instead of the system resolver
+        #[arg(long)]
+        server: Option<String>,
+    },
+    /// Identify the provider that really answers DNS queries, even behind a
+    /// router or Pi-hole forwarding to it
+    Identify,
+    /// Check that a filtering provider blocks the categories it promises to,
+    /// and that lookups on this computer actually go through it
+    Filtering {
+        /// Provider name, or "custom" followed by its servers as for `set`;
+        /// defaults to the provider configured now
+        #[arg(default_value = "auto")]
+        provider: String,
+        servers: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum VpnCommand {
+    /// Check that a VPN key parses and its server accepts connections
+    Validate {
+        /// vless://, vmess://, ss://, trojan:// or WireGuard key; "-" reads stdin
+        uri: String,
+    },
+    /// Print the sing-box config for a VPN key
+    Config {
+        /// vless://, vmess://, ss://, trojan:// or WireGuard key; "-" reads stdin
+        uri: String,
+    },
+}
+
+fn parse_scenario(name: &str) -> Result<DiagnosticScenario, String> {
+    serde_json::from_value(Value::String(name.replace('-', "_"))).map_err(|_| {
+        let names: Vec<String> = DiagnosticScenario::all()
+            .iter()
+            .filter_map(|s| serde_json::to_value(s).ok())
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect();
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+fn parse_transport(name: &str) -> Result<DnsTransport, String> {
+    serde_json::from_value(Value::String(name.to_lowercase()))
+        .map_err(|_| "expected one of: udp, doh, dot".to_string())
+}
+
+// ==================== Exit Codes ====================
+
+/// Process exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Exit {
+    Ok = 0,
+    Warn = 1,
+    Fail = 2,
+    Unknown = 3,
+    Usage = 64,
+}
+
+impl From<Status> for Exit {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Ok => Exit::Ok,
+            Status::Warn => Exit::Warn,
+            Status::Fail => Exit::Fail,
+            Status::Unknown => Exit::Unknown,
+        }
+    }
+}
+
+impl From<DiagnosticSeverity> for Exit {
+    fn from(severity: DiagnosticSeverity) -> Self {
+        match severity {
+            DiagnosticSeverity::Success => Exit::Ok,
+            DiagnosticSeverity::Warning => Exit::Warn,
+            DiagnosticSeverity::Error => Exit::Fail,
+        }
+    }
+}
+
+impl From<SecurityStatus> for Exit {
+    fn from(status: SecurityStatus) -> Self {
+        match status {
+            SecurityStatus::Safe => Exit::Ok,
+            SecurityStatus::Warning => Exit::Warn,
+            SecurityStatus::Danger => Exit::Fail,
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+/// Worst exit wins; failures outrank unknowns.
+fn worst(exits: impl IntoIterator<Item = Exit>) -> Exit {
+    exits
+        .into_iter()
+        .max_by_key(|exit| match exit {
+            Exit::Fail => 3,
+            Exit::Unknown => 2,
+            Exit::Warn => 1,
+            _ => 0,
+        })
+        .unwrap_or(Exit::Unknown)
+}
+
+// ==================== Commands ====================
+
+/// What a command produced, in both output forms.
+struct Outcome {
+    json: Value,
+    text: String,
+    exit: Exit,
+}
+
+struct Context {
+    t: I18n,
+    settings: Settings,
+    backend: Arc<dyn NetworkBackend>,
+}
+
+impl Context {
+    fn new(global: &GlobalArgs) -> Result<Self, ErrorInfo> {
+        let backend: Arc<dyn NetworkBackend> = match (&global.demo, &global.replay) {
+            (Some(scenario), _) => Arc::new(SimulatedBackend::scenario(*scenario)),
+            (None, Some(path)) => Arc::new(ReplayBackend::new(&netok_core::load_capture(path)?)),
+            (None, None) => Arc::new(SystemBackend),
+        };
+        let settings = match &global.settings {
+            Some(path) => netok_core::load_settings(path)?,
+            None => netok_core::get_default_settings(),
+        };
+        Ok(Self {
+            t: I18n::new(&i18n::detect_language(global.lang.as_deref())),
+            settings,
+            backend,
+        })
+    }
+}
+
+fn to_json(value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+fn execute(command: &Command, ctx: &Context) -> Result<Outcome, ErrorInfo> {
+    match command {
+        Command::Diagnose => {
+            let snapshot =
+                netok_core::run_diagnostics_with_backend(ctx.backend.as_ref(), &ctx.settings);
+            Ok(Outcome {
+                json: to_json(&snapshot),
+                text: render::diagnostics(&ctx.t, &snapshot),
+                // The verdict counts too: a weak signal or failed DNS lookup
+                // is a problem even when every node reports OK or Warn.
+                exit: worst(
+                    snapshot
+                        .nodes
+                        .iter()
+                        .map(|n| Exit::from(n.status))
+                        .chain([netok_core::diagnose_scenario(&snapshot).severity.into()]),
+                ),
+            })
+        }
+        Command::Scan => {
+            let devices = netok_core::scan_network_devices_with_backend(ctx.backend.as_ref(), None);
+            Ok(Outcome {
+                json: to_json(&devices),
+                text: render::devices(&ctx.t, &devices),
+                exit: Exit::Ok,
+            })
+        }
+        Command::Security => {
+            let report = netok_core::check_wifi_security_with_backend(
+                ctx.backend.as_ref(),
+                &ctx.settings.probe_targets,
+            );
+            Ok(Outcome {
+                json: to_json(&report),
+                text: render::security(&ctx.t, &report),
+                exit: report.overall_status.into(),
+            })
+        }
+        Command::Dns(command) => dns(command, ctx),
+        Command::Vpn(command) => vpn(command, ctx),
+        Command::Metrics => {
+            let sample =
+                netok_core::collect_metrics_with_backend(ctx.backend.as_ref(), &ctx.settings);
+            Ok(Outcome {
+                json: to_json(&sample),
+                // Already newline-terminated by `# EOF`
+                text: netok_core::render_openmetrics(&sample)
+                    .trim_end()
+                    .to_string(),
+                exit: Exit::Ok,
+            })
+        }
+        Command::Serve(args) => serve(args, ctx),
+        Command::Geo { ip } => {
+            let info = netok_core::lookup_ip_location_with_targets(
+                ip.as_deref().unwrap_or(""),
+                &ctx.settings.probe_targets,
+            )?;
+            Ok(Outcome {
+                json: to_json(&info),
+                text: render::geo(&ctx.t, &info),
+                exit: Exit::Ok,
+            })
+        }
+    }
+}
+
+/// Provider from a case- and separator-insensitive name, e.g. `quad9-recommended`.
+fn parse_provider(name: &str, servers: &[String]) -> Result<DnsProvider, ErrorInfo> {
+    let wanted: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    if wanted == "auto" {
+        return Ok(DnsProvider::Auto);
+    }
+    if wanted == "custom" {
+        let mut addresses = Vec::new();
+        let (mut doh_template, mut dot_hostname) = (None, None);
+        for server in servers {
+            match DnsEndpoint::parse(server)? {
+                DnsEndpoint::Udp(_) => addresses.push(server),
+                DnsEndpoint::Doh(_) => doh_template = Some(server.to_string()),
+                DnsEndpoint::Dot { hostname, .. } => dot_hostname = Some(hostname),
+            }
+        }
+        let (v6, v4): (Vec<&String>, Vec<&String>) =
+            addresses.into_iter().partition(|s| s.contains(':'));
+        let Some(primary) = v4.first() else {
+            return Err(ErrorInfo::new(
+                ErrorKind::InvalidInput,
+                "custom DNS needs at least one IPv4 address",
+            ));
+        };
+        return Ok(DnsProvider::Custom(
+            primary.to_string(),
+            v4.get(1).map(|s| s.to_string()).unwrap_or_default(),
+            v6.first().map(|s| s.to_string()),
+            v6.get(1).map(|s| s.to_string()),
+            doh_template,
+            dot_hostname,
+        ));
+    }
+
+    DnsCatalog::built_in()
+        .providers
+        .iter()
+        .find(|entry| entry.id.to_lowercase() == wanted)
+        .map(|entry| DnsProvider::Catalog(entry.id.clone()))
+        .ok_or_else(|| {
+            ErrorInfo::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown DNS provider: {}", name),
+            )
+        })
+}
+
+fn dns(command: &DnsCommand, ctx: &Context) -> Result<Outcome, ErrorInfo> {
+    match command {
+        DnsCommand::Get => {
+            let servers = ctx.backend.dns_servers()?;
+            let provider = netok_core::detect_dns_provider(&servers);
+            Ok(Outcome {
+                json: json!({ "servers": servers, "provider": provider }),
+                text: render::dns_servers(&ctx.t, &servers, &provider),
+                exit: Exit::Ok,
+            })
+        }
+        DnsCommand::Providers => {
+            let catalog = DnsCatalog::built_in();
+            Ok(Outcome {
+                json: json!(catalog),
+                text: render::dns_catalog(catalog),
+                exit: Exit::Ok,
+            })
+        }
+        DnsCommand::Set { provider, servers } => {
+            let provider = parse_provider(provider, servers)?;
+            netok_core::set_dns(provider.clone())?;
+            Ok(Outcome {
+                json: json!({ "provider": provider }),
+                text: format!(
+                    "{}: {}",
+                    ctx.t.t("dns_providers.applied"),
+                    render::provider_name(&ctx.t, &provider)
+                ),
+                exit: Exit::Ok,
+            })
+        }
+        DnsCommand::Test { server, timeout } => {
+            let latency_ms = netok_core::ping_dns_server(server, *timeout)?;
+            Ok(Outcome {
+                json: json!({
+                    "server": server,
+                    "reachable": latency_ms.is_some(),
+                    "latency_ms": latency_ms,
+                }),
+                text: format!("{}: {}", server, render::dns_latency(&ctx.t, latency_ms)),
+                exit: if latency_ms.is_some() {
+                    Exit::Ok
+                } else {
+                    Exit::Fail
+                },
+            })
+        }
+        DnsCommand::Transports {
+            provider,
+            servers,
+            timeout,
+        } => {
+            let provider = parse_provider(provider, servers)?;
+            let statuses = netok_core::check_dns_transports(&provider, *timeout);
+            let text = statuses
+                .iter()
+                .map(|status| {
+                    format!(
+                        "{:<4}  {:<45}  {}",
+                        render::transport_name(status.transport),
+                        status.endpoint,
+                        render::dns_latency(&ctx.t, status.latency_ms)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            let any_works = statuses.iter().any(|status| status.works);
+            Ok(Outcome {
+                json: json!({ "provider": provider, "transports": statuses }),
+                text,
+                exit: if any_works { Exit::Ok } else { Exit::Fail },
+            })
+        }
+        DnsCommand::Bench {
+            queries,
+            timeout,
+            transport,
+            require_dnssec,
+        } => {
+            let options = BenchmarkOptions {
+                transport: *transport,
+                uncached_queries: queries.div_ceil(2),
+                cached_queries: queries / 2,
+                timeout_ms: timeout * 1000,
+                require_dnssec: *require_dnssec,
+            };
+            let benchmark = netok_core::run_dns_benchmark(ctx.backend.as_ref(), &options);
+            let any_answered = benchmark
+                .resolvers
+                .iter()
+                .any(|resolver| resolver.median_ms.is_some());
+            Ok(Outcome {
+                json: json!(benchmark),
+                text: render::dns_benchmark(&ctx.t, &benchmark),
+                exit: if any_answered { Exit::Ok } else { Exit::Fail },
+            })
+        }
+        DnsCommand::Dnssec { timeout } => {
+            let report = netok_core::check_dnssec(ctx.backend.as_ref(), *timeout);
+            // The verdict is about the resolver in use
+            let current = report
+                .resolvers
+                .iter()
+                .find(|check| check.provider == DnsProvider::Auto)
+                .map_or(DnssecStatus::Unknown, |check| check.status);
+            let exit = match current {
+                DnssecStatus::Validating => Exit::Ok,
+                DnssecStatus::NonValidating => Exit::Warn,
+                DnssecStatus::Broken => Exit::Fail,
+                DnssecStatus::Unknown => Exit::Unknown,
+            };
+            Ok(Outcome {
+                json: json!(report),
+                text: render::dnssec_report(&ctx.t, &report),
+                exit,
+            })
+        }
+        DnsCommand::Leak {
+            queries,
+            isp_ip,
+            zone,
+            server,
+        } => {
+            let options = LeakTestOptions {
+                queries: *queries,
+                isp_ip: isp_ip.clone(),
+                zone: zone.clone(),
+                server: server.clone(),
+            };
+            let report = netok_core::run_dns_leak_test(
+                ctx.backend.as_ref(),
+                &ctx.settings.probe_targets,
+                &options,
+            );
+            Ok(Outcome {
+                json: json!(report),
+                text: render::dns_leak(&ctx.t, &report),
+                exit: match report.status {
+                    LeakStatus::NoLeak => Exit::Ok,
+                    LeakStatus::Leak => Exit::Fail,
+                    LeakStatus::Inconclusive => Exit::Unknown,
+                },
+            })
+        }
+        DnsCommand::Identify => {
+            let identity =
+                netok_core::identify_resolver(ctx.backend.as_ref(), &ctx.settings.probe_targets);
+            Ok(Outcome {
+                json: json!(identity),
+                text: render::resolver_identity(&ctx.t, &identity),
+                exit: if identity.family.is_some() {
+                    Exit::Ok
+                } else {
+                    Exit::Unknown
+                },
+            })
+        }
+        DnsCommand::Filtering { provider, servers } => {
+            let provider = parse_provider(provider, servers)?;
+            let report = netok_core::verify_dns_filtering(
+                ctx.backend.as_ref(),
+                &ctx.settings.probe_targets,
+                &provider,
+            );
+            let exit = match report.status {
+                FilteringStatus::Working if report.browser_bypass => Exit::Warn,
+                FilteringStatus::Working => Exit::Ok,
+                FilteringStatus::NotFiltering | FilteringStatus::Bypassed => Exit::Fail,
+                FilteringStatus::Unknown => Exit::Unknown,
+            };
+            Ok(Outcome {
+                json: json!(report),
+                text: render::dns_filtering(&ctx.t, &report),
+                exit,
+            })
+        }
+    }
+}
+
+/// Run the API server until Ctrl+C. Prints nothing on stdout.
+fn serve(args: &ServeArgs, ctx: &Context) -> Result<Outcome, ErrorInfo> {
+    let (token, generated) = match &args.token {
+        Some(token) => (token.clone(), false),
+        None => (server::generate_token()?, true),
+    };
+    let history = args
+        .history
+        .as_ref()
+        .map(|path| netok_core::HistoryStore::open(path, netok_core::RetentionPolicy::default()))
+        .transpose()?
+        .map(Arc::new);
+    // The API runs the checks through the bridge, which reads its own copy
+    netok_bridge::apply_settings(ctx.settings.clone());
+    let config = server::ServerConfig {
+        bind: args.bind,
+        token: token.clone(),
+        backend: ctx.backend.clone(),
+        history,
+        metrics_interval: (args.metrics_interval > 0)
+            .then(|| std::time::Duration::from_secs(args.metrics_interval)),
+    };
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(ErrorInfo::internal)?;
+    runtime.block_on(async {
+        let server = server::ApiServer::bind(config).await?;
+        eprintln!("Listening on http://{}/api/v1", server.local_addr()?);
+        if generated {
+            eprintln!("API token: {}", token);
+        }
+        server
+            .run(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await
+    })?;
+
+    Ok(Outcome {
+        json: Value::Null,
+        text: String::new(),
+        exit: Exit::Ok,
+    })
+}
+
+/// A VPN key from the argument, or from stdin for `-` (keeps it out of `ps`).
+fn read_uri(uri: &str) -> Result<String, ErrorInfo> {
+    if uri != "-" {
+        return Ok(uri.to_string());
+    }
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .map_err(|e| ErrorInfo::new(ErrorKind::Io, format!("Failed to read stdin: {}", e)))?;
+    Ok(text.trim().to_string())
+}
+
+fn vpn(command: &VpnCommand, ctx: &Context) -> Result<Outcome, ErrorInfo> {
+    match command {
+        VpnCommand::Validate { uri } => {
+            let uri = read_uri(uri)?;
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .map_err(ErrorInfo::internal)?;
+            let result = runtime.block_on(netok_bridge::validate_vpn_key(uri))?;
+
+            let (text, exit) = match &result.error_info {
+                Some(info) => (
+                    format!("{}: {}", ctx.t.t(&info.key), info.message),
+                    Exit::Fail,
+                ),
+                None if result.reachable => (
+                    format!("{} {}:{}", result.protocol, result.server, result.port),
+                    Exit::Ok,
+                ),
+                None => (
+                    format!(
+                        "{} {}:{} — {}",
+                        result.protocol,
+                        result.server,
+                        result.port,
+                        ctx.t.t("diagnostics.status_unreachable")
+                    ),
+                    Exit::Warn,
+                ),
+            };
+            Ok(Outcome {
+                json: to_json(&result),
+                text,
+                exit,
+            })
+        }
+        VpnCommand::Config { uri } => {
+            let config = netok_bridge::generate_vpn_config(&read_uri(uri)?)?;
+            Ok(Outcome {
+                json: serde_json::from_str(&config).unwrap_or(Value::Null),
+                text: config,
+                exit: Exit::Ok,
+            })
+        }
+    }
+}
+
+// ==================== Entry Point ====================
+
+fn init_logging(verbose: u8) {
+    use tracing_subscriber::EnvFilter;
+
+    let default = match verbose {
+        0 => "warn",
+        1 => "info,netok_core=debug,netok_bridge=debug,netok=debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// Write to stdout, ignoring a closed pipe (`netok scan | head`).
+fn print_stdout(text: &str) {
+    let _ = writeln!(std::io::stdout().lock(), "{}", text);
+}
+
+fn print_error(error: &ErrorInfo, json: bool, t: &I18n) {
+    if json {
+        print_stdout(&json!({ "error": error }).to_string());
+    } else {
+        eprintln!("{}: {}", t.t(&error.key), error.message);
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() {
+                Exit::Usage.into()
+            } else {
+                ExitCode::SUCCESS // --help, --version
+            };
+        }
+    };
+    init_logging(cli.global.verbose);
+
+    let ctx = match Context::new(&cli.global) {
+        Ok(ctx) => ctx,
+        Err(e) => {
+            let t = I18n::new(&i18n::detect_language(cli.global.lang.as_deref()));
+            print_error(&e, cli.global.json, &t);
+            return Exit::Unknown.into();
+        }
+    };
+
+    match execute(&cli.command, &ctx) {
+        Ok(outcome) => {
+            if cli.global.json && !outcome.json.is_null() {
+                print_stdout(&serde_json::to_string_pretty(&outcome.json).unwrap_or_default());
+            } else if !cli.global.json && !outcome.text.is_empty() {
+                print_stdout(&outcome.text);
+            }
+            outcome.exit.into()
+        }
+        Err(e) => {
+            print_error(&e, cli.global.json, &ctx.t);
+            Exit::Unknown.into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn context(args: &[&str]) -> (Command, Context) {
+        let cli = Cli::try_parse_from([&["netok"], args].concat()).unwrap();
+        let ctx = Context::new(&cli.global).unwrap();
+        (cli.command, ctx)
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_exit_code_follows_worst_status() {
+        let worst_status = |statuses: &[Status]| worst(statuses.iter().map(|s| Exit::from(*s)));
+        assert_eq!(worst_status(&[Status::Ok, Status::Ok]), Exit::Ok);
+        assert_eq!(worst_status(&[Status::Ok, Status::Warn]), Exit::Warn);
+        assert_eq!(
+            worst_status(&[Status::Unknown, Status::Warn]),
+            Exit::Unknown
+        );
+        assert_eq!(worst_status(&[Status::Unknown, Status::Fail]), Exit::Fail);
+        assert_eq!(worst_status(&[]), Exit::Unknown);
+    }
+
+    #[test]
+    fn test_demo_diagnose_exit_codes() {
+        for (scenario, exit) in [
+            ("all_good", Exit::Ok),
+            ("dns-failure", Exit::Fail),
+            ("weak_signal", Exit::Warn),
+        ] {
+            let (command, ctx) = context(&["--demo", scenario, "diagnose"]);
+            let outcome = execute(&command, &ctx).unwrap();
+            assert_eq!(outcome.exit, exit, "scenario {}", scenario);
+            assert!(outcome.json["nodes"].is_array());
+        }
+    }
+
+    #[test]
+    fn test_unknown_scenario_is_usage_error() {
+        let err = Cli::try_parse_from(["netok", "--demo", "meteor", "diagnose"]).unwrap_err();
+        assert!(err.to_string().contains("dns_failure"));
+    }
+
+    #[test]
+    fn test_metrics_text() {
+        let (command, ctx) = context(&["--demo", "all_good", "metrics"]);
+        let outcome = execute(&command, &ctx).unwrap();
+        assert!(outcome.text.ends_with("# EOF"));
+        assert_eq!(outcome.json["security"]["overall_status"], "safe");
+    }
+
+    #[test]
+    fn test_parse_provider() {
+        assert_eq!(
+            parse_provider("quad9-recommended", &[]).unwrap(),
+            DnsProvider::Catalog("Quad9Recommended".into())
+        );
+        assert_eq!(
+            parse_provider("AdGuard", &[]).unwrap(),
+            DnsProvider::Catalog("AdGuard".into())
+        );
+        assert_eq!(parse_provider("auto", &[]).unwrap(), DnsProvider::Auto);
+        assert_eq!(
+            parse_provider("custom", &["1.1.1.1".into(), "2606:4700:4700::1111".into()]).unwrap(),
+            DnsProvider::Custom(
+                "1.1.1.1".into(),
+                String::new(),
+                Some("2606:4700:4700::1111".into()),
+                None,
+                None,
+                None
+            )
+        );
+        assert_eq!(
+            parse_provider(
+                "custom",
+                &[
+                    "9.9.9.9".into(),
+                    "https://dns.quad9.net/dns-query".into(),
+                    "tls://dns.quad9.net".into()
+                ]
+            )
+            .unwrap(),
+            DnsProvider::Custom(
+                "9.9.9.9".into(),
+                String::new(),
+                None,
+                None,
+                Some("https://dns.quad9.net/dns-query".into()),
+                Some("dns.quad9.net".into())
+            )
+        );
+        assert_eq!(
+            parse_provider("custom", &["not-an-ip".into()])
+                .unwrap_err()
+                .kind,
+            ErrorKind::InvalidInput
+        );
+        assert!(parse_provider("nope", &[]).is_err());
+    }
+
+    #[test]
+    fn test_settings_file_overrides_probe_targets() {
+        let path =
+            std::env::temp_dir().join(format!("netok_cli_settings_{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"probe_targets": {"geoip_base_url": "http://127.0.0.1:9"}}"#,
+        )
+        .unwrap();
+        let path_arg = path.to_string_lossy().to_string();
+        let (_, ctx) = context(&["--settings", &path_arg, "diagnose"]);
+        assert_eq!(
+            ctx.settings.probe_targets.geoip_base_url,
+            "http://127.0.0.1:9"
+        );
+        assert_eq!(ctx.settings.language, "en");
+        std::fs::remove_file(&path).unwrap();
+
+        let cli = Cli::try_parse_from(["netok", "--settings", &path_arg, "diagnose"]).unwrap();
+        let Err(err) = Context::new(&cli.global) else {
+            panic!("missing settings file should fail");
+        };
+        assert_eq!(err.kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_vpn_config_error_maps_to_parse() {
+        let (command, ctx) = context(&["vpn", "config", "trojan://secret@example.com:notaport"]);
+        let Err(err) = execute(&command, &ctx) else {
+            panic!("invalid key should fail");
+        };
+        assert_eq!(err.kind, ErrorKind::Parse);
+        assert_eq!(err.key, "errors.parse");
+    }
+}
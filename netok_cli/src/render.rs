@@ -0,0 +1,778 @@
+//! Human-readable output for each command.
+//!
+//! Labels come from the UI locale files; values (addresses, names) are
+//! printed as-is. Each function returns the full text without a trailing
+//! newline.
+
+use netok_core::{
+    diagnose_scenario, ConnectionType, DeviceType, DiagnosticsSnapshot, DnsBenchmark, DnsCatalog,
+    DnsFilter, DnsLeakReport, DnsProvider, DnsTransport, DnssecReport, DnssecStatus, FilterResult,
+    FilteringReport, FilteringStatus, IdentityConfidence, IdentityMethod, IpInfoResponse,
+    LeakStatus, NetworkDevice, NodeId, NodeInfo, ResolverIdentity, ResolverOwner,
+    SecurityCheckType, SecurityStatus, Status, WiFiSecurityReport,
+};
+
+use crate::i18n::I18n;
+
+/// Fixed-width status tag, the same in every language so output stays greppable.
+pub fn status_tag(status: Status) -> &'static str {
+    match status {
+        Status::Ok => "[OK]  ",
+        Status::Warn => "[WARN]",
+        Status::Fail => "[FAIL]",
+        Status::Unknown => "[?]   ",
+    }
+}
+
+fn security_status(status: SecurityStatus) -> Status {
+    match status {
+        SecurityStatus::Safe => Status::Ok,
+        SecurityStatus::Warning => Status::Warn,
+        SecurityStatus::Danger => Status::Fail,
+    }
+}
+
+/// `  Label: value` lines for the fields that are present.
+fn fields(t: &I18n, pairs: &[(&str, Option<String>)]) -> Vec<String> {
+    pairs
+        .iter()
+        .filter_map(|(key, value)| {
+            value
+                .as_ref()
+                .map(|value| format!("       {}: {}", t.t(key), value))
+        })
+        .collect()
+}
+
+fn node_name_key(id: NodeId) -> &'static str {
+    match id {
+        NodeId::Computer => "diagnostics.computer",
+        NodeId::Wifi => "diagnostics.wifi",
+        NodeId::RouterUpnp => "diagnostics.router",
+        NodeId::Dns => "diagnostics.dns",
+        NodeId::Internet => "diagnostics.internet",
+    }
+}
+
+fn connection_type_key(connection_type: ConnectionType) -> &'static str {
+    match connection_type {
+        ConnectionType::Wifi => "nodes.network.type_wifi",
+        ConnectionType::Ethernet => "nodes.network.type_cable",
+        ConnectionType::Usb => "nodes.network.type_usb_modem",
+        ConnectionType::Mobile => "nodes.network.type_mobile",
+        ConnectionType::Disabled => "diagnostics.status_disabled",
+        ConnectionType::Disconnected => "diagnostics.status_not_connected",
+        ConnectionType::Unknown => "diagnostics.status_no_data",
+    }
+}
+
+fn works(t: &I18n, ok: bool) -> String {
+    if ok {
+        "OK".to_string()
+    } else {
+        t.t("node_detail.status_fail")
+    }
+}
+
+/// DNSSEC validation of a resolver; `None` if it wasn't checked.
+fn dnssec(t: &I18n, status: DnssecStatus) -> Option<String> {
+    match status {
+        DnssecStatus::Validating => Some(t.t("node_detail.dnssec_validating")),
+        DnssecStatus::NonValidating => Some(t.t("node_detail.dnssec_non_validating")),
+        DnssecStatus::Broken => Some(t.t("node_detail.dnssec_broken")),
+        DnssecStatus::Unknown => None,
+    }
+}
+
+fn node_details(t: &I18n, node: &NodeInfo, snapshot: &DiagnosticsSnapshot) -> Vec<String> {
+    match node.id {
+        NodeId::Computer => {
+            let c = &snapshot.computer;
+            fields(
+                t,
+                &[
+                    ("nodes.computer.name_field", c.hostname.clone()),
+                    ("nodes.computer.adapter_field", c.adapter.clone()),
+                    ("nodes.computer.local_ip_field", c.local_ip.clone()),
+                ],
+            )
+        }
+        NodeId::Wifi => {
+            let n = &snapshot.network;
+            let unit = t.t("node_detail.unit_mbps");
+            fields(
+                t,
+                &[
+                    (
+                        "nodes.network.link_field",
+                        Some(t.t(connection_type_key(n.connection_type))),
+                    ),
+                    ("node_detail.network_name", n.ssid.clone()),
+                    (
+                        "nodes.network.signal_field",
+                        n.rssi.map(|rssi| format!("{} dBm", rssi)),
+                    ),
+                    ("node_detail.frequency", n.frequency.clone()),
+                    ("node_detail.security", n.encryption.clone()),
+                    (
+                        "node_detail.bandwidth",
+                        n.link_speed_mbps.map(|mbps| format!("{} {}", mbps, unit)),
+                    ),
+                ],
+            )
+        }
+        NodeId::RouterUpnp => {
+            let r = &snapshot.router;
+            fields(
+                t,
+                &[
+                    ("nodes.router.local_ip_field", r.gateway_ip.clone()),
+                    ("MAC", r.gateway_mac.clone()),
+                    ("node_detail.manufacturer", r.vendor.clone()),
+                ],
+            )
+        }
+        NodeId::Dns => fields(
+            t,
+            &[
+                (
+                    "node_detail.dns_lookup",
+                    Some(works(t, snapshot.internet.dns_ok)),
+                ),
+                ("node_detail.dnssec", dnssec(t, snapshot.internet.dnssec)),
+            ],
+        ),
+        NodeId::Internet => {
+            let i = &snapshot.internet;
+            fields(
+                t,
+                &[
+                    ("node_detail.dns_lookup", Some(works(t, i.dns_ok))),
+                    ("node_detail.dnssec", dnssec(t, i.dnssec)),
+                    ("node_detail.web_loading", Some(works(t, i.http_ok))),
+                    ("nodes.internet.ip_field", i.public_ip.clone()),
+                    ("node_detail.isp", i.isp.clone()),
+                ],
+            )
+        }
+    }
+}
+
+/// The diagnostic chain, one block per node, then the verdict.
+pub fn diagnostics(t: &I18n, snapshot: &DiagnosticsSnapshot) -> String {
+    let mut lines = Vec::new();
+    for node in &snapshot.nodes {
+        let latency = node
+            .latency_ms
+            .map(|ms| format!("  {} ms", ms))
+            .unwrap_or_default();
+        lines.push(format!(
+            "{} {}{}",
+            status_tag(node.status),
+            t.t(node_name_key(node.id)),
+            latency
+        ));
+        lines.extend(node_details(t, node, snapshot));
+        if let Some(hint) = &node.hint_key {
+            lines.push(format!("       ! {}", t.t(hint)));
+        }
+    }
+
+    let scenario = diagnose_scenario(snapshot).scenario;
+    lines.push(String::new());
+    lines.push(format!(
+        "{} — {}",
+        t.t(scenario.title_key()),
+        t.t(scenario.message_key())
+    ));
+    lines.join("\n")
+}
+
+fn device_type_key(device_type: DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Router => "device_scan.type_router",
+        DeviceType::Phone => "device_scan.type_phone",
+        DeviceType::Computer => "device_scan.type_computer",
+        DeviceType::Tablet => "device_scan.type_tablet",
+        DeviceType::Printer => "device_scan.type_printer",
+        DeviceType::SmartTv => "device_scan.type_smarttv",
+        DeviceType::GameConsole => "device_scan.type_gameconsole",
+        DeviceType::IoT => "device_scan.type_iot",
+        DeviceType::Unknown => "device_scan.type_unknown",
+    }
+}
+
+/// One line per device: address, MAC, type, name and badges.
+pub fn devices(t: &I18n, devices: &[NetworkDevice]) -> String {
+    if devices.is_empty() {
+        return t.t("device_scan.no_devices");
+    }
+
+    let mut lines = vec![t.t_with(
+        "device_scan.found",
+        &[("count", &devices.len().to_string())],
+    )];
+    for device in devices {
+        let name = device
+            .hostname
+            .clone()
+            .or_else(|| {
+                device
+                    .vendor
+                    .as_ref()
+                    .map(|v| t.t_with("device_scan.branded_device", &[("name", v)]))
+            })
+            .unwrap_or_else(|| t.t("device_scan.unknown_device"));
+        let mut badges = Vec::new();
+        if device.is_gateway {
+            badges.push(t.t("device_scan.badge_router"));
+        }
+        if device.is_self {
+            badges.push(t.t("device_scan.badge_this_device"));
+        }
+        if device.is_randomized {
+            badges.push(t.t("device_scan.private_address"));
+        }
+        let badges = if badges.is_empty() {
+            String::new()
+        } else {
+            format!("  [{}]", badges.join(", "))
+        };
+        lines.push(format!(
+            "{:<15}  {:<17}  {:<10}  {}{}",
+            device.ip,
+            device.mac,
+            t.t(device_type_key(device.device_type)),
+            name,
+            badges
+        ));
+    }
+    lines.join("\n")
+}
+
+fn check_name_key(check_type: SecurityCheckType) -> &'static str {
+    match check_type {
+        SecurityCheckType::Encryption => "wifi_security.check_encryption",
+        SecurityCheckType::EvilTwin => "wifi_security.check_evil_twin",
+        SecurityCheckType::ArpSpoofing => "wifi_security.check_arp_spoofing",
+        SecurityCheckType::DnsHijacking => "wifi_security.check_dns_hijacking",
+    }
+}
+
+/// Each security check with its status and technical details, then the verdict.
+pub fn security(t: &I18n, report: &WiFiSecurityReport) -> String {
+    let mut lines = Vec::new();
+    if let Some(ssid) = &report.network_ssid {
+        lines.push(t.t_with("wifi_security.network", &[("ssid", ssid)]));
+    }
+    for check in &report.checks {
+        let details = check
+            .details
+            .as_ref()
+            .map(|d| format!("  ({})", d))
+            .unwrap_or_default();
+        lines.push(format!(
+            "{} {}{}",
+            status_tag(security_status(check.status)),
+            t.t(check_name_key(check.check_type)),
+            details
+        ));
+    }
+    lines.push(String::new());
+    lines.push(t.t(match report.overall_status {
+        SecurityStatus::Safe => "wifi_security.overall_safe",
+        SecurityStatus::Warning => "wifi_security.overall_warning",
+        SecurityStatus::Danger => "wifi_security.overall_danger",
+    }));
+    lines.join("\n")
+}
+
+/// Display name of a provider: the UI's name where it has one, else the
+/// catalog's.
+pub fn provider_name(t: &I18n, provider: &DnsProvider) -> String {
+    match provider {
+        DnsProvider::Auto => t.t("dns_providers.system"),
+        DnsProvider::Custom(..) => t.t("dns_providers.custom_display"),
+        DnsProvider::Catalog(id) => provider
+            .entry()
+            .map_or_else(|| id.clone(), |entry| entry.name.clone()),
+    }
+}
+
+/// Configured servers and the provider they belong to.
+pub fn dns_servers(t: &I18n, servers: &[String], provider: &DnsProvider) -> String {
+    let servers = if servers.is_empty() {
+        t.t("diagnostics.status_no_data")
+    } else {
+        servers.join(", ")
+    };
+    format!(
+        "{}: {}\n{}",
+        t.t("dns_providers.title"),
+        servers,
+        provider_name(t, provider)
+    )
+}
+
+/// One line per catalog provider: the id `dns set` takes, name and servers.
+pub fn dns_catalog(catalog: &DnsCatalog) -> String {
+    catalog
+        .providers
+        .iter()
+        .map(|entry| {
+            format!(
+                "{:<24}  {:<40}  {}",
+                entry.id,
+                entry.name,
+                entry.ipv4.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Short name of a DNS transport.
+pub fn transport_name(transport: DnsTransport) -> &'static str {
+    match transport {
+        DnsTransport::Udp => "UDP",
+        DnsTransport::Doh => "DoH",
+        DnsTransport::Dot => "DoT",
+    }
+}
+
+/// Latency to one DNS server, or "unreachable".
+pub fn dns_latency(t: &I18n, latency_ms: Option<u64>) -> String {
+    match latency_ms {
+        Some(ms) => format!("{} ms", ms),
+        None => t.t("diagnostics.status_unreachable"),
+    }
+}
+
+/// Resolvers ranked by median latency, then the recommendation.
+pub fn dns_benchmark(t: &I18n, benchmark: &DnsBenchmark) -> String {
+    let ms = |value: Option<f64>| match value {
+        Some(ms) => format!("{:.0} ms", ms),
+        None => "-".to_string(),
+    };
+    let mut lines: Vec<String> = benchmark
+        .resolvers
+        .iter()
+        .map(|resolver| {
+            let tag = if resolver.reliable {
+                status_tag(Status::Ok)
+            } else if resolver.median_ms.is_some() {
+                status_tag(Status::Warn)
+            } else {
+                status_tag(Status::Fail)
+            };
+            format!(
+                "{} {:<24}  {:<40}  median {:>7}  p95 {:>7}  errors {:>3.0}%  {}",
+                tag,
+                provider_name(t, &resolver.provider),
+                resolver.server,
+                ms(resolver.median_ms),
+                ms(resolver.p95_ms),
+                resolver.error_rate * 100.0,
+                dnssec_label(t, resolver.dnssec)
+            )
+        })
+        .collect();
+    let recommended = match &benchmark.recommended {
+        Some(provider) => provider_name(t, provider),
+        None => t.t("diagnostics.status_no_data"),
+    };
+    lines.push(String::new());
+    lines.push(format!(
+        "{}: {}",
+        t.t("dns_providers.recommended"),
+        recommended
+    ));
+    lines.join("\n")
+}
+
+/// "DNSSEC: status", with "-" for an unchecked resolver.
+fn dnssec_label(t: &I18n, status: DnssecStatus) -> String {
+    format!(
+        "{}: {}",
+        t.t("node_detail.dnssec"),
+        dnssec(t, status).unwrap_or_else(|| "-".to_string())
+    )
+}
+
+/// One line per resolver: whether it validates DNSSEC.
+pub fn dnssec_report(t: &I18n, report: &DnssecReport) -> String {
+    report
+        .resolvers
+        .iter()
+        .map(|check| {
+            let tag = match check.status {
+                DnssecStatus::Validating => status_tag(Status::Ok),
+                DnssecStatus::NonValidating => status_tag(Status::Warn),
+                DnssecStatus::Broken => status_tag(Status::Fail),
+                DnssecStatus::Unknown => status_tag(Status::Unknown),
+            };
+            format!(
+                "{} {:<24}  {:<40}  {}",
+                tag,
+                provider_name(t, &check.provider),
+                check.server,
+                dnssec(t, check.status).unwrap_or_else(|| t.t("diagnostics.status_unreachable"))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolvers seen by the DNS leak test, then the verdict.
+pub fn dns_leak(t: &I18n, report: &DnsLeakReport) -> String {
+    let mut lines: Vec<String> = report
+        .resolvers
+        .iter()
+        .map(|resolver| {
+            let (tag, owner) = match resolver.owner {
+                ResolverOwner::Isp => (status_tag(Status::Fail), "dns_leak.owner_isp"),
+                ResolverOwner::Vpn => (status_tag(Status::Ok), "dns_leak.owner_vpn"),
+                ResolverOwner::ThirdParty => (status_tag(Status::Ok), "dns_leak.owner_third_party"),
+                ResolverOwner::Unknown => (status_tag(Status::Unknown), "dns_leak.owner_unknown"),
+            };
+            let network = match (resolver.asn, &resolver.org) {
+                (Some(asn), Some(org)) => format!("AS{} {}", asn, org),
+                (Some(asn), None) => format!("AS{}", asn),
+                (None, Some(org)) => org.clone(),
+                (None, None) => "-".to_string(),
+            };
+            format!(
+                "{} {:<40}  {:<40}  {:<11}  {}",
+                tag,
+                resolver.ip,
+                network,
+                t.t(owner),
+                t.t_with(
+                    "dns_leak.queries",
+                    &[("count", &resolver.queries.to_string())]
+                )
+            )
+        })
+        .collect();
+    if !lines.is_empty() {
+        lines.push(String::new());
+    }
+    let verdict = match report.status {
+        LeakStatus::NoLeak => t.t("dns_leak.no_leak"),
+        LeakStatus::Leak => t.t("dns_leak.leak"),
+        LeakStatus::Inconclusive if report.isp_ip.is_none() => {
+            format!("{} (--isp-ip)", t.t("dns_leak.inconclusive_no_isp"))
+        }
+        LeakStatus::Inconclusive => t.t("dns_leak.inconclusive"),
+    };
+    lines.push(format!(
+        "{} {}",
+        t.t_with(
+            "dns_leak.answered",
+            &[
+                ("answered", &report.answered.to_string()),
+                ("total", &report.queries.to_string())
+            ]
+        ),
+        verdict
+    ));
+    lines.join("\n")
+}
+
+/// The provider behind the configured resolver, then the evidence for it.
+pub fn resolver_identity(t: &I18n, identity: &ResolverIdentity) -> String {
+    let confidence = |confidence: IdentityConfidence| {
+        t.t(match confidence {
+            IdentityConfidence::High => "resolver_identity.confidence_high",
+            IdentityConfidence::Medium => "resolver_identity.confidence_medium",
+            IdentityConfidence::Low => "resolver_identity.confidence_low",
+        })
+    };
+    // Labels padded to one width so the values line up
+    let labels = [
+        "resolver_identity.configured",
+        "resolver_identity.egress",
+        "resolver_identity.provider",
+    ]
+    .map(|key| format!("{}:", t.t(key)));
+    let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) + 1;
+    let [configured, egress, provider] = labels;
+
+    let servers = if identity.servers.is_empty() {
+        "-".to_string()
+    } else {
+        identity.servers.join(", ")
+    };
+    let mut lines = vec![format!(
+        "{:<width$}{}{}",
+        configured,
+        servers,
+        if identity.forwarder {
+            format!(" ({})", t.t("resolver_identity.local_forwarder"))
+        } else {
+            String::new()
+        }
+    )];
+    if !identity.egress_ips.is_empty() {
+        lines.push(format!(
+            "{:<width$}{}",
+            egress,
+            identity.egress_ips.join(", ")
+        ));
+    }
+    lines.push(match (&identity.family, identity.confidence) {
+        (Some(family), Some(level)) => {
+            let name = identity
+                .provider
+                .as_ref()
+                .and_then(|provider| provider.entry())
+                .map_or(family.as_str(), |entry| entry.name.as_str());
+            format!(
+                "{:<width$}{} ({})",
+                provider,
+                name,
+                t.t_with(
+                    "resolver_identity.confidence",
+                    &[("level", &confidence(level))]
+                )
+            )
+        }
+        _ => format!(
+            "{:<width$}{}",
+            provider,
+            t.t("resolver_identity.not_identified")
+        ),
+    });
+    if !identity.evidence.is_empty() {
+        lines.push(String::new());
+    }
+    lines.extend(identity.evidence.iter().map(|evidence| {
+        let method = match evidence.method {
+            IdentityMethod::ServerAddress => "resolver_identity.method_address",
+            IdentityMethod::DebugName => "resolver_identity.method_debug_name",
+            IdentityMethod::Chaos => "resolver_identity.method_chaos",
+            IdentityMethod::EgressResolver => "resolver_identity.method_egress",
+        };
+        format!(
+            "  {:<6}  {:<10}  {:<50}  {}",
+            confidence(evidence.confidence),
+            t.t(method),
+            evidence.detail,
+            evidence.family.as_deref().unwrap_or("-")
+        )
+    }));
+    lines.join("\n")
+}
+
+/// Each category through the provider and through this computer's resolver,
+/// then the verdict.
+pub fn dns_filtering(t: &I18n, report: &FilteringReport) -> String {
+    let result = |result: FilterResult| {
+        t.t(match result {
+            FilterResult::Blocked => "dns_filtering.blocked",
+            FilterResult::Resolved => "dns_filtering.resolved",
+            FilterResult::Unknown => "dns_filtering.no_answer",
+        })
+    };
+    let mut lines = vec![format!(
+        "{} ({})",
+        provider_name(t, &report.provider),
+        report.server.as_deref().unwrap_or("-")
+    )];
+    lines.extend(report.categories.iter().map(|check| {
+        let tag = match (check.expected, check.system) {
+            (false, _) => "      ",
+            (true, FilterResult::Blocked) => status_tag(Status::Ok),
+            (true, FilterResult::Resolved) => status_tag(Status::Fail),
+            (true, FilterResult::Unknown) => status_tag(Status::Unknown),
+        };
+        let category = match check.category {
+            DnsFilter::Malware => "dns_filtering.category_malware",
+            DnsFilter::Adult => "dns_filtering.category_adult",
+            DnsFilter::Ads => "dns_filtering.category_ads",
+        };
+        format!(
+            "{} {:<8}  {}: {:<9}  {}: {:<9}{}",
+            tag,
+            t.t(category),
+            t.t("dns_filtering.provider"),
+            result(check.provider),
+            t.t("dns_filtering.this_computer"),
+            result(check.system),
+            if check.expected {
+                String::new()
+            } else {
+                format!("  ({})", t.t("dns_filtering.not_filtered"))
+            }
+        )
+    }));
+    lines.push(String::new());
+    lines.push(t.t(match report.status {
+        FilteringStatus::Working => "dns_filtering.working",
+        FilteringStatus::NotFiltering => "dns_filtering.not_filtering",
+        FilteringStatus::Bypassed => "dns_filtering.bypassed",
+        FilteringStatus::Unknown if report.categories.iter().any(|c| c.expected) => {
+            "dns_filtering.inconclusive"
+        }
+        FilteringStatus::Unknown => "dns_filtering.nothing_to_filter",
+    }));
+    if report.browser_bypass {
+        lines.push(t.t("dns_filtering.browser_bypass"));
+    }
+    lines.join("\n")
+}
+
+/// Geolocation fields that are known.
+pub fn geo(t: &I18n, info: &IpInfoResponse) -> String {
+    let location = match (&info.city, &info.country) {
+        (Some(city), Some(country)) => Some(format!("{}, {}", city, country)),
+        (city, country) => city.clone().or_else(|| country.clone()),
+    };
+    fields(
+        t,
+        &[
+            ("nodes.internet.ip_field", info.ip.clone()),
+            ("node_detail.isp", info.org.clone()),
+            ("node_detail.location", location),
+        ],
+    )
+    .iter()
+    .map(|line| line.trim_start().to_string())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netok_core::{run_diagnostics_with_backend, Settings, SimulatedBackend};
+
+    #[test]
+    fn test_diagnostics_text_uses_locale_labels() {
+        let t = I18n::new("en");
+        let snapshot =
+            run_diagnostics_with_backend(&SimulatedBackend::healthy(), &Settings::default());
+        let text = diagnostics(&t, &snapshot);
+
+        assert!(text.contains("[OK]   Computer"));
+        assert!(text.contains("Name: DEMO-LAPTOP"));
+        assert!(text.contains("Network: Home_WiFi_5G"));
+        assert!(text.ends_with("Connected — Internet is working normally."));
+    }
+
+    #[test]
+    fn test_devices_text() {
+        let t = I18n::new("en");
+        let device = NetworkDevice {
+            ip: "192.168.1.1".to_string(),
+            mac: "40:ED:00:11:22:33".to_string(),
+            vendor: Some("TP-Link".to_string()),
+            hostname: None,
+            device_type: DeviceType::Router,
+            is_gateway: true,
+            is_self: false,
+            is_randomized: false,
+        };
+        let text = devices(&t, &[device]);
+        assert!(text.starts_with("1 devices found"));
+        assert!(text.contains("TP-Link device  [Router]"));
+        assert_eq!(devices(&t, &[]), "No devices found");
+    }
+
+    #[test]
+    fn test_resolver_identity_text() {
+        let t = I18n::new("en");
+        let identity = ResolverIdentity {
+            servers: vec!["192.168.1.1".to_string()],
+            forwarder: true,
+            egress_ips: vec!["172.70.1.1".to_string()],
+            family: Some("example".to_string()),
+            confidence: Some(IdentityConfidence::High),
+            evidence: vec![netok_core::IdentityEvidence {
+                method: IdentityMethod::EgressResolver,
+                detail: "172.70.1.1".to_string(),
+                family: Some("example".to_string()),
+                confidence: IdentityConfidence::High,
+            }],
+            ..Default::default()
+        };
+        let text = resolver_identity(&t, &identity);
+        assert!(
+            text.starts_with("Configured: 192.168.1.1 (local forwarder)\nEgress:     172.70.1.1")
+        );
+        assert!(text.contains("Provider:   example (high confidence)"));
+        assert!(text.ends_with(
+            "  high    egress      172.70.1.1                                          example"
+        ));
+
+        let ru = resolver_identity(&I18n::new("ru"), &identity);
+        assert!(ru.starts_with("Настроен:  192.168.1.1 (локальный ретранслятор)"));
+        assert!(ru.contains("Провайдер: example (уверенность: высокая)"));
+    }
+
+    #[test]
+    fn test_dns_filtering_text() {
+        let t = I18n::new("en");
+        let check = |category, expected, result| netok_core::CategoryCheck {
+            category,
+            expected,
+            provider: result,
+            system: result,
+        };
+        let report = FilteringReport {
+            provider: DnsProvider::Auto,
+            server: Some("94.140.14.14".to_string()),
+            status: FilteringStatus::Working,
+            categories: vec![
+                check(DnsFilter::Malware, true, FilterResult::Blocked),
+                check(DnsFilter::Adult, false, FilterResult::Resolved),
+            ],
+            os_bypass: false,
+            browser_bypass: true,
+        };
+        let text = dns_filtering(&t, &report);
+        assert!(text.contains("[OK]   malware   provider: blocked    this computer: blocked"));
+        assert!(text
+            .contains("adult     provider: resolved   this computer: resolved   (not filtered)"));
+        assert!(text.contains("Filtering works on this computer."));
+        assert!(text.ends_with("turn it off in their settings."));
+
+        let ru = dns_filtering(&I18n::new("ru"), &report);
+        assert!(ru.contains("провайдер: заблокировано"));
+        assert!(ru.contains("Фильтрация на этом компьютере работает."));
+    }
+
+    #[test]
+    fn test_dns_leak_text() {
+        let t = I18n::new("en");
+        let report = DnsLeakReport {
+            status: LeakStatus::Leak,
+            public_ip: Some("198.51.100.7".to_string()),
+            vpn_asn: Some(64500),
+            isp_ip: Some("203.0.113.5".to_string()),
+            isp_asn: Some(64496),
+            queries: 6,
+            answered: 6,
+            resolvers: vec![netok_core::LeakResolver {
+                ip: "203.0.113.53".to_string(),
+                asn: Some(64496),
+                org: Some("Example ISP".to_string()),
+                country: None,
+                queries: 6,
+                owner: ResolverOwner::Isp,
+            }],
+        };
+        let text = dns_leak(&t, &report);
+        assert!(text.starts_with("[FAIL] 203.0.113.53"));
+        assert!(text.contains("AS64496 Example ISP"));
+        assert!(text.contains("ISP          6 queries"));
+        assert!(text
+            .ends_with("6/6 answered. DNS leak: queries reach a resolver on the ISP's network."));
+
+        let ru = dns_leak(&I18n::new("ru"), &report);
+        assert!(ru.contains("провайдер"));
+        assert!(ru.ends_with(
+            "Ответов: 6/6. Утечка DNS: запросы доходят до резолвера в сети провайдера."
+        ));
+    }
+}
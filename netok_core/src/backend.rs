@@ -0,0 +1,990 @@
+//! Pluggable access to the machine's network state.
+//!
+//! Diagnostics, the device scan and the Wi-Fi security checks read the system
+//! only through [`NetworkBackend`]. [`SystemBackend`] queries the real machine;
+//! [`SimulatedBackend`] answers from plain data, so tests and the desktop demo
+//! mode get deterministic results. `SimulatedBackend::scenario` scripts every
+//! `DiagnosticScenario`.
+//!
+//! Probes that go over the network also have an async form returning a
+//! [`ProbeFuture`]. The defaults answer from the blocking method, which suits
+//! backends that never wait on the network; `SystemBackend` does real async I/O.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr};
+use std::pin::Pin;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::Cancellation;
+use crate::diagnostics::IpInfoResponse;
+use crate::domain::{
+    ConnectionType, DhcpInfo, DiagnosticScenario, DnsClass, DnssecStatus, ProbeTargets,
+};
+use crate::error::DnsError;
+use crate::infrastructure::arp::ArpEntry;
+use crate::infrastructure::dns_transport::{DnsClient, DnsEndpoint};
+use crate::infrastructure::mdns::MdnsDeviceInfo;
+use crate::infrastructure::security::{SecurityCheck, SecurityCheckType, SecurityStatus};
+use crate::infrastructure::wifi::{WifiAdapterState, WifiDetails};
+
+// ==================== Domain Types ====================
+
+/// An IPv4 address assigned to a local (non-loopback) interface.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceAddress {
+    pub name: String,
+    pub ip: Ipv4Addr,
+}
+
+impl InterfaceAddress {
+    pub fn new(name: impl Into<String>, ip: Ipv4Addr) -> Self {
+        Self {
+            name: name.into(),
+            ip,
+        }
+    }
+}
+
+/// Future returned by the async probes of [`NetworkBackend`].
+pub type ProbeFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A DNS server opened by [`NetworkBackend::dns_connect_async`] for a series
+/// of timed lookups.
+pub trait DnsConnection: Send + Sync {
+    /// Ask for the A records of `name`. Milliseconds until the answer
+    /// (including "no such name"), or `None` without one in time.
+    fn timed_lookup<'a>(&'a self, name: &'a str) -> ProbeFuture<'a, Option<f64>>;
+}
+
+/// Everything the diagnostics read from the operating system and the network.
+///
+/// Methods mirror the infrastructure functions one-to-one and must not fail
+/// loudly: "don't know" is `None`, an empty list or `false`, as on platforms
+/// where a query isn't implemented.
+pub trait NetworkBackend: Send + Sync {
+    /// Host name of this computer.
+    fn hostname(&self) -> Option<String>;
+    /// IPv4 addresses of all non-loopback interfaces.
+    fn interfaces(&self) -> Vec<InterfaceAddress>;
+    /// Wi-Fi adapter state and connection details.
+    fn wifi_info(&self) -> WifiDetails;
+    /// Connection type guessed from an adapter name or description.
+    fn connection_type(&self, adapter: &str) -> ConnectionType;
+    /// Result of the Wi-Fi encryption check.
+    fn encryption_check(&self) -> SecurityCheck;
+    /// Result of the evil twin check (needs a BSS scan).
+    fn evil_twin_check(&self) -> SecurityCheck;
+    /// IPv4 address of the default gateway.
+    fn default_gateway(&self) -> Option<String>;
+    /// MAC address of the gateway from the ARP table.
+    fn router_mac(&self, gateway_ip: &str) -> Option<String>;
+    /// Current ARP table.
+    fn arp_entries(&self) -> Vec<ArpEntry>;
+    /// Ping the gateway's subnet to populate the ARP table, stopping early
+    /// when `cancel` fires.
+    fn ping_sweep(&self, gateway_ip: &str, cancel: &Cancellation);
+    /// DHCP lease details for the adapter.
+    fn dhcp_info(&self, adapter: Option<&str>) -> Option<DhcpInfo>;
+    /// DNS servers configured on the active adapter.
+    fn dns_servers(&self) -> Result<Vec<String>, DnsError>;
+    /// Whether the system resolver resolves any of `targets.dns_domains`.
+    fn dns_probe(&self, targets: &ProbeTargets) -> bool;
+    /// Whether any of `targets.http_urls` answers.
+    fn http_probe(&self, targets: &ProbeTargets) -> bool;
+    /// IPv4 addresses of `domain` from the system resolver.
+    fn resolve_system(&self, domain: &str) -> Vec<String>;
+    /// IPv4 addresses of `domain` from the DNS server at `server` ("ip:port").
+    fn resolve_via(&self, domain: &str, server: &str) -> Vec<String>;
+    /// IPv6 addresses of `domain` from the system resolver.
+    fn resolve_system_v6(&self, domain: &str) -> Vec<String>;
+    /// IPv4 and IPv6 addresses of `domain` from the DoH server at `url`.
+    fn resolve_doh(&self, domain: &str, url: &str) -> Vec<String>;
+    /// TXT strings for `domain` in `class` from the DNS server at `server`
+    /// ("ip:port"), waiting up to `timeout`.
+    fn query_txt(
+        &self,
+        domain: &str,
+        server: &str,
+        class: DnsClass,
+        timeout: Duration,
+    ) -> Vec<String>;
+    /// PTR name of `ip`.
+    fn reverse_dns(&self, ip: &str, timeout_ms: u64) -> Option<String>;
+    /// Location and network of `ip` from the geolocation API in `targets`;
+    /// an empty `ip` asks for this machine's public address.
+    fn ip_location(&self, ip: &str, targets: &ProbeTargets) -> Option<IpInfoResponse>;
+    /// Whether the current resolver validates DNSSEC.
+    fn dnssec_status(&self) -> DnssecStatus;
+    /// Whether the DNS server at `server` validates DNSSEC, waiting `timeout`
+    /// for each test answer.
+    fn resolver_dnssec(&self, server: IpAddr, timeout: Duration) -> DnssecStatus;
+    /// Devices announcing mDNS services, keyed by IPv4 address. Listens for
+    /// `timeout`, or until `cancel` fires.
+    fn mdns_discover(
+        &self,
+        timeout: Duration,
+        cancel: &Cancellation,
+    ) -> HashMap<String, MdnsDeviceInfo>;
+
+    /// Async [`dns_probe`](Self::dns_probe).
+    fn dns_probe_async<'a>(&'a self, targets: &'a ProbeTargets) -> ProbeFuture<'a, bool> {
+        Box::pin(std::future::ready(self.dns_probe(targets)))
+    }
+    /// Async [`http_probe`](Self::http_probe).
+    fn http_probe_async<'a>(&'a self, targets: &'a ProbeTargets) -> ProbeFuture<'a, bool> {
+        Box::pin(std::future::ready(self.http_probe(targets)))
+    }
+    /// Async [`resolve_system`](Self::resolve_system).
+    fn resolve_system_async<'a>(&'a self, domain: &'a str) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(std::future::ready(self.resolve_system(domain)))
+    }
+    /// Async [`resolve_via`](Self::resolve_via).
+    fn resolve_via_async<'a>(
+        &'a self,
+        domain: &'a str,
+        server: &'a str,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(std::future::ready(self.resolve_via(domain, server)))
+    }
+    /// Async [`resolve_system_v6`](Self::resolve_system_v6).
+    fn resolve_system_v6_async<'a>(&'a self, domain: &'a str) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(std::future::ready(self.resolve_system_v6(domain)))
+    }
+    /// Async [`resolve_doh`](Self::resolve_doh).
+    fn resolve_doh_async<'a>(
+        &'a self,
+        domain: &'a str,
+        url: &'a str,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(std::future::ready(self.resolve_doh(domain, url)))
+    }
+    /// Async [`query_txt`](Self::query_txt).
+    fn query_txt_async<'a>(
+        &'a self,
+        domain: &'a str,
+        server: &'a str,
+        class: DnsClass,
+        timeout: Duration,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(std::future::ready(
+            self.query_txt(domain, server, class, timeout),
+        ))
+    }
+    /// Async [`reverse_dns`](Self::reverse_dns).
+    fn reverse_dns_async<'a>(
+        &'a self,
+        ip: &'a str,
+        timeout_ms: u64,
+    ) -> ProbeFuture<'a, Option<String>> {
+        Box::pin(std::future::ready(self.reverse_dns(ip, timeout_ms)))
+    }
+    /// Async [`ip_location`](Self::ip_location).
+    fn ip_location_async<'a>(
+        &'a self,
+        ip: &'a str,
+        targets: &'a ProbeTargets,
+    ) -> ProbeFuture<'a, Option<IpInfoResponse>> {
+        Box::pin(std::future::ready(self.ip_location(ip, targets)))
+    }
+    /// Async [`dnssec_status`](Self::dnssec_status).
+    fn dnssec_status_async(&self) -> ProbeFuture<'_, DnssecStatus> {
+        Box::pin(std::future::ready(self.dnssec_status()))
+    }
+    /// Async [`resolver_dnssec`](Self::resolver_dnssec).
+    fn resolver_dnssec_async(
+        &self,
+        server: IpAddr,
+        timeout: Duration,
+    ) -> ProbeFuture<'_, DnssecStatus> {
+        Box::pin(std::future::ready(self.resolver_dnssec(server, timeout)))
+    }
+    /// Async [`mdns_discover`](Self::mdns_discover).
+    fn mdns_discover_async<'a>(
+        &'a self,
+        timeout: Duration,
+        cancel: &'a Cancellation,
+    ) -> ProbeFuture<'a, HashMap<String, MdnsDeviceInfo>> {
+        Box::pin(std::future::ready(self.mdns_discover(timeout, cancel)))
+    }
+
+    /// Open the DNS server at `endpoint` for timed lookups, each waiting
+    /// `timeout`; DoT and DoH connections are reused across them. `None` if
+    /// the server can't be reached, e.g. a DoT hostname that doesn't resolve.
+    /// Async only, like the lookups.
+    fn dns_connect_async<'a>(
+        &'a self,
+        endpoint: &'a DnsEndpoint,
+        timeout: Duration,
+    ) -> ProbeFuture<'a, Option<Box<dyn DnsConnection>>>;
+}
+
+// ==================== System Backend ====================
+
+/// The real machine, via the platform-specific infrastructure code.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemBackend;
+
+impl NetworkBackend for SystemBackend {
+    fn hostname(&self) -> Option<String> {
+        hostname::get().ok().and_then(|s| s.into_string().ok())
+    }
+
+    fn interfaces(&self) -> Vec<InterfaceAddress> {
+        match get_if_addrs::get_if_addrs() {
+            Ok(ifaces) => ifaces
+                .into_iter()
+                .filter(|iface| !iface.is_loopback())
+                .filter_map(|iface| match iface.ip() {
+                    std::net::IpAddr::V4(ip) => Some(InterfaceAddress::new(iface.name, ip)),
+                    _ => None,
+                })
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn wifi_info(&self) -> WifiDetails {
+        crate::infrastructure::get_wifi_info()
+    }
+
+    fn connection_type(&self, adapter: &str) -> ConnectionType {
+        crate::infrastructure::detect_connection_type(adapter)
+    }
+
+    fn encryption_check(&self) -> SecurityCheck {
+        crate::infrastructure::security::check_encryption()
+    }
+
+    fn evil_twin_check(&self) -> SecurityCheck {
+        crate::infrastructure::security::check_evil_twin()
+    }
+
+    fn default_gateway(&self) -> Option<String> {
+        crate::infrastructure::get_default_gateway()
+    }
+
+    fn router_mac(&self, gateway_ip: &str) -> Option<String> {
+        crate::infrastructure::get_router_mac(gateway_ip)
+    }
+
+    fn arp_entries(&self) -> Vec<ArpEntry> {
+        crate::infrastructure::arp::get_all_arp_entries()
+    }
+
+    fn ping_sweep(&self, gateway_ip: &str, cancel: &Cancellation) {
+        crate::infrastructure::ping_sweep(gateway_ip, cancel)
+    }
+
+    fn dhcp_info(&self, adapter: Option<&str>) -> Option<DhcpInfo> {
+        crate::infrastructure::get_dhcp_info(adapter)
+    }
+
+    fn dns_servers(&self) -> Result<Vec<String>, DnsError> {
+        crate::infrastructure::get_current_dns()
+    }
+
+    fn dns_probe(&self, targets: &ProbeTargets) -> bool {
+        crate::runtime::block_on(self.dns_probe_async(targets))
+    }
+
+    fn http_probe(&self, targets: &ProbeTargets) -> bool {
+        crate::runtime::block_on(self.http_probe_async(targets))
+    }
+
+    fn resolve_system(&self, domain: &str) -> Vec<String> {
+        crate::runtime::block_on(self.resolve_system_async(domain))
+    }
+
+    fn resolve_via(&self, domain: &str, server: &str) -> Vec<String> {
+        crate::runtime::block_on(self.resolve_via_async(domain, server))
+    }
+
+    fn resolve_system_v6(&self, domain: &str) -> Vec<String> {
+        crate::runtime::block_on(self.resolve_system_v6_async(domain))
+    }
+
+    fn resolve_doh(&self, domain: &str, url: &str) -> Vec<String> {
+        crate::runtime::block_on(self.resolve_doh_async(domain, url))
+    }
+
+    fn query_txt(
+        &self,
+        domain: &str,
+        server: &str,
+        class: DnsClass,
+        timeout: Duration,
+    ) -> Vec<String> {
+        crate::runtime::block_on(self.query_txt_async(domain, server, class, timeout))
+    }
+
+    fn reverse_dns(&self, ip: &str, timeout_ms: u64) -> Option<String> {
+        crate::infrastructure::reverse_dns_lookup(ip, timeout_ms)
+    }
+
+    fn ip_location(&self, ip: &str, targets: &ProbeTargets) -> Option<IpInfoResponse> {
+        crate::runtime::block_on(self.ip_location_async(ip, targets))
+    }
+
+    fn dnssec_status(&self) -> DnssecStatus {
+        crate::runtime::block_on(self.dnssec_status_async())
+    }
+
+    fn resolver_dnssec(&self, server: IpAddr, timeout: Duration) -> DnssecStatus {
+        crate::runtime::block_on(self.resolver_dnssec_async(server, timeout))
+    }
+
+    fn mdns_discover(
+        &self,
+        timeout: Duration,
+        cancel: &Cancellation,
+    ) -> HashMap<String, MdnsDeviceInfo> {
+        crate::infrastructure::mdns_discover(timeout, cancel)
+    }
+
+    fn dns_probe_async<'a>(&'a self, targets: &'a ProbeTargets) -> ProbeFuture<'a, bool> {
+        Box::pin(crate::diagnostics::test_dns_async(targets))
+    }
+
+    fn http_probe_async<'a>(&'a self, targets: &'a ProbeTargets) -> ProbeFuture<'a, bool> {
+        Box::pin(crate::diagnostics::test_http_async(targets))
+    }
+
+    fn resolve_system_async<'a>(&'a self, domain: &'a str) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(crate::infrastructure::security::resolve_domain_system_async(domain))
+    }
+
+    fn resolve_via_async<'a>(
+        &'a self,
+        domain: &'a str,
+        server: &'a str,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(crate::infrastructure::security::resolve_domain_direct_async(domain, server))
+    }
+
+    fn resolve_system_v6_async<'a>(&'a self, domain: &'a str) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(crate::infrastructure::security::resolve_domain_system_v6_async(domain))
+    }
+
+    fn resolve_doh_async<'a>(
+        &'a self,
+        domain: &'a str,
+        url: &'a str,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(crate::infrastructure::security::resolve_domain_doh_async(
+            domain, url,
+        ))
+    }
+
+    fn query_txt_async<'a>(
+        &'a self,
+        domain: &'a str,
+        server: &'a str,
+        class: DnsClass,
+        timeout: Duration,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(crate::infrastructure::dns_transport::query_txt(
+            server, domain, class, timeout,
+        ))
+    }
+
+    fn reverse_dns_async<'a>(
+        &'a self,
+        ip: &'a str,
+        timeout_ms: u64,
+    ) -> ProbeFuture<'a, Option<String>> {
+        Box::pin(crate::infrastructure::reverse_dns_lookup_async(
+            ip, timeout_ms,
+        ))
+    }
+
+    fn ip_location_async<'a>(
+        &'a self,
+        ip: &'a str,
+        targets: &'a ProbeTargets,
+    ) -> ProbeFuture<'a, Option<IpInfoResponse>> {
+        Box::pin(async move {
+            crate::diagnostics::lookup_ip_location_async(ip, targets)
+                .await
+                .ok()
+        })
+    }
+
+    fn dnssec_status_async(&self) -> ProbeFuture<'_, DnssecStatus> {
+        Box::pin(async {
+            let servers = crate::runtime::blocking(|| self.dns_servers()).unwrap_or_default();
+            crate::dnssec::current_status(&servers).await
+        })
+    }
+
+    fn resolver_dnssec_async(
+        &self,
+        server: IpAddr,
+        timeout: Duration,
+    ) -> ProbeFuture<'_, DnssecStatus> {
+        Box::pin(crate::dnssec::resolver_status(server, timeout))
+    }
+
+    // The mDNS browser is a blocking library; keep it off the async workers.
+    fn mdns_discover_async<'a>(
+        &'a self,
+        timeout: Duration,
+        cancel: &'a Cancellation,
+    ) -> ProbeFuture<'a, HashMap<String, MdnsDeviceInfo>> {
+        let cancel = cancel.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || {
+                crate::infrastructure::mdns_discover(timeout, &cancel)
+            })
+            .await
+            .unwrap_or_default()
+        })
+    }
+
+    fn dns_connect_async<'a>(
+        &'a self,
+        endpoint: &'a DnsEndpoint,
+        timeout: Duration,
+    ) -> ProbeFuture<'a, Option<Box<dyn DnsConnection>>> {
+        Box::pin(async move {
+            let client = DnsClient::connect(endpoint, timeout).await?;
+            Some(Box::new(client) as Box<dyn DnsConnection>)
+        })
+    }
+}
+
+// ==================== Simulated Backend ====================
+
+/// A scripted network: every query answers from the fields below.
+///
+/// Start from [`SimulatedBackend::healthy`] or [`SimulatedBackend::scenario`]
+/// and change fields to build other situations. `Default` is a machine with
+/// no adapters at all.
+#[derive(Debug, Clone)]
+pub struct SimulatedBackend {
+    pub hostname: Option<String>,
+    pub interfaces: Vec<InterfaceAddress>,
+    pub wifi: WifiDetails,
+    /// Connection type reported for any adapter name.
+    pub connection_type: ConnectionType,
+    pub encryption: SecurityCheck,
+    pub evil_twin: SecurityCheck,
+    pub gateway: Option<String>,
+    pub arp_entries: Vec<ArpEntry>,
+    pub dhcp: Option<DhcpInfo>,
+    pub dns_servers: Vec<String>,
+    pub dns_ok: bool,
+    pub http_ok: bool,
+    pub dnssec: DnssecStatus,
+    /// DNSSEC status of directly queried resolvers, by IP. Configured servers
+    /// without an entry report `dnssec`, others `Unknown`.
+    pub resolver_dnssec: HashMap<String, DnssecStatus>,
+    /// Answers of the system resolver, by domain; IPv6 entries answer
+    /// [`NetworkBackend::resolve_system_v6`]. A `*.zone` key answers every
+    /// name under `zone` without an entry of its own.
+    pub system_resolutions: HashMap<String, Vec<String>>,
+    /// Answers of directly queried DNS servers (UDP and DoH), by domain, with
+    /// `*.zone` keys as for `system_resolutions`.
+    pub direct_resolutions: HashMap<String, Vec<String>>,
+    /// TXT strings of directly queried DNS servers, by name without the
+    /// trailing dot, CHAOS names (`version.bind`) included; `*.zone` keys as
+    /// for `system_resolutions`.
+    pub txt_records: HashMap<String, Vec<String>>,
+    /// PTR names, by IP.
+    pub reverse_dns: HashMap<String, String>,
+    /// Geolocation answers, by IP; `""` for this machine's public address.
+    pub ip_locations: HashMap<String, IpInfoResponse>,
+    /// Latency of every lookup sent straight to a DNS server, by server as
+    /// `DnsEndpoint` prints it (an IP, a DoH URL, `tls://` and a hostname).
+    /// Servers without an entry can't be reached.
+    pub dns_latencies: HashMap<String, f64>,
+    pub mdns: HashMap<String, MdnsDeviceInfo>,
+}
+
+impl Default for SimulatedBackend {
+    fn default() -> Self {
+        Self {
+            hostname: None,
+            interfaces: vec![],
+            wifi: WifiDetails::default(),
+            connection_type: ConnectionType::Unknown,
+            encryption: safe_check(SecurityCheckType::Encryption, None),
+            evil_twin: safe_check(SecurityCheckType::EvilTwin, None),
+            gateway: None,
+            arp_entries: vec![],
+            dhcp: None,
+            dns_servers: vec![],
+            dns_ok: false,
+            http_ok: false,
+            dnssec: DnssecStatus::Unknown,
+            resolver_dnssec: HashMap::new(),
+            system_resolutions: HashMap::new(),
+            direct_resolutions: HashMap::new(),
+            txt_records: HashMap::new(),
+            reverse_dns: HashMap::new(),
+            ip_locations: HashMap::new(),
+            dns_latencies: HashMap::new(),
+            mdns: HashMap::new(),
+        }
+    }
+}
+
+/// Address of the simulated computer.
+const SIM_LOCAL_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 1, 42);
+/// Address of the simulated router.
+const SIM_GATEWAY: &str = "192.168.1.1";
+/// Router MAC with a real OUI (TP-Link), so vendor lookup has something to find.
+const SIM_GATEWAY_MAC: &str = "40:ED:00:11:22:33";
+/// Address returned for every resolved domain in the healthy network.
+const SIM_RESOLVED_IP: &str = "93.184.216.34";
+
+impl SimulatedBackend {
+    /// A laptop on a working home Wi-Fi network with a few other devices.
+    pub fn healthy() -> Self {
+        let targets = ProbeTargets::default();
+        let resolved: HashMap<_, _> = targets
+            .hijack_test_domains
+            .iter()
+            .map(|domain| (domain.clone(), vec![SIM_RESOLVED_IP.to_string()]))
+            .collect();
+
+        Self {
+            hostname: Some("DEMO-LAPTOP".to_string()),
+            interfaces: vec![InterfaceAddress::new("wlan0", SIM_LOCAL_IP)],
+            wifi: WifiDetails {
+                ssid: Some("Home_WiFi_5G".to_string()),
+                rssi: Some(-45),
+                interface_desc: Some("Intel Wi-Fi 6 AX201 160MHz".to_string()),
+                adapter_state: WifiAdapterState::Connected,
+                tx_rate_kbps: Some(866_000),
+                channel_frequency_khz: Some(5_180_000),
+                current_phy_type: Some(10),
+            },
+            connection_type: ConnectionType::Wifi,
+            encryption: safe_check(SecurityCheckType::Encryption, Some("WPA2")),
+            evil_twin: safe_check(SecurityCheckType::EvilTwin, None),
+            gateway: Some(SIM_GATEWAY.to_string()),
+            arp_entries: vec![
+                arp_entry(SIM_GATEWAY, SIM_GATEWAY_MAC),
+                arp_entry("192.168.1.20", "F0:18:98:AA:BB:01"),
+                arp_entry("192.168.1.35", "3C:06:30:AA:BB:02"),
+                arp_entry("192.168.1.50", "A4:77:33:AA:BB:03"),
+            ],
+            dhcp: Some(DhcpInfo {
+                enabled: true,
+                server: Some(SIM_GATEWAY.to_string()),
+                lease_time_secs: Some(86_400),
+                dns_servers: vec![SIM_GATEWAY.to_string()],
+                ..Default::default()
+            }),
+            dns_servers: vec![SIM_GATEWAY.to_string()],
+            dns_ok: true,
+            http_ok: true,
+            dnssec: DnssecStatus::Validating,
+            resolver_dnssec: HashMap::new(),
+            system_resolutions: resolved.clone(),
+            direct_resolutions: resolved,
+            txt_records: HashMap::new(),
+            reverse_dns: HashMap::from([(SIM_GATEWAY.to_string(), "router.lan".to_string())]),
+            ip_locations: HashMap::new(),
+            dns_latencies: HashMap::from([(SIM_GATEWAY.to_string(), 12.0)]),
+            mdns: HashMap::from([(
+                "192.168.1.50".to_string(),
+                MdnsDeviceInfo {
+                    name: "Living Room TV".to_string(),
+                    service_types: vec!["_googlecast._tcp.local.".to_string()],
+                },
+            )]),
+        }
+    }
+
+    /// A network in the state described by `scenario`.
+    pub fn scenario(scenario: DiagnosticScenario) -> Self {
+        let mut sim = Self::healthy();
+        match scenario {
+            DiagnosticScenario::AllGood => {}
+            DiagnosticScenario::WifiDisabled => {
+                sim.go_offline();
+                sim.wifi = WifiDetails {
+                    adapter_state: WifiAdapterState::Disabled,
+                    ..Default::default()
+                };
+            }
+            DiagnosticScenario::WifiNotConnected => {
+                sim.go_offline();
+                sim.wifi = WifiDetails {
+                    interface_desc: Some("Intel Wi-Fi 6 AX201 160MHz".to_string()),
+                    adapter_state: WifiAdapterState::Disconnected,
+                    ..Default::default()
+                };
+            }
+            DiagnosticScenario::RouterUnreachable => {
+                sim.gateway = None;
+                sim.arp_entries.clear();
+                sim.set_internet(false, false);
+            }
+            DiagnosticScenario::NoInternet => sim.set_internet(false, false),
+            DiagnosticScenario::DnsFailure => sim.set_internet(false, true),
+            DiagnosticScenario::HttpBlocked => sim.set_internet(true, false),
+            DiagnosticScenario::WeakSignal => {
+                sim.wifi.rssi = Some(-78);
+                sim.wifi.tx_rate_kbps = Some(54_000);
+            }
+            DiagnosticScenario::DhcpFailure => {
+                sim.interfaces = vec![InterfaceAddress::new(
+                    "wlan0",
+                    Ipv4Addr::new(169, 254, 12, 34),
+                )];
+                sim.dhcp = None;
+                sim.dns_servers.clear();
+                sim.gateway = None;
+                sim.arp_entries.clear();
+                sim.set_internet(false, false);
+            }
+        }
+        sim
+    }
+
+    /// No addresses, no gateway, nothing reachable.
+    fn go_offline(&mut self) {
+        self.interfaces.clear();
+        self.connection_type = ConnectionType::Unknown;
+        self.gateway = None;
+        self.arp_entries.clear();
+        self.dhcp = None;
+        self.dns_servers.clear();
+        self.encryption = safe_check(SecurityCheckType::Encryption, None);
+        self.set_internet(false, false);
+    }
+
+    fn set_internet(&mut self, dns_ok: bool, http_ok: bool) {
+        self.dns_ok = dns_ok;
+        self.http_ok = http_ok;
+        if !dns_ok {
+            self.dnssec = DnssecStatus::Unknown;
+            self.resolver_dnssec.clear();
+            self.dns_latencies.clear();
+            self.system_resolutions.clear();
+            self.direct_resolutions.clear();
+            self.txt_records.clear();
+        }
+    }
+}
+
+fn safe_check(check_type: SecurityCheckType, details: Option<&str>) -> SecurityCheck {
+    SecurityCheck {
+        check_type,
+        status: SecurityStatus::Safe,
+        details: details.map(str::to_string),
+    }
+}
+
+/// Entry of `resolutions` for `domain`, or for the closest `*.zone` above it.
+fn answers<'a>(resolutions: &'a HashMap<String, Vec<String>>, domain: &str) -> &'a [String] {
+    let wildcards = domain
+        .match_indices('.')
+        .map(|(i, _)| format!("*{}", &domain[i..]));
+    std::iter::once(domain.to_string())
+        .chain(wildcards)
+        .find_map(|key| resolutions.get(&key))
+        .map_or(&[], Vec::as_slice)
+}
+
+/// A simulated DNS server answering every lookup after the same delay.
+struct SimulatedConnection {
+    latency_ms: f64,
+}
+
+impl DnsConnection for SimulatedConnection {
+    fn timed_lookup<'a>(&'a self, _name: &'a str) -> ProbeFuture<'a, Option<f64>> {
+        Box::pin(std::future::ready(Some(self.latency_ms)))
+    }
+}
+
+fn arp_entry(ip: &str, mac: &str) -> ArpEntry {
+    ArpEntry {
+        ip: ip.to_string(),
+        mac: mac.to_string(),
+    }
+}
+
+impl NetworkBackend for SimulatedBackend {
+    fn hostname(&self) -> Option<String> {
+        self.hostname.clone()
+    }
+
+    fn interfaces(&self) -> Vec<InterfaceAddress> {
+        self.interfaces.clone()
+    }
+
+    fn wifi_info(&self) -> WifiDetails {
+        self.wifi.clone()
+    }
+
+    fn connection_type(&self, _adapter: &str) -> ConnectionType {
+        self.connection_type
+    }
+
+    fn encryption_check(&self) -> SecurityCheck {
+        self.encryption.clone()
+    }
+
+    fn evil_twin_check(&self) -> SecurityCheck {
+        self.evil_twin.clone()
+    }
+
+    fn default_gateway(&self) -> Option<String> {
+        self.gateway.clone()
+    }
+
+    fn router_mac(&self, gateway_ip: &str) -> Option<String> {
+        self.arp_entries
+            .iter()
+            .find(|e| e.ip == gateway_ip)
+            .map(|e| e.mac.clone())
+    }
+
+    fn arp_entries(&self) -> Vec<ArpEntry> {
+        self.arp_entries.clone()
+    }
+
+    fn ping_sweep(&self, _gateway_ip: &str, _cancel: &Cancellation) {}
+
+    fn dhcp_info(&self, _adapter: Option<&str>) -> Option<DhcpInfo> {
+        self.dhcp.clone()
+    }
+
+    fn dns_servers(&self) -> Result<Vec<String>, DnsError> {
+        Ok(self.dns_servers.clone())
+    }
+
+    fn dns_probe(&self, _targets: &ProbeTargets) -> bool {
+        self.dns_ok
+    }
+
+    fn http_probe(&self, _targets: &ProbeTargets) -> bool {
+        self.http_ok
+    }
+
+    fn resolve_system(&self, domain: &str) -> Vec<String> {
+        answers(&self.system_resolutions, domain)
+            .iter()
+            .filter(|ip| !ip.contains(':'))
+            .cloned()
+            .collect()
+    }
+
+    fn resolve_via(&self, domain: &str, _server: &str) -> Vec<String> {
+        answers(&self.direct_resolutions, domain)
+            .iter()
+            .filter(|ip| !ip.contains(':'))
+            .cloned()
+            .collect()
+    }
+
+    fn resolve_system_v6(&self, domain: &str) -> Vec<String> {
+        answers(&self.system_resolutions, domain)
+            .iter()
+            .filter(|ip| ip.contains(':'))
+            .cloned()
+            .collect()
+    }
+
+    fn resolve_doh(&self, domain: &str, _url: &str) -> Vec<String> {
+        answers(&self.direct_resolutions, domain).to_vec()
+    }
+
+    fn query_txt(
+        &self,
+        domain: &str,
+        _server: &str,
+        _class: DnsClass,
+        _timeout: Duration,
+    ) -> Vec<String> {
+        answers(&self.txt_records, domain.trim_end_matches('.')).to_vec()
+    }
+
+    fn reverse_dns(&self, ip: &str, _timeout_ms: u64) -> Option<String> {
+        self.reverse_dns.get(ip).cloned()
+    }
+
+    fn ip_location(&self, ip: &str, _targets: &ProbeTargets) -> Option<IpInfoResponse> {
+        self.ip_locations.get(ip).cloned()
+    }
+
+    fn dnssec_status(&self) -> DnssecStatus {
+        self.dnssec
+    }
+
+    fn resolver_dnssec(&self, server: IpAddr, _timeout: Duration) -> DnssecStatus {
+        let server = server.to_string();
+        match self.resolver_dnssec.get(&server) {
+            Some(status) => *status,
+            None if self.dns_servers.contains(&server) => self.dnssec,
+            None => DnssecStatus::Unknown,
+        }
+    }
+
+    fn mdns_discover(
+        &self,
+        _timeout: Duration,
+        _cancel: &Cancellation,
+    ) -> HashMap<String, MdnsDeviceInfo> {
+        self.mdns.clone()
+    }
+
+    fn dns_connect_async<'a>(
+        &'a self,
+        endpoint: &'a DnsEndpoint,
+        _timeout: Duration,
+    ) -> ProbeFuture<'a, Option<Box<dyn DnsConnection>>> {
+        let connection = self
+            .dns_latencies
+            .get(&endpoint.to_string())
+            .map(|&latency_ms| {
+                Box::new(SimulatedConnection { latency_ms }) as Box<dyn DnsConnection>
+            });
+        Box::pin(std::future::ready(connection))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::{diagnose_scenario, run_diagnostics_with_backend};
+    use crate::domain::{DeviceType, NodeId, Settings, Status};
+    use crate::infrastructure::security::check_wifi_security_with_backend;
+
+    fn statuses(sim: &SimulatedBackend) -> Vec<(NodeId, Status)> {
+        run_diagnostics_with_backend(sim, &Settings::default())
+            .nodes
+            .iter()
+            .map(|n| (n.id, n.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_scenario_is_reproduced() {
+        for &scenario in DiagnosticScenario::all() {
+            let sim = SimulatedBackend::scenario(scenario);
+            let snapshot = run_diagnostics_with_backend(&sim, &Settings::default());
+            assert_eq!(
+                diagnose_scenario(&snapshot).scenario,
+                scenario,
+                "snapshot: {:?}",
+                snapshot
+            );
+        }
+    }
+
+    #[test]
+    fn test_healthy_snapshot() {
+        let sim = SimulatedBackend::healthy();
+        let snapshot = run_diagnostics_with_backend(&sim, &Settings::default());
+
+        assert_eq!(snapshot.summary_key, "summary.ok");
+        assert!(snapshot.nodes.iter().all(|n| n.status == Status::Ok));
+        assert_eq!(snapshot.computer.hostname.as_deref(), Some("DEMO-LAPTOP"));
+        assert_eq!(snapshot.computer.local_ip.as_deref(), Some("192.168.1.42"));
+        assert_eq!(snapshot.network.connection_type, ConnectionType::Wifi);
+        assert_eq!(snapshot.network.channel, Some(36));
+        assert_eq!(snapshot.network.frequency.as_deref(), Some("5 GHz"));
+        assert_eq!(snapshot.network.link_speed_mbps, Some(866));
+        assert_eq!(snapshot.network.encryption.as_deref(), Some("WPA2"));
+        assert_eq!(snapshot.network.dns_servers, vec![SIM_GATEWAY.to_string()]);
+        assert_eq!(
+            snapshot.router.gateway_mac.as_deref(),
+            Some(SIM_GATEWAY_MAC)
+        );
+        assert!(snapshot.router.vendor.is_some());
+    }
+
+    #[test]
+    fn test_scenario_node_statuses() {
+        use NodeId::*;
+        use Status::*;
+
+        let cases = [
+            (DiagnosticScenario::WifiDisabled, [Ok, Fail, Warn, Fail]),
+            (DiagnosticScenario::RouterUnreachable, [Ok, Ok, Warn, Fail]),
+            (DiagnosticScenario::DnsFailure, [Ok, Ok, Ok, Warn]),
+            (DiagnosticScenario::DhcpFailure, [Fail, Fail, Warn, Fail]),
+        ];
+        for (scenario, expected) in cases {
+            let got = statuses(&SimulatedBackend::scenario(scenario));
+            let ids: Vec<NodeId> = got.iter().map(|(id, _)| *id).collect();
+            assert_eq!(ids, vec![Computer, Wifi, RouterUpnp, Internet]);
+            let got: Vec<Status> = got.into_iter().map(|(_, s)| s).collect();
+            assert_eq!(got, expected, "{:?}", scenario);
+        }
+    }
+
+    #[test]
+    fn test_simulated_device_scan() {
+        let sim = SimulatedBackend::healthy();
+        let devices = crate::diagnostics::scan_network_devices_with_backend(&sim, None);
+
+        let ips: Vec<&str> = devices.iter().map(|d| d.ip.as_str()).collect();
+        assert_eq!(
+            ips,
+            vec![
+                "192.168.1.1",
+                "192.168.1.20",
+                "192.168.1.35",
+                "192.168.1.50"
+            ]
+        );
+        let router = &devices[0];
+        assert!(router.is_gateway);
+        assert_eq!(router.device_type, DeviceType::Router);
+        assert_eq!(router.hostname.as_deref(), Some("router.lan"));
+        assert_eq!(devices[3].hostname.as_deref(), Some("Living Room TV"));
+    }
+
+    #[test]
+    fn test_simulated_security_checks() {
+        let targets = ProbeTargets::default();
+
+        let report = check_wifi_security_with_backend(&SimulatedBackend::healthy(), &targets);
+        assert_eq!(report.overall_status, SecurityStatus::Safe);
+        assert_eq!(report.network_ssid.as_deref(), Some("Home_WiFi_5G"));
+
+        // Another host claims the gateway's MAC
+        let mut sim = SimulatedBackend::healthy();
+        sim.arp_entries
+            .push(arp_entry("192.168.1.66", SIM_GATEWAY_MAC));
+        let report = check_wifi_security_with_backend(&sim, &targets);
+        assert_eq!(report.overall_status, SecurityStatus::Danger);
+
+        // System resolver answers differently from the trusted one
+        let mut sim = SimulatedBackend::healthy();
+        sim.system_resolutions.insert(
+            targets.hijack_test_domains[0].clone(),
+            vec!["10.0.0.99".to_string()],
+        );
+        let report = check_wifi_security_with_backend(&sim, &targets);
+        let dns = report
+            .checks
+            .iter()
+            .find(|c| c.check_type == SecurityCheckType::DnsHijacking)
+            .unwrap();
+        assert_eq!(dns.status, SecurityStatus::Warning);
+    }
+
+    #[test]
+    fn test_simulated_wildcard_resolutions() {
+        let mut sim = SimulatedBackend::default();
+        sim.system_resolutions
+            .insert("*.whoami.test".into(), vec!["192.0.2.53".into()]);
+        sim.system_resolutions
+            .insert("a.whoami.test".into(), vec!["192.0.2.1".into()]);
+
+        assert_eq!(sim.resolve_system("x1.whoami.test"), ["192.0.2.53"]);
+        assert_eq!(sim.resolve_system("x.y.whoami.test"), ["192.0.2.53"]);
+        assert_eq!(sim.resolve_system("a.whoami.test"), ["192.0.2.1"]);
+        assert!(sim.resolve_system("whoami.test").is_empty());
+        assert!(sim.resolve_via("x1.whoami.test", "1.1.1.1:53").is_empty());
+    }
+}
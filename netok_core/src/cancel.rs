@@ -0,0 +1,260 @@
+//! Cancellation and deadlines for long-running operations.
+//!
+//! The caller keeps a [`CancelToken`] and may give an overall deadline;
+//! [`Cancellation`] combines the two for the operation. Operations check it
+//! between steps, the ping sweep and mDNS browse check it inside their loops,
+//! and network queries are raced against it. A stopped operation returns what
+//! it had so far as a [`Partial`] marked `cancelled` or `timed_out`.
+//!
+//! A platform command that is already running (a `netsh` BSS scan, a single
+//! `ping`) is not killed; the operation stops as soon as it returns.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+// ==================== Domain Types ====================
+
+/// How an operation ended.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Completion {
+    /// Every step ran.
+    #[default]
+    Complete,
+    /// Stopped through its [`CancelToken`].
+    Cancelled,
+    /// Stopped by its deadline.
+    TimedOut,
+}
+
+/// Result of an operation that may have stopped early.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Partial<T> {
+    /// Everything gathered before the operation ended.
+    pub value: T,
+    pub completion: Completion,
+}
+
+impl<T> Partial<T> {
+    pub fn new(value: T, completion: Completion) -> Self {
+        Self { value, completion }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completion == Completion::Complete
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Partial<U> {
+        Partial {
+            value: f(self.value),
+            completion: self.completion,
+        }
+    }
+}
+
+// ==================== Cancel Token ====================
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Handle for stopping an operation from another task or thread.
+///
+/// Clones share state: cancelling any clone cancels them all.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    state: Arc<TokenState>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask every operation holding this token to stop.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst);
+        self.state.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once [`cancel`](Self::cancel) has been called.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.state.notify.notified();
+            tokio::pin!(notified);
+            // Register before checking the flag so a concurrent cancel isn't missed
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+// ==================== Cancellation ====================
+
+/// Stop conditions of one operation: a token and an optional deadline.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    token: CancelToken,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Stop when `token` is cancelled or `deadline` (from now) has passed.
+    pub fn new(token: &CancelToken, deadline: Option<Duration>) -> Self {
+        Self {
+            token: token.clone(),
+            deadline: deadline.map(|d| Instant::now() + d),
+        }
+    }
+
+    /// Never stops.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Why the operation should stop, or `None` to carry on.
+    pub fn stopped(&self) -> Option<Completion> {
+        if self.token.is_cancelled() {
+            Some(Completion::Cancelled)
+        } else if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(Completion::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped().is_some()
+    }
+
+    /// Time left before the deadline, `None` without one.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    /// Resolves with the reason once the operation should stop.
+    pub async fn wait(&self) -> Completion {
+        let deadline = async {
+            match self.deadline {
+                Some(d) => tokio::time::sleep_until(d.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            biased;
+            _ = self.token.cancelled() => Completion::Cancelled,
+            _ = deadline => Completion::TimedOut,
+        }
+    }
+
+    /// Run `step` unless already stopped, abandoning it if a stop comes first.
+    pub async fn run<F: Future>(&self, step: F) -> Result<F::Output, Completion> {
+        if let Some(reason) = self.stopped() {
+            return Err(reason);
+        }
+        tokio::select! {
+            biased;
+            reason = self.wait() => Err(reason),
+            output = step => Ok(output),
+        }
+    }
+
+    /// [`run`](Self::run) for one step of a sequence: skipped once an earlier
+    /// step was stopped, and a stop is recorded in `completion`.
+    pub(crate) async fn step<F: Future>(
+        &self,
+        completion: &mut Completion,
+        step: F,
+    ) -> Option<F::Output> {
+        if *completion != Completion::Complete {
+            return None;
+        }
+        match self.run(step).await {
+            Ok(output) => Some(output),
+            Err(reason) => {
+                *completion = reason;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Completion::TimedOut).unwrap(),
+            "\"timed_out\""
+        );
+        let partial = Partial::new(vec![1], Completion::Cancelled);
+        let json = serde_json::to_value(&partial).unwrap();
+        assert_eq!(json["completion"], "cancelled");
+        assert_eq!(json["value"][0], 1);
+    }
+
+    #[test]
+    fn test_token_clones_share_state() {
+        let token = CancelToken::new();
+        let cancellation = Cancellation::new(&token, None);
+        assert_eq!(cancellation.stopped(), None);
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(cancellation.stopped(), Some(Completion::Cancelled));
+    }
+
+    #[test]
+    fn test_deadline_expires() {
+        let cancellation = Cancellation::new(&CancelToken::new(), Some(Duration::ZERO));
+        assert_eq!(cancellation.stopped(), Some(Completion::TimedOut));
+        assert_eq!(cancellation.remaining(), Some(Duration::ZERO));
+        assert!(!Cancellation::none().is_stopped());
+    }
+
+    #[tokio::test]
+    async fn test_run_completes_before_stop() {
+        let cancellation = Cancellation::new(&CancelToken::new(), Some(Duration::from_secs(5)));
+        assert_eq!(cancellation.run(async { 3 }).await, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn test_run_abandons_step_on_cancel() {
+        let token = CancelToken::new();
+        let cancellation = Cancellation::new(&token, None);
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        let result = cancellation
+            .run(tokio::time::sleep(Duration::from_secs(30)))
+            .await;
+        assert_eq!(result, Err(Completion::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_run_abandons_step_on_deadline() {
+        let cancellation = Cancellation::new(&CancelToken::new(), Some(Duration::from_millis(20)));
+        let result = cancellation
+            .run(tokio::time::sleep(Duration::from_secs(30)))
+            .await;
+        assert_eq!(result, Err(Completion::TimedOut));
+    }
+}
@@ -0,0 +1,1005 @@
+//! Record-and-replay of raw probe output.
+//!
+//! A [`Capture`] is a self-contained, versioned JSON file holding everything a
+//! diagnostics run read from the machine:
+//!
+//! - `calls`: every [`NetworkBackend`] call with its arguments and response,
+//!   recorded by [`RecordingBackend`];
+//! - `raw`: the unparsed output behind those responses — PowerShell, netsh,
+//!   `ip` and `route` output, lease files, WLAN API struct fields, DNS packets
+//!   and HTTP statuses — collected from the infrastructure layer while a
+//!   recording is active.
+//!
+//! [`ReplayBackend`] answers from the recorded calls, so feeding a capture
+//! through `run_diagnostics_with_backend` (or the device scan, or the security
+//! checks) offline reproduces the snapshot taken on the user's machine. The
+//! raw section is there for parser debugging and bug reports.
+
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::net::IpAddr;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use crate::backend::{DnsConnection, InterfaceAddress, NetworkBackend, ProbeFuture};
+use crate::cancel::Cancellation;
+use crate::diagnostics::IpInfoResponse;
+use crate::domain::{ConnectionType, DhcpInfo, DnsClass, DnssecStatus, ProbeTargets};
+use crate::error::{CaptureError, DnsError, StorageError};
+use crate::infrastructure::arp::ArpEntry;
+use crate::infrastructure::dns_transport::DnsEndpoint;
+use crate::infrastructure::mdns::MdnsDeviceInfo;
+use crate::infrastructure::security::{SecurityCheck, SecurityCheckType, SecurityStatus};
+use crate::infrastructure::wifi::WifiDetails;
+
+/// Version of the capture file format. Bumped on incompatible changes.
+pub const CAPTURE_FORMAT_VERSION: u32 = 1;
+
+// ==================== Domain Types ====================
+
+/// A recorded probe session.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Capture {
+    pub format_version: u32,
+    /// Version of netok that recorded the capture.
+    pub netok_version: String,
+    /// OS the capture was recorded on (`windows`, `linux`, `macos`).
+    pub platform: String,
+    /// RFC 3339 UTC time the recording started.
+    pub captured_at: String,
+    /// Backend calls in the order they completed.
+    pub calls: Vec<ProbeCall>,
+    /// Unparsed infrastructure output, in the order it was produced.
+    pub raw: Vec<RawOutput>,
+}
+
+/// One [`NetworkBackend`] call and what it returned.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProbeCall {
+    /// Backend method name, e.g. `wifi_info`.
+    pub probe: String,
+    /// Arguments that select the answer, e.g. the domain for `resolve_system`.
+    pub args: Vec<String>,
+    /// The method's return value as JSON.
+    pub response: Value,
+    pub duration_ms: u64,
+}
+
+/// Unparsed output from the platform, as seen by the infrastructure layer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RawOutput {
+    /// An external command (PowerShell, netsh, `ip`, `route`, `ipconfig`).
+    Command {
+        program: String,
+        args: Vec<String>,
+        /// `None` if the command could not be started or was killed.
+        exit_code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+    /// A system file read by a parser (DHCP lease files).
+    File { path: String, contents: String },
+    /// Fields read from a WLAN API struct.
+    Wlan { call: String, fields: Value },
+    /// A DNS query sent on a raw socket and the reply, hex-encoded.
+    Dns {
+        server: String,
+        query: String,
+        response: Option<String>,
+    },
+    /// An HTTP probe and the status it got.
+    Http {
+        url: String,
+        status: Option<u16>,
+        error: Option<String>,
+    },
+}
+
+impl RawOutput {
+    /// Output of a finished (or failed to start) command.
+    pub(crate) fn command(cmd: &Command, output: &std::io::Result<Output>) -> Self {
+        let (exit_code, stdout, stderr) = match output {
+            Ok(o) => (
+                o.status.code(),
+                String::from_utf8_lossy(&o.stdout).into_owned(),
+                String::from_utf8_lossy(&o.stderr).into_owned(),
+            ),
+            Err(e) => (None, String::new(), e.to_string()),
+        };
+        RawOutput::Command {
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args: cmd
+                .get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect(),
+            exit_code,
+            stdout,
+            stderr,
+        }
+    }
+}
+
+impl Capture {
+    /// Serialize as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, CaptureError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            StorageError::Serialize {
+                what: "capture",
+                message: e.to_string(),
+            }
+            .into()
+        })
+    }
+
+    /// Parse a capture, rejecting files from a newer format version.
+    pub fn from_json(text: &str) -> Result<Self, CaptureError> {
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| CaptureError::Invalid(e.to_string()))?;
+        let found = value
+            .get("format_version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| CaptureError::Invalid("missing format_version".to_string()))?;
+        if found > CAPTURE_FORMAT_VERSION as u64 {
+            return Err(CaptureError::UnsupportedVersion {
+                found: found.min(u32::MAX as u64) as u32,
+                supported: CAPTURE_FORMAT_VERSION,
+            });
+        }
+        serde_json::from_value(value).map_err(|e| CaptureError::Invalid(e.to_string()))
+    }
+}
+
+/// Write a capture to `path` as JSON.
+pub fn save_capture(capture: &Capture, path: &Path) -> Result<(), CaptureError> {
+    let text = capture.to_json()?;
+    std::fs::write(path, text).map_err(|e| StorageError::io("write capture")(e).into())
+}
+
+/// Read a capture written by [`save_capture`].
+pub fn load_capture(path: &Path) -> Result<Capture, CaptureError> {
+    let text = std::fs::read_to_string(path).map_err(StorageError::io("read capture"))?;
+    Capture::from_json(&text)
+}
+
+/// Async [`save_capture`].
+pub async fn save_capture_async(capture: &Capture, path: &Path) -> Result<(), CaptureError> {
+    let text = capture.to_json()?;
+    tokio::fs::write(path, text)
+        .await
+        .map_err(|e| StorageError::io("write capture")(e).into())
+}
+
+/// Async [`load_capture`].
+pub async fn load_capture_async(path: &Path) -> Result<Capture, CaptureError> {
+    let text = tokio::fs::read_to_string(path)
+        .await
+        .map_err(StorageError::io("read capture"))?;
+    Capture::from_json(&text)
+}
+
+// ==================== Raw Output Sink ====================
+
+/// Raw outputs produced while at least one recording is active.
+struct RawSink {
+    active: usize,
+    outputs: Vec<RawOutput>,
+}
+
+static RAW_SINK: Mutex<RawSink> = Mutex::new(RawSink {
+    active: 0,
+    outputs: Vec::new(),
+});
+
+/// Keep a raw output if a recording is active. `output` is only built then.
+pub(crate) fn record_raw(output: impl FnOnce() -> RawOutput) {
+    if let Ok(mut sink) = RAW_SINK.lock() {
+        if sink.active > 0 {
+            sink.outputs.push(output());
+        }
+    }
+}
+
+/// Lowercase hex encoding for packet bytes.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ==================== Recording ====================
+
+/// Wraps a backend and records every call it answers.
+///
+/// Raw outputs are process-wide: overlapping recordings each get everything
+/// produced while they were active.
+pub struct RecordingBackend {
+    inner: Arc<dyn NetworkBackend>,
+    calls: Mutex<Vec<ProbeCall>>,
+    captured_at: String,
+    /// Index of the first raw output belonging to this recording.
+    raw_start: usize,
+}
+
+impl RecordingBackend {
+    pub fn new(inner: Arc<dyn NetworkBackend>) -> Self {
+        let raw_start = match RAW_SINK.lock() {
+            Ok(mut sink) => {
+                sink.active += 1;
+                sink.outputs.len()
+            }
+            Err(_) => 0,
+        };
+        let captured_at = OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap_or_default();
+        Self {
+            inner,
+            calls: Mutex::new(Vec::new()),
+            captured_at,
+            raw_start,
+        }
+    }
+
+    /// Stop recording and return the capture.
+    pub fn finish(self) -> Capture {
+        let calls = self
+            .calls
+            .lock()
+            .map(|mut calls| std::mem::take(&mut *calls))
+            .unwrap_or_default();
+        let raw = RAW_SINK
+            .lock()
+            .map(|sink| {
+                sink.outputs
+                    .get(self.raw_start..)
+                    .unwrap_or_default()
+                    .to_vec()
+            })
+            .unwrap_or_default();
+        Capture {
+            format_version: CAPTURE_FORMAT_VERSION,
+            netok_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: std::env::consts::OS.to_string(),
+            captured_at: self.captured_at.clone(),
+            calls,
+            raw,
+        }
+    }
+
+    fn push(&self, probe: &str, args: &[&str], response: &impl Serialize, started: Instant) {
+        let call = ProbeCall {
+            probe: probe.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            response: serde_json::to_value(response).unwrap_or(Value::Null),
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(call);
+        }
+    }
+
+    fn record<T: Serialize>(&self, probe: &str, args: &[&str], call: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = call();
+        self.push(probe, args, &result, started);
+        result
+    }
+
+    async fn record_async<T: Serialize>(
+        &self,
+        probe: &str,
+        args: &[&str],
+        call: impl Future<Output = T>,
+    ) -> T {
+        let started = Instant::now();
+        let result = call.await;
+        self.push(probe, args, &result, started);
+        result
+    }
+}
+
+impl Drop for RecordingBackend {
+    fn drop(&mut self) {
+        if let Ok(mut sink) = RAW_SINK.lock() {
+            sink.active = sink.active.saturating_sub(1);
+            if sink.active == 0 {
+                sink.outputs.clear();
+            }
+        }
+    }
+}
+
+/// Recorded argument for a DNS query class.
+fn class_arg(class: DnsClass) -> &'static str {
+    match class {
+        DnsClass::Internet => "internet",
+        DnsClass::Chaos => "chaos",
+    }
+}
+
+// Probe targets are not part of the key for `dns_probe`/`http_probe` (the
+// answer is "did the configured probes succeed") nor for `ip_location`, so a
+// replay with different settings still reproduces the recorded result.
+impl NetworkBackend for RecordingBackend {
+    fn hostname(&self) -> Option<String> {
+        self.record("hostname", &[], || self.inner.hostname())
+    }
+
+    fn interfaces(&self) -> Vec<InterfaceAddress> {
+        self.record("interfaces", &[], || self.inner.interfaces())
+    }
+
+    fn wifi_info(&self) -> WifiDetails {
+        self.record("wifi_info", &[], || self.inner.wifi_info())
+    }
+
+    fn connection_type(&self, adapter: &str) -> ConnectionType {
+        self.record("connection_type", &[adapter], || {
+            self.inner.connection_type(adapter)
+        })
+    }
+
+    fn encryption_check(&self) -> SecurityCheck {
+        self.record("encryption_check", &[], || self.inner.encryption_check())
+    }
+
+    fn evil_twin_check(&self) -> SecurityCheck {
+        self.record("evil_twin_check", &[], || self.inner.evil_twin_check())
+    }
+
+    fn default_gateway(&self) -> Option<String> {
+        self.record("default_gateway", &[], || self.inner.default_gateway())
+    }
+
+    fn router_mac(&self, gateway_ip: &str) -> Option<String> {
+        self.record("router_mac", &[gateway_ip], || {
+            self.inner.router_mac(gateway_ip)
+        })
+    }
+
+    fn arp_entries(&self) -> Vec<ArpEntry> {
+        self.record("arp_entries", &[], || self.inner.arp_entries())
+    }
+
+    fn ping_sweep(&self, gateway_ip: &str, cancel: &Cancellation) {
+        self.record("ping_sweep", &[gateway_ip], || {
+            self.inner.ping_sweep(gateway_ip, cancel)
+        })
+    }
+
+    fn dhcp_info(&self, adapter: Option<&str>) -> Option<DhcpInfo> {
+        let args: Vec<&str> = adapter.into_iter().collect();
+        self.record("dhcp_info", &args, || self.inner.dhcp_info(adapter))
+    }
+
+    fn dns_servers(&self) -> Result<Vec<String>, DnsError> {
+        let started = Instant::now();
+        let result = self.inner.dns_servers();
+        let recorded = result.as_ref().map_err(|e| e.to_string());
+        self.push("dns_servers", &[], &recorded, started);
+        result
+    }
+
+    fn dns_probe(&self, targets: &ProbeTargets) -> bool {
+        self.record("dns_probe", &[], || self.inner.dns_probe(targets))
+    }
+
+    fn http_probe(&self, targets: &ProbeTargets) -> bool {
+        self.record("http_probe", &[], || self.inner.http_probe(targets))
+    }
+
+    fn resolve_system(&self, domain: &str) -> Vec<String> {
+        self.record("resolve_system", &[domain], || {
+            self.inner.resolve_system(domain)
+        })
+    }
+
+    fn resolve_via(&self, domain: &str, server: &str) -> Vec<String> {
+        self.record("resolve_via", &[domain, server], || {
+            self.inner.resolve_via(domain, server)
+        })
+    }
+
+    fn resolve_system_v6(&self, domain: &str) -> Vec<String> {
+        self.record("resolve_system_v6", &[domain], || {
+            self.inner.resolve_system_v6(domain)
+        })
+    }
+
+    fn resolve_doh(&self, domain: &str, url: &str) -> Vec<String> {
+        self.record("resolve_doh", &[domain, url], || {
+            self.inner.resolve_doh(domain, url)
+        })
+    }
+
+    fn query_txt(
+        &self,
+        domain: &str,
+        server: &str,
+        class: DnsClass,
+        timeout: Duration,
+    ) -> Vec<String> {
+        self.record("query_txt", &[domain, server, class_arg(class)], || {
+            self.inner.query_txt(domain, server, class, timeout)
+        })
+    }
+
+    fn reverse_dns(&self, ip: &str, timeout_ms: u64) -> Option<String> {
+        self.record("reverse_dns", &[ip], || {
+            self.inner.reverse_dns(ip, timeout_ms)
+        })
+    }
+
+    fn ip_location(&self, ip: &str, targets: &ProbeTargets) -> Option<IpInfoResponse> {
+        self.record("ip_location", &[ip], || self.inner.ip_location(ip, targets))
+    }
+
+    fn dnssec_status(&self) -> DnssecStatus {
+        self.record("dnssec_status", &[], || self.inner.dnssec_status())
+    }
+
+    fn resolver_dnssec(&self, server: IpAddr, timeout: Duration) -> DnssecStatus {
+        self.record("resolver_dnssec", &[&server.to_string()], || {
+            self.inner.resolver_dnssec(server, timeout)
+        })
+    }
+
+    fn mdns_discover(
+        &self,
+        timeout: Duration,
+        cancel: &Cancellation,
+    ) -> HashMap<String, MdnsDeviceInfo> {
+        self.record("mdns_discover", &[], || {
+            self.inner.mdns_discover(timeout, cancel)
+        })
+    }
+
+    fn dns_probe_async<'a>(&'a self, targets: &'a ProbeTargets) -> ProbeFuture<'a, bool> {
+        Box::pin(self.record_async("dns_probe", &[], self.inner.dns_probe_async(targets)))
+    }
+
+    fn http_probe_async<'a>(&'a self, targets: &'a ProbeTargets) -> ProbeFuture<'a, bool> {
+        Box::pin(self.record_async("http_probe", &[], self.inner.http_probe_async(targets)))
+    }
+
+    fn resolve_system_async<'a>(&'a self, domain: &'a str) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            self.record_async(
+                "resolve_system",
+                &[domain],
+                self.inner.resolve_system_async(domain),
+            )
+            .await
+        })
+    }
+
+    fn resolve_via_async<'a>(
+        &'a self,
+        domain: &'a str,
+        server: &'a str,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            self.record_async(
+                "resolve_via",
+                &[domain, server],
+                self.inner.resolve_via_async(domain, server),
+            )
+            .await
+        })
+    }
+
+    fn resolve_system_v6_async<'a>(&'a self, domain: &'a str) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            self.record_async(
+                "resolve_system_v6",
+                &[domain],
+                self.inner.resolve_system_v6_async(domain),
+            )
+            .await
+        })
+    }
+
+    fn resolve_doh_async<'a>(
+        &'a self,
+        domain: &'a str,
+        url: &'a str,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            self.record_async(
+                "resolve_doh",
+                &[domain, url],
+                self.inner.resolve_doh_async(domain, url),
+            )
+            .await
+        })
+    }
+
+    fn query_txt_async<'a>(
+        &'a self,
+        domain: &'a str,
+        server: &'a str,
+        class: DnsClass,
+        timeout: Duration,
+    ) -> ProbeFuture<'a, Vec<String>> {
+        Box::pin(async move {
+            self.record_async(
+                "query_txt",
+                &[domain, server, class_arg(class)],
+                self.inner.query_txt_async(domain, server, class, timeout),
+            )
+            .await
+        })
+    }
+
+    fn reverse_dns_async<'a>(
+        &'a self,
+        ip: &'a str,
+        timeout_ms: u64,
+    ) -> ProbeFuture<'a, Option<String>> {
+        Box::pin(async move {
+            self.record_async(
+                "reverse_dns",
+                &[ip],
+                self.inner.reverse_dns_async(ip, timeout_ms),
+            )
+            .await
+        })
+    }
+
+    fn ip_location_async<'a>(
+        &'a self,
+        ip: &'a str,
+        targets: &'a ProbeTargets,
+    ) -> ProbeFuture<'a, Option<IpInfoResponse>> {
+        Box::pin(async move {
+            self.record_async(
+                "ip_location",
+                &[ip],
+                self.inner.ip_location_async(ip, targets),
+            )
+            .await
+        })
+    }
+
+    fn dnssec_status_async(&self) -> ProbeFuture<'_, DnssecStatus> {
+        Box::pin(self.record_async("dnssec_status", &[], self.inner.dnssec_status_async()))
+    }
+
+    fn resolver_dnssec_async(
+        &self,
+        server: IpAddr,
+        timeout: Duration,
+    ) -> ProbeFuture<'_, DnssecStatus> {
+        Box::pin(async move {
+            self.record_async(
+                "resolver_dnssec",
+                &[&server.to_string()],
+                self.inner.resolver_dnssec_async(server, timeout),
+            )
+            .await
+        })
+    }
+
+    fn mdns_discover_async<'a>(
+        &'a self,
+        timeout: Duration,
+        cancel: &'a Cancellation,
+    ) -> ProbeFuture<'a, HashMap<String, MdnsDeviceInfo>> {
+        Box::pin(self.record_async(
+            "mdns_discover",
+            &[],
+            self.inner.mdns_discover_async(timeout, cancel),
+        ))
+    }
+
+    // Not recorded: benchmark lookups are for random names, so a replay
+    // could never ask for the same ones.
+    fn dns_connect_async<'a>(
+        &'a self,
+        endpoint: &'a DnsEndpoint,
+        timeout: Duration,
+    ) -> ProbeFuture<'a, Option<Box<dyn DnsConnection>>> {
+        self.inner.dns_connect_async(endpoint, timeout)
+    }
+}
+
+// ==================== Replay ====================
+
+/// Probe name and arguments identifying a recorded answer.
+type CallKey = (String, Vec<String>);
+
+/// Answers backend calls from a [`Capture`].
+///
+/// Repeated calls with the same arguments get the recorded answers in order,
+/// then the last one again. Calls missing from the capture get the same
+/// "nothing found" answer a machine without that feature would give.
+pub struct ReplayBackend {
+    answers: Mutex<HashMap<CallKey, VecDeque<Value>>>,
+}
+
+impl ReplayBackend {
+    pub fn new(capture: &Capture) -> Self {
+        let mut answers: HashMap<CallKey, VecDeque<Value>> = HashMap::new();
+        for call in &capture.calls {
+            answers
+                .entry((call.probe.clone(), call.args.clone()))
+                .or_default()
+                .push_back(call.response.clone());
+        }
+        Self {
+            answers: Mutex::new(answers),
+        }
+    }
+
+    fn answer<T: DeserializeOwned>(&self, probe: &str, args: &[&str]) -> Option<T> {
+        let key = (
+            probe.to_string(),
+            args.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+        );
+        let value = self.answers.lock().ok().and_then(|mut answers| {
+            let queue = answers.get_mut(&key)?;
+            if queue.len() > 1 {
+                queue.pop_front()
+            } else {
+                queue.front().cloned()
+            }
+        });
+        let Some(value) = value else {
+            tracing::debug!(probe, ?args, "no recorded answer");
+            return None;
+        };
+        match serde_json::from_value(value) {
+            Ok(answer) => Some(answer),
+            Err(e) => {
+                tracing::warn!(probe, ?args, error = %e, "unreadable recorded answer");
+                None
+            }
+        }
+    }
+}
+
+/// Stand-in for a security check missing from the capture.
+fn unrecorded_check(check_type: SecurityCheckType) -> SecurityCheck {
+    SecurityCheck {
+        check_type,
+        status: SecurityStatus::Warning,
+        details: None,
+    }
+}
+
+impl NetworkBackend for ReplayBackend {
+    fn hostname(&self) -> Option<String> {
+        self.answer("hostname", &[]).flatten()
+    }
+
+    fn interfaces(&self) -> Vec<InterfaceAddress> {
+        self.answer("interfaces", &[]).unwrap_or_default()
+    }
+
+    fn wifi_info(&self) -> WifiDetails {
+        self.answer("wifi_info", &[]).unwrap_or_default()
+    }
+
+    fn connection_type(&self, adapter: &str) -> ConnectionType {
+        self.answer("connection_type", &[adapter])
+            .unwrap_or_default()
+    }
+
+    fn encryption_check(&self) -> SecurityCheck {
+        self.answer("encryption_check", &[])
+            .unwrap_or_else(|| unrecorded_check(SecurityCheckType::Encryption))
+    }
+
+    fn evil_twin_check(&self) -> SecurityCheck {
+        self.answer("evil_twin_check", &[])
+            .unwrap_or_else(|| unrecorded_check(SecurityCheckType::EvilTwin))
+    }
+
+    fn default_gateway(&self) -> Option<String> {
+        self.answer("default_gateway", &[]).flatten()
+    }
+
+    fn router_mac(&self, gateway_ip: &str) -> Option<String> {
+        self.answer("router_mac", &[gateway_ip]).flatten()
+    }
+
+    fn arp_entries(&self) -> Vec<ArpEntry> {
+        self.answer("arp_entries", &[]).unwrap_or_default()
+    }
+
+    fn ping_sweep(&self, _gateway_ip: &str, _cancel: &Cancellation) {}
+
+    fn dhcp_info(&self, adapter: Option<&str>) -> Option<DhcpInfo> {
+        let args: Vec<&str> = adapter.into_iter().collect();
+        self.answer("dhcp_info", &args).flatten()
+    }
+
+    fn dns_servers(&self) -> Result<Vec<String>, DnsError> {
+        match self.answer::<Result<Vec<String>, String>>("dns_servers", &[]) {
+            Some(Ok(servers)) => Ok(servers),
+            Some(Err(message)) => Err(DnsError::CommandFailed {
+                command: "dns_servers (recorded)".to_string(),
+                message,
+            }),
+            None => Err(DnsError::CommandFailed {
+                command: "dns_servers (recorded)".to_string(),
+                message: "not in capture".to_string(),
+            }),
+        }
+    }
+
+    fn dns_probe(&self, _targets: &ProbeTargets) -> bool {
+        self.answer("dns_probe", &[]).unwrap_or(false)
+    }
+
+    fn http_probe(&self, _targets: &ProbeTargets) -> bool {
+        self.answer("http_probe", &[]).unwrap_or(false)
+    }
+
+    fn resolve_system(&self, domain: &str) -> Vec<String> {
+        self.answer("resolve_system", &[domain]).unwrap_or_default()
+    }
+
+    fn resolve_via(&self, domain: &str, server: &str) -> Vec<String> {
+        self.answer("resolve_via", &[domain, server])
+            .unwrap_or_default()
+    }
+
+    fn resolve_system_v6(&self, domain: &str) -> Vec<String> {
+        self.answer("resolve_system_v6", &[domain])
+            .unwrap_or_default()
+    }
+
+    fn resolve_doh(&self, domain: &str, url: &str) -> Vec<String> {
+        self.answer("resolve_doh", &[domain, url])
+            .unwrap_or_default()
+    }
+
+    fn query_txt(
+        &self,
+        domain: &str,
+        server: &str,
+        class: DnsClass,
+        _timeout: Duration,
+    ) -> Vec<String> {
+        self.answer("query_txt", &[domain, server, class_arg(class)])
+            .unwrap_or_default()
+    }
+
+    fn reverse_dns(&self, ip: &str, _timeout_ms: u64) -> Option<String> {
+        self.answer("reverse_dns", &[ip]).flatten()
+    }
+
+    fn ip_location(&self, ip: &str, _targets: &ProbeTargets) -> Option<IpInfoResponse> {
+        self.answer("ip_location", &[ip]).flatten()
+    }
+
+    fn dnssec_status(&self) -> DnssecStatus {
+        self.answer("dnssec_status", &[]).unwrap_or_default()
+    }
+
+    fn resolver_dnssec(&self, server: IpAddr, _timeout: Duration) -> DnssecStatus {
+        self.answer("resolver_dnssec", &[&server.to_string()])
+            .unwrap_or_default()
+    }
+
+    fn mdns_discover(
+        &self,
+        _timeout: Duration,
+        _cancel: &Cancellation,
+    ) -> HashMap<String, MdnsDeviceInfo> {
+        self.answer("mdns_discover", &[]).unwrap_or_default()
+    }
+
+    fn dns_connect_async<'a>(
+        &'a self,
+        _endpoint: &'a DnsEndpoint,
+        _timeout: Duration,
+    ) -> ProbeFuture<'a, Option<Box<dyn DnsConnection>>> {
+        Box::pin(std::future::ready(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SimulatedBackend;
+    use crate::diagnostics::{
+        diagnose_scenario, run_diagnostics_with_backend, scan_network_devices_with_backend,
+    };
+    use crate::domain::{DiagnosticScenario, DiagnosticsSnapshot, Settings};
+    use crate::infrastructure::security::check_wifi_security_with_backend;
+    use crate::test_util::TempPath;
+
+    fn record(backend: SimulatedBackend) -> (DiagnosticsSnapshot, Capture) {
+        let recorder = RecordingBackend::new(Arc::new(backend));
+        let snapshot = run_diagnostics_with_backend(&recorder, &Settings::default());
+        (snapshot, recorder.finish())
+    }
+
+    /// Everything but timestamps and timings.
+    fn comparable(snapshot: &DiagnosticsSnapshot) -> Value {
+        let mut value = serde_json::to_value(snapshot).unwrap();
+        value["at_utc"] = Value::Null;
+        for node in value["nodes"].as_array_mut().unwrap() {
+            node["latency_ms"] = Value::Null;
+        }
+        value
+    }
+
+    #[test]
+    fn test_replay_reproduces_snapshot() {
+        let (recorded, capture) = record(SimulatedBackend::healthy());
+        let capture = Capture::from_json(&capture.to_json().unwrap()).unwrap();
+
+        let replayed =
+            run_diagnostics_with_backend(&ReplayBackend::new(&capture), &Settings::default());
+        assert_eq!(comparable(&replayed), comparable(&recorded));
+        assert_eq!(replayed.computer.hostname.as_deref(), Some("DEMO-LAPTOP"));
+    }
+
+    #[test]
+    fn test_replay_reproduces_every_scenario() {
+        for &scenario in DiagnosticScenario::all() {
+            let (_, capture) = record(SimulatedBackend::scenario(scenario));
+            let replayed =
+                run_diagnostics_with_backend(&ReplayBackend::new(&capture), &Settings::default());
+            assert_eq!(diagnose_scenario(&replayed).scenario, scenario);
+        }
+    }
+
+    #[test]
+    fn test_replay_ignores_probe_targets() {
+        let (recorded, capture) = record(SimulatedBackend::healthy());
+        let mut settings = Settings::default();
+        settings.probe_targets.dns_domains = vec!["example.test".to_string()];
+
+        let replayed = run_diagnostics_with_backend(&ReplayBackend::new(&capture), &settings);
+        assert_eq!(replayed.internet.dns_ok, recorded.internet.dns_ok);
+        assert_eq!(replayed.internet.http_ok, recorded.internet.http_ok);
+    }
+
+    #[test]
+    fn test_replay_security_and_device_scan() {
+        let recorder = RecordingBackend::new(Arc::new(SimulatedBackend::healthy()));
+        let targets = ProbeTargets::default();
+        let security = check_wifi_security_with_backend(&recorder, &targets);
+        let devices = scan_network_devices_with_backend(&recorder, None);
+        let capture = recorder.finish();
+
+        let replay = ReplayBackend::new(&capture);
+        let replayed_security = check_wifi_security_with_backend(&replay, &targets);
+        let replayed_devices = scan_network_devices_with_backend(&replay, None);
+
+        let statuses = |r: &crate::WiFiSecurityReport| {
+            r.checks
+                .iter()
+                .map(|c| (c.check_type, c.status, c.details.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statuses(&replayed_security), statuses(&security));
+        assert_eq!(replayed_security.network_ssid, security.network_ssid);
+        assert_eq!(
+            serde_json::to_value(&replayed_devices).unwrap(),
+            serde_json::to_value(&devices).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_missing_answers_fall_back_to_nothing_found() {
+        let capture = Capture {
+            format_version: CAPTURE_FORMAT_VERSION,
+            netok_version: String::new(),
+            platform: String::new(),
+            captured_at: String::new(),
+            calls: vec![],
+            raw: vec![],
+        };
+        let replay = ReplayBackend::new(&capture);
+        assert_eq!(replay.hostname(), None);
+        assert!(replay.interfaces().is_empty());
+        assert!(replay.dns_servers().is_err());
+        assert!(!replay.dns_probe(&ProbeTargets::default()));
+        assert_eq!(replay.encryption_check().status, SecurityStatus::Warning);
+    }
+
+    #[test]
+    fn test_repeated_calls_replay_in_order() {
+        let call = |ip: &str| ProbeCall {
+            probe: "default_gateway".to_string(),
+            args: vec![],
+            response: Value::from(ip),
+            duration_ms: 0,
+        };
+        let (_, mut capture) = record(SimulatedBackend::default());
+        capture.calls = vec![call("10.0.0.1"), call("10.0.0.2")];
+
+        let replay = ReplayBackend::new(&capture);
+        assert_eq!(replay.default_gateway().as_deref(), Some("10.0.0.1"));
+        assert_eq!(replay.default_gateway().as_deref(), Some("10.0.0.2"));
+        assert_eq!(replay.default_gateway().as_deref(), Some("10.0.0.2"));
+    }
+
+    #[test]
+    fn test_raw_outputs_only_kept_while_recording() {
+        let marker = |url: &str| RawOutput::Http {
+            url: url.to_string(),
+            status: Some(204),
+            error: None,
+        };
+        record_raw(|| marker("http://before.test/"));
+
+        let recorder = RecordingBackend::new(Arc::new(SimulatedBackend::default()));
+        record_raw(|| marker("http://during.test/"));
+        let capture = recorder.finish();
+
+        assert!(capture.raw.contains(&marker("http://during.test/")));
+        assert!(!capture.raw.contains(&marker("http://before.test/")));
+    }
+
+    #[test]
+    fn test_command_output_is_recorded() {
+        let mut cmd = Command::new("netok-no-such-program");
+        cmd.arg("--version");
+        let output = cmd.output();
+        match RawOutput::command(&cmd, &output) {
+            RawOutput::Command {
+                program,
+                args,
+                exit_code,
+                stderr,
+                ..
+            } => {
+                assert_eq!(program, "netok-no-such-program");
+                assert_eq!(args, vec!["--version"]);
+                assert_eq!(exit_code, None);
+                assert!(!stderr.is_empty());
+            }
+            other => panic!("unexpected raw output: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rejects_newer_format_and_garbage() {
+        let (_, capture) = record(SimulatedBackend::default());
+        let mut value = serde_json::to_value(&capture).unwrap();
+        value["format_version"] = Value::from(CAPTURE_FORMAT_VERSION + 1);
+
+        let err = Capture::from_json(&value.to_string()).unwrap_err();
+        assert!(matches!(err, CaptureError::UnsupportedVersion { .. }));
+        assert_eq!(err.kind(), crate::ErrorKind::InvalidInput);
+
+        let err = Capture::from_json("{\"calls\": []}").unwrap_err();
+        assert_eq!(err.kind(), crate::ErrorKind::Parse);
+    }
+
+    #[test]
+    fn test_save_and_load_capture() {
+        let (_, capture) = record(SimulatedBackend::healthy());
+        let path =
+            std::env::temp_dir().join(format!("netok_capture_test_{}.json", std::process::id()));
+        save_capture(&capture, &path).unwrap();
+        let loaded = load_capture(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded, capture);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_capture_async() {
+        let (_, capture) = record(SimulatedBackend::healthy());
+        let path = TempPath::new("capture.json");
+        save_capture_async(&capture, &path.0).await.unwrap();
+        assert_eq!(load_capture_async(&path.0).await.unwrap(), capture);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(hex(&[0x12, 0x34, 0x00, 0xff]), "123400ff");
+    }
+}
@@ -3,143 +3,49 @@
 //! This module combines infrastructure calls to produce domain objects,
 //! implementing the core diagnostic logic.
 
+use futures_util::future::join_all;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use time::OffsetDateTime;
+use tracing::Instrument;
 
+use crate::backend::{NetworkBackend, SystemBackend};
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::dns_catalog::DnsCatalog;
 use crate::domain::{
-    ComputerInfo, ConnectionType, DeviceType, DiagnosticsSnapshot, DnsProvider, InternetInfo,
-    NetworkDevice, NetworkInfo, NodeId, NodeInfo, RouterInfo, Settings, Status,
+    ComputerInfo, ConnectionType, DeviceType, DhcpInfo, DiagnosticResult, DiagnosticScenario,
+    DiagnosticsSnapshot, DnsProvider, DnssecStatus, InternetInfo, NetworkDevice, NetworkInfo,
+    NodeId, NodeInfo, ProbeTargets, RouterInfo, Settings, Status,
 };
+use crate::error::GeoIpError;
 use crate::infrastructure::mdns::infer_device_type_from_services;
-use crate::infrastructure::{
-    arp::get_all_arp_entries, detect_connection_type, get_default_gateway, get_router_mac,
-    get_wifi_info, mdns_discover, security::check_encryption,
-};
 use crate::oui_database::OUI_DATABASE;
+use crate::runtime::blocking;
 
-/// DNS Test: try to resolve known domains.
-fn test_dns() -> bool {
+/// DNS Test: try to resolve the configured probe domains.
+pub(crate) async fn test_dns_async(targets: &ProbeTargets) -> bool {
     use trust_dns_resolver::config::*;
-    use trust_dns_resolver::Resolver;
+    use trust_dns_resolver::TokioAsyncResolver;
 
     // Configure resolver with timeout
     let mut opts = ResolverOpts::default();
     opts.timeout = Duration::from_secs(1); // 1 second timeout (fast check)
 
-    let resolver = match Resolver::new(ResolverConfig::default(), opts) {
-        Ok(r) => r,
-        Err(_) => return false,
-    };
-
-    // Try primary domain
-    if resolver.lookup_ip("one.one.one.one").is_ok() {
-        return true;
-    }
-
-    // Fallback domain
-    resolver.lookup_ip("dns.google").is_ok()
-}
-
-/// Test if a specific DNS server is reachable by trying to resolve a domain.
-/// Returns Ok(true) if server responds, Ok(false) if timeout, Err on invalid IP.
-pub fn test_dns_server(server_ip: &str, timeout_secs: u64) -> Result<bool, String> {
-    use std::net::IpAddr;
-    use trust_dns_resolver::config::*;
-    use trust_dns_resolver::Resolver;
-
-    use std::net::{Ipv4Addr, Ipv6Addr};
-
-    // Parse the IP address
-    let ip: IpAddr = server_ip
-        .parse()
-        .map_err(|_| format!("Invalid IP address: {}", server_ip))?;
-
-    // Configure resolver to use the specific DNS server
-    let mut opts = ResolverOpts::default();
-    opts.timeout = Duration::from_secs(timeout_secs);
-    opts.attempts = 1; // Only try once
-
-    // Bind to matching address family — IPv6 server needs an IPv6 socket
-    let bind_addr = match ip {
-        IpAddr::V4(_) => Some(std::net::SocketAddr::new(
-            IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-            0,
-        )),
-        IpAddr::V6(_) => Some(std::net::SocketAddr::new(
-            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
-            0,
-        )),
-    };
-
-    let name_server = NameServerConfig {
-        socket_addr: std::net::SocketAddr::new(ip, 53),
-        protocol: Protocol::Udp,
-        tls_dns_name: None,
-        trust_negative_responses: true,
-        bind_addr,
-    };
-
-    let config = ResolverConfig::from_parts(None, vec![], vec![name_server]);
-
-    let resolver =
-        Resolver::new(config, opts).map_err(|e| format!("Failed to create resolver: {}", e))?;
-
-    // Try to resolve google.com - a domain that should always exist
-    Ok(resolver.lookup_ip("google.com").is_ok())
-}
-
-/// Measure DNS server latency by resolving a domain and timing the query.
-/// Returns Ok(Some(ms)) on success, Ok(None) if server is unreachable, Err on invalid IP.
-pub fn ping_dns_server(server_ip: &str, timeout_secs: u64) -> Result<Option<u64>, String> {
-    use std::net::IpAddr;
-    use trust_dns_resolver::config::*;
-    use trust_dns_resolver::Resolver;
-
-    use std::net::{Ipv4Addr, Ipv6Addr};
-
-    let ip: IpAddr = server_ip
-        .parse()
-        .map_err(|_| format!("Invalid IP address: {}", server_ip))?;
-
-    let mut opts = ResolverOpts::default();
-    opts.timeout = Duration::from_secs(timeout_secs);
-    opts.attempts = 1;
-
-    let bind_addr = match ip {
-        IpAddr::V4(_) => Some(std::net::SocketAddr::new(
-            IpAddr::V4(Ipv4Addr::UNSPECIFIED),
-            0,
-        )),
-        IpAddr::V6(_) => Some(std::net::SocketAddr::new(
-            IpAddr::V6(Ipv6Addr::UNSPECIFIED),
-            0,
-        )),
-    };
-
-    let name_server = NameServerConfig {
-        socket_addr: std::net::SocketAddr::new(ip, 53),
-        protocol: Protocol::Udp,
-        tls_dns_name: None,
-        trust_negative_responses: true,
-        bind_addr,
-    };
-
-    let config = ResolverConfig::from_parts(None, vec![], vec![name_server]);
+    let resolver = TokioAsyncResolver::tokio(ResolverConfig::default(), opts);
 
-    let resolver =
-        Resolver::new(config, opts).map_err(|e| format!("Failed to create resolver: {}", e))?;
-
-    let start = Instant::now();
-    match resolver.lookup_ip("google.com") {
-        Ok(_) => Ok(Some(start.elapsed().as_millis() as u64)),
-        Err(_) => Ok(None),
+    // Try each domain in order, first success wins
+    for domain in &targets.dns_domains {
+        if resolver.lookup_ip(domain.as_str()).await.is_ok() {
+            return true;
+        }
     }
+    false
 }
 
-/// HTTP Test: try to fetch from known endpoints.
-fn test_http() -> bool {
-    let client = match reqwest::blocking::Client::builder()
+/// HTTP Test: try to fetch the configured probe URLs.
+pub(crate) async fn test_http_async(targets: &ProbeTargets) -> bool {
+    let client = match reqwest::Client::builder()
         .timeout(Duration::from_secs(2))
         .build()
     {
@@ -147,20 +53,22 @@ fn test_http() -> bool {
         Err(_) => return false,
     };
 
-    // Try Cloudflare trace endpoint
-    if client
-        .get("https://www.cloudflare.com/cdn-cgi/trace")
-        .send()
-        .is_ok()
-    {
-        return true;
+    // Try each URL in order, first response wins
+    for url in &targets.http_urls {
+        let result = client.get(url).send().await;
+        crate::capture::record_raw(|| crate::capture::RawOutput::Http {
+            url: url.clone(),
+            status: result.as_ref().ok().map(|r| r.status().as_u16()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        if result.is_ok() {
+            return true;
+        }
     }
-
-    // Fallback to example.com
-    client.get("https://example.com").send().is_ok()
+    false
 }
 
-/// Response from ipinfo.io API.
+/// Response from ipinfo.io API (or a compatible self-hosted service).
 #[derive(serde::Serialize, Deserialize, Debug, Clone)]
 pub struct IpInfoResponse {
     pub ip: Option<String>,
@@ -171,25 +79,44 @@ pub struct IpInfoResponse {
 
 /// Lookup geolocation info for a given IP address via ipinfo.io.
 /// Pass an empty string to look up the caller's own public IP.
-pub fn lookup_ip_location(ip: &str) -> Result<IpInfoResponse, String> {
-    let client = reqwest::blocking::Client::builder()
+pub fn lookup_ip_location(ip: &str) -> Result<IpInfoResponse, GeoIpError> {
+    lookup_ip_location_with_targets(ip, &ProbeTargets::default())
+}
+
+/// Lookup geolocation info via the geolocation API configured in `targets`.
+pub fn lookup_ip_location_with_targets(
+    ip: &str,
+    targets: &ProbeTargets,
+) -> Result<IpInfoResponse, GeoIpError> {
+    crate::runtime::block_on(lookup_ip_location_async(ip, targets))
+}
+
+/// Async [`lookup_ip_location_with_targets`].
+pub async fn lookup_ip_location_async(
+    ip: &str,
+    targets: &ProbeTargets,
+) -> Result<IpInfoResponse, GeoIpError> {
+    let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(3))
         .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        .map_err(|e| GeoIpError::Client(e.to_string()))?;
 
-    let url = if ip.is_empty() {
-        "https://ipinfo.io/json".to_string()
-    } else {
-        format!("https://ipinfo.io/{}/json", ip)
-    };
+    let url = targets.geoip_url(ip);
 
-    let resp = client
-        .get(&url)
-        .send()
-        .map_err(|e| format!("Request to ipinfo.io failed: {}", e))?;
+    let resp = client.get(&url).send().await.map_err(|e| {
+        if e.is_timeout() {
+            GeoIpError::Timeout { url: url.clone() }
+        } else {
+            GeoIpError::Request {
+                url: url.clone(),
+                message: e.to_string(),
+            }
+        }
+    })?;
 
     resp.json::<IpInfoResponse>()
-        .map_err(|e| format!("Failed to parse ipinfo.io response: {}", e))
+        .await
+        .map_err(|e| GeoIpError::InvalidResponse(e.to_string()))
 }
 
 /// Check if IP address is private (RFC 1918 or link-local).
@@ -207,8 +134,25 @@ fn is_private_ip(ip: &std::net::IpAddr) -> bool {
     }
 }
 
+/// Check if IP address is IPv4 link-local (169.254.0.0/16).
+///
+/// The OS assigns these (APIPA) when no DHCP server answered, so an adapter
+/// with only a link-local address has no usable network configuration.
+fn is_link_local_ip(ip: &std::net::IpAddr) -> bool {
+    match ip {
+        std::net::IpAddr::V4(ipv4) => ipv4.is_link_local(),
+        _ => false,
+    }
+}
+
+/// Check if IP address string is IPv4 link-local.
+fn is_link_local_ip_str(ip: &str) -> bool {
+    ip.parse::<std::net::IpAddr>()
+        .is_ok_and(|ip_addr| is_link_local_ip(&ip_addr))
+}
+
 /// Check if IP address string is private.
-fn is_private_ip_str(ip: &str) -> bool {
+pub(crate) fn is_private_ip_str(ip: &str) -> bool {
     ip.parse::<std::net::IpAddr>()
         .is_ok_and(|ip_addr| is_private_ip(&ip_addr))
 }
@@ -254,30 +198,26 @@ pub fn lookup_vendor_by_mac(mac: &str) -> Option<String> {
 
 /// Get information about the local computer.
 pub fn get_computer_info() -> ComputerInfo {
-    use get_if_addrs::get_if_addrs;
+    computer_info(&SystemBackend)
+}
 
-    let hostname = hostname::get().ok().and_then(|s| s.into_string().ok());
+fn computer_info(backend: &dyn NetworkBackend) -> ComputerInfo {
+    let hostname = backend.hostname();
 
     // Try to get Wi-Fi info first to determine active adapter
-    let wifi = get_wifi_info();
+    let wifi = backend.wifi_info();
     let wifi_adapter_desc = wifi.interface_desc;
 
     // Collect all non-loopback interfaces with private IPv4
-    let interfaces: Vec<(String, String)> = match get_if_addrs() {
-        Ok(ifaces) => ifaces
-            .into_iter()
-            .filter(|iface| !iface.is_loopback())
-            .filter_map(|iface| {
-                if let std::net::IpAddr::V4(ipv4) = iface.ip() {
-                    if is_private_ip(&std::net::IpAddr::V4(ipv4)) {
-                        return Some((iface.name.clone(), ipv4.to_string()));
-                    }
-                }
-                None
-            })
-            .collect(),
-        Err(_) => vec![],
-    };
+    let mut interfaces: Vec<(String, String)> = backend
+        .interfaces()
+        .into_iter()
+        .filter(|iface| is_private_ip(&std::net::IpAddr::V4(iface.ip)))
+        .map(|iface| (iface.name, iface.ip.to_string()))
+        .collect();
+
+    // Prefer DHCP/static addresses over link-local (APIPA) ones
+    interfaces.sort_by_key(|(_, ip)| is_link_local_ip_str(ip));
 
     // Determine adapter and IP based on Wi-Fi status
     let (adapter, local_ip) = if let Some(ref wifi_desc) = wifi_adapter_desc {
@@ -354,16 +294,61 @@ fn wifi_standard_from_phy_type(phy_type: u32) -> Option<(String, bool)> {
     }
 }
 
+/// Collect IPv4 addresses of non-loopback interfaces, restricted to
+/// `adapter_name` when it matches an interface.
+fn local_ipv4_addresses(
+    backend: &dyn NetworkBackend,
+    adapter_name: Option<&str>,
+) -> Vec<std::net::Ipv4Addr> {
+    let ifaces = backend.interfaces();
+
+    let matching: Vec<std::net::Ipv4Addr> = ifaces
+        .iter()
+        .filter(|iface| Some(iface.name.as_str()) == adapter_name)
+        .map(|iface| iface.ip)
+        .collect();
+
+    if matching.is_empty() {
+        ifaces.into_iter().map(|iface| iface.ip).collect()
+    } else {
+        matching
+    }
+}
+
+/// Get DHCP lease details for the adapter and flag link-local-only addressing.
+fn get_dhcp_details(backend: &dyn NetworkBackend, adapter_name: Option<&str>) -> Option<DhcpInfo> {
+    let addresses = local_ipv4_addresses(backend, adapter_name);
+    let link_local_only = !addresses.is_empty() && addresses.iter().all(|ip| ip.is_link_local());
+
+    match backend.dhcp_info(adapter_name) {
+        Some(mut info) => {
+            info.link_local_only = link_local_only;
+            Some(info)
+        }
+        // No lease records at all, but APIPA addressing tells us DHCP failed
+        None if link_local_only => Some(DhcpInfo {
+            enabled: true,
+            link_local_only: true,
+            ..Default::default()
+        }),
+        None => None,
+    }
+}
+
 /// Get network connection information.
 pub fn get_network_info(adapter_name: Option<&str>) -> NetworkInfo {
+    network_info(&SystemBackend, adapter_name)
+}
+
+fn network_info(backend: &dyn NetworkBackend, adapter_name: Option<&str>) -> NetworkInfo {
     use crate::infrastructure::wifi::WifiAdapterState;
 
     let connection_type = adapter_name
-        .map(detect_connection_type)
+        .map(|name| backend.connection_type(name))
         .unwrap_or(ConnectionType::Unknown);
 
     // Try to get Wi-Fi info from system API
-    let wifi = get_wifi_info();
+    let wifi = backend.wifi_info();
 
     // Determine final connection type using Wi-Fi adapter state
     let final_connection_type = match wifi.adapter_state {
@@ -388,7 +373,7 @@ pub fn get_network_info(adapter_name: Option<&str>) -> NetworkInfo {
 
     // Get encryption type from existing security module (only for Wi-Fi)
     let encryption = if final_connection_type == ConnectionType::Wifi {
-        check_encryption().details
+        backend.encryption_check().details
     } else {
         None
     };
@@ -409,6 +394,17 @@ pub fn get_network_info(adapter_name: Option<&str>) -> NetworkInfo {
         .map(|(name, legacy)| (Some(name), legacy))
         .unwrap_or((None, false));
 
+    let dhcp = get_dhcp_details(backend, adapter_name);
+
+    // Configured DNS servers; fall back to the ones offered by DHCP where
+    // the OS query isn't implemented
+    let dns_servers = backend
+        .dns_servers()
+        .ok()
+        .filter(|servers| !servers.is_empty())
+        .or_else(|| dhcp.as_ref().map(|d| d.dns_servers.clone()))
+        .unwrap_or_default();
+
     NetworkInfo {
         connection_type: final_connection_type,
         ssid: wifi.ssid,
@@ -420,16 +416,22 @@ pub fn get_network_info(adapter_name: Option<&str>) -> NetworkInfo {
         link_speed_mbps,
         wifi_standard,
         is_legacy_wifi,
+        dhcp,
+        dns_servers,
     }
 }
 
 /// Get router/gateway information.
 pub fn get_router_info() -> RouterInfo {
+    router_info(&SystemBackend)
+}
+
+fn router_info(backend: &dyn NetworkBackend) -> RouterInfo {
     // Try to get default gateway IP
-    let gateway_ip = get_default_gateway();
+    let gateway_ip = backend.default_gateway();
 
     // Try to get MAC address if we have gateway IP
-    let gateway_mac = gateway_ip.as_ref().and_then(|ip| get_router_mac(ip));
+    let gateway_mac = gateway_ip.as_ref().and_then(|ip| backend.router_mac(ip));
 
     // Try to lookup vendor if we have MAC address
     let vendor = gateway_mac
@@ -450,15 +452,21 @@ pub fn get_router_info() -> RouterInfo {
 /// Geo-lookup (ipinfo.io) is NOT included here — use `lookup_ip_location()`
 /// separately so the Internet node status appears immediately.
 pub fn get_internet_info() -> InternetInfo {
-    // Run DNS and HTTP checks in parallel — they are independent
-    let (dns_ok, http_ok) = std::thread::scope(|s| {
-        let dns_handle = s.spawn(test_dns);
-        let http_handle = s.spawn(test_http);
-        (
-            dns_handle.join().unwrap_or(false),
-            http_handle.join().unwrap_or(false),
-        )
-    });
+    get_internet_info_with_targets(&ProbeTargets::default())
+}
+
+/// Get internet connectivity information using the endpoints in `targets`.
+pub fn get_internet_info_with_targets(targets: &ProbeTargets) -> InternetInfo {
+    crate::runtime::block_on(internet_info(&SystemBackend, targets))
+}
+
+async fn internet_info(backend: &dyn NetworkBackend, targets: &ProbeTargets) -> InternetInfo {
+    // Run DNS, HTTP and DNSSEC checks concurrently — they are independent
+    let (dns_ok, http_ok, dnssec) = tokio::join!(
+        backend.dns_probe_async(targets),
+        backend.http_probe_async(targets),
+        backend.dnssec_status_async()
+    );
 
     InternetInfo {
         public_ip: None,
@@ -470,15 +478,27 @@ pub fn get_internet_info() -> InternetInfo {
         latency_ms: None,
         speed_down_mbps: None,
         speed_up_mbps: None,
+        dnssec,
     }
 }
 
 /// Check computer node. Returns the NodeInfo (with status/latency) and the detailed ComputerInfo.
 pub fn check_computer() -> (NodeInfo, ComputerInfo) {
+    check_computer_with_backend(&SystemBackend)
+}
+
+/// Check computer node, reading the machine through `backend`.
+pub fn check_computer_with_backend(backend: &dyn NetworkBackend) -> (NodeInfo, ComputerInfo) {
     let start = Instant::now();
-    let computer = get_computer_info();
+    let computer = computer_info(backend);
     let latency = start.elapsed().as_millis() as u32;
-    let status = if computer.hostname.is_some() {
+    let apipa = computer
+        .local_ip
+        .as_deref()
+        .is_some_and(is_link_local_ip_str);
+    let status = if apipa {
+        Status::Fail // self-assigned address → DHCP failed
+    } else if computer.hostname.is_some() {
         Status::Ok
     } else {
         Status::Warn
@@ -488,15 +508,28 @@ pub fn check_computer() -> (NodeInfo, ComputerInfo) {
         name_key: "nodes.computer.name".into(),
         status,
         latency_ms: Some(latency),
-        hint_key: None,
+        hint_key: apipa.then(|| "nodes.computer.hint_dhcp_failure".into()),
     };
     (node, computer)
 }
 
+/// Async [`check_computer_with_backend`].
+pub async fn check_computer_async(backend: &dyn NetworkBackend) -> (NodeInfo, ComputerInfo) {
+    blocking(|| check_computer_with_backend(backend))
+}
+
 /// Check network node. Requires the adapter name from the computer check.
 pub fn check_network(adapter_name: Option<&str>) -> (NodeInfo, NetworkInfo) {
+    check_network_with_backend(&SystemBackend, adapter_name)
+}
+
+/// Check network node, reading the machine through `backend`.
+pub fn check_network_with_backend(
+    backend: &dyn NetworkBackend,
+    adapter_name: Option<&str>,
+) -> (NodeInfo, NetworkInfo) {
     let start = Instant::now();
-    let network = get_network_info(adapter_name);
+    let network = network_info(backend, adapter_name);
     let latency = start.elapsed().as_millis() as u32;
     let status = match network.connection_type {
         ConnectionType::Disabled => Status::Fail, // adapter off → down
@@ -504,20 +537,121 @@ pub fn check_network(adapter_name: Option<&str>) -> (NodeInfo, NetworkInfo) {
         ConnectionType::Unknown => Status::Warn,
         _ => Status::Ok,
     };
+    let dhcp_failure = diagnose_dhcp(&network);
+    let (status, hint_key) = match (&network.dhcp, &dhcp_failure) {
+        (Some(dhcp), Some(_)) if dhcp.link_local_only => (
+            Status::Fail,
+            Some("nodes.network.hint_dhcp_failure".to_string()),
+        ),
+        (Some(_), Some(_)) if status == Status::Ok => (
+            Status::Warn,
+            Some("nodes.network.hint_dhcp_lease_expired".to_string()),
+        ),
+        _ => (status, None),
+    };
     let node = NodeInfo {
         id: NodeId::Wifi,
         name_key: "nodes.wifi.name".into(),
         status,
         latency_ms: Some(latency),
-        hint_key: None,
+        hint_key,
     };
     (node, network)
 }
 
+/// Async [`check_network_with_backend`].
+pub async fn check_network_async(
+    backend: &dyn NetworkBackend,
+    adapter_name: Option<&str>,
+) -> (NodeInfo, NetworkInfo) {
+    blocking(|| check_network_with_backend(backend, adapter_name))
+}
+
+/// Diagnose DHCP health from the network info.
+///
+/// Returns a `DhcpFailure` result when the adapter only has a link-local
+/// (APIPA) address or its lease has expired. Details are
+/// `"link_local_only"` or `"lease_expired:<expiry>"`, plus `" server:<ip>"`
+/// when the last DHCP server is known.
+pub fn diagnose_dhcp(network: &NetworkInfo) -> Option<DiagnosticResult> {
+    let dhcp = network.dhcp.as_ref()?;
+    if !dhcp.enabled {
+        return None;
+    }
+
+    let mut details = if dhcp.link_local_only {
+        "link_local_only".to_string()
+    } else if dhcp.lease_expired {
+        format!(
+            "lease_expired:{}",
+            dhcp.lease_expires_utc.as_deref().unwrap_or("unknown")
+        )
+    } else {
+        return None;
+    };
+
+    if let Some(server) = &dhcp.server {
+        details.push_str(&format!(" server:{}", server));
+    }
+
+    Some(DiagnosticResult::with_details(
+        DiagnosticScenario::DhcpFailure,
+        details,
+    ))
+}
+
+/// RSSI below which a Wi-Fi signal counts as weak (matches the UI's "weak" label).
+const WEAK_SIGNAL_RSSI_DBM: i32 = -70;
+
+/// Classify a snapshot into the single scenario the user should see.
+///
+/// Failures nearer to the computer win: a missing DHCP lease explains a
+/// missing gateway, which explains no internet. A weak signal is reported
+/// only when nothing is actually broken.
+pub fn diagnose_scenario(snapshot: &DiagnosticsSnapshot) -> DiagnosticResult {
+    if let Some(dhcp) = diagnose_dhcp(&snapshot.network) {
+        return dhcp;
+    }
+    if snapshot
+        .computer
+        .local_ip
+        .as_deref()
+        .is_some_and(is_link_local_ip_str)
+    {
+        return DiagnosticResult::with_details(DiagnosticScenario::DhcpFailure, "link_local_only");
+    }
+
+    let scenario = match snapshot.network.connection_type {
+        ConnectionType::Disabled => DiagnosticScenario::WifiDisabled,
+        ConnectionType::Disconnected => DiagnosticScenario::WifiNotConnected,
+        _ if snapshot.router.gateway_ip.is_none() => DiagnosticScenario::RouterUnreachable,
+        _ => match (snapshot.internet.dns_ok, snapshot.internet.http_ok) {
+            (false, false) => DiagnosticScenario::NoInternet,
+            (false, true) => DiagnosticScenario::DnsFailure,
+            (true, false) => DiagnosticScenario::HttpBlocked,
+            (true, true) => match snapshot.network.rssi {
+                Some(rssi)
+                    if snapshot.network.connection_type == ConnectionType::Wifi
+                        && rssi < WEAK_SIGNAL_RSSI_DBM =>
+                {
+                    DiagnosticScenario::WeakSignal
+                }
+                _ => DiagnosticScenario::AllGood,
+            },
+        },
+    };
+    DiagnosticResult::new(scenario)
+}
+
 /// Check router node.
 pub fn check_router() -> (NodeInfo, RouterInfo) {
+    check_router_with_backend(&SystemBackend)
+}
+
+/// Check router node, reading the machine through `backend`.
+pub fn check_router_with_backend(backend: &dyn NetworkBackend) -> (NodeInfo, RouterInfo) {
     let start = Instant::now();
-    let router = get_router_info();
+    let router = router_info(backend);
     let latency = start.elapsed().as_millis() as u32;
     let status = if router.gateway_ip.is_some() {
         Status::Ok
@@ -534,12 +668,40 @@ pub fn check_router() -> (NodeInfo, RouterInfo) {
     (node, router)
 }
 
+/// Async [`check_router_with_backend`].
+pub async fn check_router_async(backend: &dyn NetworkBackend) -> (NodeInfo, RouterInfo) {
+    blocking(|| check_router_with_backend(backend))
+}
+
 /// Check internet node.
 pub fn check_internet() -> (NodeInfo, InternetInfo) {
+    check_internet_with_targets(&ProbeTargets::default())
+}
+
+/// Check internet node against the endpoints in `targets`.
+pub fn check_internet_with_targets(targets: &ProbeTargets) -> (NodeInfo, InternetInfo) {
+    check_internet_with_backend(&SystemBackend, targets)
+}
+
+/// Check internet node against `targets`, probing through `backend`.
+pub fn check_internet_with_backend(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+) -> (NodeInfo, InternetInfo) {
+    crate::runtime::block_on(check_internet_async(backend, targets))
+}
+
+/// Async [`check_internet_with_backend`].
+pub async fn check_internet_async(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+) -> (NodeInfo, InternetInfo) {
     let start = Instant::now();
-    let internet = get_internet_info();
+    let internet = internet_info(backend, targets).await;
     let latency = start.elapsed().as_millis() as u32;
-    let status = if internet.dns_ok && internet.http_ok {
+    // A broken validator fails every signed domain while the probes still pass
+    let dnssec_broken = internet.dnssec == DnssecStatus::Broken;
+    let status = if internet.dns_ok && internet.http_ok && !dnssec_broken {
         Status::Ok
     } else if internet.dns_ok || internet.http_ok {
         Status::Warn
@@ -551,42 +713,125 @@ pub fn check_internet() -> (NodeInfo, InternetInfo) {
         name_key: "nodes.internet.name".into(),
         status,
         latency_ms: Some(latency),
-        hint_key: None,
+        hint_key: dnssec_broken.then(|| "nodes.internet.hint_dnssec_broken".into()),
     };
     (node, internet)
 }
 
 /// Run complete network diagnostics.
-pub fn run_diagnostics(_settings: &Settings) -> DiagnosticsSnapshot {
-    let now = OffsetDateTime::now_utc()
-        .format(&time::format_description::well_known::Rfc3339)
-        .unwrap();
-
-    let (computer_node, computer) = check_computer();
-    let (network_node, network) = check_network(computer.adapter.as_deref());
-    let (router_node, router) = check_router();
-    let (internet_node, internet) = check_internet();
-
-    let nodes = vec![computer_node, network_node, router_node, internet_node];
-
-    // Determine overall summary
-    let summary_key = if nodes.iter().all(|n| matches!(n.status, Status::Ok)) {
-        "summary.ok".into()
-    } else if nodes.iter().any(|n| matches!(n.status, Status::Fail)) {
-        "summary.fail".into()
-    } else {
-        "summary.warn".into()
-    };
+pub fn run_diagnostics(settings: &Settings) -> DiagnosticsSnapshot {
+    run_diagnostics_with_backend(&SystemBackend, settings)
+}
 
-    DiagnosticsSnapshot {
-        at_utc: now,
-        nodes,
-        summary_key,
-        computer,
-        network,
-        router,
-        internet,
+/// Run complete network diagnostics against `backend`.
+pub fn run_diagnostics_with_backend(
+    backend: &dyn NetworkBackend,
+    settings: &Settings,
+) -> DiagnosticsSnapshot {
+    crate::runtime::block_on(run_diagnostics_async(backend, settings))
+}
+
+/// Async [`run_diagnostics_with_backend`].
+pub async fn run_diagnostics_async(
+    backend: &dyn NetworkBackend,
+    settings: &Settings,
+) -> DiagnosticsSnapshot {
+    run_diagnostics_cancellable(backend, settings, &Cancellation::none())
+        .await
+        .value
+}
+
+/// [`run_diagnostics_async`] that stops when `cancel` fires.
+///
+/// Nodes not checked by then are reported with `Status::Unknown`.
+pub async fn run_diagnostics_cancellable(
+    backend: &dyn NetworkBackend,
+    settings: &Settings,
+    cancel: &Cancellation,
+) -> Partial<DiagnosticsSnapshot> {
+    async {
+        let started = std::time::Instant::now();
+        let now = OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap();
+        let mut completion = Completion::Complete;
+
+        let (computer_node, computer) = cancel
+            .step(&mut completion, check_computer_async(backend))
+            .await
+            .unwrap_or_else(|| unchecked(NodeId::Computer, "nodes.computer.name"));
+        let (network_node, network) = cancel
+            .step(
+                &mut completion,
+                check_network_async(backend, computer.adapter.as_deref()),
+            )
+            .await
+            .unwrap_or_else(|| unchecked(NodeId::Wifi, "nodes.wifi.name"));
+        let (router_node, router) = cancel
+            .step(&mut completion, check_router_async(backend))
+            .await
+            .unwrap_or_else(|| unchecked(NodeId::RouterUpnp, "nodes.router.name"));
+        let (internet_node, internet) = cancel
+            .step(
+                &mut completion,
+                check_internet_async(backend, &settings.probe_targets),
+            )
+            .await
+            .unwrap_or_else(|| unchecked(NodeId::Internet, "nodes.internet.name"));
+
+        let nodes = vec![computer_node, network_node, router_node, internet_node];
+        for node in &nodes {
+            tracing::debug!(
+                node = ?node.id,
+                status = ?node.status,
+                latency_ms = node.latency_ms,
+                "node checked"
+            );
+        }
+
+        // Determine overall summary
+        let summary_key = if nodes.iter().all(|n| matches!(n.status, Status::Ok)) {
+            "summary.ok".into()
+        } else if nodes.iter().any(|n| matches!(n.status, Status::Fail)) {
+            "summary.fail".into()
+        } else {
+            "summary.warn".into()
+        };
+
+        tracing::info!(
+            summary = %summary_key,
+            ?completion,
+            duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "diagnostics complete"
+        );
+
+        Partial::new(
+            DiagnosticsSnapshot {
+                at_utc: now,
+                nodes,
+                summary_key,
+                computer,
+                network,
+                router,
+                internet,
+            },
+            completion,
+        )
     }
+    .instrument(tracing::info_span!("diagnostics"))
+    .await
+}
+
+/// Placeholder for a node a stopped run never reached.
+fn unchecked<T: Default>(id: NodeId, name_key: &str) -> (NodeInfo, T) {
+    let node = NodeInfo {
+        id,
+        name_key: name_key.into(),
+        status: Status::Unknown,
+        latency_ms: None,
+        hint_key: None,
+    };
+    (node, T::default())
 }
 
 /// Detect which DNS provider is currently in use based on DNS server IPs.
@@ -605,53 +850,18 @@ pub fn detect_dns_provider(dns_servers: &[String]) -> DnsProvider {
         }
     }
 
+    if let Some(entry) = DnsCatalog::built_in().detect(dns_servers) {
+        return DnsProvider::Catalog(entry.id.clone());
+    }
+
     match (primary, secondary) {
-        // Cloudflare
-        (Some("1.1.1.1"), Some("1.0.0.1")) | (Some("1.1.1.1"), None) => DnsProvider::Cloudflare,
-        (Some("1.1.1.2"), Some("1.0.0.2")) | (Some("1.1.1.2"), None) => {
-            DnsProvider::CloudflareMalware
-        }
-        (Some("1.1.1.3"), Some("1.0.0.3")) | (Some("1.1.1.3"), None) => {
-            DnsProvider::CloudflareFamily
-        }
-        // Google
-        (Some("8.8.8.8"), Some("8.8.4.4")) | (Some("8.8.8.8"), None) => DnsProvider::Google,
-        // AdGuard
-        (Some("94.140.14.14"), Some("94.140.15.15")) | (Some("94.140.14.14"), None) => {
-            DnsProvider::AdGuard
-        }
-        (Some("94.140.14.140"), Some("94.140.14.141")) | (Some("94.140.14.140"), None) => {
-            DnsProvider::AdGuardNonFiltering
-        }
-        (Some("94.140.14.15"), Some("94.140.15.16")) | (Some("94.140.14.15"), None) => {
-            DnsProvider::AdGuardFamily
-        }
-        // DNS4EU
-        (Some("86.54.11.1"), _) => DnsProvider::Dns4EuProtective,
-        (Some("86.54.11.12"), _) => DnsProvider::Dns4EuProtectiveChild,
-        (Some("86.54.11.13"), _) => DnsProvider::Dns4EuProtectiveAd,
-        (Some("86.54.11.11"), _) => DnsProvider::Dns4EuProtectiveChildAd,
-        (Some("86.54.11.100"), _) => DnsProvider::Dns4EuUnfiltered,
-        // Quad9
-        (Some("9.9.9.9"), Some("149.112.112.112")) | (Some("9.9.9.9"), None) => {
-            DnsProvider::Quad9Recommended
-        }
-        (Some("9.9.9.11"), Some("149.112.112.11")) | (Some("9.9.9.11"), None) => {
-            DnsProvider::Quad9SecuredEcs
-        }
-        (Some("9.9.9.10"), Some("149.112.112.10")) | (Some("9.9.9.10"), None) => {
-            DnsProvider::Quad9Unsecured
-        }
-        // OpenDNS
-        (Some("208.67.222.123"), Some("208.67.220.123")) | (Some("208.67.222.123"), None) => {
-            DnsProvider::OpenDnsFamilyShield
+        // Custom (IPv6 unknown when detecting from IPv4 addresses)
+        (Some(p), Some(s)) => {
+            DnsProvider::Custom(p.to_string(), s.to_string(), None, None, None, None)
         }
-        (Some("208.67.222.222"), Some("208.67.220.220")) | (Some("208.67.222.222"), None) => {
-            DnsProvider::OpenDnsHome
+        (Some(p), None) => {
+            DnsProvider::Custom(p.to_string(), String::new(), None, None, None, None)
         }
-        // Custom (IPv6 unknown when detecting from IPv4 addresses)
-        (Some(p), Some(s)) => DnsProvider::Custom(p.to_string(), s.to_string(), None, None),
-        (Some(p), None) => DnsProvider::Custom(p.to_string(), String::new(), None, None),
         _ => DnsProvider::Auto,
     }
 }
@@ -822,137 +1032,195 @@ pub fn scan_network_devices() -> Vec<NetworkDevice> {
 pub type ProgressCallback = Option<Box<dyn Fn(&str) + Send + Sync>>;
 
 pub fn scan_network_devices_with_progress(on_progress: ProgressCallback) -> Vec<NetworkDevice> {
-    use crate::brand_mapping::map_vendor_to_brand;
-    use crate::infrastructure::{ping_sweep, reverse_dns_lookup};
+    scan_network_devices_with_backend(&SystemBackend, on_progress)
+}
 
-    let progress = |stage: &str| {
-        if let Some(ref cb) = on_progress {
-            cb(stage);
-        }
-    };
+/// Scan the network seen through `backend`, with optional progress reporting.
+pub fn scan_network_devices_with_backend(
+    backend: &dyn NetworkBackend,
+    on_progress: ProgressCallback,
+) -> Vec<NetworkDevice> {
+    crate::runtime::block_on(scan_network_devices_async(backend, on_progress))
+}
 
-    let gateway_ip = get_default_gateway();
-    let computer_info = get_computer_info();
-    let local_ip = computer_info.local_ip.clone();
+/// Async [`scan_network_devices_with_backend`].
+pub async fn scan_network_devices_async(
+    backend: &dyn NetworkBackend,
+    on_progress: ProgressCallback,
+) -> Vec<NetworkDevice> {
+    scan_network_devices_cancellable(backend, on_progress, &Cancellation::none())
+        .await
+        .value
+}
 
-    // Phase 0: Ping sweep to populate ARP table with all reachable devices
-    progress("scanning");
-    if let Some(ref gw) = gateway_ip {
-        ping_sweep(gw);
-    }
+/// [`scan_network_devices_async`] that stops when `cancel` fires.
+///
+/// Returns the devices identified by then. Stopped during the ping sweep, the
+/// ARP table isn't read; stopped during name lookups, the remaining devices
+/// keep `hostname: None`.
+pub async fn scan_network_devices_cancellable(
+    backend: &dyn NetworkBackend,
+    on_progress: ProgressCallback,
+    cancel: &Cancellation,
+) -> Partial<Vec<NetworkDevice>> {
+    use crate::brand_mapping::map_vendor_to_brand;
 
-    // Phase 0.5: Start mDNS discovery in background (runs concurrently with ARP + reverse DNS)
-    let mdns_handle = std::thread::spawn(|| mdns_discover(Duration::from_secs(3)));
+    async {
+        let started = std::time::Instant::now();
+        let mut completion = Completion::Complete;
+        let progress = |stage: &str| {
+            tracing::debug!(stage, "device scan progress");
+            if let Some(ref cb) = on_progress {
+                cb(stage);
+            }
+        };
 
-    // Phase 1: Read ARP table + OUI + classification
-    progress("identifying");
-    let entries = get_all_arp_entries();
+        let located =
+            async { blocking(|| (backend.default_gateway(), computer_info(backend).local_ip)) };
+        let (gateway_ip, local_ip) = cancel
+            .step(&mut completion, located)
+            .await
+            .unwrap_or_default();
 
-    // Phase 1: Build devices with OUI lookup, classification, and brand mapping
-    let mut devices: Vec<NetworkDevice> = entries
-        .into_iter()
-        .map(|entry| {
-            let is_gateway = gateway_ip.as_deref() == Some(&entry.ip);
-            let is_self = local_ip.as_deref() == Some(&entry.ip);
-            let is_randomized = is_randomized_mac(&entry.mac);
-
-            // Skip OUI lookup for randomized MACs (result would be meaningless)
-            let raw_vendor = if is_randomized {
-                None
-            } else {
-                lookup_vendor_by_mac(&entry.mac)
-            };
-
-            let device_type = match &raw_vendor {
-                Some(v) => classify_vendor(v, is_gateway),
-                None if is_gateway => DeviceType::Router,
-                None => DeviceType::Unknown,
-            };
-
-            // Apply brand mapping to clean up vendor name for display
-            let vendor = raw_vendor.map(|v| map_vendor_to_brand(&v));
-
-            NetworkDevice {
-                ip: entry.ip,
-                mac: entry.mac,
-                vendor,
-                hostname: None,
-                device_type,
-                is_gateway,
-                is_self,
-                is_randomized,
+        // Phase 0: Ping sweep to populate ARP table with all reachable devices
+        progress("scanning");
+        if let Some(ref gw) = gateway_ip {
+            let sweep = async { blocking(|| backend.ping_sweep(gw, cancel)) };
+            cancel.step(&mut completion, sweep).await;
+            if let Some(reason) = cancel.stopped() {
+                completion = reason;
             }
-        })
-        .collect();
+        }
 
-    // Phase 2: Parallel reverse DNS lookups for all devices
-    std::thread::scope(|s| {
-        let handles: Vec<_> = devices
-            .iter()
-            .map(|device| {
-                let ip = device.ip.clone();
-                s.spawn(move || reverse_dns_lookup(&ip, 500))
+        // Phase 1: Read ARP table + OUI + classification
+        progress("identifying");
+        let entries = cancel
+            .step(&mut completion, async {
+                blocking(|| backend.arp_entries())
+            })
+            .await
+            .unwrap_or_default();
+
+        // Phase 1: Build devices with OUI lookup, classification, and brand mapping
+        let mut devices: Vec<NetworkDevice> = entries
+            .into_iter()
+            .map(|entry| {
+                let is_gateway = gateway_ip.as_deref() == Some(&entry.ip);
+                let is_self = local_ip.as_deref() == Some(&entry.ip);
+                let is_randomized = is_randomized_mac(&entry.mac);
+
+                // Skip OUI lookup for randomized MACs (result would be meaningless)
+                let raw_vendor = if is_randomized {
+                    None
+                } else {
+                    lookup_vendor_by_mac(&entry.mac)
+                };
+
+                let device_type = match &raw_vendor {
+                    Some(v) => classify_vendor(v, is_gateway),
+                    None if is_gateway => DeviceType::Router,
+                    None => DeviceType::Unknown,
+                };
+
+                // Apply brand mapping to clean up vendor name for display
+                let vendor = raw_vendor.map(|v| map_vendor_to_brand(&v));
+
+                NetworkDevice {
+                    ip: entry.ip,
+                    mac: entry.mac,
+                    vendor,
+                    hostname: None,
+                    device_type,
+                    is_gateway,
+                    is_self,
+                    is_randomized,
+                }
             })
             .collect();
 
-        for (device, handle) in devices.iter_mut().zip(handles) {
-            device.hostname = handle.join().ok().flatten();
+        // Phase 2: Concurrent reverse DNS lookups for all devices, with mDNS
+        // discovery running alongside
+        let mut mdns_results = HashMap::new();
+        if completion == Completion::Complete && !devices.is_empty() {
+            let (hostnames, mdns) = tokio::join!(
+                join_all(
+                    devices
+                        .iter()
+                        .map(|device| cancel.run(backend.reverse_dns_async(&device.ip, 500)))
+                ),
+                backend.mdns_discover_async(Duration::from_secs(3), cancel)
+            );
+            for (device, hostname) in devices.iter_mut().zip(hostnames) {
+                device.hostname = hostname.unwrap_or(None);
+            }
+            mdns_results = mdns;
+            if let Some(reason) = cancel.stopped() {
+                completion = reason;
+            }
         }
-    });
-
-    // Phase 3: Join mDNS results and enrich devices with human-readable names
-    let mdns_results = mdns_handle.join().unwrap_or_default();
-    if !mdns_results.is_empty() {
-        println!(
-            "[scan] Enriching {} devices with mDNS data ({} mDNS entries)",
-            devices.len(),
-            mdns_results.len()
-        );
 
-        for device in &mut devices {
-            if let Some(mdns_info) = mdns_results.get(&device.ip) {
-                // mDNS name overrides reverse DNS hostname (more human-readable)
-                device.hostname = Some(mdns_info.name.clone());
-
-                // Refine device type if mDNS gives a stronger signal
-                // (don't reclassify gateway devices)
-                if !device.is_gateway {
-                    if let Some(inferred) =
-                        infer_device_type_from_services(&mdns_info.service_types)
-                    {
-                        device.device_type = inferred;
+        // Phase 3: Enrich devices with human-readable names from mDNS
+        if !mdns_results.is_empty() {
+            tracing::debug!(
+                devices = devices.len(),
+                mdns_entries = mdns_results.len(),
+                "enriching devices with mDNS data"
+            );
+
+            for device in &mut devices {
+                if let Some(mdns_info) = mdns_results.get(&device.ip) {
+                    // mDNS name overrides reverse DNS hostname (more human-readable)
+                    device.hostname = Some(mdns_info.name.clone());
+
+                    // Refine device type if mDNS gives a stronger signal
+                    // (don't reclassify gateway devices)
+                    if !device.is_gateway {
+                        if let Some(inferred) =
+                            infer_device_type_from_services(&mdns_info.service_types)
+                        {
+                            device.device_type = inferred;
+                        }
                     }
                 }
             }
-        }
 
-        // Second pass: try to classify Unknown devices by hostname
-        // (e.g. randomized-MAC devices where OUI was skipped but mDNS/DNS gave a name)
-        for device in &mut devices {
-            if device.device_type == DeviceType::Unknown && !device.is_gateway {
-                if let Some(ref name) = device.hostname {
-                    let inferred = classify_vendor(name, false);
-                    if inferred != DeviceType::Unknown {
-                        device.device_type = inferred;
+            // Second pass: try to classify Unknown devices by hostname
+            // (e.g. randomized-MAC devices where OUI was skipped but mDNS/DNS gave a name)
+            for device in &mut devices {
+                if device.device_type == DeviceType::Unknown && !device.is_gateway {
+                    if let Some(ref name) = device.hostname {
+                        let inferred = classify_vendor(name, false);
+                        if inferred != DeviceType::Unknown {
+                            device.device_type = inferred;
+                        }
                     }
                 }
             }
         }
-    }
 
-    // Sort by numeric IP (e.g. 192.168.1.7 before 192.168.1.21)
-    devices.sort_by(|a, b| {
-        let parse_ip =
-            |ip: &str| -> Vec<u32> { ip.split('.').filter_map(|s| s.parse().ok()).collect() };
-        parse_ip(&a.ip).cmp(&parse_ip(&b.ip))
-    });
-
-    devices
+        // Sort by numeric IP (e.g. 192.168.1.7 before 192.168.1.21)
+        devices.sort_by(|a, b| {
+            let parse_ip =
+                |ip: &str| -> Vec<u32> { ip.split('.').filter_map(|s| s.parse().ok()).collect() };
+            parse_ip(&a.ip).cmp(&parse_ip(&b.ip))
+        });
+
+        tracing::info!(
+            devices = devices.len(),
+            ?completion,
+            duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "device scan complete"
+        );
+        Partial::new(devices, completion)
+    }
+    .instrument(tracing::info_span!("device_scan"))
+    .await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::SimulatedBackend;
 
     #[test]
     fn test_randomized_mac_detection() {
@@ -990,6 +1258,72 @@ mod tests {
         assert!(is_private_ip(&ip));
     }
 
+    #[test]
+    fn test_link_local_ip() {
+        let apipa: std::net::IpAddr = "169.254.12.34".parse().unwrap();
+        assert!(is_link_local_ip(&apipa));
+        assert!(!is_link_local_ip(&"192.168.1.10".parse().unwrap()));
+        assert!(is_link_local_ip_str("169.254.0.1"));
+        assert!(!is_link_local_ip_str("not-an-ip"));
+    }
+
+    #[test]
+    fn test_diagnose_dhcp_link_local_only() {
+        let network = NetworkInfo {
+            dhcp: Some(DhcpInfo {
+                enabled: true,
+                link_local_only: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let result = diagnose_dhcp(&network).expect("APIPA should be a DHCP failure");
+        assert_eq!(result.scenario, DiagnosticScenario::DhcpFailure);
+        assert_eq!(result.details.as_deref(), Some("link_local_only"));
+    }
+
+    #[test]
+    fn test_diagnose_dhcp_lease_expired() {
+        let network = NetworkInfo {
+            dhcp: Some(DhcpInfo {
+                enabled: true,
+                server: Some("192.168.1.1".into()),
+                lease_expires_utc: Some("2024-01-17T10:00:00Z".into()),
+                lease_expired: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let result = diagnose_dhcp(&network).unwrap();
+        assert_eq!(
+            result.details.as_deref(),
+            Some("lease_expired:2024-01-17T10:00:00Z server:192.168.1.1")
+        );
+    }
+
+    #[test]
+    fn test_diagnose_dhcp_healthy_or_static() {
+        let healthy = NetworkInfo {
+            dhcp: Some(DhcpInfo {
+                enabled: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(diagnose_dhcp(&healthy).is_none());
+
+        let static_ip = NetworkInfo {
+            dhcp: Some(DhcpInfo {
+                enabled: false,
+                link_local_only: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(diagnose_dhcp(&static_ip).is_none());
+        assert!(diagnose_dhcp(&NetworkInfo::default()).is_none());
+    }
+
     #[test]
     fn test_public_ip() {
         let ip: std::net::IpAddr = "8.8.8.8".parse().unwrap();
@@ -999,13 +1333,13 @@ mod tests {
     #[test]
     fn test_detect_dns_cloudflare() {
         let provider = detect_dns_provider(&["1.1.1.1".to_string(), "1.0.0.1".to_string()]);
-        assert_eq!(provider, DnsProvider::Cloudflare);
+        assert_eq!(provider, DnsProvider::Catalog("Cloudflare".into()));
     }
 
     #[test]
     fn test_detect_dns_google() {
         let provider = detect_dns_provider(&["8.8.8.8".to_string(), "8.8.4.4".to_string()]);
-        assert_eq!(provider, DnsProvider::Google);
+        assert_eq!(provider, DnsProvider::Catalog("Google".into()));
     }
 
     #[test]
@@ -1032,4 +1366,169 @@ mod tests {
         let vendor = lookup_vendor_by_mac("invalid");
         assert!(vendor.is_none());
     }
+
+    // ==================== Probe Target Tests ====================
+
+    /// Serve `body` to a single HTTP request on a local port, returning the base URL.
+    fn serve_once(body: &'static str) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    /// A local URL nothing is listening on.
+    fn closed_url() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_http_uses_configured_urls() {
+        let targets = ProbeTargets {
+            http_urls: vec![closed_url(), serve_once("ok")],
+            ..ProbeTargets::default()
+        };
+        assert!(test_http_async(&targets).await);
+    }
+
+    #[tokio::test]
+    async fn test_http_fails_when_all_urls_unreachable() {
+        let targets = ProbeTargets {
+            http_urls: vec![closed_url()],
+            ..ProbeTargets::default()
+        };
+        assert!(!test_http_async(&targets).await);
+    }
+
+    #[tokio::test]
+    async fn test_dns_with_no_domains_fails() {
+        let targets = ProbeTargets {
+            dns_domains: vec![],
+            ..ProbeTargets::default()
+        };
+        assert!(!test_dns_async(&targets).await);
+    }
+
+    #[test]
+    fn test_lookup_ip_location_uses_configured_base_url() {
+        let targets = ProbeTargets {
+            geoip_base_url: serve_once(r#"{"ip":"203.0.113.7","city":"Testville","country":"TV"}"#),
+            ..ProbeTargets::default()
+        };
+        let info = lookup_ip_location_with_targets("", &targets).unwrap();
+        assert_eq!(info.ip.as_deref(), Some("203.0.113.7"));
+        assert_eq!(info.city.as_deref(), Some("Testville"));
+        assert!(info.org.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_ip_location_async() {
+        let targets = ProbeTargets {
+            geoip_base_url: serve_once(r#"{"ip":"203.0.113.8","country":"TV"}"#),
+            ..ProbeTargets::default()
+        };
+        let info = lookup_ip_location_async("", &targets).await.unwrap();
+        assert_eq!(info.ip.as_deref(), Some("203.0.113.8"));
+    }
+
+    // ==================== Async API Tests ====================
+
+    #[tokio::test]
+    async fn test_run_diagnostics_async_matches_blocking() {
+        let backend = SimulatedBackend::healthy();
+        let settings = crate::domain::get_default_settings();
+        let snapshot = run_diagnostics_async(&backend, &settings).await;
+        let blocking = run_diagnostics_with_backend(&backend, &settings);
+        assert_eq!(snapshot.summary_key, "summary.ok");
+        assert_eq!(snapshot.summary_key, blocking.summary_key);
+        assert_eq!(snapshot.network.ssid, blocking.network.ssid);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_async_checks_compose() {
+        let backend = SimulatedBackend::healthy();
+        let targets = ProbeTargets::default();
+        let ((router, _), (internet, info), devices) = tokio::join!(
+            check_router_async(&backend),
+            check_internet_async(&backend, &targets),
+            scan_network_devices_async(&backend, None)
+        );
+        assert_eq!(router.status, Status::Ok);
+        assert_eq!(internet.status, Status::Ok);
+        assert!(info.dns_ok && info.http_ok);
+        assert_eq!(info.dnssec, DnssecStatus::Validating);
+        assert!(!devices.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broken_dnssec_warns_on_internet_node() {
+        let mut backend = SimulatedBackend::healthy();
+        backend.dnssec = DnssecStatus::Broken;
+        let (node, info) = check_internet_async(&backend, &ProbeTargets::default()).await;
+        assert!(info.dns_ok && info.http_ok);
+        assert_eq!(node.status, Status::Warn);
+        assert_eq!(
+            node.hint_key.as_deref(),
+            Some("nodes.internet.hint_dnssec_broken")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_diagnostics_report_unchecked_nodes() {
+        let backend = SimulatedBackend::healthy();
+        let token = crate::cancel::CancelToken::new();
+        token.cancel();
+        let cancel = Cancellation::new(&token, None);
+        let partial =
+            run_diagnostics_cancellable(&backend, &crate::domain::get_default_settings(), &cancel)
+                .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        assert_eq!(partial.value.nodes.len(), 4);
+        assert!(partial
+            .value
+            .nodes
+            .iter()
+            .all(|n| n.status == Status::Unknown && n.latency_ms.is_none()));
+        assert_eq!(partial.value.summary_key, "summary.warn");
+    }
+
+    #[tokio::test]
+    async fn test_scan_past_deadline_times_out() {
+        let backend = SimulatedBackend::healthy();
+        let cancel = Cancellation::new(&crate::cancel::CancelToken::new(), Some(Duration::ZERO));
+        let partial = scan_network_devices_cancellable(&backend, None, &cancel).await;
+        assert_eq!(partial.completion, Completion::TimedOut);
+        assert!(partial.value.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_scan_without_stop_is_complete() {
+        let backend = SimulatedBackend::healthy();
+        let cancel = Cancellation::new(
+            &crate::cancel::CancelToken::new(),
+            Some(Duration::from_secs(60)),
+        );
+        let partial = scan_network_devices_cancellable(&backend, None, &cancel).await;
+        assert!(partial.is_complete());
+        assert_eq!(
+            partial.value,
+            scan_network_devices_with_backend(&backend, None)
+        );
+    }
 }
@@ -0,0 +1,528 @@
+//! Snapshot comparison.
+//!
+//! Answers "what changed since it last worked" by comparing two diagnostics
+//! snapshots, security reports or device lists and returning typed changes.
+//! Argument order is always `(before, after)`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::detect_dns_provider;
+use crate::domain::{
+    ConnectionType, DiagnosticsSnapshot, DnsProvider, NetworkDevice, NodeId, Status,
+};
+use crate::infrastructure::security::{SecurityCheckType, SecurityStatus, WiFiSecurityReport};
+
+// ==================== Domain Types ====================
+
+/// A single difference between two results.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnapshotChange {
+    NodeStatusChanged {
+        node: NodeId,
+        from: Status,
+        to: Status,
+    },
+    LatencyRegressed {
+        node: NodeId,
+        from_ms: u32,
+        to_ms: u32,
+    },
+    ConnectionTypeChanged {
+        from: ConnectionType,
+        to: ConnectionType,
+    },
+    SsidChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    LocalIpChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    GatewayIpChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    GatewayMacChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    DnsServersChanged {
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    DnsProviderChanged {
+        from: DnsProvider,
+        to: DnsProvider,
+    },
+    PublicIpChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    IspChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    SignalDropped {
+        from_dbm: i32,
+        to_dbm: i32,
+    },
+    /// Wi-Fi encryption got weaker (e.g. WPA3 → WPA2, WPA2 → Open).
+    EncryptionDowngraded {
+        from: String,
+        to: String,
+    },
+    SecurityCheckChanged {
+        check: SecurityCheckType,
+        from: SecurityStatus,
+        to: SecurityStatus,
+    },
+    DeviceAppeared {
+        device: NetworkDevice,
+    },
+    DeviceDisappeared {
+        device: NetworkDevice,
+    },
+}
+
+/// Thresholds for noisy metrics.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct DiffOptions {
+    /// Latency must grow by at least this many milliseconds...
+    pub latency_min_increase_ms: u32,
+    /// ...and by at least this factor to count as a regression.
+    pub latency_ratio: f64,
+    /// RSSI must drop by at least this many dBm.
+    pub signal_drop_dbm: i32,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            latency_min_increase_ms: 50,
+            latency_ratio: 1.5,
+            signal_drop_dbm: 10,
+        }
+    }
+}
+
+// ==================== Diffing ====================
+
+/// Compare two diagnostics snapshots with default thresholds.
+pub fn diff_snapshots(a: &DiagnosticsSnapshot, b: &DiagnosticsSnapshot) -> Vec<SnapshotChange> {
+    diff_snapshots_with(a, b, &DiffOptions::default())
+}
+
+/// Compare two diagnostics snapshots.
+pub fn diff_snapshots_with(
+    a: &DiagnosticsSnapshot,
+    b: &DiagnosticsSnapshot,
+    options: &DiffOptions,
+) -> Vec<SnapshotChange> {
+    let mut changes = Vec::new();
+
+    // Nodes, matched by id
+    for before in &a.nodes {
+        let Some(after) = b.nodes.iter().find(|n| n.id == before.id) else {
+            continue;
+        };
+        if before.status != after.status {
+            changes.push(SnapshotChange::NodeStatusChanged {
+                node: before.id,
+                from: before.status,
+                to: after.status,
+            });
+        }
+        if let (Some(from_ms), Some(to_ms)) = (before.latency_ms, after.latency_ms) {
+            if latency_regressed(from_ms, to_ms, options) {
+                changes.push(SnapshotChange::LatencyRegressed {
+                    node: before.id,
+                    from_ms,
+                    to_ms,
+                });
+            }
+        }
+    }
+
+    if a.network.connection_type != b.network.connection_type {
+        changes.push(SnapshotChange::ConnectionTypeChanged {
+            from: a.network.connection_type,
+            to: b.network.connection_type,
+        });
+    }
+    if a.network.ssid != b.network.ssid {
+        changes.push(SnapshotChange::SsidChanged {
+            from: a.network.ssid.clone(),
+            to: b.network.ssid.clone(),
+        });
+    }
+    if a.computer.local_ip != b.computer.local_ip {
+        changes.push(SnapshotChange::LocalIpChanged {
+            from: a.computer.local_ip.clone(),
+            to: b.computer.local_ip.clone(),
+        });
+    }
+    if a.router.gateway_ip != b.router.gateway_ip {
+        changes.push(SnapshotChange::GatewayIpChanged {
+            from: a.router.gateway_ip.clone(),
+            to: b.router.gateway_ip.clone(),
+        });
+    }
+    if !same_mac(&a.router.gateway_mac, &b.router.gateway_mac) {
+        changes.push(SnapshotChange::GatewayMacChanged {
+            from: a.router.gateway_mac.clone(),
+            to: b.router.gateway_mac.clone(),
+        });
+    }
+
+    if a.network.dns_servers != b.network.dns_servers {
+        changes.push(SnapshotChange::DnsServersChanged {
+            from: a.network.dns_servers.clone(),
+            to: b.network.dns_servers.clone(),
+        });
+        let from = detect_dns_provider(&a.network.dns_servers);
+        let to = detect_dns_provider(&b.network.dns_servers);
+        if from != to {
+            changes.push(SnapshotChange::DnsProviderChanged { from, to });
+        }
+    }
+
+    // Public IP/ISP are only filled in by the geo lookup — skip when unknown
+    if a.internet.public_ip.is_some()
+        && b.internet.public_ip.is_some()
+        && a.internet.public_ip != b.internet.public_ip
+    {
+        changes.push(SnapshotChange::PublicIpChanged {
+            from: a.internet.public_ip.clone(),
+            to: b.internet.public_ip.clone(),
+        });
+    }
+    if a.internet.isp.is_some() && b.internet.isp.is_some() && a.internet.isp != b.internet.isp {
+        changes.push(SnapshotChange::IspChanged {
+            from: a.internet.isp.clone(),
+            to: b.internet.isp.clone(),
+        });
+    }
+
+    // Signal and encryption only make sense on the same Wi-Fi network
+    if a.network.ssid.is_some() && a.network.ssid == b.network.ssid {
+        if let (Some(from_dbm), Some(to_dbm)) = (a.network.rssi, b.network.rssi) {
+            if from_dbm - to_dbm >= options.signal_drop_dbm {
+                changes.push(SnapshotChange::SignalDropped { from_dbm, to_dbm });
+            }
+        }
+        if let (Some(from), Some(to)) = (&a.network.encryption, &b.network.encryption) {
+            if is_encryption_downgrade(from, to) {
+                changes.push(SnapshotChange::EncryptionDowngraded {
+                    from: from.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Compare two Wi-Fi security reports.
+pub fn diff_security_reports(
+    a: &WiFiSecurityReport,
+    b: &WiFiSecurityReport,
+) -> Vec<SnapshotChange> {
+    let mut changes = Vec::new();
+
+    for before in &a.checks {
+        let Some(after) = b.checks.iter().find(|c| c.check_type == before.check_type) else {
+            continue;
+        };
+        if before.status != after.status {
+            changes.push(SnapshotChange::SecurityCheckChanged {
+                check: before.check_type,
+                from: before.status,
+                to: after.status,
+            });
+        }
+        if before.check_type == SecurityCheckType::Encryption && a.network_ssid == b.network_ssid {
+            if let (Some(from), Some(to)) = (&before.details, &after.details) {
+                if is_encryption_downgrade(from, to) {
+                    changes.push(SnapshotChange::EncryptionDowngraded {
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    changes
+}
+
+/// Compare two device lists, matching devices by MAC address.
+pub fn diff_devices(a: &[NetworkDevice], b: &[NetworkDevice]) -> Vec<SnapshotChange> {
+    let contains =
+        |list: &[NetworkDevice], mac: &str| list.iter().any(|d| d.mac.eq_ignore_ascii_case(mac));
+
+    let appeared = b
+        .iter()
+        .filter(|d| !contains(a, &d.mac))
+        .map(|d| SnapshotChange::DeviceAppeared { device: d.clone() });
+    let disappeared = a
+        .iter()
+        .filter(|d| !contains(b, &d.mac))
+        .map(|d| SnapshotChange::DeviceDisappeared { device: d.clone() });
+
+    appeared.chain(disappeared).collect()
+}
+
+fn latency_regressed(from_ms: u32, to_ms: u32, options: &DiffOptions) -> bool {
+    to_ms >= from_ms.saturating_add(options.latency_min_increase_ms)
+        && f64::from(to_ms) >= f64::from(from_ms) * options.latency_ratio
+}
+
+fn same_mac(a: &Option<String>, b: &Option<String>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Strength rank of an encryption name, `None` if unrecognized.
+fn encryption_rank(name: &str) -> Option<u8> {
+    match name.to_ascii_uppercase().as_str() {
+        "OPEN" => Some(0),
+        "WEP" => Some(1),
+        "WPA" => Some(2),
+        "WPA2" => Some(3),
+        "WPA3" => Some(4),
+        _ => None,
+    }
+}
+
+fn is_encryption_downgrade(from: &str, to: &str) -> bool {
+    matches!(
+        (encryption_rank(from), encryption_rank(to)),
+        (Some(f), Some(t)) if t < f
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        ComputerInfo, DeviceType, InternetInfo, NetworkInfo, NodeInfo, RouterInfo,
+    };
+    use crate::infrastructure::security::SecurityCheck;
+
+    fn node(id: NodeId, status: Status, latency_ms: u32) -> NodeInfo {
+        NodeInfo {
+            id,
+            name_key: String::new(),
+            status,
+            latency_ms: Some(latency_ms),
+            hint_key: None,
+        }
+    }
+
+    fn snapshot() -> DiagnosticsSnapshot {
+        DiagnosticsSnapshot {
+            at_utc: "2024-01-17T10:00:00Z".into(),
+            nodes: vec![
+                node(NodeId::Computer, Status::Ok, 5),
+                node(NodeId::Internet, Status::Ok, 40),
+            ],
+            summary_key: "summary.ok".into(),
+            computer: ComputerInfo {
+                local_ip: Some("192.168.1.10".into()),
+                ..Default::default()
+            },
+            network: NetworkInfo {
+                connection_type: ConnectionType::Wifi,
+                ssid: Some("Home".into()),
+                rssi: Some(-50),
+                encryption: Some("WPA2".into()),
+                dns_servers: vec!["1.1.1.1".into(), "1.0.0.1".into()],
+                ..Default::default()
+            },
+            router: RouterInfo {
+                gateway_ip: Some("192.168.1.1".into()),
+                gateway_mac: Some("AA:BB:CC:DD:EE:FF".into()),
+                ..Default::default()
+            },
+            internet: InternetInfo::default(),
+        }
+    }
+
+    fn device(mac: &str) -> NetworkDevice {
+        NetworkDevice {
+            ip: "192.168.1.20".into(),
+            mac: mac.into(),
+            vendor: None,
+            hostname: None,
+            device_type: DeviceType::Unknown,
+            is_gateway: false,
+            is_self: false,
+            is_randomized: false,
+        }
+    }
+
+    #[test]
+    fn test_identical_snapshots_have_no_changes() {
+        assert!(diff_snapshots(&snapshot(), &snapshot()).is_empty());
+    }
+
+    #[test]
+    fn test_gateway_mac_change_ignores_case() {
+        let a = snapshot();
+        let mut b = snapshot();
+        b.router.gateway_mac = Some("aa:bb:cc:dd:ee:ff".into());
+        assert!(diff_snapshots(&a, &b).is_empty());
+
+        b.router.gateway_mac = Some("11:22:33:44:55:66".into());
+        assert_eq!(
+            diff_snapshots(&a, &b),
+            vec![SnapshotChange::GatewayMacChanged {
+                from: Some("AA:BB:CC:DD:EE:FF".into()),
+                to: Some("11:22:33:44:55:66".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_dns_provider_change() {
+        let a = snapshot();
+        let mut b = snapshot();
+        b.network.dns_servers = vec!["8.8.8.8".into(), "8.8.4.4".into()];
+        let changes = diff_snapshots(&a, &b);
+        assert!(matches!(
+            changes[0],
+            SnapshotChange::DnsServersChanged { .. }
+        ));
+        assert_eq!(
+            changes[1],
+            SnapshotChange::DnsProviderChanged {
+                from: DnsProvider::Catalog("Cloudflare".into()),
+                to: DnsProvider::Catalog("Google".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_node_status_and_latency() {
+        let a = snapshot();
+        let mut b = snapshot();
+        b.nodes[1] = node(NodeId::Internet, Status::Warn, 200);
+        b.nodes[0] = node(NodeId::Computer, Status::Ok, 9); // small jitter is ignored
+        assert_eq!(
+            diff_snapshots(&a, &b),
+            vec![
+                SnapshotChange::NodeStatusChanged {
+                    node: NodeId::Internet,
+                    from: Status::Ok,
+                    to: Status::Warn,
+                },
+                SnapshotChange::LatencyRegressed {
+                    node: NodeId::Internet,
+                    from_ms: 40,
+                    to_ms: 200,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encryption_downgrade_and_signal_drop() {
+        let a = snapshot();
+        let mut b = snapshot();
+        b.network.encryption = Some("Open".into());
+        b.network.rssi = Some(-75);
+        let changes = diff_snapshots(&a, &b);
+        assert!(changes.contains(&SnapshotChange::EncryptionDowngraded {
+            from: "WPA2".into(),
+            to: "Open".into(),
+        }));
+        assert!(changes.contains(&SnapshotChange::SignalDropped {
+            from_dbm: -50,
+            to_dbm: -75,
+        }));
+
+        // Upgrade is not reported
+        b.network.encryption = Some("WPA3".into());
+        b.network.rssi = Some(-50);
+        assert!(diff_snapshots(&a, &b).is_empty());
+    }
+
+    #[test]
+    fn test_different_ssid_skips_wifi_comparisons() {
+        let a = snapshot();
+        let mut b = snapshot();
+        b.network.ssid = Some("Cafe".into());
+        b.network.encryption = Some("Open".into());
+        assert_eq!(
+            diff_snapshots(&a, &b),
+            vec![SnapshotChange::SsidChanged {
+                from: Some("Home".into()),
+                to: Some("Cafe".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_security_reports() {
+        let report = |status, details: &str| WiFiSecurityReport {
+            checks: vec![SecurityCheck {
+                check_type: SecurityCheckType::Encryption,
+                status,
+                details: Some(details.into()),
+            }],
+            overall_status: status,
+            network_ssid: Some("Home".into()),
+            timestamp: 0,
+        };
+        let changes = diff_security_reports(
+            &report(SecurityStatus::Safe, "WPA3"),
+            &report(SecurityStatus::Danger, "Open"),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                SnapshotChange::SecurityCheckChanged {
+                    check: SecurityCheckType::Encryption,
+                    from: SecurityStatus::Safe,
+                    to: SecurityStatus::Danger,
+                },
+                SnapshotChange::EncryptionDowngraded {
+                    from: "WPA3".into(),
+                    to: "Open".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_devices() {
+        let a = vec![device("AA:00:00:00:00:01"), device("AA:00:00:00:00:02")];
+        let b = vec![device("aa:00:00:00:00:02"), device("AA:00:00:00:00:03")];
+        let changes = diff_devices(&a, &b);
+        assert_eq!(changes.len(), 2);
+        assert!(
+            matches!(&changes[0], SnapshotChange::DeviceAppeared { device } if device.mac == "AA:00:00:00:00:03")
+        );
+        assert!(
+            matches!(&changes[1], SnapshotChange::DeviceDisappeared { device } if device.mac == "AA:00:00:00:00:01")
+        );
+    }
+
+    #[test]
+    fn test_change_serialization() {
+        let change = SnapshotChange::GatewayMacChanged {
+            from: None,
+            to: Some("AA:BB:CC:DD:EE:FF".into()),
+        };
+        let json = serde_json::to_value(&change).unwrap();
+        assert_eq!(json["type"], "gateway_mac_changed");
+        assert_eq!(json["to"], "AA:BB:CC:DD:EE:FF");
+    }
+}
@@ -0,0 +1,519 @@
+//! DNS resolver benchmark.
+//!
+//! Every resolver (the one configured on this machine plus each built-in
+//! `DnsProvider`) gets the same mix of queries: random names no resolver can
+//! have cached, which measure a full recursive lookup, and popular names
+//! (warmed up first) which measure the resolver's cache. Resolvers run
+//! concurrently, their queries one at a time so they don't queue behind each
+//! other. Each resolver's DNSSEC validation is checked too. The result ranks
+//! resolvers by median latency, reliable ones first, and recommends the
+//! winner, never one whose DNSSEC validation is broken. Lookups go through
+//! [`NetworkBackend::dns_connect_async`].
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::NetworkBackend;
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::domain::{DnsProvider, DnsTransport, DnssecStatus};
+use crate::infrastructure::dns_transport::{provider_endpoints, DnsEndpoint};
+use crate::runtime::blocking;
+
+/// Names popular enough that any busy resolver has them cached.
+const POPULAR_DOMAINS: [&str; 10] = [
+    "google.com.",
+    "youtube.com.",
+    "facebook.com.",
+    "wikipedia.org.",
+    "amazon.com.",
+    "instagram.com.",
+    "microsoft.com.",
+    "apple.com.",
+    "netflix.com.",
+    "cloudflare.com.",
+];
+
+/// Unsigned zones whose random subdomains force a lookup at the authoritative
+/// servers (a DNSSEC-signed zone could be answered from cached NSEC records).
+const UNCACHED_PARENTS: [&str; 4] = [
+    "google.com.",
+    "amazon.com.",
+    "youtube.com.",
+    "microsoft.com.",
+];
+
+/// Highest error rate of a resolver still considered reliable.
+pub const MAX_RELIABLE_ERROR_RATE: f64 = 0.05;
+
+// ==================== Domain Types ====================
+
+/// What to send to each resolver.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct BenchmarkOptions {
+    /// Transport to reach every resolver over. The current resolver is only
+    /// benchmarked over UDP.
+    pub transport: DnsTransport,
+    /// Queries for random names, answered by a full recursive lookup.
+    pub uncached_queries: usize,
+    /// Queries for popular names, answered from the resolver's cache.
+    pub cached_queries: usize,
+    /// Time to wait for each answer.
+    pub timeout_ms: u64,
+    /// Only recommend a resolver that validates DNSSEC.
+    pub require_dnssec: bool,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        Self {
+            transport: DnsTransport::Udp,
+            uncached_queries: 10,
+            cached_queries: 10,
+            timeout_ms: 2000,
+            require_dnssec: false,
+        }
+    }
+}
+
+/// Results for one resolver.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResolverBenchmark {
+    /// `Auto` for the resolver currently configured on this machine.
+    pub provider: DnsProvider,
+    /// Server as accepted by `ping_dns_server`.
+    pub server: String,
+    /// Queries sent (fewer than asked for if the run was stopped).
+    pub queries: usize,
+    /// Queries without an answer in time.
+    pub errors: usize,
+    /// `errors / queries`, 0 when nothing was sent.
+    pub error_rate: f64,
+    /// Median latency of the answered queries.
+    pub median_ms: Option<f64>,
+    /// 95th percentile latency of the answered queries.
+    pub p95_ms: Option<f64>,
+    /// Median latency of the popular (cached) names alone.
+    pub cached_median_ms: Option<f64>,
+    /// Median latency of the random (uncached) names alone.
+    pub uncached_median_ms: Option<f64>,
+    /// Answered at least once, and at most [`MAX_RELIABLE_ERROR_RATE`] errors.
+    pub reliable: bool,
+    /// Whether the resolver validates DNSSEC, checked over plain DNS.
+    #[serde(default)]
+    pub dnssec: DnssecStatus,
+}
+
+/// Results for every resolver, fastest recommendable first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DnsBenchmark {
+    pub transport: DnsTransport,
+    pub resolvers: Vec<ResolverBenchmark>,
+    /// Fastest reliable resolver whose DNSSEC validation isn't broken (or,
+    /// with `require_dnssec`, that validates); `Auto` means the current one is
+    /// best. `None` if no resolver qualifies.
+    pub recommended: Option<DnsProvider>,
+}
+
+// ==================== Statistics ====================
+
+/// `q`-quantile (0–1) of sorted `values`, nearest-rank method.
+fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+fn sorted_latencies<'a>(samples: impl Iterator<Item = &'a Option<f64>>) -> Vec<f64> {
+    let mut latencies: Vec<f64> = samples.flatten().copied().collect();
+    latencies.sort_by(f64::total_cmp);
+    latencies
+}
+
+/// Summarize per-query latencies (`None` for an error).
+fn summarize(
+    provider: DnsProvider,
+    server: String,
+    cached: &[Option<f64>],
+    uncached: &[Option<f64>],
+) -> ResolverBenchmark {
+    let all = sorted_latencies(cached.iter().chain(uncached));
+    let queries = cached.len() + uncached.len();
+    let errors = queries - all.len();
+    let error_rate = if queries == 0 {
+        0.0
+    } else {
+        errors as f64 / queries as f64
+    };
+    ResolverBenchmark {
+        provider,
+        server,
+        queries,
+        errors,
+        error_rate,
+        median_ms: quantile(&all, 0.5),
+        p95_ms: quantile(&all, 0.95),
+        cached_median_ms: quantile(&sorted_latencies(cached.iter()), 0.5),
+        uncached_median_ms: quantile(&sorted_latencies(uncached.iter()), 0.5),
+        reliable: !all.is_empty() && error_rate <= MAX_RELIABLE_ERROR_RATE,
+        dnssec: DnssecStatus::Unknown,
+    }
+}
+
+/// Whether `resolver` may be recommended.
+fn recommendable(resolver: &ResolverBenchmark, require_dnssec: bool) -> bool {
+    resolver.reliable
+        && match resolver.dnssec {
+            DnssecStatus::Validating => true,
+            DnssecStatus::Broken => false,
+            DnssecStatus::NonValidating | DnssecStatus::Unknown => !require_dnssec,
+        }
+}
+
+/// Sort recommendable resolvers first, then reliable ones, then by median
+/// latency, and pick the winner.
+fn rank(mut resolvers: Vec<ResolverBenchmark>, options: &BenchmarkOptions) -> DnsBenchmark {
+    let eligible = |r: &ResolverBenchmark| recommendable(r, options.require_dnssec);
+    resolvers.sort_by(|a, b| {
+        eligible(b)
+            .cmp(&eligible(a))
+            .then_with(|| b.reliable.cmp(&a.reliable))
+            .then_with(|| {
+                let median = |r: &ResolverBenchmark| r.median_ms.unwrap_or(f64::INFINITY);
+                median(a).total_cmp(&median(b))
+            })
+    });
+    let recommended = resolvers
+        .first()
+        .filter(|r| eligible(r))
+        .map(|r| r.provider.clone());
+    DnsBenchmark {
+        transport: options.transport,
+        resolvers,
+        recommended,
+    }
+}
+
+// ==================== Benchmark ====================
+
+/// Random name under `parent` that no resolver has seen.
+pub(crate) fn uncached_name(parent: &str) -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(Instant::now().elapsed().as_nanos());
+    format!(
+        "netok-{:012x}.{}",
+        hasher.finish() & 0xffff_ffff_ffff,
+        parent
+    )
+}
+
+/// Resolvers to benchmark over `transport`: the current one first, then
+/// every built-in provider.
+fn resolver_endpoints(
+    current: &[String],
+    transport: DnsTransport,
+) -> Vec<(DnsProvider, DnsEndpoint)> {
+    let current = current
+        .iter()
+        .find_map(|server| server.parse().ok())
+        .filter(|_| transport == DnsTransport::Udp)
+        .map(|ip| (DnsProvider::Auto, DnsEndpoint::Udp(ip)));
+    let built_in = DnsProvider::built_in().into_iter().filter_map(|provider| {
+        let endpoint = provider_endpoints(&provider)
+            .into_iter()
+            .find(|e| e.transport() == transport)?;
+        Some((provider, endpoint))
+    });
+    current.into_iter().chain(built_in).collect()
+}
+
+/// Plain DNS address of a resolver reached at `endpoint`, for the DNSSEC check.
+fn udp_address(provider: &DnsProvider, endpoint: &DnsEndpoint) -> Option<IpAddr> {
+    match endpoint {
+        DnsEndpoint::Udp(ip) => Some(*ip),
+        _ => provider.primary()?.parse().ok(),
+    }
+}
+
+/// Benchmark one resolver, stopping early when `cancel` fires.
+async fn bench_resolver(
+    backend: &dyn NetworkBackend,
+    provider: DnsProvider,
+    endpoint: DnsEndpoint,
+    options: &BenchmarkOptions,
+    cancel: &Cancellation,
+) -> (ResolverBenchmark, Completion) {
+    let timeout = Duration::from_millis(options.timeout_ms);
+    let mut completion = Completion::Complete;
+    let (mut cached, mut uncached) = (Vec::new(), Vec::new());
+    let popular = &POPULAR_DOMAINS[..options.cached_queries.min(POPULAR_DOMAINS.len())];
+
+    // Before the timed queries, so it doesn't compete with them
+    let dnssec = match udp_address(&provider, &endpoint) {
+        Some(ip) => cancel
+            .step(&mut completion, backend.resolver_dnssec_async(ip, timeout))
+            .await
+            .unwrap_or_default(),
+        None => DnssecStatus::Unknown,
+    };
+
+    match cancel
+        .step(
+            &mut completion,
+            backend.dns_connect_async(&endpoint, timeout),
+        )
+        .await
+    {
+        Some(Some(client)) => {
+            // Make sure the popular names are cached before timing them
+            for name in popular {
+                if cancel
+                    .step(&mut completion, client.timed_lookup(name))
+                    .await
+                    .is_none()
+                {
+                    break;
+                }
+            }
+            for i in 0..options.uncached_queries.max(options.cached_queries) {
+                if i < options.uncached_queries {
+                    let name = uncached_name(UNCACHED_PARENTS[i % UNCACHED_PARENTS.len()]);
+                    match cancel
+                        .step(&mut completion, client.timed_lookup(&name))
+                        .await
+                    {
+                        Some(latency) => uncached.push(latency),
+                        None => break,
+                    }
+                }
+                if i < options.cached_queries {
+                    let name = popular[i % popular.len()];
+                    match cancel
+                        .step(&mut completion, client.timed_lookup(name))
+                        .await
+                    {
+                        Some(latency) => cached.push(latency),
+                        None => break,
+                    }
+                }
+            }
+        }
+        // Unreachable (e.g. a DoT hostname that doesn't resolve): every query fails
+        Some(None) => {
+            cached = vec![None; options.cached_queries];
+            uncached = vec![None; options.uncached_queries];
+        }
+        None => {}
+    }
+
+    let summary = ResolverBenchmark {
+        dnssec,
+        ..summarize(provider, endpoint.to_string(), &cached, &uncached)
+    };
+    (summary, completion)
+}
+
+/// Benchmark the current resolver and every built-in provider.
+pub fn run_dns_benchmark(backend: &dyn NetworkBackend, options: &BenchmarkOptions) -> DnsBenchmark {
+    crate::runtime::block_on(run_dns_benchmark_async(backend, options))
+}
+
+/// Async [`run_dns_benchmark`].
+pub async fn run_dns_benchmark_async(
+    backend: &dyn NetworkBackend,
+    options: &BenchmarkOptions,
+) -> DnsBenchmark {
+    run_dns_benchmark_cancellable(backend, options, &Cancellation::none())
+        .await
+        .value
+}
+
+/// [`run_dns_benchmark_async`] that stops when `cancel` fires, ranking what was
+/// measured so far.
+pub async fn run_dns_benchmark_cancellable(
+    backend: &dyn NetworkBackend,
+    options: &BenchmarkOptions,
+    cancel: &Cancellation,
+) -> Partial<DnsBenchmark> {
+    let current = blocking(|| backend.dns_servers()).unwrap_or_default();
+    let runs = resolver_endpoints(&current, options.transport)
+        .into_iter()
+        .map(|(provider, endpoint)| bench_resolver(backend, provider, endpoint, options, cancel));
+    let results = join_all(runs).await;
+
+    let completion = results
+        .iter()
+        .map(|(_, completion)| *completion)
+        .find(|c| *c != Completion::Complete)
+        .unwrap_or_default();
+    let resolvers = results.into_iter().map(|(summary, _)| summary).collect();
+    Partial::new(rank(resolvers, options), completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SimulatedBackend;
+    use crate::cancel::CancelToken;
+
+    fn ms(values: &[f64]) -> Vec<Option<f64>> {
+        values.iter().copied().map(Some).collect()
+    }
+
+    #[test]
+    fn test_quantile_nearest_rank() {
+        let sorted: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert_eq!(quantile(&sorted, 0.5), Some(10.0));
+        assert_eq!(quantile(&sorted, 0.95), Some(19.0));
+        assert_eq!(quantile(&[7.0], 0.95), Some(7.0));
+        assert_eq!(quantile(&[], 0.5), None);
+    }
+
+    #[test]
+    fn test_summarize_counts_errors() {
+        let mut uncached = ms(&[40.0, 60.0, 50.0]);
+        uncached.push(None);
+        let summary = summarize(
+            DnsProvider::Catalog("Google".into()),
+            "8.8.8.8".into(),
+            &ms(&[5.0, 3.0, 4.0, 6.0]),
+            &uncached,
+        );
+        assert_eq!(summary.queries, 8);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.error_rate, 0.125);
+        assert!(!summary.reliable);
+        assert_eq!(summary.median_ms, Some(6.0));
+        assert_eq!(summary.p95_ms, Some(60.0));
+        assert_eq!(summary.cached_median_ms, Some(4.0));
+        assert_eq!(summary.uncached_median_ms, Some(50.0));
+    }
+
+    #[test]
+    fn test_unanswered_resolver_is_unreliable() {
+        let summary = summarize(
+            DnsProvider::Catalog("Google".into()),
+            "8.8.8.8".into(),
+            &[],
+            &[],
+        );
+        assert_eq!(summary.error_rate, 0.0);
+        assert_eq!(summary.median_ms, None);
+        assert!(!summary.reliable);
+    }
+
+    #[test]
+    fn test_rank_recommends_fastest_reliable() {
+        let fast_but_flaky = summarize(
+            DnsProvider::Catalog("Google".into()),
+            "8.8.8.8".into(),
+            &ms(&[1.0, 1.0]),
+            &[None, Some(1.0)],
+        );
+        let slow = summarize(
+            DnsProvider::Catalog("Quad9Recommended".into()),
+            "9.9.9.9".into(),
+            &ms(&[30.0]),
+            &ms(&[90.0]),
+        );
+        let fast = summarize(
+            DnsProvider::Auto,
+            "192.168.1.1".into(),
+            &ms(&[2.0]),
+            &ms(&[20.0]),
+        );
+        let options = BenchmarkOptions::default();
+        let benchmark = rank(vec![fast_but_flaky, slow, fast], &options);
+        assert_eq!(benchmark.recommended, Some(DnsProvider::Auto));
+        let order: Vec<_> = benchmark.resolvers.iter().map(|r| &r.server).collect();
+        assert_eq!(order, ["192.168.1.1", "9.9.9.9", "8.8.8.8"]);
+
+        let none = rank(vec![benchmark.resolvers[2].clone()], &options);
+        assert_eq!(none.recommended, None);
+    }
+
+    #[test]
+    fn test_rank_follows_dnssec() {
+        let resolver = |provider: &str, server: &str, latency: f64, dnssec| ResolverBenchmark {
+            dnssec,
+            ..summarize(
+                DnsProvider::Catalog(provider.into()),
+                server.into(),
+                &ms(&[latency]),
+                &ms(&[latency]),
+            )
+        };
+        let resolvers = vec![
+            resolver("Google", "8.8.8.8", 30.0, DnssecStatus::Validating),
+            resolver("Cloudflare", "1.1.1.1", 10.0, DnssecStatus::Broken),
+            resolver("AdGuard", "94.140.14.14", 20.0, DnssecStatus::NonValidating),
+        ];
+
+        // A broken validator is never recommended, however fast
+        let benchmark = rank(resolvers.clone(), &BenchmarkOptions::default());
+        assert_eq!(
+            benchmark.recommended,
+            Some(DnsProvider::Catalog("AdGuard".into()))
+        );
+        assert_eq!(benchmark.resolvers[2].server, "1.1.1.1");
+
+        let options = BenchmarkOptions {
+            require_dnssec: true,
+            ..Default::default()
+        };
+        let benchmark = rank(resolvers, &options);
+        assert_eq!(
+            benchmark.recommended,
+            Some(DnsProvider::Catalog("Google".into()))
+        );
+    }
+
+    #[test]
+    fn test_uncached_names_are_unique() {
+        let a = uncached_name("google.com.");
+        let b = uncached_name("google.com.");
+        assert_ne!(a, b);
+        assert!(a.starts_with("netok-") && a.ends_with(".google.com."));
+    }
+
+    #[test]
+    fn test_resolver_endpoints_per_transport() {
+        let current = vec!["not-an-ip".to_string(), "192.168.1.1".to_string()];
+        let udp = resolver_endpoints(&current, DnsTransport::Udp);
+        assert_eq!(udp.len(), DnsProvider::built_in().len() + 1);
+        assert_eq!(
+            udp[0],
+            (
+                DnsProvider::Auto,
+                DnsEndpoint::Udp("192.168.1.1".parse().unwrap())
+            )
+        );
+
+        // The current resolver is only reachable over plain DNS
+        let doh = resolver_endpoints(&current, DnsTransport::Doh);
+        assert_eq!(doh.len(), DnsProvider::built_in().len());
+        assert!(doh.iter().all(|(_, e)| e.transport() == DnsTransport::Doh));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_benchmark_sends_nothing() {
+        let token = CancelToken::new();
+        token.cancel();
+        let partial = run_dns_benchmark_cancellable(
+            &SimulatedBackend::healthy(),
+            &BenchmarkOptions::default(),
+            &Cancellation::new(&token, None),
+        )
+        .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        assert!(partial.value.resolvers.iter().all(|r| r.queries == 0));
+        assert_eq!(partial.value.recommended, None);
+    }
+}
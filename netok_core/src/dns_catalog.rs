@@ -0,0 +1,425 @@
+//! DNS provider catalog and user presets.
+//!
+//! The built-in providers live in `dns_catalog.json`, embedded at build time:
+//! addresses, DoH/DoT endpoints, what each one filters and how the UI groups
+//! it. [`DnsProvider::Catalog`] refers to an entry by id, and detection and
+//! the bridge's provider type are driven by the same entries, so adding a
+//! provider is a catalog edit.
+//!
+//! Presets are providers the user saved under their own name (e.g. "Work" for
+//! a custom resolver), stored as one JSON file rewritten atomically.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use crate::domain::DnsProvider;
+use crate::error::{PresetError, StorageError};
+
+/// Version of the embedded catalog's format.
+pub const CATALOG_FORMAT_VERSION: u32 = 1;
+
+/// Version of the preset file format. Files with a newer version are rejected
+/// rather than misread.
+pub const PRESET_FORMAT_VERSION: u32 = 1;
+
+static CATALOG: LazyLock<DnsCatalog> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("dns_catalog.json")).expect("embedded DNS catalog is valid")
+});
+
+// ==================== Domain Types ====================
+
+/// What a provider's goal is, for grouping in the UI.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderCategory {
+    /// Resolves everything
+    Standard,
+    /// Blocks malware and phishing
+    Security,
+    /// Blocks adult content as well
+    Family,
+    /// Blocks ads and trackers
+    Adblock,
+}
+
+/// Kind of content a provider refuses to resolve.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsFilter {
+    /// Malware and phishing domains
+    Malware,
+    /// Adult content
+    Adult,
+    /// Ads and trackers
+    Ads,
+}
+
+/// One built-in provider.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CatalogProvider {
+    /// Stable id, also the serialized [`DnsProvider`] (e.g. `"Quad9Recommended"`).
+    pub id: String,
+    /// Operator the UI groups variants under (e.g. `"Quad9"`).
+    pub family: String,
+    /// Variant within the family; `None` if the family has only one.
+    pub variant: Option<String>,
+    /// Display name.
+    pub name: String,
+    pub category: ProviderCategory,
+    /// Content blocked; empty for unfiltered resolvers.
+    pub filtering: Vec<DnsFilter>,
+    /// Networks (ASNs) the provider's resolvers query authoritative servers
+    /// from, which identify it behind a forwarder.
+    #[serde(default)]
+    pub asns: Vec<u32>,
+    /// IPv4 servers, primary first.
+    pub ipv4: Vec<String>,
+    /// IPv6 servers, primary first.
+    pub ipv6: Vec<String>,
+    /// DoH URL template (RFC 8484).
+    pub doh_template: Option<String>,
+    /// DoT hostname (RFC 7858).
+    pub dot_hostname: Option<String>,
+}
+
+impl CatalogProvider {
+    /// Whether `servers` (primary first) are this provider's: the primary
+    /// matches, and the secondary too if both have one.
+    fn matches(&self, servers: &[String]) -> bool {
+        let ours = |i: usize| self.ipv4.get(i).map(String::as_str);
+        let theirs = |i: usize| servers.get(i).map(String::as_str);
+        ours(0).is_some()
+            && ours(0) == theirs(0)
+            && match (ours(1), theirs(1)) {
+                (Some(ours), Some(theirs)) => ours == theirs,
+                _ => true,
+            }
+    }
+}
+
+/// The built-in provider list.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DnsCatalog {
+    pub version: u32,
+    pub providers: Vec<CatalogProvider>,
+}
+
+impl DnsCatalog {
+    /// The catalog embedded in this build.
+    pub fn built_in() -> &'static DnsCatalog {
+        &CATALOG
+    }
+
+    /// Entry with this id.
+    pub fn get(&self, id: &str) -> Option<&CatalogProvider> {
+        self.providers.iter().find(|p| p.id == id)
+    }
+
+    /// Entry by family and variant, as the UI names providers.
+    pub fn find(&self, family: &str, variant: Option<&str>) -> Option<&CatalogProvider> {
+        self.providers
+            .iter()
+            .find(|p| p.family == family && p.variant.as_deref() == variant)
+    }
+
+    /// First entry of the family whose resolvers are on network `asn`.
+    pub fn find_by_asn(&self, asn: u32) -> Option<&CatalogProvider> {
+        self.providers.iter().find(|p| p.asns.contains(&asn))
+    }
+
+    /// Entry whose IPv4 servers are `servers` (primary first).
+    pub fn detect(&self, servers: &[String]) -> Option<&CatalogProvider> {
+        self.providers.iter().find(|p| p.matches(servers))
+    }
+}
+
+/// A provider saved by the user under a name.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DnsPreset {
+    pub name: String,
+    pub provider: DnsProvider,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PresetFile {
+    version: u32,
+    presets: Vec<DnsPreset>,
+}
+
+// ==================== Store ====================
+
+/// User presets in a JSON file, kept in the order they were first saved.
+#[derive(Debug)]
+pub struct PresetStore {
+    path: PathBuf,
+    /// Serializes read-modify-write cycles within this process.
+    lock: Mutex<()>,
+}
+
+impl PresetStore {
+    /// Open the store at `path`; the file is created on the first save.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(StorageError::io("create preset dir"))?;
+        }
+        Ok(Self {
+            path,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All presets.
+    pub fn list(&self) -> Result<Vec<DnsPreset>, PresetError> {
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        self.read()
+    }
+
+    /// Preset named `name` (case-insensitive).
+    pub fn get(&self, name: &str) -> Result<Option<DnsPreset>, PresetError> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name.trim())))
+    }
+
+    /// Add `preset`, replacing one with the same name (case-insensitive).
+    pub fn save(&self, preset: DnsPreset) -> Result<(), PresetError> {
+        let name = preset.name.trim();
+        if name.is_empty() {
+            return Err(PresetError::InvalidName(preset.name));
+        }
+        if let DnsProvider::Catalog(id) = &preset.provider {
+            if DnsCatalog::built_in().get(id).is_none() {
+                return Err(PresetError::UnknownProvider(id.clone()));
+            }
+        }
+        let preset = DnsPreset {
+            name: name.to_string(),
+            provider: preset.provider,
+        };
+
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        let mut presets = self.read()?;
+        match presets
+            .iter_mut()
+            .find(|p| p.name.eq_ignore_ascii_case(&preset.name))
+        {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+        self.write(presets)
+    }
+
+    /// Delete the preset named `name`. Returns `false` if there was none.
+    pub fn remove(&self, name: &str) -> Result<bool, PresetError> {
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        let mut presets = self.read()?;
+        let before = presets.len();
+        presets.retain(|p| !p.name.eq_ignore_ascii_case(name.trim()));
+        if presets.len() == before {
+            return Ok(false);
+        }
+        self.write(presets)?;
+        Ok(true)
+    }
+
+    fn read(&self) -> Result<Vec<DnsPreset>, PresetError> {
+        let json = match std::fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(StorageError::io("read preset file")(e).into()),
+        };
+        let version = serde_json::from_str::<serde_json::Value>(&json)
+            .ok()
+            .and_then(|v| v.get("version")?.as_u64());
+        if let Some(found) = version.filter(|&v| v > u64::from(PRESET_FORMAT_VERSION)) {
+            return Err(PresetError::UnsupportedVersion {
+                found: found.try_into().unwrap_or(u32::MAX),
+                supported: PRESET_FORMAT_VERSION,
+            });
+        }
+        serde_json::from_str::<PresetFile>(&json)
+            .map(|file| file.presets)
+            .map_err(|e| PresetError::Invalid(e.to_string()))
+    }
+
+    fn write(&self, presets: Vec<DnsPreset>) -> Result<(), PresetError> {
+        let file = PresetFile {
+            version: PRESET_FORMAT_VERSION,
+            presets,
+        };
+        let json = serde_json::to_string_pretty(&file).map_err(|e| StorageError::Serialize {
+            what: "DNS presets",
+            message: e.to_string(),
+        })?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        {
+            let mut tmp =
+                File::create(&tmp_path).map_err(StorageError::io("create temp preset file"))?;
+            tmp.write_all(json.as_bytes())
+                .map_err(StorageError::io("write temp preset file"))?;
+            tmp.sync_all()
+                .map_err(StorageError::io("flush temp preset file"))?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(StorageError::io("replace preset file"))
+            .map_err(PresetError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+    use std::collections::HashSet;
+
+    fn custom(primary: &str) -> DnsProvider {
+        DnsProvider::Custom(primary.into(), String::new(), None, None, None, None)
+    }
+
+    #[test]
+    fn test_built_in_catalog_is_consistent() {
+        let catalog = DnsCatalog::built_in();
+        assert_eq!(catalog.version, CATALOG_FORMAT_VERSION);
+        assert!(!catalog.providers.is_empty());
+
+        let mut ids = HashSet::new();
+        let mut variants = HashSet::new();
+        for entry in &catalog.providers {
+            assert!(ids.insert(&entry.id), "duplicate id {}", entry.id);
+            assert!(
+                variants.insert((&entry.family, &entry.variant)),
+                "duplicate variant of {}",
+                entry.id
+            );
+            assert_ne!(entry.id, "Auto");
+            assert!(!entry.ipv4.is_empty(), "{} has no IPv4 server", entry.id);
+            for ip in entry.ipv4.iter().chain(&entry.ipv6) {
+                assert!(
+                    ip.parse::<std::net::IpAddr>().is_ok(),
+                    "{}: {}",
+                    entry.id,
+                    ip
+                );
+            }
+            if let Some(template) = &entry.doh_template {
+                assert!(template.starts_with("https://"), "{}", entry.id);
+            }
+            assert_eq!(
+                entry.filtering.is_empty(),
+                entry.category == ProviderCategory::Standard,
+                "{}",
+                entry.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_catalog_lookup() {
+        let catalog = DnsCatalog::built_in();
+        let quad9 = catalog.get("Quad9Recommended").unwrap();
+        assert_eq!(quad9.ipv4, ["9.9.9.9", "149.112.112.112"]);
+        assert_eq!(catalog.find("Quad9", Some("Recommended")), Some(quad9));
+        assert_eq!(catalog.find("Google", None).unwrap().id, "Google");
+        assert_eq!(catalog.find("Google", Some("Standard")), None);
+        assert_eq!(catalog.get("Nope"), None);
+        assert_eq!(catalog.find_by_asn(13335).unwrap().family, "Cloudflare");
+        assert_eq!(catalog.find_by_asn(64500), None);
+    }
+
+    #[test]
+    fn test_detect_matches_primary_and_secondary() {
+        let catalog = DnsCatalog::built_in();
+        let detect = |servers: &[&str]| {
+            let servers: Vec<String> = servers.iter().map(|s| s.to_string()).collect();
+            catalog.detect(&servers).map(|p| p.id.as_str())
+        };
+        assert_eq!(detect(&["1.1.1.1", "1.0.0.1"]), Some("Cloudflare"));
+        assert_eq!(detect(&["1.1.1.1"]), Some("Cloudflare"));
+        // A different secondary makes it someone's own mix
+        assert_eq!(detect(&["1.1.1.1", "8.8.8.8"]), None);
+        // Single-address providers accept any secondary
+        assert_eq!(detect(&["86.54.11.1", "1.1.1.1"]), Some("Dns4EuProtective"));
+        assert_eq!(detect(&[]), None);
+    }
+
+    #[test]
+    fn test_preset_store_round_trip() {
+        let path = TempPath::new("presets_round_trip.json");
+        let store = PresetStore::open(&path.0).unwrap();
+        assert!(store.list().unwrap().is_empty());
+
+        store
+            .save(DnsPreset {
+                name: " Work ".into(),
+                provider: custom("10.0.0.53"),
+            })
+            .unwrap();
+        store
+            .save(DnsPreset {
+                name: "Kids".into(),
+                provider: DnsProvider::Catalog("CloudflareFamily".into()),
+            })
+            .unwrap();
+        // Same name, different case: replaced in place
+        store
+            .save(DnsPreset {
+                name: "work".into(),
+                provider: custom("10.0.0.54"),
+            })
+            .unwrap();
+
+        let presets = PresetStore::open(&path.0).unwrap().list().unwrap();
+        assert_eq!(presets.len(), 2);
+        assert_eq!(presets[0].name, "work");
+        assert_eq!(presets[0].provider, custom("10.0.0.54"));
+        assert_eq!(
+            store.get("KIDS").unwrap().unwrap().provider,
+            DnsProvider::Catalog("CloudflareFamily".into())
+        );
+
+        assert!(store.remove("Work").unwrap());
+        assert!(!store.remove("Work").unwrap());
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_preset_store_rejects_bad_presets() {
+        let path = TempPath::new("presets_invalid.json");
+        let store = PresetStore::open(&path.0).unwrap();
+        let err = store
+            .save(DnsPreset {
+                name: "  ".into(),
+                provider: DnsProvider::Auto,
+            })
+            .unwrap_err();
+        assert!(matches!(err, PresetError::InvalidName(_)));
+        let err = store
+            .save(DnsPreset {
+                name: "Gone".into(),
+                provider: DnsProvider::Catalog("NoSuchProvider".into()),
+            })
+            .unwrap_err();
+        assert!(matches!(err, PresetError::UnknownProvider(_)));
+        assert!(store.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_preset_file_from_newer_version_is_rejected() {
+        let path = TempPath::new("presets_version.json");
+        std::fs::write(&path.0, r#"{"version": 99, "presets": [], "extra": true}"#).unwrap();
+        let err = PresetStore::open(&path.0).unwrap().list().unwrap_err();
+        assert!(matches!(
+            err,
+            PresetError::UnsupportedVersion { found: 99, .. }
+        ));
+    }
+}
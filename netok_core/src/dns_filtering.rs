@@ -0,0 +1,520 @@
+//! Check that a filtering DNS provider actually filters.
+//!
+//! For each kind of content a provider can block ([`DnsFilter`]) a few canary
+//! domains, published by filtering services for exactly this test, are
+//! resolved two ways: directly through the provider, which shows whether the
+//! provider blocks the category, and through the system resolver, which shows
+//! whether apps on this machine get the block. A category the provider blocks
+//! but the system resolver lets through means lookups don't reach the
+//! provider: another resolver configured next to it, a VPN, or a router that
+//! intercepts DNS.
+//!
+//! Filtering resolvers answer a blocked name with no address, an unroutable
+//! one (`0.0.0.0`) or the address of their block page. Since a canary that no
+//! longer exists also has no address, an empty answer only counts as blocked
+//! if [`ProbeTargets::trusted_dns_server`] resolves the canary.
+//!
+//! Browsers can go around the system resolver with their own DNS-over-HTTPS.
+//! Firefox keeps its automatic DoH off when the network's resolver refuses
+//! [`DOH_CANARY`]; a resolver that answers it leaves that path open.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::NetworkBackend;
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::dns_catalog::{DnsCatalog, DnsFilter};
+use crate::domain::{DnsProvider, ProbeTargets};
+use crate::runtime::blocking;
+
+/// Canary domains for each category, any of which a filter may block.
+const CANARIES: &[(DnsFilter, &[&str])] = &[
+    (
+        DnsFilter::Malware,
+        &["malware.testcategory.com", "internetbadguys.com"],
+    ),
+    (
+        DnsFilter::Adult,
+        &["nudity.testcategory.com", "exampleadultsite.com"],
+    ),
+    (
+        DnsFilter::Ads,
+        &["pagead2.googlesyndication.com", "ad.doubleclick.net"],
+    ),
+];
+
+/// Name every working resolver answers, filtering or not.
+const CONTROL_DOMAIN: &str = "example.com";
+
+/// Mozilla's canary: a resolver that refuses it tells Firefox not to switch
+/// to its own DNS-over-HTTPS.
+pub const DOH_CANARY: &str = "use-application-dns.net";
+
+/// Block pages of OpenDNS (Cisco Umbrella), one per reason.
+const OPENDNS_BLOCK_PAGES: std::ops::RangeInclusive<u32> =
+    u32::from_be_bytes([146, 112, 61, 104])..=u32::from_be_bytes([146, 112, 61, 110]);
+
+// ==================== Domain Types ====================
+
+/// How a category's canaries were answered.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterResult {
+    /// No address, an unroutable one or a block page.
+    Blocked,
+    /// A real address.
+    Resolved,
+    /// No usable answer.
+    #[default]
+    Unknown,
+}
+
+/// One category, through the provider and through the system resolver.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CategoryCheck {
+    pub category: DnsFilter,
+    /// Whether the provider's catalog entry says it blocks this category.
+    pub expected: bool,
+    /// Answer of the provider, queried directly.
+    pub provider: FilterResult,
+    /// Answer of the system resolver, which is what apps get.
+    pub system: FilterResult,
+}
+
+/// Whether the provider's filtering works on this machine.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilteringStatus {
+    /// Every category the provider filters is blocked for apps here.
+    Working,
+    /// A category the provider should filter loads anyway, and the provider
+    /// itself isn't seen blocking it.
+    NotFiltering,
+    /// The provider blocks a category, but system lookups go around it.
+    Bypassed,
+    /// Not enough answers, or the provider isn't known to filter anything
+    /// (a custom or unfiltered one).
+    #[default]
+    Unknown,
+}
+
+/// Result of [`verify_dns_filtering`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FilteringReport {
+    /// Provider checked; `Auto` is replaced by the catalog entry the current
+    /// servers belong to, if any.
+    pub provider: DnsProvider,
+    /// Server (`ip:port`) the provider was queried at directly.
+    pub server: Option<String>,
+    pub status: FilteringStatus,
+    pub categories: Vec<CategoryCheck>,
+    /// Some category is blocked by the provider but not by the system resolver.
+    pub os_bypass: bool,
+    /// The system resolver answers [`DOH_CANARY`], so browsers may switch to
+    /// their own DNS-over-HTTPS and skip the filter.
+    pub browser_bypass: bool,
+}
+
+// ==================== Classification ====================
+
+/// Whether `ip` is what filters answer instead of the real address.
+fn is_block_address(ip: &str) -> bool {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => {
+            v4.is_unspecified() || v4.is_loopback() || OPENDNS_BLOCK_PAGES.contains(&u32::from(v4))
+        }
+        Ok(IpAddr::V6(v6)) => v6.is_unspecified() || v6.is_loopback(),
+        Err(_) => false,
+    }
+}
+
+/// Answers through one resolver, by name; names without an entry weren't
+/// looked up (no such resolver, or cancelled).
+#[derive(Default)]
+struct Answers<'a>(HashMap<&'a str, Vec<String>>);
+
+impl Answers<'_> {
+    /// Whether `name` got a real address.
+    fn resolves(&self, name: &str) -> bool {
+        self.0
+            .get(name)
+            .is_some_and(|ips| ips.iter().any(|ip| !is_block_address(ip)))
+    }
+
+    /// How `name` was answered; an empty answer counts as blocked only if
+    /// this resolver works and the name exists (`exists`).
+    fn result(&self, name: &str, exists: bool) -> FilterResult {
+        match self.0.get(name) {
+            None => FilterResult::Unknown,
+            Some(_) if self.resolves(name) => FilterResult::Resolved,
+            Some(ips) if !ips.is_empty() => FilterResult::Blocked,
+            Some(_) if exists && self.resolves(CONTROL_DOMAIN) => FilterResult::Blocked,
+            Some(_) => FilterResult::Unknown,
+        }
+    }
+}
+
+/// Category result from its canaries: blocked if any is.
+fn combine(results: impl IntoIterator<Item = FilterResult>) -> FilterResult {
+    let results: Vec<FilterResult> = results.into_iter().collect();
+    if results.contains(&FilterResult::Blocked) {
+        FilterResult::Blocked
+    } else if !results.is_empty() && results.iter().all(|r| *r == FilterResult::Resolved) {
+        FilterResult::Resolved
+    } else {
+        FilterResult::Unknown
+    }
+}
+
+/// Overall status from the category checks.
+fn verdict(categories: &[CategoryCheck], os_bypass: bool) -> FilteringStatus {
+    let expected: Vec<&CategoryCheck> = categories.iter().filter(|c| c.expected).collect();
+    if expected.is_empty() {
+        FilteringStatus::Unknown
+    } else if expected.iter().any(|c| c.system == FilterResult::Resolved) {
+        if os_bypass {
+            FilteringStatus::Bypassed
+        } else {
+            FilteringStatus::NotFiltering
+        }
+    } else if expected.iter().all(|c| c.system == FilterResult::Blocked) {
+        FilteringStatus::Working
+    } else {
+        FilteringStatus::Unknown
+    }
+}
+
+// ==================== Verification ====================
+
+/// Where names are resolved.
+#[derive(Clone, Copy)]
+enum Via<'a> {
+    /// Straight to this server (`ip:port`).
+    Server(&'a str),
+    /// Through the system resolver.
+    System,
+}
+
+/// Resolve `names` through `via`, concurrently.
+async fn lookup_all<'a>(
+    backend: &dyn NetworkBackend,
+    via: Option<Via<'_>>,
+    names: &[&'a str],
+    cancel: &Cancellation,
+) -> (Answers<'a>, Completion) {
+    let Some(via) = via else {
+        return (Answers::default(), Completion::Complete);
+    };
+    let lookups = names.iter().map(|&name| async move {
+        let mut completion = Completion::Complete;
+        let resolve = async {
+            match via {
+                Via::Server(server) => backend.resolve_via_async(name, server).await,
+                Via::System => backend.resolve_system_async(name).await,
+            }
+        };
+        let ips = cancel.step(&mut completion, resolve).await;
+        (name, ips, completion)
+    });
+    let results = join_all(lookups).await;
+
+    let completion = results
+        .iter()
+        .map(|(_, _, completion)| *completion)
+        .find(|c| *c != Completion::Complete)
+        .unwrap_or_default();
+    let answers = results
+        .into_iter()
+        .filter_map(|(name, ips, _)| Some((name, ips?)))
+        .collect();
+    (Answers(answers), completion)
+}
+
+/// The provider to check, with `Auto` replaced by the catalog entry of the
+/// current servers when they are one.
+fn resolve_provider(provider: &DnsProvider, current: &[String]) -> DnsProvider {
+    match provider {
+        DnsProvider::Auto => DnsCatalog::built_in()
+            .detect(current)
+            .map_or(DnsProvider::Auto, |entry| {
+                DnsProvider::Catalog(entry.id.clone())
+            }),
+        other => other.clone(),
+    }
+}
+
+/// Check that `provider` (`Auto` for whatever is configured now) blocks what
+/// it promises to, and that this machine's lookups actually go through it.
+pub fn verify_dns_filtering(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    provider: &DnsProvider,
+) -> FilteringReport {
+    crate::runtime::block_on(verify_dns_filtering_async(backend, targets, provider))
+}
+
+/// Async [`verify_dns_filtering`].
+pub async fn verify_dns_filtering_async(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    provider: &DnsProvider,
+) -> FilteringReport {
+    verify_dns_filtering_cancellable(backend, targets, provider, &Cancellation::none())
+        .await
+        .value
+}
+
+/// [`verify_dns_filtering_async`] that stops when `cancel` fires. Categories
+/// without answers by then are reported as `Unknown`.
+pub async fn verify_dns_filtering_cancellable(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    provider: &DnsProvider,
+    cancel: &Cancellation,
+) -> Partial<FilteringReport> {
+    let current = blocking(|| backend.dns_servers()).unwrap_or_default();
+    let provider = resolve_provider(provider, &current);
+    let server = provider.primary().map(|ip| match ip.parse() {
+        Ok(IpAddr::V6(v6)) => format!("[{}]:53", v6),
+        _ => format!("{}:53", ip),
+    });
+    check_filtering(backend, targets, provider, server, cancel).await
+}
+
+/// [`verify_dns_filtering_cancellable`] with the provider's server (`ip:port`)
+/// already worked out.
+async fn check_filtering(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    provider: DnsProvider,
+    server: Option<String>,
+    cancel: &Cancellation,
+) -> Partial<FilteringReport> {
+    let names: Vec<&str> = [CONTROL_DOMAIN, DOH_CANARY]
+        .into_iter()
+        .chain(
+            CANARIES
+                .iter()
+                .flat_map(|(_, domains)| domains.iter().copied()),
+        )
+        .collect();
+    let ((reference, c1), (direct, c2), (system, c3)) = tokio::join!(
+        lookup_all(
+            backend,
+            Some(Via::Server(&targets.trusted_dns_server)),
+            &names,
+            cancel
+        ),
+        lookup_all(backend, server.as_deref().map(Via::Server), &names, cancel),
+        lookup_all(backend, Some(Via::System), &names, cancel),
+    );
+    let completion = [c1, c2, c3]
+        .into_iter()
+        .find(|c| *c != Completion::Complete)
+        .unwrap_or_default();
+
+    // A canary exists if the unfiltered reference resolves it; if the
+    // reference can't be reached, trust the canaries
+    let reference_works = reference.resolves(CONTROL_DOMAIN);
+    let exists = |name: &str| !reference_works || reference.resolves(name);
+    let filtering = provider
+        .entry()
+        .map(|entry| entry.filtering.clone())
+        .unwrap_or_default();
+    let categories: Vec<CategoryCheck> = CANARIES
+        .iter()
+        .map(|(category, domains)| CategoryCheck {
+            category: *category,
+            expected: filtering.contains(category),
+            provider: combine(domains.iter().map(|d| direct.result(d, exists(d)))),
+            system: combine(domains.iter().map(|d| system.result(d, exists(d)))),
+        })
+        .collect();
+    let os_bypass = categories
+        .iter()
+        .any(|c| c.provider == FilterResult::Blocked && c.system == FilterResult::Resolved);
+
+    let report = FilteringReport {
+        status: verdict(&categories, os_bypass),
+        browser_bypass: system.result(DOH_CANARY, true) == FilterResult::Resolved,
+        provider,
+        server,
+        categories,
+        os_bypass,
+    };
+    Partial::new(report, completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SimulatedBackend;
+    use crate::cancel::CancelToken;
+
+    const REAL: &str = "93.184.215.14";
+
+    fn answers(entries: &[(&'static str, &[&str])]) -> Answers<'static> {
+        Answers(
+            entries
+                .iter()
+                .map(|(name, ips)| (*name, ips.iter().map(|ip| ip.to_string()).collect()))
+                .collect(),
+        )
+    }
+
+    fn check(category: DnsFilter, provider: FilterResult, system: FilterResult) -> CategoryCheck {
+        CategoryCheck {
+            category,
+            expected: true,
+            provider,
+            system,
+        }
+    }
+
+    /// Backend whose direct lookups (provider and reference) block malware and
+    /// adult canaries, and whose system resolver answers `system` for them.
+    fn backend(system: &[&str]) -> SimulatedBackend {
+        let mut backend = SimulatedBackend::default();
+        for (category, domains) in CANARIES {
+            for domain in domains.iter() {
+                let direct = match category {
+                    DnsFilter::Malware => vec!["0.0.0.0".to_string()],
+                    DnsFilter::Adult => vec!["146.112.61.106".to_string()],
+                    DnsFilter::Ads => vec![REAL.to_string()],
+                };
+                backend
+                    .direct_resolutions
+                    .insert(domain.to_string(), direct);
+                backend.system_resolutions.insert(
+                    domain.to_string(),
+                    system.iter().map(|ip| ip.to_string()).collect(),
+                );
+            }
+        }
+        for resolutions in [
+            &mut backend.direct_resolutions,
+            &mut backend.system_resolutions,
+        ] {
+            resolutions.insert(CONTROL_DOMAIN.into(), vec![REAL.into()]);
+        }
+        backend
+    }
+
+    #[test]
+    fn test_block_addresses() {
+        assert!(is_block_address("0.0.0.0"));
+        assert!(is_block_address("127.0.0.1"));
+        assert!(is_block_address("::"));
+        assert!(is_block_address("146.112.61.104"));
+        assert!(!is_block_address("146.112.61.111"));
+        assert!(!is_block_address(REAL));
+        assert!(!is_block_address("not-an-ip"));
+    }
+
+    #[test]
+    fn test_empty_answer_needs_working_resolver_and_live_canary() {
+        let working = answers(&[(CONTROL_DOMAIN, &[REAL]), ("a.test", &[])]);
+        assert_eq!(working.result("a.test", true), FilterResult::Blocked);
+        // The canary may simply not exist any more
+        assert_eq!(working.result("a.test", false), FilterResult::Unknown);
+        assert_eq!(working.result("b.test", true), FilterResult::Unknown);
+
+        let down = answers(&[(CONTROL_DOMAIN, &[]), ("a.test", &[])]);
+        assert_eq!(down.result("a.test", true), FilterResult::Unknown);
+
+        let sinkhole = answers(&[("a.test", &["0.0.0.0"]), ("b.test", &["0.0.0.0", REAL])]);
+        assert_eq!(sinkhole.result("a.test", false), FilterResult::Blocked);
+        assert_eq!(sinkhole.result("b.test", false), FilterResult::Resolved);
+    }
+
+    #[test]
+    fn test_combine() {
+        use FilterResult::*;
+        assert_eq!(combine([Resolved, Blocked]), Blocked);
+        assert_eq!(combine([Resolved, Resolved]), Resolved);
+        assert_eq!(combine([Resolved, Unknown]), Unknown);
+        assert_eq!(combine([]), Unknown);
+    }
+
+    #[test]
+    fn test_verdict() {
+        use FilterResult::*;
+        let working = [check(DnsFilter::Malware, Blocked, Blocked)];
+        assert_eq!(verdict(&working, false), FilteringStatus::Working);
+        let bypassed = [check(DnsFilter::Malware, Blocked, Resolved)];
+        assert_eq!(verdict(&bypassed, true), FilteringStatus::Bypassed);
+        let not_filtering = [check(DnsFilter::Adult, Resolved, Resolved)];
+        assert_eq!(
+            verdict(&not_filtering, false),
+            FilteringStatus::NotFiltering
+        );
+        let unreachable = [check(DnsFilter::Malware, Unknown, Unknown)];
+        assert_eq!(verdict(&unreachable, false), FilteringStatus::Unknown);
+        let unexpected = [CategoryCheck {
+            expected: false,
+            ..check(DnsFilter::Ads, Resolved, Resolved)
+        }];
+        assert_eq!(verdict(&unexpected, false), FilteringStatus::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_filtering_provider_blocks_for_apps() {
+        let backend = backend(&["0.0.0.0"]);
+        let provider = DnsProvider::Catalog("CloudflareFamily".into());
+        let report =
+            verify_dns_filtering_async(&backend, &ProbeTargets::default(), &provider).await;
+        assert_eq!(report.server.as_deref(), Some("1.1.1.3:53"));
+        assert_eq!(report.status, FilteringStatus::Working);
+        assert!(!report.os_bypass);
+        // The system resolver has no answer for the DoH canary
+        assert!(!report.browser_bypass);
+        let ads = &report.categories[2];
+        assert_eq!(ads.category, DnsFilter::Ads);
+        assert!(!ads.expected);
+    }
+
+    #[tokio::test]
+    async fn test_system_resolver_going_around_provider_is_bypass() {
+        let mut backend = backend(&[REAL]);
+        backend.dns_servers = vec!["1.1.1.3".into(), "1.0.0.3".into()];
+        backend
+            .system_resolutions
+            .insert(DOH_CANARY.into(), vec![REAL.into()]);
+        let report =
+            verify_dns_filtering_async(&backend, &ProbeTargets::default(), &DnsProvider::Auto)
+                .await;
+        // Auto is reported as the provider the current servers belong to
+        assert_eq!(
+            report.provider,
+            DnsProvider::Catalog("CloudflareFamily".into())
+        );
+        assert_eq!(report.status, FilteringStatus::Bypassed);
+        assert!(report.os_bypass);
+        assert!(report.browser_bypass);
+        assert_eq!(report.categories[0].provider, FilterResult::Blocked);
+        assert_eq!(report.categories[0].system, FilterResult::Resolved);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_verification_reports_unknown() {
+        let token = CancelToken::new();
+        token.cancel();
+        let partial = verify_dns_filtering_cancellable(
+            &backend(&["0.0.0.0"]),
+            &ProbeTargets::default(),
+            &DnsProvider::Catalog("AdGuardFamily".into()),
+            &Cancellation::new(&token, None),
+        )
+        .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        assert_eq!(partial.value.status, FilteringStatus::Unknown);
+        assert!(partial
+            .value
+            .categories
+            .iter()
+            .all(|c| c.system == FilterResult::Unknown));
+    }
+}
@@ -0,0 +1,470 @@
+//! DNS leak test for VPN sessions.
+//!
+//! Unique names under [`ProbeTargets::dns_leak_zone`] are resolved the way
+//! every other lookup on the machine is (or sent to
+//! [`ProbeTargets::dns_leak_server`] instead). The zone's authoritative server
+//! answers each with the address of the resolver that asked it, so the answers
+//! list the resolvers that actually handled the queries; unique names keep
+//! caches from hiding any of them. Each resolver's network (ASN) is looked up
+//! and compared with the ISP's and with the VPN's: a resolver on the ISP's
+//! network means queries bypass the tunnel.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::NetworkBackend;
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::diagnostics::IpInfoResponse;
+use crate::dns_benchmark::uncached_name;
+use crate::domain::ProbeTargets;
+
+// ==================== Domain Types ====================
+
+/// What to compare the resolvers against.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct LeakTestOptions {
+    /// Unique names to resolve.
+    pub queries: usize,
+    /// Public IP of this connection without the VPN; its network is the ISP.
+    /// Without it the test can list resolvers but not tell a leak.
+    pub isp_ip: Option<String>,
+    /// Zone to resolve names under instead of [`ProbeTargets::dns_leak_zone`].
+    pub zone: Option<String>,
+    /// Server (`ip:port`) to query instead of [`ProbeTargets::dns_leak_server`].
+    pub server: Option<String>,
+}
+
+impl Default for LeakTestOptions {
+    fn default() -> Self {
+        Self {
+            queries: 10,
+            isp_ip: None,
+            zone: None,
+            server: None,
+        }
+    }
+}
+
+impl LeakTestOptions {
+    /// `targets` with this run's zone and server in place of theirs.
+    fn targets(&self, targets: &ProbeTargets) -> ProbeTargets {
+        ProbeTargets {
+            dns_leak_zone: self
+                .zone
+                .clone()
+                .unwrap_or_else(|| targets.dns_leak_zone.clone()),
+            dns_leak_server: self
+                .server
+                .clone()
+                .or_else(|| targets.dns_leak_server.clone()),
+            ..targets.clone()
+        }
+    }
+}
+
+/// Whose network a resolver is on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolverOwner {
+    /// The ISP's: these queries bypassed the VPN.
+    Isp,
+    /// The VPN's, the network the public IP is on now.
+    Vpn,
+    /// Another network, e.g. a public resolver reached through the tunnel.
+    ThirdParty,
+    /// Network unknown.
+    Unknown,
+}
+
+/// A resolver that handled some of the queries.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeakResolver {
+    pub ip: String,
+    pub asn: Option<u32>,
+    /// Name of the network, e.g. "Cloudflare, Inc.".
+    pub org: Option<String>,
+    pub country: Option<String>,
+    /// Queries this resolver handled.
+    pub queries: usize,
+    pub owner: ResolverOwner,
+}
+
+/// Overall verdict.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakStatus {
+    /// No resolver is on the ISP's network.
+    NoLeak,
+    /// At least one resolver is on the ISP's network.
+    Leak,
+    /// No query was answered, or the ISP's network is unknown.
+    Inconclusive,
+}
+
+/// Resolvers seen by the leak test, most used first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DnsLeakReport {
+    pub status: LeakStatus,
+    /// Public IP now, i.e. through the VPN.
+    pub public_ip: Option<String>,
+    pub vpn_asn: Option<u32>,
+    pub isp_ip: Option<String>,
+    pub isp_asn: Option<u32>,
+    /// Names sent (fewer than asked for if the test was stopped).
+    pub queries: usize,
+    /// Names answered with a resolver address.
+    pub answered: usize,
+    pub resolvers: Vec<LeakResolver>,
+}
+
+// ==================== Classification ====================
+
+/// ASN and name from an ipinfo-style `org` field ("AS13335 Cloudflare, Inc.").
+pub(crate) fn parse_org(org: &str) -> (Option<u32>, Option<String>) {
+    let (asn, name) = org.trim().split_once(' ').unwrap_or((org.trim(), ""));
+    match asn.strip_prefix("AS").and_then(|n| n.parse().ok()) {
+        Some(asn) => (
+            Some(asn),
+            Some(name.trim())
+                .filter(|n| !n.is_empty())
+                .map(str::to_string),
+        ),
+        None => (
+            None,
+            Some(org.trim())
+                .filter(|o| !o.is_empty())
+                .map(str::to_string),
+        ),
+    }
+}
+
+fn asn_of(info: Option<&IpInfoResponse>) -> Option<u32> {
+    info?.org.as_deref().and_then(|org| parse_org(org).0)
+}
+
+/// Whose network `ip` (on `asn`) is on.
+fn owner(
+    ip: &str,
+    asn: Option<u32>,
+    isp: (Option<&str>, Option<u32>),
+    vpn_asn: Option<u32>,
+) -> ResolverOwner {
+    let (isp_ip, isp_asn) = isp;
+    if isp_ip == Some(ip) {
+        return ResolverOwner::Isp;
+    }
+    match asn {
+        Some(asn) if Some(asn) == isp_asn => ResolverOwner::Isp,
+        Some(asn) if Some(asn) == vpn_asn => ResolverOwner::Vpn,
+        Some(_) => ResolverOwner::ThirdParty,
+        None => ResolverOwner::Unknown,
+    }
+}
+
+fn verdict(resolvers: &[LeakResolver], isp_known: bool) -> LeakStatus {
+    if resolvers.iter().any(|r| r.owner == ResolverOwner::Isp) {
+        LeakStatus::Leak
+    } else if resolvers.is_empty() || !isp_known {
+        LeakStatus::Inconclusive
+    } else {
+        LeakStatus::NoLeak
+    }
+}
+
+// ==================== Leak Test ====================
+
+/// Resolve one unique name; the answers are the resolvers that handled it.
+pub(crate) async fn resolve(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    name: &str,
+) -> Vec<String> {
+    match &targets.dns_leak_server {
+        Some(server) => backend.resolve_via_async(name, server).await,
+        None => backend.resolve_system_async(name).await,
+    }
+}
+
+/// Test which resolvers handle this machine's queries and whether any is the ISP's.
+pub fn run_dns_leak_test(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    options: &LeakTestOptions,
+) -> DnsLeakReport {
+    crate::runtime::block_on(run_dns_leak_test_async(backend, targets, options))
+}
+
+/// Async [`run_dns_leak_test`].
+pub async fn run_dns_leak_test_async(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    options: &LeakTestOptions,
+) -> DnsLeakReport {
+    run_dns_leak_test_cancellable(backend, targets, options, &Cancellation::none())
+        .await
+        .value
+}
+
+/// [`run_dns_leak_test_async`] that stops when `cancel` fires, reporting the
+/// resolvers seen so far.
+pub async fn run_dns_leak_test_cancellable(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    options: &LeakTestOptions,
+    cancel: &Cancellation,
+) -> Partial<DnsLeakReport> {
+    let targets = &options.targets(targets);
+    let zone = targets.dns_leak_zone.trim_end_matches('.');
+    let names: Vec<String> = (0..options.queries).map(|_| uncached_name(zone)).collect();
+    let lookups = names.iter().map(|name| async move {
+        let mut completion = Completion::Complete;
+        let answers = cancel
+            .step(&mut completion, resolve(backend, targets, name))
+            .await;
+        (answers, completion)
+    });
+    let results = join_all(lookups).await;
+
+    let mut completion = results
+        .iter()
+        .map(|(_, completion)| *completion)
+        .find(|c| *c != Completion::Complete)
+        .unwrap_or_default();
+    let queries = results
+        .iter()
+        .filter(|(answers, _)| answers.is_some())
+        .count();
+    let mut answered = 0;
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for answers in results.into_iter().filter_map(|(answers, _)| answers) {
+        answered += usize::from(!answers.is_empty());
+        for ip in answers {
+            *counts.entry(ip).or_default() += 1;
+        }
+    }
+
+    // Networks of the VPN path (our public IP now), the ISP and each resolver
+    let lookup = |ip: String| async move { backend.ip_location_async(&ip, targets).await };
+    let isp_ip = options.isp_ip.clone();
+    let networks = async {
+        tokio::join!(
+            lookup(String::new()),
+            async {
+                match &isp_ip {
+                    Some(ip) => lookup(ip.clone()).await,
+                    None => None,
+                }
+            },
+            join_all(counts.keys().cloned().map(lookup)),
+        )
+    };
+    let (public, isp, resolver_info) = cancel
+        .step(&mut completion, networks)
+        .await
+        .unwrap_or_else(|| (None, None, vec![None; counts.len()]));
+
+    let vpn_asn = asn_of(public.as_ref());
+    let isp_asn = asn_of(isp.as_ref());
+    let mut resolvers: Vec<LeakResolver> = counts
+        .into_iter()
+        .zip(resolver_info)
+        .map(|((ip, queries), info)| {
+            let (asn, org) = info
+                .as_ref()
+                .and_then(|info| info.org.as_deref())
+                .map_or((None, None), parse_org);
+            LeakResolver {
+                owner: owner(&ip, asn, (isp_ip.as_deref(), isp_asn), vpn_asn),
+                country: info.and_then(|info| info.country),
+                ip,
+                asn,
+                org,
+                queries,
+            }
+        })
+        .collect();
+    resolvers.sort_by_key(|r| Reverse(r.queries));
+
+    let report = DnsLeakReport {
+        status: verdict(&resolvers, isp_ip.is_some()),
+        public_ip: public.and_then(|info| info.ip),
+        vpn_asn,
+        isp_ip,
+        isp_asn,
+        queries,
+        answered,
+        resolvers,
+    };
+    Partial::new(report, completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{SimulatedBackend, SystemBackend};
+    use std::net::{IpAddr, Ipv4Addr};
+    use trust_dns_resolver::proto::op::{Message, MessageType};
+    use trust_dns_resolver::proto::rr::{rdata, RData, Record};
+    use trust_dns_resolver::proto::serialize::binary::BinDecodable;
+
+    /// Stand-in authoritative server: answers every A query with the address
+    /// it came from. Returns its `ip:port`.
+    async fn echo_server() -> String {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let Ok(query) = Message::from_bytes(&buf[..len]) else {
+                    continue;
+                };
+                let IpAddr::V4(ip) = from.ip() else { continue };
+                let mut response = Message::new();
+                response
+                    .set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .add_queries(query.queries().to_vec());
+                if let Some(question) = query.queries().first() {
+                    response.add_answer(Record::from_rdata(
+                        question.name().clone(),
+                        0,
+                        RData::A(rdata::A(ip)),
+                    ));
+                }
+                let _ = socket.send_to(&response.to_vec().unwrap(), from).await;
+            }
+        });
+        addr.to_string()
+    }
+
+    /// Geolocation API nothing listens on, so networks stay unknown.
+    fn offline_targets(server: String) -> ProbeTargets {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        ProbeTargets {
+            dns_leak_zone: "whoami.test.".into(),
+            dns_leak_server: Some(server),
+            geoip_base_url: closed,
+            ..ProbeTargets::default()
+        }
+    }
+
+    fn resolver(ip: &str, asn: Option<u32>, owner: ResolverOwner) -> LeakResolver {
+        LeakResolver {
+            ip: ip.into(),
+            asn,
+            org: None,
+            country: None,
+            queries: 1,
+            owner,
+        }
+    }
+
+    #[test]
+    fn test_parse_org() {
+        assert_eq!(
+            parse_org("AS13335 Cloudflare, Inc."),
+            (Some(13335), Some("Cloudflare, Inc.".to_string()))
+        );
+        assert_eq!(parse_org("AS64500"), (Some(64500), None));
+        assert_eq!(
+            parse_org("Example Net"),
+            (None, Some("Example Net".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_owner() {
+        let isp = (Some("198.51.100.7"), Some(64500));
+        assert_eq!(
+            owner("198.51.100.7", None, isp, Some(64501)),
+            ResolverOwner::Isp
+        );
+        assert_eq!(
+            owner("198.51.100.53", Some(64500), isp, Some(64501)),
+            ResolverOwner::Isp
+        );
+        assert_eq!(
+            owner("203.0.113.53", Some(64501), isp, Some(64501)),
+            ResolverOwner::Vpn
+        );
+        assert_eq!(
+            owner("1.1.1.1", Some(13335), isp, Some(64501)),
+            ResolverOwner::ThirdParty
+        );
+        assert_eq!(
+            owner("192.0.2.1", None, isp, Some(64501)),
+            ResolverOwner::Unknown
+        );
+    }
+
+    #[test]
+    fn test_verdict() {
+        let vpn = resolver("203.0.113.53", Some(64501), ResolverOwner::Vpn);
+        let isp = resolver("198.51.100.53", Some(64500), ResolverOwner::Isp);
+        assert_eq!(verdict(&[vpn.clone(), isp.clone()], true), LeakStatus::Leak);
+        assert_eq!(verdict(&[isp], false), LeakStatus::Leak);
+        assert_eq!(
+            verdict(std::slice::from_ref(&vpn), true),
+            LeakStatus::NoLeak
+        );
+        assert_eq!(verdict(&[vpn], false), LeakStatus::Inconclusive);
+        assert_eq!(verdict(&[], true), LeakStatus::Inconclusive);
+    }
+
+    #[tokio::test]
+    async fn test_leak_test_lists_resolvers_from_stand_in() {
+        let targets = offline_targets(echo_server().await);
+        let options = LeakTestOptions {
+            queries: 4,
+            isp_ip: Some(Ipv4Addr::LOCALHOST.to_string()),
+            ..LeakTestOptions::default()
+        };
+        let report = run_dns_leak_test_async(&SystemBackend, &targets, &options).await;
+        assert_eq!(report.queries, 4);
+        assert_eq!(report.answered, 4);
+        assert_eq!(report.resolvers.len(), 1);
+        assert_eq!(report.resolvers[0].ip, "127.0.0.1");
+        assert_eq!(report.resolvers[0].queries, 4);
+        // The "resolver" is the ISP's own address
+        assert_eq!(report.resolvers[0].owner, ResolverOwner::Isp);
+        assert_eq!(report.status, LeakStatus::Leak);
+    }
+
+    #[tokio::test]
+    async fn test_options_override_zone_and_server() {
+        // Nothing answers on the configured server; the run's own one does
+        let mut targets = offline_targets("127.0.0.1:9".into());
+        targets.dns_leak_zone = "unused.invalid".into();
+        let options = LeakTestOptions {
+            queries: 2,
+            zone: Some("whoami.test.".into()),
+            server: Some(echo_server().await),
+            ..LeakTestOptions::default()
+        };
+        let report = run_dns_leak_test_async(&SystemBackend, &targets, &options).await;
+        assert_eq!(report.answered, 2);
+        assert_eq!(report.resolvers[0].ip, "127.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_leak_test_sends_nothing() {
+        let token = crate::cancel::CancelToken::new();
+        token.cancel();
+        let partial = run_dns_leak_test_cancellable(
+            &SimulatedBackend::healthy(),
+            &ProbeTargets::default(),
+            &LeakTestOptions::default(),
+            &Cancellation::new(&token, None),
+        )
+        .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        assert_eq!(partial.value.queries, 0);
+        assert_eq!(partial.value.status, LeakStatus::Inconclusive);
+    }
+}
@@ -0,0 +1,246 @@
+//! DNSSEC validation check.
+//!
+//! A resolver is asked for three names: one in a correctly signed zone, one in
+//! a zone whose signatures are deliberately broken, and one in an unsigned zone
+//! as a control. A validating resolver answers the first and refuses the second
+//! with SERVFAIL; a non-validating one answers both. One that refuses the
+//! correctly signed zone while answering the unsigned control has a broken
+//! validator (a stale trust anchor, a wrong clock) and fails every signed domain.
+//!
+//! Resolvers are asked over plain UDP whatever transport they are used over:
+//! validation happens in the resolver, not in the transport. The checks go
+//! through [`NetworkBackend::resolver_dnssec`], so a simulated network can
+//! script each resolver's status.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::NetworkBackend;
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::domain::{DnsProvider, DnssecStatus};
+use crate::infrastructure::dns_transport::query_udp;
+use crate::infrastructure::dns_wire::Rcode;
+use crate::runtime::blocking;
+
+/// Name in a zone with valid signatures.
+const SIGNED_DOMAIN: &str = "isc.org.";
+
+/// Name in a zone whose signatures are deliberately broken.
+const BOGUS_DOMAIN: &str = "dnssec-failed.org.";
+
+/// Name in an unsigned zone, answered by any working resolver.
+const CONTROL_DOMAIN: &str = "google.com.";
+
+/// Time to wait for each answer when checking the current resolver during
+/// diagnostics.
+pub(crate) const DNSSEC_TIMEOUT: Duration = Duration::from_secs(2);
+
+// ==================== Domain Types ====================
+
+/// DNSSEC status of one resolver.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DnssecCheck {
+    /// `Auto` for the resolver currently configured on this machine.
+    pub provider: DnsProvider,
+    /// Address the test names were sent to.
+    pub server: String,
+    pub status: DnssecStatus,
+}
+
+/// DNSSEC status of the current resolver (first, when known) and every
+/// built-in provider.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DnssecReport {
+    pub resolvers: Vec<DnssecCheck>,
+}
+
+// ==================== Classification ====================
+
+/// How a resolver answered one test name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reply {
+    /// An answer, including "no such name".
+    Answered,
+    /// SERVFAIL, which is how a validator reports bad signatures.
+    ServFail,
+}
+
+fn reply(code: Option<Rcode>) -> Option<Reply> {
+    match code? {
+        Rcode::NO_ERROR | Rcode::NX_DOMAIN => Some(Reply::Answered),
+        Rcode::SERV_FAIL => Some(Reply::ServFail),
+        _ => None,
+    }
+}
+
+/// Status from the replies for the signed, the bogus and the control name
+/// (`None` where nothing usable came back).
+fn classify(signed: Option<Reply>, bogus: Option<Reply>, control: Option<Reply>) -> DnssecStatus {
+    match (signed, bogus, control) {
+        (Some(Reply::Answered), Some(Reply::ServFail), _) => DnssecStatus::Validating,
+        (Some(Reply::Answered), Some(Reply::Answered), _) => DnssecStatus::NonValidating,
+        (Some(Reply::ServFail), _, Some(Reply::Answered)) => DnssecStatus::Broken,
+        _ => DnssecStatus::Unknown,
+    }
+}
+
+// ==================== Checks ====================
+
+/// Ask `server` for the three test names, concurrently.
+pub(crate) async fn resolver_status(server: IpAddr, timeout: Duration) -> DnssecStatus {
+    let (signed, bogus, control) = tokio::join!(
+        query_udp(server, SIGNED_DOMAIN, timeout),
+        query_udp(server, BOGUS_DOMAIN, timeout),
+        query_udp(server, CONTROL_DOMAIN, timeout),
+    );
+    classify(reply(signed), reply(bogus), reply(control))
+}
+
+/// Status of the first configured resolver given as an IP address.
+pub(crate) async fn current_status(servers: &[String]) -> DnssecStatus {
+    match servers.iter().find_map(|server| server.parse().ok()) {
+        Some(ip) => resolver_status(ip, DNSSEC_TIMEOUT).await,
+        None => DnssecStatus::Unknown,
+    }
+}
+
+/// Resolvers to check: the current one first, then every built-in provider.
+fn dnssec_targets(current: &[String]) -> Vec<(DnsProvider, IpAddr)> {
+    let current = current
+        .iter()
+        .find_map(|server| server.parse().ok())
+        .map(|ip| (DnsProvider::Auto, ip));
+    let built_in = DnsProvider::built_in().into_iter().filter_map(|provider| {
+        let ip = provider.primary()?.parse().ok()?;
+        Some((provider, ip))
+    });
+    current.into_iter().chain(built_in).collect()
+}
+
+/// Check whether the current resolver and every built-in provider validate
+/// DNSSEC.
+pub fn check_dnssec(backend: &dyn NetworkBackend, timeout_secs: u64) -> DnssecReport {
+    crate::runtime::block_on(check_dnssec_async(backend, timeout_secs))
+}
+
+/// Async [`check_dnssec`].
+pub async fn check_dnssec_async(backend: &dyn NetworkBackend, timeout_secs: u64) -> DnssecReport {
+    check_dnssec_cancellable(backend, timeout_secs, &Cancellation::none())
+        .await
+        .value
+}
+
+/// [`check_dnssec_async`] that stops when `cancel` fires. Resolvers without
+/// an answer by then are reported as `Unknown`.
+pub async fn check_dnssec_cancellable(
+    backend: &dyn NetworkBackend,
+    timeout_secs: u64,
+    cancel: &Cancellation,
+) -> Partial<DnssecReport> {
+    let current = blocking(|| backend.dns_servers()).unwrap_or_default();
+    let timeout = Duration::from_secs(timeout_secs);
+    let checks = dnssec_targets(&current)
+        .into_iter()
+        .map(|(provider, ip)| async move {
+            let mut completion = Completion::Complete;
+            let status = cancel
+                .step(&mut completion, backend.resolver_dnssec_async(ip, timeout))
+                .await
+                .unwrap_or_default();
+            let check = DnssecCheck {
+                provider,
+                server: ip.to_string(),
+                status,
+            };
+            (check, completion)
+        });
+    let results = join_all(checks).await;
+
+    let completion = results
+        .iter()
+        .map(|(_, completion)| *completion)
+        .find(|c| *c != Completion::Complete)
+        .unwrap_or_default();
+    let resolvers = results.into_iter().map(|(check, _)| check).collect();
+    Partial::new(DnssecReport { resolvers }, completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SimulatedBackend;
+    use crate::cancel::CancelToken;
+
+    const ANSWERED: Option<Reply> = Some(Reply::Answered);
+    const SERVFAIL: Option<Reply> = Some(Reply::ServFail);
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(ANSWERED, SERVFAIL, ANSWERED),
+            DnssecStatus::Validating
+        );
+        assert_eq!(
+            classify(ANSWERED, ANSWERED, ANSWERED),
+            DnssecStatus::NonValidating
+        );
+        assert_eq!(classify(SERVFAIL, SERVFAIL, ANSWERED), DnssecStatus::Broken);
+        // A resolver failing everything isn't a DNSSEC problem
+        assert_eq!(
+            classify(SERVFAIL, SERVFAIL, SERVFAIL),
+            DnssecStatus::Unknown
+        );
+        assert_eq!(classify(None, None, None), DnssecStatus::Unknown);
+        assert_eq!(classify(ANSWERED, None, ANSWERED), DnssecStatus::Unknown);
+    }
+
+    #[test]
+    fn test_reply_codes() {
+        assert_eq!(reply(Some(Rcode::NX_DOMAIN)), ANSWERED);
+        assert_eq!(reply(Some(Rcode::SERV_FAIL)), SERVFAIL);
+        assert_eq!(reply(Some(Rcode(5))), None); // REFUSED
+        assert_eq!(reply(None), None);
+    }
+
+    #[test]
+    fn test_dnssec_targets() {
+        let current = vec!["not-an-ip".to_string(), "192.168.1.1".to_string()];
+        let targets = dnssec_targets(&current);
+        assert_eq!(targets.len(), DnsProvider::built_in().len() + 1);
+        assert_eq!(
+            targets[0],
+            (DnsProvider::Auto, "192.168.1.1".parse().unwrap())
+        );
+        assert_eq!(dnssec_targets(&[]).len(), DnsProvider::built_in().len());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_resolver_is_unknown() {
+        // Nothing listens on TEST-NET-1 (RFC 5737)
+        let status =
+            resolver_status("192.0.2.1".parse().unwrap(), Duration::from_millis(200)).await;
+        assert_eq!(status, DnssecStatus::Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_check_reports_unknown() {
+        let token = CancelToken::new();
+        token.cancel();
+        let partial = check_dnssec_cancellable(
+            &SimulatedBackend::healthy(),
+            2,
+            &Cancellation::new(&token, None),
+        )
+        .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        assert_eq!(partial.value.resolvers[0].provider, DnsProvider::Auto);
+        assert!(partial
+            .value
+            .resolvers
+            .iter()
+            .all(|r| r.status == DnssecStatus::Unknown));
+    }
+}
@@ -5,6 +5,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::dns_catalog::{CatalogProvider, DnsCatalog};
+
 /// Identifies a diagnostic node in the network path.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NodeId {
@@ -75,6 +77,42 @@ pub struct NetworkInfo {
     pub wifi_standard: Option<String>,
     /// True if adapter uses Wi-Fi 4 or older standard
     pub is_legacy_wifi: bool,
+    /// DHCP lease details for the active adapter, or None if unavailable
+    #[serde(default)]
+    pub dhcp: Option<DhcpInfo>,
+    /// DNS servers in use by the active adapter
+    #[serde(default)]
+    pub dns_servers: Vec<String>,
+}
+
+/// DHCP lease details for the active adapter.
+///
+/// Read from the OS lease files (Linux), `ipconfig getpacket` (macOS)
+/// or `Win32_NetworkAdapterConfiguration` (Windows).
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct DhcpInfo {
+    /// True if the adapter obtains its address via DHCP (false = static)
+    pub enabled: bool,
+    /// True when the adapter only has a link-local (169.254.0.0/16, APIPA) IPv4 address
+    pub link_local_only: bool,
+    /// Address of the DHCP server that granted the lease
+    pub server: Option<String>,
+    /// When the lease was obtained (RFC 3339, UTC)
+    pub lease_obtained_utc: Option<String>,
+    /// When the lease expires (RFC 3339, UTC)
+    pub lease_expires_utc: Option<String>,
+    /// True if the lease expiry time is in the past
+    pub lease_expired: bool,
+    /// Lease duration in seconds (option 51)
+    pub lease_time_secs: Option<u32>,
+    /// Renewal (T1) time in seconds (option 58)
+    pub renewal_time_secs: Option<u32>,
+    /// Rebinding (T2) time in seconds (option 59)
+    pub rebinding_time_secs: Option<u32>,
+    /// DNS servers offered by the DHCP server (option 6)
+    pub dns_servers: Vec<String>,
+    /// NTP servers offered by the DHCP server (option 42)
+    pub ntp_servers: Vec<String>,
 }
 
 /// Information about the router/gateway.
@@ -98,6 +136,9 @@ pub struct InternetInfo {
     pub latency_ms: Option<u32>,
     pub speed_down_mbps: Option<f64>,
     pub speed_up_mbps: Option<f64>,
+    /// Whether the current resolver validates DNSSEC.
+    #[serde(default)]
+    pub dnssec: DnssecStatus,
 }
 
 /// Complete snapshot of network diagnostics.
@@ -134,6 +175,8 @@ pub enum DiagnosticScenario {
     HttpBlocked,
     /// Wi-Fi signal is weak
     WeakSignal,
+    /// No DHCP lease: link-local (APIPA) address only, or the lease has expired
+    DhcpFailure,
 }
 
 impl DiagnosticScenario {
@@ -148,6 +191,7 @@ impl DiagnosticScenario {
             Self::DnsFailure => "diagnostic.scenario.dns_failure.title",
             Self::HttpBlocked => "diagnostic.scenario.http_blocked.title",
             Self::WeakSignal => "diagnostic.scenario.weak_signal.title",
+            Self::DhcpFailure => "diagnostic.scenario.dhcp_failure.title",
         }
     }
 
@@ -162,6 +206,7 @@ impl DiagnosticScenario {
             Self::DnsFailure => "diagnostic.scenario.dns_failure.message",
             Self::HttpBlocked => "diagnostic.scenario.http_blocked.message",
             Self::WeakSignal => "diagnostic.scenario.weak_signal.message",
+            Self::DhcpFailure => "diagnostic.scenario.dhcp_failure.message",
         }
     }
 
@@ -185,6 +230,7 @@ impl DiagnosticScenario {
             5 => Some(Self::DnsFailure),
             6 => Some(Self::HttpBlocked),
             7 => Some(Self::WeakSignal),
+            8 => Some(Self::DhcpFailure),
             _ => None,
         }
     }
@@ -200,6 +246,7 @@ impl DiagnosticScenario {
             Self::DnsFailure => 5,
             Self::HttpBlocked => 6,
             Self::WeakSignal => 7,
+            Self::DhcpFailure => 8,
         }
     }
 
@@ -214,6 +261,7 @@ impl DiagnosticScenario {
             Self::DnsFailure,
             Self::HttpBlocked,
             Self::WeakSignal,
+            Self::DhcpFailure,
         ]
     }
 }
@@ -270,7 +318,7 @@ pub enum DeviceType {
 }
 
 /// A device discovered on the local network.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct NetworkDevice {
     pub ip: String,
     pub mac: String,
@@ -282,12 +330,15 @@ pub struct NetworkDevice {
     pub is_randomized: bool,
 }
 
-/// Application settings.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Application settings. Fields missing from a settings file take their defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
 pub struct Settings {
     pub language: String,
     pub test_timeout_ms: u32,
     pub dns_servers: Vec<String>,
+    /// External endpoints used by the connectivity and security checks.
+    pub probe_targets: ProbeTargets,
 }
 
 impl Default for Settings {
@@ -296,202 +347,284 @@ impl Default for Settings {
             language: "en".into(),
             test_timeout_ms: 2000,
             dns_servers: vec![],
+            probe_targets: ProbeTargets::default(),
         }
     }
 }
 
+/// External endpoints the checks talk to.
+///
+/// Every field has a public default, and any field missing from a settings
+/// file falls back to it, so callers only override what they need (e.g. a
+/// self-hosted HTTP endpoint, or local stand-ins in tests).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ProbeTargets {
+    /// Domains resolved through the system resolver by the DNS check, tried in order.
+    pub dns_domains: Vec<String>,
+    /// URLs fetched by the HTTP check, tried in order.
+    pub http_urls: Vec<String>,
+    /// Domains compared between system and trusted DNS by the hijacking
+    /// check. Older settings files with a single `hijack_test_domain` still load.
+    #[serde(alias = "hijack_test_domain", deserialize_with = "one_or_many")]
+    pub hijack_test_domains: Vec<String>,
+    /// Trusted resolver (`ip:port`) used as the reference by the hijacking check.
+    pub trusted_dns_server: String,
+    /// DoH endpoint of a trusted resolver, the hijacking check's preferred
+    /// reference since port 53 traffic can be redirected on the way.
+    pub trusted_doh_url: String,
+    /// Base URL of an ipinfo.io-compatible geolocation API.
+    pub geoip_base_url: String,
+    /// Zone whose authoritative server answers an A query for any
+    /// `<label>.<zone>` with the address of the resolver that asked; the DNS
+    /// leak test resolves unique names under it.
+    pub dns_leak_zone: String,
+    /// Server (`ip:port`) the DNS leak test queries instead of the system
+    /// resolver, e.g. a local stand-in for the zone's authoritative server.
+    pub dns_leak_server: Option<String>,
+}
+
+impl Default for ProbeTargets {
+    fn default() -> Self {
+        Self {
+            dns_domains: vec!["one.one.one.one".into(), "dns.google".into()],
+            http_urls: vec![
+                "https://www.cloudflare.com/cdn-cgi/trace".into(),
+                "https://example.com".into(),
+            ],
+            hijack_test_domains: vec![
+                "example.com".into(),
+                "www.wikipedia.org".into(),
+                "www.cloudflare.com".into(),
+            ],
+            trusted_dns_server: "1.1.1.1:53".into(),
+            trusted_doh_url: "https://cloudflare-dns.com/dns-query".into(),
+            geoip_base_url: "https://ipinfo.io".into(),
+            dns_leak_zone: "whoami.akamai.net".into(),
+            dns_leak_server: None,
+        }
+    }
+}
+
+impl ProbeTargets {
+    /// Geolocation URL for `ip`, or for the caller's own public IP when `ip` is empty.
+    pub fn geoip_url(&self, ip: &str) -> String {
+        let base = self.geoip_base_url.trim_end_matches('/');
+        if ip.is_empty() {
+            format!("{}/json", base)
+        } else {
+            format!("{}/{}/json", base, ip)
+        }
+    }
+}
+
+/// Deserialize either a list of strings or a single string.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
 /// Returns default application settings.
 pub fn get_default_settings() -> Settings {
     Settings::default()
 }
 
+/// Protocol used to reach a DNS resolver.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsTransport {
+    /// Plain DNS over UDP port 53
+    Udp,
+    /// DNS-over-HTTPS (RFC 8484)
+    Doh,
+    /// DNS-over-TLS (RFC 7858), TCP port 853
+    Dot,
+}
+
+/// Class of a DNS query.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsClass {
+    /// Internet, for ordinary names
+    Internet,
+    /// CHAOS, for what a server says about itself (`version.bind`)
+    Chaos,
+}
+
+/// How a resolver treats DNSSEC signatures.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DnssecStatus {
+    /// Answers signed zones and refuses ones with bad signatures.
+    Validating,
+    /// Answers zones with bad signatures too: forged answers get through.
+    NonValidating,
+    /// Refuses correctly signed zones, so every signed domain fails.
+    Broken,
+    /// Not checked, or the resolver didn't answer.
+    #[default]
+    Unknown,
+}
+
 /// DNS provider configuration.
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+///
+/// Serialized as `"Auto"`, the catalog id (e.g. `"Cloudflare"`) or
+/// `{"Custom": [...]}`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum DnsProvider {
     /// Use ISP/DHCP DNS
     Auto,
-    // Cloudflare
-    /// 1.1.1.1, 1.0.0.1 - Standard
-    Cloudflare,
-    /// 1.1.1.2, 1.0.0.2 - Malware Protection
-    CloudflareMalware,
-    /// 1.1.1.3, 1.0.0.3 - Adult + Malware
-    CloudflareFamily,
-    // Google
-    /// 8.8.8.8, 8.8.4.4 - Public DNS
-    Google,
-    // AdGuard
-    /// 94.140.14.14, 94.140.15.15 - Default Filtering
-    AdGuard,
-    /// 94.140.14.140, 94.140.14.141 - Non-filtering
-    AdGuardNonFiltering,
-    /// 94.140.14.15, 94.140.15.16 - Family Protection
-    AdGuardFamily,
-    // DNS4EU
-    /// 86.54.11.1 - Protective
-    Dns4EuProtective,
-    /// 86.54.11.12 - Protective + Child
-    Dns4EuProtectiveChild,
-    /// 86.54.11.13 - Protective + Ad
-    Dns4EuProtectiveAd,
-    /// 86.54.11.11 - Protective + Child & Ads
-    Dns4EuProtectiveChildAd,
-    /// 86.54.11.100 - Unfiltered
-    Dns4EuUnfiltered,
-    // Quad9
-    /// 9.9.9.9, 149.112.112.112 - Recommended
-    Quad9Recommended,
-    /// 9.9.9.11, 149.112.112.11 - Secured w/ECS
-    Quad9SecuredEcs,
-    /// 9.9.9.10, 149.112.112.10 - Unsecured
-    Quad9Unsecured,
-    // OpenDNS
-    /// 208.67.222.123, 208.67.220.123 - FamilyShield
-    OpenDnsFamilyShield,
-    /// 208.67.222.222, 208.67.220.220 - Home
-    OpenDnsHome,
-    /// Custom DNS: (ipv4_primary, ipv4_secondary, ipv6_primary, ipv6_secondary)
-    Custom(String, String, Option<String>, Option<String>),
+    /// A provider from the built-in catalog, by id (e.g. `"Quad9Recommended"`)
+    Catalog(String),
+    /// Custom DNS: (ipv4_primary, ipv4_secondary, ipv6_primary, ipv6_secondary,
+    /// doh_template, dot_hostname)
+    Custom(
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+        Option<String>,
+    ),
+}
+
+impl Serialize for DnsProvider {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DnsProvider::Auto => serializer.serialize_unit_variant("DnsProvider", 0, "Auto"),
+            DnsProvider::Catalog(id) => serializer.serialize_str(id),
+            DnsProvider::Custom(ipv4, ipv4_2, ipv6, ipv6_2, doh, dot) => serializer
+                .serialize_newtype_variant(
+                    "DnsProvider",
+                    2,
+                    "Custom",
+                    &(ipv4, ipv4_2, ipv6, ipv6_2, doh, dot),
+                ),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DnsProvider {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        enum Tagged {
+            Custom(
+                String,
+                String,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+            ),
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Name(String),
+            Tagged(Tagged),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Name(name) if name == "Auto" => DnsProvider::Auto,
+            Repr::Name(id) => DnsProvider::Catalog(id),
+            Repr::Tagged(Tagged::Custom(ipv4, ipv4_2, ipv6, ipv6_2, doh, dot)) => {
+                DnsProvider::Custom(ipv4, ipv4_2, ipv6, ipv6_2, doh, dot)
+            }
+        })
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
 }
 
 impl DnsProvider {
+    /// Every catalog provider, in catalog order.
+    pub fn built_in() -> Vec<DnsProvider> {
+        DnsCatalog::built_in()
+            .providers
+            .iter()
+            .map(|entry| DnsProvider::Catalog(entry.id.clone()))
+            .collect()
+    }
+
+    /// Catalog entry of a `Catalog` provider; `None` for other providers and
+    /// unknown ids.
+    pub fn entry(&self) -> Option<&'static CatalogProvider> {
+        match self {
+            DnsProvider::Catalog(id) => DnsCatalog::built_in().get(id),
+            _ => None,
+        }
+    }
+
     /// Returns the primary DNS server IP address.
     pub fn primary(&self) -> Option<String> {
         match self {
-            DnsProvider::Auto => None,
-            // Cloudflare
-            DnsProvider::Cloudflare => Some("1.1.1.1".to_string()),
-            DnsProvider::CloudflareMalware => Some("1.1.1.2".to_string()),
-            DnsProvider::CloudflareFamily => Some("1.1.1.3".to_string()),
-            // Google
-            DnsProvider::Google => Some("8.8.8.8".to_string()),
-            // AdGuard
-            DnsProvider::AdGuard => Some("94.140.14.14".to_string()),
-            DnsProvider::AdGuardNonFiltering => Some("94.140.14.140".to_string()),
-            DnsProvider::AdGuardFamily => Some("94.140.14.15".to_string()),
-            // DNS4EU
-            DnsProvider::Dns4EuProtective => Some("86.54.11.1".to_string()),
-            DnsProvider::Dns4EuProtectiveChild => Some("86.54.11.12".to_string()),
-            DnsProvider::Dns4EuProtectiveAd => Some("86.54.11.13".to_string()),
-            DnsProvider::Dns4EuProtectiveChildAd => Some("86.54.11.11".to_string()),
-            DnsProvider::Dns4EuUnfiltered => Some("86.54.11.100".to_string()),
-            // Quad9
-            DnsProvider::Quad9Recommended => Some("9.9.9.9".to_string()),
-            DnsProvider::Quad9SecuredEcs => Some("9.9.9.11".to_string()),
-            DnsProvider::Quad9Unsecured => Some("9.9.9.10".to_string()),
-            // OpenDNS
-            DnsProvider::OpenDnsFamilyShield => Some("208.67.222.123".to_string()),
-            DnsProvider::OpenDnsHome => Some("208.67.222.222".to_string()),
-            DnsProvider::Custom(primary, _, _, _) => {
-                if primary.is_empty() {
-                    None
-                } else {
-                    Some(primary.clone())
-                }
-            }
+            DnsProvider::Custom(primary, _, _, _, _, _) => non_empty(primary),
+            _ => self.entry()?.ipv4.first().cloned(),
         }
     }
 
     /// Returns the secondary DNS server IP address.
     pub fn secondary(&self) -> Option<String> {
         match self {
-            DnsProvider::Auto => None,
-            // Cloudflare
-            DnsProvider::Cloudflare => Some("1.0.0.1".to_string()),
-            DnsProvider::CloudflareMalware => Some("1.0.0.2".to_string()),
-            DnsProvider::CloudflareFamily => Some("1.0.0.3".to_string()),
-            // Google
-            DnsProvider::Google => Some("8.8.4.4".to_string()),
-            // AdGuard
-            DnsProvider::AdGuard => Some("94.140.15.15".to_string()),
-            DnsProvider::AdGuardNonFiltering => Some("94.140.14.141".to_string()),
-            DnsProvider::AdGuardFamily => Some("94.140.15.16".to_string()),
-            // DNS4EU - single IP providers
-            DnsProvider::Dns4EuProtective => None,
-            DnsProvider::Dns4EuProtectiveChild => None,
-            DnsProvider::Dns4EuProtectiveAd => None,
-            DnsProvider::Dns4EuProtectiveChildAd => None,
-            DnsProvider::Dns4EuUnfiltered => None,
-            // Quad9
-            DnsProvider::Quad9Recommended => Some("149.112.112.112".to_string()),
-            DnsProvider::Quad9SecuredEcs => Some("149.112.112.11".to_string()),
-            DnsProvider::Quad9Unsecured => Some("149.112.112.10".to_string()),
-            // OpenDNS
-            DnsProvider::OpenDnsFamilyShield => Some("208.67.220.123".to_string()),
-            DnsProvider::OpenDnsHome => Some("208.67.220.220".to_string()),
-            DnsProvider::Custom(_, secondary, _, _) => {
-                if secondary.is_empty() {
-                    None
-                } else {
-                    Some(secondary.clone())
-                }
-            }
+            DnsProvider::Custom(_, secondary, _, _, _, _) => non_empty(secondary),
+            _ => self.entry()?.ipv4.get(1).cloned(),
         }
     }
 
     /// Returns the primary IPv6 DNS server address.
     pub fn primary_ipv6(&self) -> Option<String> {
         match self {
-            DnsProvider::Auto => None,
-            // Cloudflare
-            DnsProvider::Cloudflare => Some("2606:4700:4700::1111".to_string()),
-            DnsProvider::CloudflareMalware => Some("2606:4700:4700::1112".to_string()),
-            DnsProvider::CloudflareFamily => Some("2606:4700:4700::1113".to_string()),
-            // Google
-            DnsProvider::Google => Some("2001:4860:4860::8888".to_string()),
-            // AdGuard
-            DnsProvider::AdGuard => Some("2a10:50c0::ad1:ff".to_string()),
-            DnsProvider::AdGuardNonFiltering => Some("2a10:50c0::1:ff".to_string()),
-            DnsProvider::AdGuardFamily => Some("2a10:50c0::bad1:ff".to_string()),
-            // DNS4EU - no IPv6 published
-            DnsProvider::Dns4EuProtective => None,
-            DnsProvider::Dns4EuProtectiveChild => None,
-            DnsProvider::Dns4EuProtectiveAd => None,
-            DnsProvider::Dns4EuProtectiveChildAd => None,
-            DnsProvider::Dns4EuUnfiltered => None,
-            // Quad9
-            DnsProvider::Quad9Recommended => Some("2620:fe::fe".to_string()),
-            DnsProvider::Quad9SecuredEcs => Some("2620:fe::11".to_string()),
-            DnsProvider::Quad9Unsecured => Some("2620:fe::10".to_string()),
-            // OpenDNS
-            DnsProvider::OpenDnsFamilyShield => Some("2620:119:35::123".to_string()),
-            DnsProvider::OpenDnsHome => Some("2620:119:35::35".to_string()),
-            // Custom IPv6
-            DnsProvider::Custom(_, _, ipv6_primary, _) => ipv6_primary.clone(),
+            DnsProvider::Custom(_, _, ipv6_primary, _, _, _) => ipv6_primary.clone(),
+            _ => self.entry()?.ipv6.first().cloned(),
         }
     }
 
     /// Returns the secondary IPv6 DNS server address.
     pub fn secondary_ipv6(&self) -> Option<String> {
         match self {
-            DnsProvider::Auto => None,
-            // Cloudflare
-            DnsProvider::Cloudflare => Some("2606:4700:4700::1001".to_string()),
-            DnsProvider::CloudflareMalware => Some("2606:4700:4700::1002".to_string()),
-            DnsProvider::CloudflareFamily => Some("2606:4700:4700::1003".to_string()),
-            // Google
-            DnsProvider::Google => Some("2001:4860:4860::8844".to_string()),
-            // AdGuard
-            DnsProvider::AdGuard => Some("2a10:50c0::ad2:ff".to_string()),
-            DnsProvider::AdGuardNonFiltering => Some("2a10:50c0::2:ff".to_string()),
-            DnsProvider::AdGuardFamily => Some("2a10:50c0::bad2:ff".to_string()),
-            // DNS4EU - no IPv6 published
-            DnsProvider::Dns4EuProtective => None,
-            DnsProvider::Dns4EuProtectiveChild => None,
-            DnsProvider::Dns4EuProtectiveAd => None,
-            DnsProvider::Dns4EuProtectiveChildAd => None,
-            DnsProvider::Dns4EuUnfiltered => None,
-            // Quad9
-            DnsProvider::Quad9Recommended => Some("2620:fe::9".to_string()),
-            DnsProvider::Quad9SecuredEcs => Some("2620:fe::fe:11".to_string()),
-            DnsProvider::Quad9Unsecured => Some("2620:fe::fe:10".to_string()),
-            // OpenDNS
-            DnsProvider::OpenDnsFamilyShield => Some("2620:119:53::123".to_string()),
-            DnsProvider::OpenDnsHome => Some("2620:119:53::53".to_string()),
-            // Custom IPv6
-            DnsProvider::Custom(_, _, _, ipv6_secondary) => ipv6_secondary.clone(),
+            DnsProvider::Custom(_, _, _, ipv6_secondary, _, _) => ipv6_secondary.clone(),
+            _ => self.entry()?.ipv6.get(1).cloned(),
         }
     }
+
+    /// Returns the DNS-over-HTTPS URL template (RFC 8484).
+    pub fn doh_template(&self) -> Option<String> {
+        match self {
+            DnsProvider::Custom(_, _, _, _, doh_template, _) => doh_template.clone(),
+            _ => self.entry()?.doh_template.clone(),
+        }
+    }
+
+    /// Returns the DNS-over-TLS hostname (RFC 7858).
+    pub fn dot_hostname(&self) -> Option<String> {
+        match self {
+            DnsProvider::Custom(_, _, _, _, _, dot_hostname) => dot_hostname.clone(),
+            _ => self.entry()?.dot_hostname.clone(),
+        }
+    }
+
+    /// Transports the provider can be reached over.
+    pub fn transports(&self) -> Vec<DnsTransport> {
+        [
+            (DnsTransport::Udp, self.primary().is_some()),
+            (DnsTransport::Doh, self.doh_template().is_some()),
+            (DnsTransport::Dot, self.dot_hostname().is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(transport, supported)| supported.then_some(transport))
+        .collect()
+    }
 }
 
 #[cfg(test)]
@@ -504,16 +637,59 @@ mod tests {
         assert_eq!(settings.language, "en");
         assert_eq!(settings.test_timeout_ms, 2000);
         assert!(settings.dns_servers.is_empty());
+        assert_eq!(settings.probe_targets, ProbeTargets::default());
+    }
+
+    #[test]
+    fn test_probe_targets_partial_override() {
+        let json = r#"{"language":"en","test_timeout_ms":2000,"dns_servers":[],
+            "probe_targets":{"http_urls":["http://127.0.0.1:8080/health"]}}"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        let targets = &settings.probe_targets;
+        assert_eq!(targets.http_urls, vec!["http://127.0.0.1:8080/health"]);
+        assert_eq!(targets.dns_domains, ProbeTargets::default().dns_domains);
+        assert_eq!(targets.trusted_dns_server, "1.1.1.1:53");
+    }
+
+    #[test]
+    fn test_settings_without_probe_targets_uses_defaults() {
+        let json = r#"{"language":"en","test_timeout_ms":2000,"dns_servers":[]}"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.probe_targets, ProbeTargets::default());
+    }
+
+    #[test]
+    fn test_probe_targets_legacy_hijack_test_domain() {
+        let json = r#"{"hijack_test_domain":"example.org"}"#;
+        let targets: ProbeTargets = serde_json::from_str(json).unwrap();
+        assert_eq!(targets.hijack_test_domains, vec!["example.org"]);
+
+        let json = r#"{"hijack_test_domains":["a.example","b.example"]}"#;
+        let targets: ProbeTargets = serde_json::from_str(json).unwrap();
+        assert_eq!(targets.hijack_test_domains, vec!["a.example", "b.example"]);
+    }
+
+    #[test]
+    fn test_probe_targets_geoip_url() {
+        let targets = ProbeTargets {
+            geoip_base_url: "http://geo.local/".into(),
+            ..ProbeTargets::default()
+        };
+        assert_eq!(targets.geoip_url(""), "http://geo.local/json");
+        assert_eq!(
+            targets.geoip_url("8.8.8.8"),
+            "http://geo.local/8.8.8.8/json"
+        );
     }
 
     #[test]
     fn test_dns_provider_cloudflare() {
         assert_eq!(
-            DnsProvider::Cloudflare.primary(),
+            DnsProvider::Catalog("Cloudflare".into()).primary(),
             Some("1.1.1.1".to_string())
         );
         assert_eq!(
-            DnsProvider::Cloudflare.secondary(),
+            DnsProvider::Catalog("Cloudflare".into()).secondary(),
             Some("1.0.0.1".to_string())
         );
     }
@@ -531,11 +707,65 @@ mod tests {
             "5.6.7.8".to_string(),
             Some("2001:db8::1".to_string()),
             Some("2001:db8::2".to_string()),
+            Some("https://dns.example/dns-query{?dns}".to_string()),
+            None,
         );
         assert_eq!(custom.primary(), Some("1.2.3.4".to_string()));
         assert_eq!(custom.secondary(), Some("5.6.7.8".to_string()));
         assert_eq!(custom.primary_ipv6(), Some("2001:db8::1".to_string()));
         assert_eq!(custom.secondary_ipv6(), Some("2001:db8::2".to_string()));
+        assert_eq!(
+            custom.doh_template(),
+            Some("https://dns.example/dns-query{?dns}".to_string())
+        );
+        assert_eq!(custom.dot_hostname(), None);
+        assert_eq!(
+            custom.transports(),
+            vec![DnsTransport::Udp, DnsTransport::Doh]
+        );
+    }
+
+    #[test]
+    fn test_dns_provider_transports() {
+        assert_eq!(
+            DnsProvider::Catalog("Google".into()).transports(),
+            vec![DnsTransport::Udp, DnsTransport::Doh, DnsTransport::Dot]
+        );
+        assert_eq!(
+            DnsProvider::Catalog("Quad9Recommended".into()).dot_hostname(),
+            Some("dns.quad9.net".to_string())
+        );
+        assert!(DnsProvider::Auto.transports().is_empty());
+        assert_eq!(
+            serde_json::to_string(&DnsTransport::Doh).unwrap(),
+            "\"doh\""
+        );
+    }
+
+    #[test]
+    fn test_dns_provider_serialization() {
+        let cases = [
+            (DnsProvider::Auto, r#""Auto""#),
+            (
+                DnsProvider::Catalog("Quad9Recommended".into()),
+                r#""Quad9Recommended""#,
+            ),
+            (
+                DnsProvider::Custom("1.2.3.4".into(), String::new(), None, None, None, None),
+                r#"{"Custom":["1.2.3.4","",null,null,null,null]}"#,
+            ),
+        ];
+        for (provider, json) in cases {
+            assert_eq!(serde_json::to_string(&provider).unwrap(), json);
+            assert_eq!(serde_json::from_str::<DnsProvider>(json).unwrap(), provider);
+        }
+        // Ids dropped from the catalog still load, just without servers
+        let gone: DnsProvider = serde_json::from_str(r#""Retired""#).unwrap();
+        assert_eq!(gone.primary(), None);
+        assert_eq!(
+            DnsProvider::built_in().len(),
+            DnsCatalog::built_in().providers.len()
+        );
     }
 
     #[test]
@@ -591,7 +821,11 @@ mod tests {
             DiagnosticScenario::from_id(7),
             Some(DiagnosticScenario::WeakSignal)
         );
-        assert_eq!(DiagnosticScenario::from_id(8), None);
+        assert_eq!(
+            DiagnosticScenario::from_id(8),
+            Some(DiagnosticScenario::DhcpFailure)
+        );
+        assert_eq!(DiagnosticScenario::from_id(9), None);
         assert_eq!(DiagnosticScenario::from_id(255), None);
     }
 
@@ -606,7 +840,7 @@ mod tests {
 
     #[test]
     fn test_diagnostic_scenario_all_count() {
-        assert_eq!(DiagnosticScenario::all().len(), 8);
+        assert_eq!(DiagnosticScenario::all().len(), 9);
     }
 
     #[test]
@@ -627,6 +861,10 @@ mod tests {
             DiagnosticScenario::DnsFailure.severity(),
             DiagnosticSeverity::Error
         );
+        assert_eq!(
+            DiagnosticScenario::DhcpFailure.severity(),
+            DiagnosticSeverity::Error
+        );
     }
 
     #[test]
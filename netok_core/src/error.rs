@@ -0,0 +1,338 @@
+//! Typed errors for fallible core APIs.
+//!
+//! Each subsystem has its own error enum. All of them map to a shared
+//! `ErrorKind` so callers (the bridge, the UI) can react to the category —
+//! unsupported platform, permission denied, timeout, parse error — without
+//! matching on message text.
+
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable error category.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The operation is not implemented on this OS.
+    UnsupportedPlatform,
+    /// The OS refused the operation (needs elevation / access denied).
+    PermissionDenied,
+    /// The operation did not complete in time.
+    Timeout,
+    /// Input could not be parsed (see `position` where available).
+    Parse,
+    /// Input was well-formed but not acceptable.
+    InvalidInput,
+    /// A required resource (adapter, file) does not exist.
+    NotFound,
+    /// A network request failed.
+    Network,
+    /// An external command exited with an error.
+    CommandFailed,
+    /// Local file or storage failure.
+    Io,
+    /// Unexpected failure (e.g. a worker thread panicked).
+    Internal,
+}
+
+// ==================== DNS ====================
+
+/// Errors from DNS configuration, detection and server tests.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum DnsError {
+    #[error("{operation} is only supported on Windows")]
+    UnsupportedPlatform { operation: &'static str },
+    #[error("Failed to find active network adapter")]
+    AdapterNotFound,
+    #[error("Invalid IP address: {0}")]
+    InvalidAddress(String),
+    /// A DoH URL or DoT hostname that can't be used.
+    #[error("Invalid DNS server: {0}")]
+    InvalidEndpoint(String),
+    #[error("{command} requires administrator rights: {message}")]
+    PermissionDenied { command: String, message: String },
+    #[error("{command} failed: {message}")]
+    CommandFailed { command: String, message: String },
+    #[error("Failed to create resolver: {0}")]
+    Resolver(String),
+}
+
+impl DnsError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            DnsError::UnsupportedPlatform { .. } => ErrorKind::UnsupportedPlatform,
+            DnsError::AdapterNotFound => ErrorKind::NotFound,
+            DnsError::InvalidAddress(_) | DnsError::InvalidEndpoint(_) => ErrorKind::InvalidInput,
+            DnsError::PermissionDenied { .. } => ErrorKind::PermissionDenied,
+            DnsError::CommandFailed { .. } => ErrorKind::CommandFailed,
+            DnsError::Resolver(_) => ErrorKind::Network,
+        }
+    }
+
+    /// Classify a failed command by its error output.
+    ///
+    /// netsh and ipconfig report missing elevation only in their output text
+    /// ("requires elevation", "Access is denied"), so this is the one place
+    /// that looks at it.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub(crate) fn command(command: impl Into<String>, output: &str) -> Self {
+        let command = command.into();
+        let message = output.trim().to_string();
+        let lower = message.to_lowercase();
+        if lower.contains("elevation") || lower.contains("access is denied") {
+            DnsError::PermissionDenied { command, message }
+        } else {
+            DnsError::CommandFailed { command, message }
+        }
+    }
+}
+
+// ==================== VPN ====================
+
+/// Errors from VPN URI parsing and sing-box config generation.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum VpnError {
+    #[error("Unsupported VPN protocol. Supported: vless://, vmess://, ss://, trojan://, wg://")]
+    UnsupportedProtocol,
+    /// `position` is a byte offset into the trimmed URI, when the error can be
+    /// pinned to one (not inside base64-decoded payloads).
+    #[error("{message}")]
+    Parse {
+        message: String,
+        position: Option<usize>,
+    },
+    #[error("{protocol}: missing '{field}'")]
+    MissingField {
+        protocol: &'static str,
+        field: &'static str,
+    },
+    #[error("Invalid VPN config: {0}")]
+    InvalidConfig(String),
+}
+
+impl VpnError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            VpnError::UnsupportedProtocol => ErrorKind::InvalidInput,
+            VpnError::Parse { .. } | VpnError::MissingField { .. } => ErrorKind::Parse,
+            VpnError::InvalidConfig(_) => ErrorKind::InvalidInput,
+        }
+    }
+
+    /// Byte offset of a parse error in the URI, if known.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            VpnError::Parse { position, .. } => *position,
+            _ => None,
+        }
+    }
+
+    pub(crate) fn parse(message: impl Into<String>, position: usize) -> Self {
+        VpnError::Parse {
+            message: message.into(),
+            position: Some(position),
+        }
+    }
+
+    /// Shift the error position by `offset` (when a sub-parser worked on a slice).
+    pub(crate) fn offset(self, offset: usize) -> Self {
+        match self {
+            VpnError::Parse { message, position } => VpnError::Parse {
+                message,
+                position: position.map(|p| p + offset),
+            },
+            other => other,
+        }
+    }
+
+    /// Drop the position (for errors inside decoded payloads).
+    pub(crate) fn without_position(self) -> Self {
+        match self {
+            VpnError::Parse { message, .. } => VpnError::Parse {
+                message,
+                position: None,
+            },
+            other => other,
+        }
+    }
+}
+
+// ==================== GeoIP ====================
+
+/// Errors from IP geolocation lookups.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum GeoIpError {
+    #[error("Failed to create HTTP client: {0}")]
+    Client(String),
+    #[error("Request to {url} timed out")]
+    Timeout { url: String },
+    #[error("Request to {url} failed: {message}")]
+    Request { url: String, message: String },
+    #[error("Failed to parse geolocation response: {0}")]
+    InvalidResponse(String),
+}
+
+impl GeoIpError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            GeoIpError::Client(_) | GeoIpError::Request { .. } => ErrorKind::Network,
+            GeoIpError::Timeout { .. } => ErrorKind::Timeout,
+            GeoIpError::InvalidResponse(_) => ErrorKind::Parse,
+        }
+    }
+}
+
+// ==================== Storage ====================
+
+/// Errors from local files: history store and report export.
+#[derive(thiserror::Error, Debug)]
+pub enum StorageError {
+    #[error("Failed to {action}: {source}")]
+    Io {
+        action: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Failed to serialize {what}: {message}")]
+    Serialize { what: &'static str, message: String },
+    #[error("Storage lock poisoned")]
+    LockPoisoned,
+}
+
+impl StorageError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            StorageError::Io { source, .. } => match source.kind() {
+                std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+                std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+                _ => ErrorKind::Io,
+            },
+            StorageError::Serialize { .. } => ErrorKind::Parse,
+            StorageError::LockPoisoned => ErrorKind::Io,
+        }
+    }
+
+    pub(crate) fn io(action: &'static str) -> impl FnOnce(std::io::Error) -> Self {
+        move |source| StorageError::Io { action, source }
+    }
+}
+
+// ==================== Capture ====================
+
+/// Errors from saving and loading probe capture files.
+#[derive(thiserror::Error, Debug)]
+pub enum CaptureError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("Invalid capture file: {0}")]
+    Invalid(String),
+    #[error(
+        "Capture format version {found} is not supported (this build reads up to {supported})"
+    )]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl CaptureError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            CaptureError::Storage(e) => e.kind(),
+            CaptureError::Invalid(_) => ErrorKind::Parse,
+            CaptureError::UnsupportedVersion { .. } => ErrorKind::InvalidInput,
+        }
+    }
+}
+
+// ==================== Settings ====================
+
+/// Errors from saving and loading the settings file.
+#[derive(thiserror::Error, Debug)]
+pub enum SettingsError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("Invalid settings file: {0}")]
+    Invalid(String),
+}
+
+impl SettingsError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SettingsError::Storage(e) => e.kind(),
+            SettingsError::Invalid(_) => ErrorKind::Parse,
+        }
+    }
+}
+
+// ==================== DNS Presets ====================
+
+/// Errors from saving and loading DNS presets.
+#[derive(thiserror::Error, Debug)]
+pub enum PresetError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("Invalid preset name: {0:?}")]
+    InvalidName(String),
+    #[error("Unknown DNS provider: {0}")]
+    UnknownProvider(String),
+    #[error("Invalid preset file: {0}")]
+    Invalid(String),
+    #[error("Preset format version {found} is not supported (this build reads up to {supported})")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl PresetError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            PresetError::Storage(e) => e.kind(),
+            PresetError::InvalidName(_) | PresetError::UnknownProvider(_) => {
+                ErrorKind::InvalidInput
+            }
+            PresetError::Invalid(_) => ErrorKind::Parse,
+            PresetError::UnsupportedVersion { .. } => ErrorKind::InvalidInput,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dns_command_classifies_elevation() {
+        let err = DnsError::command(
+            "netsh",
+            "The requested operation requires elevation (Run as administrator).\r\n",
+        );
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+
+        let err = DnsError::command("netsh", "The filename, directory name is incorrect.");
+        assert_eq!(err.kind(), ErrorKind::CommandFailed);
+        assert_eq!(
+            err.to_string(),
+            "netsh failed: The filename, directory name is incorrect."
+        );
+    }
+
+    #[test]
+    fn test_vpn_error_offset() {
+        let err = VpnError::parse("Invalid port: x", 4).offset(8);
+        assert_eq!(err.position(), Some(12));
+        assert_eq!(err.kind(), ErrorKind::Parse);
+        assert_eq!(err.clone().without_position().position(), None);
+        assert_eq!(err.to_string(), "Invalid port: x");
+    }
+
+    #[test]
+    fn test_storage_error_kind_from_io() {
+        let err = StorageError::io("open history file")(std::io::Error::from(
+            std::io::ErrorKind::PermissionDenied,
+        ));
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert!(err.to_string().starts_with("Failed to open history file: "));
+    }
+
+    #[test]
+    fn test_error_kind_serializes_snake_case() {
+        assert_eq!(
+            serde_json::to_string(&ErrorKind::UnsupportedPlatform).unwrap(),
+            "\"unsupported_platform\""
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! Persistent diagnostics history.
+//!
+//! Stores diagnostics snapshots, Wi-Fi security reports and device scan
+//! results in an append-only JSON Lines file, one record per line. Appends
+//! never rewrite existing data; retention runs on open and at most hourly
+//! after that, rewriting the file atomically via a temporary file. Lines that
+//! fail to parse (e.g. a write interrupted by a crash) are skipped on read and
+//! dropped on the next retention pass.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::domain::{DiagnosticsSnapshot, NetworkDevice, Status};
+use crate::error::StorageError;
+use crate::infrastructure::security::WiFiSecurityReport;
+use crate::infrastructure::{get_default_gateway, get_router_mac, get_wifi_info};
+
+// ==================== Domain Types ====================
+
+/// Kind of a history record.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    Diagnostics,
+    Security,
+    DeviceScan,
+}
+
+/// Payload of a history record.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryEntry {
+    Diagnostics { snapshot: Box<DiagnosticsSnapshot> },
+    Security { report: WiFiSecurityReport },
+    DeviceScan { devices: Vec<NetworkDevice> },
+}
+
+impl HistoryEntry {
+    pub fn kind(&self) -> HistoryKind {
+        match self {
+            HistoryEntry::Diagnostics { .. } => HistoryKind::Diagnostics,
+            HistoryEntry::Security { .. } => HistoryKind::Security,
+            HistoryEntry::DeviceScan { .. } => HistoryKind::DeviceScan,
+        }
+    }
+}
+
+/// Identifies the network a record was taken on.
+///
+/// The gateway MAC tells wired networks (and same-named Wi-Fi networks) apart.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NetworkKey {
+    pub ssid: Option<String>,
+    pub gateway_mac: Option<String>,
+}
+
+impl NetworkKey {
+    /// Take the key from a diagnostics snapshot.
+    pub fn from_snapshot(snapshot: &DiagnosticsSnapshot) -> Self {
+        Self {
+            ssid: snapshot.network.ssid.clone(),
+            gateway_mac: snapshot.router.gateway_mac.clone(),
+        }
+    }
+
+    /// Look up the key for the network we're connected to right now.
+    pub fn current() -> Self {
+        Self {
+            ssid: get_wifi_info().ssid,
+            gateway_mac: get_default_gateway().and_then(|ip| get_router_mac(&ip)),
+        }
+    }
+}
+
+/// A stored history record.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryRecord {
+    /// Unix timestamp (seconds) when the record was stored.
+    pub recorded_at: u64,
+    pub network: NetworkKey,
+    #[serde(flatten)]
+    pub entry: HistoryEntry,
+}
+
+/// How long records are kept. `None` disables a limit.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_age_days: Option<u32>,
+    /// Newest records kept when the store grows beyond this.
+    pub max_records: Option<usize>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(30),
+            max_records: Some(5000),
+        }
+    }
+}
+
+/// Filter for [`HistoryStore::query`]. Unset fields match everything.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Inclusive lower bound on `recorded_at`.
+    pub from: Option<u64>,
+    /// Inclusive upper bound on `recorded_at`.
+    pub to: Option<u64>,
+    pub ssid: Option<String>,
+    /// Compared case-insensitively.
+    pub gateway_mac: Option<String>,
+    pub kind: Option<HistoryKind>,
+    /// Only diagnostics records with at least one node in this status.
+    pub node_status: Option<Status>,
+    /// Return only the newest N matches.
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        if self.from.is_some_and(|from| record.recorded_at < from)
+            || self.to.is_some_and(|to| record.recorded_at > to)
+        {
+            return false;
+        }
+        if let Some(ssid) = &self.ssid {
+            if record.network.ssid.as_deref() != Some(ssid.as_str()) {
+                return false;
+            }
+        }
+        if let Some(mac) = &self.gateway_mac {
+            let matches_mac = record
+                .network
+                .gateway_mac
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case(mac));
+            if !matches_mac {
+                return false;
+            }
+        }
+        if self.kind.is_some_and(|kind| record.entry.kind() != kind) {
+            return false;
+        }
+        if let Some(status) = self.node_status {
+            match &record.entry {
+                HistoryEntry::Diagnostics { snapshot } => {
+                    if !snapshot.nodes.iter().any(|n| n.status == status) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+// ==================== Store ====================
+
+/// Append-only JSONL history store.
+#[derive(Debug)]
+pub struct HistoryStore {
+    path: PathBuf,
+    retention: RetentionPolicy,
+    /// Serializes appends and rewrites within this process.
+    lock: Mutex<()>,
+    /// Unix time of the last retention pass.
+    last_retention: AtomicU64,
+}
+
+/// Minimum time between retention passes triggered by appends.
+const RETENTION_INTERVAL_SECS: u64 = 3600;
+
+fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl HistoryStore {
+    /// Open (or create) the store at `path` and apply the retention policy.
+    pub fn open(
+        path: impl Into<PathBuf>,
+        retention: RetentionPolicy,
+    ) -> Result<Self, StorageError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(StorageError::io("create history dir"))?;
+        }
+        let store = Self {
+            path,
+            retention,
+            lock: Mutex::new(()),
+            last_retention: AtomicU64::new(0),
+        };
+        store.apply_retention(now_unix())?;
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Store `entry`, stamped with the current time.
+    pub fn append(
+        &self,
+        entry: HistoryEntry,
+        network: NetworkKey,
+    ) -> Result<HistoryRecord, StorageError> {
+        let record = HistoryRecord {
+            recorded_at: now_unix(),
+            network,
+            entry,
+        };
+        self.append_record(&record)?;
+        Ok(record)
+    }
+
+    /// Store a pre-built record as-is.
+    ///
+    /// Applies the retention policy if the last pass is over an hour old.
+    pub fn append_record(&self, record: &HistoryRecord) -> Result<(), StorageError> {
+        let mut line = serde_json::to_string(record).map_err(|e| StorageError::Serialize {
+            what: "history record",
+            message: e.to_string(),
+        })?;
+        line.push('\n');
+
+        {
+            let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+            let mut file = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(StorageError::io("open history file"))?;
+            // Terminate a line left half-written by a crash so this record
+            // doesn't get glued onto it
+            if !ends_with_newline(&mut file).map_err(StorageError::io("read history file"))? {
+                line.insert(0, '\n');
+            }
+            // One write call per record so concurrent readers never see half a line
+            file.write_all(line.as_bytes())
+                .map_err(StorageError::io("write history record"))?;
+        }
+
+        let now = now_unix();
+        let last = self.last_retention.load(Ordering::Relaxed);
+        if now.saturating_sub(last) >= RETENTION_INTERVAL_SECS {
+            self.apply_retention(now)?;
+        }
+        Ok(())
+    }
+
+    /// Return matching records, oldest first.
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoryRecord>, StorageError> {
+        let mut records: Vec<HistoryRecord> = self
+            .read_all()?
+            .into_iter()
+            .filter(|r| query.matches(r))
+            .collect();
+        if let Some(limit) = query.limit {
+            let skip = records.len().saturating_sub(limit);
+            records.drain(..skip);
+        }
+        Ok(records)
+    }
+
+    /// Drop records outside the retention policy. Returns how many were removed.
+    pub fn apply_retention(&self, now: u64) -> Result<usize, StorageError> {
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        self.last_retention.store(now, Ordering::Relaxed);
+        if !self.path.exists() {
+            return Ok(0);
+        }
+
+        let (records, corrupt) = self.read_lines()?;
+        let total = records.len();
+
+        let min_time = self
+            .retention
+            .max_age_days
+            .map(|days| now.saturating_sub(u64::from(days) * 86_400));
+        let mut kept: Vec<HistoryRecord> = records
+            .into_iter()
+            .filter(|r| min_time.is_none_or(|min| r.recorded_at >= min))
+            .collect();
+        if let Some(max) = self.retention.max_records {
+            let skip = kept.len().saturating_sub(max);
+            kept.drain(..skip);
+        }
+
+        let removed = total - kept.len();
+        if removed == 0 && corrupt == 0 {
+            return Ok(0);
+        }
+        self.rewrite(&kept)?;
+        tracing::debug!(
+            removed,
+            corrupt,
+            kept = kept.len(),
+            "history retention applied"
+        );
+        Ok(removed)
+    }
+
+    /// Delete all history.
+    pub fn clear(&self) -> Result<(), StorageError> {
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageError::io("delete history file")(e)),
+        }
+    }
+
+    fn read_all(&self) -> Result<Vec<HistoryRecord>, StorageError> {
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        self.read_lines().map(|(records, _)| records)
+    }
+
+    /// Read every parseable record. Also returns the number of skipped lines.
+    fn read_lines(&self) -> Result<(Vec<HistoryRecord>, usize), StorageError> {
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], 0)),
+            Err(e) => return Err(StorageError::io("open history file")(e)),
+        };
+
+        let mut records = Vec::new();
+        let mut corrupt = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(StorageError::io("read history file"))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<HistoryRecord>(&line) {
+                Ok(r) => records.push(r),
+                Err(_) => corrupt += 1,
+            }
+        }
+        Ok((records, corrupt))
+    }
+
+    fn rewrite(&self, records: &[HistoryRecord]) -> Result<(), StorageError> {
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        {
+            let mut tmp =
+                File::create(&tmp_path).map_err(StorageError::io("create temp history file"))?;
+            for record in records {
+                let line = serde_json::to_string(record).map_err(|e| StorageError::Serialize {
+                    what: "history record",
+                    message: e.to_string(),
+                })?;
+                writeln!(tmp, "{}", line).map_err(StorageError::io("write temp history file"))?;
+            }
+            tmp.sync_all()
+                .map_err(StorageError::io("flush temp history file"))?;
+        }
+        std::fs::rename(&tmp_path, &self.path).map_err(StorageError::io("replace history file"))
+    }
+}
+
+/// Whether `file` is empty or its last byte is a newline.
+fn ends_with_newline(file: &mut File) -> std::io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ComputerInfo, InternetInfo, NetworkInfo, NodeId, NodeInfo, RouterInfo};
+    use crate::test_util::TempPath;
+
+    fn snapshot(internet_status: Status) -> DiagnosticsSnapshot {
+        DiagnosticsSnapshot {
+            at_utc: "2024-01-17T10:00:00Z".into(),
+            nodes: vec![NodeInfo {
+                id: NodeId::Internet,
+                name_key: "nodes.internet.name".into(),
+                status: internet_status,
+                latency_ms: Some(5),
+                hint_key: None,
+            }],
+            summary_key: "summary.ok".into(),
+            computer: ComputerInfo::default(),
+            network: NetworkInfo::default(),
+            router: RouterInfo::default(),
+            internet: InternetInfo::default(),
+        }
+    }
+
+    fn record(recorded_at: u64, ssid: &str, mac: &str, status: Status) -> HistoryRecord {
+        HistoryRecord {
+            recorded_at,
+            network: NetworkKey {
+                ssid: Some(ssid.into()),
+                gateway_mac: Some(mac.into()),
+            },
+            entry: HistoryEntry::Diagnostics {
+                snapshot: Box::new(snapshot(status)),
+            },
+        }
+    }
+
+    fn no_retention() -> RetentionPolicy {
+        RetentionPolicy {
+            max_age_days: None,
+            max_records: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_query_roundtrip() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(&tmp.0, no_retention()).unwrap();
+        store
+            .append(
+                HistoryEntry::DeviceScan { devices: vec![] },
+                NetworkKey::default(),
+            )
+            .unwrap();
+        store
+            .append_record(&record(100, "Home", "AA:BB:CC:DD:EE:FF", Status::Ok))
+            .unwrap();
+
+        let all = store.query(&HistoryQuery::default()).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].entry.kind(), HistoryKind::DeviceScan);
+        assert_eq!(all[1].entry.kind(), HistoryKind::Diagnostics);
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(&tmp.0, no_retention()).unwrap();
+        store
+            .append_record(&record(100, "Home", "aa:bb:cc:dd:ee:ff", Status::Ok))
+            .unwrap();
+        store
+            .append_record(&record(200, "Home", "AA:BB:CC:DD:EE:FF", Status::Fail))
+            .unwrap();
+        store
+            .append_record(&record(300, "Office", "11:22:33:44:55:66", Status::Fail))
+            .unwrap();
+
+        let by_time = HistoryQuery {
+            from: Some(150),
+            to: Some(250),
+            ..Default::default()
+        };
+        assert_eq!(store.query(&by_time).unwrap().len(), 1);
+
+        let by_mac = HistoryQuery {
+            gateway_mac: Some("AA:BB:CC:DD:EE:FF".into()),
+            ..Default::default()
+        };
+        assert_eq!(store.query(&by_mac).unwrap().len(), 2);
+
+        let home_failures = HistoryQuery {
+            ssid: Some("Home".into()),
+            node_status: Some(Status::Fail),
+            ..Default::default()
+        };
+        let found = store.query(&home_failures).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].recorded_at, 200);
+
+        let newest = HistoryQuery {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(store.query(&newest).unwrap()[0].recorded_at, 300);
+    }
+
+    #[test]
+    fn test_node_status_filter_skips_other_kinds() {
+        let query = HistoryQuery {
+            node_status: Some(Status::Ok),
+            ..Default::default()
+        };
+        let scan = HistoryRecord {
+            recorded_at: 0,
+            network: NetworkKey::default(),
+            entry: HistoryEntry::DeviceScan { devices: vec![] },
+        };
+        assert!(!query.matches(&scan));
+    }
+
+    #[test]
+    fn test_retention_by_age_and_count() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(
+            &tmp.0,
+            RetentionPolicy {
+                max_age_days: Some(1),
+                max_records: Some(2),
+            },
+        )
+        .unwrap();
+        let now = 10 * 86_400;
+        store
+            .append_record(&record(now - 2 * 86_400, "A", "m", Status::Ok))
+            .unwrap();
+        for i in 0..3 {
+            store
+                .append_record(&record(now - 100 + i, "A", "m", Status::Ok))
+                .unwrap();
+        }
+
+        assert_eq!(store.apply_retention(now).unwrap(), 2);
+        let left: Vec<u64> = store
+            .query(&HistoryQuery::default())
+            .unwrap()
+            .iter()
+            .map(|r| r.recorded_at)
+            .collect();
+        assert_eq!(left, vec![now - 99, now - 98]);
+    }
+
+    #[test]
+    fn test_corrupt_lines_are_skipped_and_cleaned() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(&tmp.0, no_retention()).unwrap();
+        store
+            .append_record(&record(100, "A", "m", Status::Ok))
+            .unwrap();
+        {
+            let mut f = OpenOptions::new().append(true).open(&tmp.0).unwrap();
+            f.write_all(b"{\"recorded_at\":12").unwrap();
+        }
+
+        assert_eq!(store.query(&HistoryQuery::default()).unwrap().len(), 1);
+        assert_eq!(store.apply_retention(200).unwrap(), 0);
+        let text = std::fs::read_to_string(&tmp.0).unwrap();
+        assert_eq!(text.lines().count(), 1);
+    }
+
+    #[test]
+    fn test_append_after_truncated_line_starts_a_new_line() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(&tmp.0, no_retention()).unwrap();
+        std::fs::write(&tmp.0, b"{\"recorded_at\":12").unwrap();
+        store
+            .append_record(&record(100, "A", "m", Status::Ok))
+            .unwrap();
+
+        let all = store.query(&HistoryQuery::default()).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].recorded_at, 100);
+    }
+
+    #[test]
+    fn test_append_applies_retention_once_interval_passed() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(
+            &tmp.0,
+            RetentionPolicy {
+                max_age_days: None,
+                max_records: Some(2),
+            },
+        )
+        .unwrap();
+        for i in 0..3 {
+            store
+                .append_record(&record(100 + i, "A", "m", Status::Ok))
+                .unwrap();
+        }
+        // Retention just ran on open, so appends leave the file alone
+        assert_eq!(store.query(&HistoryQuery::default()).unwrap().len(), 3);
+
+        store.last_retention.store(0, Ordering::Relaxed);
+        store
+            .append_record(&record(103, "A", "m", Status::Ok))
+            .unwrap();
+        let left: Vec<u64> = store
+            .query(&HistoryQuery::default())
+            .unwrap()
+            .iter()
+            .map(|r| r.recorded_at)
+            .collect();
+        assert_eq!(left, vec![102, 103]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let tmp = TempPath::new("history.jsonl");
+        let store = HistoryStore::open(&tmp.0, no_retention()).unwrap();
+        store
+            .append_record(&record(100, "A", "m", Status::Ok))
+            .unwrap();
+        store.clear().unwrap();
+        assert!(store.query(&HistoryQuery::default()).unwrap().is_empty());
+        // Clearing an empty store is fine
+        store.clear().unwrap();
+    }
+}
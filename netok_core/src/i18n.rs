@@ -0,0 +1,143 @@
+//! Text in the UI's languages.
+//!
+//! The locale files are the desktop UI's own (`ui/src/i18n/*.json`), embedded
+//! at build time, so the CLI and exported reports say the same thing the app
+//! does.
+//!
+//! The files are read from outside the crate directory, so `netok_core` only
+//! builds from a checkout of the whole workspace and is not published
+//! (`publish = false`).
+
+use serde_json::Value;
+
+/// Locale files shared with the desktop UI.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../../ui/src/i18n/en.json")),
+    ("de", include_str!("../../ui/src/i18n/de.json")),
+    ("es", include_str!("../../ui/src/i18n/es.json")),
+    ("fa", include_str!("../../ui/src/i18n/fa.json")),
+    ("fr", include_str!("../../ui/src/i18n/fr.json")),
+    ("it", include_str!("../../ui/src/i18n/it.json")),
+    ("ja", include_str!("../../ui/src/i18n/ja.json")),
+    ("ko", include_str!("../../ui/src/i18n/ko.json")),
+    ("pl", include_str!("../../ui/src/i18n/pl.json")),
+    ("pt", include_str!("../../ui/src/i18n/pt.json")),
+    ("ru", include_str!("../../ui/src/i18n/ru.json")),
+    ("tr", include_str!("../../ui/src/i18n/tr.json")),
+    ("uk", include_str!("../../ui/src/i18n/uk.json")),
+    ("zh", include_str!("../../ui/src/i18n/zh.json")),
+];
+
+/// Translations for one language, with English as fallback.
+pub struct I18n {
+    strings: Value,
+    english: Value,
+}
+
+impl I18n {
+    /// Load `lang` (`de`, `pt-BR`, `ru_RU.UTF-8`, …); unknown languages get English.
+    pub fn new(lang: &str) -> Self {
+        let code = lang
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let english = parse_locale("en");
+        let strings = if code == "en" {
+            english.clone()
+        } else {
+            parse_locale(&code)
+        };
+        Self { strings, english }
+    }
+
+    /// Translate `key`, falling back to English and then to the key itself.
+    pub fn t(&self, key: &str) -> String {
+        lookup(&self.strings, key)
+            .or_else(|| lookup(&self.english, key))
+            .unwrap_or(key)
+            .to_string()
+    }
+
+    /// Translate `key` and fill in `{{name}}` placeholders.
+    pub fn t_with(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.t(key), |text, (name, value)| {
+            text.replace(&format!("{{{{{}}}}}", name), value)
+        })
+    }
+}
+
+fn parse_locale(code: &str) -> Value {
+    LOCALES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .and_then(|(_, text)| serde_json::from_str(text).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn lookup<'a>(strings: &'a Value, key: &str) -> Option<&'a str> {
+    key.split('.')
+        .try_fold(strings, |node, part| node.get(part))?
+        .as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_locale_parses() {
+        for (code, text) in LOCALES {
+            assert!(
+                serde_json::from_str::<Value>(text).is_ok(),
+                "{}.json is not valid JSON",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_locale_has_every_english_key() {
+        fn keys(node: &Value, prefix: &str, out: &mut Vec<String>) {
+            if let Value::Object(map) = node {
+                for (name, child) in map {
+                    keys(child, &format!("{}{}.", prefix, name), out);
+                }
+            } else {
+                out.push(prefix.trim_end_matches('.').to_string());
+            }
+        }
+        let mut english = Vec::new();
+        keys(&parse_locale("en"), "", &mut english);
+        for (code, _) in LOCALES {
+            let strings = parse_locale(code);
+            let missing: Vec<_> = english
+                .iter()
+                .filter(|key| lookup(&strings, key).is_none())
+                .collect();
+            assert!(missing.is_empty(), "{}.json lacks {:?}", code, missing);
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_fallback() {
+        let en = I18n::new("en");
+        assert_eq!(en.t("diagnostics.router"), "Router");
+        assert_eq!(en.t("no.such.key"), "no.such.key");
+
+        let unknown = I18n::new("xx_YY.UTF-8");
+        assert_eq!(unknown.t("diagnostics.router"), "Router");
+
+        let de = I18n::new("de-DE");
+        assert!(!de.t("diagnostics.router").is_empty());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let en = I18n::new("en");
+        assert_eq!(
+            en.t_with("device_scan.found", &[("count", "3")]),
+            "3 devices found"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! ARP table lookups for MAC address resolution.
 
+use crate::cancel::Cancellation;
+
 /// Get router MAC address via ARP lookup.
 #[cfg(target_os = "windows")]
 pub fn get_router_mac(gateway_ip: &str) -> Option<String> {
@@ -59,6 +61,7 @@ pub fn get_router_mac(_gateway_ip: &str) -> Option<String> {
 }
 
 /// An entry from the system ARP table.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ArpEntry {
     pub ip: String,
     pub mac: String,
@@ -108,9 +111,10 @@ fn subnet_prefix(gateway_ip: &str) -> Option<String> {
 ///
 /// Uses parallel system `ping` commands (no admin rights needed).
 /// Each ping has a 200ms timeout, run in batches of 20 threads.
-/// Total time: ~3-5 seconds for a /24 subnet.
+/// Total time: ~3-5 seconds for a /24 subnet. Stops before the next batch
+/// once `cancel` fires.
 #[cfg(target_os = "windows")]
-pub fn ping_sweep(gateway_ip: &str) {
+pub fn ping_sweep(gateway_ip: &str, cancel: &Cancellation) {
     use super::hidden_cmd;
 
     let prefix = match subnet_prefix(gateway_ip) {
@@ -122,6 +126,9 @@ pub fn ping_sweep(gateway_ip: &str) {
     std::thread::scope(|s| {
         let batch_size = 20;
         for chunk_start in (1..=254).step_by(batch_size) {
+            if cancel.is_stopped() {
+                break;
+            }
             let chunk_end = (chunk_start + batch_size).min(255);
             let handles: Vec<_> = (chunk_start..chunk_end)
                 .map(|i| {
@@ -142,12 +149,52 @@ pub fn ping_sweep(gateway_ip: &str) {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn ping_sweep(_gateway_ip: &str) {
+pub fn ping_sweep(_gateway_ip: &str, _cancel: &Cancellation) {
     // TODO: Implement for Linux/macOS
     // Linux: ping -c 1 -W 1 <ip>
     // macOS: ping -c 1 -t 1 <ip>
 }
 
+/// Send a single ICMP echo to `ip` via the system `ping` command.
+///
+/// Returns `true` if a reply arrived within `timeout_ms`.
+#[cfg(target_os = "windows")]
+pub fn ping_host(ip: &str, timeout_ms: u32) -> bool {
+    use super::hidden_cmd;
+
+    hidden_cmd("ping")
+        .args(["-n", "1", "-w", &timeout_ms.to_string(), ip])
+        .output()
+        // Windows ping exits 0 on "Destination host unreachable" too — require a TTL line
+        .map(|o| o.status.success() && String::from_utf8_lossy(&o.stdout).contains("TTL="))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+pub fn ping_host(ip: &str, timeout_ms: u32) -> bool {
+    let secs = timeout_ms.div_ceil(1000).max(1).to_string();
+    std::process::Command::new("ping")
+        .args(["-c", "1", "-W", &secs, ip])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "macos")]
+pub fn ping_host(ip: &str, timeout_ms: u32) -> bool {
+    let secs = timeout_ms.div_ceil(1000).max(1).to_string();
+    std::process::Command::new("ping")
+        .args(["-c", "1", "-t", &secs, ip])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn ping_host(_ip: &str, _timeout_ms: u32) -> bool {
+    false
+}
+
 /// Get all reachable entries from the ARP table (IPv4 only).
 #[cfg(target_os = "windows")]
 pub fn get_all_arp_entries() -> Vec<ArpEntry> {
@@ -0,0 +1,599 @@
+//! DHCP lease discovery.
+//!
+//! Reads lease details for the active adapter from the platform's own
+//! records:
+//! - **Windows**: `Win32_NetworkAdapterConfiguration` via PowerShell
+//! - **Linux**: systemd-networkd / NetworkManager lease files, then dhclient leases
+//! - **macOS**: `ipconfig getpacket <iface>`
+//!
+//! The parsers are plain functions over text so they can be unit-tested on
+//! any platform.
+
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::domain::DhcpInfo;
+
+/// Get DHCP lease details for the given adapter (or the first DHCP adapter found).
+#[cfg(target_os = "windows")]
+pub fn get_dhcp_info(adapter_name: Option<&str>) -> Option<DhcpInfo> {
+    use super::run_powershell;
+
+    let wanted = adapter_name.unwrap_or("").replace('\'', "''");
+    let command = format!(
+        "$cfgs = Get-CimInstance Win32_NetworkAdapterConfiguration -Filter 'IPEnabled=True'; \
+         $c = $cfgs | Where-Object {{ $_.Description -eq '{}' }} | Select-Object -First 1; \
+         if (-not $c) {{ $c = $cfgs | Where-Object {{ $_.DefaultIPGateway }} | Select-Object -First 1 }}; \
+         if (-not $c) {{ $c = $cfgs | Select-Object -First 1 }}; \
+         if ($c) {{ \
+           'enabled=' + $c.DHCPEnabled; \
+           'server=' + $c.DHCPServer; \
+           if ($c.DHCPLeaseObtained) {{ 'obtained=' + $c.DHCPLeaseObtained.ToUniversalTime().ToString('yyyy-MM-ddTHH:mm:ssZ') }}; \
+           if ($c.DHCPLeaseExpires) {{ 'expires=' + $c.DHCPLeaseExpires.ToUniversalTime().ToString('yyyy-MM-ddTHH:mm:ssZ') }}; \
+           'dns=' + ($c.DNSServerSearchOrder -join ' ') \
+         }}",
+        wanted
+    );
+
+    let text = run_powershell(&command)?;
+    Some(parse_windows_dhcp(&text, now_unix()))
+}
+
+/// Get DHCP lease details for the given adapter (or the first DHCP adapter found).
+#[cfg(target_os = "linux")]
+pub fn get_dhcp_info(adapter_name: Option<&str>) -> Option<DhcpInfo> {
+    use std::path::Path;
+
+    // systemd-networkd keeps one file per interface index
+    if let Some(iface) = adapter_name {
+        let ifindex = std::fs::read_to_string(format!("/sys/class/net/{}/ifindex", iface))
+            .ok()
+            .map(|s| s.trim().to_string());
+        if let Some(ifindex) = ifindex {
+            let path = format!("/run/systemd/netif/leases/{}", ifindex);
+            if let Some(info) = read_key_value_lease(Path::new(&path)) {
+                return Some(info);
+            }
+        }
+    }
+
+    // NetworkManager internal client: internal-<uuid>-<iface>.lease
+    // dhclient: dhclient*.lease(s), possibly under NetworkManager's directory
+    let dirs = [
+        "/var/lib/NetworkManager",
+        "/var/lib/dhcp",
+        "/var/lib/dhclient",
+    ];
+    let mut lease_files: Vec<std::path::PathBuf> = dirs
+        .iter()
+        .filter_map(|d| std::fs::read_dir(d).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "lease" || ext == "leases")
+        })
+        .collect();
+
+    // Most recently written lease first
+    lease_files.sort_by_key(|p| std::cmp::Reverse(p.metadata().and_then(|m| m.modified()).ok()));
+
+    for path in &lease_files {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if name.starts_with("internal-") {
+            let matches_iface = adapter_name
+                .map(|iface| name.ends_with(&format!("-{}.lease", iface)))
+                .unwrap_or(true);
+            if matches_iface {
+                if let Some(info) = read_key_value_lease(path) {
+                    return Some(info);
+                }
+            }
+        } else if name.contains("dhclient") {
+            let text = match super::read_text_file(path) {
+                Some(t) => t,
+                None => continue,
+            };
+            if let Some(info) = parse_dhclient_leases(&text, adapter_name, now_unix()) {
+                return Some(info);
+            }
+        }
+    }
+
+    None
+}
+
+/// Read a systemd-networkd style KEY=VALUE lease file, using its mtime as the
+/// time the lease was obtained.
+#[cfg(target_os = "linux")]
+fn read_key_value_lease(path: &std::path::Path) -> Option<DhcpInfo> {
+    let text = super::read_text_file(path)?;
+    let obtained = path
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    Some(parse_networkd_lease(&text, obtained, now_unix()))
+}
+
+/// Get DHCP lease details for the given adapter (or the first DHCP adapter found).
+#[cfg(target_os = "macos")]
+pub fn get_dhcp_info(adapter_name: Option<&str>) -> Option<DhcpInfo> {
+    use super::command_output;
+    use std::process::Command;
+
+    let candidates: Vec<&str> = match adapter_name {
+        Some(iface) => vec![iface],
+        None => vec!["en0", "en1"],
+    };
+
+    for iface in candidates {
+        let output = match command_output(Command::new("ipconfig").args(["getpacket", iface])) {
+            Ok(o) => o,
+            Err(_) => continue,
+        };
+        if !output.status.success() {
+            continue;
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        if !text.trim().is_empty() {
+            return Some(parse_macos_getpacket(&text));
+        }
+    }
+
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+pub fn get_dhcp_info(_adapter_name: Option<&str>) -> Option<DhcpInfo> {
+    None
+}
+
+// ==================== Parsers ====================
+
+/// Current time as Unix seconds.
+#[allow(dead_code)]
+fn now_unix() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+/// Format Unix seconds as an RFC 3339 UTC timestamp.
+fn format_unix(secs: i64) -> Option<String> {
+    OffsetDateTime::from_unix_timestamp(secs)
+        .ok()?
+        .format(&Rfc3339)
+        .ok()
+}
+
+/// Parse a `YYYY-MM-DDTHH:MM:SSZ` or `YYYY/MM/DD HH:MM:SS` UTC timestamp to Unix seconds.
+fn parse_utc_timestamp(s: &str) -> Option<i64> {
+    let s = s.trim().trim_end_matches('Z');
+    let (date_part, time_part) = s.split_once(['T', ' '])?;
+    let date: Vec<&str> = date_part.split(['-', '/']).collect();
+    let clock: Vec<&str> = time_part.split(':').collect();
+    if date.len() != 3 || clock.len() != 3 {
+        return None;
+    }
+
+    let year: i32 = date[0].parse().ok()?;
+    let month = Month::try_from(date[1].parse::<u8>().ok()?).ok()?;
+    let day: u8 = date[2].parse().ok()?;
+    let hour: u8 = clock[0].parse().ok()?;
+    let minute: u8 = clock[1].parse().ok()?;
+    let second: u8 = clock[2].parse().ok()?;
+
+    let date = Date::from_calendar_date(year, month, day).ok()?;
+    let clock = Time::from_hms(hour, minute, second).ok()?;
+    Some(
+        PrimitiveDateTime::new(date, clock)
+            .assume_utc()
+            .unix_timestamp(),
+    )
+}
+
+/// Fill in the expiry fields and flag leases that have already run out.
+fn apply_expiry(info: &mut DhcpInfo, expires_unix: Option<i64>, now_unix: i64) {
+    if let Some(expires) = expires_unix {
+        info.lease_expires_utc = format_unix(expires);
+        info.lease_expired = expires <= now_unix;
+    }
+}
+
+/// Split a whitespace/comma separated address list.
+fn split_addresses(s: &str) -> Vec<String> {
+    s.split([' ', ',', '\t'])
+        .map(|a| a.trim().trim_matches(['{', '}', '"']))
+        .filter(|a| !a.is_empty())
+        .map(|a| a.to_string())
+        .collect()
+}
+
+/// Parse a systemd-networkd / NetworkManager internal lease file.
+///
+/// Format: `KEY=VALUE` lines (`SERVER_ADDRESS`, `LIFETIME`, `T1`, `T2`, `DNS`, `NTP`).
+/// The files carry no timestamp, so `obtained_unix` is taken from the file mtime.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_networkd_lease(
+    text: &str,
+    obtained_unix: Option<i64>,
+    now_unix: i64,
+) -> DhcpInfo {
+    let mut info = DhcpInfo {
+        enabled: true,
+        ..Default::default()
+    };
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "SERVER_ADDRESS" => info.server = Some(value.to_string()),
+            "LIFETIME" => info.lease_time_secs = value.parse().ok(),
+            "T1" => info.renewal_time_secs = value.parse().ok(),
+            "T2" => info.rebinding_time_secs = value.parse().ok(),
+            "DNS" => info.dns_servers = split_addresses(value),
+            "NTP" => info.ntp_servers = split_addresses(value),
+            _ => {}
+        }
+    }
+
+    if let Some(obtained) = obtained_unix {
+        info.lease_obtained_utc = format_unix(obtained);
+        let expires = info.lease_time_secs.map(|l| obtained + l as i64);
+        apply_expiry(&mut info, expires, now_unix);
+    }
+
+    info
+}
+
+/// Parse an ISC dhclient leases file and return the newest lease for `iface`.
+///
+/// dhclient appends a `lease { ... }` block on every renewal, so the last
+/// matching block is the current one. Timestamps are either
+/// `expire 3 2024/01/17 10:22:33;` (UTC) or `expire epoch 1705486953;`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn parse_dhclient_leases(
+    text: &str,
+    iface: Option<&str>,
+    now_unix: i64,
+) -> Option<DhcpInfo> {
+    let mut current: Option<DhcpInfo> = None;
+    let mut newest: Option<DhcpInfo> = None;
+    let mut block_iface: Option<String> = None;
+    let mut renew_unix: Option<i64> = None;
+    let mut expires_unix: Option<i64> = None;
+
+    for raw_line in text.lines() {
+        let line = raw_line.trim();
+
+        if line.starts_with("lease") && line.ends_with('{') {
+            current = Some(DhcpInfo {
+                enabled: true,
+                ..Default::default()
+            });
+            block_iface = None;
+            renew_unix = None;
+            expires_unix = None;
+            continue;
+        }
+
+        if line == "}" {
+            if let Some(mut info) = current.take() {
+                let iface_matches = match (iface, &block_iface) {
+                    (Some(wanted), Some(found)) => wanted == found,
+                    _ => true,
+                };
+                if iface_matches {
+                    // Obtained = expiry - lease time, or renewal - T1
+                    let obtained = match (expires_unix, info.lease_time_secs) {
+                        (Some(e), Some(l)) => Some(e - l as i64),
+                        _ => match (renew_unix, info.renewal_time_secs) {
+                            (Some(r), Some(t1)) => Some(r - t1 as i64),
+                            _ => None,
+                        },
+                    };
+                    info.lease_obtained_utc = obtained.and_then(format_unix);
+                    apply_expiry(&mut info, expires_unix, now_unix);
+                    newest = Some(info);
+                }
+            }
+            continue;
+        }
+
+        let Some(info) = current.as_mut() else {
+            continue;
+        };
+        let line = line.trim_end_matches(';');
+
+        if let Some(rest) = line.strip_prefix("interface ") {
+            block_iface = Some(rest.trim_matches('"').to_string());
+        } else if let Some(rest) = line.strip_prefix("option ") {
+            let Some((name, value)) = rest.split_once(' ') else {
+                continue;
+            };
+            match name {
+                "dhcp-server-identifier" => info.server = Some(value.trim().to_string()),
+                "dhcp-lease-time" => info.lease_time_secs = value.trim().parse().ok(),
+                "dhcp-renewal-time" => info.renewal_time_secs = value.trim().parse().ok(),
+                "dhcp-rebinding-time" => info.rebinding_time_secs = value.trim().parse().ok(),
+                "domain-name-servers" => info.dns_servers = split_addresses(value),
+                "ntp-servers" => info.ntp_servers = split_addresses(value),
+                _ => {}
+            }
+        } else if let Some(rest) = line.strip_prefix("renew ") {
+            renew_unix = parse_dhclient_time(rest);
+        } else if let Some(rest) = line.strip_prefix("expire ") {
+            expires_unix = parse_dhclient_time(rest);
+        }
+    }
+
+    newest
+}
+
+/// Parse a dhclient time value: `<weekday> YYYY/MM/DD HH:MM:SS` or `epoch <secs>`.
+fn parse_dhclient_time(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Some(secs) = value.strip_prefix("epoch ") {
+        return secs.trim().parse().ok();
+    }
+    if value == "never" {
+        return None;
+    }
+    // Drop the leading weekday digit
+    let (_, datetime) = value.split_once(' ')?;
+    parse_utc_timestamp(datetime)
+}
+
+/// Parse `ipconfig getpacket <iface>` output on macOS.
+///
+/// Only durations are reported by the packet dump; the lease start time is
+/// not available, so `lease_expires_utc` stays empty.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub(crate) fn parse_macos_getpacket(text: &str) -> DhcpInfo {
+    let mut info = DhcpInfo {
+        enabled: true,
+        ..Default::default()
+    };
+
+    for line in text.lines() {
+        // Format: "name (type): value"
+        let Some((name_and_type, value)) = line.split_once("):") else {
+            continue;
+        };
+        let name = name_and_type.split(" (").next().unwrap_or("").trim();
+        let value = value.trim();
+
+        match name {
+            "server_identifier" => info.server = Some(value.to_string()),
+            "lease_time" => info.lease_time_secs = parse_macos_uint(value),
+            "renewal_t1_time_value" => info.renewal_time_secs = parse_macos_uint(value),
+            "rebinding_t2_time_value" => info.rebinding_time_secs = parse_macos_uint(value),
+            "domain_name_server" => info.dns_servers = split_addresses(value),
+            "ntp_server" | "ntp_servers" => info.ntp_servers = split_addresses(value),
+            _ => {}
+        }
+    }
+
+    info
+}
+
+/// Parse a `uint32` field from `ipconfig getpacket` (hex `0x15180` or decimal).
+fn parse_macos_uint(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parse the `key=value` lines emitted by the Windows PowerShell query.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(crate) fn parse_windows_dhcp(text: &str, now_unix: i64) -> DhcpInfo {
+    let mut info = DhcpInfo::default();
+    let mut obtained_unix: Option<i64> = None;
+    let mut expires_unix: Option<i64> = None;
+
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "enabled" => info.enabled = value.eq_ignore_ascii_case("true"),
+            // APIPA leases report 255.255.255.255 as the server
+            "server" if !value.is_empty() && value != "255.255.255.255" => {
+                info.server = Some(value.to_string());
+            }
+            "obtained" => obtained_unix = parse_utc_timestamp(value),
+            "expires" => expires_unix = parse_utc_timestamp(value),
+            "dns" => info.dns_servers = split_addresses(value),
+            _ => {}
+        }
+    }
+
+    if !info.enabled {
+        return info;
+    }
+
+    info.lease_obtained_utc = obtained_unix.and_then(format_unix);
+    if let (Some(o), Some(e)) = (obtained_unix, expires_unix) {
+        info.lease_time_secs = u32::try_from(e - o).ok();
+    }
+    apply_expiry(&mut info, expires_unix, now_unix);
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-17T10:00:00Z
+    const NOW: i64 = 1_705_485_600;
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        assert_eq!(parse_utc_timestamp("2024-01-17T10:00:00Z"), Some(NOW));
+        assert_eq!(parse_utc_timestamp("2024/01/17 10:00:00"), Some(NOW));
+        assert_eq!(parse_utc_timestamp("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_networkd_lease() {
+        let text = "# This is private data. Do not parse.\n\
+                    ADDRESS=192.168.1.23\n\
+                    SERVER_ADDRESS=192.168.1.1\n\
+                    T1=43200\n\
+                    T2=75600\n\
+                    LIFETIME=86400\n\
+                    DNS=192.168.1.1 8.8.8.8\n\
+                    NTP=192.168.1.1\n";
+
+        let info = parse_networkd_lease(text, Some(NOW - 3600), NOW);
+        assert!(info.enabled);
+        assert_eq!(info.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(info.lease_time_secs, Some(86400));
+        assert_eq!(info.renewal_time_secs, Some(43200));
+        assert_eq!(info.rebinding_time_secs, Some(75600));
+        assert_eq!(info.dns_servers, vec!["192.168.1.1", "8.8.8.8"]);
+        assert_eq!(info.ntp_servers, vec!["192.168.1.1"]);
+        assert_eq!(
+            info.lease_obtained_utc.as_deref(),
+            Some("2024-01-17T09:00:00Z")
+        );
+        assert_eq!(
+            info.lease_expires_utc.as_deref(),
+            Some("2024-01-18T09:00:00Z")
+        );
+        assert!(!info.lease_expired);
+    }
+
+    #[test]
+    fn test_parse_networkd_lease_expired() {
+        let info = parse_networkd_lease("LIFETIME=600\n", Some(NOW - 3600), NOW);
+        assert!(info.lease_expired);
+    }
+
+    #[test]
+    fn test_parse_dhclient_leases_picks_latest_for_interface() {
+        let text = r#"
+lease {
+  interface "wlan0";
+  fixed-address 192.168.1.20;
+  option dhcp-server-identifier 192.168.1.1;
+  option dhcp-lease-time 3600;
+  expire 3 2024/01/17 08:00:00;
+}
+lease {
+  interface "eth0";
+  fixed-address 10.0.0.5;
+  option dhcp-server-identifier 10.0.0.1;
+  expire 3 2024/01/17 20:00:00;
+}
+lease {
+  interface "wlan0";
+  fixed-address 192.168.1.23;
+  option dhcp-server-identifier 192.168.1.1;
+  option dhcp-lease-time 86400;
+  option dhcp-renewal-time 43200;
+  option domain-name-servers 192.168.1.1,1.1.1.1;
+  option ntp-servers 192.168.1.1;
+  renew 3 2024/01/17 21:00:00;
+  expire epoch 1705572000;
+}
+"#;
+
+        let info = parse_dhclient_leases(text, Some("wlan0"), NOW).unwrap();
+        assert_eq!(info.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(info.lease_time_secs, Some(86400));
+        assert_eq!(info.renewal_time_secs, Some(43200));
+        assert_eq!(info.dns_servers, vec!["192.168.1.1", "1.1.1.1"]);
+        assert_eq!(info.ntp_servers, vec!["192.168.1.1"]);
+        assert_eq!(
+            info.lease_expires_utc.as_deref(),
+            Some("2024-01-18T10:00:00Z")
+        );
+        assert_eq!(
+            info.lease_obtained_utc.as_deref(),
+            Some("2024-01-17T10:00:00Z")
+        );
+        assert!(!info.lease_expired);
+    }
+
+    #[test]
+    fn test_parse_dhclient_leases_expired() {
+        let text = "lease {\n  interface \"eth0\";\n  expire 2 2024/01/16 10:00:00;\n}\n";
+        let info = parse_dhclient_leases(text, Some("eth0"), NOW).unwrap();
+        assert!(info.lease_expired);
+    }
+
+    #[test]
+    fn test_parse_dhclient_leases_no_match() {
+        let text = "lease {\n  interface \"eth0\";\n}\n";
+        assert!(parse_dhclient_leases(text, Some("wlan0"), NOW).is_none());
+        assert!(parse_dhclient_leases("", None, NOW).is_none());
+    }
+
+    #[test]
+    fn test_parse_macos_getpacket() {
+        let text = "op = BOOTREPLY\n\
+                    yiaddr = 192.168.1.23\n\
+                    options:\n\
+                    Options count is 7\n\
+                    dhcp_message_type (uint8): ACK 0x5\n\
+                    server_identifier (ip): 192.168.1.1\n\
+                    lease_time (uint32): 0x15180\n\
+                    renewal_t1_time_value (uint32): 0xa8c0\n\
+                    rebinding_t2_time_value (uint32): 0x12750\n\
+                    router (ip_mult): {192.168.1.1}\n\
+                    domain_name_server (ip_mult): {192.168.1.1, 8.8.8.8}\n\
+                    end (none):\n";
+
+        let info = parse_macos_getpacket(text);
+        assert_eq!(info.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(info.lease_time_secs, Some(86400));
+        assert_eq!(info.renewal_time_secs, Some(43200));
+        assert_eq!(info.rebinding_time_secs, Some(75600));
+        assert_eq!(info.dns_servers, vec!["192.168.1.1", "8.8.8.8"]);
+        assert!(info.lease_expires_utc.is_none());
+    }
+
+    #[test]
+    fn test_parse_windows_dhcp() {
+        let text = "enabled=True\n\
+                    server=192.168.1.1\n\
+                    obtained=2024-01-17T09:00:00Z\n\
+                    expires=2024-01-18T09:00:00Z\n\
+                    dns=192.168.1.1 8.8.8.8\n";
+
+        let info = parse_windows_dhcp(text, NOW);
+        assert!(info.enabled);
+        assert_eq!(info.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(info.lease_time_secs, Some(86400));
+        assert_eq!(info.dns_servers, vec!["192.168.1.1", "8.8.8.8"]);
+        assert!(!info.lease_expired);
+    }
+
+    #[test]
+    fn test_parse_windows_dhcp_static() {
+        let info = parse_windows_dhcp("enabled=False\nserver=\ndns=1.1.1.1\n", NOW);
+        assert!(!info.enabled);
+        assert!(info.lease_expires_utc.is_none());
+    }
+
+    #[test]
+    fn test_parse_windows_dhcp_apipa_server() {
+        let info = parse_windows_dhcp("enabled=True\nserver=255.255.255.255\n", NOW);
+        assert!(info.enabled);
+        assert!(info.server.is_none());
+    }
+}
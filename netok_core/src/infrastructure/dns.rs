@@ -1,16 +1,38 @@
 //! DNS configuration and detection.
 
 use crate::domain::DnsProvider;
+use crate::error::DnsError;
+
+/// Error for a command that ran but exited unsuccessfully.
+///
+/// netsh prints its errors to stdout, so fall back to that when stderr is empty.
+#[cfg(target_os = "windows")]
+fn output_error(command: &str, output: &std::process::Output) -> DnsError {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.trim().is_empty() {
+        DnsError::command(command, &String::from_utf8_lossy(&output.stdout))
+    } else {
+        DnsError::command(command, &stderr)
+    }
+}
+
+/// Error for a command that couldn't be started at all.
+#[cfg(target_os = "windows")]
+fn spawn_error(command: &'static str) -> impl FnOnce(std::io::Error) -> DnsError {
+    move |e| DnsError::CommandFailed {
+        command: command.to_string(),
+        message: e.to_string(),
+    }
+}
 
 /// Set DNS for active network adapter (both IPv4 and IPv6).
 #[cfg(target_os = "windows")]
-pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
+pub fn set_dns(provider: DnsProvider) -> Result<(), DnsError> {
     use super::adapter::get_active_adapter_name;
     use super::hidden_cmd;
 
     // Get active network adapter name
-    let adapter_name = get_active_adapter_name()
-        .ok_or_else(|| "Failed to find active network adapter".to_string())?;
+    let adapter_name = get_active_adapter_name().ok_or(DnsError::AdapterNotFound)?;
 
     match provider {
         DnsProvider::Auto => {
@@ -18,26 +40,20 @@ pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
             let output = hidden_cmd("netsh")
                 .args(["interface", "ip", "set", "dns", &adapter_name, "dhcp"])
                 .output()
-                .map_err(|e| format!("Failed to execute netsh: {}", e))?;
+                .map_err(spawn_error("netsh"))?;
 
             if !output.status.success() {
-                return Err(format!(
-                    "Failed to set IPv4 DNS to auto: {}",
-                    String::from_utf8_lossy(&output.stderr)
-                ));
+                return Err(output_error("netsh interface ip set dns dhcp", &output));
             }
 
             // Set IPv6 DNS to obtain automatically (DHCP)
             let output = hidden_cmd("netsh")
                 .args(["interface", "ipv6", "set", "dns", &adapter_name, "dhcp"])
                 .output()
-                .map_err(|e| format!("Failed to execute netsh for IPv6: {}", e))?;
+                .map_err(spawn_error("netsh"))?;
 
             if !output.status.success() {
-                return Err(format!(
-                    "Failed to set IPv6 DNS to auto: {}",
-                    String::from_utf8_lossy(&output.stderr)
-                ));
+                return Err(output_error("netsh interface ipv6 set dns dhcp", &output));
             }
         }
         _ => {
@@ -55,13 +71,10 @@ pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
                         &primary,
                     ])
                     .output()
-                    .map_err(|e| format!("Failed to execute netsh: {}", e))?;
+                    .map_err(spawn_error("netsh"))?;
 
                 if !output.status.success() {
-                    return Err(format!(
-                        "Failed to set primary IPv4 DNS: {}",
-                        String::from_utf8_lossy(&output.stderr)
-                    ));
+                    return Err(output_error("netsh interface ip set dns static", &output));
                 }
 
                 // Set secondary IPv4 DNS if available and different from primary
@@ -77,13 +90,10 @@ pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
                             "index=2",
                         ])
                         .output()
-                        .map_err(|e| format!("Failed to execute netsh: {}", e))?;
+                        .map_err(spawn_error("netsh"))?;
 
                     if !output.status.success() {
-                        return Err(format!(
-                            "Failed to set secondary IPv4 DNS: {}",
-                            String::from_utf8_lossy(&output.stderr)
-                        ));
+                        return Err(output_error("netsh interface ip add dns", &output));
                     }
                 }
             } else {
@@ -107,13 +117,10 @@ pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
                         &primary_v6,
                     ])
                     .output()
-                    .map_err(|e| format!("Failed to execute netsh for IPv6: {}", e))?;
+                    .map_err(spawn_error("netsh"))?;
 
                 if !output.status.success() {
-                    return Err(format!(
-                        "Failed to set primary IPv6 DNS: {}",
-                        String::from_utf8_lossy(&output.stderr)
-                    ));
+                    return Err(output_error("netsh interface ipv6 set dns static", &output));
                 }
 
                 // Set secondary IPv6 DNS if available and different from primary
@@ -132,13 +139,10 @@ pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
                             "index=2",
                         ])
                         .output()
-                        .map_err(|e| format!("Failed to execute netsh for IPv6: {}", e))?;
+                        .map_err(spawn_error("netsh"))?;
 
                     if !output.status.success() {
-                        return Err(format!(
-                            "Failed to set secondary IPv6 DNS: {}",
-                            String::from_utf8_lossy(&output.stderr)
-                        ));
+                        return Err(output_error("netsh interface ipv6 add dns", &output));
                     }
                 }
             } else {
@@ -154,8 +158,10 @@ pub fn set_dns(provider: DnsProvider) -> Result<(), String> {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn set_dns(_provider: DnsProvider) -> Result<(), String> {
-    Err("DNS configuration is only supported on Windows".to_string())
+pub fn set_dns(_provider: DnsProvider) -> Result<(), DnsError> {
+    Err(DnsError::UnsupportedPlatform {
+        operation: "DNS configuration",
+    })
 }
 
 /// Build netsh command lines for setting DNS without executing them.
@@ -163,11 +169,10 @@ pub fn set_dns(_provider: DnsProvider) -> Result<(), String> {
 /// Returns a list of complete command strings (e.g. `netsh interface ip set dns "Wi-Fi" dhcp`).
 /// The caller is responsible for executing them (typically with elevation on Windows).
 #[cfg(target_os = "windows")]
-pub fn build_dns_commands(provider: DnsProvider) -> Result<Vec<String>, String> {
+pub fn build_dns_commands(provider: DnsProvider) -> Result<Vec<String>, DnsError> {
     use super::adapter::get_active_adapter_name;
 
-    let adapter = get_active_adapter_name()
-        .ok_or_else(|| "Failed to find active network adapter".to_string())?;
+    let adapter = get_active_adapter_name().ok_or(DnsError::AdapterNotFound)?;
 
     let mut cmds = Vec::new();
 
@@ -224,25 +229,29 @@ pub fn build_dns_commands(provider: DnsProvider) -> Result<Vec<String>, String>
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn build_dns_commands(_provider: DnsProvider) -> Result<Vec<String>, String> {
-    Err("DNS configuration is only supported on Windows".to_string())
+pub fn build_dns_commands(_provider: DnsProvider) -> Result<Vec<String>, DnsError> {
+    Err(DnsError::UnsupportedPlatform {
+        operation: "DNS configuration",
+    })
 }
 
 /// Get current DNS servers configured on the active adapter.
 #[cfg(target_os = "windows")]
-pub fn get_current_dns() -> Result<Vec<String>, String> {
+pub fn get_current_dns() -> Result<Vec<String>, DnsError> {
     use super::adapter::get_active_adapter_name;
     use super::run_powershell;
 
-    let adapter_name = get_active_adapter_name()
-        .ok_or_else(|| "Failed to find active network adapter".to_string())?;
+    let adapter_name = get_active_adapter_name().ok_or(DnsError::AdapterNotFound)?;
 
     let command = format!(
         "Get-DnsClientServerAddress -InterfaceAlias '{}' -AddressFamily IPv4 | Select-Object -ExpandProperty ServerAddresses",
         adapter_name.replace('\'', "''")
     );
 
-    let text = run_powershell(&command).ok_or_else(|| "Failed to get DNS servers".to_string())?;
+    let text = run_powershell(&command).ok_or_else(|| DnsError::CommandFailed {
+        command: "Get-DnsClientServerAddress".to_string(),
+        message: "no output".to_string(),
+    })?;
 
     let dns_servers: Vec<String> = text
         .lines()
@@ -254,8 +263,10 @@ pub fn get_current_dns() -> Result<Vec<String>, String> {
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn get_current_dns() -> Result<Vec<String>, String> {
-    Err("DNS detection is only supported on Windows".to_string())
+pub fn get_current_dns() -> Result<Vec<String>, DnsError> {
+    Err(DnsError::UnsupportedPlatform {
+        operation: "DNS detection",
+    })
 }
 
 /// Perform a reverse DNS (PTR) lookup for an IP address.
@@ -263,8 +274,13 @@ pub fn get_current_dns() -> Result<Vec<String>, String> {
 /// Returns the hostname if found, or `None` on timeout/failure.
 /// Uses a short timeout to avoid blocking the network scan.
 pub fn reverse_dns_lookup(ip: &str, timeout_ms: u64) -> Option<String> {
+    crate::runtime::block_on(reverse_dns_lookup_async(ip, timeout_ms))
+}
+
+/// Async [`reverse_dns_lookup`].
+pub async fn reverse_dns_lookup_async(ip: &str, timeout_ms: u64) -> Option<String> {
     use trust_dns_resolver::config::*;
-    use trust_dns_resolver::Resolver;
+    use trust_dns_resolver::TokioAsyncResolver;
 
     let ip_addr: std::net::IpAddr = ip.parse().ok()?;
 
@@ -272,9 +288,9 @@ pub fn reverse_dns_lookup(ip: &str, timeout_ms: u64) -> Option<String> {
     opts.timeout = std::time::Duration::from_millis(timeout_ms);
     opts.attempts = 1;
 
-    let resolver = Resolver::new(ResolverConfig::default(), opts).ok()?;
+    let resolver = TokioAsyncResolver::tokio(ResolverConfig::default(), opts);
 
-    let response = resolver.reverse_lookup(ip_addr).ok()?;
+    let response = resolver.reverse_lookup(ip_addr).await.ok()?;
 
     response.iter().next().map(|name| {
         let s = name.to_string();
@@ -285,25 +301,24 @@ pub fn reverse_dns_lookup(ip: &str, timeout_ms: u64) -> Option<String> {
 
 /// Flush the OS DNS resolver cache.
 #[cfg(target_os = "windows")]
-pub fn flush_dns() -> Result<(), String> {
+pub fn flush_dns() -> Result<(), DnsError> {
     use super::hidden_cmd;
 
     let output = hidden_cmd("ipconfig")
         .args(["/flushdns"])
         .output()
-        .map_err(|e| format!("Failed to execute ipconfig: {}", e))?;
+        .map_err(spawn_error("ipconfig"))?;
 
     if !output.status.success() {
-        return Err(format!(
-            "ipconfig /flushdns failed: {}",
-            String::from_utf8_lossy(&output.stderr).trim()
-        ));
+        return Err(output_error("ipconfig /flushdns", &output));
     }
 
     Ok(())
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn flush_dns() -> Result<(), String> {
-    Err("DNS cache flush is only supported on Windows".to_string())
+pub fn flush_dns() -> Result<(), DnsError> {
+    Err(DnsError::UnsupportedPlatform {
+        operation: "DNS cache flush",
+    })
 }
@@ -0,0 +1,600 @@
+//! Queries to a single DNS server over plain UDP, DoH or DoT.
+//!
+//! A server is written as an IP address (`1.1.1.1`), a DoH URL template
+//! (`https://dns.google/dns-query`) or a DoT hostname (`tls://dns.google`).
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+use trust_dns_resolver::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::proto::op::ResponseCode;
+use trust_dns_resolver::TokioAsyncResolver;
+
+use super::dns_wire as wire;
+use crate::backend::{DnsConnection, ProbeFuture};
+use crate::domain::{DnsClass, DnsProvider, DnsTransport};
+use crate::error::DnsError;
+
+/// Domain resolved to check that a server answers.
+const PROBE_DOMAIN: &str = "google.com.";
+
+/// DoT port (RFC 7858).
+const DOT_PORT: u16 = 853;
+
+/// Media type of DoH request and response bodies (RFC 8484).
+const DNS_MESSAGE: &str = "application/dns-message";
+
+// ==================== Domain Types ====================
+
+/// A DNS server together with the transport used to reach it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DnsEndpoint {
+    /// Plain DNS to this address on UDP port 53.
+    Udp(IpAddr),
+    /// DoH requests POSTed to this URL.
+    Doh(String),
+    /// DoT to `hostname`, connecting to `ip` when known instead of resolving
+    /// the hostname through the system resolver.
+    Dot {
+        hostname: String,
+        ip: Option<IpAddr>,
+    },
+}
+
+impl DnsEndpoint {
+    /// Parse an IP address, a `https://` DoH URL template or a `tls://` DoT hostname.
+    pub fn parse(server: &str) -> Result<Self, DnsError> {
+        let server = server.trim();
+        if let Some(hostname) = server.strip_prefix("tls://") {
+            let hostname = hostname.trim_end_matches('/');
+            if hostname.is_empty() || hostname.contains(['/', '@', ' ']) {
+                return Err(DnsError::InvalidEndpoint(server.to_string()));
+            }
+            return Ok(DnsEndpoint::Dot {
+                hostname: hostname.to_string(),
+                ip: hostname.parse().ok(),
+            });
+        }
+        if server.starts_with("https://") {
+            let url = doh_url(server);
+            return match reqwest::Url::parse(&url) {
+                Ok(parsed) if parsed.host_str().is_some() => Ok(DnsEndpoint::Doh(url)),
+                _ => Err(DnsError::InvalidEndpoint(server.to_string())),
+            };
+        }
+        server
+            .parse()
+            .map(DnsEndpoint::Udp)
+            .map_err(|_| DnsError::InvalidAddress(server.to_string()))
+    }
+
+    pub fn transport(&self) -> DnsTransport {
+        match self {
+            DnsEndpoint::Udp(_) => DnsTransport::Udp,
+            DnsEndpoint::Doh(_) => DnsTransport::Doh,
+            DnsEndpoint::Dot { .. } => DnsTransport::Dot,
+        }
+    }
+}
+
+impl fmt::Display for DnsEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsEndpoint::Udp(ip) => write!(f, "{}", ip),
+            DnsEndpoint::Doh(url) => f.write_str(url),
+            DnsEndpoint::Dot { hostname, .. } => write!(f, "tls://{}", hostname),
+        }
+    }
+}
+
+/// How one transport of a provider fared on this network.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DnsTransportStatus {
+    pub transport: DnsTransport,
+    /// Server as accepted by `ping_dns_server`.
+    pub endpoint: String,
+    /// Whether the server answered over this transport.
+    pub works: bool,
+    /// Round-trip time of the answer in milliseconds.
+    pub latency_ms: Option<u64>,
+}
+
+// ==================== Endpoints ====================
+
+/// DoH URL from an RFC 8484 template: the `{?dns}` variable is dropped since
+/// queries are POSTed.
+fn doh_url(template: &str) -> String {
+    match template.find('{') {
+        Some(start) => template[..start].to_string(),
+        None => template.to_string(),
+    }
+}
+
+/// One endpoint per transport `provider` supports, in [`DnsProvider::transports`] order.
+///
+/// Built-in providers' DoT servers are reached at their primary address, so
+/// the check doesn't depend on the system resolver.
+pub fn provider_endpoints(provider: &DnsProvider) -> Vec<DnsEndpoint> {
+    let primary_ip = provider.primary().and_then(|ip| ip.parse().ok());
+    let mut endpoints = Vec::new();
+    if let Some(ip) = primary_ip {
+        endpoints.push(DnsEndpoint::Udp(ip));
+    }
+    if let Some(template) = provider.doh_template() {
+        endpoints.push(DnsEndpoint::Doh(doh_url(&template)));
+    }
+    if let Some(hostname) = provider.dot_hostname() {
+        let ip = match provider {
+            DnsProvider::Custom(..) => hostname.parse().ok(),
+            _ => primary_ip,
+        };
+        endpoints.push(DnsEndpoint::Dot { hostname, ip });
+    }
+    endpoints
+}
+
+// ==================== Queries ====================
+
+/// Local address to send from: an IPv6 server needs an IPv6 socket.
+fn bind_addr(server: IpAddr) -> SocketAddr {
+    match server {
+        IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED), 0),
+        IpAddr::V6(_) => SocketAddr::new(IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED), 0),
+    }
+}
+
+/// Resolver that asks only `socket_addr`, once per query, without caching.
+fn single_server_resolver(
+    socket_addr: SocketAddr,
+    protocol: Protocol,
+    tls_dns_name: Option<String>,
+    timeout: Duration,
+) -> TokioAsyncResolver {
+    let mut opts = ResolverOpts::default();
+    opts.timeout = timeout;
+    opts.attempts = 1; // Only try once
+    opts.cache_size = 0; // Every query goes to the server
+
+    let name_server = NameServerConfig {
+        socket_addr,
+        protocol,
+        tls_dns_name,
+        trust_negative_responses: true,
+        tls_config: None,
+        bind_addr: Some(bind_addr(socket_addr.ip())),
+    };
+
+    let config = ResolverConfig::from_parts(None, vec![], vec![name_server]);
+    TokioAsyncResolver::tokio(config, opts)
+}
+
+/// Client for one endpoint. DoT and DoH connections are reused across queries.
+pub(crate) enum DnsClient {
+    Resolver(Box<TokioAsyncResolver>),
+    Doh {
+        client: reqwest::Client,
+        url: String,
+    },
+}
+
+impl DnsClient {
+    /// `None` if a DoT hostname doesn't resolve or the HTTP client can't be built.
+    pub(crate) async fn connect(endpoint: &DnsEndpoint, timeout: Duration) -> Option<Self> {
+        match endpoint {
+            DnsEndpoint::Udp(ip) => Some(DnsClient::Resolver(Box::new(single_server_resolver(
+                SocketAddr::new(*ip, 53),
+                Protocol::Udp,
+                None,
+                timeout,
+            )))),
+            DnsEndpoint::Doh(url) => Some(DnsClient::Doh {
+                client: reqwest::Client::builder().timeout(timeout).build().ok()?,
+                url: url.clone(),
+            }),
+            DnsEndpoint::Dot { hostname, ip } => {
+                let ip = match ip {
+                    Some(ip) => *ip,
+                    None => tokio::net::lookup_host((hostname.as_str(), DOT_PORT))
+                        .await
+                        .ok()?
+                        .next()?
+                        .ip(),
+                };
+                Some(DnsClient::Resolver(Box::new(single_server_resolver(
+                    SocketAddr::new(ip, DOT_PORT),
+                    Protocol::Tls,
+                    Some(hostname.clone()),
+                    timeout,
+                ))))
+            }
+        }
+    }
+
+    /// Ask for the A records of `name`. `true` if the server answered in time,
+    /// including "no such name".
+    pub(crate) async fn lookup(&self, name: &str) -> bool {
+        match self {
+            DnsClient::Resolver(resolver) => match resolver.ipv4_lookup(name).await {
+                Ok(_) => true,
+                Err(e) => matches!(
+                    e.kind(),
+                    ResolveErrorKind::NoRecordsFound {
+                        response_code: ResponseCode::NoError | ResponseCode::NXDomain,
+                        ..
+                    }
+                ),
+            },
+            DnsClient::Doh { client, url } => query_doh(client, url, name).await.unwrap_or(false),
+        }
+    }
+}
+
+impl DnsConnection for DnsClient {
+    fn timed_lookup<'a>(&'a self, name: &'a str) -> ProbeFuture<'a, Option<f64>> {
+        Box::pin(async move {
+            let start = Instant::now();
+            self.lookup(name)
+                .await
+                .then(|| start.elapsed().as_secs_f64() * 1000.0)
+        })
+    }
+}
+
+/// Wire form of a recursive query. `None` if `name` isn't a domain.
+fn build_query(name: &str, record_type: u16, class: u16, id: u16) -> Option<Vec<u8>> {
+    wire::Message::query(id, wire::Name::parse(name).ok()?, record_type, class)
+        .encode()
+        .ok()
+}
+
+/// POST an A query for `name` to a DoH server. `None` on any failure.
+async fn query_doh(client: &reqwest::Client, url: &str, name: &str) -> Option<bool> {
+    // RFC 8484 §4.1: id 0 keeps responses cacheable
+    let body = build_query(name, wire::TYPE_A, wire::CLASS_IN, 0)?;
+    let answer = exchange_doh(client, url, body).await?;
+    Some(matches!(
+        answer.rcode,
+        wire::Rcode::NO_ERROR | wire::Rcode::NX_DOMAIN
+    ))
+}
+
+/// POST one wire-format query to a DoH server. `None` on any failure,
+/// including an HTTP error status.
+async fn exchange_doh(client: &reqwest::Client, url: &str, body: Vec<u8>) -> Option<wire::Message> {
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, DNS_MESSAGE)
+        .header(reqwest::header::ACCEPT, DNS_MESSAGE)
+        .body(body)
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    wire::Message::decode(&response.bytes().await.ok()?).ok()
+}
+
+/// Resolve `name` to its A and AAAA addresses through the DoH server at
+/// `url`. Empty if the server fails, refuses or has no addresses.
+pub(crate) async fn resolve_doh(url: &str, name: &str, timeout: Duration) -> Vec<String> {
+    let Ok(client) = reqwest::Client::builder().timeout(timeout).build() else {
+        return vec![];
+    };
+    let lookup = |record_type| {
+        let client = &client;
+        async move {
+            let body = build_query(name, record_type, wire::CLASS_IN, 0)?;
+            exchange_doh(client, url, body).await
+        }
+    };
+    let (v4, v6) = tokio::join!(lookup(wire::TYPE_A), lookup(wire::TYPE_AAAA));
+    [v4, v6]
+        .into_iter()
+        .flatten()
+        .flat_map(|answer| answer.addresses())
+        .map(|ip| ip.to_string())
+        .collect()
+}
+
+/// Random DNS transaction id, so off-path spoofed answers have to guess it.
+pub(crate) fn random_id() -> u16 {
+    RandomState::new().build_hasher().finish() as u16
+}
+
+/// Send an A query for `name` to `server` on UDP port 53 and return the
+/// response code, which a resolver library would turn into an error. `None`
+/// if nothing answered in time.
+pub(crate) async fn query_udp(
+    server: IpAddr,
+    name: &str,
+    timeout: Duration,
+) -> Option<wire::Rcode> {
+    let server = SocketAddr::new(server, 53);
+    exchange_udp(server, name, wire::TYPE_A, wire::CLASS_IN, timeout)
+        .await
+        .map(|answer| answer.rcode)
+}
+
+/// Send one query to `server` over UDP and return the whole response, for
+/// records a resolver library won't ask for (CHAOS class, raw TXT). `None` if
+/// nothing answered in time.
+pub(crate) async fn exchange_udp(
+    server: SocketAddr,
+    name: &str,
+    record_type: u16,
+    class: u16,
+    timeout: Duration,
+) -> Option<wire::Message> {
+    let query = wire::Message::query(
+        random_id(),
+        wire::Name::parse(name).ok()?,
+        record_type,
+        class,
+    );
+    exchange_udp_raw(server, &query, timeout)
+        .await
+        .map(|(answer, _)| answer)
+}
+
+/// TXT strings for `name` in `class` from `server` ("ip:port") over UDP.
+/// Empty if the address is invalid or nothing answered in time.
+pub(crate) async fn query_txt(
+    server: &str,
+    name: &str,
+    class: DnsClass,
+    timeout: Duration,
+) -> Vec<String> {
+    let Ok(server) = server.parse() else {
+        return vec![];
+    };
+    let class = match class {
+        DnsClass::Internet => wire::CLASS_IN,
+        DnsClass::Chaos => wire::CLASS_CH,
+    };
+    let Some(answer) = exchange_udp(server, name, wire::TYPE_TXT, class, timeout).await else {
+        return vec![];
+    };
+    answer
+        .answers
+        .iter()
+        .filter_map(|record| match &record.data {
+            wire::RecordData::Txt(parts) => Some(
+                parts
+                    .iter()
+                    .map(|part| String::from_utf8_lossy(part))
+                    .collect::<String>(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Send `query` to `server` over UDP and wait for its response, returned
+/// decoded and as received. The query goes out from a fresh socket (a random
+/// source port) connected to `server`, and only a response from there with
+/// the query's id and question is accepted, so stray or spoofed datagrams
+/// are skipped. `None` if nothing answered in time.
+pub(crate) async fn exchange_udp_raw(
+    server: SocketAddr,
+    query: &wire::Message,
+    timeout: Duration,
+) -> Option<(wire::Message, Vec<u8>)> {
+    let bytes = query.encode().ok()?;
+    let socket = UdpSocket::bind(bind_addr(server.ip())).await.ok()?;
+    socket.connect(server).await.ok()?;
+
+    let exchange = async {
+        socket.send(&bytes).await.ok()?;
+        let mut buf = [0u8; 4096];
+        loop {
+            let len = socket.recv(&mut buf).await.ok()?;
+            // Skip stray datagrams, e.g. a late answer to an earlier socket
+            match wire::Message::decode(&buf[..len]) {
+                Ok(answer) if answer.answers_query(query) => {
+                    return Some((answer, buf[..len].to_vec()));
+                }
+                _ => continue,
+            }
+        }
+    };
+    tokio::time::timeout(timeout, exchange).await.ok().flatten()
+}
+
+/// Resolve [`PROBE_DOMAIN`] through `endpoint`. `true` if it answered in time.
+async fn query(endpoint: &DnsEndpoint, timeout: Duration) -> bool {
+    match DnsClient::connect(endpoint, timeout).await {
+        Some(client) => client.lookup(PROBE_DOMAIN).await,
+        None => false,
+    }
+}
+
+/// Test if a DNS server answers. `server` is an IP, DoH URL or `tls://` hostname.
+/// Returns Ok(true) if it responds, Ok(false) if not, Err on an invalid server.
+pub fn test_dns_server(server: &str, timeout_secs: u64) -> Result<bool, DnsError> {
+    crate::runtime::block_on(test_dns_server_async(server, timeout_secs))
+}
+
+/// Async [`test_dns_server`].
+pub async fn test_dns_server_async(server: &str, timeout_secs: u64) -> Result<bool, DnsError> {
+    let endpoint = DnsEndpoint::parse(server)?;
+    Ok(query(&endpoint, Duration::from_secs(timeout_secs)).await)
+}
+
+/// Measure DNS server latency by resolving a domain and timing the query.
+/// Returns Ok(Some(ms)) on success, Ok(None) if unreachable, Err on an invalid server.
+pub fn ping_dns_server(server: &str, timeout_secs: u64) -> Result<Option<u64>, DnsError> {
+    crate::runtime::block_on(ping_dns_server_async(server, timeout_secs))
+}
+
+/// Async [`ping_dns_server`].
+pub async fn ping_dns_server_async(
+    server: &str,
+    timeout_secs: u64,
+) -> Result<Option<u64>, DnsError> {
+    let endpoint = DnsEndpoint::parse(server)?;
+    Ok(ping_endpoint(&endpoint, Duration::from_secs(timeout_secs)).await)
+}
+
+async fn ping_endpoint(endpoint: &DnsEndpoint, timeout: Duration) -> Option<u64> {
+    let start = Instant::now();
+    query(endpoint, timeout)
+        .await
+        .then(|| start.elapsed().as_millis() as u64)
+}
+
+/// Check every transport `provider` supports, concurrently.
+pub fn check_dns_transports(provider: &DnsProvider, timeout_secs: u64) -> Vec<DnsTransportStatus> {
+    crate::runtime::block_on(check_dns_transports_async(provider, timeout_secs))
+}
+
+/// Async [`check_dns_transports`].
+pub async fn check_dns_transports_async(
+    provider: &DnsProvider,
+    timeout_secs: u64,
+) -> Vec<DnsTransportStatus> {
+    let timeout = Duration::from_secs(timeout_secs);
+    join_all(
+        provider_endpoints(provider)
+            .into_iter()
+            .map(|endpoint| async move {
+                let latency_ms = ping_endpoint(&endpoint, timeout).await;
+                DnsTransportStatus {
+                    transport: endpoint.transport(),
+                    endpoint: endpoint.to_string(),
+                    works: latency_ms.is_some(),
+                    latency_ms,
+                }
+            }),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoints() {
+        assert_eq!(
+            DnsEndpoint::parse("1.1.1.1").unwrap(),
+            DnsEndpoint::Udp("1.1.1.1".parse().unwrap())
+        );
+        assert_eq!(
+            DnsEndpoint::parse("https://dns.example/dns-query{?dns}").unwrap(),
+            DnsEndpoint::Doh("https://dns.example/dns-query".to_string())
+        );
+        assert_eq!(
+            DnsEndpoint::parse("tls://dns.google").unwrap(),
+            DnsEndpoint::Dot {
+                hostname: "dns.google".to_string(),
+                ip: None
+            }
+        );
+        assert_eq!(
+            DnsEndpoint::parse("tls://9.9.9.9").unwrap(),
+            DnsEndpoint::Dot {
+                hostname: "9.9.9.9".to_string(),
+                ip: Some("9.9.9.9".parse().unwrap())
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_endpoints() {
+        assert!(matches!(
+            DnsEndpoint::parse("not-an-ip"),
+            Err(DnsError::InvalidAddress(_))
+        ));
+        assert!(matches!(
+            DnsEndpoint::parse("tls://"),
+            Err(DnsError::InvalidEndpoint(_))
+        ));
+        assert!(matches!(
+            DnsEndpoint::parse("https://"),
+            Err(DnsError::InvalidEndpoint(_))
+        ));
+    }
+
+    #[test]
+    fn test_endpoint_display_round_trips() {
+        for server in [
+            "8.8.8.8",
+            "https://dns.google/dns-query",
+            "tls://dns.google",
+        ] {
+            let endpoint = DnsEndpoint::parse(server).unwrap();
+            assert_eq!(endpoint.to_string(), server);
+            assert_eq!(DnsEndpoint::parse(&endpoint.to_string()).unwrap(), endpoint);
+        }
+    }
+
+    #[test]
+    fn test_provider_endpoints() {
+        let endpoints = provider_endpoints(&DnsProvider::Catalog("Cloudflare".into()));
+        assert_eq!(
+            endpoints,
+            vec![
+                DnsEndpoint::Udp("1.1.1.1".parse().unwrap()),
+                DnsEndpoint::Doh("https://cloudflare-dns.com/dns-query".to_string()),
+                DnsEndpoint::Dot {
+                    hostname: "one.one.one.one".to_string(),
+                    ip: Some("1.1.1.1".parse().unwrap())
+                },
+            ]
+        );
+        let transports: Vec<_> = endpoints.iter().map(DnsEndpoint::transport).collect();
+        assert_eq!(
+            transports,
+            DnsProvider::Catalog("Cloudflare".into()).transports()
+        );
+
+        // A custom DoT hostname isn't assumed to live at the custom IP
+        let custom = DnsProvider::Custom(
+            "192.0.2.1".to_string(),
+            String::new(),
+            None,
+            None,
+            None,
+            Some("dns.example".to_string()),
+        );
+        assert_eq!(
+            provider_endpoints(&custom)[1],
+            DnsEndpoint::Dot {
+                hostname: "dns.example".to_string(),
+                ip: None
+            }
+        );
+        assert!(provider_endpoints(&DnsProvider::Auto).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ping_dns_server_async_rejects_invalid_address() {
+        let result = ping_dns_server_async("not-an-ip", 1).await;
+        assert!(matches!(result, Err(DnsError::InvalidAddress(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_transports_do_not_work() {
+        // Nothing listens on TEST-NET-1 (RFC 5737) or the discard port
+        let custom = DnsProvider::Custom(
+            "192.0.2.1".to_string(),
+            String::new(),
+            None,
+            None,
+            Some("https://127.0.0.1:9/dns-query".to_string()),
+            Some("192.0.2.1".to_string()),
+        );
+        let statuses = check_dns_transports_async(&custom, 1).await;
+        assert_eq!(statuses.len(), 3);
+        assert!(statuses.iter().all(|s| !s.works && s.latency_ms.is_none()));
+        assert_eq!(statuses[1].endpoint, "https://127.0.0.1:9/dns-query");
+    }
+}
@@ -0,0 +1,1019 @@
+//! DNS message encoding and decoding (RFC 1035, EDNS from RFC 6891).
+//!
+//! Covers what the raw queries need: header flags and response code, all
+//! four sections, name compression, A/AAAA/CNAME/TXT/PTR/SOA records and the
+//! OPT pseudo-record. Other record types keep their RDATA as raw bytes.
+//! Decoding never panics on malformed input; it returns a [`WireError`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub(crate) const TYPE_A: u16 = 1;
+pub(crate) const TYPE_CNAME: u16 = 5;
+pub(crate) const TYPE_SOA: u16 = 6;
+pub(crate) const TYPE_PTR: u16 = 12;
+pub(crate) const TYPE_TXT: u16 = 16;
+pub(crate) const TYPE_AAAA: u16 = 28;
+const TYPE_OPT: u16 = 41;
+
+pub(crate) const CLASS_IN: u16 = 1;
+/// CHAOS class, for server identity names like `id.server.`.
+pub(crate) const CLASS_CH: u16 = 3;
+
+/// UDP payload size advertised in queries (the DNS Flag Day 2020 value), and
+/// so the largest response to expect.
+pub(crate) const EDNS_PAYLOAD_SIZE: u16 = 1232;
+
+const MAX_LABEL_LEN: usize = 63;
+const MAX_NAME_LEN: usize = 255;
+/// Compression pointers followed for one name; a real name needs at most one
+/// per label.
+const MAX_POINTERS: usize = MAX_NAME_LEN / 2;
+
+/// Why a message couldn't be decoded or encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum WireError {
+    #[error("message ends early")]
+    Truncated,
+    #[error("compression pointer loops or points forward")]
+    BadPointer,
+    #[error("unsupported label type")]
+    BadLabel,
+    #[error("empty or too long label or name")]
+    BadName,
+    #[error("record data does not match its type")]
+    BadRdata,
+    #[error("more than one OPT record")]
+    DuplicateOpt,
+    #[error("extended response code without an OPT record")]
+    ExtendedRcode,
+    #[error("section or field too large to encode")]
+    TooLarge,
+}
+
+// ==================== Names ====================
+
+/// A domain name as labels. Compares case-insensitively, like DNS does.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Name {
+    labels: Vec<Vec<u8>>,
+}
+
+impl Name {
+    /// The root name (`.`).
+    pub(crate) fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parse a dotted name; the trailing dot is optional.
+    pub(crate) fn parse(name: &str) -> Result<Self, WireError> {
+        let name = name.strip_suffix('.').unwrap_or(name);
+        if name.is_empty() {
+            return Ok(Self::root());
+        }
+        Self::from_labels(name.split('.').map(|l| l.as_bytes().to_vec()).collect())
+    }
+
+    fn from_labels(labels: Vec<Vec<u8>>) -> Result<Self, WireError> {
+        if labels
+            .iter()
+            .any(|l| l.is_empty() || l.len() > MAX_LABEL_LEN)
+        {
+            return Err(WireError::BadName);
+        }
+        let name = Self { labels };
+        if name.wire_len() > MAX_NAME_LEN {
+            return Err(WireError::BadName);
+        }
+        Ok(name)
+    }
+
+    fn is_root(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Uncompressed length on the wire.
+    fn wire_len(&self) -> usize {
+        self.labels.iter().map(|l| l.len() + 1).sum::<usize>() + 1
+    }
+
+    /// Lowercased labels from the `from`th on, the key suffixes are
+    /// compressed by.
+    fn suffix_key(&self, from: usize) -> Vec<Vec<u8>> {
+        self.labels[from..]
+            .iter()
+            .map(|l| l.to_ascii_lowercase())
+            .collect()
+    }
+}
+
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        self.labels.len() == other.labels.len()
+            && self
+                .labels
+                .iter()
+                .zip(&other.labels)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+}
+
+impl Eq for Name {}
+
+impl fmt::Display for Name {
+    /// Dotted form without the trailing dot; `.` for the root. Dots and
+    /// backslashes inside labels are escaped, other unprintable bytes
+    /// written as `\DDD`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return f.write_str(".");
+        }
+        for (i, label) in self.labels.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            for &b in label {
+                match b {
+                    b'.' | b'\\' => write!(f, "\\{}", b as char)?,
+                    0x21..=0x7e => write!(f, "{}", b as char)?,
+                    _ => write!(f, "\\{:03}", b)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// ==================== Message ====================
+
+/// Header fields other than the id, counts and response code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub id: u16,
+    /// QR: a response rather than a query.
+    pub response: bool,
+    pub opcode: u8,
+    pub authoritative: bool,
+    pub truncated: bool,
+    pub recursion_desired: bool,
+    pub recursion_available: bool,
+    /// AD: the resolver validated the answer with DNSSEC.
+    pub authentic_data: bool,
+    /// CD: the asker doesn't want DNSSEC validation.
+    pub checking_disabled: bool,
+}
+
+/// Response code, including the upper bits carried by an OPT record.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Rcode(pub u16);
+
+impl Rcode {
+    pub(crate) const NO_ERROR: Self = Self(0);
+    pub(crate) const SERV_FAIL: Self = Self(2);
+    pub(crate) const NX_DOMAIN: Self = Self(3);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Question {
+    pub name: Name,
+    pub qtype: u16,
+    pub qclass: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Soa {
+    pub mname: Name,
+    pub rname: Name,
+    pub serial: u32,
+    pub refresh: u32,
+    pub retry: u32,
+    pub expire: u32,
+    pub minimum: u32,
+}
+
+/// Record data, decoded for the types the checks read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RecordData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Cname(Name),
+    Ptr(Name),
+    /// Character strings, as sent.
+    Txt(Vec<Vec<u8>>),
+    Soa(Soa),
+    Other {
+        rtype: u16,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Record {
+    pub name: Name,
+    pub class: u16,
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+impl Record {
+    pub(crate) fn rtype(&self) -> u16 {
+        match &self.data {
+            RecordData::A(_) => TYPE_A,
+            RecordData::Aaaa(_) => TYPE_AAAA,
+            RecordData::Cname(_) => TYPE_CNAME,
+            RecordData::Ptr(_) => TYPE_PTR,
+            RecordData::Txt(_) => TYPE_TXT,
+            RecordData::Soa(_) => TYPE_SOA,
+            RecordData::Other { rtype, .. } => *rtype,
+        }
+    }
+}
+
+/// EDNS parameters from the OPT pseudo-record.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Edns {
+    pub payload_size: u16,
+    pub version: u8,
+    /// DO: the sender understands DNSSEC records.
+    pub dnssec_ok: bool,
+    /// Options as (code, data).
+    pub options: Vec<(u16, Vec<u8>)>,
+}
+
+impl Default for Edns {
+    fn default() -> Self {
+        Self {
+            payload_size: EDNS_PAYLOAD_SIZE,
+            version: 0,
+            dnssec_ok: false,
+            options: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Message {
+    pub header: Header,
+    pub rcode: Rcode,
+    pub questions: Vec<Question>,
+    pub answers: Vec<Record>,
+    pub authority: Vec<Record>,
+    /// Additional records other than OPT, which is [`Message::edns`].
+    pub additional: Vec<Record>,
+    pub edns: Option<Edns>,
+}
+
+impl Message {
+    /// A recursive query for `name` with a single question, advertising
+    /// [`EDNS_PAYLOAD_SIZE`].
+    pub(crate) fn query(id: u16, name: Name, qtype: u16, qclass: u16) -> Self {
+        Self {
+            header: Header {
+                id,
+                recursion_desired: true,
+                ..Header::default()
+            },
+            questions: vec![Question {
+                name,
+                qtype,
+                qclass,
+            }],
+            edns: Some(Edns::default()),
+            ..Self::default()
+        }
+    }
+
+    /// Whether this is a response to `query`: same id and question, QR set.
+    pub(crate) fn answers_query(&self, query: &Message) -> bool {
+        self.header.response
+            && self.header.id == query.header.id
+            && self.questions == query.questions
+    }
+
+    /// A and AAAA addresses in the answer section, in order.
+    pub(crate) fn addresses(&self) -> Vec<IpAddr> {
+        self.answers
+            .iter()
+            .filter_map(|record| match record.data {
+                RecordData::A(ip) => Some(IpAddr::V4(ip)),
+                RecordData::Aaaa(ip) => Some(IpAddr::V6(ip)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Wire form, with names compressed.
+    pub(crate) fn encode(&self) -> Result<Vec<u8>, WireError> {
+        let extended_rcode =
+            u8::try_from(self.rcode.0 >> 4).map_err(|_| WireError::ExtendedRcode)?;
+        if extended_rcode != 0 && self.edns.is_none() {
+            return Err(WireError::ExtendedRcode);
+        }
+
+        let mut e = Encoder::default();
+        let h = &self.header;
+        e.u16(h.id);
+        e.buf.push(
+            u8::from(h.response) << 7
+                | (h.opcode & 0x0f) << 3
+                | u8::from(h.authoritative) << 2
+                | u8::from(h.truncated) << 1
+                | u8::from(h.recursion_desired),
+        );
+        e.buf.push(
+            u8::from(h.recursion_available) << 7
+                | u8::from(h.authentic_data) << 5
+                | u8::from(h.checking_disabled) << 4
+                | (self.rcode.0 & 0x0f) as u8,
+        );
+        let additional = self.additional.len() + usize::from(self.edns.is_some());
+        for count in [
+            self.questions.len(),
+            self.answers.len(),
+            self.authority.len(),
+            additional,
+        ] {
+            e.u16(u16::try_from(count).map_err(|_| WireError::TooLarge)?);
+        }
+
+        for question in &self.questions {
+            e.name(&question.name);
+            e.u16(question.qtype);
+            e.u16(question.qclass);
+        }
+        for record in self
+            .answers
+            .iter()
+            .chain(&self.authority)
+            .chain(&self.additional)
+        {
+            e.record(record)?;
+        }
+        if let Some(edns) = &self.edns {
+            e.opt(edns, extended_rcode)?;
+        }
+        Ok(e.buf)
+    }
+
+    /// Parse a whole message. Bytes after the last record are ignored.
+    pub(crate) fn decode(data: &[u8]) -> Result<Self, WireError> {
+        let mut d = Decoder { data, pos: 0 };
+        let id = d.u16()?;
+        let flags = d.u16()?;
+        let counts = [d.u16()?, d.u16()?, d.u16()?, d.u16()?];
+        let header = Header {
+            id,
+            response: flags & 0x8000 != 0,
+            opcode: ((flags >> 11) & 0x0f) as u8,
+            authoritative: flags & 0x0400 != 0,
+            truncated: flags & 0x0200 != 0,
+            recursion_desired: flags & 0x0100 != 0,
+            recursion_available: flags & 0x0080 != 0,
+            authentic_data: flags & 0x0020 != 0,
+            checking_disabled: flags & 0x0010 != 0,
+        };
+        let mut message = Message {
+            header,
+            rcode: Rcode(flags & 0x000f),
+            ..Message::default()
+        };
+
+        for _ in 0..counts[0] {
+            message.questions.push(Question {
+                name: d.name()?,
+                qtype: d.u16()?,
+                qclass: d.u16()?,
+            });
+        }
+        for _ in 0..counts[1] {
+            message.answers.push(d.record()?.into_record()?);
+        }
+        for _ in 0..counts[2] {
+            message.authority.push(d.record()?.into_record()?);
+        }
+        for _ in 0..counts[3] {
+            let raw = d.record()?;
+            if raw.rtype != TYPE_OPT {
+                message.additional.push(raw.into_record()?);
+                continue;
+            }
+            if message.edns.is_some() {
+                return Err(WireError::DuplicateOpt);
+            }
+            if !raw.name.is_root() {
+                return Err(WireError::BadRdata);
+            }
+            message.rcode.0 |= ((raw.ttl >> 24) as u16) << 4;
+            message.edns = Some(Edns {
+                payload_size: raw.class,
+                version: (raw.ttl >> 16) as u8,
+                dnssec_ok: raw.ttl & 0x8000 != 0,
+                options: options(raw.rdata)?,
+            });
+        }
+        Ok(message)
+    }
+}
+
+// ==================== Encoding ====================
+
+#[derive(Default)]
+struct Encoder {
+    buf: Vec<u8>,
+    /// Offsets of name suffixes already written, for compression.
+    names: HashMap<Vec<Vec<u8>>, u16>,
+}
+
+impl Encoder {
+    fn u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Write `name`, pointing at an earlier copy of its longest known suffix.
+    fn name(&mut self, name: &Name) {
+        for (i, label) in name.labels.iter().enumerate() {
+            let key = name.suffix_key(i);
+            if let Some(&offset) = self.names.get(&key) {
+                self.u16(0xc000 | offset);
+                return;
+            }
+            // Pointers only have 14 bits of offset
+            if let Ok(offset @ 0..=0x3fff) = u16::try_from(self.buf.len()) {
+                self.names.insert(key, offset);
+            }
+            self.buf.push(label.len() as u8);
+            self.buf.extend_from_slice(label);
+        }
+        self.buf.push(0);
+    }
+
+    /// Write a resource record whose RDATA `rdata` writes.
+    fn resource(
+        &mut self,
+        name: &Name,
+        rtype: u16,
+        class: u16,
+        ttl: u32,
+        rdata: impl FnOnce(&mut Self) -> Result<(), WireError>,
+    ) -> Result<(), WireError> {
+        self.name(name);
+        self.u16(rtype);
+        self.u16(class);
+        self.u32(ttl);
+        let length_at = self.buf.len();
+        self.u16(0);
+        rdata(self)?;
+        let length =
+            u16::try_from(self.buf.len() - length_at - 2).map_err(|_| WireError::TooLarge)?;
+        self.buf[length_at..length_at + 2].copy_from_slice(&length.to_be_bytes());
+        Ok(())
+    }
+
+    fn record(&mut self, record: &Record) -> Result<(), WireError> {
+        self.resource(
+            &record.name,
+            record.rtype(),
+            record.class,
+            record.ttl,
+            |e| {
+                match &record.data {
+                    RecordData::A(ip) => e.buf.extend_from_slice(&ip.octets()),
+                    RecordData::Aaaa(ip) => e.buf.extend_from_slice(&ip.octets()),
+                    RecordData::Cname(name) | RecordData::Ptr(name) => e.name(name),
+                    RecordData::Txt(strings) => {
+                        for s in strings {
+                            e.buf
+                                .push(u8::try_from(s.len()).map_err(|_| WireError::TooLarge)?);
+                            e.buf.extend_from_slice(s);
+                        }
+                    }
+                    RecordData::Soa(soa) => {
+                        e.name(&soa.mname);
+                        e.name(&soa.rname);
+                        for value in [soa.serial, soa.refresh, soa.retry, soa.expire, soa.minimum] {
+                            e.u32(value);
+                        }
+                    }
+                    RecordData::Other { data, .. } => e.buf.extend_from_slice(data),
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn opt(&mut self, edns: &Edns, extended_rcode: u8) -> Result<(), WireError> {
+        let ttl = u32::from(extended_rcode) << 24
+            | u32::from(edns.version) << 16
+            | if edns.dnssec_ok { 0x8000 } else { 0 };
+        self.resource(&Name::root(), TYPE_OPT, edns.payload_size, ttl, |e| {
+            for (code, data) in &edns.options {
+                e.u16(*code);
+                e.u16(u16::try_from(data.len()).map_err(|_| WireError::TooLarge)?);
+                e.buf.extend_from_slice(data);
+            }
+            Ok(())
+        })
+    }
+}
+
+// ==================== Decoding ====================
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+/// A record before its RDATA is interpreted.
+struct RawRecord<'a> {
+    name: Name,
+    rtype: u16,
+    class: u16,
+    ttl: u32,
+    /// The whole message, for compressed names inside the RDATA.
+    message: &'a [u8],
+    rdata_start: usize,
+    rdata: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], WireError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(WireError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, WireError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, WireError> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, WireError> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Read a possibly compressed name. Each pointer must point before the
+    /// labels read so far, so following them always ends.
+    fn name(&mut self) -> Result<Name, WireError> {
+        let mut labels = Vec::new();
+        let mut len = 1;
+        let mut pos = self.pos;
+        let mut limit = self.pos;
+        let mut end = None;
+        let mut pointers = 0;
+        loop {
+            let b = *self.data.get(pos).ok_or(WireError::Truncated)?;
+            match b & 0xc0 {
+                0x00 if b == 0 => break,
+                0x00 => {
+                    let label = self
+                        .data
+                        .get(pos + 1..pos + 1 + b as usize)
+                        .ok_or(WireError::Truncated)?;
+                    len += label.len() + 1;
+                    if len > MAX_NAME_LEN {
+                        return Err(WireError::BadName);
+                    }
+                    labels.push(label.to_vec());
+                    pos += 1 + label.len();
+                }
+                0xc0 => {
+                    let low = *self.data.get(pos + 1).ok_or(WireError::Truncated)?;
+                    let target = usize::from(b & 0x3f) << 8 | usize::from(low);
+                    pointers += 1;
+                    if target >= limit || pointers > MAX_POINTERS {
+                        return Err(WireError::BadPointer);
+                    }
+                    end.get_or_insert(pos + 2);
+                    limit = target;
+                    pos = target;
+                }
+                _ => return Err(WireError::BadLabel),
+            }
+        }
+        self.pos = end.unwrap_or(pos + 1);
+        Ok(Name { labels })
+    }
+
+    fn record(&mut self) -> Result<RawRecord<'a>, WireError> {
+        let name = self.name()?;
+        let rtype = self.u16()?;
+        let class = self.u16()?;
+        let ttl = self.u32()?;
+        let length = self.u16()?;
+        let rdata_start = self.pos;
+        let rdata = self.bytes(usize::from(length))?;
+        Ok(RawRecord {
+            name,
+            rtype,
+            class,
+            ttl,
+            message: self.data,
+            rdata_start,
+            rdata,
+        })
+    }
+}
+
+impl RawRecord<'_> {
+    fn into_record(self) -> Result<Record, WireError> {
+        let rdata_end = self.rdata_start + self.rdata.len();
+        let mut d = Decoder {
+            data: self.message,
+            pos: self.rdata_start,
+        };
+        let data = match self.rtype {
+            TYPE_A => RecordData::A(Ipv4Addr::from(
+                <[u8; 4]>::try_from(self.rdata).map_err(|_| WireError::BadRdata)?,
+            )),
+            TYPE_AAAA => RecordData::Aaaa(Ipv6Addr::from(
+                <[u8; 16]>::try_from(self.rdata).map_err(|_| WireError::BadRdata)?,
+            )),
+            TYPE_CNAME => RecordData::Cname(d.name()?),
+            TYPE_PTR => RecordData::Ptr(d.name()?),
+            TYPE_SOA => RecordData::Soa(Soa {
+                mname: d.name()?,
+                rname: d.name()?,
+                serial: d.u32()?,
+                refresh: d.u32()?,
+                retry: d.u32()?,
+                expire: d.u32()?,
+                minimum: d.u32()?,
+            }),
+            TYPE_TXT => {
+                let mut strings = Vec::new();
+                while d.pos < rdata_end {
+                    let len = d.u8()?;
+                    strings.push(d.bytes(usize::from(len))?.to_vec());
+                }
+                RecordData::Txt(strings)
+            }
+            rtype => RecordData::Other {
+                rtype,
+                data: self.rdata.to_vec(),
+            },
+        };
+        let parsed_to_end = match data {
+            RecordData::Cname(_) | RecordData::Ptr(_) | RecordData::Soa(_) | RecordData::Txt(_) => {
+                d.pos == rdata_end
+            }
+            _ => true,
+        };
+        if !parsed_to_end {
+            return Err(WireError::BadRdata);
+        }
+        Ok(Record {
+            name: self.name,
+            class: self.class,
+            ttl: self.ttl,
+            data,
+        })
+    }
+}
+
+/// EDNS options in OPT RDATA.
+fn options(rdata: &[u8]) -> Result<Vec<(u16, Vec<u8>)>, WireError> {
+    let mut d = Decoder {
+        data: rdata,
+        pos: 0,
+    };
+    let mut options = Vec::new();
+    while d.pos < rdata.len() {
+        let code = d.u16()?;
+        let len = d.u16()?;
+        options.push((code, d.bytes(usize::from(len))?.to_vec()));
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trust_dns_resolver::proto::serialize::binary::BinDecodable;
+
+    const HEADER_LEN: usize = 12;
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// `www.example.com` A: a CNAME to `example.com` and its address, both
+    /// owners compressed, with an OPT record setting DO.
+    const CNAME_CHAIN: &str = concat!(
+        "1a2b8180000100020000000103777777",
+        "076578616d706c6503636f6d00000100",
+        "01c00c000500010000012c0002c010c0",
+        "10000100010000012c00045db8d82200",
+        "002904d0000080000000",
+    );
+
+    /// `nx.example.com` A: NXDOMAIN with the zone's SOA in the authority
+    /// section, the RNAME compressed against the MNAME.
+    const NXDOMAIN_SOA: &str = concat!(
+        "beef81830001000000010000026e7807",
+        "6578616d706c6503636f6d0000010001",
+        "c00f0006000100000e10002c026e7305",
+        "6963616e6e036f726700036e6f630364",
+        "6e73c02f78a3f17500001c2000000e10",
+        "0012750000000e10",
+    );
+
+    /// `id.server.` TXT CH with two character strings.
+    const CHAOS_TXT: &str = concat!(
+        "00018400000100010000000002696406",
+        "7365727665720000100003c00c001000",
+        "0300000000001d1a7265733130302e71",
+        "667261312e7272646e732e7063682e6e",
+        "65740178",
+    );
+
+    /// Reverse lookup of 93.184.216.34, the PTR target uncompressed.
+    const REVERSE_PTR: &str = concat!(
+        "04048180000100010000000002333403",
+        "3231360331383402393307696e2d6164",
+        "6472046172706100000c0001c00c000c",
+        "000100015180000d076578616d706c65",
+        "03636f6d00",
+    );
+
+    /// `example.com` AAAA with AD set and a client cookie option.
+    const AAAA_COOKIE: &str = concat!(
+        "555581a0000100010000000107657861",
+        "6d706c6503636f6d00001c0001c00c00",
+        "1c000100000e10001026062800022000",
+        "010248189325c8194600002904d00000",
+        "8000000c000a00080102030405060708",
+    );
+
+    /// BADVERS: response code 16, split between the header and the OPT TTL.
+    const BADVERS: &str = concat!(
+        "01028100000100000000000107657861",
+        "6d706c6503636f6d0000010001000029",
+        "04d0010000000000",
+    );
+
+    const GOLDEN: [&str; 6] = [
+        CNAME_CHAIN,
+        NXDOMAIN_SOA,
+        CHAOS_TXT,
+        REVERSE_PTR,
+        AAAA_COOKIE,
+        BADVERS,
+    ];
+
+    fn name(name: &str) -> Name {
+        Name::parse(name).unwrap()
+    }
+
+    #[test]
+    fn test_golden_packets_decode() {
+        let m = Message::decode(&unhex(CNAME_CHAIN)).unwrap();
+        assert_eq!(m.header.id, 0x1a2b);
+        assert!(m.header.response && m.header.recursion_desired && m.header.recursion_available);
+        assert_eq!(m.rcode, Rcode::NO_ERROR);
+        assert_eq!(m.questions[0].name, name("www.example.com"));
+        assert_eq!(m.answers[0].data, RecordData::Cname(name("example.com")));
+        assert_eq!(m.answers[1].name, name("example.com"));
+        assert_eq!(m.answers[1].ttl, 300);
+        assert_eq!(m.addresses(), vec![IpAddr::from([93, 184, 216, 34])]);
+        let edns = m.edns.unwrap();
+        assert_eq!(edns.payload_size, 1232);
+        assert!(edns.dnssec_ok);
+
+        let m = Message::decode(&unhex(NXDOMAIN_SOA)).unwrap();
+        assert_eq!(m.rcode, Rcode::NX_DOMAIN);
+        assert!(m.answers.is_empty());
+        assert_eq!(
+            m.authority[0].data,
+            RecordData::Soa(Soa {
+                mname: name("ns.icann.org"),
+                rname: name("noc.dns.icann.org"),
+                serial: 2024010101,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1209600,
+                minimum: 3600,
+            })
+        );
+        assert_eq!(m.edns, None);
+
+        let m = Message::decode(&unhex(CHAOS_TXT)).unwrap();
+        assert!(m.header.authoritative);
+        assert_eq!(m.questions[0].qclass, CLASS_CH);
+        assert_eq!(
+            m.answers[0].data,
+            RecordData::Txt(vec![b"res100.qfra1.rrdns.pch.net".to_vec(), b"x".to_vec()])
+        );
+
+        let m = Message::decode(&unhex(REVERSE_PTR)).unwrap();
+        assert_eq!(m.answers[0].data, RecordData::Ptr(name("example.com")));
+        assert_eq!(m.answers[0].name.to_string(), "34.216.184.93.in-addr.arpa");
+
+        let m = Message::decode(&unhex(AAAA_COOKIE)).unwrap();
+        assert!(m.header.authentic_data);
+        assert_eq!(
+            m.addresses(),
+            vec!["2606:2800:220:1:248:1893:25c8:1946"
+                .parse::<IpAddr>()
+                .unwrap()]
+        );
+        assert_eq!(
+            m.edns.unwrap().options,
+            vec![(10, vec![1, 2, 3, 4, 5, 6, 7, 8])]
+        );
+
+        let m = Message::decode(&unhex(BADVERS)).unwrap();
+        assert_eq!(m.rcode, Rcode(16));
+    }
+
+    #[test]
+    fn test_golden_packets_encode_byte_for_byte() {
+        for packet in GOLDEN {
+            let bytes = unhex(packet);
+            let message = Message::decode(&bytes).unwrap();
+            assert_eq!(message.encode().unwrap(), bytes, "{}", packet);
+        }
+    }
+
+    #[test]
+    fn test_golden_packets_agree_with_trust_dns() {
+        use trust_dns_resolver::proto::op::Message as Reference;
+
+        for packet in GOLDEN {
+            let ours = Message::decode(&unhex(packet)).unwrap();
+            let theirs = Reference::from_bytes(&unhex(packet)).unwrap();
+            assert_eq!(ours.header.id, theirs.id());
+            assert_eq!(ours.answers.len(), theirs.answers().len());
+            assert_eq!(ours.authority.len(), theirs.name_servers().len());
+            assert_eq!(ours.edns.is_some(), theirs.extensions().is_some());
+            assert_eq!(
+                ours.rcode.0,
+                u16::from(theirs.response_code()),
+                "{}",
+                packet
+            );
+        }
+
+        // And it reads the queries built here
+        let query = Message::query(0x4242, name("Example.COM."), TYPE_AAAA, CLASS_IN);
+        let theirs = Reference::from_bytes(&query.encode().unwrap()).unwrap();
+        assert_eq!(theirs.id(), 0x4242);
+        assert!(theirs.recursion_desired());
+        assert_eq!(theirs.queries()[0].name().to_ascii(), "Example.COM.");
+        assert_eq!(u16::from(theirs.queries()[0].query_type()), TYPE_AAAA);
+        assert_eq!(theirs.extensions().as_ref().unwrap().max_payload(), 1232);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(name("example.com."), name("EXAMPLE.com"));
+        assert_ne!(name("example.com"), name("example.org"));
+        assert!(name(".").is_root());
+        assert_eq!(Name::root().to_string(), ".");
+        assert_eq!(Name::parse("a..b"), Err(WireError::BadName));
+        assert_eq!(Name::parse(&"x".repeat(64)), Err(WireError::BadName));
+        let long = vec!["x".repeat(63); 4].join(".");
+        assert_eq!(Name::parse(&long), Err(WireError::BadName));
+
+        let odd = Name::from_labels(vec![b"a.b".to_vec(), vec![0, b'z']]).unwrap();
+        assert_eq!(odd.to_string(), "a\\.b.\\000z");
+    }
+
+    #[test]
+    fn test_rejects_malformed_names() {
+        let mut packet = unhex(CNAME_CHAIN);
+        // The CNAME owner pointing at itself
+        packet[33..35].copy_from_slice(&[0xc0, 33]);
+        assert_eq!(Message::decode(&packet), Err(WireError::BadPointer));
+        // ...or forward, at the next record
+        packet[33..35].copy_from_slice(&[0xc0, 47]);
+        assert_eq!(Message::decode(&packet), Err(WireError::BadPointer));
+        // A reserved label type
+        packet[33] = 0x80;
+        assert_eq!(Message::decode(&packet), Err(WireError::BadLabel));
+
+        let packet = unhex(CNAME_CHAIN);
+        for len in 0..packet.len() {
+            assert!(Message::decode(&packet[..len]).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_rdata() {
+        // The A record's RDLENGTH says 5
+        let mut packet = unhex(CNAME_CHAIN);
+        packet[58] = 5;
+        packet.insert(63, 0);
+        assert_eq!(Message::decode(&packet), Err(WireError::BadRdata));
+
+        // Two OPT records
+        let mut packet = unhex(BADVERS);
+        packet[11] = 2;
+        packet.extend_from_slice(&unhex("00002904d0000000000000"));
+        assert_eq!(Message::decode(&packet), Err(WireError::DuplicateOpt));
+
+        // Response code 16 can't be sent without EDNS
+        let mut message = Message::decode(&unhex(BADVERS)).unwrap();
+        message.edns = None;
+        assert_eq!(message.encode(), Err(WireError::ExtendedRcode));
+    }
+
+    #[test]
+    fn test_compression_is_case_insensitive() {
+        let mut message = Message::query(1, name("Example.com"), TYPE_A, CLASS_IN);
+        message.answers.push(Record {
+            name: name("example.COM"),
+            class: CLASS_IN,
+            ttl: 60,
+            data: RecordData::Cname(name("www.example.com")),
+        });
+        let bytes = message.encode().unwrap();
+        // Owner is a pointer to the question; the CNAME target one label
+        // and a pointer
+        assert_eq!(&bytes[29..31], &[0xc0, 0x0c]);
+        assert_eq!(&bytes[41..47], &[3, b'w', b'w', b'w', 0xc0, 0x0c]);
+        assert_eq!(Message::decode(&bytes).unwrap(), message);
+    }
+
+    /// xorshift64*, so failures reproduce.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    /// Decoding anything must not panic, and whatever decodes must survive
+    /// an encode/decode round trip unchanged.
+    fn check_round_trip(input: &[u8]) {
+        if let Ok(message) = Message::decode(input) {
+            let encoded = message
+                .encode()
+                .unwrap_or_else(|e| panic!("{:?} from {:02x?}", e, input));
+            assert_eq!(Message::decode(&encoded), Ok(message), "{:02x?}", input);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_random_bytes() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..20_000 {
+            let len = rng.below(96);
+            let mut input: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+            // Small counts, so records get parsed rather than the header
+            // failing straight away
+            if len >= HEADER_LEN {
+                for i in (4..HEADER_LEN).step_by(2) {
+                    input[i] = 0;
+                    input[i + 1] &= 0x03;
+                }
+            }
+            check_round_trip(&input);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_mutated_golden_packets() {
+        let mut rng = Rng(0x0123_4567_89ab_cdef);
+        let packets: Vec<Vec<u8>> = GOLDEN.iter().map(|p| unhex(p)).collect();
+        for _ in 0..50_000 {
+            let mut input = packets[rng.below(packets.len())].clone();
+            for _ in 0..=rng.below(4) {
+                let at = rng.below(input.len());
+                match rng.below(4) {
+                    0 => input[at] = rng.next() as u8,
+                    1 => input[at] ^= 1 << rng.below(8),
+                    2 => input.truncate(at),
+                    _ => input.insert(at, rng.next() as u8),
+                }
+                if input.is_empty() {
+                    break;
+                }
+            }
+            check_round_trip(&input);
+        }
+    }
+}
@@ -3,15 +3,12 @@
 /// Get default gateway IP address.
 #[cfg(target_os = "windows")]
 pub fn get_default_gateway() -> Option<String> {
-    use super::hidden_cmd;
+    use super::{command_output, hidden_cmd};
 
     // Run "route print" and parse the output
     // LOCALE-INDEPENDENT: We parse IP addresses (0.0.0.0) which are not localized
     // The route table format is consistent across locales
-    let output = hidden_cmd("cmd")
-        .args(["/C", "route print 0.0.0.0"])
-        .output()
-        .ok()?;
+    let output = command_output(hidden_cmd("cmd").args(["/C", "route print 0.0.0.0"])).ok()?;
 
     let text = String::from_utf8_lossy(&output.stdout);
 
@@ -31,15 +28,13 @@ pub fn get_default_gateway() -> Option<String> {
 
 #[cfg(target_os = "linux")]
 pub fn get_default_gateway() -> Option<String> {
+    use super::command_output;
     use std::process::Command;
 
     // Run "ip route" and parse the output
     // LOCALE-INDEPENDENT: The `ip` command outputs English keywords regardless of system locale
     // Keywords like "default" and "via" are not translated
-    let output = Command::new("ip")
-        .args(["route", "show", "default"])
-        .output()
-        .ok()?;
+    let output = command_output(Command::new("ip").args(["route", "show", "default"])).ok()?;
 
     let text = String::from_utf8_lossy(&output.stdout);
 
@@ -57,12 +52,13 @@ pub fn get_default_gateway() -> Option<String> {
 
 #[cfg(target_os = "macos")]
 pub fn get_default_gateway() -> Option<String> {
+    use super::command_output;
     use std::process::Command;
 
     // Run "netstat -nr" and parse the output
     // LOCALE-INDEPENDENT: netstat uses numeric format (-n) and standard keywords
     // The keyword "default" and IP "0.0.0.0" are not localized
-    let output = Command::new("netstat").args(["-nr"]).output().ok()?;
+    let output = command_output(Command::new("netstat").args(["-nr"])).ok()?;
 
     let text = String::from_utf8_lossy(&output.stdout);
 
@@ -8,9 +8,12 @@ use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 use mdns_sd::{ServiceDaemon, ServiceEvent};
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::Cancellation;
 
 /// Information discovered about a device via mDNS.
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MdnsDeviceInfo {
     /// Human-readable device name (extracted from mDNS instance name).
     pub name: String,
@@ -34,18 +37,20 @@ const SERVICE_TYPES: &[(&str, &str)] = &[
 
 /// Discover devices on the local network using mDNS.
 ///
-/// Browses multiple service types simultaneously for the given timeout.
+/// Browses multiple service types simultaneously for the given timeout, or
+/// until `cancel` fires.
 /// Returns a map of IPv4 address string → discovered device info.
 ///
 /// This function is synchronous. It creates its own mDNS daemon thread
 /// and shuts it down before returning. Safe to call from `spawn_blocking`.
-pub fn mdns_discover(timeout: Duration) -> HashMap<String, MdnsDeviceInfo> {
+pub fn mdns_discover(timeout: Duration, cancel: &Cancellation) -> HashMap<String, MdnsDeviceInfo> {
+    let started = Instant::now();
     let mut results: HashMap<String, MdnsDeviceInfo> = HashMap::new();
 
     let daemon = match ServiceDaemon::new() {
         Ok(d) => d,
         Err(e) => {
-            println!("[mDNS] Failed to create daemon: {}", e);
+            tracing::warn!(error = %e, "failed to create mDNS daemon");
             return results;
         }
     };
@@ -56,7 +61,7 @@ pub fn mdns_discover(timeout: Duration) -> HashMap<String, MdnsDeviceInfo> {
         match daemon.browse(service_type) {
             Ok(receiver) => receivers.push((receiver, *service_type)),
             Err(e) => {
-                println!("[mDNS] Failed to browse {}: {}", service_type, e);
+                tracing::warn!(service_type, error = %e, "mDNS browse failed");
             }
         }
     }
@@ -64,8 +69,8 @@ pub fn mdns_discover(timeout: Duration) -> HashMap<String, MdnsDeviceInfo> {
     let deadline = Instant::now() + timeout;
     let poll_interval = Duration::from_millis(50);
 
-    // Collect resolved services until timeout
-    while Instant::now() < deadline {
+    // Collect resolved services until timeout or cancellation
+    while Instant::now() < deadline && !cancel.is_stopped() {
         let remaining = deadline.saturating_duration_since(Instant::now());
         if remaining.is_zero() {
             break;
@@ -82,9 +87,11 @@ pub fn mdns_discover(timeout: Duration) -> HashMap<String, MdnsDeviceInfo> {
                         continue;
                     }
 
-                    println!(
-                        "[mDNS] Resolved: {} → {} ({})",
-                        info.fullname, clean_name, service_type
+                    tracing::trace!(
+                        fullname = %info.fullname,
+                        name = %clean_name,
+                        service_type,
+                        "mDNS service resolved"
                     );
 
                     for ipv4 in info.get_addresses_v4() {
@@ -117,7 +124,11 @@ pub fn mdns_discover(timeout: Duration) -> HashMap<String, MdnsDeviceInfo> {
         let _ = receiver.recv_timeout(Duration::from_secs(1));
     }
 
-    println!("[mDNS] Discovery complete: {} devices found", results.len());
+    tracing::debug!(
+        devices = results.len(),
+        duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+        "mDNS discovery complete"
+    );
     results
 }
 
@@ -5,13 +5,19 @@
 //! - Gateway/router discovery
 //! - ARP lookups
 //! - DNS configuration
+//! - DNS queries over UDP, DoH and DoT
+//! - DNS message encoding and decoding
+//! - DHCP lease details
 //!
 //! Each submodule provides platform-specific implementations via `#[cfg(...)]`.
 
 pub mod adapter;
 pub mod arp;
 pub mod connection;
+pub mod dhcp;
 pub mod dns;
+pub mod dns_transport;
+pub mod dns_wire;
 pub mod gateway;
 pub mod mdns;
 pub mod security;
@@ -45,6 +51,26 @@ pub fn hidden_cmd(program: &str) -> std::process::Command {
     cmd
 }
 
+/// Run `cmd` to completion, keeping its output in an active capture.
+pub(crate) fn command_output(
+    cmd: &mut std::process::Command,
+) -> std::io::Result<std::process::Output> {
+    let output = cmd.output();
+    crate::capture::record_raw(|| crate::capture::RawOutput::command(cmd, &output));
+    output
+}
+
+/// Read a text file, keeping its contents in an active capture.
+#[cfg(target_os = "linux")]
+pub(crate) fn read_text_file(path: &std::path::Path) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    crate::capture::record_raw(|| crate::capture::RawOutput::File {
+        path: path.display().to_string(),
+        contents: text.clone(),
+    });
+    Some(text)
+}
+
 /// Run a PowerShell command and return trimmed stdout as a String.
 ///
 /// Forces UTF-8 output encoding (critical for GUI apps without a console,
@@ -56,10 +82,9 @@ pub fn run_powershell(command: &str) -> Option<String> {
                   [System.Threading.Thread]::CurrentThread.CurrentUICulture = 'en-US'; ";
     let full_command = format!("{}{}", prefix, command);
 
-    let output = hidden_cmd("powershell")
-        .args(["-NoProfile", "-Command", &full_command])
-        .output()
-        .ok()?;
+    let output =
+        command_output(hidden_cmd("powershell").args(["-NoProfile", "-Command", &full_command]))
+            .ok()?;
 
     if !output.status.success() {
         return None;
@@ -74,10 +99,17 @@ pub fn run_powershell(command: &str) -> Option<String> {
 }
 
 // Re-export commonly used functions
-pub use arp::{get_router_mac, ping_sweep};
+pub use arp::{get_router_mac, ping_host, ping_sweep};
 pub use connection::detect_connection_type;
-pub use dns::{build_dns_commands, flush_dns, get_current_dns, reverse_dns_lookup, set_dns};
+pub use dhcp::get_dhcp_info;
+pub use dns::{
+    build_dns_commands, flush_dns, get_current_dns, reverse_dns_lookup, reverse_dns_lookup_async,
+    set_dns,
+};
 pub use gateway::get_default_gateway;
 pub use mdns::mdns_discover;
-pub use security::check_wifi_security;
+pub use security::{
+    check_wifi_security, check_wifi_security_async, check_wifi_security_cancellable,
+    check_wifi_security_with_backend, check_wifi_security_with_targets,
+};
 pub use wifi::get_wifi_info;
@@ -4,9 +4,23 @@
 //! 1. Encryption — type of Wi-Fi encryption (Open/WEP/WPA/WPA2/WPA3)
 //! 2. Evil Twin — duplicate SSIDs with different security
 //! 3. ARP Spoofing — duplicate MACs in ARP table
-//! 4. DNS Hijacking — DNS response mismatch
+//! 4. DNS Hijacking — DNS answers that differ from a trusted resolver's
 
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+
+use super::dns_transport::{exchange_udp_raw, random_id};
+use super::dns_wire as wire;
+use crate::backend::{NetworkBackend, SystemBackend};
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::dns_leak::parse_org;
+use crate::domain::ProbeTargets;
+use crate::runtime::blocking;
 
 // ==================== Domain Types ====================
 
@@ -155,6 +169,10 @@ pub fn check_encryption() -> SecurityCheck {
             WlanFreeMemory(connection_attrs as *const core::ffi::c_void);
             (auth, cipher)
         };
+        crate::capture::record_raw(|| crate::capture::RawOutput::Wlan {
+            call: "WlanQueryInterface(current_connection).wlanSecurityAttributes".to_string(),
+            fields: serde_json::json!({ "auth_algorithm": auth.0, "cipher_algorithm": cipher.0 }),
+        });
 
         // All logic below is safe — no raw pointers involved.
         let enc_type = match auth {
@@ -200,10 +218,11 @@ pub fn check_encryption() -> SecurityCheck {
 
     // SAFETY: interface_list was allocated by WlanEnumInterfaces, non-null.
     unsafe { WlanFreeMemory(interface_list as *const core::ffi::c_void) };
-    println!(
-        "[security] check_encryption: {:?} in {:.1}ms",
-        check_result.status,
-        start.elapsed().as_secs_f64() * 1000.0
+    tracing::debug!(
+        check = "encryption",
+        status = ?check_result.status,
+        duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+        "security check finished"
     );
     check_result
 }
@@ -403,11 +422,12 @@ pub fn check_evil_twin() -> SecurityCheck {
 
     // SAFETY: interface_list was allocated by WlanEnumInterfaces.
     unsafe { WlanFreeMemory(interface_list as *const core::ffi::c_void) };
-    println!(
-        "[security] check_evil_twin: {:?} (scanned {} BSSIDs) in {:.1}ms",
-        check_result.status,
+    tracing::debug!(
+        check = "evil_twin",
+        status = ?check_result.status,
         bss_scanned,
-        start.elapsed().as_secs_f64() * 1000.0
+        duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+        "security check finished"
     );
     check_result
 }
@@ -424,21 +444,22 @@ pub fn check_evil_twin() -> SecurityCheck {
 // ==================== ARP Spoofing Detection ====================
 
 /// Check for ARP spoofing by looking for duplicate MACs in the ARP table.
-pub fn check_arp_spoofing() -> SecurityCheck {
+pub fn check_arp_spoofing(backend: &dyn NetworkBackend) -> SecurityCheck {
     let start = std::time::Instant::now();
-    use super::arp::get_all_arp_entries;
-    use super::gateway::get_default_gateway;
     use std::collections::HashMap;
 
-    let gateway_ip = get_default_gateway();
-    let entries = get_all_arp_entries();
+    let gateway_ip = backend.default_gateway();
+    let entries = backend.arp_entries();
 
     let entry_count = entries.len();
 
     if entries.is_empty() {
-        println!(
-            "[security] check_arp_spoofing: Safe (empty ARP table) in {:.1}ms",
-            start.elapsed().as_secs_f64() * 1000.0
+        tracing::debug!(
+            check = "arp_spoofing",
+            status = ?SecurityStatus::Safe,
+            arp_entries = 0,
+            duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "security check finished"
         );
         return SecurityCheck {
             check_type: SecurityCheckType::ArpSpoofing,
@@ -478,9 +499,14 @@ pub fn check_arp_spoofing() -> SecurityCheck {
                 status: SecurityStatus::Danger,
                 details: Some(format!("gateway_mac_duplicate:{}", mac)),
             };
-            println!(
-                "[security] check_arp_spoofing: Danger (gateway MAC dup: {}) ({} entries) in {:.1}ms",
-                mac, entry_count, start.elapsed().as_secs_f64() * 1000.0
+            tracing::debug!(
+                check = "arp_spoofing",
+                status = ?r.status,
+                duplicate_mac = %mac,
+                gateway = true,
+                arp_entries = entry_count,
+                duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+                "security check finished"
             );
             return r;
         }
@@ -491,11 +517,14 @@ pub fn check_arp_spoofing() -> SecurityCheck {
             status: SecurityStatus::Warning,
             details: Some(format!("mac_duplicate:{}", mac)),
         };
-        println!(
-            "[security] check_arp_spoofing: Warning (MAC dup: {}) ({} entries) in {:.1}ms",
-            mac,
-            entry_count,
-            start.elapsed().as_secs_f64() * 1000.0
+        tracing::debug!(
+            check = "arp_spoofing",
+            status = ?r.status,
+            duplicate_mac = %mac,
+            gateway = false,
+            arp_entries = entry_count,
+            duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "security check finished"
         );
         return r;
     }
@@ -505,43 +534,286 @@ pub fn check_arp_spoofing() -> SecurityCheck {
         status: SecurityStatus::Safe,
         details: None,
     };
-    println!(
-        "[security] check_arp_spoofing: {:?} ({} ARP entries, gateway={:?}) in {:.1}ms",
-        result.status,
-        entry_count,
-        gateway_ip,
-        start.elapsed().as_secs_f64() * 1000.0
+    tracing::debug!(
+        check = "arp_spoofing",
+        status = ?result.status,
+        arp_entries = entry_count,
+        gateway = ?gateway_ip,
+        duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+        "security check finished"
     );
     result
 }
 
 // ==================== DNS Hijacking Detection ====================
 
-/// Check for DNS hijacking by comparing system DNS response with a trusted DNS.
-pub fn check_dns_hijacking() -> SecurityCheck {
-    let start = std::time::Instant::now();
-    let test_domain = "example.com";
+/// Time allowed for each raw or DoH query made by the hijacking check.
+const HIJACK_QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Answers for one test domain, from the system resolver and the references.
+#[derive(Debug, Default)]
+struct DomainAnswers {
+    domain: String,
+    system_v4: Vec<String>,
+    system_v6: Vec<String>,
+    /// `ProbeTargets::trusted_dns_server` over plain UDP.
+    trusted: Vec<String>,
+    /// `ProbeTargets::trusted_doh_url`, both families.
+    doh: Vec<String>,
+}
 
-    // Resolve via system DNS
-    let t0 = std::time::Instant::now();
-    let system_ips = resolve_domain_system(test_domain);
-    let system_ms = t0.elapsed().as_secs_f64() * 1000.0;
+impl DomainAnswers {
+    /// IPv4 reference: DoH when it answered, since a middlebox can rewrite
+    /// port 53 to any server but not an HTTPS exchange.
+    fn reference_v4(&self) -> Vec<String> {
+        let doh = only_v4(&self.doh);
+        if doh.is_empty() {
+            self.trusted.clone()
+        } else {
+            doh
+        }
+    }
+
+    /// IPv6 reference; only DoH is asked for AAAA records.
+    fn reference_v6(&self) -> Vec<String> {
+        self.doh.iter().filter(|ip| !is_v4(ip)).cloned().collect()
+    }
+}
+
+/// How a set of answers compares with a reference set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Agreement {
+    /// At least one address in common.
+    Same,
+    /// Different addresses, all on networks the reference also answers from,
+    /// as CDNs do when they pick a nearby edge.
+    SameNetwork,
+    Different,
+    /// One side has no answer.
+    Unknown,
+}
+
+/// Compare `answers` with `reference`; `asns` maps addresses to their ASN.
+fn agreement(answers: &[String], reference: &[String], asns: &HashMap<String, u32>) -> Agreement {
+    if answers.is_empty() || reference.is_empty() {
+        return Agreement::Unknown;
+    }
+    if answers.iter().any(|ip| reference.contains(ip)) {
+        return Agreement::Same;
+    }
+    let reference_asns: HashSet<u32> = reference
+        .iter()
+        .filter_map(|ip| asns.get(ip).copied())
+        .collect();
+    let same_network = answers
+        .iter()
+        .all(|ip| asns.get(ip).is_some_and(|asn| reference_asns.contains(asn)));
+    if same_network {
+        Agreement::SameNetwork
+    } else {
+        Agreement::Different
+    }
+}
 
-    // Resolve via trusted DNS (1.1.1.1)
-    let t1 = std::time::Instant::now();
-    let trusted_ips = resolve_domain_direct(test_domain, "1.1.1.1:53");
-    let trusted_ms = t1.elapsed().as_secs_f64() * 1000.0;
+/// Addresses whose ASN is needed to tell a CDN edge from a rewritten answer:
+/// those of disagreeing answer sets made only of public addresses. A private
+/// or loopback answer for a public domain is never a CDN.
+fn addresses_to_classify(domains: &[DomainAnswers]) -> BTreeSet<String> {
+    let mut pairs: Vec<(Vec<String>, Vec<String>)> = Vec::new();
+    for answers in domains {
+        pairs.push((answers.system_v4.clone(), answers.reference_v4()));
+        pairs.push((answers.system_v6.clone(), answers.reference_v6()));
+        pairs.push((answers.trusted.clone(), only_v4(&answers.doh)));
+    }
+    pairs
+        .into_iter()
+        .filter(|(a, b)| agreement(a, b, &HashMap::new()) == Agreement::Different)
+        .filter(|(a, b)| a.iter().chain(b).all(|ip| is_public(ip)))
+        .flat_map(|(a, b)| a.into_iter().chain(b))
+        .collect()
+}
 
-    println!(
-        "[security] check_dns_hijacking: system DNS -> {:?} ({:.1}ms), trusted DNS -> {:?} ({:.1}ms)",
-        system_ips, system_ms, trusted_ips, trusted_ms
+/// Findings from the answers for the test domains and for a name that
+/// doesn't exist (`nx_system`/`nx_trusted`/`nx_doh`). Empty when nothing
+/// looks rewritten.
+fn hijacking_findings(
+    domains: &[DomainAnswers],
+    nx: (&[String], &[String], &[String]),
+    asns: &HashMap<String, u32>,
+    trusted_server: &str,
+) -> Vec<String> {
+    let mut findings = Vec::new();
+    for answers in domains {
+        let families = [
+            (&answers.system_v4, answers.reference_v4()),
+            (&answers.system_v6, answers.reference_v6()),
+        ];
+        for (system, reference) in families {
+            if agreement(system, &reference, asns) == Agreement::Different {
+                findings.push(format!(
+                    "mismatch:{} system:{} trusted:{}",
+                    answers.domain,
+                    system.join(","),
+                    reference.join(",")
+                ));
+            }
+        }
+    }
+
+    // The trusted server disagreeing with the same operator over HTTPS means
+    // something on the path answers port 53 in its place.
+    let intercepted = domains
+        .iter()
+        .any(|a| agreement(&a.trusted, &only_v4(&a.doh), asns) == Agreement::Different);
+    if intercepted {
+        findings.push(format!("intercepted:{}", trusted_server));
+    }
+
+    // An ISP that "helpfully" answers nonexistent names with a search or ad
+    // page; only if the references are up and agree the name doesn't exist.
+    let (nx_system, nx_trusted, nx_doh) = nx;
+    let references_up = domains
+        .iter()
+        .any(|a| !a.trusted.is_empty() || !a.doh.is_empty());
+    if references_up && !nx_system.is_empty() && nx_trusted.is_empty() && nx_doh.is_empty() {
+        findings.push(format!("nxdomain_rewrite:{}", nx_system.join(",")));
+    }
+    findings
+}
+
+/// A name under `domain` no resolver can have cached and that shouldn't exist.
+fn nonexistent_name(domain: &str) -> String {
+    format!("netok-nx-{:04x}{:04x}.{}", random_id(), random_id(), domain)
+}
+
+fn is_v4(ip: &str) -> bool {
+    ip.parse::<std::net::Ipv4Addr>().is_ok()
+}
+
+fn only_v4(ips: &[String]) -> Vec<String> {
+    ips.iter().filter(|ip| is_v4(ip)).cloned().collect()
+}
+
+/// Whether `ip` is a globally routed address (not private, loopback,
+/// link-local or unspecified).
+fn is_public(ip: &str) -> bool {
+    match ip.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => {
+            !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified())
+        }
+        Ok(IpAddr::V6(v6)) => {
+            let unique_local = (v6.segments()[0] & 0xfe00) == 0xfc00;
+            let link_local = (v6.segments()[0] & 0xffc0) == 0xfe80;
+            !(v6.is_loopback() || v6.is_unspecified() || unique_local || link_local)
+        }
+        Err(_) => false,
+    }
+}
+
+/// ASN of each address, from the geolocation API. Addresses it can't place
+/// are left out.
+async fn lookup_asns(
+    backend: &dyn NetworkBackend,
+    ips: BTreeSet<String>,
+    targets: &ProbeTargets,
+) -> HashMap<String, u32> {
+    let lookups = ips.into_iter().map(|ip| async move {
+        let info = backend.ip_location_async(&ip, targets).await?;
+        let asn = parse_org(info.org.as_deref()?).0?;
+        Some((ip, asn))
+    });
+    join_all(lookups).await.into_iter().flatten().collect()
+}
+
+/// Check for DNS hijacking by comparing system DNS answers with trusted ones.
+///
+/// Each of `targets.hijack_test_domains` is resolved for A and AAAA through
+/// the system resolver and compared with `trusted_doh_url` (or, for A records
+/// when DoH is blocked, `trusted_dns_server`). Different addresses on the
+/// same networks are CDN variation, not hijacking. The check also flags a
+/// trusted server whose answers differ from its DoH endpoint (port 53
+/// interception) and a system resolver that answers a nonexistent name
+/// (NXDOMAIN rewriting). All lookups run concurrently.
+pub async fn check_dns_hijacking(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+) -> SecurityCheck {
+    let start = std::time::Instant::now();
+    let trusted_server = targets.trusted_dns_server.as_str();
+    let doh_url = targets.trusted_doh_url.as_str();
+
+    let resolve_domain = |domain: &str| {
+        let domain = domain.to_string();
+        async move {
+            let ((system_v4, system_ms), system_v6, (trusted, trusted_ms), (doh, doh_ms)) = tokio::join!(
+                timed(backend.resolve_system_async(&domain)),
+                backend.resolve_system_v6_async(&domain),
+                timed(backend.resolve_via_async(&domain, trusted_server)),
+                timed(backend.resolve_doh_async(&domain, doh_url)),
+            );
+            tracing::debug!(
+                check = "dns_hijacking",
+                domain = %domain,
+                system = ?system_v4,
+                system_v6 = ?system_v6,
+                system_ms,
+                trusted = ?trusted,
+                trusted_ms,
+                doh = ?doh,
+                doh_ms,
+                "resolved test domain"
+            );
+            DomainAnswers {
+                domain,
+                system_v4,
+                system_v6,
+                trusted,
+                doh,
+            }
+        }
+    };
+    let nx_name = nonexistent_name(
+        targets
+            .hijack_test_domains
+            .first()
+            .map_or("example.com", String::as_str),
+    );
+    let (domains, (nx_system, nx_trusted, nx_doh)) = tokio::join!(
+        join_all(
+            targets
+                .hijack_test_domains
+                .iter()
+                .map(|d| resolve_domain(d))
+        ),
+        async {
+            tokio::join!(
+                backend.resolve_system_async(&nx_name),
+                backend.resolve_via_async(&nx_name, trusted_server),
+                backend.resolve_doh_async(&nx_name, doh_url),
+            )
+        },
+    );
+    tracing::debug!(
+        check = "dns_hijacking",
+        system = ?nx_system,
+        trusted = ?nx_trusted,
+        doh = ?nx_doh,
+        "resolved nonexistent name"
     );
 
-    // If either fails, we can't determine — report safe to avoid false positives
-    if system_ips.is_empty() || trusted_ips.is_empty() {
-        println!(
-            "[security] check_dns_hijacking: Safe (dns_check_failed) in {:.1}ms",
-            start.elapsed().as_secs_f64() * 1000.0
+    // Nothing to compare — report safe to avoid false positives
+    let compared = domains.iter().any(|a| {
+        let system = a.system_v4.len() + a.system_v6.len();
+        let reference = a.trusted.len() + a.doh.len();
+        system > 0 && reference > 0
+    });
+    if !compared {
+        tracing::debug!(
+            check = "dns_hijacking",
+            status = ?SecurityStatus::Safe,
+            details = "dns_check_failed",
+            duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+            "security check finished"
         );
         return SecurityCheck {
             check_type: SecurityCheckType::DnsHijacking,
@@ -550,10 +822,15 @@ pub fn check_dns_hijacking() -> SecurityCheck {
         };
     }
 
-    // Compare: if system DNS returns completely different IPs, it's suspicious
-    let has_overlap = system_ips.iter().any(|ip| trusted_ips.contains(ip));
+    let asns = lookup_asns(backend, addresses_to_classify(&domains), targets).await;
+    let findings = hijacking_findings(
+        &domains,
+        (&nx_system, &nx_trusted, &nx_doh),
+        &asns,
+        trusted_server,
+    );
 
-    let result = if has_overlap {
+    let result = if findings.is_empty() {
         SecurityCheck {
             check_type: SecurityCheckType::DnsHijacking,
             status: SecurityStatus::Safe,
@@ -563,247 +840,202 @@ pub fn check_dns_hijacking() -> SecurityCheck {
         SecurityCheck {
             check_type: SecurityCheckType::DnsHijacking,
             status: SecurityStatus::Warning,
-            details: Some(format!(
-                "system:{} trusted:{}",
-                system_ips.join(","),
-                trusted_ips.join(",")
-            )),
+            details: Some(findings.join("; ")),
         }
     };
-    println!(
-        "[security] check_dns_hijacking: {:?} in {:.1}ms",
-        result.status,
-        start.elapsed().as_secs_f64() * 1000.0
+    tracing::debug!(
+        check = "dns_hijacking",
+        status = ?result.status,
+        details = ?result.details,
+        duration_ms = start.elapsed().as_secs_f64() * 1000.0,
+        "security check finished"
     );
     result
 }
 
-/// Resolve a domain using the system's default DNS.
-fn resolve_domain_system(domain: &str) -> Vec<String> {
-    use std::net::ToSocketAddrs;
+/// Await `lookup`, returning its answer and how long it took in ms.
+async fn timed<T>(lookup: impl std::future::Future<Output = T>) -> (T, f64) {
+    let t0 = std::time::Instant::now();
+    let result = lookup.await;
+    (result, t0.elapsed().as_secs_f64() * 1000.0)
+}
+
+/// Resolve a domain's IPv4 addresses using the system's default DNS.
+pub(crate) async fn resolve_domain_system_async(domain: &str) -> Vec<String> {
+    resolve_domain_system_filtered(domain, SocketAddr::is_ipv4).await
+}
 
-    let addr = format!("{}:80", domain);
-    match addr.to_socket_addrs() {
+/// Resolve a domain's IPv6 addresses using the system's default DNS.
+pub(crate) async fn resolve_domain_system_v6_async(domain: &str) -> Vec<String> {
+    resolve_domain_system_filtered(domain, SocketAddr::is_ipv6).await
+}
+
+async fn resolve_domain_system_filtered(
+    domain: &str,
+    keep: impl Fn(&SocketAddr) -> bool,
+) -> Vec<String> {
+    match tokio::net::lookup_host(format!("{}:80", domain)).await {
         Ok(addrs) => addrs
-            .filter_map(|a| match a {
-                std::net::SocketAddr::V4(v4) => Some(v4.ip().to_string()),
-                _ => None,
-            })
+            .filter(|a| keep(a))
+            .map(|a| a.ip().to_string())
             .collect(),
         Err(_) => vec![],
     }
 }
 
-/// Resolve a domain by sending a raw DNS query to a specific server.
-fn resolve_domain_direct(domain: &str, dns_server: &str) -> Vec<String> {
-    use std::net::UdpSocket;
-    use std::time::Duration;
+/// Resolve a domain's A and AAAA addresses through a DoH server.
+pub(crate) async fn resolve_domain_doh_async(domain: &str, url: &str) -> Vec<String> {
+    crate::infrastructure::dns_transport::resolve_doh(url, domain, HIJACK_QUERY_TIMEOUT).await
+}
 
-    let socket = match UdpSocket::bind("0.0.0.0:0") {
-        Ok(s) => s,
-        Err(_) => return vec![],
+/// Resolve a domain by sending a raw DNS query to a specific server
+/// (`ip:port`, or an IP for port 53).
+///
+/// Each query goes out from a fresh socket (a random source port) with a
+/// random transaction id, and only a response from `dns_server` that echoes
+/// both is accepted, so a spoofed or stray datagram can't answer it.
+pub(crate) async fn resolve_domain_direct_async(domain: &str, dns_server: &str) -> Vec<String> {
+    let Some(server) = dns_server
+        .parse::<SocketAddr>()
+        .ok()
+        .or_else(|| Some(SocketAddr::new(dns_server.parse().ok()?, 53)))
+    else {
+        return vec![];
     };
-
-    let _ = socket.set_read_timeout(Some(Duration::from_secs(3)));
-
-    let packet = build_dns_query(domain);
-    if socket.send_to(&packet, dns_server).is_err() {
+    let Ok(name) = wire::Name::parse(domain) else {
         return vec![];
-    }
-
-    let mut buf = [0u8; 512];
-    match socket.recv_from(&mut buf) {
-        Ok((len, _)) => parse_dns_response(&buf[..len]),
-        Err(_) => vec![],
-    }
+    };
+    let query = wire::Message::query(random_id(), name, wire::TYPE_A, wire::CLASS_IN);
+
+    let response = exchange_udp_raw(server, &query, HIJACK_QUERY_TIMEOUT).await;
+    crate::capture::record_raw(|| crate::capture::RawOutput::Dns {
+        server: dns_server.to_string(),
+        query: query
+            .encode()
+            .map(|bytes| crate::capture::hex(&bytes))
+            .unwrap_or_default(),
+        response: response
+            .as_ref()
+            .map(|(_, bytes)| crate::capture::hex(bytes)),
+    });
+    response
+        .map(|(answer, _)| answer.addresses().iter().map(|ip| ip.to_string()).collect())
+        .unwrap_or_default()
 }
 
-/// Build a simple DNS A record query packet.
-fn build_dns_query(domain: &str) -> Vec<u8> {
-    let mut packet = Vec::with_capacity(64);
-
-    // Transaction ID
-    packet.extend_from_slice(&[0x12, 0x34]);
-    // Flags: standard query, recursion desired
-    packet.extend_from_slice(&[0x01, 0x00]);
-    // Questions: 1
-    packet.extend_from_slice(&[0x00, 0x01]);
-    // Answer, Authority, Additional: 0
-    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
-
-    // Domain name
-    for label in domain.split('.') {
-        packet.push(label.len() as u8);
-        packet.extend_from_slice(label.as_bytes());
-    }
-    packet.push(0x00); // End of domain
-
-    // Type A (1) and Class IN (1)
-    packet.extend_from_slice(&[0x00, 0x01, 0x00, 0x01]);
+// ==================== Orchestrator ====================
 
-    packet
+/// Run all 4 security checks and produce a report.
+pub fn check_wifi_security() -> WiFiSecurityReport {
+    check_wifi_security_with_targets(&ProbeTargets::default())
 }
 
-/// Parse DNS response and extract A record IPs.
-fn parse_dns_response(data: &[u8]) -> Vec<String> {
-    let mut ips = Vec::new();
-
-    if data.len() < 12 {
-        return ips;
-    }
-
-    let answer_count = u16::from_be_bytes([data[6], data[7]]) as usize;
-    if answer_count == 0 {
-        return ips;
-    }
-
-    // Skip header (12 bytes) and question section
-    let mut pos = 12;
-
-    // Skip question section (QNAME + QTYPE + QCLASS)
-    loop {
-        if pos >= data.len() {
-            return ips;
-        }
-        let b = data[pos];
-        if b == 0 {
-            pos += 1;
-            break;
-        }
-        if (b & 0xC0) == 0xC0 {
-            // Compression pointer — 2 bytes
-            pos += 2;
-            break;
-        }
-        let label_len = b as usize;
-        pos += 1 + label_len;
-    }
-    // Skip QTYPE (2) and QCLASS (2)
-    if pos + 4 > data.len() {
-        return ips;
-    }
-    pos += 4;
-
-    // Parse answers
-    for _ in 0..answer_count {
-        if pos >= data.len() {
-            break;
-        }
-
-        // Name (may be a compression pointer)
-        if pos >= data.len() {
-            break;
-        }
-        if (data[pos] & 0xC0) == 0xC0 {
-            // Compression pointer — 2 bytes
-            if pos + 2 > data.len() {
-                break;
-            }
-            pos += 2;
-        } else {
-            // Walk labels until null terminator
-            loop {
-                if pos >= data.len() {
-                    break;
-                }
-                let b = data[pos];
-                if b == 0 {
-                    pos += 1;
-                    break;
-                }
-                if (b & 0xC0) == 0xC0 {
-                    // Compression pointer inside name
-                    if pos + 2 > data.len() {
-                        break;
-                    }
-                    pos += 2;
-                    break;
-                }
-                let label_len = b as usize;
-                pos += 1 + label_len;
-            }
-        }
-
-        if pos + 10 > data.len() {
-            break;
-        }
-
-        let rtype = u16::from_be_bytes([data[pos], data[pos + 1]]);
-        let rdlength = u16::from_be_bytes([data[pos + 8], data[pos + 9]]) as usize;
-        pos += 10;
-
-        if pos + rdlength > data.len() {
-            break;
-        }
-
-        // Type A = 1, rdlength = 4
-        if rtype == 1 && rdlength == 4 {
-            let ip = format!(
-                "{}.{}.{}.{}",
-                data[pos],
-                data[pos + 1],
-                data[pos + 2],
-                data[pos + 3]
-            );
-            ips.push(ip);
-        }
-
-        pos += rdlength;
-    }
-
-    ips
+/// Run all 4 security checks, using `targets` for the network-facing ones.
+pub fn check_wifi_security_with_targets(targets: &ProbeTargets) -> WiFiSecurityReport {
+    check_wifi_security_with_backend(&SystemBackend, targets)
 }
 
-// ==================== Orchestrator ====================
-
-/// Get the SSID of the currently connected Wi-Fi network.
-fn get_current_ssid() -> Option<String> {
-    super::wifi::get_wifi_info().ssid
+/// Run all 4 security checks against `backend`.
+pub fn check_wifi_security_with_backend(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+) -> WiFiSecurityReport {
+    crate::runtime::block_on(check_wifi_security_async(backend, targets))
 }
 
-/// Run all 4 security checks and produce a report.
-pub fn check_wifi_security() -> WiFiSecurityReport {
-    let total_start = std::time::Instant::now();
-    println!("[security] ===== Starting WiFi security scan =====");
-
-    let ssid = get_current_ssid();
-    println!("[security] Network SSID: {:?}", ssid);
-
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map(|d| d.as_secs())
-        .unwrap_or(0);
+/// Async [`check_wifi_security_with_backend`].
+pub async fn check_wifi_security_async(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+) -> WiFiSecurityReport {
+    check_wifi_security_cancellable(backend, targets, &Cancellation::none())
+        .await
+        .value
+}
 
-    let encryption = check_encryption();
-    let evil_twin = check_evil_twin();
-    let arp = check_arp_spoofing();
-    let dns = check_dns_hijacking();
+/// [`check_wifi_security_async`] that stops when `cancel` fires.
+///
+/// The report holds the checks finished by then; the overall status is the
+/// worst of those.
+pub async fn check_wifi_security_cancellable(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    cancel: &Cancellation,
+) -> Partial<WiFiSecurityReport> {
+    async {
+        let total_start = std::time::Instant::now();
+        let mut completion = Completion::Complete;
+
+        let ssid = cancel
+            .step(&mut completion, async { blocking(|| backend.wifi_info()) })
+            .await
+            .and_then(|wifi| wifi.ssid);
+        // The SSID is left out on purpose: log files get attached to bug reports.
+        tracing::debug!(connected = ssid.is_some(), "starting Wi-Fi security scan");
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut checks = Vec::new();
+        checks.extend(
+            cancel
+                .step(&mut completion, async {
+                    blocking(|| backend.encryption_check())
+                })
+                .await,
+        );
+        checks.extend(
+            cancel
+                .step(&mut completion, async {
+                    blocking(|| backend.evil_twin_check())
+                })
+                .await,
+        );
+        checks.extend(
+            cancel
+                .step(&mut completion, async {
+                    blocking(|| check_arp_spoofing(backend))
+                })
+                .await,
+        );
+        checks.extend(
+            cancel
+                .step(&mut completion, check_dns_hijacking(backend, targets))
+                .await,
+        );
 
-    let checks = vec![encryption, evil_twin, arp, dns];
+        // Overall status = worst of all checks
+        let overall_status = checks
+            .iter()
+            .map(|c| c.status)
+            .max_by_key(|s| match s {
+                SecurityStatus::Safe => 0,
+                SecurityStatus::Warning => 1,
+                SecurityStatus::Danger => 2,
+            })
+            .unwrap_or(SecurityStatus::Safe);
 
-    // Overall status = worst of all checks
-    let overall_status = checks
-        .iter()
-        .map(|c| c.status)
-        .max_by_key(|s| match s {
-            SecurityStatus::Safe => 0,
-            SecurityStatus::Warning => 1,
-            SecurityStatus::Danger => 2,
-        })
-        .unwrap_or(SecurityStatus::Safe);
-
-    println!(
-        "[security] ===== Scan complete: {:?} in {:.1}ms =====",
-        overall_status,
-        total_start.elapsed().as_secs_f64() * 1000.0
-    );
+        tracing::info!(
+            status = ?overall_status,
+            ?completion,
+            duration_ms = total_start.elapsed().as_secs_f64() * 1000.0,
+            "Wi-Fi security scan complete"
+        );
 
-    WiFiSecurityReport {
-        checks,
-        overall_status,
-        network_ssid: ssid,
-        timestamp,
+        Partial::new(
+            WiFiSecurityReport {
+                checks,
+                overall_status,
+                network_ssid: ssid,
+                timestamp,
+            },
+            completion,
+        )
     }
+    .instrument(tracing::info_span!("wifi_security"))
+    .await
 }
 
 #[cfg(test)]
@@ -828,25 +1060,211 @@ mod tests {
         assert_eq!(*worst, SecurityStatus::Danger);
     }
 
+    #[tokio::test]
+    async fn test_cancelled_security_check_is_partial() {
+        use crate::cancel::CancelToken;
+
+        let backend = crate::backend::SimulatedBackend::healthy();
+        let token = CancelToken::new();
+        token.cancel();
+        let partial = check_wifi_security_cancellable(
+            &backend,
+            &ProbeTargets::default(),
+            &Cancellation::new(&token, None),
+        )
+        .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        assert!(partial.value.checks.is_empty());
+        assert_eq!(partial.value.overall_status, SecurityStatus::Safe);
+
+        let complete = check_wifi_security_cancellable(
+            &backend,
+            &ProbeTargets::default(),
+            &Cancellation::none(),
+        )
+        .await;
+        assert!(complete.is_complete());
+        assert_eq!(complete.value.checks.len(), 4);
+    }
+
+    /// A response to `query` carrying one A and one AAAA record.
+    fn response_to(query: &wire::Message) -> wire::Message {
+        let record = |data| wire::Record {
+            name: query.questions[0].name.clone(),
+            class: wire::CLASS_IN,
+            ttl: 60,
+            data,
+        };
+        let mut response = query.clone();
+        response.header.response = true;
+        response.answers = vec![
+            record(wire::RecordData::A([93, 184, 216, 34].into())),
+            record(wire::RecordData::Aaaa(
+                "2606:2800:220:1::1".parse().unwrap(),
+            )),
+        ];
+        response
+    }
+
+    #[tokio::test]
+    async fn test_resolve_direct_ignores_mismatched_responses() {
+        let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        let stand_in = tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+            let query = wire::Message::decode(&buf[..len]).unwrap();
+            assert_eq!(query.questions[0].qtype, wire::TYPE_A);
+            let answer = response_to(&query);
+            let mut spoofed = answer.clone();
+            spoofed.header.id ^= 0xffff;
+            spoofed.answers[0].data = wire::RecordData::A([10, 0, 0, 1].into());
+            let mut other_question = answer.clone();
+            other_question.questions[0].name = wire::Name::parse("example.org").unwrap();
+            for response in [spoofed, other_question, answer] {
+                server
+                    .send_to(&response.encode().unwrap(), peer)
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let ips = resolve_domain_direct_async("example.com", &addr).await;
+        stand_in.await.unwrap();
+        assert_eq!(ips, vec!["93.184.216.34", "2606:2800:220:1::1"]);
+    }
+
+    fn strings(ips: &[&str]) -> Vec<String> {
+        ips.iter().map(|ip| ip.to_string()).collect()
+    }
+
     #[test]
-    fn test_build_dns_query() {
-        let packet = build_dns_query("example.com");
-        assert!(packet.len() > 12);
-        // Check header: 1 question
-        assert_eq!(packet[4], 0x00);
-        assert_eq!(packet[5], 0x01);
+    fn test_agreement() {
+        let asns = HashMap::from([
+            ("104.16.1.1".to_string(), 13335),
+            ("104.16.2.2".to_string(), 13335),
+            ("203.0.113.5".to_string(), 64500),
+        ]);
+        let reference = strings(&["104.16.1.1"]);
+        assert_eq!(
+            agreement(&strings(&["104.16.1.1", "104.16.9.9"]), &reference, &asns),
+            Agreement::Same
+        );
+        assert_eq!(
+            agreement(&strings(&["104.16.2.2"]), &reference, &asns),
+            Agreement::SameNetwork
+        );
+        assert_eq!(
+            agreement(&strings(&["203.0.113.5"]), &reference, &asns),
+            Agreement::Different
+        );
+        // An address with no known ASN can't be excused as a CDN edge
+        assert_eq!(
+            agreement(&strings(&["104.16.3.3"]), &reference, &asns),
+            Agreement::Different
+        );
+        assert_eq!(agreement(&[], &reference, &asns), Agreement::Unknown);
+    }
+
+    fn answers(domain: &str, system: &[&str], trusted: &[&str], doh: &[&str]) -> DomainAnswers {
+        DomainAnswers {
+            domain: domain.to_string(),
+            system_v4: strings(system).into_iter().filter(|ip| is_v4(ip)).collect(),
+            system_v6: strings(system)
+                .into_iter()
+                .filter(|ip| !is_v4(ip))
+                .collect(),
+            trusted: strings(trusted),
+            doh: strings(doh),
+        }
     }
 
     #[test]
-    fn test_parse_dns_response_empty() {
-        let ips = parse_dns_response(&[]);
-        assert!(ips.is_empty());
+    fn test_hijacking_findings() {
+        let asns = HashMap::from([
+            ("104.16.1.1".to_string(), 13335),
+            ("104.16.2.2".to_string(), 13335),
+        ]);
+        let none: &[String] = &[];
+        let findings = |domains: &[DomainAnswers], nx_system: &[String]| {
+            hijacking_findings(domains, (nx_system, none, none), &asns, "1.1.1.1:53")
+        };
+
+        // Agreeing answers, and a CDN edge on the reference's network
+        let clean = [
+            answers(
+                "a.test",
+                &["93.184.216.34", "2001:db8::1"],
+                &["93.184.216.34"],
+                &["93.184.216.34", "2001:db8::1"],
+            ),
+            answers(
+                "cdn.test",
+                &["104.16.2.2"],
+                &["104.16.1.1"],
+                &["104.16.1.1"],
+            ),
+        ];
+        assert!(findings(&clean, none).is_empty());
+
+        // A rewritten AAAA record; DoH is the reference for both families
+        let v6 = [answers(
+            "a.test",
+            &["93.184.216.34", "2001:db8::66"],
+            &["93.184.216.34"],
+            &["93.184.216.34", "2001:db8::1"],
+        )];
+        assert_eq!(
+            findings(&v6, none),
+            vec!["mismatch:a.test system:2001:db8::66 trusted:2001:db8::1"]
+        );
+
+        // Port 53 answered by something other than the trusted server
+        let intercepted = [answers(
+            "a.test",
+            &["10.0.0.1"],
+            &["10.0.0.1"],
+            &["93.184.216.34"],
+        )];
+        assert_eq!(
+            findings(&intercepted, none),
+            vec![
+                "mismatch:a.test system:10.0.0.1 trusted:93.184.216.34",
+                "intercepted:1.1.1.1:53"
+            ]
+        );
+
+        // DoH blocked: plain UDP to the trusted server is the reference
+        let no_doh = [answers(
+            "a.test",
+            &["93.184.216.34"],
+            &["93.184.216.34"],
+            &[],
+        )];
+        assert!(findings(&no_doh, none).is_empty());
+
+        // A nonexistent name answered by the system resolver only
+        let ad_page = strings(&["198.51.100.7"]);
+        assert_eq!(
+            findings(&clean, &ad_page),
+            vec!["nxdomain_rewrite:198.51.100.7"]
+        );
+        // ...unless the references are down too
+        let down = [answers("a.test", &["93.184.216.34"], &[], &[])];
+        assert!(findings(&down, &ad_page).is_empty());
     }
 
     #[test]
-    fn test_parse_dns_response_short() {
-        let ips = parse_dns_response(&[0u8; 5]);
-        assert!(ips.is_empty());
+    fn test_addresses_to_classify_skips_private_answers() {
+        let domains = [
+            answers("a.test", &["10.0.0.99"], &["93.184.216.34"], &[]),
+            answers("cdn.test", &["104.16.2.2"], &["104.16.1.1"], &[]),
+            answers("b.test", &["93.184.216.34"], &["93.184.216.34"], &[]),
+        ];
+        assert_eq!(
+            addresses_to_classify(&domains),
+            BTreeSet::from(["104.16.1.1".to_string(), "104.16.2.2".to_string()])
+        );
     }
 
     #[test]
@@ -6,10 +6,11 @@
 use serde_json::{json, Value};
 
 use super::uri_parser::*;
+use crate::error::VpnError;
 
 /// Generate a complete sing-box config JSON for the given VPN protocol.
 /// Optional `log_path` writes sing-box logs to a file (useful for debugging elevated processes).
-pub fn generate_singbox_config(protocol: &VpnProtocol) -> Result<Value, String> {
+pub fn generate_singbox_config(protocol: &VpnProtocol) -> Result<Value, VpnError> {
     generate_singbox_config_with_log(protocol, None)
 }
 
@@ -17,7 +18,7 @@ pub fn generate_singbox_config(protocol: &VpnProtocol) -> Result<Value, String>
 pub fn generate_singbox_config_with_log(
     protocol: &VpnProtocol,
     log_path: Option<&str>,
-) -> Result<Value, String> {
+) -> Result<Value, VpnError> {
     let outbound = match protocol {
         VpnProtocol::Vless(p) => generate_vless_outbound(p),
         VpnProtocol::Vmess(p) => generate_vmess_outbound(p),
@@ -84,7 +85,7 @@ pub fn generate_singbox_config_with_log(
 
 // ==================== Protocol-Specific Outbound Generators ====================
 
-fn generate_vless_outbound(p: &VlessParams) -> Result<Value, String> {
+fn generate_vless_outbound(p: &VlessParams) -> Result<Value, VpnError> {
     let mut outbound = json!({
         "type": "vless",
         "tag": "proxy",
@@ -140,7 +141,7 @@ fn generate_vless_outbound(p: &VlessParams) -> Result<Value, String> {
     Ok(outbound)
 }
 
-fn generate_vmess_outbound(p: &VmessParams) -> Result<Value, String> {
+fn generate_vmess_outbound(p: &VmessParams) -> Result<Value, VpnError> {
     let mut outbound = json!({
         "type": "vmess",
         "tag": "proxy",
@@ -171,7 +172,7 @@ fn generate_vmess_outbound(p: &VmessParams) -> Result<Value, String> {
     Ok(outbound)
 }
 
-fn generate_ss_outbound(p: &ShadowsocksParams) -> Result<Value, String> {
+fn generate_ss_outbound(p: &ShadowsocksParams) -> Result<Value, VpnError> {
     let mut outbound = json!({
         "type": "shadowsocks",
         "tag": "proxy",
@@ -191,7 +192,7 @@ fn generate_ss_outbound(p: &ShadowsocksParams) -> Result<Value, String> {
     Ok(outbound)
 }
 
-fn generate_trojan_outbound(p: &TrojanParams) -> Result<Value, String> {
+fn generate_trojan_outbound(p: &TrojanParams) -> Result<Value, VpnError> {
     let mut outbound = json!({
         "type": "trojan",
         "tag": "proxy",
@@ -225,7 +226,7 @@ fn generate_trojan_outbound(p: &TrojanParams) -> Result<Value, String> {
     Ok(outbound)
 }
 
-fn generate_wireguard_outbound(p: &WireGuardParams) -> Result<Value, String> {
+fn generate_wireguard_outbound(p: &WireGuardParams) -> Result<Value, VpnError> {
     let mut outbound = json!({
         "type": "wireguard",
         "tag": "proxy",
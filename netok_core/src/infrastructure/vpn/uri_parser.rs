@@ -5,6 +5,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::error::VpnError;
+
 // ==================== Protocol Parameter Types ====================
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,7 +94,9 @@ pub struct WireGuardParams {
 // ==================== Main Parser ====================
 
 /// Parse a VPN URI string into a typed protocol configuration.
-pub fn parse_vpn_uri(uri: &str) -> Result<VpnProtocol, String> {
+///
+/// Error positions are byte offsets into the trimmed URI.
+pub fn parse_vpn_uri(uri: &str) -> Result<VpnProtocol, VpnError> {
     let trimmed = uri.trim();
     let lower = trimmed.to_lowercase();
 
@@ -107,10 +111,7 @@ pub fn parse_vpn_uri(uri: &str) -> Result<VpnProtocol, String> {
     } else if lower.starts_with("wg://") || lower.starts_with("wireguard://") {
         parse_wireguard(trimmed)
     } else {
-        Err(
-            "Unsupported VPN protocol. Supported: vless://, vmess://, ss://, trojan://, wg://"
-                .to_string(),
-        )
+        Err(VpnError::UnsupportedProtocol)
     }
 }
 
@@ -161,19 +162,19 @@ fn split_fragment(s: &str) -> (&str, Option<&str>) {
 
 /// Parse authority as `userinfo@host:port`.
 /// Returns (userinfo, host, port).
-fn parse_authority(authority: &str) -> Result<(String, String, u16), String> {
+fn parse_authority(authority: &str) -> Result<(String, String, u16), VpnError> {
     let (userinfo, hostport) = authority
         .split_once('@')
-        .ok_or_else(|| format!("Missing '@' in authority: {}", authority))?;
+        .ok_or_else(|| VpnError::parse(format!("Missing '@' in authority: {}", authority), 0))?;
 
-    let (host, port) = parse_host_port(hostport)?;
+    let (host, port) = parse_host_port(hostport).map_err(|e| e.offset(userinfo.len() + 1))?;
 
     Ok((userinfo.to_string(), host, port))
 }
 
 /// Parse `host:port` or `[ipv6]:port`.
 /// Strips any trailing path (e.g. `/` from `host:port/`).
-fn parse_host_port(s: &str) -> Result<(String, u16), String> {
+fn parse_host_port(s: &str) -> Result<(String, u16), VpnError> {
     // Strip trailing path component (e.g. "host:port/" → "host:port")
     let s = s.split('/').next().unwrap_or(s);
 
@@ -181,25 +182,25 @@ fn parse_host_port(s: &str) -> Result<(String, u16), String> {
     if s.starts_with('[') {
         let bracket_end = s
             .find(']')
-            .ok_or_else(|| "Missing closing bracket for IPv6 address".to_string())?;
+            .ok_or_else(|| VpnError::parse("Missing closing bracket for IPv6 address", 0))?;
         let host = &s[1..bracket_end];
         let rest = &s[bracket_end + 1..];
         let port_str = rest.strip_prefix(':').unwrap_or("443");
         let port = port_str
             .parse::<u16>()
-            .map_err(|_| format!("Invalid port: {}", port_str))?;
+            .map_err(|_| VpnError::parse(format!("Invalid port: {}", port_str), bracket_end + 2))?;
         return Ok((host.to_string(), port));
     }
 
     // Regular host:port
     let colon_idx = s
         .rfind(':')
-        .ok_or_else(|| format!("Missing port in: {}", s))?;
+        .ok_or_else(|| VpnError::parse(format!("Missing port in: {}", s), s.len()))?;
     let host = &s[..colon_idx];
     let port_str = &s[colon_idx + 1..];
     let port = port_str
         .parse::<u16>()
-        .map_err(|_| format!("Invalid port: {}", port_str))?;
+        .map_err(|_| VpnError::parse(format!("Invalid port: {}", port_str), colon_idx + 1))?;
     Ok((host.to_string(), port))
 }
 
@@ -214,11 +215,12 @@ fn non_empty(s: &str) -> Option<String> {
 // ==================== Protocol-Specific Parsers ====================
 
 /// Parse VLESS URI: `vless://uuid@server:port?params#fragment`
-fn parse_vless(uri: &str) -> Result<VpnProtocol, String> {
+fn parse_vless(uri: &str) -> Result<VpnProtocol, VpnError> {
     let rest = uri
         .strip_prefix("vless://")
         .or_else(|| uri.strip_prefix("VLESS://"))
-        .ok_or("Not a VLESS URI")?;
+        .ok_or_else(|| VpnError::parse("Not a VLESS URI", 0))?;
+    let offset = uri.len() - rest.len();
 
     let (before_frag, fragment) = split_fragment(rest);
 
@@ -227,7 +229,7 @@ fn parse_vless(uri: &str) -> Result<VpnProtocol, String> {
         None => (before_frag, None),
     };
 
-    let (uuid, server, port) = parse_authority(main_part)?;
+    let (uuid, server, port) = parse_authority(main_part).map_err(|e| e.offset(offset))?;
     let query = query_str.map(parse_query).unwrap_or_default();
 
     Ok(VpnProtocol::Vless(VlessParams {
@@ -257,35 +259,47 @@ fn parse_vless(uri: &str) -> Result<VpnProtocol, String> {
 }
 
 /// Parse VMess URI: `vmess://base64json`
-fn parse_vmess(uri: &str) -> Result<VpnProtocol, String> {
+fn parse_vmess(uri: &str) -> Result<VpnProtocol, VpnError> {
     let rest = uri
         .strip_prefix("vmess://")
         .or_else(|| uri.strip_prefix("VMESS://"))
-        .ok_or("Not a VMess URI")?;
+        .ok_or_else(|| VpnError::parse("Not a VMess URI", 0))?;
+    let offset = uri.len() - rest.len();
 
     let (b64, fragment) = split_fragment(rest);
 
     // Decode base64
-    let decoded = base64_decode(b64)?;
-    let json: serde_json::Value =
-        serde_json::from_str(&decoded).map_err(|e| format!("Invalid VMess JSON: {}", e))?;
+    let decoded = base64_decode(b64).map_err(|e| e.offset(offset))?;
+    let json: serde_json::Value = serde_json::from_str(&decoded).map_err(|e| VpnError::Parse {
+        message: format!("Invalid VMess JSON: {}", e),
+        position: None,
+    })?;
+    let missing = |field| VpnError::MissingField {
+        protocol: "VMess",
+        field,
+    };
+    let invalid_port = |value: String| VpnError::Parse {
+        message: format!("VMess: invalid port: {}", value),
+        position: None,
+    };
 
     let server = json["add"]
         .as_str()
-        .ok_or("VMess: missing 'add' (server address)")?
+        .ok_or_else(|| missing("add"))?
         .to_string();
 
     let port = match &json["port"] {
-        serde_json::Value::Number(n) => n.as_u64().ok_or("VMess: invalid port")? as u16,
-        serde_json::Value::String(s) => s
-            .parse::<u16>()
-            .map_err(|_| format!("VMess: invalid port: {}", s))?,
-        _ => return Err("VMess: missing 'port'".to_string()),
+        serde_json::Value::Number(n) => n
+            .as_u64()
+            .and_then(|p| u16::try_from(p).ok())
+            .ok_or_else(|| invalid_port(n.to_string()))?,
+        serde_json::Value::String(s) => s.parse::<u16>().map_err(|_| invalid_port(s.clone()))?,
+        _ => return Err(missing("port")),
     };
 
     let uuid = json["id"]
         .as_str()
-        .ok_or("VMess: missing 'id' (UUID)")?
+        .ok_or_else(|| missing("id"))?
         .to_string();
 
     let alter_id = match &json["aid"] {
@@ -318,11 +332,12 @@ fn parse_vmess(uri: &str) -> Result<VpnProtocol, String> {
 ///
 /// Format 1 (SIP002): `ss://base64(method:password)@server:port#fragment`
 /// Format 2 (legacy): `ss://base64(method:password@server:port)#fragment`
-fn parse_shadowsocks(uri: &str) -> Result<VpnProtocol, String> {
+fn parse_shadowsocks(uri: &str) -> Result<VpnProtocol, VpnError> {
     let rest = uri
         .strip_prefix("ss://")
         .or_else(|| uri.strip_prefix("SS://"))
-        .ok_or("Not a Shadowsocks URI")?;
+        .ok_or_else(|| VpnError::parse("Not a Shadowsocks URI", 0))?;
+    let offset = uri.len() - rest.len();
 
     let (before_frag, fragment) = split_fragment(rest);
 
@@ -337,13 +352,17 @@ fn parse_shadowsocks(uri: &str) -> Result<VpnProtocol, String> {
             None => (after_at, None),
         };
 
-        let (server, port) = parse_host_port(hostport_str)?;
+        let (server, port) =
+            parse_host_port(hostport_str).map_err(|e| e.offset(offset + at_idx + 1))?;
 
         // Decode userinfo
-        let userinfo = base64_decode(userinfo_b64)?;
-        let (method, password) = userinfo
-            .split_once(':')
-            .ok_or("SS: invalid userinfo format, expected method:password")?;
+        let userinfo = base64_decode(userinfo_b64).map_err(|e| e.offset(offset))?;
+        let (method, password) = userinfo.split_once(':').ok_or_else(|| {
+            VpnError::parse(
+                "SS: invalid userinfo format, expected method:password",
+                offset,
+            )
+        })?;
 
         let query = query_str.map(parse_query).unwrap_or_default();
 
@@ -359,12 +378,14 @@ fn parse_shadowsocks(uri: &str) -> Result<VpnProtocol, String> {
     }
 
     // Legacy format: ss://base64(method:password@server:port)
-    let decoded = base64_decode(before_frag)?;
-    let (method_pass, hostport) = decoded.split_once('@').ok_or("SS legacy: missing '@'")?;
+    let decoded = base64_decode(before_frag).map_err(|e| e.offset(offset))?;
+    let (method_pass, hostport) = decoded
+        .split_once('@')
+        .ok_or_else(|| VpnError::parse("SS legacy: missing '@'", offset))?;
     let (method, password) = method_pass
         .split_once(':')
-        .ok_or("SS legacy: invalid method:password")?;
-    let (server, port) = parse_host_port(hostport)?;
+        .ok_or_else(|| VpnError::parse("SS legacy: invalid method:password", offset))?;
+    let (server, port) = parse_host_port(hostport).map_err(VpnError::without_position)?;
 
     Ok(VpnProtocol::Shadowsocks(ShadowsocksParams {
         method: method.to_string(),
@@ -378,11 +399,12 @@ fn parse_shadowsocks(uri: &str) -> Result<VpnProtocol, String> {
 }
 
 /// Parse Trojan URI: `trojan://password@server:port?params#fragment`
-fn parse_trojan(uri: &str) -> Result<VpnProtocol, String> {
+fn parse_trojan(uri: &str) -> Result<VpnProtocol, VpnError> {
     let rest = uri
         .strip_prefix("trojan://")
         .or_else(|| uri.strip_prefix("TROJAN://"))
-        .ok_or("Not a Trojan URI")?;
+        .ok_or_else(|| VpnError::parse("Not a Trojan URI", 0))?;
+    let offset = uri.len() - rest.len();
 
     let (before_frag, fragment) = split_fragment(rest);
 
@@ -391,7 +413,7 @@ fn parse_trojan(uri: &str) -> Result<VpnProtocol, String> {
         None => (before_frag, None),
     };
 
-    let (password, server, port) = parse_authority(main_part)?;
+    let (password, server, port) = parse_authority(main_part).map_err(|e| e.offset(offset))?;
     let query = query_str.map(parse_query).unwrap_or_default();
 
     Ok(VpnProtocol::Trojan(TrojanParams {
@@ -415,13 +437,14 @@ fn parse_trojan(uri: &str) -> Result<VpnProtocol, String> {
 }
 
 /// Parse WireGuard URI: `wg://privatekey@server:port?publickey=KEY&address=ADDR#fragment`
-fn parse_wireguard(uri: &str) -> Result<VpnProtocol, String> {
+fn parse_wireguard(uri: &str) -> Result<VpnProtocol, VpnError> {
     let rest = uri
         .strip_prefix("wg://")
         .or_else(|| uri.strip_prefix("WG://"))
         .or_else(|| uri.strip_prefix("wireguard://"))
         .or_else(|| uri.strip_prefix("WIREGUARD://"))
-        .ok_or("Not a WireGuard URI")?;
+        .ok_or_else(|| VpnError::parse("Not a WireGuard URI", 0))?;
+    let offset = uri.len() - rest.len();
 
     let (before_frag, fragment) = split_fragment(rest);
 
@@ -430,14 +453,17 @@ fn parse_wireguard(uri: &str) -> Result<VpnProtocol, String> {
         None => (before_frag, None),
     };
 
-    let (private_key, server, port) = parse_authority(main_part)?;
+    let (private_key, server, port) = parse_authority(main_part).map_err(|e| e.offset(offset))?;
     let query = query_str.map(parse_query).unwrap_or_default();
 
     let peer_public_key = query
         .get("publickey")
         .or_else(|| query.get("peer"))
         .cloned()
-        .ok_or("WireGuard: missing 'publickey' parameter")?;
+        .ok_or(VpnError::MissingField {
+            protocol: "WireGuard",
+            field: "publickey",
+        })?;
 
     let local_address = query
         .get("address")
@@ -465,8 +491,11 @@ fn parse_wireguard(uri: &str) -> Result<VpnProtocol, String> {
 // ==================== Base64 ====================
 
 /// Decode base64 (standard or URL-safe, with or without padding).
-fn base64_decode(input: &str) -> Result<String, String> {
+///
+/// Error positions are offsets into `input`.
+fn base64_decode(input: &str) -> Result<String, VpnError> {
     let s = input.trim();
+    let leading = input.len() - input.trim_start().len();
 
     // Replace URL-safe characters
     let normalized: String = s
@@ -486,37 +515,50 @@ fn base64_decode(input: &str) -> Result<String, String> {
     };
 
     // Decode using a simple base64 decoder
-    decode_base64_bytes(&padded)
-        .and_then(|bytes| String::from_utf8(bytes).map_err(|e| format!("Invalid UTF-8: {}", e)))
+    let bytes = decode_base64_bytes(&padded).map_err(|e| e.offset(leading))?;
+    String::from_utf8(bytes).map_err(|e| VpnError::Parse {
+        message: format!("Invalid UTF-8: {}", e),
+        position: None,
+    })
 }
 
-fn decode_base64_bytes(input: &str) -> Result<Vec<u8>, String> {
+fn decode_base64_bytes(input: &str) -> Result<Vec<u8>, VpnError> {
     let mut output = Vec::with_capacity(input.len() * 3 / 4);
 
-    let chars: Vec<u8> = input
+    // Keep each byte's original index for error positions
+    let chars: Vec<(usize, u8)> = input
         .bytes()
-        .filter(|&b| b != b'\n' && b != b'\r' && b != b' ')
+        .enumerate()
+        .filter(|&(_, b)| b != b'\n' && b != b'\r' && b != b' ')
         .collect();
 
     if !chars.len().is_multiple_of(4) {
-        return Err(format!(
-            "Invalid base64 length: {} (must be multiple of 4)",
-            chars.len()
-        ));
+        return Err(VpnError::Parse {
+            message: format!(
+                "Invalid base64 length: {} (must be multiple of 4)",
+                chars.len()
+            ),
+            position: None,
+        });
     }
 
+    let val = |(pos, c): (usize, u8)| {
+        b64_val(c)
+            .ok_or_else(|| VpnError::parse(format!("Invalid base64 character: {}", c as char), pos))
+    };
+
     for chunk in chars.chunks(4) {
-        let a = b64_val(chunk[0])?;
-        let b = b64_val(chunk[1])?;
+        let a = val(chunk[0])?;
+        let b = val(chunk[1])?;
 
         output.push((a << 2) | (b >> 4));
 
-        if chunk[2] != b'=' {
-            let c = b64_val(chunk[2])?;
+        if chunk[2].1 != b'=' {
+            let c = val(chunk[2])?;
             output.push(((b & 0x0F) << 4) | (c >> 2));
 
-            if chunk[3] != b'=' {
-                let d = b64_val(chunk[3])?;
+            if chunk[3].1 != b'=' {
+                let d = val(chunk[3])?;
                 output.push(((c & 0x03) << 6) | d);
             }
         }
@@ -525,14 +567,14 @@ fn decode_base64_bytes(input: &str) -> Result<Vec<u8>, String> {
     Ok(output)
 }
 
-fn b64_val(c: u8) -> Result<u8, String> {
+fn b64_val(c: u8) -> Option<u8> {
     match c {
-        b'A'..=b'Z' => Ok(c - b'A'),
-        b'a'..=b'z' => Ok(c - b'a' + 26),
-        b'0'..=b'9' => Ok(c - b'0' + 52),
-        b'+' => Ok(62),
-        b'/' => Ok(63),
-        _ => Err(format!("Invalid base64 character: {}", c as char)),
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
     }
 }
 
@@ -686,7 +728,39 @@ mod tests {
     #[test]
     fn test_parse_unsupported_protocol() {
         let result = parse_vpn_uri("http://google.com");
-        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), VpnError::UnsupportedProtocol);
+    }
+
+    #[test]
+    fn test_parse_error_positions() {
+        // Invalid port: points at the port digits
+        let uri = "trojan://pass@example.com:99999";
+        let err = parse_vpn_uri(uri).unwrap_err();
+        assert_eq!(err.position(), Some(uri.find("99999").unwrap()));
+        assert_eq!(err.kind(), crate::error::ErrorKind::Parse);
+
+        // Missing port: points at the end of the host
+        let uri = "  vless://uuid@example.com?type=tcp";
+        let err = parse_vpn_uri(uri).unwrap_err();
+        assert_eq!(err.position(), Some(uri.trim().find('?').unwrap()));
+
+        // Bad base64 character in the VMess payload
+        let uri = "vmess://eyJh*GQ";
+        let err = parse_vpn_uri(uri).unwrap_err();
+        assert_eq!(err.position(), Some(uri.find('*').unwrap()));
+    }
+
+    #[test]
+    fn test_parse_missing_field() {
+        let err = parse_vpn_uri("wg://key@example.com:51820?address=10.0.0.2/32").unwrap_err();
+        assert_eq!(
+            err,
+            VpnError::MissingField {
+                protocol: "WireGuard",
+                field: "publickey"
+            }
+        );
+        assert_eq!(err.to_string(), "WireGuard: missing 'publickey'");
     }
 
     #[test]
@@ -1,7 +1,9 @@
 //! Wi-Fi information retrieval.
 
+use serde::{Deserialize, Serialize};
+
 /// State of the Wi-Fi adapter as reported by the WLAN API.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum WifiAdapterState {
     /// No wireless interface found on the system
     #[default]
@@ -18,7 +20,7 @@ pub enum WifiAdapterState {
 }
 
 /// Detailed Wi-Fi connection information from the WLAN API.
-#[derive(Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct WifiDetails {
     pub ssid: Option<String>,
     pub rssi: Option<i32>,
@@ -127,6 +129,10 @@ pub fn get_wifi_info() -> WifiDetails {
         let interface_ptr = list.InterfaceInfo.as_ptr();
         for i in 0..list.dwNumberOfItems as usize {
             let interface = &*interface_ptr.add(i);
+            crate::capture::record_raw(|| crate::capture::RawOutput::Wlan {
+                call: "WlanEnumInterfaces".to_string(),
+                fields: serde_json::json!({ "index": i, "state": interface.isState.0 }),
+            });
 
             // Get interface description (UTF-16 string)
             // SAFETY: Interface description is a fixed-size array of u16 (UTF-16)
@@ -218,6 +224,20 @@ pub fn get_wifi_info() -> WifiDetails {
                         details.current_phy_type = Some(phy_type as u32);
                     }
 
+                    crate::capture::record_raw(|| crate::capture::RawOutput::Wlan {
+                        call: "WlanQueryInterface(current_connection)".to_string(),
+                        fields: serde_json::json!({
+                            "ssid": crate::capture::hex(
+                                &attrs.wlanAssociationAttributes.dot11Ssid.ucSSID
+                                    [..ssid_len.min(32)]
+                            ),
+                            "signal_quality": quality,
+                            "tx_rate_kbps": tx,
+                            "bssid": crate::capture::hex(&connected_bssid),
+                            "phy_type": phy_type,
+                        }),
+                    });
+
                     // SAFETY: Free memory allocated by WlanQueryInterface
                     // - connection_attrs is non-null and was allocated by Windows API
                     WlanFreeMemory(connection_attrs as *const core::ffi::c_void);
@@ -250,6 +270,15 @@ pub fn get_wifi_info() -> WifiDetails {
                             let entry = &*bss_entry_ptr.add(j);
                             if entry.dot11Bssid == connected_bssid {
                                 let freq = entry.ulChCenterFrequency;
+                                crate::capture::record_raw(|| crate::capture::RawOutput::Wlan {
+                                    call: "WlanGetNetworkBssList".to_string(),
+                                    fields: serde_json::json!({
+                                        "bssid": crate::capture::hex(&entry.dot11Bssid),
+                                        "center_frequency_khz": freq,
+                                        "rssi": entry.lRssi,
+                                        "link_quality": entry.uLinkQuality,
+                                    }),
+                                });
                                 if freq > 0 {
                                     details.channel_frequency_khz = Some(freq);
                                 }
@@ -10,35 +10,199 @@
 //! - **infrastructure**: Platform-specific implementations (Wi-Fi, gateway, DNS)
 //! - **diagnostics**: Orchestration logic that combines infrastructure to produce results
 //!
+//! plus **monitor**, a background loop built on the diagnostics probes,
+//! **history**, a local store of past results, **diff**, which compares them,
+//! **report**, which exports them, and **redact**, which strips identifying
+//! data before sharing. **backend** is the seam between diagnostics and the
+//! machine: the real system or a scripted simulation. **capture** records a
+//! backend's answers and the raw platform output behind them, and replays them
+//! offline. **metrics** renders a collection run as OpenMetrics text.
+//! **cancel** lets long-running operations be stopped by a token or a deadline,
+//! returning partial results. **dns_benchmark** times many queries against the
+//! current resolver and the built-in providers and recommends the fastest.
+//! **dns_catalog** holds the built-in DNS providers as data, plus the user's
+//! saved presets. **dnssec** checks whether resolvers validate DNSSEC.
+//! **dns_leak** finds which resolvers handle this machine's queries, to catch
+//! DNS bypassing a VPN. **dns_filtering** checks that a filtering provider
+//! blocks what it promises to, and that lookups actually go through it.
+//! **resolver_identity** identifies the provider that really answers, even
+//! behind a router or Pi-hole forwarding to it. **settings** loads and saves
+//! the settings file. **i18n** looks up text in the UI's locale files, for
+//! reports in the user's language.
+//!
+//! # Async API
+//!
+//! Network probes (DNS, HTTP, geolocation, reverse lookups) are async and run
+//! on tokio. Every check has an `*_async` form that can share the caller's
+//! runtime, be composed with `tokio::join!` and be cancelled by dropping it;
+//! the blocking functions are thin wrappers that drive the async ones on a
+//! shared internal runtime. Platform reads that shell out stay blocking and are
+//! moved off the async workers.
+//!
 //! # Public API
 //!
 //! All types and functions are re-exported at the crate root for backward compatibility.
+//! Fallible APIs return the typed errors from the **error** module.
 //! Consumers can use `netok_core::*` without knowing the internal module structure.
 
+mod backend;
 mod brand_mapping;
+mod cancel;
+mod capture;
 mod diagnostics;
+mod diff;
+mod dns_benchmark;
+mod dns_catalog;
+mod dns_filtering;
+mod dns_leak;
+mod dnssec;
 mod domain;
+mod error;
+mod history;
+mod i18n;
 mod infrastructure;
+mod metrics;
+mod monitor;
 mod oui_database;
+mod redact;
+mod report;
+mod resolver_identity;
+mod runtime;
+mod settings;
+#[cfg(test)]
+mod test_util;
 
 // Re-export all domain types at crate root (backward compatibility)
 pub use domain::{
-    get_default_settings, ComputerInfo, ConnectionType, DeviceType, DiagnosticResult,
-    DiagnosticScenario, DiagnosticSeverity, DiagnosticsSnapshot, DnsProvider, InternetInfo,
-    NetworkDevice, NetworkInfo, NodeId, NodeInfo, RouterInfo, Settings, Status,
+    get_default_settings, ComputerInfo, ConnectionType, DeviceType, DhcpInfo, DiagnosticResult,
+    DiagnosticScenario, DiagnosticSeverity, DiagnosticsSnapshot, DnsClass, DnsProvider,
+    DnsTransport, DnssecStatus, InternetInfo, NetworkDevice, NetworkInfo, NodeId, NodeInfo,
+    ProbeTargets, RouterInfo, Settings, Status,
+};
+
+// Re-export error types
+pub use error::{
+    CaptureError, DnsError, ErrorKind, GeoIpError, PresetError, SettingsError, StorageError,
+    VpnError,
 };
 
 // Re-export diagnostics functions
 pub use diagnostics::{
-    check_computer, check_internet, check_network, check_router, detect_dns_provider,
-    get_computer_info, get_internet_info, get_network_info, get_router_info, lookup_ip_location,
-    ping_dns_server, run_diagnostics, scan_network_devices, scan_network_devices_with_progress,
-    test_dns_server, IpInfoResponse,
+    check_computer, check_computer_async, check_computer_with_backend, check_internet,
+    check_internet_async, check_internet_with_backend, check_internet_with_targets, check_network,
+    check_network_async, check_network_with_backend, check_router, check_router_async,
+    check_router_with_backend, detect_dns_provider, diagnose_dhcp, diagnose_scenario,
+    get_computer_info, get_internet_info, get_internet_info_with_targets, get_network_info,
+    get_router_info, lookup_ip_location, lookup_ip_location_async, lookup_ip_location_with_targets,
+    run_diagnostics, run_diagnostics_async, run_diagnostics_cancellable,
+    run_diagnostics_with_backend, scan_network_devices, scan_network_devices_async,
+    scan_network_devices_cancellable, scan_network_devices_with_backend,
+    scan_network_devices_with_progress, IpInfoResponse,
 };
 
 // Re-export infrastructure functions used by bridge
 pub use infrastructure::{
-    build_dns_commands, check_wifi_security, flush_dns, get_current_dns, set_dns,
+    build_dns_commands, check_wifi_security, check_wifi_security_async,
+    check_wifi_security_cancellable, check_wifi_security_with_backend,
+    check_wifi_security_with_targets, flush_dns, get_current_dns, set_dns,
+};
+
+// Re-export DNS transports (plain, DoH, DoT)
+pub use infrastructure::dns_transport::{
+    check_dns_transports, check_dns_transports_async, ping_dns_server, ping_dns_server_async,
+    provider_endpoints, test_dns_server, test_dns_server_async, DnsEndpoint, DnsTransportStatus,
+};
+
+// Re-export the network backend and the infrastructure types it returns
+pub use backend::{
+    DnsConnection, InterfaceAddress, NetworkBackend, ProbeFuture, SimulatedBackend, SystemBackend,
+};
+pub use infrastructure::arp::ArpEntry;
+pub use infrastructure::mdns::MdnsDeviceInfo;
+pub use infrastructure::wifi::{WifiAdapterState, WifiDetails};
+
+// Re-export cancellation
+pub use cancel::{CancelToken, Cancellation, Completion, Partial};
+
+// Re-export DNS catalog and presets
+pub use dns_catalog::{
+    CatalogProvider, DnsCatalog, DnsFilter, DnsPreset, PresetStore, ProviderCategory,
+    CATALOG_FORMAT_VERSION, PRESET_FORMAT_VERSION,
+};
+
+// Re-export DNS benchmark
+pub use dns_benchmark::{
+    run_dns_benchmark, run_dns_benchmark_async, run_dns_benchmark_cancellable, BenchmarkOptions,
+    DnsBenchmark, ResolverBenchmark, MAX_RELIABLE_ERROR_RATE,
+};
+
+// Re-export DNSSEC check
+pub use dnssec::{
+    check_dnssec, check_dnssec_async, check_dnssec_cancellable, DnssecCheck, DnssecReport,
+};
+
+// Re-export DNS leak test
+pub use dns_leak::{
+    run_dns_leak_test, run_dns_leak_test_async, run_dns_leak_test_cancellable, DnsLeakReport,
+    LeakResolver, LeakStatus, LeakTestOptions, ResolverOwner,
+};
+
+// Re-export DNS filtering verification
+pub use dns_filtering::{
+    verify_dns_filtering, verify_dns_filtering_async, verify_dns_filtering_cancellable,
+    CategoryCheck, FilterResult, FilteringReport, FilteringStatus, DOH_CANARY,
+};
+
+// Re-export resolver identification
+pub use resolver_identity::{
+    identify_resolver, identify_resolver_async, identify_resolver_cancellable, IdentityConfidence,
+    IdentityEvidence, IdentityMethod, ResolverIdentity,
+};
+
+// Re-export probe capture and replay
+pub use capture::{
+    load_capture, load_capture_async, save_capture, save_capture_async, Capture, ProbeCall,
+    RawOutput, RecordingBackend, ReplayBackend, CAPTURE_FORMAT_VERSION,
+};
+
+// Re-export privacy redaction
+pub use redact::{RedactionLevel, Redactor};
+
+// Re-export UI translations
+pub use i18n::I18n;
+
+// Re-export report export
+pub use report::{
+    render_html, render_json, render_markdown, render_report, save_report, Report, ReportFormat,
+    REPORT_SCHEMA_VERSION,
+};
+
+// Re-export snapshot diff
+pub use diff::{
+    diff_devices, diff_security_reports, diff_snapshots, diff_snapshots_with, DiffOptions,
+    SnapshotChange,
+};
+
+// Re-export settings file
+pub use settings::{load_settings, SettingsStore};
+
+// Re-export history store
+pub use history::{
+    HistoryEntry, HistoryKind, HistoryQuery, HistoryRecord, HistoryStore, NetworkKey,
+    RetentionPolicy,
+};
+
+// Re-export OpenMetrics export
+pub use metrics::{
+    collect_metrics_async, collect_metrics_with_backend, render_openmetrics, MetricsSample,
+    ResolverLatency, OPENMETRICS_CONTENT_TYPE,
+};
+
+// Re-export connectivity monitor
+pub use monitor::{
+    probe_connectivity, probe_connectivity_async, start_monitor, ConnectivityLayer,
+    MonitorCallback, MonitorConfig, MonitorEvent, MonitorHandle, Outage, OutageTracker,
+    ProbeSample,
 };
 
 // Re-export security types
@@ -184,93 +348,114 @@ mod tests {
     #[test]
     fn test_dns_provider_cloudflare_standard() {
         let provider = detect_dns_provider(&["1.1.1.1".to_string(), "1.0.0.1".to_string()]);
-        assert!(matches!(provider, DnsProvider::Cloudflare));
+        assert_eq!(provider, DnsProvider::Catalog("Cloudflare".into()));
     }
 
     #[test]
     fn test_dns_provider_cloudflare_malware() {
         let provider = detect_dns_provider(&["1.1.1.2".to_string(), "1.0.0.2".to_string()]);
-        assert!(matches!(provider, DnsProvider::CloudflareMalware));
+        assert_eq!(provider, DnsProvider::Catalog("CloudflareMalware".into()));
     }
 
     #[test]
     fn test_dns_provider_cloudflare_family() {
         let provider = detect_dns_provider(&["1.1.1.3".to_string(), "1.0.0.3".to_string()]);
-        assert!(matches!(provider, DnsProvider::CloudflareFamily));
+        assert_eq!(provider, DnsProvider::Catalog("CloudflareFamily".into()));
     }
 
     #[test]
     fn test_dns_provider_google() {
         let provider = detect_dns_provider(&["8.8.8.8".to_string(), "8.8.4.4".to_string()]);
-        assert!(matches!(provider, DnsProvider::Google));
+        assert_eq!(provider, DnsProvider::Catalog("Google".into()));
     }
 
     #[test]
     fn test_dns_provider_adguard_variants() {
         let provider =
             detect_dns_provider(&["94.140.14.14".to_string(), "94.140.15.15".to_string()]);
-        assert!(matches!(provider, DnsProvider::AdGuard));
+        assert_eq!(provider, DnsProvider::Catalog("AdGuard".into()));
 
         let provider_nf =
             detect_dns_provider(&["94.140.14.140".to_string(), "94.140.14.141".to_string()]);
-        assert!(matches!(provider_nf, DnsProvider::AdGuardNonFiltering));
+        assert_eq!(
+            provider_nf,
+            DnsProvider::Catalog("AdGuardNonFiltering".into())
+        );
 
         let provider_family =
             detect_dns_provider(&["94.140.14.15".to_string(), "94.140.15.16".to_string()]);
-        assert!(matches!(provider_family, DnsProvider::AdGuardFamily));
+        assert_eq!(
+            provider_family,
+            DnsProvider::Catalog("AdGuardFamily".into())
+        );
     }
 
     #[test]
     fn test_dns_provider_quad9_variants() {
         let provider = detect_dns_provider(&["9.9.9.9".to_string(), "149.112.112.112".to_string()]);
-        assert!(matches!(provider, DnsProvider::Quad9Recommended));
+        assert_eq!(provider, DnsProvider::Catalog("Quad9Recommended".into()));
 
         let provider_ecs =
             detect_dns_provider(&["9.9.9.11".to_string(), "149.112.112.11".to_string()]);
-        assert!(matches!(provider_ecs, DnsProvider::Quad9SecuredEcs));
+        assert_eq!(provider_ecs, DnsProvider::Catalog("Quad9SecuredEcs".into()));
 
         let provider_unsec =
             detect_dns_provider(&["9.9.9.10".to_string(), "149.112.112.10".to_string()]);
-        assert!(matches!(provider_unsec, DnsProvider::Quad9Unsecured));
+        assert_eq!(
+            provider_unsec,
+            DnsProvider::Catalog("Quad9Unsecured".into())
+        );
     }
 
     #[test]
     fn test_dns_provider_opendns_variants() {
         let provider =
             detect_dns_provider(&["208.67.222.123".to_string(), "208.67.220.123".to_string()]);
-        assert!(matches!(provider, DnsProvider::OpenDnsFamilyShield));
+        assert_eq!(provider, DnsProvider::Catalog("OpenDnsFamilyShield".into()));
 
         let provider_home =
             detect_dns_provider(&["208.67.222.222".to_string(), "208.67.220.220".to_string()]);
-        assert!(matches!(provider_home, DnsProvider::OpenDnsHome));
+        assert_eq!(provider_home, DnsProvider::Catalog("OpenDnsHome".into()));
     }
 
     #[test]
     fn test_dns_provider_dns4eu_variants() {
         let provider_protective = detect_dns_provider(&["86.54.11.1".to_string()]);
-        assert!(matches!(provider_protective, DnsProvider::Dns4EuProtective));
+        assert_eq!(
+            provider_protective,
+            DnsProvider::Catalog("Dns4EuProtective".into())
+        );
 
         let provider_child = detect_dns_provider(&["86.54.11.12".to_string()]);
-        assert!(matches!(provider_child, DnsProvider::Dns4EuProtectiveChild));
+        assert_eq!(
+            provider_child,
+            DnsProvider::Catalog("Dns4EuProtectiveChild".into())
+        );
 
         let provider_ad = detect_dns_provider(&["86.54.11.13".to_string()]);
-        assert!(matches!(provider_ad, DnsProvider::Dns4EuProtectiveAd));
+        assert_eq!(
+            provider_ad,
+            DnsProvider::Catalog("Dns4EuProtectiveAd".into())
+        );
 
         let provider_child_ad = detect_dns_provider(&["86.54.11.11".to_string()]);
-        assert!(matches!(
+        assert_eq!(
             provider_child_ad,
-            DnsProvider::Dns4EuProtectiveChildAd
-        ));
+            DnsProvider::Catalog("Dns4EuProtectiveChildAd".into())
+        );
 
         let provider_unfiltered = detect_dns_provider(&["86.54.11.100".to_string()]);
-        assert!(matches!(provider_unfiltered, DnsProvider::Dns4EuUnfiltered));
+        assert_eq!(
+            provider_unfiltered,
+            DnsProvider::Catalog("Dns4EuUnfiltered".into())
+        );
     }
 
     #[test]
     fn test_dns_provider_custom() {
         let provider = detect_dns_provider(&["1.2.3.4".to_string(), "5.6.7.8".to_string()]);
         match provider {
-            DnsProvider::Custom(primary, secondary, _, _) => {
+            DnsProvider::Custom(primary, secondary, ..) => {
                 assert_eq!(primary, "1.2.3.4");
                 assert_eq!(secondary, "5.6.7.8");
             }
@@ -404,6 +589,7 @@ mod tests {
             language: "en".to_string(),
             test_timeout_ms: 3000,
             dns_servers: vec!["1.1.1.1".to_string(), "8.8.8.8".to_string()],
+            probe_targets: ProbeTargets::default(),
         };
 
         let json = serde_json::to_string(&settings).unwrap();
@@ -0,0 +1,420 @@
+//! OpenMetrics export.
+//!
+//! `collect_metrics_with_backend` runs the diagnostics, a DNS latency probe of
+//! each configured resolver, the Wi-Fi security checks and a device scan, and
+//! `render_openmetrics` turns the result into the OpenMetrics text format for
+//! Prometheus and compatible scrapers. Collection is slow (the device scan
+//! alone takes seconds), so callers collect periodically and serve the last
+//! rendering.
+
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::NetworkBackend;
+use crate::diagnostics::{run_diagnostics_async, scan_network_devices_async};
+use crate::domain::{DeviceType, DiagnosticsSnapshot, NetworkDevice, NodeId, Settings, Status};
+use crate::infrastructure::security::{
+    check_wifi_security_async, SecurityCheckType, SecurityStatus, WiFiSecurityReport,
+};
+
+/// `Content-Type` of `render_openmetrics` output.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+// ==================== Domain Types ====================
+
+/// Round-trip time of one query to a configured DNS server.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ResolverLatency {
+    pub server: String,
+    /// `None` when the server returned no answer.
+    pub latency_ms: Option<u64>,
+}
+
+/// One collection run.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MetricsSample {
+    /// Unix timestamp (seconds) when collection finished.
+    pub collected_at: u64,
+    pub duration_ms: u64,
+    pub snapshot: DiagnosticsSnapshot,
+    pub resolvers: Vec<ResolverLatency>,
+    pub security: WiFiSecurityReport,
+    pub devices: Vec<NetworkDevice>,
+}
+
+/// Every device type, so absent types export 0 rather than disappearing.
+const DEVICE_TYPES: [DeviceType; 9] = [
+    DeviceType::Router,
+    DeviceType::Phone,
+    DeviceType::Computer,
+    DeviceType::Tablet,
+    DeviceType::Printer,
+    DeviceType::SmartTv,
+    DeviceType::GameConsole,
+    DeviceType::IoT,
+    DeviceType::Unknown,
+];
+
+// ==================== Collection ====================
+
+/// Run every check once against `backend`.
+pub fn collect_metrics_with_backend(
+    backend: &dyn NetworkBackend,
+    settings: &Settings,
+) -> MetricsSample {
+    crate::runtime::block_on(collect_metrics_async(backend, settings))
+}
+
+/// Async [`collect_metrics_with_backend`]; the resolvers are probed concurrently.
+pub async fn collect_metrics_async(
+    backend: &dyn NetworkBackend,
+    settings: &Settings,
+) -> MetricsSample {
+    let started = Instant::now();
+    let snapshot = run_diagnostics_async(backend, settings).await;
+    // A domain the hijacking check already queries directly
+    let domain = settings
+        .probe_targets
+        .hijack_test_domains
+        .first()
+        .map_or("example.com", String::as_str);
+    let resolvers = join_all(
+        snapshot
+            .network
+            .dns_servers
+            .iter()
+            .map(|server| async move {
+                let query_started = Instant::now();
+                let answered = !backend.resolve_via_async(domain, server).await.is_empty();
+                ResolverLatency {
+                    server: server.clone(),
+                    latency_ms: answered.then(|| query_started.elapsed().as_millis() as u64),
+                }
+            }),
+    )
+    .await;
+    let security = check_wifi_security_async(backend, &settings.probe_targets).await;
+    let devices = scan_network_devices_async(backend, None).await;
+
+    MetricsSample {
+        collected_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        duration_ms: started.elapsed().as_millis() as u64,
+        snapshot,
+        resolvers,
+        security,
+        devices,
+    }
+}
+
+// ==================== Rendering ====================
+
+fn node_label(id: NodeId) -> &'static str {
+    match id {
+        NodeId::Computer => "computer",
+        NodeId::Wifi => "network",
+        NodeId::RouterUpnp => "router",
+        NodeId::Dns => "dns",
+        NodeId::Internet => "internet",
+    }
+}
+
+fn status_value(status: Status) -> u8 {
+    match status {
+        Status::Ok => 0,
+        Status::Warn => 1,
+        Status::Fail => 2,
+        Status::Unknown => 3,
+    }
+}
+
+fn security_value(status: SecurityStatus) -> u8 {
+    match status {
+        SecurityStatus::Safe => 0,
+        SecurityStatus::Warning => 1,
+        SecurityStatus::Danger => 2,
+    }
+}
+
+fn check_label(check_type: SecurityCheckType) -> &'static str {
+    match check_type {
+        SecurityCheckType::Encryption => "encryption",
+        SecurityCheckType::EvilTwin => "evil_twin",
+        SecurityCheckType::ArpSpoofing => "arp_spoofing",
+        SecurityCheckType::DnsHijacking => "dns_hijacking",
+    }
+}
+
+fn device_type_label(device_type: DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Router => "router",
+        DeviceType::Phone => "phone",
+        DeviceType::Computer => "computer",
+        DeviceType::Tablet => "tablet",
+        DeviceType::Printer => "printer",
+        DeviceType::SmartTv => "smart_tv",
+        DeviceType::GameConsole => "game_console",
+        DeviceType::IoT => "iot",
+        DeviceType::Unknown => "unknown",
+    }
+}
+
+/// Label value with `\`, `"` and newlines escaped.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn seconds(ms: impl Into<u64>) -> String {
+    format!("{}", Duration::from_millis(ms.into()).as_secs_f64())
+}
+
+/// Writes metric families; each `family` call emits the metadata lines.
+struct Writer(String);
+
+impl Writer {
+    fn family(&mut self, name: &str, unit: Option<&str>, help: &str) {
+        let _ = writeln!(self.0, "# TYPE {} gauge", name);
+        if let Some(unit) = unit {
+            let _ = writeln!(self.0, "# UNIT {} {}", name, unit);
+        }
+        let _ = writeln!(self.0, "# HELP {} {}", name, help);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+            .collect();
+        if labels.is_empty() {
+            let _ = writeln!(self.0, "{} {}", name, value);
+        } else {
+            let _ = writeln!(self.0, "{}{{{}}} {}", name, labels.join(","), value);
+        }
+    }
+}
+
+/// Render `sample` in the OpenMetrics text format, ending with `# EOF`.
+pub fn render_openmetrics(sample: &MetricsSample) -> String {
+    let mut w = Writer(String::new());
+    let snapshot = &sample.snapshot;
+
+    w.family(
+        "netok_node_status",
+        None,
+        "Diagnostic node status (0 ok, 1 warn, 2 fail, 3 unknown).",
+    );
+    for node in &snapshot.nodes {
+        w.sample(
+            "netok_node_status",
+            &[("node", node_label(node.id))],
+            status_value(node.status),
+        );
+    }
+
+    w.family(
+        "netok_node_latency_seconds",
+        Some("seconds"),
+        "Time the node check took.",
+    );
+    for node in &snapshot.nodes {
+        if let Some(ms) = node.latency_ms {
+            w.sample(
+                "netok_node_latency_seconds",
+                &[("node", node_label(node.id))],
+                seconds(ms),
+            );
+        }
+    }
+
+    w.family(
+        "netok_dns_resolver_up",
+        None,
+        "Whether the configured DNS server answered (1) or not (0).",
+    );
+    for resolver in &sample.resolvers {
+        w.sample(
+            "netok_dns_resolver_up",
+            &[("server", &resolver.server)],
+            u8::from(resolver.latency_ms.is_some()),
+        );
+    }
+    w.family(
+        "netok_dns_resolver_latency_seconds",
+        Some("seconds"),
+        "Round-trip time of a query to the configured DNS server.",
+    );
+    for resolver in &sample.resolvers {
+        if let Some(ms) = resolver.latency_ms {
+            w.sample(
+                "netok_dns_resolver_latency_seconds",
+                &[("server", &resolver.server)],
+                seconds(ms),
+            );
+        }
+    }
+
+    w.family(
+        "netok_security_status",
+        None,
+        "Overall Wi-Fi security status (0 safe, 1 warning, 2 danger).",
+    );
+    w.sample(
+        "netok_security_status",
+        &[],
+        security_value(sample.security.overall_status),
+    );
+    w.family(
+        "netok_security_check_status",
+        None,
+        "Wi-Fi security check status (0 safe, 1 warning, 2 danger).",
+    );
+    for check in &sample.security.checks {
+        w.sample(
+            "netok_security_check_status",
+            &[("check", check_label(check.check_type))],
+            security_value(check.status),
+        );
+    }
+
+    w.family(
+        "netok_devices",
+        None,
+        "Devices found on the local network, by type.",
+    );
+    for device_type in DEVICE_TYPES {
+        let count = sample
+            .devices
+            .iter()
+            .filter(|d| d.device_type == device_type)
+            .count();
+        w.sample(
+            "netok_devices",
+            &[("type", device_type_label(device_type))],
+            count,
+        );
+    }
+
+    let network = &snapshot.network;
+    w.family("netok_wifi_rssi_dbm", Some("dbm"), "Wi-Fi signal strength.");
+    if let Some(rssi) = network.rssi {
+        w.sample(
+            "netok_wifi_rssi_dbm",
+            &[("ssid", network.ssid.as_deref().unwrap_or(""))],
+            rssi,
+        );
+    }
+    w.family(
+        "netok_wifi_link_speed_bits_per_second",
+        Some("bits_per_second"),
+        "Negotiated Wi-Fi link speed.",
+    );
+    if let Some(mbps) = network.link_speed_mbps {
+        w.sample(
+            "netok_wifi_link_speed_bits_per_second",
+            &[("ssid", network.ssid.as_deref().unwrap_or(""))],
+            u64::from(mbps) * 1_000_000,
+        );
+    }
+
+    w.family(
+        "netok_collect_timestamp_seconds",
+        Some("seconds"),
+        "Unix time the last collection finished.",
+    );
+    w.sample("netok_collect_timestamp_seconds", &[], sample.collected_at);
+    w.family(
+        "netok_collect_duration_seconds",
+        Some("seconds"),
+        "Time the last collection took.",
+    );
+    w.sample(
+        "netok_collect_duration_seconds",
+        &[],
+        seconds(sample.duration_ms),
+    );
+
+    w.0.push_str("# EOF\n");
+    w.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::SimulatedBackend;
+    use crate::domain::DiagnosticScenario;
+
+    fn healthy_sample() -> MetricsSample {
+        collect_metrics_with_backend(&SimulatedBackend::healthy(), &Settings::default())
+    }
+
+    #[test]
+    fn test_collect_healthy() {
+        let sample = healthy_sample();
+        assert_eq!(
+            sample.resolvers.len(),
+            sample.snapshot.network.dns_servers.len()
+        );
+        assert!(sample.resolvers.iter().all(|r| r.latency_ms.is_some()));
+        assert_eq!(sample.security.overall_status, SecurityStatus::Safe);
+        assert!(!sample.devices.is_empty());
+    }
+
+    #[test]
+    fn test_render_healthy() {
+        let text = render_openmetrics(&healthy_sample());
+
+        assert!(text.contains("netok_node_status{node=\"computer\"} 0\n"));
+        assert!(text.contains("netok_security_status 0\n"));
+        assert!(text.contains("netok_devices{type=\"router\"} 1\n"));
+        assert!(text.contains("netok_devices{type=\"printer\"} 0\n"));
+        assert!(text.contains("netok_wifi_rssi_dbm{ssid=\"Home_WiFi_5G\"} -45\n"));
+        assert!(text
+            .contains("netok_wifi_link_speed_bits_per_second{ssid=\"Home_WiFi_5G\"} 866000000\n"));
+        assert!(text.contains("# UNIT netok_node_latency_seconds seconds\n"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_render_failure() {
+        let backend = SimulatedBackend::scenario(DiagnosticScenario::WifiDisabled);
+        let text = render_openmetrics(&collect_metrics_with_backend(
+            &backend,
+            &Settings::default(),
+        ));
+
+        assert!(text.contains("netok_node_status{node=\"network\"} 2\n"));
+        // No Wi-Fi: the family is declared but has no samples
+        assert!(text.contains("# TYPE netok_wifi_rssi_dbm gauge\n"));
+        assert!(!text.contains("netok_wifi_rssi_dbm{"));
+    }
+
+    #[test]
+    fn test_every_family_has_metadata_before_samples() {
+        let text = render_openmetrics(&healthy_sample());
+        let mut declared = std::collections::HashSet::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let name = rest.split(' ').next().unwrap();
+                assert!(declared.insert(name.to_string()), "{} declared twice", name);
+            } else if !line.starts_with('#') {
+                let name = line.split(['{', ' ']).next().unwrap();
+                assert!(declared.contains(name), "{} has no TYPE line", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("line\nbreak"), "line\\nbreak");
+    }
+}
@@ -0,0 +1,489 @@
+//! Continuous connectivity monitor.
+//!
+//! Runs lightweight gateway/DNS/HTTP probes on an interval in a background
+//! thread, tracks state transitions and records outages with the layer that
+//! failed. Events are delivered through a callback so the caller can forward
+//! them to the UI.
+
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::diagnostics::{test_dns_async, test_http_async};
+use crate::domain::ProbeTargets;
+use crate::infrastructure::{get_default_gateway, ping_host};
+
+// ==================== Domain Types ====================
+
+/// Network layer probed by the monitor, ordered from nearest to farthest.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityLayer {
+    Gateway,
+    Dns,
+    Http,
+}
+
+/// Result of one round of monitor probes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProbeSample {
+    /// Unix timestamp (seconds) when the round started.
+    pub timestamp: u64,
+    pub gateway_ok: bool,
+    pub dns_ok: bool,
+    pub http_ok: bool,
+    /// Wall-clock time of the probe round.
+    pub latency_ms: u32,
+}
+
+impl ProbeSample {
+    /// The nearest layer that failed, or `None` when everything is up.
+    ///
+    /// A gateway failure explains DNS and HTTP failures, so it wins.
+    pub fn failing_layer(&self) -> Option<ConnectivityLayer> {
+        if !self.gateway_ok {
+            Some(ConnectivityLayer::Gateway)
+        } else if !self.dns_ok {
+            Some(ConnectivityLayer::Dns)
+        } else if !self.http_ok {
+            Some(ConnectivityLayer::Http)
+        } else {
+            None
+        }
+    }
+}
+
+/// A period during which one layer was down.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Outage {
+    /// Unix timestamp of the first failing sample.
+    pub started_at: u64,
+    /// Unix timestamp of the sample that ended it, `None` while ongoing.
+    pub ended_at: Option<u64>,
+    pub failing_layer: ConnectivityLayer,
+}
+
+impl Outage {
+    /// Outage length in seconds, `None` while ongoing.
+    pub fn duration_secs(&self) -> Option<u64> {
+        self.ended_at.map(|end| end.saturating_sub(self.started_at))
+    }
+}
+
+/// Event emitted by the monitor.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MonitorEvent {
+    /// A probe round finished.
+    Sample { sample: ProbeSample },
+    /// Confirmed transition to a failing layer.
+    OutageStarted { outage: Outage },
+    /// The outage ended, either by recovery or by a different layer failing.
+    OutageEnded { outage: Outage },
+}
+
+/// Monitor settings.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Delay between probe rounds.
+    pub interval_secs: u64,
+    /// Consecutive samples that must agree before a state change is accepted.
+    /// Filters out single dropped packets.
+    pub confirm_samples: u32,
+    /// Outages kept in memory, oldest dropped first.
+    pub max_outages: usize,
+    /// Timeout for the gateway ping.
+    pub gateway_timeout_ms: u32,
+    pub probe_targets: ProbeTargets,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            confirm_samples: 2,
+            max_outages: 500,
+            gateway_timeout_ms: 1000,
+            probe_targets: ProbeTargets::default(),
+        }
+    }
+}
+
+// ==================== Outage Tracking ====================
+
+/// State machine that turns probe samples into outages.
+///
+/// Pure logic — no I/O — so transitions can be tested with synthetic samples.
+#[derive(Debug, Clone)]
+pub struct OutageTracker {
+    confirm_samples: u32,
+    max_outages: usize,
+    /// Confirmed state: `None` = online.
+    state: Option<ConnectivityLayer>,
+    /// Candidate state, how many samples agreed so far, and when it was first seen.
+    pending: Option<(Option<ConnectivityLayer>, u32, u64)>,
+    outages: Vec<Outage>,
+}
+
+impl OutageTracker {
+    pub fn new(confirm_samples: u32, max_outages: usize) -> Self {
+        Self {
+            confirm_samples: confirm_samples.max(1),
+            max_outages,
+            state: None,
+            pending: None,
+            outages: Vec::new(),
+        }
+    }
+
+    /// Confirmed failing layer, `None` when online.
+    pub fn current_state(&self) -> Option<ConnectivityLayer> {
+        self.state
+    }
+
+    /// Recorded outages, oldest first. The last one may still be ongoing.
+    pub fn outages(&self) -> &[Outage] {
+        &self.outages
+    }
+
+    /// Feed one sample and return the resulting events.
+    pub fn observe(&mut self, sample: &ProbeSample) -> Vec<MonitorEvent> {
+        let mut events = vec![MonitorEvent::Sample {
+            sample: sample.clone(),
+        }];
+
+        let observed = sample.failing_layer();
+        if observed == self.state {
+            self.pending = None;
+            return events;
+        }
+
+        let (count, first_seen) = match self.pending {
+            Some((candidate, count, first_seen)) if candidate == observed => {
+                (count + 1, first_seen)
+            }
+            _ => (1, sample.timestamp),
+        };
+
+        if count < self.confirm_samples {
+            self.pending = Some((observed, count, first_seen));
+            return events;
+        }
+        self.pending = None;
+
+        // Close the running outage, if any
+        if self.state.is_some() {
+            if let Some(outage) = self.outages.last_mut() {
+                outage.ended_at = Some(first_seen);
+                tracing::info!(
+                    layer = ?outage.failing_layer,
+                    duration_secs = outage.duration_secs(),
+                    "outage ended"
+                );
+                events.push(MonitorEvent::OutageEnded {
+                    outage: outage.clone(),
+                });
+            }
+        }
+
+        if let Some(layer) = observed {
+            let outage = Outage {
+                started_at: first_seen,
+                ended_at: None,
+                failing_layer: layer,
+            };
+            tracing::warn!(layer = ?layer, started_at = first_seen, "outage started");
+            self.outages.push(outage.clone());
+            if self.outages.len() > self.max_outages {
+                let excess = self.outages.len() - self.max_outages;
+                self.outages.drain(..excess);
+            }
+            events.push(MonitorEvent::OutageStarted { outage });
+        }
+
+        self.state = observed;
+        events
+    }
+}
+
+// ==================== Probing ====================
+
+/// Check whether the default gateway answers.
+///
+/// Tries ICMP first; routers that drop pings are still counted as reachable
+/// if a TCP connection to DNS or HTTP is accepted or actively refused.
+fn probe_gateway(timeout_ms: u32) -> bool {
+    let Some(gateway) = get_default_gateway() else {
+        return false;
+    };
+
+    if ping_host(&gateway, timeout_ms) {
+        return true;
+    }
+
+    let Ok(ip) = gateway.parse::<std::net::IpAddr>() else {
+        return false;
+    };
+    let timeout = Duration::from_millis(u64::from(timeout_ms));
+    [53u16, 80].iter().any(|port| {
+        match std::net::TcpStream::connect_timeout(&std::net::SocketAddr::new(ip, *port), timeout) {
+            Ok(_) => true,
+            Err(e) => e.kind() == std::io::ErrorKind::ConnectionRefused,
+        }
+    })
+}
+
+/// Run one round of gateway, DNS and HTTP probes in parallel.
+pub fn probe_connectivity(config: &MonitorConfig) -> ProbeSample {
+    crate::runtime::block_on(probe_connectivity_async(config))
+}
+
+/// Async [`probe_connectivity`].
+pub async fn probe_connectivity_async(config: &MonitorConfig) -> ProbeSample {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let start = Instant::now();
+    let targets = &config.probe_targets;
+
+    // The gateway ping shells out, so it gets a blocking thread
+    let gateway_timeout_ms = config.gateway_timeout_ms;
+    let gateway = tokio::task::spawn_blocking(move || probe_gateway(gateway_timeout_ms));
+    let (gateway_ok, dns_ok, http_ok) =
+        tokio::join!(gateway, test_dns_async(targets), test_http_async(targets));
+    let gateway_ok = gateway_ok.unwrap_or(false);
+
+    ProbeSample {
+        timestamp,
+        gateway_ok,
+        dns_ok,
+        http_ok,
+        latency_ms: start.elapsed().as_millis() as u32,
+    }
+}
+
+// ==================== Background Runner ====================
+
+/// Callback invoked for every monitor event.
+pub type MonitorCallback = Box<dyn Fn(&MonitorEvent) + Send + Sync>;
+
+/// Handle to a running monitor. Dropping it stops the background thread.
+pub struct MonitorHandle {
+    stop_tx: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+    tracker: Arc<Mutex<OutageTracker>>,
+}
+
+impl MonitorHandle {
+    /// Confirmed failing layer, `None` when online.
+    pub fn current_state(&self) -> Option<ConnectivityLayer> {
+        self.tracker
+            .lock()
+            .map(|t| t.current_state())
+            .unwrap_or(None)
+    }
+
+    /// Outage timeline recorded so far, oldest first.
+    pub fn outages(&self) -> Vec<Outage> {
+        self.tracker
+            .lock()
+            .map(|t| t.outages().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Stop the monitor and wait for the current probe round to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the sender wakes the thread out of its interval wait
+        self.stop_tx.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MonitorHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Start probing in a background thread.
+///
+/// The first round runs immediately, then every `config.interval_secs`.
+pub fn start_monitor(config: MonitorConfig, on_event: Option<MonitorCallback>) -> MonitorHandle {
+    let tracker = Arc::new(Mutex::new(OutageTracker::new(
+        config.confirm_samples,
+        config.max_outages,
+    )));
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let thread_tracker = Arc::clone(&tracker);
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+
+    let thread = std::thread::spawn(move || loop {
+        let sample = probe_connectivity(&config);
+        let events = match thread_tracker.lock() {
+            Ok(mut t) => t.observe(&sample),
+            Err(_) => {
+                tracing::error!("monitor state lock poisoned, stopping");
+                break;
+            }
+        };
+        if let Some(ref cb) = on_event {
+            for event in &events {
+                cb(event);
+            }
+        }
+
+        match stop_rx.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            _ => break,
+        }
+    });
+
+    MonitorHandle {
+        stop_tx: Some(stop_tx),
+        thread: Some(thread),
+        tracker,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, gateway_ok: bool, dns_ok: bool, http_ok: bool) -> ProbeSample {
+        ProbeSample {
+            timestamp,
+            gateway_ok,
+            dns_ok,
+            http_ok,
+            latency_ms: 10,
+        }
+    }
+
+    fn up(t: u64) -> ProbeSample {
+        sample(t, true, true, true)
+    }
+
+    fn outage_events(events: &[MonitorEvent]) -> Vec<&MonitorEvent> {
+        events
+            .iter()
+            .filter(|e| !matches!(e, MonitorEvent::Sample { .. }))
+            .collect()
+    }
+
+    #[test]
+    fn test_failing_layer_prefers_nearest() {
+        assert_eq!(up(0).failing_layer(), None);
+        assert_eq!(
+            sample(0, false, false, false).failing_layer(),
+            Some(ConnectivityLayer::Gateway)
+        );
+        assert_eq!(
+            sample(0, true, false, false).failing_layer(),
+            Some(ConnectivityLayer::Dns)
+        );
+        assert_eq!(
+            sample(0, true, true, false).failing_layer(),
+            Some(ConnectivityLayer::Http)
+        );
+    }
+
+    #[test]
+    fn test_single_failure_is_ignored() {
+        let mut tracker = OutageTracker::new(2, 10);
+        tracker.observe(&up(0));
+        assert!(outage_events(&tracker.observe(&sample(10, true, false, true))).is_empty());
+        assert!(outage_events(&tracker.observe(&up(20))).is_empty());
+        assert!(tracker.outages().is_empty());
+        assert_eq!(tracker.current_state(), None);
+    }
+
+    #[test]
+    fn test_outage_start_and_end() {
+        let mut tracker = OutageTracker::new(2, 10);
+        tracker.observe(&up(0));
+        tracker.observe(&sample(10, true, true, false));
+        let events = tracker.observe(&sample(20, true, true, false));
+        assert_eq!(
+            outage_events(&events),
+            vec![&MonitorEvent::OutageStarted {
+                outage: Outage {
+                    started_at: 10,
+                    ended_at: None,
+                    failing_layer: ConnectivityLayer::Http,
+                }
+            }]
+        );
+        assert_eq!(tracker.current_state(), Some(ConnectivityLayer::Http));
+
+        tracker.observe(&up(30));
+        let events = tracker.observe(&up(40));
+        let ended = Outage {
+            started_at: 10,
+            ended_at: Some(30),
+            failing_layer: ConnectivityLayer::Http,
+        };
+        assert_eq!(
+            outage_events(&events),
+            vec![&MonitorEvent::OutageEnded {
+                outage: ended.clone()
+            }]
+        );
+        assert_eq!(tracker.outages(), &[ended]);
+        assert_eq!(tracker.outages()[0].duration_secs(), Some(20));
+        assert_eq!(tracker.current_state(), None);
+    }
+
+    #[test]
+    fn test_layer_change_splits_outage() {
+        let mut tracker = OutageTracker::new(1, 10);
+        tracker.observe(&sample(0, true, false, false));
+        let events = tracker.observe(&sample(10, false, false, false));
+        let events = outage_events(&events);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events[0],
+            MonitorEvent::OutageEnded { outage } if outage.failing_layer == ConnectivityLayer::Dns
+                && outage.ended_at == Some(10)
+        ));
+        assert!(matches!(
+            events[1],
+            MonitorEvent::OutageStarted { outage } if outage.failing_layer == ConnectivityLayer::Gateway
+        ));
+        assert_eq!(tracker.outages().len(), 2);
+    }
+
+    #[test]
+    fn test_max_outages_drops_oldest() {
+        let mut tracker = OutageTracker::new(1, 2);
+        for i in 0..3 {
+            tracker.observe(&sample(i * 20, false, false, false));
+            tracker.observe(&up(i * 20 + 10));
+        }
+        let starts: Vec<u64> = tracker.outages().iter().map(|o| o.started_at).collect();
+        assert_eq!(starts, vec![20, 40]);
+    }
+
+    #[test]
+    fn test_monitor_event_serialization() {
+        let event = MonitorEvent::OutageStarted {
+            outage: Outage {
+                started_at: 5,
+                ended_at: None,
+                failing_layer: ConnectivityLayer::Dns,
+            },
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "outage_started");
+        assert_eq!(json["outage"]["failing_layer"], "dns");
+    }
+}
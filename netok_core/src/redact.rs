@@ -0,0 +1,565 @@
+//! Privacy redaction for snapshots, device lists and security reports.
+//!
+//! Identifying values (public IP, SSID, hostnames, MAC addresses) are replaced
+//! with salted pseudonyms. The same value always maps to the same pseudonym
+//! under one salt, so two results redacted by the same `Redactor` still diff
+//! cleanly.
+
+use serde::{Deserialize, Serialize};
+use std::hash::{BuildHasher, Hasher};
+use std::net::IpAddr;
+
+use crate::dns_catalog::DnsCatalog;
+use crate::domain::{DiagnosticsSnapshot, NetworkDevice};
+use crate::history::{HistoryEntry, HistoryRecord, NetworkKey};
+use crate::infrastructure::security::WiFiSecurityReport;
+use crate::report::Report;
+
+// ==================== Domain Types ====================
+
+/// How much identifying data to strip.
+///
+/// | Field                            | `none` | `share_with_support` | `public`       |
+/// |----------------------------------|--------|----------------------|----------------|
+/// | Public IP                        | kept   | pseudonym            | pseudonym      |
+/// | SSID, hostnames                  | kept   | pseudonym            | pseudonym      |
+/// | MAC addresses                    | kept   | vendor prefix kept   | full pseudonym |
+/// | ISP, city                        | kept   | kept                 | removed        |
+/// | Other global IPs¹                | kept   | kept                 | pseudonym      |
+/// | Private IPs, public DNS, country | kept   | kept                 | kept           |
+///
+/// ¹ The computer's and devices' addresses, the gateway, DNS, DHCP and NTP
+/// servers: global IPv6 (and any with an EUI-64 interface id, which embeds a
+/// MAC) and ISP resolvers. Resolvers from the DNS catalog are public
+/// knowledge and kept.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionLevel {
+    #[default]
+    None,
+    ShareWithSupport,
+    Public,
+}
+
+/// Applies a `RedactionLevel` with a fixed salt.
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    level: RedactionLevel,
+    salt: String,
+}
+
+impl Redactor {
+    /// Redactor with a random salt: pseudonyms are stable only for this instance.
+    pub fn new(level: RedactionLevel) -> Self {
+        let salt = std::collections::hash_map::RandomState::new()
+            .build_hasher()
+            .finish();
+        Self::with_salt(level, format!("{:016x}", salt))
+    }
+
+    /// Redactor with a caller-chosen salt, for pseudonyms that stay stable
+    /// across sessions.
+    pub fn with_salt(level: RedactionLevel, salt: impl Into<String>) -> Self {
+        Self {
+            level,
+            salt: salt.into(),
+        }
+    }
+
+    pub fn level(&self) -> RedactionLevel {
+        self.level
+    }
+
+    pub fn redact_snapshot(&self, mut snapshot: DiagnosticsSnapshot) -> DiagnosticsSnapshot {
+        if self.level == RedactionLevel::None {
+            return snapshot;
+        }
+        snapshot.computer.hostname = snapshot.computer.hostname.map(|h| self.hostname(&h));
+        snapshot.network.ssid = snapshot.network.ssid.map(|s| self.ssid(&s));
+        snapshot.router.gateway_mac = snapshot.router.gateway_mac.map(|m| self.mac(&m));
+        snapshot.internet.public_ip = snapshot.internet.public_ip.map(|ip| self.public_ip(&ip));
+        if self.level == RedactionLevel::Public {
+            snapshot.internet.isp = None;
+            snapshot.internet.city = None;
+            snapshot.computer.local_ip = snapshot.computer.local_ip.map(|ip| self.address(&ip));
+            snapshot.router.gateway_ip = snapshot.router.gateway_ip.map(|ip| self.address(&ip));
+            snapshot.network.dns_servers = self.addresses(&snapshot.network.dns_servers);
+            if let Some(dhcp) = &mut snapshot.network.dhcp {
+                dhcp.server = dhcp.server.as_deref().map(|ip| self.address(ip));
+                dhcp.dns_servers = self.addresses(&dhcp.dns_servers);
+                dhcp.ntp_servers = self.addresses(&dhcp.ntp_servers);
+            }
+        }
+        snapshot
+    }
+
+    pub fn redact_devices(&self, devices: Vec<NetworkDevice>) -> Vec<NetworkDevice> {
+        if self.level == RedactionLevel::None {
+            return devices;
+        }
+        devices
+            .into_iter()
+            .map(|mut device| {
+                device.mac = self.mac(&device.mac);
+                device.hostname = device.hostname.map(|h| self.hostname(&h));
+                if self.level == RedactionLevel::Public {
+                    device.ip = self.address(&device.ip);
+                }
+                device
+            })
+            .collect()
+    }
+
+    pub fn redact_security_report(&self, mut report: WiFiSecurityReport) -> WiFiSecurityReport {
+        if self.level == RedactionLevel::None {
+            return report;
+        }
+        let ssid = report.network_ssid.take();
+        for check in &mut report.checks {
+            check.details = check
+                .details
+                .take()
+                .map(|d| self.redact_text(&d, ssid.as_deref()));
+        }
+        report.network_ssid = ssid.map(|s| self.ssid(&s));
+        report
+    }
+
+    pub fn redact_history_record(&self, mut record: HistoryRecord) -> HistoryRecord {
+        if self.level == RedactionLevel::None {
+            return record;
+        }
+        record.network = NetworkKey {
+            ssid: record.network.ssid.map(|s| self.ssid(&s)),
+            gateway_mac: record.network.gateway_mac.map(|m| self.mac(&m)),
+        };
+        record.entry = match record.entry {
+            HistoryEntry::Diagnostics { snapshot } => HistoryEntry::Diagnostics {
+                snapshot: Box::new(self.redact_snapshot(*snapshot)),
+            },
+            HistoryEntry::Security { report } => HistoryEntry::Security {
+                report: self.redact_security_report(report),
+            },
+            HistoryEntry::DeviceScan { devices } => HistoryEntry::DeviceScan {
+                devices: self.redact_devices(devices),
+            },
+        };
+        record
+    }
+
+    /// Redact every part of a report and record the level in it.
+    pub fn redact_report(&self, mut report: Report) -> Report {
+        if self.level == RedactionLevel::None {
+            return report;
+        }
+        report.snapshot = report.snapshot.map(|s| self.redact_snapshot(s));
+        report.security = report.security.map(|r| self.redact_security_report(r));
+        report.devices = report.devices.map(|d| self.redact_devices(d));
+        report.history = report
+            .history
+            .into_iter()
+            .map(|r| self.redact_history_record(r))
+            .collect();
+        report.redaction = self.level;
+        report
+    }
+
+    // ==================== Pseudonyms ====================
+
+    fn ssid(&self, ssid: &str) -> String {
+        format!("ssid-{:06x}", self.digest("ssid", ssid) & 0xFF_FFFF)
+    }
+
+    fn hostname(&self, hostname: &str) -> String {
+        let normalized = hostname.trim_end_matches('.').to_ascii_lowercase();
+        format!("host-{:06x}", self.digest("host", &normalized) & 0xFF_FFFF)
+    }
+
+    fn public_ip(&self, ip: &str) -> String {
+        format!("ip-{:06x}", self.digest("ip", ip.trim()) & 0xFF_FFFF)
+    }
+
+    /// `ip` if it identifies nobody (see [`RedactionLevel`]), else a pseudonym.
+    fn address(&self, ip: &str) -> String {
+        if is_shareable(ip) {
+            ip.to_string()
+        } else {
+            self.public_ip(ip)
+        }
+    }
+
+    fn addresses(&self, ips: &[String]) -> Vec<String> {
+        ips.iter().map(|ip| self.address(ip)).collect()
+    }
+
+    /// Pseudonymous MAC in `AA:BB:CC:DD:EE:FF` form.
+    ///
+    /// `share_with_support` keeps the vendor prefix (OUI) so vendor lookups
+    /// still work; `public` replaces it with a locally administered prefix.
+    /// Values that don't parse as a MAC get a plain pseudonym.
+    fn mac(&self, mac: &str) -> String {
+        let Some(octets) = parse_mac(mac) else {
+            return format!("mac-{:06x}", self.digest("mac", mac) & 0xFF_FFFF);
+        };
+        let normalized = format_mac(&octets);
+        let hash = self.digest("mac", &normalized).to_be_bytes();
+        let out = match self.level {
+            RedactionLevel::Public => [0x02, hash[0], hash[1], hash[2], hash[3], hash[4]],
+            _ => [octets[0], octets[1], octets[2], hash[0], hash[1], hash[2]],
+        };
+        format_mac(&out)
+    }
+
+    /// Replace the SSID and any MAC addresses inside free-form check details.
+    /// The SSID is only replaced as a whole token, not inside longer words.
+    fn redact_text(&self, text: &str, ssid: Option<&str>) -> String {
+        let mut out = match ssid {
+            Some(ssid) if !ssid.is_empty() => replace_token(text, ssid, &self.ssid(ssid)),
+            _ => text.to_string(),
+        };
+        let mut start = 0;
+        while let Some((pos, len)) = find_mac(&out[start..]) {
+            let pos = start + pos;
+            let replacement = self.mac(&out[pos..pos + len]);
+            out.replace_range(pos..pos + len, &replacement);
+            start = pos + replacement.len();
+        }
+        out
+    }
+
+    /// FNV-1a over salt, kind and value.
+    fn digest(&self, kind: &str, value: &str) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for part in [
+            self.salt.as_bytes(),
+            &[0],
+            kind.as_bytes(),
+            &[0],
+            value.as_bytes(),
+        ] {
+            for &b in part {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+/// Private, loopback, link-local or unspecified addresses without an EUI-64
+/// interface id, and the DNS catalog's public resolvers.
+fn is_shareable(ip: &str) -> bool {
+    let catalog = DnsCatalog::built_in();
+    if catalog
+        .providers
+        .iter()
+        .any(|p| p.ipv4.iter().chain(&p.ipv6).any(|known| known == ip))
+    {
+        return true;
+    }
+    // Scoped IPv6 addresses carry the interface after a `%`
+    match ip.split('%').next().unwrap_or(ip).parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => {
+            v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified()
+        }
+        Ok(IpAddr::V6(v6)) => {
+            let octets = v6.octets();
+            let eui64 = octets[11] == 0xff && octets[12] == 0xfe;
+            let unique_local = (v6.segments()[0] & 0xfe00) == 0xfc00;
+            let link_local = (v6.segments()[0] & 0xffc0) == 0xfe80;
+            v6.is_loopback() || v6.is_unspecified() || ((unique_local || link_local) && !eui64)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Replace `token` in `text` where it doesn't continue a longer word
+/// (letters, digits, `_`) on either side.
+fn replace_token(text: &str, token: &str, replacement: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (pos, _) in text.match_indices(token) {
+        let end = pos + token.len();
+        let joins_before =
+            is_word(text[..pos].chars().next_back()) && is_word(token.chars().next());
+        let joins_after = is_word(text[end..].chars().next()) && is_word(token.chars().next_back());
+        if !joins_before && !joins_after {
+            out.push_str(&text[copied..pos]);
+            out.push_str(replacement);
+            copied = end;
+        }
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let parts: Vec<&str> = mac.trim().split([':', '-']).collect();
+    if parts.len() != 6 {
+        return None;
+    }
+    let mut octets = [0u8; 6];
+    for (octet, part) in octets.iter_mut().zip(parts) {
+        if part.len() != 2 {
+            return None;
+        }
+        *octet = u8::from_str_radix(part, 16).ok()?;
+    }
+    Some(octets)
+}
+
+fn format_mac(octets: &[u8; 6]) -> String {
+    octets
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Find the first `XX:XX:XX:XX:XX:XX` (or `-`-separated) token in `text`.
+fn find_mac(text: &str) -> Option<(usize, usize)> {
+    const LEN: usize = 17;
+    let bytes = text.as_bytes();
+    if bytes.len() < LEN {
+        return None;
+    }
+    (0..=bytes.len() - LEN)
+        .find(|&i| {
+            let window = &bytes[i..i + LEN];
+            let sep = window[2];
+            let shape = window.iter().enumerate().all(|(j, &b)| {
+                if j % 3 == 2 {
+                    b == sep && (sep == b':' || sep == b'-')
+                } else {
+                    b.is_ascii_hexdigit()
+                }
+            });
+            let before = i == 0 || !bytes[i - 1].is_ascii_hexdigit();
+            let after = i + LEN == bytes.len() || !bytes[i + LEN].is_ascii_hexdigit();
+            shape && before && after
+        })
+        .map(|i| (i, LEN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        ComputerInfo, DeviceType, DhcpInfo, InternetInfo, NetworkInfo, RouterInfo,
+    };
+    use crate::infrastructure::security::{SecurityCheck, SecurityCheckType, SecurityStatus};
+
+    fn snapshot() -> DiagnosticsSnapshot {
+        DiagnosticsSnapshot {
+            at_utc: "2024-01-17T10:00:00Z".into(),
+            nodes: vec![],
+            summary_key: "summary.ok".into(),
+            computer: ComputerInfo {
+                hostname: Some("Alices-Laptop".into()),
+                local_ip: Some("192.168.1.20".into()),
+                ..Default::default()
+            },
+            network: NetworkInfo {
+                ssid: Some("HomeNet".into()),
+                ..Default::default()
+            },
+            router: RouterInfo {
+                gateway_ip: Some("192.168.1.1".into()),
+                gateway_mac: Some("aa-bb-cc-dd-ee-ff".into()),
+                ..Default::default()
+            },
+            internet: InternetInfo {
+                public_ip: Some("203.0.113.7".into()),
+                isp: Some("Example ISP".into()),
+                country: Some("DE".into()),
+                city: Some("Berlin".into()),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn device(mac: &str, hostname: Option<&str>) -> NetworkDevice {
+        NetworkDevice {
+            ip: "192.168.1.20".into(),
+            mac: mac.into(),
+            vendor: None,
+            hostname: hostname.map(String::from),
+            device_type: DeviceType::Computer,
+            is_gateway: false,
+            is_self: true,
+            is_randomized: false,
+        }
+    }
+
+    #[test]
+    fn test_none_keeps_everything() {
+        let redacted = Redactor::new(RedactionLevel::None).redact_snapshot(snapshot());
+        assert_eq!(redacted.network.ssid.as_deref(), Some("HomeNet"));
+        assert_eq!(redacted.internet.public_ip.as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_support_level_pseudonymizes_identifiers() {
+        let r = Redactor::with_salt(RedactionLevel::ShareWithSupport, "salt");
+        let s = r.redact_snapshot(snapshot());
+
+        let ssid = s.network.ssid.unwrap();
+        assert!(ssid.starts_with("ssid-") && !ssid.contains("HomeNet"));
+        assert!(s.computer.hostname.unwrap().starts_with("host-"));
+        assert!(s.internet.public_ip.unwrap().starts_with("ip-"));
+        // Vendor prefix survives, NIC part doesn't
+        let mac = s.router.gateway_mac.unwrap();
+        assert!(mac.starts_with("AA:BB:CC:"));
+        assert_ne!(mac, "AA:BB:CC:DD:EE:FF");
+        // Useful context is kept
+        assert_eq!(s.internet.isp.as_deref(), Some("Example ISP"));
+        assert_eq!(s.internet.city.as_deref(), Some("Berlin"));
+        assert_eq!(s.computer.local_ip.as_deref(), Some("192.168.1.20"));
+        assert_eq!(s.router.gateway_ip.as_deref(), Some("192.168.1.1"));
+    }
+
+    #[test]
+    fn test_public_level_strips_more() {
+        let r = Redactor::with_salt(RedactionLevel::Public, "salt");
+        let s = r.redact_snapshot(snapshot());
+        assert!(s.internet.isp.is_none());
+        assert!(s.internet.city.is_none());
+        assert_eq!(s.internet.country.as_deref(), Some("DE"));
+        let mac = s.router.gateway_mac.unwrap();
+        assert!(mac.starts_with("02:"));
+        assert!(parse_mac(&mac).is_some());
+    }
+
+    #[test]
+    fn test_public_level_pseudonymizes_global_addresses() {
+        let mut snap = snapshot();
+        snap.computer.local_ip = Some("2001:db8::211:22ff:fe33:4455".into());
+        snap.network.dns_servers = vec!["198.51.100.53".into(), "1.1.1.1".into()];
+        snap.network.dhcp = Some(DhcpInfo {
+            server: Some("192.168.1.1".into()),
+            dns_servers: vec!["198.51.100.53".into(), "fe80::211:22ff:fe33:4455".into()],
+            ..Default::default()
+        });
+
+        let support = Redactor::with_salt(RedactionLevel::ShareWithSupport, "salt");
+        let s = support.redact_snapshot(snap.clone());
+        assert_eq!(s.network.dns_servers[0], "198.51.100.53");
+
+        let r = Redactor::with_salt(RedactionLevel::Public, "salt");
+        let s = r.redact_snapshot(snap);
+        let local_ip = s.computer.local_ip.unwrap();
+        assert!(local_ip.starts_with("ip-"));
+        let isp_dns = &s.network.dns_servers[0];
+        assert!(isp_dns.starts_with("ip-"));
+        // A public resolver identifies nobody
+        assert_eq!(s.network.dns_servers[1], "1.1.1.1");
+        assert_eq!(s.router.gateway_ip.as_deref(), Some("192.168.1.1"));
+        let dhcp = s.network.dhcp.unwrap();
+        assert_eq!(dhcp.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(&dhcp.dns_servers[0], isp_dns);
+        // Link-local, but the EUI-64 interface id is the MAC
+        assert!(dhcp.dns_servers[1].starts_with("ip-"));
+
+        let devices = r.redact_devices(vec![device("AA:BB:CC:DD:EE:FF", None)]);
+        assert_eq!(devices[0].ip, "192.168.1.20");
+    }
+
+    #[test]
+    fn test_pseudonyms_are_consistent_per_salt() {
+        let a = Redactor::with_salt(RedactionLevel::ShareWithSupport, "one");
+        let b = Redactor::with_salt(RedactionLevel::ShareWithSupport, "two");
+
+        // Same value, same salt, different formatting -> same pseudonym
+        let snap = a.redact_snapshot(snapshot());
+        let devices = a.redact_devices(vec![device("AA:BB:CC:DD:EE:FF", Some("alices-laptop."))]);
+        assert_eq!(
+            snap.router.gateway_mac.as_deref(),
+            Some(devices[0].mac.as_str())
+        );
+        assert_eq!(snap.computer.hostname, devices[0].hostname);
+
+        // Different salt -> different pseudonym
+        let other = b.redact_snapshot(snapshot());
+        assert_ne!(snap.network.ssid, other.network.ssid);
+    }
+
+    #[test]
+    fn test_redacted_snapshots_still_diff() {
+        let r = Redactor::with_salt(RedactionLevel::Public, "salt");
+        let before = snapshot();
+        let mut after = snapshot();
+        after.network.ssid = Some("CafeNet".into());
+
+        let unchanged = crate::diff_snapshots(
+            &r.redact_snapshot(before.clone()),
+            &r.redact_snapshot(before.clone()),
+        );
+        assert!(unchanged.is_empty());
+
+        let changes = crate::diff_snapshots(&r.redact_snapshot(before), &r.redact_snapshot(after));
+        assert_eq!(changes.len(), 1);
+    }
+
+    #[test]
+    fn test_security_report_details_redacted() {
+        let r = Redactor::with_salt(RedactionLevel::ShareWithSupport, "salt");
+        let report = WiFiSecurityReport {
+            checks: vec![
+                SecurityCheck {
+                    check_type: SecurityCheckType::EvilTwin,
+                    status: SecurityStatus::Warning,
+                    details: Some("HomeNet".into()),
+                },
+                SecurityCheck {
+                    check_type: SecurityCheckType::ArpSpoofing,
+                    status: SecurityStatus::Danger,
+                    details: Some("gateway_mac_duplicate:aa:bb:cc:dd:ee:ff".into()),
+                },
+            ],
+            overall_status: SecurityStatus::Danger,
+            network_ssid: Some("HomeNet".into()),
+            timestamp: 0,
+        };
+        let redacted = r.redact_security_report(report);
+        let ssid = redacted.network_ssid.unwrap();
+        assert_eq!(redacted.checks[0].details.as_deref(), Some(ssid.as_str()));
+        let arp = redacted.checks[1].details.as_deref().unwrap();
+        assert!(arp.starts_with("gateway_mac_duplicate:AA:BB:CC:"));
+        assert!(!arp.to_ascii_uppercase().contains("DD:EE:FF"));
+    }
+
+    #[test]
+    fn test_ssid_replaced_only_as_whole_token() {
+        let r = Redactor::with_salt(RedactionLevel::ShareWithSupport, "salt");
+        let ssid = r.ssid("Net");
+        assert_eq!(
+            r.redact_text("Net seen on HomeNet, Net-5G and Network", Some("Net")),
+            format!("{0} seen on HomeNet, {0}-5G and Network", ssid)
+        );
+        assert_eq!(
+            r.redact_text("evil_twin:a", Some("a")),
+            format!("evil_twin:{}", r.ssid("a"))
+        );
+    }
+
+    #[test]
+    fn test_find_mac_boundaries() {
+        assert_eq!(find_mac("x 00:11:22:33:44:55 y"), Some((2, 17)));
+        assert_eq!(find_mac("00-11-22-33-44-55"), Some((0, 17)));
+        assert_eq!(find_mac("00:11-22:33:44:55"), None);
+        assert_eq!(find_mac("100:11:22:33:44:55"), None);
+        assert_eq!(find_mac("short"), None);
+    }
+
+    #[test]
+    fn test_redact_report_sets_level() {
+        let r = Redactor::with_salt(RedactionLevel::Public, "salt");
+        let report = Report::new(Some(snapshot()), None, None, vec![]);
+        let redacted = r.redact_report(report);
+        assert_eq!(redacted.redaction, RedactionLevel::Public);
+        let md = crate::render_markdown(&redacted);
+        assert!(!md.contains("HomeNet"));
+        assert!(!md.contains("203.0.113.7"));
+    }
+}
@@ -0,0 +1,810 @@
+//! Shareable diagnostic reports.
+//!
+//! Renders a diagnostics snapshot, Wi-Fi security report, device list and
+//! optional history into self-contained Markdown or HTML for humans, or a
+//! versioned JSON bundle for tools. Both text formats are built from the same
+//! section model so they never drift apart.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::domain::{
+    ConnectionType, DeviceType, DiagnosticsSnapshot, DnssecStatus, NetworkDevice, NodeId, Status,
+};
+use crate::error::StorageError;
+use crate::history::{HistoryEntry, HistoryRecord};
+use crate::i18n::I18n;
+use crate::infrastructure::security::{SecurityCheckType, SecurityStatus, WiFiSecurityReport};
+use crate::redact::RedactionLevel;
+
+/// Version of the JSON bundle layout. Bump on breaking changes.
+pub const REPORT_SCHEMA_VERSION: u32 = 1;
+
+// ==================== Domain Types ====================
+
+/// Output format of a report.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+            ReportFormat::Json => "json",
+        }
+    }
+
+    /// Guess the format from a file extension (`.md`, `.html`/`.htm`, `.json`).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            "html" | "htm" => Some(ReportFormat::Html),
+            "json" => Some(ReportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Everything that goes into a report. Serialized as-is for the JSON bundle.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Report {
+    pub schema_version: u32,
+    /// Producing application, e.g. `"netok 0.1.0"`.
+    pub generator: String,
+    /// Unix timestamp (seconds) of report creation.
+    pub generated_at: u64,
+    /// Redaction applied to the contents, see `Redactor::redact_report`.
+    #[serde(default)]
+    pub redaction: RedactionLevel,
+    /// Language of the Markdown and HTML text (`en`, `de`, …); unknown
+    /// languages and missing translations fall back to English.
+    #[serde(default = "default_language")]
+    pub language: String,
+    pub snapshot: Option<DiagnosticsSnapshot>,
+    pub security: Option<WiFiSecurityReport>,
+    pub devices: Option<Vec<NetworkDevice>>,
+    #[serde(default)]
+    pub history: Vec<HistoryRecord>,
+}
+
+impl Report {
+    /// Build an English report stamped with the current time.
+    pub fn new(
+        snapshot: Option<DiagnosticsSnapshot>,
+        security: Option<WiFiSecurityReport>,
+        devices: Option<Vec<NetworkDevice>>,
+        history: Vec<HistoryRecord>,
+    ) -> Self {
+        let generated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            schema_version: REPORT_SCHEMA_VERSION,
+            generator: format!("netok {}", env!("CARGO_PKG_VERSION")),
+            generated_at,
+            redaction: RedactionLevel::None,
+            language: default_language(),
+            snapshot,
+            security,
+            devices,
+            history,
+        }
+    }
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+// ==================== Rendering ====================
+
+/// Render `report` in the given format.
+pub fn render_report(report: &Report, format: ReportFormat) -> Result<String, StorageError> {
+    match format {
+        ReportFormat::Markdown => Ok(render_markdown(report)),
+        ReportFormat::Html => Ok(render_html(report)),
+        ReportFormat::Json => render_json(report),
+    }
+}
+
+/// Render and write `report` to `path`.
+pub fn save_report(report: &Report, format: ReportFormat, path: &Path) -> Result<(), StorageError> {
+    let text = render_report(report, format)?;
+    std::fs::write(path, text).map_err(StorageError::io("write report"))
+}
+
+/// Machine-readable JSON bundle.
+pub fn render_json(report: &Report) -> Result<String, StorageError> {
+    serde_json::to_string_pretty(report).map_err(|e| StorageError::Serialize {
+        what: "report",
+        message: e.to_string(),
+    })
+}
+
+/// Self-contained Markdown.
+pub fn render_markdown(report: &Report) -> String {
+    let t = I18n::new(&report.language);
+    let mut out = String::new();
+    out.push_str(&format!("# {}\n\n", t.t("report.title")));
+    out.push_str(&format!("{}\n", generated_line(&t, report)));
+    if let Some(label) = redaction_label(&t, report.redaction) {
+        out.push_str(&format!("\n{}\n", label));
+    }
+
+    for section in sections(&t, report) {
+        out.push_str(&format!("\n## {}\n\n", section.title));
+        for block in section.blocks {
+            match block {
+                Block::Text(text) => out.push_str(&format!("{}\n\n", md_escape(&text))),
+                Block::KeyValues(rows) => {
+                    for (key, value) in rows {
+                        out.push_str(&format!(
+                            "- **{}:** {}\n",
+                            md_escape(&key),
+                            md_escape(&value)
+                        ));
+                    }
+                    out.push('\n');
+                }
+                Block::Table { headers, rows } => {
+                    let headers: Vec<String> = headers.iter().map(|h| md_escape(h)).collect();
+                    out.push_str(&format!("| {} |\n", headers.join(" | ")));
+                    out.push_str(&format!(
+                        "|{}\n",
+                        headers.iter().map(|_| "---|").collect::<String>()
+                    ));
+                    for row in rows {
+                        let cells: Vec<String> = row.iter().map(|c| md_escape(c)).collect();
+                        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    out.trim_end().to_string() + "\n"
+}
+
+/// Self-contained HTML page with inline styles, no external resources.
+pub fn render_html(report: &Report) -> String {
+    let t = I18n::new(&report.language);
+    let title = html_escape(&t.t("report.title"));
+    let mut out = String::new();
+    out.push_str(&format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n",
+        html_escape(&report.language)
+    ));
+    out.push_str(&format!("<title>{}</title>\n", title));
+    out.push_str(
+        "<style>\n\
+         body{font-family:system-ui,sans-serif;max-width:960px;margin:2em auto;padding:0 1em;color:#222}\n\
+         table{border-collapse:collapse;width:100%;margin-bottom:1em}\n\
+         th,td{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:top}\n\
+         th{background:#f4f4f4}\n\
+         dt{font-weight:bold;float:left;clear:left;width:12em}\n\
+         dd{margin-left:13em;margin-bottom:4px}\n\
+         .meta{color:#666}\n\
+         </style>\n</head>\n<body>\n",
+    );
+    out.push_str(&format!("<h1>{}</h1>\n", title));
+    out.push_str(&format!(
+        "<p class=\"meta\">{}</p>\n",
+        html_escape(&generated_line(&t, report))
+    ));
+    if let Some(label) = redaction_label(&t, report.redaction) {
+        out.push_str(&format!("<p class=\"meta\">{}</p>\n", html_escape(&label)));
+    }
+
+    for section in sections(&t, report) {
+        out.push_str(&format!("<h2>{}</h2>\n", html_escape(&section.title)));
+        for block in section.blocks {
+            match block {
+                Block::Text(text) => out.push_str(&format!("<p>{}</p>\n", html_escape(&text))),
+                Block::KeyValues(rows) => {
+                    out.push_str("<dl>\n");
+                    for (key, value) in rows {
+                        out.push_str(&format!(
+                            "<dt>{}</dt><dd>{}</dd>\n",
+                            html_escape(&key),
+                            html_escape(&value)
+                        ));
+                    }
+                    out.push_str("</dl>\n");
+                }
+                Block::Table { headers, rows } => {
+                    out.push_str("<table>\n<tr>");
+                    for header in headers {
+                        out.push_str(&format!("<th>{}</th>", html_escape(&header)));
+                    }
+                    out.push_str("</tr>\n");
+                    for row in rows {
+                        out.push_str("<tr>");
+                        for cell in row {
+                            out.push_str(&format!("<td>{}</td>", html_escape(&cell)));
+                        }
+                        out.push_str("</tr>\n");
+                    }
+                    out.push_str("</table>\n");
+                }
+            }
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+// ==================== Section Model ====================
+
+enum Block {
+    Text(String),
+    KeyValues(Vec<(String, String)>),
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+struct Section {
+    title: String,
+    blocks: Vec<Block>,
+}
+
+/// Translated labels for `keys`, e.g. table headers.
+fn labels(t: &I18n, keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| t.t(key)).collect()
+}
+
+fn sections(t: &I18n, report: &Report) -> Vec<Section> {
+    let mut out = Vec::new();
+
+    if let Some(snapshot) = &report.snapshot {
+        out.push(Section {
+            title: t.t("diagnostics.title"),
+            blocks: vec![
+                Block::KeyValues(vec![
+                    (t.t("report.checked_at"), snapshot.at_utc.clone()),
+                    (t.t("report.overall"), t.t(overall_key(snapshot))),
+                ]),
+                Block::Table {
+                    headers: labels(t, &["report.check", "report.status", "report.latency"]),
+                    rows: snapshot
+                        .nodes
+                        .iter()
+                        .map(|n| {
+                            vec![
+                                t.t(node_key(n.id)),
+                                t.t(status_key(n.status)),
+                                n.latency_ms
+                                    .map(|ms| format!("{} {}", ms, t.t("speed_test.unit_ms")))
+                                    .unwrap_or_else(|| "—".into()),
+                            ]
+                        })
+                        .collect(),
+                },
+            ],
+        });
+        out.push(Section {
+            title: t.t("report.connection"),
+            blocks: vec![Block::KeyValues(connection_rows(t, snapshot))],
+        });
+    }
+
+    if let Some(security) = &report.security {
+        out.push(Section {
+            title: t.t("report.wifi_security"),
+            blocks: vec![
+                Block::KeyValues(vec![
+                    (t.t("report.network"), opt(&security.network_ssid)),
+                    (
+                        t.t("report.checked_at"),
+                        format_timestamp(security.timestamp),
+                    ),
+                    (
+                        t.t("report.overall"),
+                        t.t(security_status_key(security.overall_status)),
+                    ),
+                ]),
+                Block::Table {
+                    headers: labels(t, &["report.check", "report.result", "report.details"]),
+                    rows: security
+                        .checks
+                        .iter()
+                        .map(|c| {
+                            vec![
+                                t.t(security_check_key(c.check_type)),
+                                t.t(security_status_key(c.status)),
+                                opt(&c.details),
+                            ]
+                        })
+                        .collect(),
+                },
+            ],
+        });
+    }
+
+    if let Some(devices) = &report.devices {
+        let blocks = if devices.is_empty() {
+            vec![Block::Text(t.t("device_scan.no_devices"))]
+        } else {
+            vec![Block::Table {
+                headers: labels(
+                    t,
+                    &[
+                        "report.ip",
+                        "report.mac",
+                        "report.name",
+                        "report.vendor",
+                        "report.type",
+                    ],
+                ),
+                rows: devices
+                    .iter()
+                    .map(|d| {
+                        let mut name = opt(&d.hostname);
+                        if d.is_gateway {
+                            name.push_str(&format!(" ({})", t.t("device_scan.badge_router")));
+                        } else if d.is_self {
+                            name.push_str(&format!(" ({})", t.t("device_scan.badge_this_device")));
+                        }
+                        vec![
+                            d.ip.clone(),
+                            d.mac.clone(),
+                            name,
+                            opt(&d.vendor),
+                            t.t(device_type_key(d.device_type)),
+                        ]
+                    })
+                    .collect(),
+            }]
+        };
+        out.push(Section {
+            title: t.t("device_scan.title"),
+            blocks,
+        });
+    }
+
+    if !report.history.is_empty() {
+        out.push(Section {
+            title: t.t("report.history"),
+            blocks: vec![Block::Table {
+                headers: labels(
+                    t,
+                    &[
+                        "report.time",
+                        "report.network",
+                        "report.kind",
+                        "report.result",
+                    ],
+                ),
+                rows: report
+                    .history
+                    .iter()
+                    .map(|record| history_row(t, record))
+                    .collect(),
+            }],
+        });
+    }
+
+    out
+}
+
+fn connection_rows(t: &I18n, snapshot: &DiagnosticsSnapshot) -> Vec<(String, String)> {
+    let network = &snapshot.network;
+    let mut rows = vec![
+        (
+            "nodes.network.link_field",
+            t.t(connection_type_key(network.connection_type)),
+        ),
+        (
+            "nodes.computer.name_field",
+            opt(&snapshot.computer.hostname),
+        ),
+        (
+            "nodes.computer.adapter_field",
+            opt(&snapshot.computer.adapter),
+        ),
+        (
+            "nodes.computer.local_ip_field",
+            opt(&snapshot.computer.local_ip),
+        ),
+    ];
+    if network.connection_type == ConnectionType::Wifi {
+        rows.push(("node_detail.network_name", opt(&network.ssid)));
+        rows.push((
+            "node_detail.signal",
+            network
+                .rssi
+                .map(|r| format!("{} dBm", r))
+                .unwrap_or_else(|| "—".into()),
+        ));
+        rows.push(("node_detail.security", opt(&network.encryption)));
+        rows.push(("node_detail.wifi_standard", opt(&network.wifi_standard)));
+    }
+    if !network.dns_servers.is_empty() {
+        rows.push(("report.dns_servers", network.dns_servers.join(", ")));
+    }
+    rows.push((
+        "nodes.router.local_ip_field",
+        opt(&snapshot.router.gateway_ip),
+    ));
+    rows.push(("report.gateway_mac", opt(&snapshot.router.gateway_mac)));
+    rows.push(("node_detail.manufacturer", opt(&snapshot.router.vendor)));
+    rows.push(("nodes.internet.ip_field", opt(&snapshot.internet.public_ip)));
+    rows.push(("node_detail.isp", opt(&snapshot.internet.isp)));
+    rows.push(("node_detail.dns_lookup", works(t, snapshot.internet.dns_ok)));
+    if let Some(key) = dnssec_key(snapshot.internet.dnssec) {
+        rows.push(("node_detail.dnssec", t.t(key)));
+    }
+    rows.push((
+        "node_detail.web_loading",
+        works(t, snapshot.internet.http_ok),
+    ));
+    rows.into_iter()
+        .map(|(key, value)| (t.t(key), value))
+        .collect()
+}
+
+fn history_row(t: &I18n, record: &HistoryRecord) -> Vec<String> {
+    let network = record
+        .network
+        .ssid
+        .clone()
+        .or_else(|| record.network.gateway_mac.clone())
+        .unwrap_or_else(|| "—".into());
+    let (kind, result) = match &record.entry {
+        HistoryEntry::Diagnostics { snapshot } => ("diagnostics.title", t.t(overall_key(snapshot))),
+        HistoryEntry::Security { report } => (
+            "wifi_security.title",
+            t.t(security_status_key(report.overall_status)),
+        ),
+        HistoryEntry::DeviceScan { devices } => (
+            "device_scan.title",
+            t.t_with(
+                "device_scan.found",
+                &[("count", &devices.len().to_string())],
+            ),
+        ),
+    };
+    vec![
+        format_timestamp(record.recorded_at),
+        network,
+        t.t(kind),
+        result,
+    ]
+}
+
+// ==================== Labels ====================
+
+fn opt(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "—".into())
+}
+
+/// Human-readable UTC timestamp, e.g. `2024-01-17 10:00:00 UTC`.
+fn format_timestamp(unix: u64) -> String {
+    let Ok(format) =
+        time::format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second] UTC")
+    else {
+        return unix.to_string();
+    };
+    time::OffsetDateTime::from_unix_timestamp(unix as i64)
+        .ok()
+        .and_then(|t| t.format(&format).ok())
+        .unwrap_or_else(|| unix.to_string())
+}
+
+fn generated_line(t: &I18n, report: &Report) -> String {
+    t.t_with(
+        "report.generated",
+        &[
+            ("time", &format_timestamp(report.generated_at)),
+            ("generator", &report.generator),
+        ],
+    )
+}
+
+fn redaction_label(t: &I18n, level: RedactionLevel) -> Option<String> {
+    let key = match level {
+        RedactionLevel::None => return None,
+        RedactionLevel::ShareWithSupport => "report.redaction_share_with_support",
+        RedactionLevel::Public => "report.redaction_public",
+    };
+    Some(t.t_with("report.redacted", &[("level", &t.t(key))]))
+}
+
+fn works(t: &I18n, ok: bool) -> String {
+    t.t(if ok {
+        "report.status_ok"
+    } else {
+        "node_detail.status_fail"
+    })
+}
+
+fn overall_key(snapshot: &DiagnosticsSnapshot) -> &'static str {
+    if snapshot.nodes.iter().all(|n| n.status == Status::Ok) {
+        "report.overall_ok"
+    } else if snapshot.nodes.iter().any(|n| n.status == Status::Fail) {
+        "report.overall_fail"
+    } else {
+        "report.overall_partial"
+    }
+}
+
+fn node_key(id: NodeId) -> &'static str {
+    match id {
+        NodeId::Computer => "diagnostics.computer",
+        NodeId::Wifi => "nodes.network.name",
+        NodeId::RouterUpnp => "diagnostics.router",
+        NodeId::Dns => "diagnostics.dns",
+        NodeId::Internet => "diagnostics.internet",
+    }
+}
+
+fn status_key(status: Status) -> &'static str {
+    match status {
+        Status::Ok => "report.status_ok",
+        Status::Warn => "report.status_warn",
+        Status::Fail => "report.status_fail",
+        Status::Unknown => "report.status_unknown",
+    }
+}
+
+fn connection_type_key(connection_type: ConnectionType) -> &'static str {
+    match connection_type {
+        ConnectionType::Wifi => "nodes.network.type_wifi",
+        ConnectionType::Ethernet => "nodes.network.type_cable",
+        ConnectionType::Usb => "nodes.network.type_usb_modem",
+        ConnectionType::Mobile => "nodes.network.type_mobile",
+        ConnectionType::Disabled => "diagnostics.status_disabled",
+        ConnectionType::Disconnected => "diagnostics.status_not_connected",
+        ConnectionType::Unknown => "diagnostics.status_no_data",
+    }
+}
+
+/// DNSSEC validation of the resolver; `None` if it wasn't checked.
+fn dnssec_key(status: DnssecStatus) -> Option<&'static str> {
+    match status {
+        DnssecStatus::Validating => Some("node_detail.dnssec_validating"),
+        DnssecStatus::NonValidating => Some("node_detail.dnssec_non_validating"),
+        DnssecStatus::Broken => Some("node_detail.dnssec_broken"),
+        DnssecStatus::Unknown => None,
+    }
+}
+
+fn device_type_key(device_type: DeviceType) -> &'static str {
+    match device_type {
+        DeviceType::Router => "device_scan.type_router",
+        DeviceType::Phone => "device_scan.type_phone",
+        DeviceType::Computer => "device_scan.type_computer",
+        DeviceType::Tablet => "device_scan.type_tablet",
+        DeviceType::Printer => "device_scan.type_printer",
+        DeviceType::SmartTv => "device_scan.type_smarttv",
+        DeviceType::GameConsole => "device_scan.type_gameconsole",
+        DeviceType::IoT => "device_scan.type_iot",
+        DeviceType::Unknown => "device_scan.type_unknown",
+    }
+}
+
+fn security_check_key(check: SecurityCheckType) -> &'static str {
+    match check {
+        SecurityCheckType::Encryption => "wifi_security.check_encryption",
+        SecurityCheckType::EvilTwin => "wifi_security.check_evil_twin",
+        SecurityCheckType::ArpSpoofing => "wifi_security.check_arp_spoofing",
+        SecurityCheckType::DnsHijacking => "wifi_security.check_dns_hijacking",
+    }
+}
+
+fn security_status_key(status: SecurityStatus) -> &'static str {
+    match status {
+        SecurityStatus::Safe => "report.safe",
+        SecurityStatus::Warning => "report.warning",
+        SecurityStatus::Danger => "report.danger",
+    }
+}
+
+/// Escape characters that would break Markdown tables or formatting.
+fn md_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '|' | '*' | '_' | '`' | '<' | '>' | '[' | ']' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' | '\r' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{
+        ComputerInfo, DeviceType, InternetInfo, NetworkInfo, NodeInfo, RouterInfo,
+    };
+    use crate::history::NetworkKey;
+    use crate::infrastructure::security::SecurityCheck;
+
+    fn report() -> Report {
+        let snapshot = DiagnosticsSnapshot {
+            at_utc: "2024-01-17T10:00:00Z".into(),
+            nodes: vec![
+                NodeInfo {
+                    id: NodeId::Computer,
+                    name_key: "nodes.computer.name".into(),
+                    status: Status::Ok,
+                    latency_ms: Some(3),
+                    hint_key: None,
+                },
+                NodeInfo {
+                    id: NodeId::Internet,
+                    name_key: "nodes.internet.name".into(),
+                    status: Status::Fail,
+                    latency_ms: None,
+                    hint_key: None,
+                },
+            ],
+            summary_key: "summary.fail".into(),
+            computer: ComputerInfo {
+                hostname: Some("desk".into()),
+                ..Default::default()
+            },
+            network: NetworkInfo {
+                connection_type: ConnectionType::Wifi,
+                ssid: Some("Cafe <Guest> | 5G".into()),
+                ..Default::default()
+            },
+            router: RouterInfo::default(),
+            internet: InternetInfo::default(),
+        };
+        let security = WiFiSecurityReport {
+            checks: vec![SecurityCheck {
+                check_type: SecurityCheckType::Encryption,
+                status: SecurityStatus::Danger,
+                details: Some("Open".into()),
+            }],
+            overall_status: SecurityStatus::Danger,
+            network_ssid: Some("Cafe".into()),
+            timestamp: 1_705_485_600,
+        };
+        let devices = vec![NetworkDevice {
+            ip: "192.168.1.1".into(),
+            mac: "AA:BB:CC:DD:EE:FF".into(),
+            vendor: Some("TP-Link".into()),
+            hostname: None,
+            device_type: DeviceType::Router,
+            is_gateway: true,
+            is_self: false,
+            is_randomized: false,
+        }];
+        let history = vec![HistoryRecord {
+            recorded_at: 1_705_400_000,
+            network: NetworkKey {
+                ssid: Some("Cafe".into()),
+                gateway_mac: None,
+            },
+            entry: HistoryEntry::DeviceScan { devices: vec![] },
+        }];
+        let mut report = Report::new(Some(snapshot), Some(security), Some(devices), history);
+        report.generated_at = 1_705_485_600;
+        report
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(1_705_485_600), "2024-01-17 10:00:00 UTC");
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("r.MD")),
+            Some(ReportFormat::Markdown)
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("r.htm")),
+            Some(ReportFormat::Html)
+        );
+        assert_eq!(ReportFormat::from_path(Path::new("r.txt")), None);
+    }
+
+    #[test]
+    fn test_markdown_contains_sections_and_escapes() {
+        let md = render_markdown(&report());
+        assert!(md.starts_with("# Netok Diagnostic Report\n"));
+        assert!(md.contains("Generated 2024-01-17 10:00:00 UTC"));
+        assert!(md.contains("## Diagnostics"));
+        assert!(md.contains("| Internet | Failed | — |"));
+        assert!(md.contains("- **Network:** Cafe \\<Guest\\> \\| 5G"));
+        assert!(md.contains("| Encryption | Danger | Open |"));
+        assert!(md.contains("| 192.168.1.1 | AA:BB:CC:DD:EE:FF | — (Router) | TP-Link | Router |"));
+        assert!(md.contains("| 2024-01-16 10:13:20 UTC | Cafe | Devices | 0 devices found |"));
+    }
+
+    #[test]
+    fn test_report_in_another_language() {
+        let mut report = report();
+        report.language = "ru".into();
+        let ru = I18n::new("ru");
+
+        let md = render_markdown(&report);
+        assert!(md.contains(&format!("## {}", ru.t("diagnostics.title"))));
+        assert!(md.contains(&format!("| {} |", ru.t("wifi_security.check_encryption"))));
+        assert!(!md.contains("## Diagnostics"));
+        assert!(md.starts_with("# Диагностический отчёт Netok\n"));
+        assert!(md.contains(&format!("| {} |", ru.t("report.check"))));
+        assert!(!md.contains("| Check |"));
+
+        let html = render_html(&report);
+        assert!(html.contains("<html lang=\"ru\">"));
+        assert!(html.contains(&format!("<h2>{}</h2>", ru.t("device_scan.title"))));
+    }
+
+    #[test]
+    fn test_html_is_self_contained_and_escaped() {
+        let html = render_html(&report());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("http://") && !html.contains("https://"));
+        assert!(html.contains("Cafe &lt;Guest&gt; | 5G"));
+        assert!(html.contains("<td>Failed</td>"));
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn test_json_bundle_roundtrip() {
+        let json = render_json(&report()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema_version"], REPORT_SCHEMA_VERSION);
+        assert_eq!(value["history"][0]["kind"], "device_scan");
+        assert_eq!(value["language"], "en");
+
+        let parsed: Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.devices.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_empty_report_renders() {
+        let empty = Report::new(None, None, None, vec![]);
+        let md = render_markdown(&empty);
+        assert!(!md.contains("##"));
+        assert!(render_html(&empty).contains("<h1>"));
+    }
+
+    #[test]
+    fn test_save_report_writes_file() {
+        let path =
+            std::env::temp_dir().join(format!("netok_report_test_{}.md", std::process::id()));
+        save_report(&report(), ReportFormat::Markdown, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("## Devices"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
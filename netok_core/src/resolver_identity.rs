@@ -0,0 +1,494 @@
+//! Which provider actually answers this machine's DNS queries.
+//!
+//! [`detect_dns_provider`](crate::detect_dns_provider) only compares the
+//! configured server addresses with the catalog, so a router or a Pi-hole
+//! forwarding to a public resolver shows up as `Auto`. Here the resolver is
+//! asked instead:
+//!
+//! - **Server address**: the configured server is a catalog provider's.
+//! - **Debug names**: names only one provider's resolvers answer (OpenDNS's
+//!   `debug.opendns.com` TXT). They are ordinary queries, so forwarders pass
+//!   them upstream.
+//! - **CHAOS**: `id.server` and `hostname.bind` name the server that answers
+//!   them (Quad9's are under `rrdns.pch.net`), `version.bind` its software
+//!   (dnsmasq, Pi-hole). Forwarders answer these themselves, so they describe
+//!   the first hop.
+//! - **Egress resolver**: the resolver that finally asks authoritative servers
+//!   shows up in the answers for a name under
+//!   [`ProbeTargets::dns_leak_zone`] and for Google's `o-o.myaddr.l.google.com`
+//!   TXT. Its network (ASN) is matched against the catalog's `asns`.
+//!
+//! Each piece of evidence carries a confidence; the identity is the provider
+//! family with the most confident evidence. Queries and network lookups go
+//! through the [`NetworkBackend`].
+
+use std::collections::BTreeSet;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::backend::NetworkBackend;
+use crate::cancel::{Cancellation, Completion, Partial};
+use crate::diagnostics::{is_private_ip_str, IpInfoResponse};
+use crate::dns_benchmark::uncached_name;
+use crate::dns_catalog::DnsCatalog;
+use crate::dns_leak::{self, parse_org};
+use crate::domain::{DnsClass, DnsProvider, ProbeTargets};
+use crate::runtime::blocking;
+
+/// Names only one provider's resolvers answer, by family.
+const DEBUG_NAMES: &[(&str, &str)] = &[("OpenDns", "debug.opendns.com.")];
+
+/// CHAOS names identifying the server that answers them.
+const CHAOS_NAMES: &[&str] = &["id.server.", "hostname.bind."];
+
+/// CHAOS name of the answering server's software.
+const VERSION_NAME: &str = "version.bind.";
+
+/// Suffixes of CHAOS server ids, by family.
+const CHAOS_IDS: &[(&str, &str)] = &[("rrdns.pch.net", "Quad9")];
+
+/// Google's authoritative servers answer this TXT with the address of the
+/// resolver that asked.
+const EGRESS_TXT: &str = "o-o.myaddr.l.google.com.";
+
+/// Time to wait for each answer.
+const IDENTITY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// ==================== Domain Types ====================
+
+/// How sure a piece of evidence is.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityConfidence {
+    /// A name match only, e.g. the egress resolver's organisation.
+    Low,
+    /// The provider's network, which says nothing about the variant.
+    Medium,
+    /// Only this provider gives this answer.
+    High,
+}
+
+/// How a piece of evidence was obtained.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityMethod {
+    /// The configured server address is a catalog provider's.
+    ServerAddress,
+    /// A name only one provider answers.
+    DebugName,
+    /// A CHAOS-class TXT query to the configured server.
+    Chaos,
+    /// The resolver that queried authoritative servers.
+    EgressResolver,
+}
+
+/// One observation about the resolver.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct IdentityEvidence {
+    pub method: IdentityMethod,
+    /// What was seen: an address, a TXT string, a network.
+    pub detail: String,
+    /// Provider family this points to; `None` when it names no provider
+    /// (e.g. forwarder software).
+    pub family: Option<String>,
+    pub confidence: IdentityConfidence,
+}
+
+/// Result of [`identify_resolver`].
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResolverIdentity {
+    /// Configured DNS servers, primary first.
+    pub servers: Vec<String>,
+    /// The configured server is on the local network or this machine (a
+    /// router, a Pi-hole, a local stub) and forwards queries upstream.
+    pub forwarder: bool,
+    /// Resolvers seen asking authoritative servers.
+    pub egress_ips: Vec<String>,
+    /// Provider family that answers, e.g. `"Cloudflare"`.
+    pub family: Option<String>,
+    /// Exact provider, when the variant is known as well.
+    pub provider: Option<DnsProvider>,
+    /// Confidence of the best evidence for `family`.
+    pub confidence: Option<IdentityConfidence>,
+    pub evidence: Vec<IdentityEvidence>,
+}
+
+// ==================== Evidence ====================
+
+/// Family whose servers use CHAOS id `id`.
+fn chaos_family(id: &str) -> Option<&'static str> {
+    let id = id.trim_end_matches('.').to_ascii_lowercase();
+    CHAOS_IDS
+        .iter()
+        .find(|(suffix, _)| id.ends_with(suffix))
+        .map(|(_, family)| *family)
+}
+
+/// What the network of egress resolver `ip` says about the provider: its ASN
+/// in the catalog, else a family named in its organisation.
+fn egress_evidence(ip: &str, info: Option<&IpInfoResponse>) -> IdentityEvidence {
+    let (asn, org) = info
+        .and_then(|info| info.org.as_deref())
+        .map_or((None, None), parse_org);
+    let catalog = DnsCatalog::built_in();
+    let by_asn = asn.and_then(|asn| catalog.find_by_asn(asn));
+    let by_name = org.as_deref().and_then(|org| {
+        let org = org.to_ascii_lowercase();
+        catalog
+            .providers
+            .iter()
+            .find(|p| org.contains(&p.family.to_ascii_lowercase()))
+    });
+    let (family, confidence) = match (by_asn, by_name) {
+        (Some(entry), _) => (Some(entry.family.clone()), IdentityConfidence::Medium),
+        (None, Some(entry)) => (Some(entry.family.clone()), IdentityConfidence::Low),
+        (None, None) => (None, IdentityConfidence::Low),
+    };
+    let network = match (asn, org) {
+        (Some(asn), Some(org)) => format!(" (AS{} {})", asn, org),
+        (Some(asn), None) => format!(" (AS{})", asn),
+        (None, Some(org)) => format!(" ({})", org),
+        (None, None) => String::new(),
+    };
+    IdentityEvidence {
+        method: IdentityMethod::EgressResolver,
+        detail: format!("{}{}", ip, network),
+        family,
+        confidence,
+    }
+}
+
+/// Family with the most confident evidence (the first one on a tie).
+fn conclude(evidence: &[IdentityEvidence]) -> Option<(&str, IdentityConfidence)> {
+    evidence
+        .iter()
+        .filter_map(|e| Some((e.family.as_deref()?, e.confidence)))
+        .fold(None, |best, (family, confidence)| match best {
+            Some((_, best_confidence)) if best_confidence >= confidence => best,
+            _ => Some((family, confidence)),
+        })
+}
+
+// ==================== Identification ====================
+
+/// Identify the provider behind the configured resolver, even when a local
+/// forwarder sits in front of it.
+pub fn identify_resolver(backend: &dyn NetworkBackend, targets: &ProbeTargets) -> ResolverIdentity {
+    crate::runtime::block_on(identify_resolver_async(backend, targets))
+}
+
+/// Async [`identify_resolver`].
+pub async fn identify_resolver_async(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+) -> ResolverIdentity {
+    identify_resolver_cancellable(backend, targets, &Cancellation::none())
+        .await
+        .value
+}
+
+/// [`identify_resolver_async`] that stops when `cancel` fires, concluding
+/// from the evidence gathered so far.
+pub async fn identify_resolver_cancellable(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    cancel: &Cancellation,
+) -> Partial<ResolverIdentity> {
+    let servers = blocking(|| backend.dns_servers()).unwrap_or_default();
+    let server = servers
+        .iter()
+        .find_map(|server| server.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53));
+    identify(backend, targets, servers, server, cancel).await
+}
+
+/// [`identify_resolver_cancellable`] with the server to query (`ip:port`)
+/// already worked out.
+async fn identify(
+    backend: &dyn NetworkBackend,
+    targets: &ProbeTargets,
+    servers: Vec<String>,
+    server: Option<SocketAddr>,
+    cancel: &Cancellation,
+) -> Partial<ResolverIdentity> {
+    let mut completion = Completion::Complete;
+    let mut evidence = Vec::new();
+    let configured = DnsCatalog::built_in().detect(&servers);
+    if let Some(entry) = configured {
+        evidence.push(IdentityEvidence {
+            method: IdentityMethod::ServerAddress,
+            detail: servers[0].clone(),
+            family: Some(entry.family.clone()),
+            confidence: IdentityConfidence::High,
+        });
+    }
+
+    let server = server.map(|server| server.to_string());
+    let txt = |name: &'static str, class: DnsClass| {
+        let server = server.as_deref();
+        async move {
+            let Some(server) = server else {
+                return Vec::new();
+            };
+            backend
+                .query_txt_async(name, server, class, IDENTITY_TIMEOUT)
+                .await
+        }
+    };
+    let leak_name = uncached_name(targets.dns_leak_zone.trim_end_matches('.'));
+    let probes = async {
+        tokio::join!(
+            join_all(
+                DEBUG_NAMES
+                    .iter()
+                    .map(|(_, name)| txt(name, DnsClass::Internet))
+            ),
+            join_all(CHAOS_NAMES.iter().map(|name| txt(name, DnsClass::Chaos))),
+            txt(VERSION_NAME, DnsClass::Chaos),
+            txt(EGRESS_TXT, DnsClass::Internet),
+            dns_leak::resolve(backend, targets, &leak_name),
+        )
+    };
+    let mut egress = BTreeSet::new();
+    if let Some((debug, chaos, version, egress_txt, egress_a)) =
+        cancel.step(&mut completion, probes).await
+    {
+        for ((family, name), answers) in DEBUG_NAMES.iter().zip(debug) {
+            if let Some(answer) = answers.first() {
+                evidence.push(IdentityEvidence {
+                    method: IdentityMethod::DebugName,
+                    detail: format!("{} {}", name.trim_end_matches('.'), answer),
+                    family: Some(family.to_string()),
+                    confidence: IdentityConfidence::High,
+                });
+            }
+        }
+        let chaos = CHAOS_NAMES
+            .iter()
+            .zip(chaos)
+            .chain([(&VERSION_NAME, version)]);
+        for (name, answers) in chaos {
+            if let Some(answer) = answers.first() {
+                let family = chaos_family(answer);
+                evidence.push(IdentityEvidence {
+                    method: IdentityMethod::Chaos,
+                    detail: format!("{} {}", name.trim_end_matches('.'), answer),
+                    family: family.map(str::to_string),
+                    confidence: match family {
+                        Some(_) => IdentityConfidence::High,
+                        None => IdentityConfidence::Low,
+                    },
+                });
+            }
+        }
+        // The TXT also carries an "edns0-client-subnet ..." string
+        let egress_txt = egress_txt
+            .into_iter()
+            .filter(|s| s.parse::<IpAddr>().is_ok());
+        egress.extend(egress_txt.chain(egress_a));
+    }
+
+    let egress_ips: Vec<String> = egress.into_iter().collect();
+    let lookups = join_all(
+        egress_ips
+            .iter()
+            .map(|ip| backend.ip_location_async(ip, targets)),
+    );
+    let networks = cancel
+        .step(&mut completion, lookups)
+        .await
+        .unwrap_or_else(|| vec![None; egress_ips.len()]);
+    evidence.extend(
+        egress_ips
+            .iter()
+            .zip(&networks)
+            .map(|(ip, info)| egress_evidence(ip, info.as_ref())),
+    );
+
+    let best = conclude(&evidence);
+    let family = best.map(|(family, _)| family.to_string());
+    // The variant is only known from the configured address
+    let provider = configured
+        .filter(|entry| family.as_deref() == Some(entry.family.as_str()))
+        .map(|entry| DnsProvider::Catalog(entry.id.clone()));
+    let identity = ResolverIdentity {
+        forwarder: servers.first().is_some_and(|server| {
+            is_private_ip_str(server) || server.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        }),
+        confidence: best.map(|(_, confidence)| confidence),
+        family,
+        provider,
+        servers,
+        egress_ips,
+        evidence,
+    };
+    Partial::new(identity, completion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{SimulatedBackend, SystemBackend};
+    use crate::cancel::CancelToken;
+    use trust_dns_resolver::proto::op::{Message, MessageType};
+    use trust_dns_resolver::proto::rr::{rdata, RData, Record, RecordType};
+    use trust_dns_resolver::proto::serialize::binary::BinDecodable;
+
+    /// Stand-in for a Pi-hole in front of Quad9: answers the CHAOS names, the
+    /// egress TXT with 192.0.2.53 and any A query with the asker's address.
+    /// Returns its address.
+    async fn forwarder() -> SocketAddr {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, from)) = socket.recv_from(&mut buf).await {
+                let Ok(query) = Message::from_bytes(&buf[..len]) else {
+                    continue;
+                };
+                let Some(question) = query.queries().first() else {
+                    continue;
+                };
+                let txt = |s: &str| RData::TXT(rdata::TXT::new(vec![s.to_string()]));
+                let data = match (question.name().to_ascii().as_str(), question.query_type()) {
+                    ("id.server.", _) => Some(txt("res100.qfra1.rrdns.pch.net")),
+                    ("version.bind.", _) => Some(txt("dnsmasq-pi-hole-2.90")),
+                    (EGRESS_TXT, _) => Some(txt("192.0.2.53")),
+                    (_, RecordType::A) => match from.ip() {
+                        IpAddr::V4(ip) => Some(RData::A(rdata::A(ip))),
+                        IpAddr::V6(_) => None,
+                    },
+                    _ => None,
+                };
+                let mut response = Message::new();
+                response
+                    .set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .add_queries(query.queries().to_vec());
+                if let Some(data) = data {
+                    let mut record = Record::from_rdata(question.name().clone(), 0, data);
+                    record.set_dns_class(question.query_class());
+                    response.add_answer(record);
+                }
+                let _ = socket.send_to(&response.to_vec().unwrap(), from).await;
+            }
+        });
+        addr
+    }
+
+    fn evidence(family: Option<&str>, confidence: IdentityConfidence) -> IdentityEvidence {
+        IdentityEvidence {
+            method: IdentityMethod::EgressResolver,
+            detail: String::new(),
+            family: family.map(str::to_string),
+            confidence,
+        }
+    }
+
+    fn org(org: &str) -> IpInfoResponse {
+        IpInfoResponse {
+            ip: None,
+            city: None,
+            country: None,
+            org: Some(org.into()),
+        }
+    }
+
+    #[test]
+    fn test_chaos_family() {
+        assert_eq!(chaos_family("res100.qfra1.rrdns.pch.net."), Some("Quad9"));
+        assert_eq!(chaos_family("RES1.RRDNS.PCH.NET"), Some("Quad9"));
+        assert_eq!(chaos_family("dnsmasq-2.90"), None);
+    }
+
+    #[test]
+    fn test_egress_network() {
+        let by_asn = egress_evidence("172.70.0.1", Some(&org("AS13335 Cloudflare, Inc.")));
+        assert_eq!(by_asn.family.as_deref(), Some("Cloudflare"));
+        assert_eq!(by_asn.confidence, IdentityConfidence::Medium);
+        assert_eq!(by_asn.detail, "172.70.0.1 (AS13335 Cloudflare, Inc.)");
+
+        let by_name = egress_evidence("192.0.2.1", Some(&org("AS64500 Quad9 Anycast")));
+        assert_eq!(by_name.family.as_deref(), Some("Quad9"));
+        assert_eq!(by_name.confidence, IdentityConfidence::Low);
+
+        let isp = egress_evidence("198.51.100.53", Some(&org("AS64501 Example ISP")));
+        assert_eq!(isp.family, None);
+        assert_eq!(egress_evidence("192.0.2.1", None).detail, "192.0.2.1");
+    }
+
+    #[test]
+    fn test_most_confident_family_wins() {
+        use IdentityConfidence::*;
+        let evidence = [
+            evidence(Some("Quad9"), Low),
+            evidence(None, High),
+            evidence(Some("Cloudflare"), Medium),
+            evidence(Some("Google"), Medium),
+        ];
+        assert_eq!(conclude(&evidence), Some(("Cloudflare", Medium)));
+        assert_eq!(conclude(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn test_identifies_upstream_behind_forwarder() {
+        let server = forwarder().await;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let targets = ProbeTargets {
+            dns_leak_zone: "whoami.test.".into(),
+            dns_leak_server: Some(server.to_string()),
+            geoip_base_url: closed,
+            ..ProbeTargets::default()
+        };
+        let servers = vec!["192.168.1.1".to_string()];
+        let identity = identify(
+            &SystemBackend,
+            &targets,
+            servers,
+            Some(server),
+            &Cancellation::none(),
+        )
+        .await
+        .value;
+        assert!(identity.forwarder);
+        assert_eq!(identity.family.as_deref(), Some("Quad9"));
+        assert_eq!(identity.confidence, Some(IdentityConfidence::High));
+        // The variant can't be told from behind a forwarder
+        assert_eq!(identity.provider, None);
+        assert_eq!(identity.egress_ips, ["127.0.0.1", "192.0.2.53"]);
+        assert!(identity
+            .evidence
+            .iter()
+            .any(|e| e.detail == "version.bind dnsmasq-pi-hole-2.90" && e.family.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_identification_keeps_server_address() {
+        let backend = SimulatedBackend {
+            dns_servers: vec!["9.9.9.9".into(), "149.112.112.112".into()],
+            ..SimulatedBackend::default()
+        };
+        let token = CancelToken::new();
+        token.cancel();
+        let partial = identify_resolver_cancellable(
+            &backend,
+            &ProbeTargets::default(),
+            &Cancellation::new(&token, None),
+        )
+        .await;
+        assert_eq!(partial.completion, Completion::Cancelled);
+        let identity = partial.value;
+        assert!(!identity.forwarder);
+        assert_eq!(identity.family.as_deref(), Some("Quad9"));
+        assert_eq!(
+            identity.provider,
+            Some(DnsProvider::Catalog("Quad9Recommended".into()))
+        );
+        assert_eq!(identity.confidence, Some(IdentityConfidence::High));
+    }
+}
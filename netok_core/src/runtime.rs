@@ -0,0 +1,77 @@
+//! The tokio runtime behind the blocking API.
+//!
+//! Network probes are async. The blocking functions drive them on one shared
+//! runtime through [`block_on`], and async code runs platform reads (shelling
+//! out to `netsh`, reading `/proc`) through [`blocking`] so they don't stall
+//! the runtime it is polled on.
+
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("netok-core")
+            .enable_all()
+            .build()
+            .expect("failed to start the netok_core runtime")
+    })
+}
+
+/// Run `future` to completion on the shared runtime, blocking the caller.
+///
+/// Tokio refuses to block a thread that is already inside a runtime (a
+/// `spawn_blocking` task, a `#[tokio::test]`), so there the future is driven
+/// from a scoped helper thread instead.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    if Handle::try_current().is_err() {
+        return runtime().block_on(future);
+    }
+    std::thread::scope(|s| {
+        s.spawn(|| runtime().block_on(future))
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+/// Run a blocking platform read from async code.
+///
+/// On a multi-threaded runtime the worker hands its other tasks off first;
+/// elsewhere (a current-thread runtime, plain threads) `f` simply runs inline.
+pub(crate) fn blocking<T>(f: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_on_outside_runtime() {
+        assert_eq!(block_on(async { 7 }), 7);
+    }
+
+    #[tokio::test]
+    async fn test_block_on_inside_current_thread_runtime() {
+        assert_eq!(block_on(async { 7 }), 7);
+        assert_eq!(blocking(|| 8), 8);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_blocking_on_multi_thread_runtime() {
+        assert_eq!(blocking(|| block_on(async { 9 })), 9);
+    }
+}
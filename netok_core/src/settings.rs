@@ -0,0 +1,137 @@
+//! Settings file.
+//!
+//! [`Settings`] are stored as one pretty-printed JSON file, rewritten
+//! atomically on save. Any field missing from the file takes its default, so a
+//! hand-written file only needs the values it overrides.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::domain::Settings;
+use crate::error::{SettingsError, StorageError};
+
+/// Read settings from `path`. Unlike [`SettingsStore::load`], a missing file
+/// is an error.
+pub fn load_settings(path: &Path) -> Result<Settings, SettingsError> {
+    let json = std::fs::read_to_string(path).map_err(StorageError::io("read settings file"))?;
+    serde_json::from_str(&json).map_err(|e| SettingsError::Invalid(e.to_string()))
+}
+
+/// The app's settings file.
+#[derive(Debug)]
+pub struct SettingsStore {
+    path: PathBuf,
+    /// Serializes writes within this process.
+    lock: Mutex<()>,
+}
+
+impl SettingsStore {
+    /// Open the store at `path`; the file is created on the first save.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(StorageError::io("create settings dir"))?;
+        }
+        Ok(Self {
+            path,
+            lock: Mutex::new(()),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stored settings, or the defaults if nothing was saved yet.
+    pub fn load(&self) -> Result<Settings, SettingsError> {
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        if !self.path.exists() {
+            return Ok(Settings::default());
+        }
+        load_settings(&self.path)
+    }
+
+    /// Replace the stored settings.
+    pub fn save(&self, settings: &Settings) -> Result<(), SettingsError> {
+        let json = serde_json::to_string_pretty(settings).map_err(|e| StorageError::Serialize {
+            what: "settings",
+            message: e.to_string(),
+        })?;
+
+        let _guard = self.lock.lock().map_err(|_| StorageError::LockPoisoned)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        {
+            let mut tmp =
+                File::create(&tmp_path).map_err(StorageError::io("create temp settings file"))?;
+            tmp.write_all(json.as_bytes())
+                .map_err(StorageError::io("write temp settings file"))?;
+            tmp.sync_all()
+                .map_err(StorageError::io("flush temp settings file"))?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(StorageError::io("replace settings file"))
+            .map_err(SettingsError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    #[test]
+    fn test_missing_file_loads_defaults() {
+        let path = TempPath::new("settings_missing.json");
+        let store = SettingsStore::open(&path.0).unwrap();
+        assert_eq!(store.load().unwrap(), Settings::default());
+        assert!(load_settings(&path.0).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let path = TempPath::new("settings_roundtrip.json");
+        let store = SettingsStore::open(&path.0).unwrap();
+        let settings = Settings {
+            language: "de".into(),
+            probe_targets: crate::domain::ProbeTargets {
+                geoip_base_url: "http://127.0.0.1:8080".into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        store.save(&settings).unwrap();
+
+        assert_eq!(store.load().unwrap(), settings);
+    }
+
+    #[test]
+    fn test_partial_file_fills_defaults() {
+        let path = TempPath::new("settings_partial.json");
+        std::fs::write(
+            &path.0,
+            r#"{"probe_targets": {"dns_leak_zone": "leak.example.net"}}"#,
+        )
+        .unwrap();
+
+        let settings = load_settings(&path.0).unwrap();
+        assert_eq!(settings.language, "en");
+        assert_eq!(settings.probe_targets.dns_leak_zone, "leak.example.net");
+        assert_eq!(
+            settings.probe_targets.geoip_base_url,
+            Settings::default().probe_targets.geoip_base_url
+        );
+    }
+
+    #[test]
+    fn test_invalid_file_is_a_parse_error() {
+        let path = TempPath::new("settings_invalid.json");
+        std::fs::write(&path.0, "{ not json").unwrap();
+        let store = SettingsStore::open(&path.0).unwrap();
+        assert_eq!(
+            store.load().unwrap_err().kind(),
+            crate::error::ErrorKind::Parse
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Fixtures shared by the unit tests.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Unique temp path per test, removed on drop.
+pub(crate) struct TempPath(pub(crate) PathBuf);
+
+impl TempPath {
+    /// A path ending in `name` (e.g. `history.jsonl`) that no other test uses.
+    pub(crate) fn new(name: &str) -> Self {
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        Self(std::env::temp_dir().join(format!("netok_test_{}_{}_{}", std::process::id(), n, name)))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
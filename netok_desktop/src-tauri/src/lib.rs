@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use tauri::{
     menu::{Menu, MenuItem},
@@ -7,6 +8,8 @@ use tauri::{
     Emitter, Manager,
 };
 
+use netok_bridge::{ErrorInfo, ErrorKind};
+
 // Re-export types from netok_bridge
 pub use netok_bridge::{DnsProviderType, IpInfoResponse, SingleNodeResult, Snapshot};
 
@@ -38,13 +41,58 @@ impl Default for VpnProcessState {
 mod win_elevation {
     use std::path::Path;
 
+    use netok_bridge::{ErrorInfo, ErrorKind};
+
+    /// Why an elevated launch failed.
+    #[derive(Debug)]
+    pub enum ElevationError {
+        /// The user declined the UAC prompt.
+        Denied,
+        Failed(String),
+    }
+
+    impl std::fmt::Display for ElevationError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                ElevationError::Denied => write!(f, "elevation_denied"),
+                ElevationError::Failed(msg) => write!(f, "elevation_failed: {}", msg),
+            }
+        }
+    }
+
+    /// Denial gets its own key so the UI can tell it from a failed launch.
+    impl From<ElevationError> for ErrorInfo {
+        fn from(e: ElevationError) -> Self {
+            match e {
+                ElevationError::Denied => ErrorInfo {
+                    key: "errors.elevation_denied".to_string(),
+                    ..ErrorInfo::new(ErrorKind::PermissionDenied, e.to_string())
+                },
+                ElevationError::Failed(_) => {
+                    ErrorInfo::new(ErrorKind::CommandFailed, e.to_string())
+                }
+            }
+        }
+    }
+
+    /// HRESULT_FROM_WIN32(ERROR_CANCELLED): the user clicked No on UAC.
+    const HRESULT_ERROR_CANCELLED: i32 = 0x8007_04C7_u32 as i32;
+
+    fn shell_execute_error(e: windows::core::Error) -> ElevationError {
+        if e.code().0 == HRESULT_ERROR_CANCELLED {
+            ElevationError::Denied
+        } else {
+            ElevationError::Failed(e.to_string())
+        }
+    }
+
     /// Spawn a process elevated (with UAC prompt) and return (PID, raw HANDLE).
     /// The caller MUST close the handle via `close_handle()` or `terminate_process()`.
     pub fn spawn_elevated(
         exe_path: &Path,
         args: &str,
         working_dir: &Path,
-    ) -> Result<(u32, isize), String> {
+    ) -> Result<(u32, isize), ElevationError> {
         use std::ffi::OsStr;
         use std::mem;
         use std::os::windows::ffi::OsStrExt;
@@ -77,22 +125,21 @@ mod win_elevation {
         sei.lpDirectory = PCWSTR(dir.as_ptr());
         sei.nShow = 0; // SW_HIDE
 
-        unsafe { ShellExecuteExW(&mut sei) }.map_err(|e| {
-            format!(
-                "Failed to launch elevated process (UAC denied or error): {}",
-                e
-            )
-        })?;
+        unsafe { ShellExecuteExW(&mut sei) }.map_err(shell_execute_error)?;
 
         let hprocess = sei.hProcess;
         if hprocess.is_invalid() {
-            return Err("Process handle is invalid".to_string());
+            return Err(ElevationError::Failed(
+                "Process handle is invalid".to_string(),
+            ));
         }
 
         let pid = unsafe { GetProcessId(hprocess) };
         if pid == 0 {
             let _ = unsafe { CloseHandle(hprocess) };
-            return Err("Failed to get process ID".to_string());
+            return Err(ElevationError::Failed(
+                "Failed to get process ID".to_string(),
+            ));
         }
 
         // Return raw handle value as isize — caller owns it
@@ -115,7 +162,7 @@ mod win_elevation {
     }
 
     /// Run a command elevated (UAC prompt), wait for it to finish, and return the result.
-    pub fn run_elevated_wait(exe_path: &Path, args: &str) -> Result<(), String> {
+    pub fn run_elevated_wait(exe_path: &Path, args: &str) -> Result<(), ErrorInfo> {
         use std::ffi::OsStr;
         use std::mem;
         use std::os::windows::ffi::OsStrExt;
@@ -146,19 +193,11 @@ mod win_elevation {
         sei.lpParameters = PCWSTR(params.as_ptr());
         sei.nShow = 0; // SW_HIDE
 
-        unsafe { ShellExecuteExW(&mut sei) }.map_err(|e| {
-            // ERROR_CANCELLED (1223) = user clicked No on UAC
-            let msg = e.to_string();
-            if msg.contains("1223") || msg.contains("cancelled") {
-                "elevation_denied".to_string()
-            } else {
-                format!("elevation_failed: {}", msg)
-            }
-        })?;
+        unsafe { ShellExecuteExW(&mut sei) }.map_err(shell_execute_error)?;
 
         let hprocess = sei.hProcess;
         if hprocess.is_invalid() {
-            return Err("Process handle is invalid".to_string());
+            return Err(ErrorInfo::internal("Process handle is invalid"));
         }
 
         // Wait up to 60 seconds for the process to finish
@@ -169,9 +208,9 @@ mod win_elevation {
         let _ = unsafe { CloseHandle(hprocess) };
 
         if exit_code != 0 {
-            return Err(format!(
-                "dns_config_failed: netsh exited with code {}",
-                exit_code
+            return Err(ErrorInfo::new(
+                ErrorKind::CommandFailed,
+                format!("dns_config_failed: netsh exited with code {}", exit_code),
             ));
         }
 
@@ -193,9 +232,9 @@ mod win_elevation {
         let _ = unsafe { CloseHandle(handle) };
 
         if wait_result != WAIT_OBJECT_0 {
-            eprintln!(
-                "[VPN] Warning: process did not exit within 5s (wait_result={:?})",
-                wait_result
+            tracing::warn!(
+                wait_result = ?wait_result,
+                "sing-box process did not exit within 5s"
             );
         }
 
@@ -269,27 +308,86 @@ mod win_elevation {
 
 // ==================== Existing Commands ====================
 
+/// Settings file, `None` if the data dir couldn't be opened (settings then
+/// last until exit).
+#[derive(Default)]
+struct SettingsState(Option<Arc<netok_bridge::SettingsStore>>);
+
+impl SettingsState {
+    /// Open the settings file and apply what it holds.
+    fn open(app: &tauri::AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| {
+                netok_bridge::SettingsStore::open(dir.join("settings.json"))
+                    .map_err(|e| e.to_string())
+            });
+        match store {
+            Ok(store) => {
+                let store = Arc::new(store);
+                if let Err(e) =
+                    tauri::async_runtime::block_on(netok_bridge::load_settings(store.clone()))
+                {
+                    tracing::warn!(error = %e, "settings file unreadable, using defaults");
+                }
+                Self(Some(store))
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "settings will not be saved");
+                Self(None)
+            }
+        }
+    }
+}
+
 #[tauri::command]
 fn get_settings() -> String {
     netok_bridge::get_settings_json()
 }
 
 #[tauri::command]
-fn set_settings(json: String) -> Result<(), String> {
-    netok_bridge::set_settings_json(&json)
-        .map(|_| ())
-        .map_err(|e| e.to_string())
+async fn set_settings(
+    json: String,
+    settings: tauri::State<'_, SettingsState>,
+) -> Result<(), ErrorInfo> {
+    let parsed = netok_bridge::set_settings_json(&json)
+        .map_err(|e| ErrorInfo::new(ErrorKind::Parse, e.to_string()))?;
+    if let Some(store) = settings.0.clone() {
+        netok_bridge::save_settings(store, parsed).await?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn run_diagnostics() -> Result<netok_bridge::Snapshot, String> {
-    netok_bridge::run_diagnostics_struct()
-        .await
-        .map_err(|e| e.to_string())
+async fn run_diagnostics(
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    history: tauri::State<'_, HistoryState>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::Snapshot>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    let snapshot = netok_bridge::run_diagnostics_cancellable(
+        demo.backend(),
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await;
+    if let Some(store) = history
+        .store()
+        .filter(|_| !demo.is_active() && snapshot.is_complete())
+    {
+        if let Err(e) = netok_bridge::record_diagnostics(store, snapshot.value.clone()).await {
+            tracing::warn!(error = %e, "failed to record diagnostics in history");
+        }
+    }
+    Ok(snapshot)
 }
 
 #[tauri::command]
-async fn set_dns(provider: DnsProviderType) -> Result<(), String> {
+async fn set_dns(provider: DnsProviderType) -> Result<(), ErrorInfo> {
     let log_path = std::env::temp_dir().join("netok_dns.log");
 
     // Build netsh commands (no elevation needed — only reads adapter name)
@@ -322,7 +420,7 @@ async fn set_dns(provider: DnsProviderType) -> Result<(), String> {
     let mut bat_bytes = vec![0xEF, 0xBB, 0xBF]; // UTF-8 BOM
     bat_bytes.extend_from_slice(bat_content.as_bytes());
     std::fs::write(&bat_path, &bat_bytes)
-        .map_err(|e| format!("Failed to write DNS script: {}", e))?;
+        .map_err(|e| ErrorInfo::new(ErrorKind::Io, format!("Failed to write DNS script: {}", e)))?;
 
     log.push_str(&format!("[DNS] Bat written to: {}\n", bat_path.display()));
     log.push_str(&format!("[DNS] Bat content:\n{}\n", bat_content));
@@ -337,7 +435,7 @@ async fn set_dns(provider: DnsProviderType) -> Result<(), String> {
         let res =
             tokio::task::spawn_blocking(move || win_elevation::run_elevated_wait(&cmd_exe, &args))
                 .await
-                .map_err(|e| format!("Task error: {}", e))?;
+                .map_err(ErrorInfo::internal)?;
         match &res {
             Ok(()) => log.push_str("[DNS] SUCCESS\n"),
             Err(e) => log.push_str(&format!("[DNS] FAILED: {}\n", e)),
@@ -346,7 +444,10 @@ async fn set_dns(provider: DnsProviderType) -> Result<(), String> {
     };
 
     #[cfg(not(target_os = "windows"))]
-    let result = Err::<(), String>("DNS configuration requires Windows".to_string());
+    let result = Err(ErrorInfo::new(
+        ErrorKind::UnsupportedPlatform,
+        "DNS configuration requires Windows",
+    ));
 
     // Write log file (always, for debugging)
     let _ = std::fs::write(&log_path, &log);
@@ -358,33 +459,166 @@ async fn set_dns(provider: DnsProviderType) -> Result<(), String> {
 }
 
 #[tauri::command]
-async fn get_dns_provider() -> Result<DnsProviderType, String> {
-    netok_bridge::get_dns_provider()
-        .await
-        .map_err(|e| e.to_string())
+async fn get_dns_provider() -> Result<DnsProviderType, ErrorInfo> {
+    netok_bridge::get_dns_provider().await
 }
 
 #[tauri::command]
-async fn get_dns_servers() -> Result<Vec<String>, String> {
-    netok_bridge::get_dns_servers()
-        .await
-        .map_err(|e| e.to_string())
+async fn get_dns_servers() -> Result<Vec<String>, ErrorInfo> {
+    netok_bridge::get_dns_servers().await
 }
 
 #[tauri::command]
-async fn test_dns_server(server_ip: String) -> Result<bool, String> {
-    netok_bridge::test_dns_server_reachable(server_ip)
-        .await
-        .map_err(|e| e.to_string())
+async fn test_dns_server(server_ip: String) -> Result<bool, ErrorInfo> {
+    netok_bridge::test_dns_server_reachable(server_ip).await
 }
 
 #[tauri::command]
-async fn ping_dns_server(server_ip: String) -> Result<Option<u64>, String> {
+async fn ping_dns_server(server_ip: String) -> Result<Option<u64>, ErrorInfo> {
     netok_bridge::ping_dns_server(server_ip).await
 }
 
 #[tauri::command]
-fn run_all() -> Result<serde_json::Value, String> {
+async fn check_dns_transports(
+    provider: DnsProviderType,
+) -> Result<Vec<netok_bridge::DnsTransportStatus>, ErrorInfo> {
+    netok_bridge::check_dns_transports(provider).await
+}
+
+#[tauri::command]
+fn get_dns_catalog() -> netok_bridge::DnsCatalog {
+    netok_bridge::get_dns_catalog().clone()
+}
+
+// ==================== DNS Presets ====================
+
+/// Preset store, `None` if the data dir couldn't be opened.
+#[derive(Default)]
+struct PresetState(Option<Arc<netok_bridge::PresetStore>>);
+
+impl PresetState {
+    fn store(&self) -> Result<Arc<netok_bridge::PresetStore>, ErrorInfo> {
+        self.0
+            .clone()
+            .ok_or_else(|| ErrorInfo::new(ErrorKind::NotFound, "DNS presets are not available"))
+    }
+
+    fn open(app: &tauri::AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| {
+                netok_bridge::PresetStore::open(dir.join("dns_presets.json"))
+                    .map_err(|e| e.to_string())
+            });
+        match store {
+            Ok(store) => Self(Some(Arc::new(store))),
+            Err(e) => {
+                tracing::warn!(error = %e, "DNS presets disabled");
+                Self(None)
+            }
+        }
+    }
+}
+
+#[tauri::command]
+async fn list_dns_presets(
+    presets: tauri::State<'_, PresetState>,
+) -> Result<Vec<netok_bridge::DnsPresetInfo>, ErrorInfo> {
+    netok_bridge::list_dns_presets(presets.store()?).await
+}
+
+/// Save `provider` as preset `name`, replacing one with the same name.
+#[tauri::command]
+async fn save_dns_preset(
+    name: String,
+    provider: DnsProviderType,
+    presets: tauri::State<'_, PresetState>,
+) -> Result<(), ErrorInfo> {
+    netok_bridge::save_dns_preset(presets.store()?, name, provider).await
+}
+
+#[tauri::command]
+async fn delete_dns_preset(
+    name: String,
+    presets: tauri::State<'_, PresetState>,
+) -> Result<bool, ErrorInfo> {
+    netok_bridge::delete_dns_preset(presets.store()?, name).await
+}
+
+#[tauri::command]
+async fn run_dns_benchmark(
+    options: Option<netok_bridge::BenchmarkOptions>,
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::DnsBenchmark>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    Ok(netok_bridge::run_dns_benchmark_cancellable(
+        demo.backend(),
+        options.unwrap_or_default(),
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await)
+}
+
+#[tauri::command]
+async fn check_dnssec(
+    timeout_secs: Option<u64>,
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::DnssecReport>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    Ok(netok_bridge::check_dnssec_cancellable(
+        demo.backend(),
+        timeout_secs.unwrap_or(2),
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await)
+}
+
+#[tauri::command]
+async fn verify_dns_filtering(
+    provider: Option<DnsProviderType>,
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::FilteringReport>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    netok_bridge::verify_dns_filtering_cancellable(
+        demo.backend(),
+        provider.unwrap_or(DnsProviderType::Auto),
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await
+}
+
+#[tauri::command]
+async fn identify_resolver(
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::ResolverIdentity>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    Ok(netok_bridge::identify_resolver_cancellable(
+        demo.backend(),
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await)
+}
+
+#[tauri::command]
+fn run_all() -> Result<serde_json::Value, ErrorInfo> {
     let data = serde_json::json!({
         "hostname": "test-hostname",
         "local_ip": "192.168.1.100",
@@ -398,46 +632,47 @@ fn run_all() -> Result<serde_json::Value, String> {
 // ==================== IP Geolocation ====================
 
 #[tauri::command]
-async fn lookup_ip_location(ip: String) -> Result<IpInfoResponse, String> {
-    netok_bridge::lookup_ip_location(ip)
-        .await
-        .map_err(|e| e.to_string())
+async fn lookup_ip_location(ip: String) -> Result<IpInfoResponse, ErrorInfo> {
+    netok_bridge::lookup_ip_location(ip).await
 }
 
 // ==================== Progressive Diagnostics Commands ====================
 
 #[tauri::command]
-async fn check_computer() -> Result<SingleNodeResult, String> {
-    netok_bridge::check_computer_node()
+async fn check_computer(demo: tauri::State<'_, DemoState>) -> Result<SingleNodeResult, ErrorInfo> {
+    netok_bridge::check_computer_node_with_backend(demo.backend())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(ErrorInfo::internal)
 }
 
 #[tauri::command]
-async fn check_network(adapter: Option<String>) -> Result<SingleNodeResult, String> {
-    netok_bridge::check_network_node(adapter)
+async fn check_network(
+    demo: tauri::State<'_, DemoState>,
+    adapter: Option<String>,
+) -> Result<SingleNodeResult, ErrorInfo> {
+    netok_bridge::check_network_node_with_backend(demo.backend(), adapter)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(ErrorInfo::internal)
 }
 
 #[tauri::command]
-async fn check_router() -> Result<SingleNodeResult, String> {
-    netok_bridge::check_router_node()
+async fn check_router(demo: tauri::State<'_, DemoState>) -> Result<SingleNodeResult, ErrorInfo> {
+    netok_bridge::check_router_node_with_backend(demo.backend())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(ErrorInfo::internal)
 }
 
 #[tauri::command]
-async fn check_internet() -> Result<SingleNodeResult, String> {
-    netok_bridge::check_internet_node()
+async fn check_internet(demo: tauri::State<'_, DemoState>) -> Result<SingleNodeResult, ErrorInfo> {
+    netok_bridge::check_internet_node_with_backend(demo.backend())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(ErrorInfo::internal)
 }
 
 // ==================== Flush DNS ====================
 
 #[tauri::command]
-async fn flush_dns() -> Result<(), String> {
+async fn flush_dns() -> Result<(), ErrorInfo> {
     netok_bridge::flush_dns().await
 }
 
@@ -446,25 +681,282 @@ async fn flush_dns() -> Result<(), String> {
 #[tauri::command]
 async fn scan_network_devices(
     app: tauri::AppHandle,
-) -> Result<Vec<netok_bridge::NetworkDevice>, String> {
-    netok_bridge::scan_network_devices_with_progress(move |stage| {
-        let _ = app.emit("scan-progress", stage);
-    })
-    .await
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    history: tauri::State<'_, HistoryState>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<Vec<netok_bridge::NetworkDevice>>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    let devices = netok_bridge::scan_network_devices_cancellable(
+        demo.backend(),
+        move |stage| {
+            let _ = app.emit("scan-progress", stage);
+        },
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await;
+    if let Some(store) = history
+        .store()
+        .filter(|_| !demo.is_active() && devices.is_complete())
+    {
+        if let Err(e) = netok_bridge::record_device_scan(store, devices.value.clone()).await {
+            tracing::warn!(error = %e, "failed to record device scan in history");
+        }
+    }
+    Ok(devices)
 }
 
 // ==================== WiFi Security ====================
 
 #[tauri::command]
-async fn check_wifi_security() -> Result<netok_bridge::WiFiSecurityReport, String> {
-    netok_bridge::check_wifi_security().await
+async fn check_wifi_security(
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    history: tauri::State<'_, HistoryState>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::WiFiSecurityReport>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    let report = netok_bridge::check_wifi_security_cancellable(
+        demo.backend(),
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await;
+    if let Some(store) = history
+        .store()
+        .filter(|_| !demo.is_active() && report.is_complete())
+    {
+        if let Err(e) = netok_bridge::record_security_report(store, report.value.clone()).await {
+            tracing::warn!(error = %e, "failed to record security report in history");
+        }
+    }
+    Ok(report)
+}
+
+// ==================== Cancellation ====================
+
+/// Cancel the in-flight operation started with `operation_id` = `id`.
+/// Returns `false` if it already finished.
+#[tauri::command]
+fn cancel_operation(
+    id: String,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> bool {
+    operations.cancel(&id)
+}
+
+// ==================== Demo Mode ====================
+
+/// Scenario simulated instead of the real network, `None` in normal operation.
+#[derive(Default)]
+struct DemoState(Mutex<Option<netok_bridge::DiagnosticScenario>>);
+
+impl DemoState {
+    fn scenario(&self) -> Option<netok_bridge::DiagnosticScenario> {
+        self.0.lock().ok().and_then(|s| *s)
+    }
+
+    fn is_active(&self) -> bool {
+        self.scenario().is_some()
+    }
+
+    /// Backend for diagnostics, scans and security checks.
+    fn backend(&self) -> Arc<dyn netok_bridge::NetworkBackend> {
+        match self.scenario() {
+            Some(scenario) => netok_bridge::simulated_backend(scenario),
+            None => netok_bridge::system_backend(),
+        }
+    }
+}
+
+/// Switch demo mode on (with the scenario to simulate) or off (`None`).
+///
+/// While on, checks run against a simulated network and nothing is recorded
+/// in history.
+#[tauri::command]
+fn set_demo_scenario(
+    demo: tauri::State<'_, DemoState>,
+    scenario: Option<netok_bridge::DiagnosticScenario>,
+) -> Result<(), ErrorInfo> {
+    *demo.0.lock().map_err(ErrorInfo::internal)? = scenario;
+    tracing::info!(scenario = ?scenario, "demo scenario changed");
+    Ok(())
+}
+
+#[tauri::command]
+fn get_demo_scenario(
+    demo: tauri::State<'_, DemoState>,
+) -> Option<netok_bridge::DiagnosticScenario> {
+    demo.scenario()
+}
+
+// ==================== History ====================
+
+/// History store, `None` if the data dir couldn't be opened.
+#[derive(Default)]
+struct HistoryState(Option<Arc<netok_bridge::HistoryStore>>);
+
+impl HistoryState {
+    fn store(&self) -> Option<Arc<netok_bridge::HistoryStore>> {
+        self.0.clone()
+    }
+
+    /// Store for commands that need history to work at all.
+    fn require(&self) -> Result<Arc<netok_bridge::HistoryStore>, ErrorInfo> {
+        self.store()
+            .ok_or_else(|| ErrorInfo::new(ErrorKind::NotFound, "History is not available"))
+    }
+
+    fn open(app: &tauri::AppHandle) -> Self {
+        let store = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| {
+                netok_bridge::HistoryStore::open(
+                    dir.join("history.jsonl"),
+                    netok_bridge::RetentionPolicy::default(),
+                )
+                .map_err(|e| e.to_string())
+            });
+        match store {
+            Ok(store) => Self(Some(Arc::new(store))),
+            Err(e) => {
+                tracing::warn!(error = %e, "history disabled");
+                Self(None)
+            }
+        }
+    }
+}
+
+/// Store the result of a progressive diagnostics run assembled by the UI.
+#[tauri::command]
+async fn record_diagnostics(
+    history: tauri::State<'_, HistoryState>,
+    demo: tauri::State<'_, DemoState>,
+    snapshot: netok_bridge::Snapshot,
+) -> Result<(), ErrorInfo> {
+    // Simulated results don't belong in the user's history
+    if demo.is_active() {
+        return Ok(());
+    }
+    let store = history.require()?;
+    netok_bridge::record_diagnostics(store, snapshot).await?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn query_history(
+    history: tauri::State<'_, HistoryState>,
+    query: netok_bridge::HistoryQuery,
+) -> Result<Vec<netok_bridge::HistoryRecord>, ErrorInfo> {
+    let store = history.require()?;
+    netok_bridge::query_history(store, query).await
+}
+
+#[tauri::command]
+fn diff_snapshots(
+    before: netok_bridge::Snapshot,
+    after: netok_bridge::Snapshot,
+) -> Vec<netok_bridge::SnapshotChange> {
+    netok_bridge::diff_snapshots(&before, &after)
+}
+
+#[tauri::command]
+fn diff_history_records(
+    before: netok_bridge::HistoryRecord,
+    after: netok_bridge::HistoryRecord,
+) -> Result<Vec<netok_bridge::SnapshotChange>, ErrorInfo> {
+    netok_bridge::diff_history_records(&before, &after)
+}
+
+#[tauri::command]
+async fn clear_history(history: tauri::State<'_, HistoryState>) -> Result<(), ErrorInfo> {
+    let store = history.require()?;
+    Ok(tokio::task::spawn_blocking(move || store.clear())
+        .await
+        .map_err(ErrorInfo::internal)??)
+}
+
+// ==================== Report Export ====================
+
+/// Export a diagnostic report to `path`.
+///
+/// Format is taken from `format` or the file extension. When `history_limit`
+/// is set, the most recent history records are included. `redaction`
+/// defaults to none, `language` (the UI's) to English.
+#[tauri::command]
+async fn export_report(
+    history: tauri::State<'_, HistoryState>,
+    path: String,
+    format: Option<netok_bridge::ReportFormat>,
+    snapshot: Option<netok_bridge::Snapshot>,
+    security: Option<netok_bridge::WiFiSecurityReport>,
+    devices: Option<Vec<netok_bridge::NetworkDevice>>,
+    history_limit: Option<usize>,
+    redaction: Option<netok_bridge::RedactionLevel>,
+    language: Option<String>,
+) -> Result<(), ErrorInfo> {
+    let records = match (history_limit, history.store()) {
+        (Some(limit), Some(store)) => {
+            let query = netok_bridge::HistoryQuery {
+                limit: Some(limit),
+                ..Default::default()
+            };
+            netok_bridge::query_history(store, query).await?
+        }
+        _ => Vec::new(),
+    };
+    let report = netok_bridge::build_report(
+        snapshot.as_ref(),
+        security,
+        devices,
+        records,
+        redaction.unwrap_or_default(),
+        language.as_deref().unwrap_or("en"),
+    );
+    netok_bridge::save_report(PathBuf::from(path), format, report).await
+}
+
+// ==================== Probe Capture ====================
+
+/// Run diagnostics and security checks, saving every raw probe response to
+/// `path` so the run can be replayed offline.
+#[tauri::command]
+async fn record_capture(
+    demo: tauri::State<'_, DemoState>,
+    path: String,
+) -> Result<netok_bridge::CaptureRun, ErrorInfo> {
+    let path = PathBuf::from(path);
+    tracing::info!(path = %path.display(), "recording probe capture");
+    netok_bridge::record_capture(demo.backend(), path).await
+}
+
+/// Replay a capture file recorded by `record_capture`.
+#[tauri::command]
+async fn replay_capture(path: String) -> Result<netok_bridge::CaptureRun, ErrorInfo> {
+    netok_bridge::replay_capture(PathBuf::from(path)).await
 }
 
 // ==================== VPN Commands ====================
 
 #[tauri::command]
-async fn validate_vpn_key(raw_uri: String) -> Result<netok_bridge::VpnKeyValidation, String> {
-    netok_bridge::validate_vpn_key(raw_uri).await
+async fn validate_vpn_key(
+    raw_uri: String,
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::VpnKeyValidation>, ErrorInfo> {
+    let operation = operations.register(operation_id);
+    Ok(netok_bridge::validate_vpn_key_cancellable(
+        raw_uri,
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await)
 }
 
 /// Resolve the sing-box sidecar binary path.
@@ -472,7 +964,7 @@ async fn validate_vpn_key(raw_uri: String) -> Result<netok_bridge::VpnKeyValidat
 /// Tauri v2 NSIS strips the target triple and flattens the directory, so:
 /// - Build time: `binaries/sing-box-x86_64-pc-windows-msvc.exe`
 /// - Installed:  `sing-box.exe` (in install root)
-fn get_singbox_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+fn get_singbox_path(app: &tauri::AppHandle) -> Result<PathBuf, ErrorInfo> {
     // Names to check: NSIS-installed name first (most common), then dev name with triple
     let names: &[&str] = if cfg!(target_os = "windows") {
         &["sing-box.exe", "sing-box-x86_64-pc-windows-msvc.exe"]
@@ -529,20 +1021,24 @@ fn get_singbox_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
         checked_paths.push(dev_path.to_string_lossy().to_string());
     }
 
-    Err(format!(
-        "sing-box binary not found. Checked:\n{}",
-        checked_paths.join("\n")
+    Err(ErrorInfo::new(
+        ErrorKind::NotFound,
+        format!(
+            "sing-box binary not found. Checked:\n{}",
+            checked_paths.join("\n")
+        ),
     ))
 }
 
 /// Get the path for the sing-box config file in app data dir.
-fn get_config_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+fn get_config_path(app: &tauri::AppHandle) -> Result<PathBuf, ErrorInfo> {
     let data_dir = app
         .path()
         .app_data_dir()
-        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+        .map_err(|e| ErrorInfo::internal(format!("Failed to get app data dir: {}", e)))?;
 
-    std::fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    std::fs::create_dir_all(&data_dir)
+        .map_err(|e| ErrorInfo::new(ErrorKind::Io, format!("Failed to create data dir: {}", e)))?;
 
     Ok(data_dir.join("singbox-config.json"))
 }
@@ -552,7 +1048,7 @@ async fn connect_vpn(
     app: tauri::AppHandle,
     vpn_state: tauri::State<'_, Arc<Mutex<VpnProcessState>>>,
     raw_uri: String,
-) -> Result<(), String> {
+) -> Result<(), ErrorInfo> {
     // Step 1: Generate sing-box config from URI (with log file for debugging)
     let config_path = get_config_path(&app)?;
     let log_path = config_path
@@ -565,14 +1061,14 @@ async fn connect_vpn(
 
     // Step 2: Write config to disk
     std::fs::write(&config_path, &config_json)
-        .map_err(|e| format!("Failed to write config: {}", e))?;
+        .map_err(|e| ErrorInfo::new(ErrorKind::Io, format!("Failed to write config: {}", e)))?;
 
     // Step 3: Resolve sing-box binary path
     let singbox_path = get_singbox_path(&app)?;
 
     // Step 4: Set state to Connecting
     {
-        let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+        let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
         state.state = netok_bridge::VpnConnectionState::Connecting;
         state.config_path = Some(config_path.clone());
     }
@@ -580,6 +1076,9 @@ async fn connect_vpn(
     // Step 5: Spawn elevated process (Windows only)
     #[cfg(target_os = "windows")]
     {
+        // Public IP before the tunnel is up: the DNS leak test's ISP reference
+        let original_ip = netok_bridge::verify_vpn_ip().await.ok().flatten();
+
         let args = format!("run -c \"{}\"", config_path.to_string_lossy());
         // Use the sing-box binary directory as working dir so wintun.dll is found
         let working_dir = singbox_path.parent().unwrap().to_path_buf();
@@ -589,15 +1088,15 @@ async fn connect_vpn(
             win_elevation::spawn_elevated(&singbox_path_clone, &args, &working_dir)
         })
         .await
-        .map_err(|e| format!("Task error: {}", e))?;
+        .map_err(ErrorInfo::internal)?;
 
         match spawn_result {
             Ok((pid, handle)) => {
-                eprintln!("[VPN] sing-box started with PID: {}", pid);
+                tracing::info!(pid, "sing-box started");
 
                 // Store PID and handle
                 {
-                    let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+                    let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
                     state.elevated_pid = Some(pid);
                     state.elevated_handle = Some(handle);
                 }
@@ -609,10 +1108,10 @@ async fn connect_vpn(
                 let alive =
                     tokio::task::spawn_blocking(move || win_elevation::is_process_alive(pid))
                         .await
-                        .map_err(|e| e.to_string())?;
+                        .map_err(ErrorInfo::internal)?;
 
                 if !alive {
-                    let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+                    let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
                     state.state = netok_bridge::VpnConnectionState::Error {
                         message: "sing-box process exited immediately after start".to_string(),
                     };
@@ -621,7 +1120,10 @@ async fn connect_vpn(
                     if let Some(h) = state.elevated_handle.take() {
                         win_elevation::close_handle(h);
                     }
-                    return Err("sing-box process exited immediately".to_string());
+                    return Err(ErrorInfo::new(
+                        ErrorKind::CommandFailed,
+                        "sing-box process exited immediately",
+                    ));
                 }
 
                 // Step 8: Verify IP changed
@@ -629,9 +1131,9 @@ async fn connect_vpn(
 
                 // Step 9: Update state to Connected
                 {
-                    let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+                    let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
                     state.state = netok_bridge::VpnConnectionState::Connected {
-                        original_ip: None,
+                        original_ip,
                         vpn_ip,
                     };
                 }
@@ -646,35 +1148,71 @@ async fn connect_vpn(
                 Ok(())
             }
             Err(e) => {
-                let mut state = vpn_state.lock().map_err(|err| err.to_string())?;
-                if e.contains("UAC denied") || e.contains("cancelled") {
-                    state.state = netok_bridge::VpnConnectionState::ElevationDenied;
-                } else {
-                    state.state = netok_bridge::VpnConnectionState::Error { message: e.clone() };
-                }
+                let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
+                state.state = match e {
+                    win_elevation::ElevationError::Denied => {
+                        netok_bridge::VpnConnectionState::ElevationDenied
+                    }
+                    win_elevation::ElevationError::Failed(_) => {
+                        netok_bridge::VpnConnectionState::Error {
+                            message: e.to_string(),
+                        }
+                    }
+                };
                 state.elevated_pid = None;
                 state.elevated_handle = None;
-                Err(e)
+                Err(e.into())
             }
         }
     }
 
     #[cfg(not(target_os = "windows"))]
     {
-        let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+        let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
         state.state = netok_bridge::VpnConnectionState::Error {
             message: "VPN is only supported on Windows currently".to_string(),
         };
-        Err("VPN is only supported on Windows currently".to_string())
+        Err(ErrorInfo::new(
+            ErrorKind::UnsupportedPlatform,
+            "VPN is only supported on Windows currently",
+        ))
     }
 }
 
+/// DNS leak test; the ISP reference defaults to the public IP recorded before
+/// the VPN connected.
+#[tauri::command]
+async fn run_dns_leak_test(
+    options: Option<netok_bridge::LeakTestOptions>,
+    operation_id: Option<String>,
+    deadline_ms: Option<u64>,
+    vpn_state: tauri::State<'_, Arc<Mutex<VpnProcessState>>>,
+    demo: tauri::State<'_, DemoState>,
+    operations: tauri::State<'_, netok_bridge::OperationRegistry>,
+) -> Result<netok_bridge::Partial<netok_bridge::DnsLeakReport>, ErrorInfo> {
+    let mut options = options.unwrap_or_default();
+    if options.isp_ip.is_none() {
+        let state = vpn_state.lock().map_err(ErrorInfo::internal)?;
+        if let netok_bridge::VpnConnectionState::Connected { original_ip, .. } = &state.state {
+            options.isp_ip = original_ip.clone();
+        }
+    }
+    let operation = operations.register(operation_id);
+    Ok(netok_bridge::run_dns_leak_test_cancellable(
+        demo.backend(),
+        options,
+        operation.token(),
+        deadline_ms.map(Duration::from_millis),
+    )
+    .await)
+}
+
 #[tauri::command]
 async fn disconnect_vpn(
     vpn_state: tauri::State<'_, Arc<Mutex<VpnProcessState>>>,
-) -> Result<(), String> {
+) -> Result<(), ErrorInfo> {
     let (pid, handle, config_path) = {
-        let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+        let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
         state.state = netok_bridge::VpnConnectionState::Disconnecting;
         (
             state.elevated_pid,
@@ -686,17 +1224,17 @@ async fn disconnect_vpn(
     #[cfg(target_os = "windows")]
     if let Some(h) = handle {
         let the_pid = pid.unwrap_or(0);
-        eprintln!("[VPN] Terminating sing-box PID: {}", the_pid);
+        tracing::info!(pid = the_pid, "terminating sing-box");
 
         // Step 1: Try TerminateProcess with the stored handle (fast, no UAC)
         let terminate_ok = tokio::task::spawn_blocking(move || win_elevation::terminate_process(h))
             .await
-            .map_err(|e| format!("Task error: {}", e))?;
+            .map_err(ErrorInfo::internal)?;
 
         let mut process_dead = terminate_ok.is_ok();
 
         if let Err(ref e) = terminate_ok {
-            eprintln!("[VPN] TerminateProcess failed: {}, trying fallbacks...", e);
+            tracing::warn!(error = %e, "TerminateProcess failed, trying fallbacks");
         }
 
         // Step 2: If TerminateProcess failed, try non-elevated taskkill
@@ -715,7 +1253,7 @@ async fn disconnect_vpn(
 
             if let Some(output) = taskkill_result {
                 if output.status.success() {
-                    eprintln!("[VPN] Non-elevated taskkill succeeded");
+                    tracing::info!("non-elevated taskkill succeeded");
                     // Wait a moment for process to fully exit
                     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                     process_dead = true;
@@ -735,22 +1273,22 @@ async fn disconnect_vpn(
 
         // Step 4: If still alive, try elevated taskkill (shows UAC prompt)
         if !process_dead && the_pid > 0 {
-            eprintln!("[VPN] Trying elevated taskkill for PID: {}", the_pid);
+            tracing::info!(pid = the_pid, "trying elevated taskkill");
             let pid_for_elevated = the_pid;
             let elevated_result = tokio::task::spawn_blocking(move || {
                 win_elevation::kill_process_elevated(pid_for_elevated)
             })
             .await
-            .map_err(|e| format!("Task error: {}", e))?;
+            .map_err(ErrorInfo::internal)?;
 
             match elevated_result {
                 Ok(()) => {
-                    eprintln!("[VPN] Elevated taskkill succeeded");
+                    tracing::info!("elevated taskkill succeeded");
                     tokio::time::sleep(std::time::Duration::from_secs(1)).await;
                     process_dead = true;
                 }
                 Err(e) => {
-                    eprintln!("[VPN] Elevated taskkill failed: {}", e);
+                    tracing::warn!(error = %e, "elevated taskkill failed");
                 }
             }
         }
@@ -773,13 +1311,16 @@ async fn disconnect_vpn(
             // However, we no longer have the raw value since it was moved into
             // terminate_process. We still have the PID for future kill attempts.
             {
-                let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+                let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
                 state.state = netok_bridge::VpnConnectionState::Error {
                     message: "Failed to stop VPN process — access denied".to_string(),
                 };
                 // PID is kept so monitor can still track the process
             }
-            return Err("Failed to terminate VPN process after all attempts".to_string());
+            return Err(ErrorInfo::new(
+                ErrorKind::PermissionDenied,
+                "Failed to terminate VPN process after all attempts",
+            ));
         }
     }
 
@@ -790,22 +1331,22 @@ async fn disconnect_vpn(
 
     // Update state — process is confirmed dead
     {
-        let mut state = vpn_state.lock().map_err(|e| e.to_string())?;
+        let mut state = vpn_state.lock().map_err(ErrorInfo::internal)?;
         state.state = netok_bridge::VpnConnectionState::Disconnected;
         state.elevated_pid = None;
         state.elevated_handle = None;
         state.config_path = None;
     }
 
-    eprintln!("[VPN] Disconnected");
+    tracing::info!("VPN disconnected");
     Ok(())
 }
 
 #[tauri::command]
 fn get_vpn_status(
     vpn_state: tauri::State<'_, Arc<Mutex<VpnProcessState>>>,
-) -> Result<netok_bridge::VpnStatus, String> {
-    let state = vpn_state.lock().map_err(|e| e.to_string())?;
+) -> Result<netok_bridge::VpnStatus, ErrorInfo> {
+    let state = vpn_state.lock().map_err(ErrorInfo::internal)?;
     Ok(netok_bridge::VpnStatus {
         state: state.state.clone(),
     })
@@ -837,7 +1378,7 @@ async fn monitor_vpn_process(pid: u32, state: Arc<Mutex<VpnProcessState>>, app:
                         break;
                     }
                     _ => {
-                        eprintln!("[VPN] Process {} exited unexpectedly", pid);
+                        tracing::warn!(pid, "VPN process exited unexpectedly");
                         s.state = netok_bridge::VpnConnectionState::Error {
                             message: "VPN process stopped unexpectedly".to_string(),
                         };
@@ -867,8 +1408,200 @@ async fn monitor_vpn_process(pid: u32, state: Arc<Mutex<VpnProcessState>>, app:
     }
 }
 
+// ==================== Connectivity Monitor ====================
+
+#[derive(Default)]
+struct MonitorState {
+    handle: Option<netok_bridge::MonitorHandle>,
+}
+
+#[tauri::command]
+fn start_monitor(
+    app: tauri::AppHandle,
+    monitor_state: tauri::State<'_, Arc<Mutex<MonitorState>>>,
+    interval_secs: Option<u64>,
+) -> Result<(), ErrorInfo> {
+    let mut state = monitor_state.lock().map_err(ErrorInfo::internal)?;
+    if state.handle.is_some() {
+        return Ok(());
+    }
+
+    let mut config = netok_bridge::MonitorConfig::default();
+    if let Some(secs) = interval_secs {
+        config.interval_secs = secs;
+    }
+
+    let handle = netok_bridge::start_connectivity_monitor(config, move |event| {
+        let _ = app.emit("monitor-event", event);
+        match event {
+            netok_bridge::MonitorEvent::OutageStarted { outage } => {
+                update_tray_status(&app, Some(outage.failing_layer))
+            }
+            netok_bridge::MonitorEvent::OutageEnded { .. } => update_tray_status(&app, None),
+            netok_bridge::MonitorEvent::Sample { .. } => {}
+        }
+    });
+    state.handle = Some(handle);
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_monitor(
+    app: tauri::AppHandle,
+    monitor_state: tauri::State<'_, Arc<Mutex<MonitorState>>>,
+) -> Result<(), ErrorInfo> {
+    let handle = monitor_state
+        .lock()
+        .map_err(ErrorInfo::internal)?
+        .handle
+        .take();
+
+    // Stopping waits for the current probe round — keep it off the main thread
+    if let Some(handle) = handle {
+        tokio::task::spawn_blocking(move || handle.stop())
+            .await
+            .map_err(ErrorInfo::internal)?;
+    }
+    update_tray_status(&app, None);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_monitor_status(
+    monitor_state: tauri::State<'_, Arc<Mutex<MonitorState>>>,
+) -> Result<netok_bridge::MonitorStatus, ErrorInfo> {
+    let state = monitor_state.lock().map_err(ErrorInfo::internal)?;
+    Ok(netok_bridge::MonitorStatus {
+        running: state.handle.is_some(),
+        failing_layer: state.handle.as_ref().and_then(|h| h.current_state()),
+    })
+}
+
+#[tauri::command]
+fn get_outage_timeline(
+    monitor_state: tauri::State<'_, Arc<Mutex<MonitorState>>>,
+) -> Result<Vec<netok_bridge::Outage>, ErrorInfo> {
+    let state = monitor_state.lock().map_err(ErrorInfo::internal)?;
+    Ok(state
+        .handle
+        .as_ref()
+        .map(|h| h.outages())
+        .unwrap_or_default())
+}
+
 // ==================== System Tray ====================
 
+/// Language of the tray menu and tooltip, set by `update_tray_language`.
+struct TrayLanguage(Mutex<String>);
+
+impl Default for TrayLanguage {
+    fn default() -> Self {
+        // Matches the menu built in `create_tray`
+        Self(Mutex::new("ru".to_string()))
+    }
+}
+
+/// Reflect live connectivity in the tray tooltip.
+fn update_tray_status(
+    app: &tauri::AppHandle,
+    failing_layer: Option<netok_bridge::ConnectivityLayer>,
+) {
+    let Some(tray) = app.tray_by_id("main") else {
+        return;
+    };
+    let lang = app
+        .try_state::<TrayLanguage>()
+        .and_then(|state| state.0.lock().ok().map(|lang| lang.clone()))
+        .unwrap_or_default();
+    let _ = tray.set_tooltip(Some(tray_tooltip(&lang, failing_layer)));
+}
+
+fn tray_tooltip(
+    lang: &str,
+    failing_layer: Option<netok_bridge::ConnectivityLayer>,
+) -> &'static str {
+    let Some(layer) = failing_layer else {
+        return "Netok";
+    };
+    let (gateway, dns, http) = match lang {
+        "ru" => (
+            "Netok: роутер недоступен",
+            "Netok: DNS не отвечает",
+            "Netok: нет доступа в интернет",
+        ),
+        "de" => (
+            "Netok: Router nicht erreichbar",
+            "Netok: DNS antwortet nicht",
+            "Netok: kein Internetzugang",
+        ),
+        "es" => (
+            "Netok: router inaccesible",
+            "Netok: el DNS no responde",
+            "Netok: sin acceso a internet",
+        ),
+        "fr" => (
+            "Netok : routeur injoignable",
+            "Netok : le DNS ne répond pas",
+            "Netok : pas d'accès à Internet",
+        ),
+        "it" => (
+            "Netok: router non raggiungibile",
+            "Netok: il DNS non risponde",
+            "Netok: nessun accesso a Internet",
+        ),
+        "pt" => (
+            "Netok: roteador inacessível",
+            "Netok: o DNS não responde",
+            "Netok: sem acesso à internet",
+        ),
+        "tr" => (
+            "Netok: yönlendiriciye ulaşılamıyor",
+            "Netok: DNS yanıt vermiyor",
+            "Netok: internet erişimi yok",
+        ),
+        "fa" => (
+            "Netok: روتر در دسترس نیست",
+            "Netok: DNS پاسخ نمی‌دهد",
+            "Netok: دسترسی به اینترنت وجود ندارد",
+        ),
+        "zh" => (
+            "Netok：无法连接路由器",
+            "Netok：DNS 无响应",
+            "Netok：无法访问互联网",
+        ),
+        "ja" => (
+            "Netok: ルーターに接続できません",
+            "Netok: DNS が応答しません",
+            "Netok: インターネットに接続できません",
+        ),
+        "ko" => (
+            "Netok: 라우터에 연결할 수 없음",
+            "Netok: DNS 응답 없음",
+            "Netok: 인터넷에 연결되지 않음",
+        ),
+        "uk" => (
+            "Netok: роутер недоступний",
+            "Netok: DNS не відповідає",
+            "Netok: немає доступу до інтернету",
+        ),
+        "pl" => (
+            "Netok: router nieosiągalny",
+            "Netok: DNS nie odpowiada",
+            "Netok: brak dostępu do internetu",
+        ),
+        _ => (
+            "Netok: router unreachable",
+            "Netok: DNS not responding",
+            "Netok: no internet access",
+        ),
+    };
+    match layer {
+        netok_bridge::ConnectivityLayer::Gateway => gateway,
+        netok_bridge::ConnectivityLayer::Dns => dns,
+        netok_bridge::ConnectivityLayer::Http => http,
+    }
+}
+
 fn build_tray_menu(
     app: &tauri::AppHandle,
     lang: &str,
@@ -934,30 +1667,117 @@ fn create_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 #[tauri::command]
-fn update_tray_language(app: tauri::AppHandle, lang: String) -> Result<(), String> {
-    let tray = app.tray_by_id("main").ok_or("Tray not found")?;
-    let menu = build_tray_menu(&app, &lang).map_err(|e| e.to_string())?;
-    tray.set_menu(Some(menu)).map_err(|e| e.to_string())?;
+fn update_tray_language(
+    app: tauri::AppHandle,
+    lang: String,
+    tray_language: tauri::State<'_, TrayLanguage>,
+    monitor_state: tauri::State<'_, Arc<Mutex<MonitorState>>>,
+) -> Result<(), ErrorInfo> {
+    let tray = app
+        .tray_by_id("main")
+        .ok_or_else(|| ErrorInfo::new(ErrorKind::NotFound, "Tray not found"))?;
+    let menu = build_tray_menu(&app, &lang).map_err(ErrorInfo::internal)?;
+    tray.set_menu(Some(menu)).map_err(ErrorInfo::internal)?;
+    *tray_language.0.lock().map_err(ErrorInfo::internal)? = lang;
+
+    // Re-translate the tooltip of an ongoing outage
+    let failing_layer = monitor_state
+        .lock()
+        .map_err(ErrorInfo::internal)?
+        .handle
+        .as_ref()
+        .and_then(|h| h.current_state());
+    update_tray_status(&app, failing_layer);
     Ok(())
 }
 
 // ==================== Autostart ====================
 
 #[tauri::command]
-fn get_autostart_enabled(app: tauri::AppHandle) -> Result<bool, String> {
+fn get_autostart_enabled(app: tauri::AppHandle) -> Result<bool, ErrorInfo> {
     use tauri_plugin_autostart::ManagerExt;
-    app.autolaunch().is_enabled().map_err(|e| e.to_string())
+    app.autolaunch().is_enabled().map_err(ErrorInfo::internal)
 }
 
 #[tauri::command]
-fn set_autostart_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+fn set_autostart_enabled(app: tauri::AppHandle, enabled: bool) -> Result<(), ErrorInfo> {
     use tauri_plugin_autostart::ManagerExt;
     let manager = app.autolaunch();
     if enabled {
-        manager.enable().map_err(|e| e.to_string())
+        manager.enable().map_err(ErrorInfo::internal)
     } else {
-        manager.disable().map_err(|e| e.to_string())
+        manager.disable().map_err(ErrorInfo::internal)
+    }
+}
+
+// ==================== Logging ====================
+
+/// Number of daily log files kept in the app log directory.
+const LOG_FILES_KEPT: usize = 7;
+
+/// Filter used when `RUST_LOG` is not set.
+const DEFAULT_LOG_FILTER: &str = "info,netok_core=debug,netok_bridge=debug,netok_desktop_lib=debug";
+
+/// Directory holding the rotating log files, `None` if file logging is off.
+#[derive(Default)]
+struct LogState(Option<PathBuf>);
+
+/// Install the global tracing subscriber.
+///
+/// Events go to stderr (visible in dev builds) and to `netok.<date>.log` in the
+/// app log directory, rotated daily. The GUI build on Windows has no console,
+/// so the file is what users attach to bug reports.
+fn init_logging(app: &tauri::AppHandle) -> LogState {
+    use tracing_appender::rolling::{RollingFileAppender, Rotation};
+    use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+
+    let file = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| {
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .filename_prefix("netok")
+                .filename_suffix("log")
+                .max_log_files(LOG_FILES_KEPT)
+                .build(&dir)
+                .map(|appender| (dir, appender))
+                .map_err(|e| e.to_string())
+        });
+    let (dir, appender, file_error) = match file {
+        Ok((dir, appender)) => (Some(dir), Some(appender), None),
+        Err(e) => (None, None, Some(e)),
+    };
+
+    let _ = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(appender.map(|a| fmt::layer().with_ansi(false).with_writer(a)))
+        .try_init();
+
+    tracing::info!(
+        version = env!("CARGO_PKG_VERSION"),
+        os = std::env::consts::OS,
+        "netok started"
+    );
+    if let Some(e) = file_error {
+        tracing::warn!(error = %e, "log file disabled");
     }
+    LogState(dir)
+}
+
+/// Directory of the log files, for "attach logs" in bug reports.
+#[tauri::command]
+fn get_log_dir(logs: tauri::State<'_, LogState>) -> Result<String, ErrorInfo> {
+    logs.0
+        .as_ref()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .ok_or_else(|| ErrorInfo::new(ErrorKind::NotFound, "File logging is disabled"))
 }
 
 // ==================== App Entry ====================
@@ -976,7 +1796,7 @@ fn kill_orphaned_singbox() {
                 .output();
             if let Ok(out) = output {
                 if out.status.success() {
-                    eprintln!("[VPN] Killed orphaned sing-box process ({})", name);
+                    tracing::info!(process = name, "killed orphaned sing-box process");
                 }
             }
         }
@@ -988,10 +1808,7 @@ fn cleanup_vpn(vpn_state: &Mutex<VpnProcessState>) {
     if let Ok(mut state) = vpn_state.lock() {
         #[cfg(target_os = "windows")]
         if let Some(h) = state.elevated_handle.take() {
-            eprintln!(
-                "[VPN] Cleaning up: terminating PID {:?}",
-                state.elevated_pid
-            );
+            tracing::info!(pid = ?state.elevated_pid, "cleaning up: terminating sing-box");
             let _ = win_elevation::terminate_process(h);
         }
 
@@ -1013,6 +1830,10 @@ pub fn run() {
             None,
         ))
         .manage(Arc::new(Mutex::new(VpnProcessState::default())))
+        .manage(Arc::new(Mutex::new(MonitorState::default())))
+        .manage(TrayLanguage::default())
+        .manage(DemoState::default())
+        .manage(netok_bridge::OperationRegistry::default())
         .invoke_handler(tauri::generate_handler![
             get_settings,
             set_settings,
@@ -1022,6 +1843,15 @@ pub fn run() {
             get_dns_servers,
             test_dns_server,
             ping_dns_server,
+            check_dns_transports,
+            get_dns_catalog,
+            list_dns_presets,
+            save_dns_preset,
+            delete_dns_preset,
+            run_dns_benchmark,
+            check_dnssec,
+            verify_dns_filtering,
+            identify_resolver,
             run_all,
             check_computer,
             check_network,
@@ -1032,15 +1862,36 @@ pub fn run() {
             flush_dns,
             scan_network_devices,
             check_wifi_security,
+            cancel_operation,
             validate_vpn_key,
             connect_vpn,
             disconnect_vpn,
+            run_dns_leak_test,
             get_vpn_status,
             get_autostart_enabled,
-            set_autostart_enabled
+            set_autostart_enabled,
+            start_monitor,
+            stop_monitor,
+            get_monitor_status,
+            get_outage_timeline,
+            record_diagnostics,
+            query_history,
+            clear_history,
+            diff_snapshots,
+            diff_history_records,
+            export_report,
+            record_capture,
+            replay_capture,
+            get_log_dir,
+            set_demo_scenario,
+            get_demo_scenario
         ])
         .setup(|app| {
+            app.manage(init_logging(app.handle()));
             kill_orphaned_singbox();
+            app.manage(SettingsState::open(app.handle()));
+            app.manage(HistoryState::open(app.handle()));
+            app.manage(PresetState::open(app.handle()));
             create_tray(app)?;
             Ok(())
         })
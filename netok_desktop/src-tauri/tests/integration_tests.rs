@@ -193,11 +193,7 @@ async fn test_set_dns_auto_provider() {
 
 #[tokio::test]
 async fn test_set_dns_cloudflare_standard() {
-    use netok_bridge::CloudflareVariant;
-
-    let provider = DnsProviderType::Cloudflare {
-        variant: CloudflareVariant::Standard,
-    };
+    let provider = DnsProviderType::catalog("Cloudflare", Some("Standard")).unwrap();
 
     let result = set_dns_provider(provider).await;
 
@@ -208,7 +204,7 @@ async fn test_set_dns_cloudflare_standard() {
 
 #[tokio::test]
 async fn test_set_dns_google() {
-    let provider = DnsProviderType::Google;
+    let provider = DnsProviderType::catalog("Google", None).unwrap();
 
     let result = set_dns_provider(provider).await;
 
@@ -239,40 +235,18 @@ async fn test_set_dns_custom_provider() {
 
 #[test]
 fn test_dns_provider_all_variants_serialize() {
-    use netok_bridge::{
-        AdGuardVariant, CloudflareVariant, Dns4EuVariant, OpenDnsVariant, Quad9Variant,
-    };
-
     let variants = vec![
         DnsProviderType::Auto,
-        DnsProviderType::Google,
-        DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Standard,
-        },
-        DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Malware,
-        },
-        DnsProviderType::Cloudflare {
-            variant: CloudflareVariant::Family,
-        },
-        DnsProviderType::AdGuard {
-            variant: AdGuardVariant::Standard,
-        },
-        DnsProviderType::AdGuard {
-            variant: AdGuardVariant::NonFiltering,
-        },
-        DnsProviderType::AdGuard {
-            variant: AdGuardVariant::Family,
-        },
-        DnsProviderType::Dns4Eu {
-            variant: Dns4EuVariant::Protective,
-        },
-        DnsProviderType::Quad9 {
-            variant: Quad9Variant::Recommended,
-        },
-        DnsProviderType::OpenDns {
-            variant: OpenDnsVariant::FamilyShield,
-        },
+        DnsProviderType::catalog("Google", None).unwrap(),
+        DnsProviderType::catalog("Cloudflare", Some("Standard")).unwrap(),
+        DnsProviderType::catalog("Cloudflare", Some("Malware")).unwrap(),
+        DnsProviderType::catalog("Cloudflare", Some("Family")).unwrap(),
+        DnsProviderType::catalog("AdGuard", Some("Standard")).unwrap(),
+        DnsProviderType::catalog("AdGuard", Some("NonFiltering")).unwrap(),
+        DnsProviderType::catalog("AdGuard", Some("Family")).unwrap(),
+        DnsProviderType::catalog("Dns4Eu", Some("Protective")).unwrap(),
+        DnsProviderType::catalog("Quad9", Some("Recommended")).unwrap(),
+        DnsProviderType::catalog("OpenDns", Some("FamilyShield")).unwrap(),
         DnsProviderType::Custom {
             primary: "1.2.3.4".to_string(),
             secondary: "5.6.7.8".to_string(),
@@ -8,6 +8,7 @@ export interface NodeResult {
   status: NodeStatus;
   latency_ms: number | null;
   details: string | null;
+  hint_key?: string | null;  // i18n key explaining the status
 }
 
 export interface Speed {
@@ -35,6 +36,22 @@ export interface NetworkInfo {
   link_speed_mbps: number | null;  // negotiated PHY link speed in Mbps
   wifi_standard: string | null;  // "Wi-Fi 5 (802.11ac)", etc.
   is_legacy_wifi: boolean;  // true if Wi-Fi 4 or older
+  dhcp?: DhcpInfo | null;  // DHCP lease details, absent on older backends
+  dns_servers?: string[];  // DNS servers in use by the active adapter
+}
+
+export interface DhcpInfo {
+  enabled: boolean;
+  link_local_only: boolean;  // only a 169.254.x.x (APIPA) address
+  server: string | null;
+  lease_obtained_utc: string | null;
+  lease_expires_utc: string | null;
+  lease_expired: boolean;
+  lease_time_secs: number | null;
+  renewal_time_secs: number | null;
+  rebinding_time_secs: number | null;
+  dns_servers: string[];
+  ntp_servers: string[];
 }
 
 export interface RouterInfo {
@@ -54,6 +71,7 @@ export interface InternetInfo {
   latency_ms: number | null;
   speed_down_mbps: number | null;
   speed_up_mbps: number | null;
+  dnssec?: DnssecStatus;  // absent in snapshots saved before the check existed
 }
 
 export interface DiagnosticsSnapshot {
@@ -66,8 +84,36 @@ export interface DiagnosticsSnapshot {
   internet: InternetInfo;
 }
 
-export async function runDiagnostics(): Promise<DiagnosticsSnapshot> {
-  return await invoke<DiagnosticsSnapshot>('run_diagnostics');
+// Cancellable operations
+export type Completion = 'complete' | 'cancelled' | 'timed_out';
+
+// Result of an operation that may have been stopped early
+export interface PartialResult<T> {
+  value: T;  // everything gathered before it stopped
+  completion: Completion;
+}
+
+export interface OperationOptions {
+  operationId?: string;  // pass to cancelOperation() to stop it
+  deadlineMs?: number;   // overall time limit
+}
+
+function operationArgs(options?: OperationOptions) {
+  return {
+    operationId: options?.operationId ?? null,
+    deadlineMs: options?.deadlineMs ?? null,
+  };
+}
+
+// Returns false if the operation already finished
+export async function cancelOperation(id: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_operation', { id });
+}
+
+export async function runDiagnostics(
+  options?: OperationOptions,
+): Promise<PartialResult<DiagnosticsSnapshot>> {
+  return await invoke<PartialResult<DiagnosticsSnapshot>>('run_diagnostics', operationArgs(options));
 }
 
 export async function getSettings(): Promise<string> {
@@ -93,7 +139,15 @@ export type DnsProvider =
   | { type: 'Dns4Eu'; variant: Dns4EuVariant }
   | { type: 'Quad9'; variant: Quad9Variant }
   | { type: 'OpenDns'; variant: OpenDnsVariant }
-  | { type: 'Custom'; primary: string; secondary: string; primaryIpv6: string | null; secondaryIpv6: string | null };
+  | {
+      type: 'Custom';
+      primary: string;
+      secondary: string;
+      primaryIpv6: string | null;
+      secondaryIpv6: string | null;
+      dohTemplate?: string | null;  // DoH URL template (RFC 8484)
+      dotHostname?: string | null;  // DoT hostname (RFC 7858)
+    };
 
 export async function setDns(provider: DnsProvider): Promise<void> {
   return await invoke('set_dns', { provider });
@@ -107,6 +161,7 @@ export async function getDnsServers(): Promise<string[]> {
   return await invoke<string[]>('get_dns_servers');
 }
 
+// serverIp may also be a DoH URL (https://...) or a DoT hostname (tls://...)
 export async function testDnsServer(serverIp: string): Promise<boolean> {
   return await invoke<boolean>('test_dns_server', { serverIp });
 }
@@ -115,6 +170,192 @@ export async function pingDnsServer(serverIp: string): Promise<number | null> {
   return await invoke<number | null>('ping_dns_server', { serverIp });
 }
 
+export type DnsTransport = 'udp' | 'doh' | 'dot';
+
+export interface DnsTransportStatus {
+  transport: DnsTransport;
+  endpoint: string;  // accepted by pingDnsServer
+  works: boolean;
+  latency_ms: number | null;
+}
+
+// Which transports of a provider work on this network
+export async function checkDnsTransports(provider: DnsProvider): Promise<DnsTransportStatus[]> {
+  return await invoke<DnsTransportStatus[]>('check_dns_transports', { provider });
+}
+
+// DNS provider catalog: the built-in providers, as data
+export type ProviderCategory = 'standard' | 'security' | 'family' | 'adblock';
+export type DnsFilter = 'malware' | 'adult' | 'ads';
+
+export interface CatalogProvider {
+  id: string;                  // serialized core provider, e.g. "Quad9Recommended"
+  family: string;              // DnsProvider `type`, e.g. "Quad9"
+  variant: string | null;      // DnsProvider `variant`; null for single-entry families
+  name: string;
+  category: ProviderCategory;
+  filtering: DnsFilter[];      // empty = unfiltered
+  asns: number[];              // networks its resolvers query from
+  ipv4: string[];              // primary first
+  ipv6: string[];
+  doh_template: string | null;
+  dot_hostname: string | null;
+}
+
+export interface DnsCatalog {
+  version: number;
+  providers: CatalogProvider[];
+}
+
+export async function getDnsCatalog(): Promise<DnsCatalog> {
+  return await invoke<DnsCatalog>('get_dns_catalog');
+}
+
+// User presets: providers saved under a name
+export interface DnsPreset {
+  name: string;
+  provider: DnsProvider;
+}
+
+export async function listDnsPresets(): Promise<DnsPreset[]> {
+  return await invoke<DnsPreset[]>('list_dns_presets');
+}
+
+// Replaces a preset with the same name (case-insensitive)
+export async function saveDnsPreset(name: string, provider: DnsProvider): Promise<void> {
+  return await invoke('save_dns_preset', { name, provider });
+}
+
+// Returns false if there was no such preset
+export async function deleteDnsPreset(name: string): Promise<boolean> {
+  return await invoke<boolean>('delete_dns_preset', { name });
+}
+
+// DNS benchmark
+export interface BenchmarkOptions {
+  transport?: DnsTransport;     // default 'udp'; the current resolver is only benchmarked over UDP
+  uncached_queries?: number;    // random names, default 10
+  cached_queries?: number;      // popular names, default 10
+  timeout_ms?: number;          // per query, default 2000
+  require_dnssec?: boolean;     // only recommend a validating resolver, default false
+}
+
+export interface ResolverBenchmark {
+  provider: CoreDnsProvider;    // 'Auto' = the resolver currently in use
+  server: string;
+  queries: number;
+  errors: number;
+  error_rate: number;           // 0..1
+  median_ms: number | null;
+  p95_ms: number | null;
+  cached_median_ms: number | null;
+  uncached_median_ms: number | null;
+  reliable: boolean;
+  dnssec: DnssecStatus;
+}
+
+export interface DnsBenchmark {
+  transport: DnsTransport;
+  resolvers: ResolverBenchmark[];        // recommendable first, then reliable, then by median latency
+  recommended: CoreDnsProvider | null;   // 'Auto' = keep the current resolver; never a broken validator
+}
+
+// Benchmark the current resolver and every built-in provider
+export async function runDnsBenchmark(
+  benchmarkOptions?: BenchmarkOptions,
+  options?: OperationOptions,
+): Promise<PartialResult<DnsBenchmark>> {
+  return await invoke<PartialResult<DnsBenchmark>>('run_dns_benchmark', {
+    options: benchmarkOptions ?? null,
+    ...operationArgs(options),
+  });
+}
+
+// DNSSEC validation of the current resolver and every built-in provider
+export type DnssecStatus = 'validating' | 'non_validating' | 'broken' | 'unknown';
+
+export interface DnssecCheck {
+  provider: CoreDnsProvider;    // 'Auto' = the resolver currently in use
+  server: string;
+  status: DnssecStatus;
+}
+
+export interface DnssecReport {
+  resolvers: DnssecCheck[];     // current resolver first, when known
+}
+
+export async function checkDnssec(
+  timeoutSecs?: number,
+  options?: OperationOptions,
+): Promise<PartialResult<DnssecReport>> {
+  return await invoke<PartialResult<DnssecReport>>('check_dnssec', {
+    timeoutSecs: timeoutSecs ?? null,
+    ...operationArgs(options),
+  });
+}
+
+// Does a filtering provider block what it promises, for apps on this machine?
+export type FilterResult = 'blocked' | 'resolved' | 'unknown';
+
+export type FilteringStatus = 'working' | 'not_filtering' | 'bypassed' | 'unknown';
+
+export interface CategoryCheck {
+  category: DnsFilter;
+  expected: boolean;            // the provider's catalog entry filters this category
+  provider: FilterResult;       // the provider, queried directly
+  system: FilterResult;         // the system resolver, what apps get
+}
+
+export interface FilteringReport {
+  provider: CoreDnsProvider;    // 'Auto' only when the current servers aren't a catalog provider
+  server: string | null;        // ip:port the provider was queried at
+  status: FilteringStatus;
+  categories: CategoryCheck[];
+  os_bypass: boolean;           // the provider blocks something the system resolver lets through
+  browser_bypass: boolean;      // browsers may switch to their own DoH and skip the filter
+}
+
+// Verify filtering of a provider (default: the one configured now)
+export async function verifyDnsFiltering(
+  provider?: DnsProvider,
+  options?: OperationOptions,
+): Promise<PartialResult<FilteringReport>> {
+  return await invoke<PartialResult<FilteringReport>>('verify_dns_filtering', {
+    provider: provider ?? null,
+    ...operationArgs(options),
+  });
+}
+
+// Which provider really answers DNS queries, even behind a router or Pi-hole
+export type IdentityConfidence = 'low' | 'medium' | 'high';
+
+export type IdentityMethod = 'server_address' | 'debug_name' | 'chaos' | 'egress_resolver';
+
+export interface IdentityEvidence {
+  method: IdentityMethod;
+  detail: string;               // address, TXT string or network seen
+  family: string | null;        // provider family it points to; null = names none (e.g. forwarder software)
+  confidence: IdentityConfidence;
+}
+
+export interface ResolverIdentity {
+  servers: string[];            // configured, primary first
+  forwarder: boolean;           // configured server is local and forwards upstream
+  egress_ips: string[];         // resolvers seen asking authoritative servers
+  family: string | null;        // e.g. "Cloudflare"
+  provider: CoreDnsProvider | null;  // only when the variant is known too
+  confidence: IdentityConfidence | null;
+  evidence: IdentityEvidence[];
+}
+
+export async function identifyResolver(
+  options?: OperationOptions,
+): Promise<PartialResult<ResolverIdentity>> {
+  return await invoke<PartialResult<ResolverIdentity>>('identify_resolver', {
+    ...operationArgs(options),
+  });
+}
+
 // IP geolocation lookup
 export interface IpLocationInfo {
   ip: string | null;
@@ -136,7 +377,8 @@ export type DiagnosticScenario =
   | 'no_internet'
   | 'dns_failure'
   | 'http_blocked'
-  | 'weak_signal';
+  | 'weak_signal'
+  | 'dhcp_failure';
 
 export type DiagnosticSeverity = 'success' | 'warning' | 'error';
 
@@ -146,6 +388,19 @@ export interface DiagnosticResult {
   details: string | null;
 }
 
+/**
+ * Run diagnostics, scans and security checks against a simulated network in
+ * the given scenario (`null` = real network). Nothing is recorded in history
+ * while a scenario is set.
+ */
+export async function setDemoScenario(scenario: DiagnosticScenario | null): Promise<void> {
+  return await invoke('set_demo_scenario', { scenario });
+}
+
+export async function getDemoScenario(): Promise<DiagnosticScenario | null> {
+  return await invoke<DiagnosticScenario | null>('get_demo_scenario');
+}
+
 // Progressive diagnostics: individual node checks
 export interface SingleNodeResult {
   node: NodeResult;
@@ -184,6 +439,43 @@ export interface VpnStatus {
   state: VpnConnectionState;
 }
 
+export type ErrorKind =
+  | 'unsupported_platform'
+  | 'permission_denied'
+  | 'timeout'
+  | 'parse'
+  | 'invalid_input'
+  | 'not_found'
+  | 'network'
+  | 'command_failed'
+  | 'io'
+  | 'internal';
+
+export interface ErrorInfo {
+  kind: ErrorKind;
+  /** i18n key under `errors.*` */
+  key: string;
+  message: string;
+  /** Byte offset into the input for parse errors */
+  position: number | null;
+}
+
+/** Commands reject with an `ErrorInfo`. */
+export function isErrorInfo(error: unknown): error is ErrorInfo {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'key' in error;
+}
+
+/** i18n key for a rejected command; anything unexpected maps to `errors.internal`. */
+export function errorKey(error: unknown): string {
+  return isErrorInfo(error) ? error.key : 'errors.internal';
+}
+
+/** English message of a rejected command, for state that carries raw text. */
+export function errorMessage(error: unknown): string {
+  if (isErrorInfo(error)) return error.message;
+  return error instanceof Error ? error.message : String(error);
+}
+
 export interface VpnKeyValidation {
   valid: boolean;
   reachable: boolean;
@@ -191,10 +483,17 @@ export interface VpnKeyValidation {
   server: string;
   port: number;
   error: string | null;
+  error_info?: ErrorInfo | null;
 }
 
-export async function validateVpnKey(rawUri: string): Promise<VpnKeyValidation> {
-  return await invoke<VpnKeyValidation>('validate_vpn_key', { rawUri });
+export async function validateVpnKey(
+  rawUri: string,
+  options?: OperationOptions,
+): Promise<PartialResult<VpnKeyValidation>> {
+  return await invoke<PartialResult<VpnKeyValidation>>('validate_vpn_key', {
+    rawUri,
+    ...operationArgs(options),
+  });
 }
 
 // VPN commands
@@ -210,6 +509,47 @@ export async function getVpnStatus(): Promise<VpnStatus> {
   return await invoke<VpnStatus>('get_vpn_status');
 }
 
+// DNS leak test: which resolvers handle queries while the VPN is up
+export interface LeakTestOptions {
+  queries?: number;             // unique names to resolve, default 10
+  isp_ip?: string | null;       // public IP without the VPN; default: the one recorded on connect
+  zone?: string | null;         // zone to resolve under; default from settings (whoami.akamai.net)
+  server?: string | null;       // ip:port to query instead of the system resolver
+}
+
+export type ResolverOwner = 'isp' | 'vpn' | 'third_party' | 'unknown';
+export type LeakStatus = 'no_leak' | 'leak' | 'inconclusive';
+
+export interface LeakResolver {
+  ip: string;
+  asn: number | null;
+  org: string | null;
+  country: string | null;
+  queries: number;              // queries this resolver handled
+  owner: ResolverOwner;         // 'isp' = bypassed the VPN
+}
+
+export interface DnsLeakReport {
+  status: LeakStatus;
+  public_ip: string | null;     // through the VPN
+  vpn_asn: number | null;
+  isp_ip: string | null;
+  isp_asn: number | null;
+  queries: number;
+  answered: number;
+  resolvers: LeakResolver[];    // most used first
+}
+
+export async function runDnsLeakTest(
+  leakOptions?: LeakTestOptions,
+  options?: OperationOptions,
+): Promise<PartialResult<DnsLeakReport>> {
+  return await invoke<PartialResult<DnsLeakReport>>('run_dns_leak_test', {
+    options: leakOptions ?? null,
+    ...operationArgs(options),
+  });
+}
+
 // Flush DNS cache
 export async function flushDns(): Promise<void> {
   return await invoke('flush_dns');
@@ -229,8 +569,10 @@ export interface NetworkDevice {
   is_randomized: boolean;
 }
 
-export async function scanNetworkDevices(): Promise<NetworkDevice[]> {
-  return await invoke<NetworkDevice[]>('scan_network_devices');
+export async function scanNetworkDevices(
+  options?: OperationOptions,
+): Promise<PartialResult<NetworkDevice[]>> {
+  return await invoke<PartialResult<NetworkDevice[]>>('scan_network_devices', operationArgs(options));
 }
 
 // WiFi Security types
@@ -250,8 +592,10 @@ export interface WiFiSecurityReport {
   timestamp: number;
 }
 
-export async function checkWifiSecurity(): Promise<WiFiSecurityReport> {
-  return await invoke<WiFiSecurityReport>('check_wifi_security');
+export async function checkWifiSecurity(
+  options?: OperationOptions,
+): Promise<PartialResult<WiFiSecurityReport>> {
+  return await invoke<PartialResult<WiFiSecurityReport>>('check_wifi_security', operationArgs(options));
 }
 
 // Autostart
@@ -262,3 +606,179 @@ export async function getAutostartEnabled(): Promise<boolean> {
 export async function setAutostartEnabled(enabled: boolean): Promise<void> {
   return await invoke('set_autostart_enabled', { enabled });
 }
+
+// Connectivity monitor
+export type ConnectivityLayer = 'gateway' | 'dns' | 'http';
+
+export interface ProbeSample {
+  timestamp: number;  // unix seconds
+  gateway_ok: boolean;
+  dns_ok: boolean;
+  http_ok: boolean;
+  latency_ms: number;
+}
+
+export interface Outage {
+  started_at: number;  // unix seconds
+  ended_at: number | null;  // null while ongoing
+  failing_layer: ConnectivityLayer;
+}
+
+export type MonitorEvent =
+  | { type: 'sample'; sample: ProbeSample }
+  | { type: 'outage_started'; outage: Outage }
+  | { type: 'outage_ended'; outage: Outage };
+
+export interface MonitorStatus {
+  running: boolean;
+  failing_layer: ConnectivityLayer | null;
+}
+
+/** Start background probing; events arrive on the `monitor-event` channel. */
+export async function startMonitor(intervalSecs?: number): Promise<void> {
+  return await invoke('start_monitor', { intervalSecs: intervalSecs ?? null });
+}
+
+export async function stopMonitor(): Promise<void> {
+  return await invoke('stop_monitor');
+}
+
+export async function getMonitorStatus(): Promise<MonitorStatus> {
+  return await invoke<MonitorStatus>('get_monitor_status');
+}
+
+export async function getOutageTimeline(): Promise<Outage[]> {
+  return await invoke<Outage[]>('get_outage_timeline');
+}
+
+// Diagnostics history
+export type HistoryKind = 'diagnostics' | 'security' | 'device_scan';
+
+export interface NetworkKey {
+  ssid: string | null;
+  gateway_mac: string | null;
+}
+
+/** Core snapshot as stored in history (node statuses use backend names). */
+export interface HistorySnapshot {
+  at_utc: string;
+  nodes: { id: string; name_key: string; status: 'Ok' | 'Warn' | 'Fail' | 'Unknown'; latency_ms: number | null; hint_key: string | null }[];
+  summary_key: string;
+  computer: ComputerInfo;
+  network: NetworkInfo;
+  router: RouterInfo;
+  internet: InternetInfo;
+}
+
+export type HistoryRecord = { recorded_at: number; network: NetworkKey } & (
+  | { kind: 'diagnostics'; snapshot: HistorySnapshot }
+  | { kind: 'security'; report: WiFiSecurityReport }
+  | { kind: 'device_scan'; devices: NetworkDevice[] }
+);
+
+export interface HistoryQuery {
+  from?: number;  // unix seconds, inclusive
+  to?: number;
+  ssid?: string;
+  gateway_mac?: string;
+  kind?: HistoryKind;
+  node_status?: 'Ok' | 'Warn' | 'Fail' | 'Unknown';
+  limit?: number;  // newest N
+}
+
+export async function recordDiagnostics(snapshot: DiagnosticsSnapshot & { overall: NodeStatus }): Promise<void> {
+  return await invoke('record_diagnostics', { snapshot });
+}
+
+export async function queryHistory(query: HistoryQuery = {}): Promise<HistoryRecord[]> {
+  return await invoke<HistoryRecord[]>('query_history', { query });
+}
+
+export async function clearHistory(): Promise<void> {
+  return await invoke('clear_history');
+}
+
+// Snapshot diff (arguments are before, after)
+/** Core DnsProvider: "Auto", a catalog id like "Cloudflare", or a Custom tuple. */
+export type CoreDnsProvider =
+  | string
+  | { Custom: [string, string, string | null, string | null, string | null, string | null] };
+
+export type SnapshotChange =
+  | { type: 'node_status_changed'; node: string; from: string; to: string }
+  | { type: 'latency_regressed'; node: string; from_ms: number; to_ms: number }
+  | { type: 'connection_type_changed'; from: ConnectionType; to: ConnectionType }
+  | { type: 'ssid_changed'; from: string | null; to: string | null }
+  | { type: 'local_ip_changed'; from: string | null; to: string | null }
+  | { type: 'gateway_ip_changed'; from: string | null; to: string | null }
+  | { type: 'gateway_mac_changed'; from: string | null; to: string | null }
+  | { type: 'dns_servers_changed'; from: string[]; to: string[] }
+  | { type: 'dns_provider_changed'; from: CoreDnsProvider; to: CoreDnsProvider }
+  | { type: 'public_ip_changed'; from: string | null; to: string | null }
+  | { type: 'isp_changed'; from: string | null; to: string | null }
+  | { type: 'signal_dropped'; from_dbm: number; to_dbm: number }
+  | { type: 'encryption_downgraded'; from: string; to: string }
+  | { type: 'security_check_changed'; check: SecurityCheckType; from: SecurityStatus; to: SecurityStatus }
+  | { type: 'device_appeared'; device: NetworkDevice }
+  | { type: 'device_disappeared'; device: NetworkDevice };
+
+export async function diffSnapshots(before: DiagnosticsSnapshot, after: DiagnosticsSnapshot): Promise<SnapshotChange[]> {
+  return await invoke<SnapshotChange[]>('diff_snapshots', { before, after });
+}
+
+export async function diffHistoryRecords(before: HistoryRecord, after: HistoryRecord): Promise<SnapshotChange[]> {
+  return await invoke<SnapshotChange[]>('diff_history_records', { before, after });
+}
+
+// Report export
+export type ReportFormat = 'markdown' | 'html' | 'json';
+export type RedactionLevel = 'none' | 'share_with_support' | 'public';
+
+export interface ExportReportOptions {
+  /** Inferred from the file extension when omitted. */
+  format?: ReportFormat;
+  snapshot?: DiagnosticsSnapshot;
+  security?: WiFiSecurityReport;
+  devices?: NetworkDevice[];
+  /** Include this many most recent history records. */
+  historyLimit?: number;
+  /** Strip identifying data before writing. Defaults to 'none'. */
+  redaction?: RedactionLevel;
+  /** Language of Markdown/HTML labels, e.g. `i18n.language`. Defaults to English. */
+  language?: string;
+}
+
+export async function exportReport(path: string, options: ExportReportOptions = {}): Promise<void> {
+  return await invoke<void>('export_report', {
+    path,
+    format: options.format ?? null,
+    snapshot: options.snapshot ?? null,
+    security: options.security ?? null,
+    devices: options.devices ?? null,
+    historyLimit: options.historyLimit ?? null,
+    redaction: options.redaction ?? null,
+    language: options.language ?? null,
+  });
+}
+
+// Probe capture
+export interface CaptureRun {
+  snapshot: DiagnosticsSnapshot;
+  security: WiFiSecurityReport;
+}
+
+/** Run diagnostics and security checks, saving every raw probe response to `path`. */
+export async function recordCapture(path: string): Promise<CaptureRun> {
+  return await invoke<CaptureRun>('record_capture', { path });
+}
+
+/** Replay a capture file offline through diagnostics and security checks. */
+export async function replayCapture(path: string): Promise<CaptureRun> {
+  return await invoke<CaptureRun>('replay_capture', { path });
+}
+
+// Logs
+/** Directory with the rotating log files (one per day, last 7 kept). */
+export async function getLogDir(): Promise<string> {
+  return await invoke<string>('get_log_dir');
+}
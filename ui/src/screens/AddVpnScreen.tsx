@@ -5,7 +5,7 @@ import { Button } from '@/components/ui/button';
 
 import { CloseButton } from '../components/WindowControls';
 import { useVpnStore, type VpnConfig } from '../stores/vpnStore';
-import { lookupIpLocation, validateVpnKey } from '../api/tauri';
+import { errorKey, lookupIpLocation, validateVpnKey } from '../api/tauri';
 import { notifications } from '../utils/notifications';
 
 interface AddVpnScreenProps {
@@ -39,9 +39,9 @@ export function AddVpnScreen({ onBack, onAdded }: AddVpnScreenProps) {
     setPhase('validating');
     let validation;
     try {
-      validation = await validateVpnKey(trimmed);
+      validation = (await validateVpnKey(trimmed)).value;
     } catch (e) {
-      setError(String(e));
+      setError(t(errorKey(e)));
       setPhase('idle');
       return;
     }
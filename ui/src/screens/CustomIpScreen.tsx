@@ -2,7 +2,7 @@ import { useState, useEffect } from 'react';
 import { useTranslation } from 'react-i18next';
 import { toast } from 'sonner';
 import { ArrowLeft, Loader2, XThick } from '../components/icons/UIIcons';
-import { setDns, testDnsServer, pingDnsServer, type DnsProvider as ApiDnsProvider } from '../api/tauri';
+import { setDns, testDnsServer, pingDnsServer, isErrorInfo, type DnsProvider as ApiDnsProvider } from '../api/tauri';
 import { dnsStore } from '../stores/dnsStore';
 import { Button } from '@/components/ui/button';
 import { Input } from '@/components/ui/input';
@@ -193,8 +193,7 @@ export function CustomIpScreen({ onBack, onApplied }: CustomIpScreenProps) {
       setTimeout(() => onApplied(), 1500);
     } catch (err) {
       setIsApplying(false);
-      const msg = String(err);
-      if (msg.includes('elevation_denied')) {
+      if (isErrorInfo(err) && err.key === 'errors.elevation_denied') {
         toast.error(t('dns_providers.error_elevation_denied'));
       } else {
         toast.error(t('dns_providers.error_set_failed'));
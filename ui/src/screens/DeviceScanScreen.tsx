@@ -57,7 +57,7 @@ function getDeviceVisual(device: NetworkDevice) {
 
 export function DeviceScanScreen({ onBack }: DeviceScanScreenProps) {
   const { t } = useTranslation();
-  const { devices, lastUpdated, isScanning, scanStage, scanProgress, error, runScan } = useDeviceScanStore();
+  const { devices, lastUpdated, isScanning, scanStage, scanProgress, error, runScan, cancelScan } = useDeviceScanStore();
   const nodes = useDiagnosticsStore(s => s.nodes);
   const availability = getNetworkAvailability(nodes);
   const networkBlocked = availability === 'no_network';
@@ -77,6 +77,9 @@ export function DeviceScanScreen({ onBack }: DeviceScanScreenProps) {
     }
   }, []); // eslint-disable-line react-hooks/exhaustive-deps
 
+  // Stop pinging the subnet once the screen is closed
+  useEffect(() => cancelScan, [cancelScan]);
+
   const showLoading = isScanning && devices.length === 0;
 
   return (
@@ -126,7 +129,7 @@ export function DeviceScanScreen({ onBack }: DeviceScanScreenProps) {
         )}
 
         {error && (
-          <div className="text-sm text-destructive text-center py-8">{error}</div>
+          <div className="text-sm text-destructive text-center py-8">{t(error)}</div>
         )}
 
         {!isScanning && !error && devices.length === 0 && lastUpdated && (
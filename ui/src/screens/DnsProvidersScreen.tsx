@@ -2,7 +2,7 @@ import { useState, useEffect, useCallback } from 'react';
 import { useTranslation } from 'react-i18next';
 import { toast } from 'sonner';
 import { ArrowLeft, Loader2, BrushCleaning } from '../components/icons/UIIcons';
-import { setDns, pingDnsServer, flushDns, type DnsProvider as ApiDnsProvider } from '../api/tauri';
+import { setDns, pingDnsServer, flushDns, errorKey, errorMessage, isErrorInfo, type DnsProvider as ApiDnsProvider } from '../api/tauri';
 import { useDnsStore } from '../stores/useDnsStore';
 import { dnsStore } from '../stores/dnsStore';
 import { Button } from '@/components/ui/button';
@@ -137,10 +137,10 @@ export function DnsProvidersScreen({ onBack, onCustomIp }: DnsProvidersScreenPro
       dnsStore.setProvider(provider);
       toast.success(t('dns_providers.applied'));
     } catch (err) {
-      const msg = String(err);
-      if (msg.includes('elevation_denied')) {
+      if (isErrorInfo(err) && err.key === 'errors.elevation_denied') {
         toast.error(t('dns_providers.error_elevation_denied'));
       } else {
+        const msg = errorMessage(err);
         console.error('[DNS] set_dns error:', msg);
         toast.error(t('dns_providers.error_set_failed'), {
           description: msg.length > 100 ? msg.slice(0, 100) + '…' : msg,
@@ -276,7 +276,7 @@ export function DnsProvidersScreen({ onBack, onCustomIp }: DnsProvidersScreenPro
               await flushDns();
               toast.success(t('dns_providers.cache_cleared'));
             } catch (e) {
-              toast.error(String(e));
+              toast.error(t(errorKey(e)));
             }
           }}
         >
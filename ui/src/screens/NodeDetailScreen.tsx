@@ -251,6 +251,30 @@ export function NodeDetailScreen({ nodeId, result, onBack, onNavigateToDnsProvid
         }),
       });
     }
+
+    // DHCP server and lease
+    if (net.dhcp) {
+      const dhcp = net.dhcp;
+      const problemKey = dhcp.link_local_only
+        ? 'node_detail.dhcp_no_lease'
+        : dhcp.lease_expired
+          ? 'node_detail.dhcp_lease_expired'
+          : null;
+      rows.push({
+        label: t('node_detail.dhcp'),
+        value: dhcp.enabled ? (dhcp.server ?? '—') : t('node_detail.dhcp_static'),
+        ...(problemKey && {
+          subtitle: t(problemKey),
+          subtitleClass: 'text-warning bg-warning/10',
+        }),
+      });
+      if (dhcp.lease_expires_utc) {
+        rows.push({
+          label: t('node_detail.dhcp_lease_expires'),
+          value: new Date(dhcp.lease_expires_utc).toLocaleString(),
+        });
+      }
+    }
   }
 
   if (nodeId === 'dns' && result.router) {
@@ -307,6 +331,14 @@ export function NodeDetailScreen({ nodeId, result, onBack, onNavigateToDnsProvid
     } else {
       rows.push({ label: t('node_detail.ip_address'), value: dash });
     }
+
+    if (inet.dnssec && inet.dnssec !== 'unknown') {
+      rows.push({
+        label: t('node_detail.dnssec'),
+        value: t(`node_detail.dnssec_${inet.dnssec}`),
+        ...(inet.dnssec === 'broken' && { valueClass: 'text-destructive' }),
+      });
+    }
   }
 
   return (
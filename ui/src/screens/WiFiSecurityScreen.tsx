@@ -190,7 +190,7 @@ export function WiFiSecurityScreen({ onBack, onNavigateToDns, onNavigateToVpn }:
         <div className="flex-1 flex items-center justify-center px-4">
           <div className="text-center">
             <p className="text-foreground mb-4">{t('wifi_security.scanning')}</p>
-            <p className="text-muted-foreground text-sm">{error}</p>
+            <p className="text-muted-foreground text-sm">{t(error)}</p>
           </div>
         </div>
       )}
@@ -230,7 +230,7 @@ export function WiFiSecurityScreen({ onBack, onNavigateToDns, onNavigateToVpn }:
           {/* Error inline — shown after partial results */}
           {error && visibleChecks.length > 0 && (
             <div className="mb-4 text-sm text-destructive animate-in fade-in duration-300">
-              {error}
+              {t(error)}
             </div>
           )}
 
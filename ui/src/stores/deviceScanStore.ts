@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { listen } from '@tauri-apps/api/event';
-import { scanNetworkDevices, type NetworkDevice } from '../api/tauri';
+import { cancelOperation, errorKey, scanNetworkDevices, type NetworkDevice } from '../api/tauri';
 
 interface DeviceScanState {
   devices: NetworkDevice[];
@@ -8,11 +8,13 @@ interface DeviceScanState {
   isScanning: boolean;
   scanStage: string | null;
   scanProgress: number;
+  /** i18n key of the error from the last scan */
   error: string | null;
 }
 
 interface DeviceScanActions {
   runScan: () => Promise<void>;
+  cancelScan: () => void;
 }
 
 export type DeviceScanStore = DeviceScanState & DeviceScanActions;
@@ -28,6 +30,9 @@ function initProgressListener(setScanStage: (stage: string | null) => void) {
   });
 }
 
+// Lets the backend stop the sweep when the screen is closed
+const SCAN_OPERATION_ID = 'device-scan';
+
 // Simulated progress timer handle
 let progressTimer: ReturnType<typeof setInterval> | null = null;
 
@@ -100,19 +105,25 @@ export const useDeviceScanStore = create<DeviceScanStore>((set, get) => {
       startProgressSimulation(get, set);
 
       try {
-        const result = await scanNetworkDevices();
+        const { value: result, completion } = await scanNetworkDevices({ operationId: SCAN_OPERATION_ID });
         clearProgressTimer();
+        if (completion === 'cancelled') return;
         set({ scanProgress: 100 });
         // Brief pause at 100% before showing results
         await new Promise((r) => setTimeout(r, 400));
         set({ devices: result, lastUpdated: Date.now() });
       } catch (e) {
         clearProgressTimer();
-        set({ error: String(e) });
+        set({ error: errorKey(e) });
       } finally {
         set({ isScanning: false, scanStage: null, scanProgress: 0 });
       }
     },
+
+    cancelScan: () => {
+      if (!get().isScanning) return;
+      cancelOperation(SCAN_OPERATION_ID).catch(() => {});
+    },
   };
 });
 
@@ -4,7 +4,9 @@ import {
   checkNetwork,
   checkRouter,
   checkInternet,
+  errorKey,
   lookupIpLocation,
+  recordDiagnostics,
   type SingleNodeResult,
   type NetworkInfo,
   type ComputerInfo,
@@ -165,6 +167,11 @@ function transformSingleNode(
     }
   }
 
+  // Core explains some statuses (DHCP failure, expired lease, broken DNSSEC)
+  if (node.hint_key) {
+    details.push({ text: t(node.hint_key), isStatus: true });
+  }
+
   return {
     id: node.id,
     title: node.label,
@@ -195,6 +202,7 @@ const SCENARIO_NODE_STATUSES: Record<DiagnosticScenario, Record<string, NodeStat
   no_internet:        { computer: 'ok', network: 'ok', dns: 'ok', internet: 'down' },
   dns_failure:        { computer: 'ok', network: 'ok', dns: 'ok', internet: 'partial' },
   http_blocked:       { computer: 'ok', network: 'ok', dns: 'ok', internet: 'partial' },
+  dhcp_failure:       { computer: 'down', network: 'down', dns: 'down', internet: 'down' },
 };
 
 // Connection type for network node per scenario
@@ -378,6 +386,29 @@ function buildDemoResults(
 // Run ID for cancellation
 let runIdCounter = 0;
 
+// Persist a completed progressive run to history (fire-and-forget)
+function recordRun(results: SingleNodeResult[]): void {
+  const [computer, network, router, internet] = results;
+  if (!computer.computer || !network.network || !router.router || !internet.internet) return;
+
+  const statuses = results.map((r) => r.node.status);
+  const overall: NodeStatus = statuses.every((s) => s === 'ok')
+    ? 'ok'
+    : statuses.some((s) => s === 'down') ? 'down' : 'partial';
+  const summaryKey = { ok: 'summary.ok', partial: 'summary.warn', down: 'summary.fail' }[overall];
+
+  recordDiagnostics({
+    at_utc: new Date().toISOString(),
+    overall,
+    summary_key: summaryKey,
+    nodes: results.map((r) => r.node),
+    computer: computer.computer,
+    network: network.network,
+    router: router.router,
+    internet: internet.internet,
+  }).catch(() => { /* history is best-effort */ });
+}
+
 // Progressive override run ID (separate counter to avoid conflicts with real diagnostics)
 let progressiveRunId = 0;
 
@@ -459,6 +490,8 @@ export const useDiagnosticsStore = create<DiagnosticsStore>((set, get) => ({
         };
       });
 
+      recordRun([computerResult, networkResult, routerResult, internetResult]);
+
       // Background: fetch geo-info (ISP, city, public IP) without blocking status
       if (internetResult.internet?.http_ok) {
         // Fire-and-forget — enriches the internet node card when data arrives
@@ -493,7 +526,7 @@ export const useDiagnosticsStore = create<DiagnosticsStore>((set, get) => ({
       if (stale()) return;
       console.error('Failed to run diagnostics:', err);
       set({
-        error: err instanceof Error ? err.message : 'Unknown error',
+        error: t(errorKey(err)),
         currentCheckIndex: 4,
         isRunning: false,
         lastUpdated: Date.now(),
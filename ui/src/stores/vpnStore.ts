@@ -1,6 +1,6 @@
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
-import { connectVpn, disconnectVpn, getVpnStatus, lookupIpLocation, type VpnConnectionState } from '../api/tauri';
+import { connectVpn, disconnectVpn, errorMessage, getVpnStatus, isErrorInfo, lookupIpLocation, type VpnConnectionState } from '../api/tauri';
 import { listen } from '@tauri-apps/api/event';
 import { extractServerHost } from '../utils/vpnUri';
 
@@ -95,11 +95,10 @@ export const useVpnStore = create<VpnState>()(
           const status = await getVpnStatus();
           set({ connectionState: status.state });
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
-          if (message.includes('elevation') || message.includes('cancelled')) {
+          if (isErrorInfo(error) && error.key === 'errors.elevation_denied') {
             set({ connectionState: { type: 'elevation_denied' } });
           } else {
-            set({ connectionState: { type: 'error', message } });
+            set({ connectionState: { type: 'error', message: errorMessage(error) } });
           }
         }
       },
@@ -114,11 +113,10 @@ export const useVpnStore = create<VpnState>()(
           const status = await getVpnStatus();
           set({ connectionState: status.state });
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
-          if (message.includes('elevation') || message.includes('cancelled')) {
+          if (isErrorInfo(error) && error.key === 'errors.elevation_denied') {
             set({ connectionState: { type: 'elevation_denied' } });
           } else {
-            set({ connectionState: { type: 'error', message } });
+            set({ connectionState: { type: 'error', message: errorMessage(error) } });
           }
         }
       },
@@ -129,8 +127,7 @@ export const useVpnStore = create<VpnState>()(
           await disconnectVpn();
           set({ connectionState: { type: 'disconnected' } });
         } catch (error) {
-          const message = error instanceof Error ? error.message : String(error);
-          set({ connectionState: { type: 'error', message } });
+          set({ connectionState: { type: 'error', message: errorMessage(error) } });
         }
       },
 
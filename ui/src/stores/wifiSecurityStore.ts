@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import {
   checkWifiSecurity,
+  errorKey,
   type WiFiSecurityReport,
 } from '../api/tauri';
 
@@ -10,7 +11,7 @@ interface WifiSecurityState {
   report: WiFiSecurityReport | null;
   /** Whether a scan is currently running */
   isRunning: boolean;
-  /** Error from the last scan attempt */
+  /** i18n key of the error from the last scan attempt */
   error: string | null;
   /** Number of revealed results (0–4, for staggered animation) */
   revealedCount: number;
@@ -60,11 +61,11 @@ export const useWifiSecurityStore = create<WifiSecurityStore>((set, get) => ({
     });
 
     try {
-      const result = await checkWifiSecurity();
+      const { value: result } = await checkWifiSecurity();
       set({ report: result });
     } catch (e) {
       set({
-        error: String(e),
+        error: errorKey(e),
         isRunning: false,
         currentCheckIndex: -1,
       });
@@ -75,13 +76,13 @@ export const useWifiSecurityStore = create<WifiSecurityStore>((set, get) => ({
     const { isRunning } = get();
     if (isRunning) return;
     try {
-      const result = await checkWifiSecurity();
+      const { value: result } = await checkWifiSecurity();
       const count = CHECK_ORDER.filter(type =>
         result.checks.some(c => c.check_type === type),
       ).length;
       set({ report: result, revealedCount: count, currentCheckIndex: -1, isRunning: false, error: null });
     } catch (e) {
-      set({ error: String(e) });
+      set({ error: errorKey(e) });
     }
   },
 
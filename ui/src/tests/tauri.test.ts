@@ -6,7 +6,10 @@ import {
   setDns,
   getSettings,
   setSettings,
+  errorKey,
+  isErrorInfo,
   type DiagnosticsSnapshot,
+  type ErrorInfo,
   type DnsProvider,
 } from '../api/tauri';
 
@@ -51,12 +54,21 @@ describe('Tauri API', () => {
         },
       };
 
-      vi.mocked(invoke).mockResolvedValue(mockSnapshot);
+      vi.mocked(invoke).mockResolvedValue({ value: mockSnapshot, completion: 'complete' });
 
       const result = await runDiagnostics();
 
-      expect(invoke).toHaveBeenCalledWith('run_diagnostics');
-      expect(result).toEqual(mockSnapshot);
+      expect(invoke).toHaveBeenCalledWith('run_diagnostics', { operationId: null, deadlineMs: null });
+      expect(result).toEqual({ value: mockSnapshot, completion: 'complete' });
+    });
+
+    it('should pass operation id and deadline', async () => {
+      vi.mocked(invoke).mockResolvedValue({ value: null, completion: 'timed_out' });
+
+      const result = await runDiagnostics({ operationId: 'diag', deadlineMs: 5000 });
+
+      expect(invoke).toHaveBeenCalledWith('run_diagnostics', { operationId: 'diag', deadlineMs: 5000 });
+      expect(result.completion).toBe('timed_out');
     });
 
     it('should throw error if invoke fails', async () => {
@@ -215,5 +227,21 @@ describe('Tauri API', () => {
 
       await expect(runDiagnostics()).rejects.toThrow('Request timeout');
     });
+
+    it('should read the i18n key from a rejected ErrorInfo', async () => {
+      const info: ErrorInfo = {
+        kind: 'permission_denied',
+        key: 'errors.elevation_denied',
+        message: 'elevation_denied',
+        position: null,
+      };
+      vi.mocked(invoke).mockRejectedValue(info);
+
+      const error = await setDns({ type: 'Auto' }).catch((e: unknown) => e);
+
+      expect(isErrorInfo(error)).toBe(true);
+      expect(errorKey(error)).toBe('errors.elevation_denied');
+      expect(errorKey(new Error('IPC failure'))).toBe('errors.internal');
+    });
   });
 });
@@ -31,10 +31,16 @@ export function deriveScenario(
     return { scenario: 'all_good', severity: 'success' };
   }
 
+  const computerNode = nodes.find(n => n.id === 'computer');
   const networkNode = nodes.find(n => n.id === 'network');
   const routerNode = nodes.find(n => n.id === 'dns');
   const internetNode = nodes.find(n => n.id === 'internet');
 
+  // Computer down — the backend only fails this node for a self-assigned (APIPA) address
+  if (computerNode?.status === 'down') {
+    return { scenario: 'dhcp_failure', severity: 'error' };
+  }
+
   // Network down — distinguish disabled adapter from disconnected
   if (networkNode?.status === 'down') {
     if (context?.connectionType === 'Disabled') {